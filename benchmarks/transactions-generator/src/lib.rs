@@ -9,8 +9,8 @@ use near_primitives::types::{AccountId, BlockReference};
 use near_primitives::views::{BlockView, QueryRequest, QueryResponse, QueryResponseKind};
 use node_runtime::metrics::TRANSACTION_PROCESSED_FAILED_TOTAL;
 use parking_lot::Mutex;
-use rand::SeedableRng;
 use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use std::path::PathBuf;
 use std::sync::{Arc, atomic};
 use std::time::Duration;
@@ -21,6 +21,32 @@ pub mod account;
 pub mod actix_actor;
 mod welford;
 
+/// Creates a transaction between two random (but different) accounts, using the next nonce of
+/// the sender.
+pub fn random_send_transaction(
+    rnd: &mut impl Rng,
+    accounts: &[account::Account],
+    block_hash: &CryptoHash,
+) -> SignedTransaction {
+    // each transaction will transfer this amount
+    const AMOUNT: near_primitives::types::Balance = 1;
+
+    let idx = rand::seq::index::sample(rnd, accounts.len(), 2);
+    let sender = &accounts[idx.index(0)];
+    let nonce = sender.nonce.fetch_add(1, atomic::Ordering::Relaxed) + 1;
+    let signer = sender.as_signer();
+
+    let receiver = &accounts[idx.index(1)];
+    SignedTransaction::send_money(
+        nonce,
+        sender.id.clone(),
+        receiver.id.clone(),
+        &signer,
+        AMOUNT,
+        *block_hash,
+    )
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct TxGeneratorConfig {
     tps: u64,
@@ -130,28 +156,15 @@ impl TxGenerator {
         block_hash: &CryptoHash,
         client_sender: &ClientSender,
     ) -> bool {
-        // each transaction will transfer this amount
-        const AMOUNT: near_primitives::types::Balance = 1;
-
-        let idx = rand::seq::index::sample(rnd, accounts.len(), 2);
-        let sender = &accounts[idx.index(0)];
-        let nonce = sender.nonce.fetch_add(1, atomic::Ordering::Relaxed) + 1;
-        let sender_id = sender.id.clone();
-        let signer = sender.as_signer();
-
-        let receiver = &accounts[idx.index(1)];
-        let transaction = SignedTransaction::send_money(
-            nonce,
-            sender_id,
-            receiver.id.clone(),
-            &signer,
-            AMOUNT,
-            *block_hash,
-        );
-
+        let transaction = random_send_transaction(rnd, accounts, block_hash);
         match client_sender
             .tx_request_sender
-            .send_async(ProcessTxRequest { transaction, is_forwarded: false, check_only: false })
+            .send_async(ProcessTxRequest {
+                transaction,
+                is_forwarded: false,
+                is_gossiped: false,
+                check_only: false,
+            })
             .await
         {
             Ok(res) => match res {
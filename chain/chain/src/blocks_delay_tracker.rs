@@ -17,6 +17,7 @@ use std::num::NonZeroUsize;
 use time::ext::InstantExt as _;
 use tracing::error;
 
+use crate::timeline::{TimelineEvent, TimelineRecorder};
 use crate::{Chain, ChainStoreAccess, metrics};
 
 const BLOCK_DELAY_TRACKING_COUNT: u64 = 50;
@@ -44,10 +45,14 @@ pub struct BlocksDelayTracker {
     // Chunks that we don't know which block it belongs to yet
     floating_chunks: HashMap<ChunkHash, BlockHeight>,
     head_height: BlockHeight,
+    /// Lifecycle events of the tracked blocks and chunks, together with events
+    /// recorded by other components, for the timeline export.
+    pub timeline: TimelineRecorder,
 }
 
 #[derive(Debug, Clone)]
 pub struct BlockTrackingStats {
+    pub height: BlockHeight,
     /// Timestamp when block was received or self-produced.
     pub received_timestamp: Instant,
     pub received_utc_timestamp: Utc,
@@ -183,7 +188,7 @@ impl ChunkTrackingStats {
 impl BlocksDelayTracker {
     pub fn new(clock: Clock) -> Self {
         Self {
-            clock,
+            clock: clock.clone(),
             blocks: HashMap::new(),
             blocks_height_map: BTreeMap::new(),
             optimistic_blocks: LruCache::new(
@@ -192,6 +197,7 @@ impl BlocksDelayTracker {
             chunks: HashMap::new(),
             floating_chunks: HashMap::new(),
             head_height: 0,
+            timeline: TimelineRecorder::new(clock),
         }
     }
 
@@ -220,6 +226,7 @@ impl BlocksDelayTracker {
             })
             .collect();
         entry.insert(BlockTrackingStats {
+            height,
             received_timestamp: self.clock.now(),
             received_utc_timestamp: self.clock.now_utc(),
             orphaned_timestamp: None,
@@ -242,8 +249,28 @@ impl BlocksDelayTracker {
         entry.observe_metrics();
     }
 
+    /// Hashes of the tracked blocks up to `max_height` which were put to the
+    /// missing chunks pool.
+    pub fn blocks_with_missing_chunks(&self, max_height: BlockHeight) -> Vec<CryptoHash> {
+        self.blocks_height_map
+            .range(..=max_height)
+            .flat_map(|(_, hashes)| hashes)
+            .filter(|hash| {
+                self.blocks.get(*hash).is_some_and(|stats| stats.missing_chunks_timestamp.is_some())
+            })
+            .copied()
+            .collect()
+    }
+
     pub fn mark_block_dropped(&mut self, block_hash: &CryptoHash, reason: DroppedReason) {
         if let Some(block_entry) = self.blocks.get_mut(block_hash) {
+            self.timeline.record_instant(
+                "block dropped",
+                "block",
+                block_entry.height,
+                None,
+                vec![("hash", block_hash.to_string()), ("reason", format!("{:?}", reason))],
+            );
             block_entry.dropped = Some(reason);
         } else {
             error!(target:"blocks_delay_tracker", "block {:?} was dropped but was not marked received", block_hash);
@@ -252,6 +279,13 @@ impl BlocksDelayTracker {
 
     pub fn mark_block_errored(&mut self, block_hash: &CryptoHash, err: String) {
         if let Some(block_entry) = self.blocks.get_mut(block_hash) {
+            self.timeline.record_instant(
+                "block errored",
+                "block",
+                block_entry.height,
+                None,
+                vec![("hash", block_hash.to_string()), ("error", err.clone())],
+            );
             block_entry.error = Some(err);
         } else {
             error!(target:"blocks_delay_tracker", "block {:?} was errored but was not marked received", block_hash);
@@ -312,6 +346,7 @@ impl BlocksDelayTracker {
             .get_or_insert_mut(block_height, || OptimisticBlockStats::default());
         entry.ready_timestamp = Some(self.clock.now());
         entry.observe_metrics();
+        self.timeline.record_instant("optimistic block ready", "block", block_height, None, vec![]);
     }
 
     pub fn record_optimistic_block_processed(&mut self, block_height: BlockHeight) {
@@ -320,6 +355,13 @@ impl BlocksDelayTracker {
             .get_or_insert_mut(block_height, || OptimisticBlockStats::default());
         entry.processed_timestamp = Some(self.clock.now());
         entry.observe_metrics();
+        self.timeline.record_instant(
+            "optimistic block processed",
+            "block",
+            block_height,
+            None,
+            vec![],
+        );
     }
 
     pub fn mark_chunk_completed(&mut self, chunk_header: &ShardChunkHeader) {
@@ -350,6 +392,7 @@ impl BlocksDelayTracker {
         if head_height != self.head_height {
             let cutoff_height = head_height.saturating_sub(BLOCK_DELAY_TRACKING_COUNT);
             self.head_height = head_height;
+            self.timeline.prune(cutoff_height);
             let mut blocks_to_remove = self.blocks_height_map.split_off(&cutoff_height);
             mem::swap(&mut self.blocks_height_map, &mut blocks_to_remove);
 
@@ -393,6 +436,7 @@ impl BlocksDelayTracker {
         if let Some(processed_block) = self.blocks.get(&block_hash) {
             let chunks = processed_block.chunks.clone();
             self.update_block_metrics(processed_block);
+            self.record_block_timeline(shard_layout, block_hash, processed_block);
             for (shard_index, chunk_hash) in chunks.into_iter().enumerate() {
                 if let Some(chunk_hash) = chunk_hash {
                     if let Some(processed_chunk) = self.chunks.get(&chunk_hash) {
@@ -437,6 +481,74 @@ impl BlocksDelayTracker {
         }
     }
 
+    /// Records the lifecycle of a processed block and its chunks in the timeline.
+    fn record_block_timeline(
+        &self,
+        shard_layout: &ShardLayout,
+        block_hash: &CryptoHash,
+        block: &BlockTrackingStats,
+    ) {
+        // Most of the timestamps are monotonic, they are converted to UTC
+        // relative to the time the block was received.
+        let to_utc = |instant: Instant| {
+            block.received_utc_timestamp + instant.signed_duration_since(block.received_timestamp)
+        };
+        let record_span = |name: &'static str, start: Option<Instant>, end: Option<Instant>| {
+            let (Some(start), Some(end)) = (start, end) else {
+                return;
+            };
+            self.timeline.record(TimelineEvent {
+                name,
+                category: "block",
+                height: block.height,
+                shard_id: None,
+                start: to_utc(start),
+                end: Some(to_utc(end)),
+                args: vec![("hash", block_hash.to_string())],
+            });
+        };
+        record_span("block processing", Some(block.received_timestamp), block.processed_timestamp);
+        record_span("orphaned", block.orphaned_timestamp, block.removed_from_orphan_timestamp);
+        record_span(
+            "missing chunks",
+            block.missing_chunks_timestamp,
+            block.removed_from_missing_chunks_timestamp,
+        );
+        record_span(
+            "pending execution",
+            block.pending_execution_timestamp,
+            block.removed_from_pending_timestamp.or(block.processed_timestamp),
+        );
+
+        for (shard_index, chunk_hash) in block.chunks.iter().enumerate() {
+            let Some((chunk_hash, chunk)) = chunk_hash
+                .as_ref()
+                .and_then(|chunk_hash| Some((chunk_hash, self.chunks.get(chunk_hash)?)))
+            else {
+                continue;
+            };
+            let Some(completed) = chunk.completed_timestamp else {
+                continue;
+            };
+            let Ok(shard_id) = shard_layout.get_shard_id(shard_index) else {
+                continue;
+            };
+            self.timeline.record(TimelineEvent {
+                name: if chunk.requested_timestamp.is_some() {
+                    "chunk requested"
+                } else {
+                    "chunk completed"
+                },
+                category: "chunk",
+                height: chunk.height_created,
+                shard_id: Some(shard_id),
+                start: chunk.requested_timestamp.unwrap_or(completed),
+                end: chunk.requested_timestamp.map(|_| completed),
+                args: vec![("chunk_hash", chunk_hash.0.to_string())],
+            });
+        }
+    }
+
     fn update_chunk_metrics(&self, chunk: &ChunkTrackingStats, shard_id: ShardId) {
         if let Some(chunk_requested) = chunk.requested_timestamp {
             // Theoretically chunk_received should have been set here because a block being processed
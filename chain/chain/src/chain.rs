@@ -23,9 +23,11 @@ use crate::state_sync::ChainStateSyncAdapter;
 use crate::stateless_validation::chunk_endorsement::{
     validate_chunk_endorsements_in_block, validate_chunk_endorsements_in_header,
 };
+use crate::stateless_validation::divergence::DivergenceReportLimiter;
 use crate::store::utils::{get_chunk_clone_from_header, get_incoming_receipts_for_shard};
 use crate::store::{
-    ChainStore, ChainStoreAccess, ChainStoreUpdate, MerkleProofAccess, ReceiptFilter,
+    ChainStore, ChainStoreAccess, ChainStoreUpdate, MAX_PERSISTED_ORPHAN_BLOCKS, MerkleProofAccess,
+    PersistedOrphans, ReceiptFilter,
 };
 use crate::types::{
     AcceptedBlock, ApplyChunkBlockContext, BlockEconomicsConfig, BlockType, ChainConfig,
@@ -298,6 +300,8 @@ pub struct Chain {
     pub runtime_adapter: Arc<dyn RuntimeAdapter>,
     pub state_sync_adapter: ChainStateSyncAdapter,
     pub(crate) orphans: OrphanBlockPool,
+    /// Copy of recent orphans in the database, if `persist_orphans` is enabled.
+    pub(crate) persisted_orphans: Option<PersistedOrphans<Block>>,
     pub blocks_with_missing_chunks: MissingChunksPool<Orphan>,
     pub optimistic_block_chunks: OptimisticBlockChunksPool,
     pub blocks_pending_execution: PendingBlocksPool<Orphan>,
@@ -346,6 +350,9 @@ pub struct Chain {
 
     /// Manages all tasks related to resharding.
     pub resharding_manager: ReshardingManager,
+
+    /// Rate limits shadow validation divergence reports.
+    pub(crate) divergence_report_limiter: Arc<DivergenceReportLimiter>,
 }
 
 impl Drop for Chain {
@@ -424,6 +431,7 @@ impl Chain {
             runtime_adapter,
             state_sync_adapter,
             orphans: OrphanBlockPool::new(),
+            persisted_orphans: None,
             blocks_with_missing_chunks: MissingChunksPool::new(),
             optimistic_block_chunks: OptimisticBlockChunksPool::new(),
             blocks_pending_execution: PendingBlocksPool::new(),
@@ -443,6 +451,7 @@ impl Chain {
             pending_state_patch: Default::default(),
             snapshot_callbacks: None,
             resharding_manager,
+            divergence_report_limiter: Arc::new(DivergenceReportLimiter::new(clock.clone())),
         })
     }
 
@@ -570,6 +579,11 @@ impl Chain {
         let (sc, rc) = unbounded();
         let resharding_manager =
             ReshardingManager::new(chain_store.store(), epoch_manager.clone(), resharding_sender);
+        let persisted_orphans = if chain_config.persist_orphans {
+            Some(PersistedOrphans::new(chain_store.store(), MAX_PERSISTED_ORPHAN_BLOCKS)?)
+        } else {
+            None
+        };
         Ok(Chain {
             clock: clock.clone(),
             chain_store,
@@ -578,6 +592,7 @@ impl Chain {
             runtime_adapter,
             state_sync_adapter,
             orphans: OrphanBlockPool::new(),
+            persisted_orphans,
             blocks_with_missing_chunks: MissingChunksPool::new(),
             optimistic_block_chunks: OptimisticBlockChunksPool::new(),
             blocks_pending_execution: PendingBlocksPool::new(),
@@ -597,6 +612,7 @@ impl Chain {
             pending_state_patch: Default::default(),
             snapshot_callbacks,
             resharding_manager,
+            divergence_report_limiter: Arc::new(DivergenceReportLimiter::new(clock.clone())),
         })
     }
 
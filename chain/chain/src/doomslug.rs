@@ -1,14 +1,16 @@
 use crate::doomslug::trackable::TrackableBlockHeightValue;
 use crate::metrics;
 use near_async::time::{Clock, Duration, Instant, Utc};
-use near_client_primitives::debug::{ApprovalAtHeightStatus, ApprovalHistoryEntry};
+use near_client_primitives::debug::{
+    ApprovalArrival, ApprovalAtHeightStatus, ApprovalHistoryEntry, ApprovalsReceivedAtHeight,
+};
 use near_crypto::Signature;
 use near_primitives::block::{Approval, ApprovalInner};
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::{AccountId, ApprovalStake, Balance, BlockHeight, BlockHeightDelta};
 use near_primitives::validator_signer::ValidatorSigner;
 use num_rational::Rational32;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::Arc;
 use time::ext::InstantExt as _;
 use tracing::{debug, debug_span, field, info};
@@ -30,6 +32,13 @@ const MAX_HEIGHTS_BEFORE_TO_STORE_APPROVALS: u64 = 20;
 // Maximum amount of historical approvals that we'd keep for debugging purposes.
 const MAX_HISTORY_SIZE: usize = 1000;
 
+// Number of most recent target heights for which to keep the received approvals (for debugging).
+const MAX_HEIGHTS_TO_STORE_RECEIVED_APPROVALS: usize = 100;
+
+// Received approvals are recorded only for target heights at most that many heights away from
+// the tip, so that approvals for far away heights can't evict the recent ones (for debugging).
+const MAX_HEIGHTS_AROUND_TIP_TO_RECORD_RECEIVED_APPROVALS: BlockHeight = 20;
+
 /// The threshold for doomslug to create a block.
 /// `TwoThirds` means the block can only be produced if at least 2/3 of the stake is approving it,
 ///             and is what should be used in production (and what guarantees finality)
@@ -146,6 +155,9 @@ pub struct Doomslug {
     /// Approvals that were created by this doomslug instance (for debugging only).
     /// Keeps up to MAX_HISTORY_SIZE entries.
     history: VecDeque<ApprovalHistoryEntry>,
+    /// Approvals received by this doomslug instance, by target height (for debugging only).
+    /// Keeps up to MAX_HEIGHTS_TO_STORE_RECEIVED_APPROVALS most recent heights.
+    received_approvals: BTreeMap<BlockHeight, ApprovalsReceivedAtHeight>,
 }
 
 impl DoomslugTimer {
@@ -389,6 +401,7 @@ impl Doomslug {
             },
             threshold_mode,
             history: VecDeque::new(),
+            received_approvals: BTreeMap::new(),
         }
     }
 
@@ -443,6 +456,64 @@ impl Doomslug {
         self.history.push_back(entry);
     }
 
+    /// Returns the approvals received for the recent target heights, in ascending order of height.
+    pub fn get_received_approval_history(&self) -> Vec<ApprovalsReceivedAtHeight> {
+        self.received_approvals.values().cloned().collect()
+    }
+
+    /// Adds the arrival of an approval to the history of received approvals. Approvals from
+    /// accounts without stake are ignored, same as in `DoomslugApprovalsTrackersAtHeight`, and
+    /// so are repeated approvals and approvals for heights far away from the tip.
+    fn update_received_approvals(
+        &mut self,
+        approval: &Approval,
+        stakes: &[ApprovalStake],
+        readiness: &DoomslugBlockProductionReadiness,
+    ) {
+        let window = MAX_HEIGHTS_AROUND_TIP_TO_RECORD_RECEIVED_APPROVALS;
+        let heights = self.tip.height.saturating_sub(window)..=self.tip.height + window;
+        if !heights.contains(&approval.target_height) {
+            return;
+        }
+        let Some(stake) = stakes.iter().find(|stake| stake.account_id == approval.account_id)
+        else {
+            return;
+        };
+        let now = self.clock.now_utc();
+        let entry = self.received_approvals.entry(approval.target_height).or_insert_with(|| {
+            ApprovalsReceivedAtHeight {
+                target_height: approval.target_height,
+                arrivals: vec![],
+                threshold_reached_at: None,
+            }
+        });
+        let is_repeated = entry.arrivals.iter().any(|arrival| {
+            arrival.account_id == approval.account_id && arrival.approval == approval.inner
+        });
+        if !is_repeated {
+            let latency_millis = entry
+                .arrivals
+                .first()
+                .map_or(0, |first| (now - first.arrival_time).whole_milliseconds().max(0) as u64);
+            entry.arrivals.push(ApprovalArrival {
+                account_id: approval.account_id.clone(),
+                approval: approval.inner.clone(),
+                stake_this_epoch: stake.stake_this_epoch,
+                stake_next_epoch: stake.stake_next_epoch,
+                arrival_time: now,
+                latency_millis,
+            });
+        }
+        if let DoomslugBlockProductionReadiness::ReadySince(ready_since) = readiness {
+            if entry.threshold_reached_at.is_none() {
+                entry.threshold_reached_at = Some(now - (self.clock.now() - *ready_since));
+            }
+        }
+        while self.received_approvals.len() > MAX_HEIGHTS_TO_STORE_RECEIVED_APPROVALS {
+            self.received_approvals.pop_first();
+        }
+    }
+
     /// Is expected to be called periodically and processed the timer (`start_timer` in the paper)
     /// If the `cur_time` way ahead of last time the `process_timer` was called, will only process
     /// a bounded number of steps, to avoid an infinite loop in case of some bugs.
@@ -643,6 +714,7 @@ impl Doomslug {
             .entry(approval.target_height)
             .or_insert_with(|| DoomslugApprovalsTrackersAtHeight::new(self.clock.clone()))
             .process_approval(approval, stakes, threshold_mode);
+        self.update_received_approvals(approval, stakes, &ret);
 
         if approval.target_height > self.largest_approval_height.get() {
             self.largest_approval_height.set(approval.target_height);
@@ -1218,4 +1290,87 @@ mod tests {
             5
         );
     }
+
+    #[test]
+    fn test_received_approval_history() {
+        let accounts: Vec<(&str, u128, u128)> =
+            vec![("test1", 2, 0), ("test2", 1, 0), ("test3", 3, 0)];
+        let stakes = accounts
+            .iter()
+            .map(|(account_id, stake_this_epoch, stake_next_epoch)| ApprovalStake {
+                account_id: account_id.parse().unwrap(),
+                stake_this_epoch: *stake_this_epoch,
+                stake_next_epoch: *stake_next_epoch,
+                public_key: SecretKey::from_seed(KeyType::ED25519, account_id).public_key(),
+            })
+            .collect::<Vec<_>>();
+        let signers = accounts
+            .iter()
+            .map(|(account_id, _, _)| create_test_signer(account_id))
+            .collect::<Vec<_>>();
+
+        let clock = FakeClock::new(Utc::UNIX_EPOCH);
+        let mut ds = Doomslug::new(
+            clock.clock(),
+            0,
+            Duration::milliseconds(400),
+            Duration::milliseconds(1000),
+            Duration::milliseconds(100),
+            Duration::milliseconds(3000),
+            Rational32::new(1, 3),
+            DoomslugThresholdMode::TwoThirds,
+        );
+
+        let _ =
+            ds.on_approval_message_internal(&Approval::new(hash(&[1]), 1, 2, &signers[2]), &stakes);
+        clock.advance(Duration::milliseconds(50));
+        // Repeated approvals are recorded only once.
+        let _ =
+            ds.on_approval_message_internal(&Approval::new(hash(&[1]), 1, 2, &signers[2]), &stakes);
+        // An approval from an account without stake is ignored.
+        let _ = ds.on_approval_message_internal(
+            &Approval::new(hash(&[1]), 1, 2, &create_test_signer("test4")),
+            &stakes,
+        );
+        clock.advance(Duration::milliseconds(100));
+        let _ =
+            ds.on_approval_message_internal(&Approval::new(hash(&[0]), 0, 2, &signers[1]), &stakes);
+        clock.advance(Duration::milliseconds(100));
+        // Crosses the threshold of 2/3 of the total stake of 6.
+        let _ =
+            ds.on_approval_message_internal(&Approval::new(hash(&[1]), 1, 2, &signers[0]), &stakes);
+
+        let history = ds.get_received_approval_history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].target_height, 2);
+        let arrivals = history[0]
+            .arrivals
+            .iter()
+            .map(|arrival| {
+                (arrival.account_id.as_str(), arrival.approval.clone(), arrival.latency_millis)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            arrivals,
+            vec![
+                ("test3", ApprovalInner::Endorsement(hash(&[1])), 0),
+                ("test2", ApprovalInner::Skip(0), 150),
+                ("test1", ApprovalInner::Endorsement(hash(&[1])), 250),
+            ]
+        );
+        assert_eq!(history[0].arrivals[2].stake_this_epoch, 2);
+        assert_eq!(history[0].threshold_reached_at, Some(clock.now_utc()));
+
+        // Only the heights close to the tip are recorded.
+        let _ = ds
+            .on_approval_message_internal(&Approval::new(hash(&[1]), 1, 20, &signers[0]), &stakes);
+        let _ = ds
+            .on_approval_message_internal(&Approval::new(hash(&[1]), 1, 21, &signers[0]), &stakes);
+        let heights = ds
+            .get_received_approval_history()
+            .iter()
+            .map(|approvals| approvals.target_height)
+            .collect::<Vec<_>>();
+        assert_eq!(heights, vec![2, 20]);
+    }
 }
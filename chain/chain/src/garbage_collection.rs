@@ -1,25 +1,31 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use std::{fmt, io};
 
-use near_chain_configs::GCConfig;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_chain_configs::{GCConfig, GCRetentionConfig};
 use near_chain_primitives::Error;
 use near_epoch_manager::EpochManagerAdapter;
 use near_epoch_manager::shard_assignment::shard_id_to_uid;
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_primitives::block::Block;
 use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::Receipt;
 use near_primitives::shard_layout::get_block_shard_uid;
 use near_primitives::state_sync::{StateHeaderKey, StatePartKey};
+use near_primitives::transaction::{ExecutionOutcomeWithProof, SignedTransaction};
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockHeightDelta, EpochId, NumBlocks, ShardId,
+    AccountId, BlockHeight, BlockHeightDelta, EpochId, NumBlocks, RawStateChangesWithTrieKey,
+    ShardId,
 };
 use near_primitives::utils::{
     get_block_shard_id, get_block_shard_id_rev, get_outcome_id_block_hash, index_to_bytes,
 };
 use near_store::adapter::trie_store::get_shard_uid_mapping;
 use near_store::adapter::{StoreAdapter, StoreUpdateAdapter};
-use near_store::{DBCol, KeyForStateChanges, ShardTries, ShardUId, StoreUpdate};
+use near_store::{
+    DBCol, GC_RETAINED_RECEIPTS_KEY, KeyForStateChanges, ShardTries, ShardUId, StoreUpdate,
+};
 
 use crate::types::RuntimeAdapter;
 use crate::{Chain, ChainStore, ChainStoreAccess, ChainStoreUpdate, metrics};
@@ -27,20 +33,98 @@ use crate::{Chain, ChainStore, ChainStoreAccess, ChainStoreUpdate, metrics};
 #[derive(Clone)]
 pub enum GCMode {
     Fork(ShardTries),
-    Canonical(ShardTries),
-    StateSync { clear_block_info: bool },
+    /// Clearing of the canonical chain, keeping the data chosen by the retention policy.
+    Canonical(ShardTries, Option<Arc<GCRetention>>),
+    StateSync {
+        clear_block_info: bool,
+    },
 }
 
 impl fmt::Debug for GCMode {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             GCMode::Fork(_) => write!(f, "GCMode::Fork"),
-            GCMode::Canonical(_) => write!(f, "GCMode::Canonical"),
+            GCMode::Canonical(..) => write!(f, "GCMode::Canonical"),
             GCMode::StateSync { .. } => write!(f, "GCMode::StateSync"),
         }
     }
 }
 
+/// How long after being produced a receipt of the retained data is still expected to be
+/// executed, e.g. when delayed because of congestion. Afterwards it is no longer tracked.
+const MAX_RETAINED_RECEIPT_DELAY: BlockHeightDelta = 100_000;
+
+/// Retention policy of the garbage collection, see `GCRetentionConfig`.
+///
+/// The data touching the chosen accounts is not deleted together with the rest of the block:
+/// transactions signed by or sent to the accounts, receipts sent by or to them, the outcomes
+/// of these, and the state changes of the accounts. To keep the whole execution of such
+/// transactions and receipts queryable, the outcomes of the receipts they produce (e.g.
+/// refunds) are retained too. These receipts are tracked under `GC_RETAINED_RECEIPTS_KEY`
+/// from the time their parent is garbage collected until their own outcome is.
+///
+/// If the retained data expires, its keys are stored in `DBCol::GCRetainedData` by height,
+/// so that it can be deleted once the height falls out of the retention window.
+#[derive(Debug)]
+pub struct GCRetention {
+    accounts: HashSet<AccountId>,
+    num_epochs_to_keep: Option<u64>,
+}
+
+impl GCRetention {
+    pub fn new(config: &GCRetentionConfig) -> Self {
+        Self {
+            accounts: config.accounts.iter().cloned().collect(),
+            num_epochs_to_keep: config.num_epochs_to_keep,
+        }
+    }
+
+    fn retains_transaction(&self, transaction: &SignedTransaction) -> bool {
+        let transaction = &transaction.transaction;
+        self.accounts.contains(transaction.signer_id())
+            || self.accounts.contains(transaction.receiver_id())
+    }
+
+    fn retains_receipt(&self, receipt: &Receipt) -> bool {
+        self.accounts.contains(receipt.predecessor_id())
+            || self.accounts.contains(receipt.receiver_id())
+    }
+}
+
+/// Key of the data retained by the garbage collection, stored in `DBCol::GCRetainedData`.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum RetainedKey {
+    Transaction(CryptoHash),
+    Receipt(CryptoHash),
+    /// Key in `DBCol::TransactionResultForBlock`.
+    Outcome(Vec<u8>),
+    /// Key in `DBCol::StateChanges`.
+    StateChanges(Vec<u8>),
+}
+
+fn retained_data_key(height: BlockHeight) -> [u8; 8] {
+    // Big-endian, so that the rows are iterated by height.
+    height.to_be_bytes()
+}
+
+/// Data retained while garbage collecting a block on the canonical chain.
+struct RetainedData {
+    retention: Arc<GCRetention>,
+    /// Receipts produced by the retained data, whose outcomes have to be retained too,
+    /// by the height of the block in which they were produced.
+    receipts: HashMap<CryptoHash, BlockHeight>,
+    /// Keys of the retained data by height, stored if the retained data expires.
+    keys: BTreeMap<BlockHeight, Vec<RetainedKey>>,
+}
+
+impl RetainedData {
+    fn push(&mut self, height: BlockHeight, key: RetainedKey) {
+        if self.retention.num_epochs_to_keep.is_some() {
+            self.keys.entry(height).or_default().push(key);
+        }
+    }
+}
+
 /// Both functions here are only used for testing as they create convenient
 /// wrappers that allow us to do correctness integration testing without having
 /// to fully spin up GCActor
@@ -216,6 +300,15 @@ impl ChainStore {
             fork_tail = gc_stop_height;
         }
         let mut gc_blocks_remaining = gc_config.gc_blocks_limit;
+        let retention =
+            gc_config.retention.as_ref().map(|config| Arc::new(GCRetention::new(config)));
+        if let Some(retention) = &retention {
+            self.clear_expired_retained_data(
+                retention,
+                epoch_manager.as_ref(),
+                gc_config.gc_blocks_limit,
+            )?;
+        }
 
         // Forks Cleaning
         let gc_fork_clean_step = gc_config.gc_fork_clean_step;
@@ -274,7 +367,7 @@ impl ChainStore {
                 chain_store_update.clear_block_data(
                     epoch_manager.as_ref(),
                     *block_hash,
-                    GCMode::Canonical(tries.clone()),
+                    GCMode::Canonical(tries.clone(), retention.clone()),
                 )?;
                 gc_blocks_remaining -= 1;
 
@@ -296,6 +389,75 @@ impl ChainStore {
         Ok(())
     }
 
+    /// Deletes the data kept by the retention policy once it is older than
+    /// `num_epochs_to_keep` epochs, at most `limit` heights at a time.
+    fn clear_expired_retained_data(
+        &mut self,
+        retention: &GCRetention,
+        epoch_manager: &dyn EpochManagerAdapter,
+        limit: NumBlocks,
+    ) -> Result<(), Error> {
+        let Some(num_epochs_to_keep) = retention.num_epochs_to_keep else {
+            return Ok(());
+        };
+        let stop_height = self.retention_stop_height(epoch_manager, num_epochs_to_keep)?;
+        let rows = self
+            .store()
+            .iter_ser::<Vec<RetainedKey>>(DBCol::GCRetainedData)
+            .take(limit as usize)
+            .collect::<io::Result<Vec<_>>>()?;
+        let mut chain_store_update = self.store_update();
+        for (key, retained_keys) in rows {
+            let height =
+                BlockHeight::from_be_bytes(key.as_ref().try_into().map_err(|_| {
+                    Error::GCError(format!("invalid key in GCRetainedData: {key:?}"))
+                })?);
+            if height >= stop_height {
+                break;
+            }
+            for retained_key in retained_keys {
+                match retained_key {
+                    RetainedKey::Transaction(hash) => {
+                        chain_store_update.gc_col(DBCol::Transactions, hash.as_bytes())
+                    }
+                    RetainedKey::Receipt(id) => {
+                        chain_store_update.gc_col(DBCol::Receipts, id.as_bytes())
+                    }
+                    RetainedKey::Outcome(key) => {
+                        chain_store_update.gc_col(DBCol::TransactionResultForBlock, &key)
+                    }
+                    RetainedKey::StateChanges(key) => {
+                        chain_store_update.gc_col(DBCol::StateChanges, &key)
+                    }
+                }
+            }
+            chain_store_update.gc_col(DBCol::GCRetainedData, &key);
+        }
+        chain_store_update.commit()
+    }
+
+    /// Returns the height of the first block of the epoch `num_epochs - 1` epochs before the
+    /// epoch of the head. Unlike `get_gc_stop_height`, it relies only on the data which is never
+    /// garbage collected: block headers, block hashes by height and epoch start heights.
+    fn retention_stop_height(
+        &self,
+        epoch_manager: &dyn EpochManagerAdapter,
+        num_epochs: u64,
+    ) -> Result<BlockHeight, Error> {
+        let head = self.head()?;
+        let mut epoch_start_height = epoch_manager.get_epoch_start_from_epoch_id(&head.epoch_id)?;
+        for _ in 1..num_epochs {
+            if epoch_start_height <= self.get_genesis_height() {
+                break;
+            }
+            let epoch_first_block = self.get_block_hash_by_height(epoch_start_height)?;
+            let prev_hash = *self.get_block_header(&epoch_first_block)?.prev_hash();
+            let prev_epoch_id = *self.get_block_header(&prev_hash)?.epoch_id();
+            epoch_start_height = epoch_manager.get_epoch_start_from_epoch_id(&prev_epoch_id)?;
+        }
+        Ok(epoch_start_height)
+    }
+
     fn clear_state_transition_data(
         &self,
         epoch_manager: &dyn EpochManagerAdapter,
@@ -508,7 +670,7 @@ impl ChainStore {
         let mut chain_store_update = self.store_update();
         // The largest height of chunk we have in storage is head.height + 1
         let chunk_height = std::cmp::min(head.height + 2, sync_height);
-        chain_store_update.clear_chunk_data_and_headers(chunk_height)?;
+        chain_store_update.clear_chunk_data_and_headers(chunk_height, None)?;
         chain_store_update.commit()?;
 
         // clear all trie data
@@ -546,7 +708,11 @@ impl<'a> ChainStoreUpdate<'a> {
         Ok(())
     }
 
-    fn clear_chunk_data_and_headers(&mut self, min_chunk_height: BlockHeight) -> Result<(), Error> {
+    fn clear_chunk_data_and_headers(
+        &mut self,
+        min_chunk_height: BlockHeight,
+        mut retained: Option<&mut RetainedData>,
+    ) -> Result<(), Error> {
         let chunk_tail = self.chunk_tail()?;
         for height in chunk_tail..min_chunk_height {
             let chunk_hashes = self.chain_store().get_all_chunk_hashes_by_height(height)?;
@@ -555,14 +721,28 @@ impl<'a> ChainStoreUpdate<'a> {
                 let chunk = self.get_chunk(&chunk_hash)?;
                 debug_assert_eq!(chunk.height_created(), height);
                 for transaction in chunk.to_transactions() {
-                    self.gc_col(DBCol::Transactions, transaction.get_hash().as_bytes());
+                    let hash = transaction.get_hash();
+                    if let Some(retained) = retained.as_deref_mut() {
+                        if retained.retention.retains_transaction(transaction) {
+                            retained.push(height, RetainedKey::Transaction(hash));
+                            continue;
+                        }
+                    }
+                    self.gc_col(DBCol::Transactions, hash.as_bytes());
                 }
 
                 let partial_chunk = self.get_partial_chunk(&chunk_hash);
                 if let Ok(partial_chunk) = partial_chunk {
                     for receipts in partial_chunk.prev_outgoing_receipts() {
                         for receipt in &receipts.0 {
-                            self.gc_col(DBCol::Receipts, receipt.receipt_id().as_bytes());
+                            let receipt_id = *receipt.receipt_id();
+                            if let Some(retained) = retained.as_deref_mut() {
+                                if retained.retention.retains_receipt(receipt) {
+                                    retained.push(height, RetainedKey::Receipt(receipt_id));
+                                    continue;
+                                }
+                            }
+                            self.gc_col(DBCol::Receipts, receipt_id.as_bytes());
                         }
                     }
                 }
@@ -671,7 +851,7 @@ impl<'a> ChainStoreUpdate<'a> {
         // 1. Garbage collect TrieChanges.
         self.gc_trie_changes(epoch_manager, block_hash, &gc_mode, &mut store_update)?;
 
-        if matches!(gc_mode, GCMode::Canonical(_)) {
+        if matches!(gc_mode, GCMode::Canonical(..)) {
             // If you know why do we do this in case of canonical chain please add a comment here.
             block_hash = *self.get_block_header(&block_hash)?.prev_hash();
         }
@@ -681,6 +861,10 @@ impl<'a> ChainStoreUpdate<'a> {
         let height = block.header().height();
         let epoch_id = block.header().epoch_id();
         let shard_layout = epoch_manager.get_shard_layout(epoch_id).expect("epoch id must exist");
+        let mut retained = match &gc_mode {
+            GCMode::Canonical(_, Some(retention)) => Some(self.load_retained_data(retention)?),
+            _ => None,
+        };
 
         // 2. Delete shard_id-indexed data (Receipts, State Headers and Parts, etc.)
         for shard_id in shard_layout.shard_ids() {
@@ -716,16 +900,26 @@ impl<'a> ChainStoreUpdate<'a> {
         self.gc_col(DBCol::ChallengedBlocks, block_hash.as_bytes());
         self.gc_col(DBCol::BlocksToCatchup, block_hash.as_bytes());
         let storage_key = KeyForStateChanges::for_block(&block_hash);
-        let stored_state_changes: Vec<Box<[u8]>> = self
+        let stored_state_changes: Vec<(Box<[u8]>, Box<[u8]>)> = self
             .store()
             .iter_prefix(DBCol::StateChanges, storage_key.as_ref())
-            .map(|item| item.map(|(key, _)| key))
             .collect::<io::Result<Vec<_>>>()?;
-        for key in stored_state_changes {
+        for (key, value) in stored_state_changes {
+            if let Some(retained) = &mut retained {
+                let changes = RawStateChangesWithTrieKey::try_from_slice(&value)?;
+                if changes
+                    .trie_key
+                    .get_account_id()
+                    .is_some_and(|account_id| retained.retention.accounts.contains(&account_id))
+                {
+                    retained.push(height, RetainedKey::StateChanges(key.to_vec()));
+                    continue;
+                }
+            }
             self.gc_col(DBCol::StateChanges, &key);
         }
         self.gc_col(DBCol::BlockRefCount, block_hash.as_bytes());
-        self.gc_outcomes(&block)?;
+        self.gc_outcomes(&block, retained.as_mut())?;
         match gc_mode {
             GCMode::StateSync { clear_block_info: false } => {}
             _ => self.gc_col(DBCol::BlockInfo, block_hash.as_bytes()),
@@ -740,7 +934,7 @@ impl<'a> ChainStoreUpdate<'a> {
                 // 5. Forks only clearing
                 self.dec_block_refcount(block.header().prev_hash())?;
             }
-            GCMode::Canonical(_) => {
+            GCMode::Canonical(..) => {
                 // 6. Canonical Chain only clearing
                 // Delete chunks, chunk-indexed data and block headers
                 let mut min_chunk_height = self.tail()?;
@@ -749,17 +943,49 @@ impl<'a> ChainStoreUpdate<'a> {
                         min_chunk_height = chunk_header.height_created();
                     }
                 }
-                self.clear_chunk_data_and_headers(min_chunk_height)?;
+                self.clear_chunk_data_and_headers(min_chunk_height, retained.as_mut())?;
             }
             GCMode::StateSync { .. } => {
                 // 7. State Sync clearing
                 // Chunks deleted separately
             }
         };
+        if let Some(retained) = retained {
+            self.save_retained_data(retained, height)?;
+        }
         self.merge(store_update.into());
         Ok(())
     }
 
+    fn load_retained_data(&self, retention: &Arc<GCRetention>) -> Result<RetainedData, Error> {
+        let receipts =
+            self.store().get_ser(DBCol::BlockMisc, GC_RETAINED_RECEIPTS_KEY)?.unwrap_or_default();
+        Ok(RetainedData { retention: retention.clone(), receipts, keys: BTreeMap::new() })
+    }
+
+    /// Stores the receipts whose outcomes are to be retained, and the keys of the retained
+    /// data if it expires.
+    fn save_retained_data(
+        &mut self,
+        mut retained: RetainedData,
+        height: BlockHeight,
+    ) -> Result<(), Error> {
+        let mut store_update = self.store().store_update();
+        retained
+            .receipts
+            .retain(|_, produced_height| *produced_height + MAX_RETAINED_RECEIPT_DELAY > height);
+        store_update.set_ser(DBCol::BlockMisc, GC_RETAINED_RECEIPTS_KEY, &retained.receipts)?;
+        for (height, keys) in retained.keys {
+            let key = retained_data_key(height);
+            let mut row: Vec<RetainedKey> =
+                self.store().get_ser(DBCol::GCRetainedData, &key)?.unwrap_or_default();
+            row.extend(keys);
+            store_update.set_ser(DBCol::GCRetainedData, &key, &row)?;
+        }
+        self.merge(store_update);
+        Ok(())
+    }
+
     fn gc_trie_changes(
         &mut self,
         epoch_manager: &dyn EpochManagerAdapter,
@@ -780,7 +1006,7 @@ impl<'a> ChainStoreUpdate<'a> {
                     // If the block is on a fork, we delete the state that's the result of applying this block
                     tries.revert_insertions(&trie_changes, shard_uid, store_update);
                 }
-                GCMode::Canonical(tries) => {
+                GCMode::Canonical(tries, _) => {
                     // If the block is on canonical chain, we delete the state that's before applying this block
                     tries.apply_deletions(&trie_changes, shard_uid, store_update);
                 }
@@ -961,8 +1187,13 @@ impl<'a> ChainStoreUpdate<'a> {
         self.merge(store_update);
     }
 
-    fn gc_outcomes(&mut self, block: &Block) -> Result<(), Error> {
+    fn gc_outcomes(
+        &mut self,
+        block: &Block,
+        mut retained: Option<&mut RetainedData>,
+    ) -> Result<(), Error> {
         let block_hash = block.hash();
+        let height = block.header().height();
         let store_update = self.store().store_update();
         for chunk_header in block
             .chunks()
@@ -975,10 +1206,14 @@ impl<'a> ChainStoreUpdate<'a> {
             let outcome_ids =
                 self.chain_store().get_outcomes_by_block_hash_and_shard_id(block_hash, shard_id)?;
             for outcome_id in outcome_ids {
-                self.gc_col(
-                    DBCol::TransactionResultForBlock,
-                    &get_outcome_id_block_hash(&outcome_id, block_hash),
-                );
+                let key = get_outcome_id_block_hash(&outcome_id, block_hash);
+                if let Some(retained) = retained.as_deref_mut() {
+                    if self.retain_outcome(retained, &outcome_id, &key, height)? {
+                        retained.push(height, RetainedKey::Outcome(key.to_vec()));
+                        continue;
+                    }
+                }
+                self.gc_col(DBCol::TransactionResultForBlock, &key);
             }
             self.gc_col(DBCol::OutcomeIds, &get_block_shard_id(block_hash, shard_id));
         }
@@ -986,6 +1221,41 @@ impl<'a> ChainStoreUpdate<'a> {
         Ok(())
     }
 
+    /// Checks whether the outcome has to be retained: if it is executed by one of the accounts,
+    /// or is the outcome of a retained transaction or receipt, or of a receipt produced by
+    /// a retained outcome. If so, the receipts it produces are to be retained as well.
+    fn retain_outcome(
+        &self,
+        retained: &mut RetainedData,
+        outcome_id: &CryptoHash,
+        key: &[u8],
+        height: BlockHeight,
+    ) -> Result<bool, Error> {
+        let Some(outcome) = self
+            .store()
+            .get_ser::<ExecutionOutcomeWithProof>(DBCol::TransactionResultForBlock, key)?
+        else {
+            return Ok(false);
+        };
+        let retention = &retained.retention;
+        let retain = retained.receipts.remove(outcome_id).is_some()
+            || retention.accounts.contains(&outcome.outcome.executor_id)
+            || self
+                .chain_store()
+                .get_transaction(outcome_id)?
+                .is_some_and(|transaction| retention.retains_transaction(&transaction))
+            || self
+                .chain_store()
+                .get_receipt(outcome_id)?
+                .is_some_and(|receipt| retention.retains_receipt(&receipt));
+        if retain {
+            for receipt_id in outcome.outcome.receipt_ids {
+                retained.receipts.insert(receipt_id, height);
+            }
+        }
+        Ok(retain)
+    }
+
     fn gc_col(&mut self, col: DBCol, key: &[u8]) {
         let mut store_update = self.store().store_update();
         match col {
@@ -1090,6 +1360,9 @@ impl<'a> ChainStoreUpdate<'a> {
             DBCol::StateSyncNewChunks => {
                 store_update.delete(col, key);
             }
+            DBCol::GCRetainedData => {
+                store_update.delete(col, key);
+            }
             DBCol::ChunkApplyStats => {
                 store_update.delete(col, key);
             }
@@ -1128,6 +1401,9 @@ impl<'a> ChainStoreUpdate<'a> {
             // Note that StateSyncHashes should not ever have too many keys in them
             // because we remove unneeded keys as we add new ones.
             | DBCol::StateSyncHashes
+            // Orphans are removed once they are processed or fall below the final height.
+            | DBCol::OrphanBlocks
+            | DBCol::OrphanChunkStateWitnesses
             => unreachable!(),
         }
         self.merge(store_update);
@@ -12,8 +12,9 @@ pub use store::utils::{
     get_incoming_receipts_for_shard, retrieve_headers,
 };
 pub use store::{
-    ChainStore, ChainStoreAccess, ChainStoreUpdate, LatestWitnessesInfo, MerkleProofAccess,
-    ReceiptFilter,
+    ChainStore, ChainStoreAccess, ChainStoreUpdate, LatestWitnessesInfo,
+    MAX_PERSISTED_ORPHAN_BLOCKS, MerkleProofAccess, OrphanStateWitness, PersistableOrphan,
+    PersistedOrphans, ReceiptFilter,
 };
 pub use store_validator::{ErrorMessage, StoreValidator};
 pub use types::{Block, BlockHeader, BlockStatus, ChainGenesis, LatestKnown, Provenance};
@@ -46,6 +47,7 @@ pub mod store_validator;
 pub mod test_utils;
 #[cfg(test)]
 mod tests;
+pub mod timeline;
 pub mod types;
 pub mod update_shard;
 pub mod validate;
@@ -99,6 +99,22 @@ pub static NUM_OPTIMISTIC_ORPHANS: LazyLock<IntGauge> = LazyLock::new(|| {
     try_create_int_gauge("near_num_optimistic_orphans", "Number of optimistic orphan blocks.")
         .unwrap()
 });
+pub static PERSISTED_ORPHANS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_persisted_orphans",
+        "Number of orphans persisted in the database, by kind",
+        &["kind"],
+    )
+    .unwrap()
+});
+pub static PERSISTED_ORPHANS_RESTORED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_persisted_orphans_restored_total",
+        "Number of persisted orphans requeued for processing after a restart, by kind",
+        &["kind"],
+    )
+    .unwrap()
+});
 pub static NUM_PENDING_BLOCKS: LazyLock<IntGauge> = LazyLock::new(|| {
     try_create_int_gauge(
         "near_num_pending_blocks",
@@ -12,7 +12,7 @@ use near_primitives::utils::MaybeValidated;
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::num::NonZeroUsize;
-use tracing::{debug, debug_span};
+use tracing::{debug, debug_span, warn};
 
 use crate::missing_chunks::BlockLike;
 use crate::{BlockProcessingArtifact, Chain, Provenance, metrics};
@@ -286,6 +286,11 @@ impl Chain {
     ) {
         let block_hash = *block.hash();
         if !self.orphans.contains(block.hash()) {
+            if let Some(persisted_orphans) = &mut self.persisted_orphans {
+                if let Err(err) = persisted_orphans.save(block.get_inner()) {
+                    warn!(target: "chain", ?block_hash, ?err, "Failed to persist orphan block");
+                }
+            }
             self.orphans.add(
                 Orphan { block, provenance, added: self.clock.now() },
                 requested_missing_chunks,
@@ -415,6 +420,16 @@ impl Chain {
             for orphan in orphans {
                 let block_hash = orphan.hash();
                 self.blocks_delay_tracker.mark_block_unorphaned(&block_hash);
+                if let Some(persisted_orphans) = &mut self.persisted_orphans {
+                    if let Err(err) = persisted_orphans.remove(orphan.block.get_inner()) {
+                        warn!(
+                            target: "chain",
+                            ?block_hash,
+                            ?err,
+                            "Failed to remove persisted orphan block"
+                        );
+                    }
+                }
                 let res = self.start_process_block_async(
                     me,
                     orphan.block,
@@ -436,6 +451,41 @@ impl Chain {
             debug!(target: "chain", ?optimistic_block, "Check optimistic orphan");
             self.preprocess_optimistic_block(optimistic_block, me, apply_chunks_done_sender);
         }
+        self.prune_persisted_orphans();
+    }
+
+    /// Removes persisted orphans which are below the final head, as they can no
+    /// longer be accepted.
+    fn prune_persisted_orphans(&mut self) {
+        if self.persisted_orphans.is_none() {
+            return;
+        }
+        let final_head = match self.final_head() {
+            Ok(final_head) => final_head,
+            Err(err) => {
+                warn!(target: "chain", ?err, "Failed to get final head");
+                return;
+            }
+        };
+        let persisted_orphans = self.persisted_orphans.as_mut().unwrap();
+        if let Err(err) = persisted_orphans.remove_below_height(final_head.height + 1) {
+            warn!(target: "chain", ?err, "Failed to prune persisted orphan blocks");
+        }
+    }
+
+    /// Takes out orphan blocks persisted before the node was restarted, in the
+    /// order of increasing height. They are meant to be processed again.
+    pub fn take_persisted_orphans(&mut self) -> Vec<Block> {
+        let Some(persisted_orphans) = &mut self.persisted_orphans else {
+            return vec![];
+        };
+        match persisted_orphans.take_all() {
+            Ok(blocks) => blocks,
+            Err(err) => {
+                warn!(target: "chain", ?err, "Failed to load persisted orphan blocks");
+                vec![]
+            }
+        }
     }
 
     /// Returns number of orphans currently in the orphan pool.
@@ -2,7 +2,8 @@ use crate::Error;
 use crate::types::{
     ApplyChunkBlockContext, ApplyChunkResult, ApplyChunkShardContext,
     PrepareTransactionsBlockContext, PrepareTransactionsChunkContext, PrepareTransactionsLimit,
-    PreparedTransactions, RuntimeAdapter, RuntimeStorageConfig, StorageDataSource, Tip,
+    PrepareTransactionsRejection, PreparedTransactions, RuntimeAdapter, RuntimeStorageConfig,
+    StorageDataSource, Tip,
 };
 use borsh::BorshDeserialize;
 use errors::FromStateViewerErrors;
@@ -11,6 +12,7 @@ use near_chain_configs::{GenesisConfig, MIN_GC_NUM_EPOCHS_TO_KEEP, ProtocolConfi
 use near_crypto::PublicKey;
 use near_epoch_manager::shard_assignment::account_id_to_shard_id;
 use near_epoch_manager::{EpochManagerAdapter, EpochManagerHandle};
+use near_parameters::vm::VMKind;
 use near_parameters::{RuntimeConfig, RuntimeConfigStore};
 use near_pool::types::TransactionGroupIterator;
 use near_primitives::account::{AccessKey, Account};
@@ -49,8 +51,8 @@ use node_runtime::config::tx_cost;
 use node_runtime::state_viewer::{TrieViewer, ViewApplyState};
 use node_runtime::{
     ApplyState, Runtime, SignedValidPeriodTransactions, ValidatorAccountsUpdate,
-    get_signer_and_access_key, set_tx_state_changes, validate_transaction,
-    verify_and_charge_tx_ephemeral,
+    get_signer_and_access_key, prefetch_next_chunk_data, set_tx_state_changes,
+    validate_transaction, verify_and_charge_tx_ephemeral,
 };
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -90,13 +92,17 @@ impl NightshadeRuntime {
         gc_num_epochs_to_keep: u64,
         trie_config: TrieConfig,
         state_snapshot_config: StateSnapshotConfig,
+        shadow_vm_kind: Option<VMKind>,
+        parallel_receipt_execution: bool,
     ) -> Arc<Self> {
         let runtime_config_store = match runtime_config_store {
             Some(store) => store,
             None => RuntimeConfigStore::for_chain_id(&genesis_config.chain_id),
         };
 
-        let runtime = Runtime::new();
+        let runtime = Runtime::new()
+            .with_shadow_vm_kind(shadow_vm_kind)
+            .with_parallel_receipt_execution(parallel_receipt_execution);
         let trie_viewer = TrieViewer::new(trie_viewer_state_size_limit, max_gas_burnt_view);
         let flat_storage_manager = FlatStorageManager::new(store.flat_store());
         let epoch_config = epoch_manager.read().get_epoch_config(genesis_config.protocol_version);
@@ -631,7 +637,11 @@ impl RuntimeAdapter for NightshadeRuntime {
 
         let transactions_gas_limit = chunk_tx_gas_limit(runtime_config, &prev_block, shard_id);
 
-        let mut result = PreparedTransactions { transactions: Vec::new(), limited_by: None };
+        let mut result = PreparedTransactions {
+            transactions: Vec::new(),
+            limited_by: None,
+            rejected: Vec::new(),
+        };
         let mut num_checked_transactions = 0;
 
         let size_limit = runtime_config.witness_config.combined_transactions_size_limit as u64;
@@ -691,6 +701,9 @@ impl RuntimeAdapter for NightshadeRuntime {
                 )? {
                     tracing::trace!(target: "runtime", tx=?validated_tx.get_hash(), "discarding transaction due to congestion");
                     rejected_due_to_congestion += 1;
+                    result
+                        .rejected
+                        .push((validated_tx.get_hash(), PrepareTransactionsRejection::Congestion));
                     continue;
                 }
 
@@ -698,6 +711,10 @@ impl RuntimeAdapter for NightshadeRuntime {
                 if !chain_validate(&validated_tx.to_signed_tx()) {
                     tracing::trace!(target: "runtime", tx=?validated_tx.get_hash(), "discarding transaction that failed chain validation");
                     rejected_invalid_for_chain += 1;
+                    result.rejected.push((
+                        validated_tx.get_hash(),
+                        PrepareTransactionsRejection::InvalidForChain,
+                    ));
                     continue;
                 }
 
@@ -740,6 +757,10 @@ impl RuntimeAdapter for NightshadeRuntime {
                         tracing::trace!(target: "runtime", tx=?validated_tx.get_hash(), ?err, "discarding transaction that failed verification or verification");
                         rejected_invalid_tx += 1;
                         state_update.rollback();
+                        result.rejected.push((
+                            validated_tx.get_hash(),
+                            PrepareTransactionsRejection::InvalidTx(err),
+                        ));
                     }
                 }
             }
@@ -1174,6 +1195,19 @@ impl RuntimeAdapter for NightshadeRuntime {
         Ok(ProtocolConfig { genesis_config, runtime_config })
     }
 
+    fn prefetch_next_chunk_state(
+        &self,
+        shard_uid: ShardUId,
+        state_root: StateRoot,
+        signers: &[(AccountId, PublicKey)],
+        receipts: &[Receipt],
+    ) {
+        let trie = self.tries.get_trie_for_shard(shard_uid, state_root);
+        if let Err(err) = prefetch_next_chunk_data(&trie, signers, receipts) {
+            debug!(target: "runtime", ?shard_uid, ?err, "failed to prefetch next chunk state");
+        }
+    }
+
     fn will_shard_layout_change_next_epoch(&self, parent_hash: &CryptoHash) -> Result<bool, Error> {
         Ok(self.epoch_manager.will_shard_layout_change(parent_hash)?)
     }
@@ -29,6 +29,7 @@ impl NightshadeRuntime {
             DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
             Default::default(),
             StateSnapshotConfig::enabled(home_dir, "data", "state_snapshot"),
+            None,
         )
     }
 
@@ -53,6 +54,7 @@ impl NightshadeRuntime {
             gc_num_epochs_to_keep,
             trie_config,
             StateSnapshotConfig::enabled(home_dir, "data", "state_snapshot"),
+            None,
         )
     }
 
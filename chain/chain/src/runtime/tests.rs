@@ -138,6 +138,8 @@ impl TestEnv {
             DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
             Default::default(),
             StateSnapshotConfig::enabled(dir.path(), "data", "state_snapshot"),
+            None,
+            false,
         );
         let state_roots = get_genesis_state_roots(&store).unwrap().unwrap();
         let genesis_hash = hash(&[0]);
@@ -1451,6 +1453,42 @@ fn test_prepare_transactions_empty_storage_proof() {
         .expect_err("prepare transactions should fail with empty storage proof");
 }
 
+/// Check that transactions taken from the pool but not included are reported with the reason.
+#[test]
+fn test_prepare_transactions_rejected() {
+    let (env, chain, mut transaction_pool) = get_test_env_with_chain_and_pool();
+    let transactions_count = transaction_pool.len();
+    let signer = InMemorySigner::test_signer(&"test1".parse().unwrap());
+    let expired_tx = SignedTransaction::send_money(
+        100,
+        "test1".parse().unwrap(),
+        "test2".parse().unwrap(),
+        &signer,
+        1,
+        CryptoHash::hash_bytes(b"unknown block"),
+    );
+    let expired_tx_hash = expired_tx.get_hash();
+    assert_eq!(
+        transaction_pool.insert_transaction(ValidatedTransaction::new_for_test(expired_tx)),
+        InsertTransactionResult::Success
+    );
+
+    let storage_config = RuntimeStorageConfig {
+        state_root: env.state_roots[0],
+        use_flat_storage: true,
+        source: StorageDataSource::Db,
+        state_patch: Default::default(),
+    };
+    let mut transaction_groups = PoolIteratorWrapper::new(&mut transaction_pool);
+    let prepared_transactions =
+        prepare_transactions(&env, &chain, &mut transaction_groups, storage_config).unwrap();
+    assert_eq!(prepared_transactions.transactions.len(), transactions_count);
+    assert_eq!(
+        prepared_transactions.rejected,
+        vec![(expired_tx_hash, PrepareTransactionsRejection::InvalidForChain)]
+    );
+}
+
 // Helper function to test prepare_transactions with different storage sources.
 fn test_prepare_transactions_helper(
     storage_source: StorageDataSource,
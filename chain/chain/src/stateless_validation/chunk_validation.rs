@@ -6,6 +6,7 @@ use crate::rayon_spawner::RayonAsyncComputationSpawner;
 use crate::resharding::event_type::ReshardingEventType;
 use crate::resharding::manager::ReshardingManager;
 use crate::sharding::{get_receipts_shuffle_salt, shuffle_receipt_proofs};
+use crate::stateless_validation::divergence::{MainTransitionCapture, save_divergence_report};
 use crate::stateless_validation::processing_tracker::ProcessingDoneTracker;
 use crate::store::filter_incoming_receipts_for_shard;
 use crate::types::{ApplyChunkBlockContext, ApplyChunkResult, RuntimeAdapter, StorageDataSource};
@@ -36,6 +37,7 @@ use node_runtime::SignedValidPeriodTransactions;
 use parking_lot::Mutex;
 use std::collections::HashMap;
 use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -518,6 +520,27 @@ pub fn validate_chunk_state_witness(
     epoch_manager: &dyn EpochManagerAdapter,
     runtime_adapter: &dyn RuntimeAdapter,
     main_state_transition_cache: &MainStateTransitionCache,
+) -> Result<(), Error> {
+    validate_chunk_state_witness_impl(
+        state_witness,
+        pre_validation_output,
+        epoch_manager,
+        runtime_adapter,
+        main_state_transition_cache,
+        None,
+    )
+}
+
+/// Same as `validate_chunk_state_witness`, but if `capture` is set, the output
+/// of the main state transition is saved there so that a divergence can be
+/// reported even if validation fails at a later step.
+fn validate_chunk_state_witness_impl(
+    state_witness: ChunkStateWitness,
+    pre_validation_output: PreValidationOutput,
+    epoch_manager: &dyn EpochManagerAdapter,
+    runtime_adapter: &dyn RuntimeAdapter,
+    main_state_transition_cache: &MainStateTransitionCache,
+    capture: Option<&mut MainTransitionCapture>,
 ) -> Result<(), Error> {
     let _timer = crate::stateless_validation::metrics::CHUNK_STATE_WITNESS_VALIDATION_TIME
         .with_label_values(&[&state_witness.chunk_header.shard_id().to_string()])
@@ -551,7 +574,14 @@ pub fn validate_chunk_state_witness(
                     runtime_adapter,
                 )?;
                 let outgoing_receipts = std::mem::take(&mut main_apply_result.outgoing_receipts);
+                let state_changes = capture
+                    .is_some()
+                    .then(|| main_apply_result.trie_changes.state_changes().to_vec());
                 let chunk_extra = apply_result_to_chunk_extra(main_apply_result, &chunk_header);
+                if let (Some(capture), Some(state_changes)) = (capture, state_changes) {
+                    capture.chunk_extra = Some(chunk_extra.clone());
+                    capture.state_changes = state_changes;
+                }
 
                 (chunk_extra, outgoing_receipts)
             }
@@ -720,6 +750,7 @@ impl Chain {
         witness: ChunkStateWitness,
         epoch_manager: &dyn EpochManagerAdapter,
         processing_done_tracker: Option<ProcessingDoneTracker>,
+        divergence_dir: Option<PathBuf>,
     ) -> Result<(), Error> {
         let shard_id = witness.chunk_header.shard_id();
         let height_created = witness.chunk_header.height_created();
@@ -759,21 +790,29 @@ impl Chain {
             pre_validation_elapsed = ?pre_validation_start.elapsed(),
             "completed shadow chunk pre-validation"
         );
+        let protocol_version = epoch_manager.get_epoch_protocol_version(&witness.epoch_id)?;
+        let main_block_hash = pre_validation_result.main_transition_params.block_hash();
+        let main_shard_id = pre_validation_result.main_transition_params.shard_id();
+        // The witness is consumed by validation, so keep a copy for the report.
+        let report_witness = divergence_dir.as_ref().map(|_| witness.clone());
         let epoch_manager = self.epoch_manager.clone();
         let runtime_adapter = self.runtime_adapter.clone();
+        let divergence_report_limiter = self.divergence_report_limiter.clone();
         Arc::new(RayonAsyncComputationSpawner).spawn("shadow_validate", move || {
             // processing_done_tracker must survive until the processing is finished.
             let _processing_done_tracker_capture: Option<ProcessingDoneTracker> =
                 processing_done_tracker;
 
             let validation_start = Instant::now();
+            let mut capture = MainTransitionCapture::default();
 
-            match validate_chunk_state_witness(
+            match validate_chunk_state_witness_impl(
                 witness,
                 pre_validation_result,
                 epoch_manager.as_ref(),
                 runtime_adapter.as_ref(),
                 &MainStateTransitionCache::default(),
+                report_witness.is_some().then_some(&mut capture),
             ) {
                 Ok(()) => {
                     tracing::debug!(
@@ -788,6 +827,9 @@ impl Chain {
                     crate::stateless_validation::metrics::CHUNK_WITNESS_VALIDATION_FAILED_TOTAL
                         .with_label_values(&[&shard_id.to_string(), err.prometheus_label_value()])
                         .inc();
+                    crate::stateless_validation::metrics::SHADOW_VALIDATION_DIVERGENCES_TOTAL
+                        .with_label_values(&[&shard_id.to_string(), &protocol_version.to_string()])
+                        .inc();
                     tracing::error!(
                         parent: &parent_span,
                         ?err,
@@ -795,6 +837,43 @@ impl Chain {
                         ?chunk_hash,
                         "shadow chunk validation failed"
                     );
+                    let (Some(dir), Some(witness)) = (divergence_dir, report_witness) else {
+                        return;
+                    };
+                    if !divergence_report_limiter.try_acquire(shard_id) {
+                        tracing::debug!(
+                            parent: &parent_span,
+                            ?shard_id,
+                            ?chunk_hash,
+                            "skipped shadow validation divergence report due to rate limit"
+                        );
+                        return;
+                    }
+                    match save_divergence_report(
+                        &dir,
+                        witness,
+                        &err,
+                        capture,
+                        main_block_hash,
+                        main_shard_id,
+                        epoch_manager.as_ref(),
+                        runtime_adapter.as_ref(),
+                    ) {
+                        Ok(path) => tracing::warn!(
+                            parent: &parent_span,
+                            ?shard_id,
+                            ?chunk_hash,
+                            ?path,
+                            "saved shadow validation divergence report"
+                        ),
+                        Err(err) => tracing::error!(
+                            parent: &parent_span,
+                            ?err,
+                            ?shard_id,
+                            ?chunk_hash,
+                            "failed to save shadow validation divergence report"
+                        ),
+                    }
                 }
             }
         });
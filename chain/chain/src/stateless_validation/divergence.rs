@@ -0,0 +1,281 @@
+//! Structured reports for divergences detected by shadow chunk validation.
+//!
+//! Shadow validation re-executes state witnesses produced by this node from
+//! chunks it has already applied. Any validation failure therefore means that
+//! stateless execution diverged from stateful execution. When a dump directory
+//! is configured, each divergence is written to a self-contained file which can
+//! be replayed offline with `neard view-state state-witness replay-divergence`.
+//!
+//! Witnesses can be large, so at most one report per shard is saved every
+//! `MIN_REPORT_INTERVAL` and only the newest `MAX_SAVED_REPORTS` files are kept
+//! in the directory. Reports skipped or removed this way are counted by the
+//! `near_shadow_validation_divergence_reports_dropped_total` metric.
+
+use crate::stateless_validation::metrics::SHADOW_VALIDATION_DIVERGENCE_REPORTS_DROPPED_TOTAL;
+use crate::types::RuntimeAdapter;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_async::time::{Clock, Duration, Instant};
+use near_chain_primitives::Error;
+use near_epoch_manager::EpochManagerAdapter;
+use near_epoch_manager::shard_assignment::shard_id_to_uid;
+use near_primitives::hash::CryptoHash;
+use near_primitives::stateless_validation::state_witness::ChunkStateWitness;
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{RawStateChangesWithTrieKey, ShardId};
+use near_primitives::version::ProtocolVersion;
+use near_store::adapter::StoreAdapter;
+use near_store::trie::AccessOptions;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Minimum time between two reports saved for the same shard.
+const MIN_REPORT_INTERVAL: Duration = Duration::minutes(1);
+/// Maximum number of reports kept in the divergence directory.
+const MAX_SAVED_REPORTS: usize = 100;
+const REPORT_FILE_PREFIX: &str = "divergence_";
+const REPORT_FILE_SUFFIX: &str = ".borsh";
+
+/// Limits how often divergence reports are saved for each shard.
+pub(crate) struct DivergenceReportLimiter {
+    clock: Clock,
+    last_saved: Mutex<HashMap<ShardId, Instant>>,
+}
+
+impl DivergenceReportLimiter {
+    pub(crate) fn new(clock: Clock) -> Self {
+        Self { clock, last_saved: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns whether a report for `shard_id` may be saved now, and if so
+    /// records it as saved.
+    pub(crate) fn try_acquire(&self, shard_id: ShardId) -> bool {
+        let now = self.clock.now();
+        let mut last_saved = self.last_saved.lock();
+        if last_saved.get(&shard_id).is_some_and(|last| now < *last + MIN_REPORT_INTERVAL) {
+            SHADOW_VALIDATION_DIVERGENCE_REPORTS_DROPPED_TOTAL
+                .with_label_values(&[&shard_id.to_string(), "rate_limited"])
+                .inc();
+            return false;
+        }
+        last_saved.insert(shard_id, now);
+        true
+    }
+}
+
+/// Output of the main state transition recomputed from the witness. Filled in
+/// by `validate_chunk_state_witness_impl` when a divergence report may be
+/// needed, because the validation error alone does not carry it.
+#[derive(Default)]
+pub struct MainTransitionCapture {
+    pub chunk_extra: Option<ChunkExtra>,
+    pub state_changes: Vec<RawStateChangesWithTrieKey>,
+}
+
+/// A single post-state value which differs between the state this node
+/// computed when applying the chunk and the state recomputed from the witness.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq)]
+pub struct TrieValueDiff {
+    pub trie_key: TrieKey,
+    pub local_value: Option<Vec<u8>>,
+    pub witness_value: Option<Vec<u8>>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ShadowValidationDivergence {
+    pub witness: ChunkStateWitness,
+    pub protocol_version: ProtocolVersion,
+    /// Error returned by witness validation.
+    pub error: String,
+    /// `ChunkExtra` this node stored when it applied the chunk.
+    pub local_chunk_extra: Option<ChunkExtra>,
+    /// `ChunkExtra` recomputed from the witness, if the main transition could
+    /// be applied at all.
+    pub witness_chunk_extra: Option<ChunkExtra>,
+    /// Keys written by the main transition whose post-state value differs from
+    /// the value in the local post-state.
+    pub trie_diff: Vec<TrieValueDiff>,
+}
+
+impl ShadowValidationDivergence {
+    pub fn file_name(&self) -> String {
+        format!(
+            "{REPORT_FILE_PREFIX}{}_{}_{}{REPORT_FILE_SUFFIX}",
+            self.witness.chunk_header.height_created(),
+            self.witness.chunk_header.shard_id(),
+            self.witness.chunk_header.chunk_hash().0,
+        )
+    }
+
+    /// Writes the report to `dir` and removes the oldest reports there so
+    /// that at most `MAX_SAVED_REPORTS` remain.
+    pub fn save(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(self.file_name());
+        std::fs::write(&path, borsh::to_vec(self)?)?;
+        prune_reports(dir, MAX_SAVED_REPORTS)?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        Self::try_from_slice(&std::fs::read(path)?)
+    }
+}
+
+fn is_report_file_name(name: &str) -> bool {
+    name.starts_with(REPORT_FILE_PREFIX) && name.ends_with(REPORT_FILE_SUFFIX)
+}
+
+/// Removes the oldest report files in `dir` until at most `max_reports`
+/// remain. Files not named like reports are left alone.
+fn prune_reports(dir: &Path, max_reports: usize) -> std::io::Result<()> {
+    let mut reports = vec![];
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let Some(name) = entry.file_name().to_str().map(str::to_owned) else {
+            continue;
+        };
+        if !is_report_file_name(&name) {
+            continue;
+        }
+        reports.push((entry.metadata()?.modified()?, name));
+    }
+    if reports.len() <= max_reports {
+        return Ok(());
+    }
+    reports.sort();
+    for (_, name) in &reports[..reports.len() - max_reports] {
+        std::fs::remove_file(dir.join(name))?;
+        // File names are `divergence_{height}_{shard_id}_{chunk_hash}.borsh`.
+        let shard_id = name.split('_').nth(2).unwrap_or_default();
+        SHADOW_VALIDATION_DIVERGENCE_REPORTS_DROPPED_TOTAL
+            .with_label_values(&[shard_id, "pruned"])
+            .inc();
+    }
+    Ok(())
+}
+
+/// Builds a divergence report and writes it to `dir`. `main_block_hash` and
+/// `main_shard_id` identify the main transition of the witness.
+pub(crate) fn save_divergence_report(
+    dir: &Path,
+    witness: ChunkStateWitness,
+    error: &Error,
+    capture: MainTransitionCapture,
+    main_block_hash: CryptoHash,
+    main_shard_id: ShardId,
+    epoch_manager: &dyn EpochManagerAdapter,
+    runtime_adapter: &dyn RuntimeAdapter,
+) -> Result<PathBuf, Error> {
+    let protocol_version = epoch_manager.get_epoch_protocol_version(&witness.epoch_id)?;
+    let main_epoch_id = epoch_manager.get_epoch_id(&main_block_hash)?;
+    let shard_uid = shard_id_to_uid(epoch_manager, main_shard_id, &main_epoch_id)?;
+    let local_chunk_extra =
+        match runtime_adapter.store().chain_store().get_chunk_extra(&main_block_hash, &shard_uid) {
+            Ok(chunk_extra) => Some(chunk_extra.as_ref().clone()),
+            Err(Error::DBNotFoundErr(_)) => None,
+            Err(err) => return Err(err),
+        };
+    let trie_diff = match &local_chunk_extra {
+        Some(chunk_extra) => {
+            let trie = runtime_adapter
+                .get_tries()
+                .get_view_trie_for_shard(shard_uid, *chunk_extra.state_root());
+            let mut diff = vec![];
+            for change in &capture.state_changes {
+                let witness_value = change.changes.last().and_then(|change| change.data.clone());
+                let local_value = trie.get(&change.trie_key.to_vec(), AccessOptions::DEFAULT)?;
+                if local_value != witness_value {
+                    diff.push(TrieValueDiff {
+                        trie_key: change.trie_key.clone(),
+                        local_value,
+                        witness_value,
+                    });
+                }
+            }
+            diff
+        }
+        None => vec![],
+    };
+    let divergence = ShadowValidationDivergence {
+        witness,
+        protocol_version,
+        error: error.to_string(),
+        local_chunk_extra,
+        witness_chunk_extra: capture.chunk_extra,
+        trie_diff,
+    };
+    divergence.save(dir).map_err(|err| Error::Other(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DivergenceReportLimiter, MIN_REPORT_INTERVAL, ShadowValidationDivergence, TrieValueDiff,
+        prune_reports,
+    };
+    use near_async::time::{Duration, FakeClock};
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::stateless_validation::state_witness::ChunkStateWitness;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::ShardId;
+
+    #[test]
+    fn test_save_and_load_divergence() {
+        let dir = tempfile::tempdir().unwrap();
+        let witness = ChunkStateWitness::new_dummy(10, ShardId::new(2), CryptoHash::default());
+        let divergence = ShadowValidationDivergence {
+            witness,
+            protocol_version: 1,
+            error: "post state root mismatch".to_string(),
+            local_chunk_extra: None,
+            witness_chunk_extra: None,
+            trie_diff: vec![TrieValueDiff {
+                trie_key: TrieKey::Account { account_id: "alice.near".parse().unwrap() },
+                local_value: Some(vec![1]),
+                witness_value: None,
+            }],
+        };
+        let path = divergence.save(&dir.path().join("divergences")).unwrap();
+        assert!(path.file_name().unwrap().to_str().unwrap().starts_with("divergence_10_2_"));
+
+        let loaded = ShadowValidationDivergence::load(&path).unwrap();
+        assert_eq!(loaded.witness, divergence.witness);
+        assert_eq!(loaded.error, divergence.error);
+        assert_eq!(loaded.trie_diff, divergence.trie_diff);
+    }
+
+    #[test]
+    fn test_prune_reports() {
+        let dir = tempfile::tempdir().unwrap();
+        for height in 0..5 {
+            let name = format!("divergence_{height}_0_hash.borsh");
+            std::fs::write(dir.path().join(name), [0]).unwrap();
+        }
+        std::fs::write(dir.path().join("notes.txt"), [0]).unwrap();
+
+        prune_reports(dir.path(), 3).unwrap();
+        let mut names: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names.len(), 4);
+        assert!(names.contains(&"notes.txt".to_string()));
+    }
+
+    #[test]
+    fn test_divergence_report_limiter() {
+        let clock = FakeClock::default();
+        let limiter = DivergenceReportLimiter::new(clock.clock());
+        assert!(limiter.try_acquire(ShardId::new(0)));
+        assert!(!limiter.try_acquire(ShardId::new(0)));
+        assert!(limiter.try_acquire(ShardId::new(1)));
+
+        clock.advance(MIN_REPORT_INTERVAL - Duration::seconds(1));
+        assert!(!limiter.try_acquire(ShardId::new(0)));
+        clock.advance(Duration::seconds(1));
+        assert!(limiter.try_acquire(ShardId::new(0)));
+    }
+}
@@ -70,6 +70,25 @@ pub static SHADOW_CHUNK_VALIDATION_FAILED_TOTAL: LazyLock<IntCounter> = LazyLock
     .unwrap()
 });
 
+pub static SHADOW_VALIDATION_DIVERGENCES_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_shadow_validation_divergences_total",
+        "Shadow chunk validation divergences between stateful and stateless execution",
+        &["shard_id", "protocol_version"],
+    )
+    .unwrap()
+});
+
+pub static SHADOW_VALIDATION_DIVERGENCE_REPORTS_DROPPED_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_shadow_validation_divergence_reports_dropped_total",
+            "Shadow validation divergence reports which were not saved or were removed to bound disk usage",
+            &["shard_id", "reason"],
+        )
+        .unwrap()
+    });
+
 pub static CHUNK_WITNESS_VALIDATION_FAILED_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
     try_create_int_counter_vec(
         "near_chunk_witness_validation_failed_total",
@@ -1,4 +1,5 @@
 pub mod chunk_endorsement;
 pub mod chunk_validation;
+pub mod divergence;
 pub mod metrics;
 pub mod processing_tracker;
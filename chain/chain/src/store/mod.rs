@@ -45,6 +45,9 @@ use near_store::{
     KeyForStateChanges, LARGEST_TARGET_HEIGHT_KEY, LATEST_KNOWN_KEY, PartialStorage, Store,
     StoreUpdate, TAIL_KEY, WrappedTrieChanges,
 };
+pub use persisted_orphans::{
+    MAX_PERSISTED_ORPHAN_BLOCKS, OrphanStateWitness, PersistableOrphan, PersistedOrphans,
+};
 use std::collections::hash_map::Entry;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io;
@@ -54,6 +57,7 @@ use utils::check_transaction_validity_period;
 
 mod latest_witnesses;
 mod merkle_proof;
+mod persisted_orphans;
 pub mod utils;
 
 /// Filter receipts mode for incoming receipts collection.
@@ -0,0 +1,244 @@
+//! Optional persistence of orphan blocks and orphan chunk state witnesses.
+//!
+//! Orphans are normally kept only in memory, so they are lost when the node
+//! restarts and have to be fetched from peers again. When `persist_orphans` is
+//! enabled, recent orphans are mirrored to the database and requeued for
+//! processing on startup. Persisted orphans are removed once they are taken out
+//! of the in-memory pools or fall below the final height. Their number is
+//! capped, orphans with the lowest heights are preferred.
+
+use crate::metrics;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::block::Block;
+use near_primitives::stateless_validation::state_witness::ChunkStateWitness;
+use near_primitives::types::BlockHeight;
+use near_store::{DBCol, Store};
+use std::collections::BTreeSet;
+use std::marker::PhantomData;
+
+/// Maximum number of orphan blocks kept in the database.
+pub const MAX_PERSISTED_ORPHAN_BLOCKS: usize = 32;
+
+/// An orphan which can be persisted in the database.
+pub trait PersistableOrphan: BorshSerialize + BorshDeserialize {
+    /// Column the orphans are stored in.
+    const COL: DBCol;
+    /// Label of the kind of orphans in metrics.
+    const KIND: &'static str;
+
+    fn height(&self) -> BlockHeight;
+
+    /// Unique identifier of the orphan, appended to the height in the key.
+    fn id_bytes(&self) -> Vec<u8>;
+}
+
+impl PersistableOrphan for Block {
+    const COL: DBCol = DBCol::OrphanBlocks;
+    const KIND: &'static str = "block";
+
+    fn height(&self) -> BlockHeight {
+        self.header().height()
+    }
+
+    fn id_bytes(&self) -> Vec<u8> {
+        self.hash().as_bytes().to_vec()
+    }
+}
+
+/// An orphan state witness together with its size as received from the
+/// network, which is needed to process the witness again.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct OrphanStateWitness {
+    pub witness: ChunkStateWitness,
+    pub witness_size: u64,
+}
+
+impl OrphanStateWitness {
+    /// Id of the persisted witness, which allows to remove it without
+    /// constructing `OrphanStateWitness`.
+    pub fn witness_id(witness: &ChunkStateWitness) -> Vec<u8> {
+        let shard_id: u64 = witness.chunk_header.shard_id().into();
+        let mut id = shard_id.to_be_bytes().to_vec();
+        id.extend_from_slice(witness.epoch_id.0.as_bytes());
+        id
+    }
+}
+
+impl PersistableOrphan for OrphanStateWitness {
+    const COL: DBCol = DBCol::OrphanChunkStateWitnesses;
+    const KIND: &'static str = "state_witness";
+
+    fn height(&self) -> BlockHeight {
+        self.witness.chunk_header.height_created()
+    }
+
+    fn id_bytes(&self) -> Vec<u8> {
+        Self::witness_id(&self.witness)
+    }
+}
+
+/// Orphans of one kind persisted in the database. Keys of all persisted
+/// orphans are kept in memory, so that enforcing the limit doesn't require
+/// reading the database.
+pub struct PersistedOrphans<T> {
+    store: Store,
+    max_count: usize,
+    /// Keys in the database: big-endian height followed by the orphan id.
+    keys: BTreeSet<Vec<u8>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: PersistableOrphan> PersistedOrphans<T> {
+    pub fn new(store: Store, max_count: usize) -> std::io::Result<Self> {
+        let mut keys = BTreeSet::new();
+        for item in store.iter_raw_bytes(T::COL) {
+            let (key, _) = item?;
+            keys.insert(key.to_vec());
+        }
+        let persisted = Self { store, max_count, keys, _phantom: PhantomData };
+        persisted.update_metrics();
+        Ok(persisted)
+    }
+
+    fn key(height: BlockHeight, id: &[u8]) -> Vec<u8> {
+        let mut key = height.to_be_bytes().to_vec();
+        key.extend_from_slice(id);
+        key
+    }
+
+    fn update_metrics(&self) {
+        metrics::PERSISTED_ORPHANS.with_label_values(&[T::KIND]).set(self.keys.len() as i64);
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Saves the orphan. If the limit is reached, the orphan with the highest
+    /// height is dropped, which may be the new orphan itself.
+    pub fn save(&mut self, orphan: &T) -> std::io::Result<()> {
+        let key = Self::key(orphan.height(), &orphan.id_bytes());
+        if self.keys.contains(&key) || self.max_count == 0 {
+            return Ok(());
+        }
+        let mut store_update = self.store.store_update();
+        if self.keys.len() >= self.max_count {
+            let highest = self.keys.last().unwrap();
+            if &key > highest {
+                return Ok(());
+            }
+            store_update.delete(T::COL, highest);
+            self.keys.pop_last();
+        }
+        store_update.set_ser(T::COL, &key, orphan)?;
+        store_update.commit()?;
+        self.keys.insert(key);
+        self.update_metrics();
+        Ok(())
+    }
+
+    pub fn remove(&mut self, orphan: &T) -> std::io::Result<()> {
+        self.remove_by_id(orphan.height(), &orphan.id_bytes())
+    }
+
+    pub fn remove_by_id(&mut self, height: BlockHeight, id: &[u8]) -> std::io::Result<()> {
+        let key = Self::key(height, id);
+        if !self.keys.remove(&key) {
+            return Ok(());
+        }
+        let mut store_update = self.store.store_update();
+        store_update.delete(T::COL, &key);
+        store_update.commit()?;
+        self.update_metrics();
+        Ok(())
+    }
+
+    /// Removes orphans with height lower than `height`.
+    pub fn remove_below_height(&mut self, height: BlockHeight) -> std::io::Result<()> {
+        let keys_to_keep = self.keys.split_off(height.to_be_bytes().as_slice());
+        let keys_to_remove = std::mem::replace(&mut self.keys, keys_to_keep);
+        if keys_to_remove.is_empty() {
+            return Ok(());
+        }
+        let mut store_update = self.store.store_update();
+        for key in &keys_to_remove {
+            store_update.delete(T::COL, key);
+        }
+        store_update.commit()?;
+        self.update_metrics();
+        Ok(())
+    }
+
+    /// Removes all persisted orphans from the database and returns them in
+    /// the order of increasing height.
+    pub fn take_all(&mut self) -> std::io::Result<Vec<T>> {
+        let mut orphans = vec![];
+        let mut store_update = self.store.store_update();
+        for key in &self.keys {
+            if let Some(orphan) = self.store.get_ser::<T>(T::COL, key)? {
+                orphans.push(orphan);
+            }
+            store_update.delete(T::COL, key);
+        }
+        store_update.commit()?;
+        self.keys.clear();
+        self.update_metrics();
+        Ok(orphans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OrphanStateWitness, PersistedOrphans};
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::stateless_validation::state_witness::ChunkStateWitness;
+    use near_primitives::types::{BlockHeight, ShardId};
+    use near_store::test_utils::create_test_store;
+
+    fn orphan_witness(height: BlockHeight, shard_id: u64) -> OrphanStateWitness {
+        let witness =
+            ChunkStateWitness::new_dummy(height, ShardId::new(shard_id), CryptoHash::default());
+        OrphanStateWitness { witness, witness_size: 100 }
+    }
+
+    fn heights(orphans: &[OrphanStateWitness]) -> Vec<(BlockHeight, ShardId)> {
+        orphans
+            .iter()
+            .map(|orphan| {
+                let header = &orphan.witness.chunk_header;
+                (header.height_created(), header.shard_id())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_persisted_orphans() {
+        let store = create_test_store();
+        let mut persisted = PersistedOrphans::new(store.clone(), 3).unwrap();
+        for (height, shard_id) in [(12, 0), (10, 0), (11, 1), (10, 0)] {
+            persisted.save(&orphan_witness(height, shard_id)).unwrap();
+        }
+        assert_eq!(persisted.len(), 3);
+
+        // The limit is reached, so the orphan with the highest height is dropped.
+        persisted.save(&orphan_witness(13, 0)).unwrap();
+        persisted.save(&orphan_witness(11, 0)).unwrap();
+        persisted.remove(&orphan_witness(10, 0)).unwrap();
+        assert_eq!(persisted.len(), 2);
+
+        // Orphans survive reopening.
+        let mut persisted = PersistedOrphans::<OrphanStateWitness>::new(store.clone(), 3).unwrap();
+        assert_eq!(persisted.len(), 2);
+        persisted.save(&orphan_witness(9, 0)).unwrap();
+        persisted.remove_below_height(10).unwrap();
+        let orphans = persisted.take_all().unwrap();
+        assert_eq!(heights(&orphans), vec![(11, ShardId::new(0)), (11, ShardId::new(1))]);
+
+        let persisted = PersistedOrphans::<OrphanStateWitness>::new(store, 3).unwrap();
+        assert!(persisted.is_empty());
+    }
+}
@@ -12,17 +12,20 @@ use crate::test_utils::{
 };
 use crate::types::Tip;
 
-use near_chain_configs::{DEFAULT_GC_NUM_EPOCHS_TO_KEEP, GCConfig};
+use near_chain_configs::{DEFAULT_GC_NUM_EPOCHS_TO_KEEP, GCConfig, GCRetentionConfig};
 use near_epoch_manager::EpochManagerAdapter;
 use near_primitives::block::Block;
 use near_primitives::epoch_block_info::BlockInfo;
 use near_primitives::merkle::PartialMerkleTree;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::test_utils::{TestBlockBuilder, create_test_signer};
-use near_primitives::types::{BlockHeight, StateRoot};
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::{
+    AccountId, BlockHeight, RawStateChange, RawStateChangesWithTrieKey, StateChangeCause, StateRoot,
+};
 use near_primitives::validator_signer::ValidatorSigner;
 use near_store::test_utils::gen_changes;
-use near_store::{ShardTries, Trie, WrappedTrieChanges};
+use near_store::{DBCol, KeyForStateChanges, ShardTries, Trie, WrappedTrieChanges};
 
 // Build a chain of num_blocks on top of prev_block
 fn do_fork(
@@ -721,6 +724,76 @@ fn test_clear_old_data() {
     }
 }
 
+/// Builds a chain with state changes of two accounts in every block, and garbage collects it
+/// retaining the changes of one of them.
+fn check_clear_old_data_retention(num_epochs_to_keep: Option<u64>) {
+    let max_height = 14usize;
+    let mut chain = get_chain_with_epoch_length(Clock::real(), 1);
+    let epoch_manager = chain.epoch_manager.clone();
+    let genesis = chain.get_block_by_height(0).unwrap();
+    let signer = Arc::new(create_test_signer("test1"));
+    let alice: AccountId = "alice.near".parse().unwrap();
+    let bob: AccountId = "bob.near".parse().unwrap();
+    let mut prev_block = genesis;
+    let mut blocks = vec![prev_block.clone()];
+    for i in 1..=max_height {
+        add_block(
+            &mut chain,
+            epoch_manager.as_ref(),
+            &mut prev_block,
+            &mut blocks,
+            signer.clone(),
+            i as BlockHeight,
+        );
+        let mut store_update = chain.mut_chain_store().store().store_update();
+        for account_id in [&alice, &bob] {
+            let trie_key = TrieKey::Account { account_id: account_id.clone() };
+            let key = KeyForStateChanges::from_trie_key(blocks[i].hash(), &trie_key);
+            let changes = RawStateChangesWithTrieKey {
+                trie_key,
+                changes: vec![RawStateChange {
+                    cause: StateChangeCause::InitialState,
+                    data: Some(vec![i as u8]),
+                }],
+            };
+            store_update.set_ser(DBCol::StateChanges, key.as_ref(), &changes).unwrap();
+        }
+        store_update.commit().unwrap();
+    }
+
+    let gc_config = GCConfig {
+        gc_blocks_limit: 100,
+        retention: Some(GCRetentionConfig { accounts: vec![alice.clone()], num_epochs_to_keep }),
+        ..GCConfig::default()
+    };
+    chain.clear_data(&gc_config, None).unwrap();
+    // The retained data expires in the next garbage collection step.
+    chain.clear_data(&gc_config, None).unwrap();
+
+    let store = chain.chain_store().store();
+    for i in 1..=max_height {
+        let removed = chain.get_block(blocks[i].hash()).is_err();
+        assert_eq!(removed, i < max_height - DEFAULT_GC_NUM_EPOCHS_TO_KEEP as usize);
+        let has_changes = |account_id: &AccountId| {
+            let trie_key = TrieKey::Account { account_id: account_id.clone() };
+            let key = KeyForStateChanges::from_trie_key(blocks[i].hash(), &trie_key);
+            store.exists(DBCol::StateChanges, key.as_ref()).unwrap()
+        };
+        assert_eq!(has_changes(&alice), !removed || num_epochs_to_keep.is_none());
+        assert_eq!(has_changes(&bob), !removed);
+    }
+}
+
+#[test]
+fn test_clear_old_data_retention_forever() {
+    check_clear_old_data_retention(None);
+}
+
+#[test]
+fn test_clear_old_data_retention_expired() {
+    check_clear_old_data_retention(Some(DEFAULT_GC_NUM_EPOCHS_TO_KEEP));
+}
+
 // Adds block to the chain at given height after prev_block.
 fn add_block(
     chain: &mut Chain,
@@ -807,7 +880,11 @@ fn test_clear_old_data_fixed_height() {
     let mut store_update = chain.mut_chain_store().store_update();
     assert!(
         store_update
-            .clear_block_data(epoch_manager.as_ref(), *blocks[5].hash(), GCMode::Canonical(trie))
+            .clear_block_data(
+                epoch_manager.as_ref(),
+                *blocks[5].hash(),
+                GCMode::Canonical(trie, None)
+            )
             .is_ok()
     );
     store_update.commit().unwrap();
@@ -0,0 +1,178 @@
+//! Recorder of block, chunk, state witness and endorsement lifecycle events,
+//! exported in the Chrome trace event format to visualise why a particular
+//! height was slow.
+//!
+//! Events are kept for the same rolling window of heights as in
+//! `BlocksDelayTracker`. The trace is served by the `/debug/api/timeline`
+//! endpoint and can be opened directly in <https://ui.perfetto.dev>.
+
+use near_async::time::{Clock, Utc};
+use near_primitives::types::{BlockHeight, ShardId};
+use near_primitives::views::{ChromeTraceEventView, ChromeTraceView};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::sync::Arc;
+
+/// Upper bound on the number of kept events, in case the head does not move.
+const MAX_TIMELINE_EVENTS: usize = 100_000;
+
+/// All events belong to a single process in the trace. Block-level events are
+/// shown on the first thread and each shard gets a thread of its own.
+const TRACE_PID: u64 = 1;
+const BLOCKS_TID: u64 = 0;
+
+#[derive(Debug, Clone)]
+pub struct TimelineEvent {
+    pub name: &'static str,
+    pub category: &'static str,
+    pub height: BlockHeight,
+    /// Shard the event belongs to, or None for block-level events.
+    pub shard_id: Option<ShardId>,
+    pub start: Utc,
+    /// End of the event; None for instant events.
+    pub end: Option<Utc>,
+    pub args: Vec<(&'static str, String)>,
+}
+
+/// Cheaply cloneable handle, shared by all components which record events.
+#[derive(Clone)]
+pub struct TimelineRecorder {
+    clock: Clock,
+    events: Arc<Mutex<VecDeque<TimelineEvent>>>,
+}
+
+impl TimelineRecorder {
+    pub fn new(clock: Clock) -> Self {
+        Self { clock, events: Arc::new(Mutex::new(VecDeque::new())) }
+    }
+
+    pub fn record(&self, event: TimelineEvent) {
+        let mut events = self.events.lock();
+        if events.len() >= MAX_TIMELINE_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Records an event which happened now.
+    pub fn record_instant(
+        &self,
+        name: &'static str,
+        category: &'static str,
+        height: BlockHeight,
+        shard_id: Option<ShardId>,
+        args: Vec<(&'static str, String)>,
+    ) {
+        self.record(TimelineEvent {
+            name,
+            category,
+            height,
+            shard_id,
+            start: self.clock.now_utc(),
+            end: None,
+            args,
+        });
+    }
+
+    /// Drops events for heights below `cutoff_height`.
+    pub fn prune(&self, cutoff_height: BlockHeight) {
+        self.events.lock().retain(|event| event.height >= cutoff_height);
+    }
+
+    pub fn to_chrome_trace(&self) -> ChromeTraceView {
+        let events = self.events.lock();
+        let mut shard_ids = BTreeSet::new();
+        let mut trace_events = vec![];
+        for event in events.iter() {
+            let tid = match event.shard_id {
+                Some(shard_id) => {
+                    shard_ids.insert(shard_id);
+                    shard_tid(shard_id)
+                }
+                None => BLOCKS_TID,
+            };
+            let mut args: BTreeMap<String, String> =
+                event.args.iter().map(|(key, value)| (key.to_string(), value.clone())).collect();
+            args.insert("height".to_string(), event.height.to_string());
+            let dur = event.end.map(|end| (end - event.start).whole_microseconds().max(0) as u64);
+            trace_events.push(ChromeTraceEventView {
+                name: event.name.to_string(),
+                cat: event.category.to_string(),
+                ph: if dur.is_some() { "X" } else { "i" }.to_string(),
+                ts: unix_micros(event.start),
+                dur,
+                pid: TRACE_PID,
+                tid,
+                args,
+            });
+        }
+        let thread_names = std::iter::once((BLOCKS_TID, "blocks".to_string())).chain(
+            shard_ids
+                .into_iter()
+                .map(|shard_id| (shard_tid(shard_id), format!("shard {shard_id}"))),
+        );
+        for (tid, name) in thread_names {
+            trace_events.push(ChromeTraceEventView {
+                name: "thread_name".to_string(),
+                cat: "__metadata".to_string(),
+                ph: "M".to_string(),
+                ts: 0,
+                dur: None,
+                pid: TRACE_PID,
+                tid,
+                args: BTreeMap::from([("name".to_string(), name)]),
+            });
+        }
+        ChromeTraceView { trace_events, display_time_unit: "ms".to_string() }
+    }
+}
+
+fn shard_tid(shard_id: ShardId) -> u64 {
+    let shard_id: u64 = shard_id.into();
+    shard_id + 1
+}
+
+fn unix_micros(time: Utc) -> u64 {
+    (time.unix_timestamp_nanos() / 1000).max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TimelineEvent, TimelineRecorder};
+    use near_async::time::{Duration, FakeClock, Utc};
+    use near_primitives::types::ShardId;
+
+    #[test]
+    fn test_chrome_trace_export() {
+        let clock = FakeClock::new(Utc::UNIX_EPOCH + Duration::seconds(1));
+        let recorder = TimelineRecorder::new(clock.clock());
+        let start = clock.now_utc();
+        recorder.record(TimelineEvent {
+            name: "block processing",
+            category: "block",
+            height: 10,
+            shard_id: None,
+            start,
+            end: Some(start + Duration::milliseconds(250)),
+            args: vec![],
+        });
+        clock.advance(Duration::milliseconds(100));
+        recorder.record_instant("witness produced", "witness", 11, Some(ShardId::new(3)), vec![]);
+
+        let trace = recorder.to_chrome_trace();
+        let block = &trace.trace_events[0];
+        assert_eq!((block.ph.as_str(), block.ts, block.dur), ("X", 1_000_000, Some(250_000)));
+        assert_eq!(block.args["height"], "10");
+        let witness = &trace.trace_events[1];
+        assert_eq!((witness.ph.as_str(), witness.ts, witness.tid), ("i", 1_100_000, 4));
+        let thread_names: Vec<_> = trace.trace_events[2..]
+            .iter()
+            .map(|event| (event.tid, event.args["name"].as_str()))
+            .collect();
+        assert_eq!(thread_names, vec![(0, "blocks"), (4, "shard 3")]);
+
+        recorder.prune(11);
+        let trace = recorder.to_chrome_trace();
+        assert_eq!(trace.trace_events.iter().filter(|event| event.ph != "M").count(), 1);
+    }
+}
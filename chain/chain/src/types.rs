@@ -5,6 +5,7 @@ use near_chain_configs::MutableConfigValue;
 use near_chain_configs::ProtocolConfig;
 use near_chain_configs::ReshardingConfig;
 use near_chain_primitives::Error;
+use near_crypto::PublicKey;
 pub use near_epoch_manager::EpochManagerAdapter;
 use near_parameters::RuntimeConfig;
 use near_pool::types::TransactionGroupIterator;
@@ -29,8 +30,8 @@ use near_primitives::transaction::ValidatedTransaction;
 use near_primitives::transaction::{ExecutionOutcomeWithId, SignedTransaction};
 use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
 use near_primitives::types::{
-    Balance, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash, NumBlocks, ShardId,
-    StateRoot, StateRootNode,
+    AccountId, Balance, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash, NumBlocks,
+    ShardId, StateRoot, StateRootNode,
 };
 use near_primitives::utils::to_timestamp;
 use near_primitives::version::PROD_GENESIS_PROTOCOL_VERSION;
@@ -203,6 +204,8 @@ pub struct ChainConfig {
     pub background_migration_threads: usize,
     /// The resharding configuration.
     pub resharding_config: MutableConfigValue<ReshardingConfig>,
+    /// Whether to save orphan blocks to the database, so that they survive a restart.
+    pub persist_orphans: bool,
 }
 
 impl ChainConfig {
@@ -214,6 +217,7 @@ impl ChainConfig {
                 ReshardingConfig::default(),
                 "resharding_config",
             ),
+            persist_orphans: false,
         }
     }
 }
@@ -336,6 +340,9 @@ pub struct PreparedTransactions {
     pub transactions: Vec<ValidatedTransaction>,
     /// Describes which limit was hit when preparing the transactions.
     pub limited_by: Option<PrepareTransactionsLimit>,
+    /// Transactions which were taken from the pool but not included, with the reason. They are
+    /// not returned to the pool.
+    pub rejected: Vec<(CryptoHash, PrepareTransactionsRejection)>,
 }
 
 /// Chunk producer prepares transactions from the transaction pool
@@ -350,6 +357,17 @@ pub enum PrepareTransactionsLimit {
     StorageProofSize,
 }
 
+/// Reason why a transaction taken from the pool was not included into the chunk.
+#[derive(Debug, Clone, PartialEq, Eq, strum::AsRefStr)]
+pub enum PrepareTransactionsRejection {
+    /// The receiver's shard doesn't accept transactions due to congestion.
+    Congestion,
+    /// The transaction failed the `chain_validate` check, e.g. it has expired.
+    InvalidForChain,
+    /// The transaction failed verification against the state.
+    InvalidTx(InvalidTxError),
+}
+
 pub struct PrepareTransactionsBlockContext {
     pub next_gas_price: Balance,
     pub height: BlockHeight,
@@ -441,6 +459,19 @@ pub trait RuntimeAdapter: Send + Sync {
         time_limit: Option<Duration>,
     ) -> Result<PreparedTransactions, Error>;
 
+    /// Starts prefetching, in the background, the state that the next chunk of
+    /// the shard is likely to access: accounts and access keys of the given
+    /// transaction signers and receivers of the given receipts. Does nothing if
+    /// prefetching is disabled in the store config or memtries are used. Errors
+    /// are only logged, as prefetching is merely an optimization.
+    fn prefetch_next_chunk_state(
+        &self,
+        shard_uid: ShardUId,
+        state_root: StateRoot,
+        signers: &[(AccountId, PublicKey)],
+        receipts: &[Receipt],
+    );
+
     /// Returns true if the shard layout will change in the next epoch
     /// Current epoch is the epoch of the block after `parent_hash`
     fn will_shard_layout_change_next_epoch(&self, parent_hash: &CryptoHash) -> Result<bool, Error>;
@@ -285,7 +285,8 @@ mod tests {
 
     fn create_chunk_header(height: u64, shard_id: ShardId) -> ShardChunkHeader {
         let signer =
-            InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+            InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519)
+                .unwrap();
         ShardChunkHeader::V2(ShardChunkHeaderV2::new(
             CryptoHash::default(),
             CryptoHash::default(),
@@ -1,5 +1,6 @@
 use actix::Message;
 use itertools::Itertools;
+use near_crypto::PublicKey;
 use near_pool::types::TransactionGroupIterator;
 use near_pool::{InsertTransactionResult, PoolIteratorWrapper, TransactionPool};
 use near_primitives::shard_layout::{ShardLayout, ShardUId};
@@ -53,6 +54,24 @@ impl ShardedTransactionPool {
         self.tx_pools.get_mut(&shard_uid).map(|pool| pool.pool_iterator())
     }
 
+    /// Returns the signer and the access key of each of up to `limit` transaction groups in the
+    /// pool of the given shard, see `TransactionPool::peek_groups`. Only the keys are copied, so
+    /// that the pool lock is held briefly even if the transactions are large.
+    pub fn peek_group_keys(
+        &self,
+        shard_uid: ShardUId,
+        limit: usize,
+    ) -> Vec<(AccountId, PublicKey)> {
+        let Some(pool) = self.tx_pools.get(&shard_uid) else {
+            return vec![];
+        };
+        pool.peek_groups(limit)
+            .map(|validated_tx| {
+                (validated_tx.signer_id().clone(), validated_tx.public_key().clone())
+            })
+            .collect()
+    }
+
     /// Tries to insert the transaction into the pool for a given shard.
     pub fn insert_transaction(
         &mut self,
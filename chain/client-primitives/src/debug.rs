@@ -2,16 +2,17 @@
 //! without backwards compatibility of JSON encoding.
 use crate::types::StatusError;
 use near_primitives::congestion_info::CongestionInfo;
+use near_primitives::serialize::dec_format;
 use near_primitives::types::{EpochId, ShardId};
 use near_primitives::views::{
-    CatchupStatusView, ChainProcessingInfo, EpochValidatorInfo, RequestedStatePartsView,
-    SyncStatusView,
+    CatchupStatusView, ChainProcessingInfo, ChromeTraceView, EpochValidatorInfo,
+    RequestedStatePartsView, SyncStatusView,
 };
 use near_primitives::{
     block_header::ApprovalInner,
     hash::CryptoHash,
     sharding::ChunkHash,
-    types::{AccountId, BlockHeight},
+    types::{AccountId, Balance, BlockHeight},
     views::ValidatorInfo,
 };
 use near_time::Utc;
@@ -102,6 +103,32 @@ pub struct ApprovalHistoryEntry {
     pub expected_delay_millis: u64,
 }
 
+// Information about an approval received by this node.
+// Used for debug purposes only.
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ApprovalArrival {
+    pub account_id: AccountId,
+    // Endorsement of the parent block or skip from the given height.
+    pub approval: ApprovalInner,
+    #[serde(with = "dec_format")]
+    pub stake_this_epoch: Balance,
+    #[serde(with = "dec_format")]
+    pub stake_next_epoch: Balance,
+    pub arrival_time: Utc,
+    // Time between the first approval for the same target height and this one.
+    pub latency_millis: u64,
+}
+
+// Approvals received by this node for a single target height, in the order of arrival.
+// Used for debug purposes only.
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct ApprovalsReceivedAtHeight {
+    pub target_height: BlockHeight,
+    pub arrivals: Vec<ApprovalArrival>,
+    // Time when the approvals crossed the doomslug threshold, if they did.
+    pub threshold_reached_at: Option<Utc>,
+}
+
 // Information about chunk produced by this node.
 // For debug purposes only.
 #[derive(serde::Serialize, Debug, Default, Clone)]
@@ -267,6 +294,10 @@ pub enum DebugStatus {
     ChainProcessingStatus,
     // The state parts already requested.
     RequestedStateParts,
+    // Recent block, chunk, state witness and endorsement events in the Chrome trace format.
+    Timeline,
+    // Approvals received for the recent target heights.
+    ApprovalHistory,
 }
 
 impl actix::Message for DebugStatus {
@@ -288,4 +319,8 @@ pub enum DebugStatusResponse {
     ChainProcessingStatus(ChainProcessingInfo),
     // The state parts already requested.
     RequestedStateParts(Vec<RequestedStatePartsView>),
+    // Recent block, chunk, state witness and endorsement events in the Chrome trace format.
+    Timeline(ChromeTraceView),
+    // Approvals received for the recent target heights, in ascending order of height.
+    ApprovalHistory(Vec<ApprovalsReceivedAtHeight>),
 }
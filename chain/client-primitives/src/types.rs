@@ -1,5 +1,6 @@
 use actix::Message;
 use near_chain_configs::{ClientConfig, ProtocolConfigView};
+use near_primitives::epoch_sync::CompressedEpochSyncProof;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
@@ -13,11 +14,12 @@ use near_primitives::views::{
     BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView, GasPriceView,
     LightClientBlockLiteView, LightClientBlockView, MaintenanceWindowsView, QueryRequest,
     QueryResponse, ReceiptView, SplitStorageInfoView, StateChangesKindsView,
-    StateChangesRequestView, StateChangesView, StateSyncStatusView, SyncStatusView, TxStatusView,
+    StateChangesRequestView, StateChangesView, StateSyncHostStatsView, StateSyncStatusView,
+    SyncStatusView, TxStatusView, ValidatorDutyView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
-use near_time::Duration;
-use std::collections::HashMap;
+use near_time::{Duration, Utc};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tracing::debug_span;
 
@@ -94,6 +96,7 @@ pub struct StateSyncStatus {
     pub sync_status: HashMap<ShardId, ShardSyncStatus>,
     pub download_tasks: Vec<String>,
     pub computation_tasks: Vec<String>,
+    pub host_stats: Vec<StateSyncHostStatsView>,
 }
 
 impl StateSyncStatus {
@@ -103,6 +106,7 @@ impl StateSyncStatus {
             sync_status: HashMap::new(),
             download_tasks: Vec::new(),
             computation_tasks: Vec::new(),
+            host_stats: Vec::new(),
         }
     }
 }
@@ -220,6 +224,7 @@ impl From<SyncStatus> for SyncStatusView {
                         .collect(),
                     download_tasks: state_sync_status.download_tasks,
                     computation_tasks: state_sync_status.computation_tasks,
+                    host_stats: state_sync_status.host_stats,
                 })
             }
             SyncStatus::StateSyncDone => SyncStatusView::StateSyncDone,
@@ -992,6 +997,92 @@ impl From<near_chain_primitives::Error> for GetMaintenanceWindowsError {
     }
 }
 
+/// Lists duties of a validator in the given inclusive height range, with the
+/// outcome of each duty and the likely reason of missed ones.
+///
+/// Handled by the view client. Reasons are only given if `local_records` are
+/// set, see `GetValidatorDutyRecords`.
+#[derive(Debug)]
+pub struct GetValidatorDuties {
+    pub account_id: AccountId,
+    pub start_height: BlockHeight,
+    pub end_height: BlockHeight,
+    pub local_records: Option<ValidatorDutyRecords>,
+}
+
+impl Message for GetValidatorDuties {
+    type Result = Result<Vec<ValidatorDutyView>, GetValidatorDutiesError>;
+}
+
+/// Takes a snapshot of what the client observed locally in the given inclusive
+/// height range. Returns None unless `account_id` is the validator running this
+/// node.
+#[derive(Debug)]
+pub struct GetValidatorDutyRecords {
+    pub account_id: AccountId,
+    pub start_height: BlockHeight,
+    pub end_height: BlockHeight,
+}
+
+impl Message for GetValidatorDutyRecords {
+    type Result = Result<Option<ValidatorDutyRecords>, GetValidatorDutiesError>;
+}
+
+/// Local observations of the validator running this node which help to explain
+/// why a duty was missed.
+#[derive(Debug, Default)]
+pub struct ValidatorDutyRecords {
+    /// Start and end of the periods in which the node was syncing. The end of
+    /// the last period is None if the node is still syncing.
+    pub sync_periods: Vec<(Utc, Option<Utc>)>,
+    /// Block production attempts, by height.
+    pub block_production: HashMap<BlockHeight, BlockProductionRecord>,
+    /// Errors of chunk production, by height created and shard.
+    pub chunk_production_errors: HashMap<(BlockHeight, ShardId), String>,
+    /// Chunks which were produced, by height created and shard.
+    pub chunks_produced: HashSet<(BlockHeight, ShardId)>,
+    /// Time when the state witness was first received, by height created and shard.
+    pub witness_received: HashMap<(BlockHeight, ShardId), Utc>,
+    /// Blocks which waited for missing chunks.
+    pub blocks_with_missing_chunks: HashSet<CryptoHash>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BlockProductionRecord {
+    pub enough_approvals: bool,
+    pub produced: bool,
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetValidatorDutiesError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("Invalid height range: {0}")]
+    InvalidRange(String),
+    #[error("Block is not available, it may have been garbage collected: {0}")]
+    UnknownBlock(String),
+    #[error(
+        "It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}"
+    )]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetValidatorDutiesError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            near_chain_primitives::Error::DBNotFoundErr(error) => Self::UnknownBlock(error),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
+impl From<near_primitives::errors::EpochError> for GetValidatorDutiesError {
+    fn from(error: near_primitives::errors::EpochError) -> Self {
+        Self::from(near_chain_primitives::Error::from(error))
+    }
+}
+
 #[derive(Debug)]
 pub struct GetClientConfig {}
 
@@ -1052,6 +1143,37 @@ impl From<near_chain_primitives::Error> for GetSplitStorageInfoError {
     }
 }
 
+/// Gets the compressed epoch sync proof for a recent epoch, the same proof
+/// which is sent to peers that bootstrap with epoch sync.
+#[derive(Debug)]
+pub struct GetEpochSyncProof {}
+
+impl Message for GetEpochSyncProof {
+    type Result = Result<CompressedEpochSyncProof, GetEpochSyncProofError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetEpochSyncProofError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("Epoch sync proof is not available yet: {0}")]
+    NotAvailable(String),
+    #[error(
+        "It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}"
+    )]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetEpochSyncProofError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            near_chain_primitives::Error::DBNotFoundErr(error) => Self::NotAvailable(error),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
 impl From<std::io::Error> for GetSplitStorageInfoError {
     fn from(error: std::io::Error) -> Self {
         Self::IOError(error.to_string())
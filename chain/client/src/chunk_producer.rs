@@ -1,6 +1,7 @@
 use crate::debug::PRODUCTION_TIMES_CACHE_SIZE;
 use crate::metrics;
 use itertools::Itertools;
+use near_async::futures::{AsyncComputationSpawner, AsyncComputationSpawnerExt};
 use near_async::time::{Clock, Duration, Instant};
 use near_chain::types::{
     PrepareTransactionsChunkContext, PreparedTransactions, RuntimeAdapter, RuntimeStorageConfig,
@@ -45,6 +46,10 @@ pub enum AdvProduceChunksMode {
     ProduceWithoutTxValidityCheck,
 }
 
+/// Maximum number of transaction groups from the pool for which state is
+/// prefetched before producing a chunk.
+const MAX_PREFETCHED_TRANSACTION_GROUPS: usize = 2000;
+
 pub struct ProduceChunkResult {
     pub chunk: ShardChunkWithEncoding,
     pub encoded_chunk_parts_paths: Vec<MerklePath>,
@@ -69,6 +74,8 @@ pub struct ChunkProducer {
     chain: ChainStoreAdapter,
     epoch_manager: Arc<dyn EpochManagerAdapter>,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
+    /// Runs the prefetching of the state for the next chunk in the background.
+    async_computation_spawner: Arc<dyn AsyncComputationSpawner>,
     // TODO: put mutex on individual shards instead of the complete pool
     pub sharded_tx_pool: Arc<Mutex<ShardedTransactionPool>>,
     /// A ReedSolomon instance to encode shard chunks.
@@ -84,6 +91,7 @@ impl ChunkProducer {
         chain_store: &ChainStoreAdapter,
         epoch_manager: Arc<dyn EpochManagerAdapter>,
         runtime_adapter: Arc<dyn RuntimeAdapter>,
+        async_computation_spawner: Arc<dyn AsyncComputationSpawner>,
         rng_seed: RngSeed,
         transaction_pool_size_limit: Option<u64>,
     ) -> Self {
@@ -102,6 +110,7 @@ impl ChunkProducer {
             chain: chain_store.clone(),
             epoch_manager,
             runtime_adapter,
+            async_computation_spawner,
             sharded_tx_pool: Arc::new(Mutex::new(ShardedTransactionPool::new(
                 rng_seed,
                 transaction_pool_size_limit,
@@ -243,13 +252,16 @@ impl ChunkProducer {
             .chain
             .get_chunk_extra(&prev_block_hash, &shard_uid)
             .map_err(|err| Error::ChunkProducer(format!("No chunk extra available: {}", err)))?;
+        self.prefetch_next_chunk_state(prev_block, epoch_id, shard_uid, chunk_extra.as_ref());
 
         let prepared_transactions = {
             #[cfg(feature = "test_features")]
             match self.adv_produce_chunks {
-                Some(AdvProduceChunksMode::ProduceWithoutTx) => {
-                    PreparedTransactions { transactions: Vec::new(), limited_by: None }
-                }
+                Some(AdvProduceChunksMode::ProduceWithoutTx) => PreparedTransactions {
+                    transactions: Vec::new(),
+                    limited_by: None,
+                    rejected: Vec::new(),
+                },
                 _ => self.prepare_transactions(
                     shard_uid,
                     prev_block,
@@ -350,6 +362,64 @@ impl ChunkProducer {
         }))
     }
 
+    /// Starts prefetching the state which the next chunk is likely to access,
+    /// so that transaction preparation and chunk application hit warm caches.
+    /// Transactions are taken from the pool, receipts from the outgoing
+    /// receipts of the previous block which are addressed to this shard.
+    ///
+    /// Only the transaction pool is read here, before the transactions are
+    /// taken from it. The rest is done in the background, so that it doesn't
+    /// delay chunk production. Errors are only logged, as prefetching is merely
+    /// an optimization.
+    fn prefetch_next_chunk_state(
+        &self,
+        prev_block: &Block,
+        epoch_id: &EpochId,
+        shard_uid: ShardUId,
+        chunk_extra: &ChunkExtra,
+    ) {
+        let signers = self
+            .sharded_tx_pool
+            .lock()
+            .peek_group_keys(shard_uid, MAX_PREFETCHED_TRANSACTION_GROUPS);
+        let chain = self.chain.clone();
+        let epoch_manager = self.epoch_manager.clone();
+        let runtime_adapter = self.runtime_adapter.clone();
+        let prev_block_hash = *prev_block.hash();
+        let shard_ids = prev_block.chunks().iter_raw().map(|chunk| chunk.shard_id()).collect_vec();
+        let epoch_id = *epoch_id;
+        let state_root = *chunk_extra.state_root();
+        self.async_computation_spawner.spawn("prefetch_next_chunk_state", move || {
+            let _timer = metrics::PREFETCH_NEXT_CHUNK_STATE_TIME
+                .with_label_values(&[&shard_uid.shard_id().to_string()])
+                .start_timer();
+            let shard_layout = match epoch_manager.get_shard_layout(&epoch_id) {
+                Ok(shard_layout) => shard_layout,
+                Err(err) => {
+                    debug!(target: "client", ?shard_uid, ?err, "failed to prefetch next chunk state");
+                    return;
+                }
+            };
+            let mut receipts = vec![];
+            for shard_id in shard_ids {
+                let Ok(outgoing_receipts) = chain.get_outgoing_receipts(&prev_block_hash, shard_id)
+                else {
+                    continue;
+                };
+                receipts.extend(
+                    outgoing_receipts
+                        .iter()
+                        .filter(|receipt| {
+                            shard_layout.account_id_to_shard_id(receipt.receiver_id())
+                                == shard_uid.shard_id()
+                        })
+                        .cloned(),
+                );
+            }
+            runtime_adapter.prefetch_next_chunk_state(shard_uid, state_root, &signers, &receipts);
+        });
+    }
+
     /// Prepares an ordered list of valid transactions from the pool up the limits.
     fn prepare_transactions(
         &self,
@@ -377,7 +447,11 @@ impl ChunkProducer {
                 self.chunk_transactions_time_limit.get(),
             )?
         } else {
-            PreparedTransactions { transactions: Vec::new(), limited_by: None }
+            PreparedTransactions {
+                transactions: Vec::new(),
+                limited_by: None,
+                rejected: Vec::new(),
+            }
         };
         // Reintroduce valid transactions back to the pool. They will be removed when the chunk is
         // included into the block.
@@ -16,6 +16,7 @@ use crate::sync::handler::SyncHandler;
 use crate::sync::header::HeaderSync;
 use crate::sync::state::chain_requests::ChainSenderForStateSync;
 use crate::sync::state::{StateSync, StateSyncResult};
+use crate::validator_duties::DutyTracker;
 use crate::{ProduceChunkResult, metrics};
 use itertools::Itertools;
 use near_async::futures::{AsyncComputationSpawner, FutureSpawner};
@@ -33,7 +34,7 @@ use near_chain::test_utils::format_hash;
 use near_chain::types::{ChainConfig, LatestKnown, RuntimeAdapter};
 use near_chain::{
     BlockProcessingArtifact, BlockStatus, Chain, ChainGenesis, ChainStoreAccess, Doomslug,
-    DoomslugThresholdMode, Provenance,
+    DoomslugThresholdMode, OrphanStateWitness, PersistableOrphan, PersistedOrphans, Provenance,
 };
 use near_chain_configs::{ClientConfig, MutableValidatorSigner, UpdatableClientConfig};
 use near_chunks::adapter::ShardsManagerRequestFromClient;
@@ -176,6 +177,10 @@ pub struct Client {
     last_optimistic_block_produced: Option<OptimisticBlock>,
     /// Cached precomputed set of the chunk producers for current and next epochs.
     chunk_producer_accounts_cache: Option<(EpochId, Arc<Vec<AccountId>>)>,
+    /// Local observations used to explain missed validator duties.
+    pub(crate) duty_tracker: DutyTracker,
+    /// Copy of recent orphan state witnesses in the database, if `persist_orphans` is enabled.
+    pub(crate) persisted_orphan_witnesses: Option<PersistedOrphans<OrphanStateWitness>>,
 }
 
 impl AsRef<Client> for Client {
@@ -234,6 +239,7 @@ impl Client {
             save_trie_changes: config.save_trie_changes,
             background_migration_threads: config.client_background_migration_threads,
             resharding_config: config.resharding_config.clone(),
+            persist_orphans: config.persist_orphans,
         };
         let chain = Chain::new(
             clock.clone(),
@@ -280,6 +286,7 @@ impl Client {
             epoch_manager.clone(),
             runtime_adapter.clone(),
             network_adapter.clone().into_sender(),
+            network_adapter.clone().into_sender(),
             config.state_sync_external_timeout,
             config.state_sync_p2p_timeout,
             config.state_sync_retry_backoff,
@@ -305,6 +312,7 @@ impl Client {
         let chunk_endorsement_tracker = Arc::new(ChunkEndorsementTracker::new(
             epoch_manager.clone(),
             chain.chain_store().store(),
+            chain.blocks_delay_tracker.timeline.clone(),
         ));
         let chunk_producer = ChunkProducer::new(
             clock.clone(),
@@ -312,6 +320,7 @@ impl Client {
             &chain.chain_store(),
             epoch_manager.clone(),
             runtime_adapter.clone(),
+            async_computation_spawner.clone(),
             rng_seed,
             config.transaction_pool_size_limit,
         );
@@ -323,6 +332,13 @@ impl Client {
             async_computation_spawner,
         );
         let chunk_distribution_network = ChunkDistributionNetwork::from_config(&config);
+        let persisted_orphan_witnesses = if config.persist_orphans {
+            let store = chain.chain_store().store();
+            let max_count = config.orphan_state_witness_pool_size;
+            Some(PersistedOrphans::new(store, max_count).map_err(near_chain::Error::from)?)
+        } else {
+            None
+        };
         Ok(Self {
             #[cfg(feature = "test_features")]
             adv_produce_blocks: None,
@@ -358,6 +374,8 @@ impl Client {
             ),
             last_time_head_progress_made: clock.now(),
             block_production_info: BlockProductionTracker::new(),
+            duty_tracker: DutyTracker::new(clock.clone()),
+            persisted_orphan_witnesses,
             tier1_accounts_cache: None,
             resharding_sender,
             chunk_producer,
@@ -822,6 +840,13 @@ impl Client {
         let mut chunk_endorsements = vec![vec![]; chunk_headers.len()];
 
         // Add debug information about the block production (and info on when did the chunks arrive).
+        self.chain.blocks_delay_tracker.timeline.record_instant(
+            "block produced",
+            "block",
+            height,
+            None,
+            vec![("prev_hash", prev_hash.to_string())],
+        );
         self.block_production_info.record_block_production(
             height,
             BlockProductionTracker::construct_chunk_collection_info(
@@ -1181,6 +1206,52 @@ impl Client {
         result
     }
 
+    /// Requeues orphan blocks and orphan state witnesses persisted before the
+    /// node was restarted. Those which are still orphans are persisted again.
+    pub fn requeue_persisted_orphans(&mut self, signer: &Option<Arc<ValidatorSigner>>) {
+        let blocks = self.chain.take_persisted_orphans();
+        near_chain::metrics::PERSISTED_ORPHANS_RESTORED
+            .with_label_values(&[<Block as PersistableOrphan>::KIND])
+            .inc_by(blocks.len() as u64);
+        for block in blocks {
+            let block_hash = *block.hash();
+            let res = self.start_process_block(
+                block.into(),
+                Provenance::NONE,
+                Some(self.myself_sender.apply_chunks_done.clone()),
+                signer,
+            );
+            if let Err(err) = res {
+                debug!(target: "client", ?block_hash, ?err, "Persisted orphan block declined");
+            }
+        }
+
+        let Some(persisted_orphan_witnesses) = &mut self.persisted_orphan_witnesses else {
+            return;
+        };
+        let witnesses = match persisted_orphan_witnesses.take_all() {
+            Ok(witnesses) => witnesses,
+            Err(err) => {
+                warn!(target: "client", ?err, "Failed to load persisted orphan state witnesses");
+                return;
+            }
+        };
+        near_chain::metrics::PERSISTED_ORPHANS_RESTORED
+            .with_label_values(&[OrphanStateWitness::KIND])
+            .inc_by(witnesses.len() as u64);
+        for OrphanStateWitness { witness, witness_size } in witnesses {
+            let res = self.process_chunk_state_witness(
+                witness,
+                witness_size as usize,
+                None,
+                signer.clone(),
+            );
+            if let Err(err) = res {
+                debug!(target: "client", ?err, "Persisted orphan state witness declined");
+            }
+        }
+    }
+
     /// Check if there are any blocks that has finished applying chunks, run post processing on these
     /// blocks.
     pub fn postprocess_ready_blocks(
@@ -1734,6 +1805,11 @@ impl Client {
                 Ok(None) => return,
                 Err(err) => {
                     error!(target: "client", ?err, "Error producing chunk");
+                    self.duty_tracker.record_chunk_production_error(
+                        next_height,
+                        shard_id,
+                        err.to_string(),
+                    );
                     return;
                 }
             };
@@ -2123,6 +2199,7 @@ impl Client {
                             self.epoch_manager.clone(),
                             self.runtime_adapter.clone(),
                             self.network_adapter.clone().into_sender(),
+                            self.network_adapter.clone().into_sender(),
                             self.config.state_sync_external_timeout,
                             self.config.state_sync_p2p_timeout,
                             self.config.state_sync_retry_backoff,
@@ -2138,6 +2215,7 @@ impl Client {
                             sync_status: HashMap::new(),
                             download_tasks: Vec::new(),
                             computation_tasks: Vec::new(),
+                            host_stats: Vec::new(),
                         },
                         catchup: BlocksCatchUpState::new(sync_hash, *epoch_id),
                     }
@@ -2341,6 +2419,7 @@ impl Client {
             block,
             tracked_shards,
             tier1_accounts,
+            chunk_validation_backlog: self.chunk_validator.validation_backlog(),
         }));
         Ok(())
     }
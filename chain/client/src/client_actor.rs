@@ -47,8 +47,9 @@ use near_chain_primitives::error::EpochErrorResultToChainError;
 use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::{ShardedTransactionPool, ShardsManagerResponse};
 use near_client_primitives::types::{
-    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, NetworkInfoResponse,
-    StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
+    Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, GetValidatorDutiesError,
+    GetValidatorDutyRecords, NetworkInfoResponse, StateSyncStatus, Status, StatusError,
+    StatusSyncInfo, SyncStatus, ValidatorDutyRecords,
 };
 use near_epoch_manager::EpochManagerAdapter;
 use near_epoch_manager::shard_tracker::ShardTracker;
@@ -894,6 +895,10 @@ impl ClientActorInner {
         // Start catchup job.
         self.catchup(ctx);
 
+        // Requeue orphans which were persisted before the restart.
+        let signer = self.client.validator_signer.get();
+        self.client.requeue_persisted_orphans(&signer);
+
         if let Err(err) = self.client.send_network_chain_info() {
             tracing::error!(target: "client", ?err, "Failed to update network chain info");
         }
@@ -1033,8 +1038,10 @@ impl ClientActorInner {
         signer: &Option<Arc<ValidatorSigner>>,
     ) -> Result<(), Error> {
         let _span = tracing::debug_span!(target: "client", "handle_block_production").entered();
+        let is_syncing = self.client.sync_handler.sync_status.is_syncing();
+        self.client.duty_tracker.record_sync_status(is_syncing);
         // If syncing, don't try to produce blocks.
-        if self.client.sync_handler.sync_status.is_syncing() {
+        if is_syncing {
             debug!(target:"client", sync_status=format!("{:#?}", self.client.sync_handler.sync_status), "Syncing - block production disabled");
             return Ok(());
         }
@@ -1175,6 +1182,10 @@ impl ClientActorInner {
                     self.client.update_client_config(updatable_client_config)
                 },
                 &|validator_signer| self.client.update_validator_signer(validator_signer),
+                &|network_config| {
+                    self.network_adapter
+                        .send(PeerManagerMessageRequest::UpdateNetworkConfig(network_config))
+                },
             );
 
             if update_result.validator_signer_updated {
@@ -1942,6 +1953,16 @@ impl Handler<GetClientConfig> for ClientActorInner {
     }
 }
 
+impl Handler<GetValidatorDutyRecords> for ClientActorInner {
+    fn handle(
+        &mut self,
+        msg: GetValidatorDutyRecords,
+    ) -> Result<Option<ValidatorDutyRecords>, GetValidatorDutiesError> {
+        tracing::debug!(target: "client", ?msg);
+        self.client.get_validator_duty_records(&msg.account_id, msg.start_height, msg.end_height)
+    }
+}
+
 impl Handler<ChunkStateWitnessMessage> for ClientActorInner {
     #[perf]
     fn handle(&mut self, msg: ChunkStateWitnessMessage) {
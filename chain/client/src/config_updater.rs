@@ -1,5 +1,6 @@
 use near_chain_configs::UpdatableClientConfig;
 use near_dyn_configs::{UpdatableConfigLoaderError, UpdatableConfigs, UpdatableValidatorSigner};
+use near_network::config_json::UpdatableNetworkConfig;
 use near_primitives::validator_signer::ValidatorSigner;
 use std::sync::Arc;
 use tokio::sync::broadcast::Receiver;
@@ -34,6 +35,7 @@ impl ConfigUpdater {
         &mut self,
         update_client_config_fn: &dyn Fn(UpdatableClientConfig) -> bool,
         update_validator_signer_fn: &dyn Fn(Option<Arc<ValidatorSigner>>) -> bool,
+        update_network_config_fn: &dyn Fn(UpdatableNetworkConfig),
     ) -> ConfigUpdaterResult {
         let mut update_result = ConfigUpdaterResult::default();
         while let Ok(maybe_updatable_configs) = self.rx_config_update.try_recv() {
@@ -51,6 +53,17 @@ impl ConfigUpdater {
                             update_validator_signer_fn(validator_signer);
                         tracing::info!(target: "config", "Updated validator key");
                     }
+                    if let Some(network_config) = updatable_configs.network_config {
+                        match serde_json::from_value::<UpdatableNetworkConfig>(network_config) {
+                            Ok(network_config) => {
+                                update_network_config_fn(network_config);
+                                tracing::info!(target: "config", "Updated NetworkConfig");
+                            }
+                            Err(err) => {
+                                tracing::warn!(target: "config", ?err, "Failed to parse the updated NetworkConfig");
+                            }
+                        }
+                    }
                     self.updatable_configs_error = None;
                 }
                 Err(err) => {
@@ -77,6 +77,11 @@ impl BlockProductionTracker {
         self.0.get(&height).cloned().unwrap_or_default()
     }
 
+    /// Iterates over the tracked heights without updating the LRU order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&BlockHeight, &BlockProduction)> {
+        self.0.iter()
+    }
+
     /// Record approvals received so far for this block. Must be called before block is produced.
     pub(crate) fn record_approvals(
         &mut self,
@@ -199,6 +204,12 @@ impl Handler<DebugStatus> for ClientActorInner {
             DebugStatus::ChainProcessingStatus => Ok(DebugStatusResponse::ChainProcessingStatus(
                 self.client.chain.get_chain_processing_info(),
             )),
+            DebugStatus::Timeline => Ok(DebugStatusResponse::Timeline(
+                self.client.chain.blocks_delay_tracker.timeline.to_chrome_trace(),
+            )),
+            DebugStatus::ApprovalHistory => Ok(DebugStatusResponse::ApprovalHistory(
+                self.client.doomslug.get_received_approval_history(),
+            )),
         }
     }
 }
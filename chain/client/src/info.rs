@@ -753,6 +753,7 @@ pub fn display_sync_status(
             sync_status: shard_statuses,
             download_tasks,
             computation_tasks,
+            ..
         }) => {
             let mut res = format!("State {:?}", sync_hash);
             let mut shard_statuses: Vec<_> = shard_statuses.iter().collect();
@@ -421,6 +421,16 @@ pub(crate) static PRODUCE_CHUNK_TIME: LazyLock<HistogramVec> = LazyLock::new(||
     .unwrap()
 });
 
+pub(crate) static PREFETCH_NEXT_CHUNK_STATE_TIME: LazyLock<HistogramVec> = LazyLock::new(|| {
+    try_create_histogram_vec(
+        "near_prefetch_next_chunk_state_time",
+        "Time taken in the background to start prefetching the state for the next chunk before producing it",
+        &["shard_id"],
+        Some(exponential_buckets(0.0001, 2.0, 16).unwrap()),
+    )
+    .unwrap()
+});
+
 pub(crate) static VIEW_CLIENT_MESSAGE_TIME: LazyLock<HistogramVec> = LazyLock::new(|| {
     try_create_histogram_vec(
         "near_view_client_messages_processing_time",
@@ -20,6 +20,7 @@ use near_performance_metrics_macros::perf;
 use near_pool::InsertTransactionResult;
 use near_primitives::stateless_validation::ChunkProductionKey;
 use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::AccountId;
 use near_primitives::types::BlockHeightDelta;
 use near_primitives::types::EpochId;
 use near_primitives::types::ShardId;
@@ -38,8 +39,8 @@ pub type RpcHandlerActor = SyncActixWrapper<RpcHandler>;
 
 impl Handler<ProcessTxRequest> for RpcHandler {
     fn handle(&mut self, msg: ProcessTxRequest) -> ProcessTxResponse {
-        let ProcessTxRequest { transaction, is_forwarded, check_only } = msg;
-        self.process_tx(transaction, is_forwarded, check_only)
+        let ProcessTxRequest { transaction, is_forwarded, is_gossiped, check_only } = msg;
+        self.process_tx_from(transaction, is_forwarded, is_gossiped, check_only)
     }
 }
 
@@ -83,6 +84,9 @@ pub struct RpcHandlerConfig {
     pub tx_routing_height_horizon: u64,
     pub epoch_length: u64,
     pub transaction_validity_period: BlockHeightDelta,
+    /// Whether to announce the transactions accepted into the pool to the other
+    /// upcoming chunk producers, see `near_network::config::NetworkConfig::tx_gossip`.
+    pub tx_gossip: bool,
 }
 
 /// Accepts and processes rpc requests (`process_tx`, etc) and does some preprocessing on incoming data.
@@ -139,9 +143,22 @@ impl RpcHandler {
         tx: SignedTransaction,
         is_forwarded: bool,
         check_only: bool,
+    ) -> ProcessTxResponse {
+        self.process_tx_from(tx, is_forwarded, false, check_only)
+    }
+
+    /// Same as `process_tx`, `is_gossiped` tells whether the transaction was received
+    /// through the transaction gossip rather than from a client or a `ForwardTx` message.
+    fn process_tx_from(
+        &self,
+        tx: SignedTransaction,
+        is_forwarded: bool,
+        is_gossiped: bool,
+        check_only: bool,
     ) -> ProcessTxResponse {
         let signer = self.validator_signer.get();
-        unwrap_or_return!(self.process_tx_internal(&tx, is_forwarded, check_only, &signer), {
+        let result = self.process_tx_internal(&tx, is_forwarded, is_gossiped, check_only, &signer);
+        unwrap_or_return!(result, {
             let me = signer.as_ref().map(|signer| signer.validator_id());
             tracing::debug!(target: "client", ?me, ?tx, "Dropping tx");
             ProcessTxResponse::NoResponse
@@ -153,9 +170,13 @@ impl RpcHandler {
         &self,
         signed_tx: &SignedTransaction,
         is_forwarded: bool,
+        is_gossiped: bool,
         check_only: bool,
         signer: &Option<Arc<ValidatorSigner>>,
     ) -> Result<ProcessTxResponse, near_client_primitives::types::Error> {
+        // The announcer of a gossiped transaction has already sent it to the upcoming
+        // chunk producers, so it is handled as a forwarded one.
+        let is_forwarded = is_forwarded || is_gossiped;
         let head = self.chain_store.head()?;
         let me = signer.as_ref().map(|vs| vs.validator_id());
         let cur_block = self.chain_store.get_block(&head.last_block_hash)?;
@@ -233,11 +254,13 @@ impl RpcHandler {
                 return Ok(ProcessTxResponse::ValidTx);
             }
             // Transactions only need to be recorded if the node is a validator.
+            let mut inserted = false;
             if me.is_some() {
                 let mut pool = self.tx_pool.lock();
                 match pool.insert_transaction(shard_uid, validated_tx) {
                     InsertTransactionResult::Success => {
                         tracing::trace!(target: "client", ?shard_uid, tx_hash = ?signed_tx.get_hash(), "Recorded a transaction.");
+                        inserted = true;
                     }
                     InsertTransactionResult::Duplicate => {
                         tracing::trace!(target: "client", ?shard_uid, tx_hash = ?signed_tx.get_hash(), "Duplicate transaction, not forwarding it.");
@@ -262,6 +285,16 @@ impl RpcHandler {
                 tracing::trace!(target: "client", account = ?me, ?shard_id, tx_hash = ?signed_tx.get_hash(), is_forwarded, "Recording a transaction.");
                 metrics::TRANSACTION_RECEIVED_VALIDATOR.inc();
 
+                if inserted && self.config.tx_gossip && !is_gossiped {
+                    let chunk_producers =
+                        self.upcoming_chunk_producers(&epoch_id, signed_tx, signer)?;
+                    self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                        NetworkRequests::AnnounceTx(
+                            chunk_producers.into_iter().collect(),
+                            signed_tx.clone(),
+                        ),
+                    ));
+                }
                 if !is_forwarded {
                     self.possibly_forward_tx_to_next_epoch(signed_tx, signer)?;
                 }
@@ -297,6 +330,33 @@ impl RpcHandler {
         tx: &SignedTransaction,
         signer: &Option<Arc<ValidatorSigner>>,
     ) -> Result<(), near_client_primitives::types::Error> {
+        let validators = self.upcoming_chunk_producers(epoch_id, tx, signer)?;
+        let shard_id = account_id_to_shard_id(
+            self.epoch_manager.as_ref(),
+            tx.transaction.signer_id(),
+            epoch_id,
+        )?;
+        for validator in validators {
+            let tx_hash = tx.get_hash();
+            tracing::trace!(target: "client", me = ?signer.as_ref().map(|bp| bp.validator_id()), ?tx_hash, ?validator, ?shard_id, "Routing a transaction");
+
+            // Send message to network to actually forward transaction.
+            self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                NetworkRequests::ForwardTx(validator, tx.clone()),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the chunk producers of the transaction's shard for the next
+    /// `tx_routing_height_horizon` heights (and a few further ones), excluding this node.
+    fn upcoming_chunk_producers(
+        &self,
+        epoch_id: &EpochId,
+        tx: &SignedTransaction,
+        signer: &Option<Arc<ValidatorSigner>>,
+    ) -> Result<HashSet<AccountId>, near_client_primitives::types::Error> {
         let shard_id = account_id_to_shard_id(
             self.epoch_manager.as_ref(),
             tx.transaction.signer_id(),
@@ -342,17 +402,7 @@ impl RpcHandler {
         if let Some(account_id) = signer.as_ref().map(|bp| bp.validator_id()) {
             validators.remove(account_id);
         }
-        for validator in validators {
-            let tx_hash = tx.get_hash();
-            tracing::trace!(target: "client", me = ?signer.as_ref().map(|bp| bp.validator_id()), ?tx_hash, ?validator, ?shard_id, "Routing a transaction");
-
-            // Send message to network to actually forward transaction.
-            self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
-                NetworkRequests::ForwardTx(validator, tx.clone()),
-            ));
-        }
-
-        Ok(())
+        Ok(validators)
     }
 
     /// Determine if I am a validator in next few blocks for specified shard, assuming epoch doesn't change.
@@ -1,6 +1,7 @@
 use super::validate::{ChunkRelevance, validate_chunk_endorsement};
 use crate::metrics;
 use near_cache::SyncLruCache;
+use near_chain::timeline::TimelineRecorder;
 use near_chain_primitives::Error;
 use near_crypto::Signature;
 use near_epoch_manager::EpochManagerAdapter;
@@ -26,16 +27,22 @@ pub struct ChunkEndorsementTracker {
     /// We store the validated chunk endorsements received from chunk validators.
     chunk_endorsements:
         SyncLruCache<ChunkProductionKey, HashMap<AccountId, (ChunkHash, Signature)>>,
+    timeline: TimelineRecorder,
 }
 
 impl ChunkEndorsementTracker {
-    pub fn new(epoch_manager: Arc<dyn EpochManagerAdapter>, store: Store) -> Self {
+    pub fn new(
+        epoch_manager: Arc<dyn EpochManagerAdapter>,
+        store: Store,
+        timeline: TimelineRecorder,
+    ) -> Self {
         Self {
             epoch_manager,
             store,
             chunk_endorsements: SyncLruCache::new(
                 NonZeroUsize::new(NUM_CHUNKS_IN_CHUNK_ENDORSEMENTS_CACHE).unwrap().into(),
             ),
+            timeline,
         }
     }
 
@@ -56,6 +63,7 @@ impl ChunkEndorsementTracker {
         // Validate the chunk endorsement and store it in the cache.
         match validate_chunk_endorsement(self.epoch_manager.as_ref(), &endorsement, &self.store)? {
             ChunkRelevance::Relevant => {
+                let height_created = key.height_created;
                 let mut cache = self.chunk_endorsements.lock();
                 cache.get_or_insert_mut(key, || HashMap::new()).insert(
                     account_id.clone(),
@@ -64,6 +72,13 @@ impl ChunkEndorsementTracker {
                 metrics::CHUNK_ENDORSEMENTS_ACCEPTED
                     .with_label_values(&[&endorsement.shard_id().to_string()])
                     .inc();
+                self.timeline.record_instant(
+                    "endorsement received",
+                    "endorsement",
+                    height_created,
+                    Some(endorsement.shard_id()),
+                    vec![("validator", account_id.to_string())],
+                );
             }
             irrelevant => {
                 metrics::CHUNK_ENDORSEMENTS_REJECTED
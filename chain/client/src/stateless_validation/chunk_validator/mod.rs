@@ -24,6 +24,7 @@ use near_primitives::stateless_validation::state_witness::{
 use near_primitives::validator_signer::ValidatorSigner;
 use orphan_witness_pool::OrphanStateWitnessPool;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 // After validating a chunk state witness, we ideally need to send the chunk endorsement
 // to just the next block producer at height h. However, it's possible that blocks at height
@@ -44,6 +45,8 @@ pub struct ChunkValidator {
     orphan_witness_pool: OrphanStateWitnessPool,
     validation_spawner: Arc<dyn AsyncComputationSpawner>,
     main_state_transition_result_cache: chunk_validation::MainStateTransitionCache,
+    /// Number of state witnesses spawned for validation, which haven't been validated yet.
+    pending_validations: Arc<AtomicUsize>,
 }
 
 impl ChunkValidator {
@@ -62,9 +65,15 @@ impl ChunkValidator {
             validation_spawner,
             main_state_transition_result_cache: chunk_validation::MainStateTransitionCache::default(
             ),
+            pending_validations: Default::default(),
         }
     }
 
+    /// Number of state witnesses waiting for validation or being validated.
+    pub fn validation_backlog(&self) -> usize {
+        self.pending_validations.load(Ordering::Relaxed)
+    }
+
     /// Performs the chunk validation logic. When done, it will send the chunk
     /// endorsement message to the block producer. The actual validation logic
     /// happens in a separate thread.
@@ -154,6 +163,8 @@ impl ChunkValidator {
         let runtime_adapter = self.runtime_adapter.clone();
         let cache = self.main_state_transition_result_cache.clone();
         let signer = signer.clone();
+        let pending_validations = self.pending_validations.clone();
+        pending_validations.fetch_add(1, Ordering::Relaxed);
         self.validation_spawner.spawn("stateless_validation", move || {
             // processing_done_tracker must survive until the processing is finished.
             let _processing_done_tracker_capture: Option<ProcessingDoneTracker> =
@@ -187,6 +198,7 @@ impl ChunkValidator {
                     );
                 }
             }
+            pending_validations.fetch_sub(1, Ordering::Relaxed);
         });
         Ok(())
     }
@@ -269,6 +281,10 @@ impl Client {
         // This is currently used for network roundtrip time measurement, so we do not need to
         // wait for validation to finish.
         self.send_state_witness_ack(&witness, &signer);
+        self.duty_tracker.record_witness_received(
+            witness.chunk_header.height_created(),
+            witness.chunk_header.shard_id(),
+        );
 
         if self.config.save_latest_witnesses {
             self.chain.chain_store.save_latest_chunk_state_witness(&witness)?;
@@ -326,6 +342,13 @@ impl Client {
             )));
         }
 
+        self.chain.blocks_delay_tracker.timeline.record_instant(
+            "witness validation started",
+            "witness",
+            witness.chunk_header.height_created(),
+            Some(witness.chunk_header.shard_id()),
+            vec![("chunk_producer", witness.chunk_producer.to_string())],
+        );
         self.chunk_validator.start_validating_chunk(
             witness,
             &self.chain,
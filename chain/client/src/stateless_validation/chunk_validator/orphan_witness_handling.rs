@@ -6,7 +6,7 @@
 //! arrives, all witnesses that were waiting for it can be processed.
 
 use crate::Client;
-use near_chain::Block;
+use near_chain::{Block, OrphanStateWitness};
 use near_chain_primitives::Error;
 use near_primitives::hash::CryptoHash;
 use near_primitives::stateless_validation::state_witness::ChunkStateWitness;
@@ -73,6 +73,16 @@ impl Client {
 
         // Orphan witness is OK, save it to the pool
         tracing::debug!(target: "client", "Saving an orphaned ChunkStateWitness to orphan pool");
+        let witness = match &mut self.persisted_orphan_witnesses {
+            Some(persisted_orphan_witnesses) => {
+                let orphan = OrphanStateWitness { witness, witness_size: witness_size_u64 };
+                if let Err(err) = persisted_orphan_witnesses.save(&orphan) {
+                    tracing::warn!(target: "client", ?err, "Failed to persist orphaned ChunkStateWitness");
+                }
+                orphan.witness
+            }
+            None => witness,
+        };
         self.chunk_validator.orphan_witness_pool.add_orphan_state_witness(witness, witness_size);
         Ok(HandleOrphanWitnessOutcome::SavedToPool)
     }
@@ -84,6 +94,14 @@ impl Client {
             .take_state_witnesses_waiting_for_block(new_block.hash());
         for witness in ready_witnesses {
             let header = &witness.chunk_header;
+            if let Some(persisted_orphan_witnesses) = &mut self.persisted_orphan_witnesses {
+                let witness_id = OrphanStateWitness::witness_id(&witness);
+                let res =
+                    persisted_orphan_witnesses.remove_by_id(header.height_created(), &witness_id);
+                if let Err(err) = res {
+                    tracing::warn!(target: "client", ?err, "Failed to remove persisted orphaned ChunkStateWitness");
+                }
+            }
             tracing::debug!(
                 target: "client",
                 witness_height = header.height_created(),
@@ -134,6 +152,12 @@ impl Client {
         self.chunk_validator
             .orphan_witness_pool
             .remove_witnesses_below_final_height(last_final_block.height());
+        if let Some(persisted_orphan_witnesses) = &mut self.persisted_orphan_witnesses {
+            let res = persisted_orphan_witnesses.remove_below_height(last_final_block.height() + 1);
+            if let Err(err) = res {
+                tracing::warn!(target: "client", ?err, "Failed to prune persisted orphaned ChunkStateWitnesses");
+            }
+        }
     }
 }
 
@@ -62,6 +62,7 @@ impl Client {
             state_witness,
             self.epoch_manager.as_ref(),
             None,
+            self.config.shadow_validation_divergence_dir.clone(),
         )?;
         Ok(())
     }
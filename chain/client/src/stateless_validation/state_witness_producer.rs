@@ -78,6 +78,13 @@ impl Client {
         }
 
         let height = chunk_header.height_created();
+        self.chain.blocks_delay_tracker.timeline.record_instant(
+            "witness produced",
+            "witness",
+            height,
+            Some(shard_id),
+            vec![("chunk_hash", chunk_header.chunk_hash().0.to_string())],
+        );
         if self
             .epoch_manager
             .get_chunk_validator_assignments(epoch_id, shard_id, height)?
@@ -13,7 +13,7 @@ use downloader::StateSyncDownloader;
 use external::StateSyncDownloadSourceExternal;
 use futures::future::BoxFuture;
 use near_async::futures::{FutureSpawner, FutureSpawnerExt};
-use near_async::messaging::{AsyncSender, IntoSender};
+use near_async::messaging::{AsyncSender, IntoSender, Sender};
 use near_async::time::{Clock, Duration};
 use near_chain::Chain;
 use near_chain::types::RuntimeAdapter;
@@ -21,7 +21,7 @@ use near_chain_configs::{ExternalStorageConfig, ExternalStorageLocation, SyncCon
 use near_client_primitives::types::{ShardSyncStatus, StateSyncStatus};
 use near_epoch_manager::EpochManagerAdapter;
 use near_network::types::{
-    HighestHeightPeerInfo, PeerManagerMessageRequest, PeerManagerMessageResponse,
+    HighestHeightPeerInfo, PeerManagerMessageRequest, PeerManagerMessageResponse, StateSyncEvent,
 };
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
@@ -90,6 +90,7 @@ impl StateSync {
         epoch_manager: Arc<dyn EpochManagerAdapter>,
         runtime: Arc<dyn RuntimeAdapter>,
         network_adapter: AsyncSender<PeerManagerMessageRequest, PeerManagerMessageResponse>,
+        state_sync_event_sender: Sender<StateSyncEvent>,
         external_timeout: Duration,
         p2p_timeout: Duration,
         retry_backoff: Duration,
@@ -108,6 +109,7 @@ impl StateSync {
             request_sender: network_adapter,
             request_timeout: p2p_timeout,
             state: peer_source_state.clone(),
+            event_sender: state_sync_event_sender,
         }) as Arc<dyn StateSyncDownloadSource>;
         let (fallback_source, num_attempts_before_fallback, num_concurrent_requests) =
             if let SyncConfig::ExternalStorage(ExternalStorageConfig {
@@ -298,6 +300,7 @@ impl StateSync {
 
         sync_status.download_tasks = self.downloading_task_tracker.statuses();
         sync_status.computation_tasks = self.computation_task_tracker.statuses();
+        sync_status.host_stats = self.peer_source_state.lock().host_stats_view();
         Ok(if all_done { StateSyncResult::Completed } else { StateSyncResult::InProgress })
     }
 }
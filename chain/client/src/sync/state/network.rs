@@ -4,19 +4,21 @@ use crate::metrics;
 use crate::sync::state::util::increment_download_count;
 use futures::FutureExt;
 use futures::future::BoxFuture;
-use near_async::messaging::AsyncSender;
+use near_async::messaging::{AsyncSender, CanSend, Sender};
 use near_async::time::{Clock, Duration};
 use near_chain::BlockHeader;
 use near_network::types::{
     NetworkRequests, NetworkResponses, PeerManagerMessageRequest, PeerManagerMessageResponse,
+    StateSyncEvent,
 };
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
 use near_primitives::state_sync::{ShardStateSyncResponse, ShardStateSyncResponseHeader};
 use near_primitives::types::ShardId;
+use near_primitives::views::StateSyncHostStatsView;
 use near_store::{DBCol, Store};
 use parking_lot::Mutex;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::select;
 use tokio::sync::oneshot;
@@ -30,6 +32,43 @@ pub(super) struct StateSyncDownloadSourcePeer {
     pub request_sender: AsyncSender<PeerManagerMessageRequest, PeerManagerMessageResponse>,
     pub request_timeout: Duration,
     pub state: Arc<Mutex<StateSyncDownloadSourcePeerSharedState>>,
+    /// Used to report the slow or unreliable snapshot hosts to the network layer,
+    /// so that it queries them only after the other hosts.
+    pub event_sender: Sender<StateSyncEvent>,
+}
+
+/// Weight of the latest download in the moving average of the throughput of a host.
+const THROUGHPUT_AVERAGE_WEIGHT: f64 = 0.2;
+/// Hosts with throughput below this fraction of the median throughput of all hosts are demoted.
+const SLOW_HOST_THROUGHPUT_RATIO: f64 = 0.25;
+/// Hosts with at least this many failed downloads, and more failed than successful ones,
+/// are demoted.
+const UNRELIABLE_HOST_MIN_FAILURES: u64 = 3;
+
+/// State part downloads from a single snapshot host.
+#[derive(Default)]
+struct HostStats {
+    parts_downloaded: u64,
+    failures: u64,
+    bytes_downloaded: u64,
+    /// Exponential moving average of the throughput, in bytes per second.
+    throughput: Option<f64>,
+}
+
+impl HostStats {
+    fn record_download(&mut self, bytes: u64, elapsed: Duration) {
+        self.parts_downloaded += 1;
+        self.bytes_downloaded += bytes;
+        let throughput = bytes as f64 / elapsed.as_seconds_f64().max(0.001);
+        self.throughput = Some(match self.throughput {
+            Some(avg) => avg + THROUGHPUT_AVERAGE_WEIGHT * (throughput - avg),
+            None => throughput,
+        });
+    }
+
+    fn is_unreliable(&self) -> bool {
+        self.failures >= UNRELIABLE_HOST_MIN_FAILURES && self.failures > self.parts_downloaded
+    }
 }
 
 #[derive(Default)]
@@ -40,6 +79,10 @@ pub(super) struct StateSyncDownloadSourcePeerSharedState {
     /// expect the response from, as well as a channel sender to complete the future
     /// waiting for the response.
     pending_requests: HashMap<PendingPeerRequestKey, PendingPeerRequestValue>,
+    /// Throughput and failures of the hosts we have requested state parts from.
+    host_stats: HashMap<PeerId, HostStats>,
+    /// Hosts which are slow or unreliable compared to the others.
+    demoted_hosts: HashSet<PeerId>,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -91,6 +134,60 @@ impl StateSyncDownloadSourcePeerSharedState {
     pub fn set_highest_peers(&mut self, peers: Vec<PeerId>) {
         self.highest_height_peers = peers;
     }
+
+    /// Records the outcome of a state part request sent to the host: the size of the part
+    /// and the time it took to download it, or `None` if the download failed.
+    /// Returns the new set of demoted hosts if it has changed.
+    fn record_part_download(
+        &mut self,
+        peer_id: PeerId,
+        download: Option<(u64, Duration)>,
+    ) -> Option<HashSet<PeerId>> {
+        let stats = self.host_stats.entry(peer_id).or_default();
+        match download {
+            Some((bytes, elapsed)) => stats.record_download(bytes, elapsed),
+            None => stats.failures += 1,
+        }
+
+        let mut throughputs: Vec<f64> =
+            self.host_stats.values().filter_map(|stats| stats.throughput).collect();
+        throughputs.sort_by(f64::total_cmp);
+        let median_throughput = throughputs.get(throughputs.len() / 2).copied();
+        let demoted_hosts: HashSet<PeerId> = self
+            .host_stats
+            .iter()
+            .filter(|(_, stats)| {
+                stats.is_unreliable()
+                    || stats.throughput.zip(median_throughput).is_some_and(
+                        |(throughput, median)| throughput < SLOW_HOST_THROUGHPUT_RATIO * median,
+                    )
+            })
+            .map(|(peer_id, _)| peer_id.clone())
+            .collect();
+        if demoted_hosts == self.demoted_hosts {
+            return None;
+        }
+        self.demoted_hosts = demoted_hosts.clone();
+        Some(demoted_hosts)
+    }
+
+    /// Per-host stats to show on the debug page, the fastest hosts first.
+    pub fn host_stats_view(&self) -> Vec<StateSyncHostStatsView> {
+        let mut view: Vec<_> = self
+            .host_stats
+            .iter()
+            .map(|(peer_id, stats)| StateSyncHostStatsView {
+                peer_id: peer_id.clone(),
+                parts_downloaded: stats.parts_downloaded,
+                failures: stats.failures,
+                bytes_downloaded: stats.bytes_downloaded,
+                throughput_bytes_per_sec: stats.throughput.map(|throughput| throughput as u64),
+                demoted: self.demoted_hosts.contains(peer_id),
+            })
+            .collect();
+        view.sort_by(|a, b| b.throughput_bytes_per_sec.cmp(&a.throughput_bytes_per_sec));
+        view
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -106,6 +203,7 @@ impl StateSyncDownloadSourcePeer {
         key: PendingPeerRequestKey,
         store: Store,
         state: Arc<Mutex<StateSyncDownloadSourcePeerSharedState>>,
+        event_sender: Sender<StateSyncEvent>,
         cancel: CancellationToken,
         request_timeout: Duration,
         handle: Arc<TaskHandle>,
@@ -188,7 +286,8 @@ impl StateSyncDownloadSourcePeer {
             }
         };
 
-        let state_value = PendingPeerRequestValue { peer_id: Some(request_sent_to_peer), sender };
+        let state_value =
+            PendingPeerRequestValue { peer_id: Some(request_sent_to_peer.clone()), sender };
 
         // Ensures that the key is removed from the map of pending requests when this scope exits,
         // whether on success or timeout.
@@ -199,14 +298,15 @@ impl StateSyncDownloadSourcePeer {
         }
 
         handle.set_status("Waiting for peer response");
-        select! {
+        let request_start = clock.now();
+        let result = select! {
             _ = clock.sleep_until(deadline) => {
                 increment_download_count(key.shard_id, typ, "network", "timeout");
                 Err(near_chain::Error::Other("Timeout".to_owned()))
             }
             _ = cancel.cancelled() => {
                 increment_download_count(key.shard_id, typ, "network", "cancelled");
-                Err(near_chain::Error::Other("Cancelled".to_owned()))
+                return Err(near_chain::Error::Other("Cancelled".to_owned()));
             }
             result = receiver => {
                 match result {
@@ -220,7 +320,24 @@ impl StateSyncDownloadSourcePeer {
                     },
                 }
             }
+        };
+
+        // Feed the outcome of part downloads back into the selection of the hosts.
+        if let PartIdOrHeader::Part { .. } = key.kind {
+            let download = match &result {
+                Ok(response) => response
+                    .part()
+                    .as_ref()
+                    .map(|(_, part)| (part.len() as u64, clock.now() - request_start)),
+                Err(_) => None,
+            };
+            let demoted_hosts = state.lock().record_part_download(request_sent_to_peer, download);
+            if let Some(demoted_hosts) = demoted_hosts {
+                tracing::debug!(target: "sync", ?demoted_hosts, "demoted snapshot hosts changed");
+                event_sender.send(StateSyncEvent::DemotedSnapshotHosts(demoted_hosts));
+            }
         }
+        result
     }
 }
 
@@ -252,6 +369,7 @@ impl StateSyncDownloadSource for StateSyncDownloadSourcePeer {
             key,
             self.store.clone(),
             self.state.clone(),
+            self.event_sender.clone(),
             cancel,
             self.request_timeout,
             handle,
@@ -283,6 +401,7 @@ impl StateSyncDownloadSource for StateSyncDownloadSourcePeer {
             key,
             self.store.clone(),
             self.state.clone(),
+            self.event_sender.clone(),
             cancel,
             self.request_timeout,
             handle,
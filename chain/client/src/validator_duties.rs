@@ -0,0 +1,414 @@
+//! Explains block production, chunk production and chunk endorsement duties of
+//! a validator over a range of heights, for the `EXPERIMENTAL_validator_duties`
+//! RPC.
+//!
+//! Outcomes are derived from the canonical chain and the epoch manager, so they
+//! are available for any validator. Reasons for missed duties additionally rely
+//! on what this node observed locally, so they are only reported when the
+//! queried account is the validator running this node, and only for recent
+//! heights.
+//!
+//! Walking the chain is done by the view client. The client only provides a
+//! snapshot of its local observations, which is cheap to take, so that the RPC
+//! can't slow down block and chunk production.
+
+use crate::Client;
+use crate::debug::PRODUCTION_TIMES_CACHE_SIZE;
+use near_async::time::{Clock, Utc};
+use near_chain::{Block, Chain, ChainStoreAccess};
+use near_client_primitives::types::{
+    BlockProductionRecord, GetValidatorDutiesError, ValidatorDutyRecords,
+};
+use near_epoch_manager::EpochManagerAdapter;
+use near_primitives::block::MaybeNew;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::hash::CryptoHash;
+use near_primitives::stateless_validation::ChunkProductionKey;
+use near_primitives::types::{AccountId, BlockHeight, BlockHeightDelta, ShardId};
+use near_primitives::views::{
+    ValidatorDutyKind, ValidatorDutyOutcome, ValidatorDutyReason, ValidatorDutyView,
+};
+use std::collections::VecDeque;
+use std::num::NonZeroUsize;
+
+/// Maximum number of heights which can be requested at once.
+const MAX_VALIDATOR_DUTIES_RANGE: BlockHeightDelta = 1000;
+
+/// Maximum number of remembered periods in which the node was syncing.
+const MAX_SYNC_PERIODS: usize = 100;
+
+/// Local observations which are not kept anywhere else and which help to
+/// explain why a duty was missed.
+pub(crate) struct DutyTracker {
+    clock: Clock,
+    /// Start and end of the periods in which the node was syncing. The end of
+    /// the last period is None if the node is still syncing.
+    sync_periods: VecDeque<(Utc, Option<Utc>)>,
+    /// Errors of chunk production, by height created and shard.
+    chunk_production_errors: lru::LruCache<(BlockHeight, ShardId), String>,
+    /// Time when the state witness was first received, by height created and shard.
+    witness_received: lru::LruCache<(BlockHeight, ShardId), Utc>,
+}
+
+impl DutyTracker {
+    pub(crate) fn new(clock: Clock) -> Self {
+        let cache_size = NonZeroUsize::new(PRODUCTION_TIMES_CACHE_SIZE).unwrap();
+        Self {
+            clock,
+            sync_periods: VecDeque::new(),
+            chunk_production_errors: lru::LruCache::new(cache_size),
+            witness_received: lru::LruCache::new(cache_size),
+        }
+    }
+
+    /// Must be called periodically with the current sync status.
+    pub(crate) fn record_sync_status(&mut self, is_syncing: bool) {
+        let is_open = matches!(self.sync_periods.back(), Some((_, None)));
+        if is_syncing && !is_open {
+            if self.sync_periods.len() >= MAX_SYNC_PERIODS {
+                self.sync_periods.pop_front();
+            }
+            self.sync_periods.push_back((self.clock.now_utc(), None));
+        } else if !is_syncing && is_open {
+            self.sync_periods.back_mut().unwrap().1 = Some(self.clock.now_utc());
+        }
+    }
+
+    pub(crate) fn record_chunk_production_error(
+        &mut self,
+        height_created: BlockHeight,
+        shard_id: ShardId,
+        error: String,
+    ) {
+        self.chunk_production_errors.put((height_created, shard_id), error);
+    }
+
+    pub(crate) fn record_witness_received(
+        &mut self,
+        height_created: BlockHeight,
+        shard_id: ShardId,
+    ) {
+        let now = self.clock.now_utc();
+        self.witness_received.get_or_insert((height_created, shard_id), || now);
+    }
+}
+
+fn was_syncing_at<'a>(
+    sync_periods: impl IntoIterator<Item = &'a (Utc, Option<Utc>)>,
+    time: Utc,
+) -> bool {
+    sync_periods.into_iter().any(|(start, end)| *start <= time && end.is_none_or(|end| time <= end))
+}
+
+fn check_range(
+    start_height: BlockHeight,
+    end_height: BlockHeight,
+) -> Result<(), GetValidatorDutiesError> {
+    if start_height > end_height {
+        return Err(GetValidatorDutiesError::InvalidRange(format!(
+            "start height {} is greater than end height {}",
+            start_height, end_height
+        )));
+    }
+    if end_height - start_height >= MAX_VALIDATOR_DUTIES_RANGE {
+        return Err(GetValidatorDutiesError::InvalidRange(format!(
+            "at most {} heights can be requested at once",
+            MAX_VALIDATOR_DUTIES_RANGE
+        )));
+    }
+    Ok(())
+}
+
+impl Client {
+    /// Takes a snapshot of the local observations in `[start_height, end_height]`
+    /// if `account_id` is the validator running this node. Only looks at the
+    /// bounded in-memory caches, never at the store.
+    pub(crate) fn get_validator_duty_records(
+        &self,
+        account_id: &AccountId,
+        start_height: BlockHeight,
+        end_height: BlockHeight,
+    ) -> Result<Option<ValidatorDutyRecords>, GetValidatorDutiesError> {
+        check_range(start_height, end_height)?;
+        if !self.validator_signer.get().is_some_and(|signer| signer.validator_id() == account_id) {
+            return Ok(None);
+        }
+        let in_range = |height: &BlockHeight| (start_height..=end_height).contains(height);
+        let block_production = self
+            .block_production_info
+            .iter()
+            .filter(|(height, _)| in_range(*height))
+            .map(|(height, block_production)| {
+                let record = BlockProductionRecord {
+                    enough_approvals: block_production.approvals.ready_at.is_some(),
+                    produced: block_production.block_production_time.is_some(),
+                };
+                (*height, record)
+            })
+            .collect();
+        let chunk_production_errors = self
+            .duty_tracker
+            .chunk_production_errors
+            .iter()
+            .filter(|((height, _), _)| in_range(height))
+            .map(|(key, error)| (*key, error.clone()))
+            .collect();
+        let chunks_produced = self
+            .chunk_producer
+            .chunk_production_info
+            .iter()
+            .map(|(key, _)| *key)
+            .filter(|(height, _)| in_range(height))
+            .collect();
+        let witness_received = self
+            .duty_tracker
+            .witness_received
+            .iter()
+            .filter(|((height, _), _)| in_range(height))
+            .map(|(key, received)| (*key, *received))
+            .collect();
+        Ok(Some(ValidatorDutyRecords {
+            sync_periods: self.duty_tracker.sync_periods.iter().copied().collect(),
+            block_production,
+            chunk_production_errors,
+            chunks_produced,
+            witness_received,
+            blocks_with_missing_chunks: self
+                .chain
+                .blocks_delay_tracker
+                .blocks_with_missing_chunks(end_height)
+                .into_iter()
+                .collect(),
+        }))
+    }
+}
+
+/// Lists duties of `account_id` for heights in `[start_height, end_height]`
+/// covered by the canonical chain. Reasons for missed duties are only given if
+/// `local_records` are available.
+pub(crate) fn get_validator_duties(
+    chain: &Chain,
+    epoch_manager: &dyn EpochManagerAdapter,
+    account_id: &AccountId,
+    start_height: BlockHeight,
+    end_height: BlockHeight,
+    local_records: Option<&ValidatorDutyRecords>,
+) -> Result<Vec<ValidatorDutyView>, GetValidatorDutiesError> {
+    check_range(start_height, end_height)?;
+    let head = chain.head()?;
+
+    // Skipped heights are only known from the next block on the canonical
+    // chain, so start the walk from the first block above the range.
+    let mut block_hash = head.last_block_hash;
+    for height in end_height.saturating_add(1)..=head.height {
+        if let Ok(hash) = chain.chain_store().get_block_hash_by_height(height) {
+            block_hash = hash;
+            break;
+        }
+    }
+
+    let collector = DutyCollector { epoch_manager, account_id, local_records };
+    let mut duties = vec![];
+    let mut header = chain.get_block_header(&block_hash)?;
+    while !header.is_genesis() && header.height() >= start_height {
+        let block = chain.get_block(header.hash())?;
+        let prev_header = chain.get_block_header(header.prev_hash())?;
+        collector.collect_block_duties(&block, &prev_header, &mut duties)?;
+        header = prev_header;
+    }
+    duties.retain(|duty| start_height <= duty.height && duty.height <= end_height);
+    duties.sort_by_key(|duty| duty.height);
+    Ok(duties)
+}
+
+struct DutyCollector<'a> {
+    epoch_manager: &'a dyn EpochManagerAdapter,
+    account_id: &'a AccountId,
+    local_records: Option<&'a ValidatorDutyRecords>,
+}
+
+impl DutyCollector<'_> {
+    /// Collects duties which were supposed to be fulfilled by `block`: the
+    /// production of the block itself and of blocks at skipped heights before
+    /// it, the production of its chunks and their endorsements.
+    fn collect_block_duties(
+        &self,
+        block: &Block,
+        prev_header: &BlockHeader,
+        duties: &mut Vec<ValidatorDutyView>,
+    ) -> Result<(), GetValidatorDutiesError> {
+        let account_id = self.account_id;
+        let header = block.header();
+        let epoch_id = header.epoch_id();
+        for height in prev_header.height() + 1..=header.height() {
+            if self.epoch_manager.get_block_producer(epoch_id, height)? != *account_id {
+                continue;
+            }
+            let (outcome, reason) = if height == header.height() {
+                (ValidatorDutyOutcome::Done, None)
+            } else {
+                let reason = self
+                    .local_records
+                    .map(|records| block_miss_reason(records, height, header.timestamp()));
+                (ValidatorDutyOutcome::Missed, reason)
+            };
+            duties.push(ValidatorDutyView {
+                height,
+                duty: ValidatorDutyKind::BlockProduction,
+                outcome,
+                reason,
+            });
+        }
+
+        let height_created = prev_header.height() + 1;
+        for (chunk, endorsements) in block.chunks().iter().zip(block.chunk_endorsements()) {
+            let (chunk_header, is_new) = match chunk {
+                MaybeNew::New(chunk_header) => (chunk_header, true),
+                MaybeNew::Old(chunk_header) => (chunk_header, false),
+            };
+            let shard_id = chunk_header.shard_id();
+            let key = ChunkProductionKey { epoch_id: *epoch_id, height_created, shard_id };
+            if self.epoch_manager.get_chunk_producer_info(&key)?.account_id() == account_id {
+                let (outcome, reason) = if is_new {
+                    let reason = (chunk_header.tx_root() == CryptoHash::default())
+                        .then_some(ValidatorDutyReason::EmptyTxPool);
+                    (ValidatorDutyOutcome::Done, reason)
+                } else {
+                    let reason = self.local_records.map(|records| {
+                        chunk_miss_reason(
+                            records,
+                            height_created,
+                            shard_id,
+                            prev_header.hash(),
+                            header.timestamp(),
+                        )
+                    });
+                    (ValidatorDutyOutcome::Missed, reason)
+                };
+                duties.push(ValidatorDutyView {
+                    height: height_created,
+                    duty: ValidatorDutyKind::ChunkProduction { shard_id },
+                    outcome,
+                    reason,
+                });
+            }
+
+            let chunk_validators = self
+                .epoch_manager
+                .get_chunk_validator_assignments(epoch_id, shard_id, height_created)?
+                .ordered_chunk_validators();
+            let Some(validator_index) =
+                chunk_validators.iter().position(|validator| validator == account_id)
+            else {
+                continue;
+            };
+            let (outcome, reason) = if !is_new {
+                (ValidatorDutyOutcome::Skipped, None)
+            } else if endorsements.get(validator_index).is_some_and(Option::is_some) {
+                (ValidatorDutyOutcome::Done, None)
+            } else {
+                let reason = self.local_records.map(|records| {
+                    endorsement_miss_reason(records, height_created, shard_id, header)
+                });
+                (ValidatorDutyOutcome::Missed, reason)
+            };
+            duties.push(ValidatorDutyView {
+                height: height_created,
+                duty: ValidatorDutyKind::ChunkEndorsement { shard_id },
+                outcome,
+                reason,
+            });
+        }
+        Ok(())
+    }
+}
+
+fn block_miss_reason(
+    records: &ValidatorDutyRecords,
+    height: BlockHeight,
+    time: Utc,
+) -> ValidatorDutyReason {
+    if let Some(block_production) = records.block_production.get(&height) {
+        if block_production.produced {
+            return ValidatorDutyReason::BlockNotIncluded;
+        }
+        if !block_production.enough_approvals {
+            return ValidatorDutyReason::NotEnoughApprovals;
+        }
+    }
+    if was_syncing_at(&records.sync_periods, time) {
+        return ValidatorDutyReason::NodeSyncing;
+    }
+    ValidatorDutyReason::NoLocalRecord
+}
+
+fn chunk_miss_reason(
+    records: &ValidatorDutyRecords,
+    height_created: BlockHeight,
+    shard_id: ShardId,
+    prev_block_hash: &CryptoHash,
+    time: Utc,
+) -> ValidatorDutyReason {
+    let key = (height_created, shard_id);
+    if let Some(error) = records.chunk_production_errors.get(&key) {
+        return ValidatorDutyReason::ChunkProductionFailed { error: error.clone() };
+    }
+    if records.chunks_produced.contains(&key) {
+        return ValidatorDutyReason::ChunkNotIncluded;
+    }
+    if records.blocks_with_missing_chunks.contains(prev_block_hash) {
+        return ValidatorDutyReason::MissingChunkParts;
+    }
+    if was_syncing_at(&records.sync_periods, time) {
+        return ValidatorDutyReason::NodeSyncing;
+    }
+    ValidatorDutyReason::NoLocalRecord
+}
+
+fn endorsement_miss_reason(
+    records: &ValidatorDutyRecords,
+    height_created: BlockHeight,
+    shard_id: ShardId,
+    block_header: &BlockHeader,
+) -> ValidatorDutyReason {
+    let Some(received) = records.witness_received.get(&(height_created, shard_id)) else {
+        if was_syncing_at(&records.sync_periods, block_header.timestamp()) {
+            return ValidatorDutyReason::NodeSyncing;
+        }
+        return ValidatorDutyReason::WitnessNotReceived;
+    };
+    let delay = *received - block_header.timestamp();
+    if delay.is_positive() {
+        return ValidatorDutyReason::LateWitness { delay_ms: delay.whole_milliseconds() as u64 };
+    }
+    ValidatorDutyReason::EndorsementNotIncluded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DutyTracker, was_syncing_at};
+    use near_async::time::{Duration, FakeClock, Utc};
+
+    #[test]
+    fn test_sync_periods() {
+        let clock = FakeClock::new(Utc::UNIX_EPOCH);
+        let mut tracker = DutyTracker::new(clock.clock());
+        tracker.record_sync_status(false);
+        clock.advance(Duration::seconds(10));
+        tracker.record_sync_status(true);
+        clock.advance(Duration::seconds(10));
+        tracker.record_sync_status(true);
+        clock.advance(Duration::seconds(10));
+        tracker.record_sync_status(false);
+        clock.advance(Duration::seconds(10));
+        tracker.record_sync_status(true);
+
+        let at = |seconds| Utc::UNIX_EPOCH + Duration::seconds(seconds);
+        assert!(!was_syncing_at(&tracker.sync_periods, at(5)));
+        assert!(was_syncing_at(&tracker.sync_periods, at(15)));
+        assert!(was_syncing_at(&tracker.sync_periods, at(30)));
+        assert!(!was_syncing_at(&tracker.sync_periods, at(35)));
+        assert!(was_syncing_at(&tracker.sync_periods, at(100)));
+        assert_eq!(tracker.sync_periods.len(), 2);
+    }
+}
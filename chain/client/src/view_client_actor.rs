@@ -19,12 +19,13 @@ use near_chain_configs::{ClientConfig, MutableValidatorSigner, ProtocolConfigVie
 use near_chain_primitives::error::EpochErrorResultToChainError;
 use near_client_primitives::types::{
     Error, GetBlock, GetBlockError, GetBlockProof, GetBlockProofError, GetBlockProofResponse,
-    GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome, GetExecutionOutcomeError,
-    GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError, GetMaintenanceWindows,
-    GetMaintenanceWindowsError, GetNextLightClientBlockError, GetProtocolConfig,
-    GetProtocolConfigError, GetReceipt, GetReceiptError, GetSplitStorageInfo,
-    GetSplitStorageInfoError, GetStateChangesError, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
+    GetBlockWithMerkleTree, GetChunkError, GetEpochSyncProof, GetEpochSyncProofError,
+    GetExecutionOutcome, GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice,
+    GetGasPriceError, GetMaintenanceWindows, GetMaintenanceWindowsError,
+    GetNextLightClientBlockError, GetProtocolConfig, GetProtocolConfigError, GetReceipt,
+    GetReceiptError, GetSplitStorageInfo, GetSplitStorageInfoError, GetStateChangesError,
+    GetStateChangesWithCauseInBlock, GetStateChangesWithCauseInBlockForTrackedShards,
+    GetValidatorDuties, GetValidatorDutiesError, GetValidatorInfoError, Query, QueryError,
     TxStatus, TxStatusError,
 };
 use near_epoch_manager::EpochManagerAdapter;
@@ -41,6 +42,7 @@ use near_network::types::{
 use near_performance_metrics_macros::perf;
 use near_primitives::block::{Block, BlockHeader};
 use near_primitives::epoch_info::EpochInfo;
+use near_primitives::epoch_sync::CompressedEpochSyncProof;
 use near_primitives::errors::EpochError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{PartialMerkleTree, merklize};
@@ -62,7 +64,7 @@ use near_primitives::views::{
     FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView,
     LightClientBlockView, MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView,
     SignedTransactionView, SplitStorageInfoView, StateChangesKindsView, StateChangesView,
-    TxExecutionStatus, TxStatusView,
+    TxExecutionStatus, TxStatusView, ValidatorDutyView,
 };
 use near_store::{COLD_HEAD_KEY, DBCol, FINAL_HEAD_KEY, HEAD_KEY};
 use parking_lot::{Mutex, RwLock};
@@ -105,6 +107,9 @@ pub struct ViewClientActorInner {
     pub config: ClientConfig,
     request_manager: Arc<RwLock<ViewClientRequestManager>>,
     state_request_cache: Arc<Mutex<VecDeque<Instant>>>,
+    /// The last epoch sync proof served over RPC and the epoch ID it was
+    /// computed for, see `EpochSync::derive_epoch_sync_proof`.
+    epoch_sync_proof_cache: Arc<Mutex<Option<(EpochId, CompressedEpochSyncProof)>>>,
 }
 
 impl ViewClientRequestManager {
@@ -183,6 +188,7 @@ impl ViewClientActorInner {
             config,
             request_manager: Arc::new(RwLock::new(ViewClientRequestManager::new())),
             state_request_cache: Arc::new(Mutex::new(VecDeque::default())),
+            epoch_sync_proof_cache: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -1536,6 +1542,32 @@ impl Handler<GetMaintenanceWindows> for ViewClientActorInner {
     }
 }
 
+impl Handler<GetValidatorDuties> for ViewClientActorInner {
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: GetValidatorDuties,
+    ) -> Result<Vec<ValidatorDutyView>, GetValidatorDutiesError> {
+        tracing::debug!(
+            target: "client",
+            account_id = %msg.account_id,
+            start_height = msg.start_height,
+            end_height = msg.end_height,
+        );
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetValidatorDuties"])
+            .start_timer();
+        crate::validator_duties::get_validator_duties(
+            &self.chain,
+            self.epoch_manager.as_ref(),
+            &msg.account_id,
+            msg.start_height,
+            msg.end_height,
+            msg.local_records.as_ref(),
+        )
+    }
+}
+
 impl Handler<GetSplitStorageInfo> for ViewClientActorInner {
     fn handle(
         &mut self,
@@ -1558,3 +1590,18 @@ impl Handler<GetSplitStorageInfo> for ViewClientActorInner {
         })
     }
 }
+
+impl Handler<GetEpochSyncProof> for ViewClientActorInner {
+    fn handle(
+        &mut self,
+        msg: GetEpochSyncProof,
+    ) -> Result<CompressedEpochSyncProof, GetEpochSyncProofError> {
+        tracing::debug!(target: "client", ?msg);
+
+        Ok(sync::epoch::EpochSync::derive_epoch_sync_proof(
+            self.chain.chain_store().store(),
+            self.chain.transaction_validity_period(),
+            self.epoch_sync_proof_cache.clone(),
+        )?)
+    }
+}
@@ -0,0 +1,38 @@
+use serde_json::Value;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcEpochSyncProofRequest {}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcEpochSyncProofResponse {
+    /// The zstd-compressed borsh encoding of the `EpochSyncProof`, in base64.
+    /// It can be verified with the `near-light-client` crate.
+    pub proof_base64: String,
+}
+
+#[derive(thiserror::Error, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcEpochSyncProofError {
+    #[error("Epoch sync proof is not available yet: {error_message}")]
+    NotAvailable { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcEpochSyncProofError> for crate::errors::RpcError {
+    fn from(error: RpcEpochSyncProofError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcEpochSyncProofError: {:?}", err),
+                );
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
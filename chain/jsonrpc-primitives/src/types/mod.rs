@@ -5,6 +5,7 @@ pub mod client_config;
 pub mod config;
 pub mod congestion;
 pub mod entity_debug;
+pub mod epoch_sync;
 pub mod gas_price;
 pub mod light_client;
 pub mod maintenance;
@@ -16,3 +17,4 @@ pub mod split_storage;
 pub mod status;
 pub mod transactions;
 pub mod validator;
+pub mod validator_duties;
@@ -1,11 +1,12 @@
 #[cfg(feature = "debug_types")]
 use near_client_primitives::debug::{
-    DebugBlockStatusData, EpochInfoView, TrackedShardsView, ValidatorStatus,
+    ApprovalsReceivedAtHeight, DebugBlockStatusData, EpochInfoView, TrackedShardsView,
+    ValidatorStatus,
 };
 #[cfg(feature = "debug_types")]
 use near_primitives::views::{
-    CatchupStatusView, ChainProcessingInfo, NetworkGraphView, NetworkRoutesView, PeerStoreView,
-    RecentOutboundConnectionsView, RequestedStatePartsView, SnapshotHostsView,
+    CatchupStatusView, ChainProcessingInfo, ChromeTraceView, NetworkGraphView, NetworkRoutesView,
+    PeerStoreView, RecentOutboundConnectionsView, RequestedStatePartsView, SnapshotHostsView,
     SplitStorageInfoView, SyncStatusView,
 };
 
@@ -36,6 +37,8 @@ pub enum DebugStatusResponse {
     Routes(NetworkRoutesView),
     SnapshotHosts(SnapshotHostsView),
     SplitStoreStatus(SplitStorageInfoView),
+    Timeline(ChromeTraceView),
+    ApprovalHistory(Vec<ApprovalsReceivedAtHeight>),
 }
 
 #[cfg(feature = "debug_types")]
@@ -0,0 +1,42 @@
+use serde_json::Value;
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcValidatorDutiesRequest {
+    pub account_id: near_primitives::types::AccountId,
+    pub start_height: near_primitives::types::BlockHeight,
+    pub end_height: near_primitives::types::BlockHeight,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcValidatorDutiesResponse {
+    pub duties: Vec<near_primitives::views::ValidatorDutyView>,
+}
+
+#[derive(thiserror::Error, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcValidatorDutiesError {
+    #[error("Invalid height range: {error_message}")]
+    InvalidRange { error_message: String },
+    #[error("Block is not available: {error_message}")]
+    UnknownBlock { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcValidatorDutiesError> for crate::errors::RpcError {
+    fn from(error: RpcValidatorDutiesError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcValidatorDutiesError: {:?}", err),
+                );
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
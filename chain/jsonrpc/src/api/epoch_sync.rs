@@ -0,0 +1,40 @@
+use near_async::messaging::AsyncSendError;
+use serde_json::Value;
+
+use near_client_primitives::types::GetEpochSyncProofError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::epoch_sync::{
+    RpcEpochSyncProofError, RpcEpochSyncProofRequest,
+};
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcEpochSyncProofRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcEpochSyncProofError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetEpochSyncProofError> for RpcEpochSyncProofError {
+    fn rpc_from(error: GetEpochSyncProofError) -> Self {
+        match error {
+            GetEpochSyncProofError::IOError(error_message) => Self::InternalError { error_message },
+            GetEpochSyncProofError::NotAvailable(error_message) => {
+                Self::NotAvailable { error_message }
+            }
+            GetEpochSyncProofError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcEpochSyncProofError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
@@ -10,6 +10,7 @@ mod chunks;
 mod client_config;
 mod config;
 mod congestion;
+mod epoch_sync;
 mod gas_price;
 mod light_client;
 mod maintenance;
@@ -21,6 +22,7 @@ mod split_storage;
 mod status;
 mod transactions;
 mod validator;
+mod validator_duties;
 
 pub trait RpcRequest: Sized {
     fn parse(value: Value) -> Result<Self, RpcParseError>;
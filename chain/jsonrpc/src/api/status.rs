@@ -50,6 +50,12 @@ impl RpcFrom<near_client_primitives::debug::DebugStatusResponse>
                     x,
                 )
             }
+            near_client_primitives::debug::DebugStatusResponse::Timeline(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::Timeline(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::ApprovalHistory(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::ApprovalHistory(x)
+            }
         }
     }
 }
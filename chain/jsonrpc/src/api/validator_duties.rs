@@ -0,0 +1,45 @@
+use near_async::messaging::AsyncSendError;
+use serde_json::Value;
+
+use near_client_primitives::types::GetValidatorDutiesError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::validator_duties::{
+    RpcValidatorDutiesError, RpcValidatorDutiesRequest,
+};
+
+use super::{Params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcValidatorDutiesRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcValidatorDutiesError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetValidatorDutiesError> for RpcValidatorDutiesError {
+    fn rpc_from(error: GetValidatorDutiesError) -> Self {
+        match error {
+            GetValidatorDutiesError::IOError(error_message) => {
+                Self::InternalError { error_message }
+            }
+            GetValidatorDutiesError::InvalidRange(error_message) => {
+                Self::InvalidRange { error_message }
+            }
+            GetValidatorDutiesError::UnknownBlock(error_message) => {
+                Self::UnknownBlock { error_message }
+            }
+            GetValidatorDutiesError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcValidatorDutiesError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
@@ -11,10 +11,11 @@ use near_async::messaging::{
 };
 use near_chain_configs::GenesisConfig;
 use near_client::{
-    DebugStatus, GetBlock, GetBlockProof, GetChunk, GetClientConfig, GetExecutionOutcome,
-    GetGasPrice, GetMaintenanceWindows, GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig,
-    GetReceipt, GetStateChanges, GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
-    ProcessTxRequest, ProcessTxResponse, Query, Status, TxStatus,
+    DebugStatus, GetBlock, GetBlockProof, GetChunk, GetClientConfig, GetEpochSyncProof,
+    GetExecutionOutcome, GetGasPrice, GetMaintenanceWindows, GetNetworkInfo,
+    GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
+    GetStateChangesInBlock, GetValidatorDuties, GetValidatorDutyRecords, GetValidatorInfo,
+    GetValidatorOrdered, ProcessTxRequest, ProcessTxResponse, Query, Status, TxStatus,
 };
 use near_client_primitives::debug::{DebugBlockStatusQuery, DebugBlocksStartingMode};
 use near_client_primitives::types::GetSplitStorageInfo;
@@ -234,6 +235,7 @@ pub struct ClientSenderForRpc(
     AsyncSender<DebugStatus, ActixResult<DebugStatus>>,
     AsyncSender<GetClientConfig, ActixResult<GetClientConfig>>,
     AsyncSender<GetNetworkInfo, ActixResult<GetNetworkInfo>>,
+    AsyncSender<GetValidatorDutyRecords, ActixResult<GetValidatorDutyRecords>>,
     AsyncSender<Status, ActixResult<Status>>,
     #[cfg(feature = "test_features")] Sender<near_client::NetworkAdversarialMessage>,
     #[cfg(feature = "test_features")]
@@ -253,6 +255,7 @@ pub struct ViewClientSenderForRpc(
     AsyncSender<GetBlock, ActixResult<GetBlock>>,
     AsyncSender<GetBlockProof, ActixResult<GetBlockProof>>,
     AsyncSender<GetChunk, ActixResult<GetChunk>>,
+    AsyncSender<GetEpochSyncProof, ActixResult<GetEpochSyncProof>>,
     AsyncSender<GetExecutionOutcome, ActixResult<GetExecutionOutcome>>,
     AsyncSender<GetGasPrice, ActixResult<GetGasPrice>>,
     AsyncSender<GetMaintenanceWindows, ActixResult<GetMaintenanceWindows>>,
@@ -262,6 +265,7 @@ pub struct ViewClientSenderForRpc(
     AsyncSender<GetSplitStorageInfo, ActixResult<GetSplitStorageInfo>>,
     AsyncSender<GetStateChanges, ActixResult<GetStateChanges>>,
     AsyncSender<GetStateChangesInBlock, ActixResult<GetStateChangesInBlock>>,
+    AsyncSender<GetValidatorDuties, ActixResult<GetValidatorDuties>>,
     AsyncSender<GetValidatorInfo, ActixResult<GetValidatorInfo>>,
     AsyncSender<GetValidatorOrdered, ActixResult<GetValidatorOrdered>>,
     AsyncSender<Query, ActixResult<Query>>,
@@ -279,7 +283,10 @@ pub struct GCSenderForRpc(
 );
 
 #[derive(Clone, near_async::MultiSend, near_async::MultiSenderFrom)]
-pub struct PeerManagerSenderForRpc(AsyncSender<GetDebugStatus, ActixResult<GetDebugStatus>>);
+pub struct PeerManagerSenderForRpc(
+    AsyncSender<GetDebugStatus, ActixResult<GetDebugStatus>>,
+    #[cfg(feature = "test_features")] Sender<near_network::fault_injection::SetFaultInjection>,
+);
 
 struct JsonRpcHandler {
     client_sender: ClientSenderForRpc,
@@ -456,9 +463,15 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_maintenance_windows" => {
                 process_method_call(request, |params| self.maintenance_windows(params)).await
             }
+            "EXPERIMENTAL_validator_duties" => {
+                process_method_call(request, |params| self.validator_duties(params)).await
+            }
             "EXPERIMENTAL_split_storage_info" => {
                 process_method_call(request, |params| self.split_storage_info(params)).await
             }
+            "EXPERIMENTAL_epoch_sync_proof" => {
+                process_method_call(request, |params| self.epoch_sync_proof(params)).await
+            }
             #[cfg(feature = "sandbox")]
             "sandbox_patch_state" => {
                 process_method_call(request, |params| self.sandbox_patch_state(params)).await
@@ -500,6 +513,7 @@ impl JsonRpcHandler {
             "adv_switch_to_height" => self.adv_switch_to_height(request.params),
             "adv_get_saved_blocks" => self.adv_get_saved_blocks(request.params).await,
             "adv_check_store" => self.adv_check_store(request.params).await,
+            "adv_network_faults" => self.adv_network_faults(request.params),
             _ => return Err(request),
         })
     }
@@ -547,6 +561,7 @@ impl JsonRpcHandler {
         self.process_tx_sender.send(ProcessTxRequest {
             transaction: tx,
             is_forwarded: false,
+            is_gossiped: false,
             check_only: false, // if we set true here it will not actually send the transaction
         });
         hash
@@ -676,7 +691,12 @@ impl JsonRpcHandler {
         let signer_account_id = tx.transaction.signer_id().clone();
         let response = self
             .process_tx_sender
-            .send_async(ProcessTxRequest { transaction: tx, is_forwarded: false, check_only })
+            .send_async(ProcessTxRequest {
+                transaction: tx,
+                is_forwarded: false,
+                is_gossiped: false,
+                check_only,
+            })
             .await
             .map_err(RpcFrom::rpc_from)?;
 
@@ -812,6 +832,9 @@ impl JsonRpcHandler {
                     "/debug/api/requested_state_parts" => {
                         self.client_send(DebugStatus::RequestedStateParts).await?.rpc_into()
                     }
+                    "/debug/api/approval_history" => {
+                        self.client_send(DebugStatus::ApprovalHistory).await?.rpc_into()
+                    }
                     "/debug/api/peer_store" => self
                         .peer_manager_send(near_network::debug::GetDebugStatus::PeerStore)
                         .await?
@@ -852,6 +875,26 @@ impl JsonRpcHandler {
         }
     }
 
+    pub async fn debug_timeline(
+        &self,
+    ) -> Result<
+        Option<near_primitives::views::ChromeTraceView>,
+        near_jsonrpc_primitives::types::status::RpcStatusError,
+    > {
+        if self.enable_debug_rpc {
+            match self.client_send(DebugStatus::Timeline).await? {
+                near_client_primitives::debug::DebugStatusResponse::Timeline(trace) => {
+                    Ok(Some(trace))
+                }
+                _ => Err(near_jsonrpc_primitives::types::status::RpcStatusError::InternalError {
+                    error_message: "unexpected response to timeline request".to_string(),
+                }),
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
     pub async fn debug_block_status(
         &self,
         query: DebugBlockStatusQuery,
@@ -1192,6 +1235,40 @@ impl JsonRpcHandler {
         Ok(windows.iter().map(|r| (r.start, r.end)).collect())
     }
 
+    /// Returns block, chunk and endorsement duties of the specified account in the given height
+    /// range, together with their outcome and the likely reason for the missed ones
+    async fn validator_duties(
+        &self,
+        request: near_jsonrpc_primitives::types::validator_duties::RpcValidatorDutiesRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::validator_duties::RpcValidatorDutiesResponse,
+        near_jsonrpc_primitives::types::validator_duties::RpcValidatorDutiesError,
+    > {
+        let near_jsonrpc_primitives::types::validator_duties::RpcValidatorDutiesRequest {
+            account_id,
+            start_height,
+            end_height,
+        } = request;
+        // Only the cheap snapshot of local observations is taken on the client, the chain is
+        // walked on the view client so that the RPC can't stall block and chunk production.
+        let local_records = self
+            .client_send(GetValidatorDutyRecords {
+                account_id: account_id.clone(),
+                start_height,
+                end_height,
+            })
+            .await?;
+        let duties = self
+            .view_client_send(GetValidatorDuties {
+                account_id,
+                start_height,
+                end_height,
+                local_records,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::validator_duties::RpcValidatorDutiesResponse { duties })
+    }
+
     async fn client_config(
         &self,
     ) -> Result<
@@ -1212,6 +1289,21 @@ impl JsonRpcHandler {
         let split_storage = self.view_client_send(GetSplitStorageInfo {}).await?;
         Ok(RpcSplitStorageInfoResponse { result: split_storage })
     }
+
+    /// Returns the compressed epoch sync proof for a recent epoch, which lets a light client
+    /// verify the block producers of every epoch since genesis.
+    async fn epoch_sync_proof(
+        &self,
+        _request_data: near_jsonrpc_primitives::types::epoch_sync::RpcEpochSyncProofRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::epoch_sync::RpcEpochSyncProofResponse,
+        near_jsonrpc_primitives::types::epoch_sync::RpcEpochSyncProofError,
+    > {
+        let proof = self.view_client_send(GetEpochSyncProof {}).await?;
+        Ok(near_jsonrpc_primitives::types::epoch_sync::RpcEpochSyncProofResponse {
+            proof_base64: near_primitives::serialize::to_base64(proof.as_ref()),
+        })
+    }
 }
 
 #[cfg(feature = "sandbox")]
@@ -1356,6 +1448,18 @@ impl JsonRpcHandler {
         }
     }
 
+    /// Sets the network faults injected into the messages received by this node,
+    /// see `near_network::fault_injection`. `null` disables the injection.
+    fn adv_network_faults(&self, params: Value) -> Result<Value, RpcError> {
+        let (config,): (Option<near_network::fault_injection::Config>,) =
+            crate::api::Params::parse(params)?;
+        if let Some(config) = &config {
+            config.validate().map_err(|err| RpcError::invalid_params(err.to_string()))?;
+        }
+        self.peer_manager_sender.send(near_network::fault_injection::SetFaultInjection(config));
+        Ok(Value::String(String::new()))
+    }
+
     /// First, stop GC by sending a message to GC Actor. Then run store validator inside Client.
     /// After store validator is done, resume GC by sending another message to GC Actor.
     /// This ensures that store validator is not run concurrently with another thread that may modify storage.
@@ -1489,6 +1593,18 @@ async fn handle_entity_debug_readonly(
     }
 }
 
+/// Unlike other debug endpoints, returns the trace without a wrapper so that it
+/// can be opened in Perfetto as is.
+async fn debug_timeline_handler(
+    handler: web::Data<JsonRpcHandler>,
+) -> Result<HttpResponse, HttpError> {
+    match handler.debug_timeline().await {
+        Ok(Some(value)) => Ok(HttpResponse::Ok().json(&value)),
+        Ok(None) => Ok(HttpResponse::MethodNotAllowed().finish()),
+        Err(_) => Ok(HttpResponse::ServiceUnavailable().finish()),
+    }
+}
+
 async fn debug_block_status_handler(
     query: web::Query<DebugBlockStatusQuery>,
     handler: web::Data<JsonRpcHandler>,
@@ -1728,6 +1844,10 @@ pub fn start_http(
                     web::resource("/debug/api/epoch_info/{epoch_id}")
                         .route(web::get().to(debug_epoch_info_handler)),
                 )
+                .service(
+                    web::resource("/debug/api/timeline")
+                        .route(web::get().to(debug_timeline_handler)),
+                )
                 .service(web::resource("/debug/api/{api}").route(web::get().to(debug_handler)))
                 .service(
                     web::resource("/debug/client_config")
@@ -0,0 +1,339 @@
+use crate::LightClientError;
+use crate::epoch_sync::{epoch_info_block_producers, verify_epoch_sync_proof};
+use near_primitives::block::{Approval, ApprovalInner, compute_bp_hash_from_validator_stakes};
+use near_primitives::epoch_sync::EpochSyncProofV1;
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::combine_hash;
+use near_primitives::types::Balance;
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::views::{LightClientBlockLiteView, LightClientBlockView};
+use std::collections::HashMap;
+
+/// Latest block trusted by a light client, together with the block producers
+/// needed to verify the blocks which follow it.
+///
+/// Blocks are verified as described in the light client spec: each block must
+/// be approved by more than 2/3 of the stake of its epoch's block producers,
+/// and the first block verified in an epoch must carry the block producers of
+/// the next one.
+#[derive(Debug, Clone)]
+pub struct LightClientState {
+    head: LightClientBlockLiteView,
+    /// Block producers of the head's epoch and, once known, of the next one.
+    epoch_block_producers: HashMap<CryptoHash, Vec<ValidatorStake>>,
+}
+
+impl LightClientState {
+    /// Starts from a head which is trusted by other means, e.g. a checkpoint
+    /// shipped with the client.
+    pub fn new(
+        head: LightClientBlockLiteView,
+        block_producers: Vec<ValidatorStake>,
+        next_block_producers: Vec<ValidatorStake>,
+    ) -> Self {
+        let epoch_block_producers = HashMap::from([
+            (head.inner_lite.epoch_id, block_producers),
+            (head.inner_lite.next_epoch_id, next_block_producers),
+        ]);
+        Self { head, epoch_block_producers }
+    }
+
+    /// Verifies the epoch sync proof and starts from the first block of the
+    /// epoch it syncs to. See `verify_epoch_sync_proof`.
+    pub fn from_epoch_sync_proof(
+        proof: &EpochSyncProofV1,
+        genesis_block_producers: &[ValidatorStake],
+    ) -> Result<Self, LightClientError> {
+        verify_epoch_sync_proof(proof, genesis_block_producers)?;
+        // The last epoch data is committed to by the first block of the current
+        // epoch, its next epoch is the current one.
+        let head =
+            LightClientBlockLiteView::from(proof.current_epoch.first_block_header_in_epoch.clone());
+        Ok(Self::new(
+            head,
+            epoch_info_block_producers(&proof.last_epoch.next_epoch_info),
+            epoch_info_block_producers(&proof.last_epoch.next_next_epoch_info),
+        ))
+    }
+
+    pub fn head(&self) -> &LightClientBlockLiteView {
+        &self.head
+    }
+
+    /// Verifies a block which follows the head, in the same epoch or in the
+    /// next one, and makes it the new head.
+    pub fn validate_and_update_head(
+        &mut self,
+        block: &LightClientBlockView,
+    ) -> Result<(), LightClientError> {
+        let head = &self.head.inner_lite;
+        let inner_lite = &block.inner_lite;
+        if inner_lite.height <= head.height {
+            return Err(LightClientError::InvalidBlock(format!(
+                "block height {} is not above head height {}",
+                inner_lite.height, head.height
+            )));
+        }
+        if inner_lite.epoch_id != head.epoch_id && inner_lite.epoch_id != head.next_epoch_id {
+            return Err(LightClientError::InvalidBlock(format!(
+                "block epoch {} is neither the head epoch nor the next one",
+                inner_lite.epoch_id
+            )));
+        }
+        if inner_lite.epoch_id == head.next_epoch_id && block.next_bps.is_none() {
+            return Err(LightClientError::InvalidBlock(
+                "first block of the next epoch must have next_bps".to_string(),
+            ));
+        }
+        let block_producers =
+            self.epoch_block_producers.get(&inner_lite.epoch_id).ok_or_else(|| {
+                LightClientError::InvalidBlock(format!(
+                    "unknown block producers for epoch {}",
+                    inner_lite.epoch_id
+                ))
+            })?;
+        if block.approvals_after_next.len() < block_producers.len() {
+            return Err(LightClientError::InvalidBlock(format!(
+                "block should have at least {} approvals but has {}",
+                block_producers.len(),
+                block.approvals_after_next.len()
+            )));
+        }
+
+        let block_lite = LightClientBlockLiteView {
+            prev_block_hash: block.prev_block_hash,
+            inner_rest_hash: block.inner_rest_hash,
+            inner_lite: inner_lite.clone(),
+        };
+        let next_block_hash = combine_hash(&block.next_block_inner_hash, &block_lite.hash());
+        let message_to_sign = Approval::get_data_for_sig(
+            &ApprovalInner::Endorsement(next_block_hash),
+            inner_lite.height + 2,
+        );
+
+        let mut total_stake: Balance = 0;
+        let mut approved_stake: Balance = 0;
+        for (validator, approval) in block_producers.iter().zip(block.approvals_after_next.iter()) {
+            total_stake += validator.stake();
+            let Some(signature) = approval else {
+                continue;
+            };
+            if !signature.verify(&message_to_sign, validator.public_key()) {
+                return Err(LightClientError::InvalidBlock(format!(
+                    "invalid approval from validator {:?}",
+                    validator.account_id()
+                )));
+            }
+            approved_stake += validator.stake();
+        }
+        if approved_stake <= total_stake * 2 / 3 {
+            return Err(LightClientError::InvalidBlock("not enough approvals".to_string()));
+        }
+
+        let next_block_producers = match &block.next_bps {
+            Some(next_bps) => {
+                let next_bps: Vec<ValidatorStake> =
+                    next_bps.iter().cloned().map(Into::into).collect();
+                // The format of next_bp_hash depends on the protocol version,
+                // which a light client doesn't know.
+                if compute_bp_hash_from_validator_stakes(&next_bps, true) != inner_lite.next_bp_hash
+                    && compute_bp_hash_from_validator_stakes(&next_bps, false)
+                        != inner_lite.next_bp_hash
+                {
+                    return Err(LightClientError::InvalidBlock(
+                        "next_bps does not match next_bp_hash".to_string(),
+                    ));
+                }
+                Some(next_bps)
+            }
+            None => None,
+        };
+
+        self.head = block_lite;
+        let head = &self.head.inner_lite;
+        self.epoch_block_producers
+            .retain(|epoch_id, _| *epoch_id == head.epoch_id || *epoch_id == head.next_epoch_id);
+        if let Some(next_block_producers) = next_block_producers {
+            self.epoch_block_producers.insert(head.next_epoch_id, next_block_producers);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_primitives::hash::hash;
+    use near_primitives::test_utils::create_test_signer;
+    use near_primitives::validator_signer::ValidatorSigner;
+    use near_primitives::views::BlockHeaderInnerLiteView;
+    use near_primitives::views::validator_stake_view::ValidatorStakeView;
+
+    const EPOCH: CryptoHash = CryptoHash([1; 32]);
+    const NEXT_EPOCH: CryptoHash = CryptoHash([2; 32]);
+    const NEXT_NEXT_EPOCH: CryptoHash = CryptoHash([3; 32]);
+
+    fn create_signers(names: &[&str]) -> Vec<ValidatorSigner> {
+        names.iter().map(|name| create_test_signer(name)).collect()
+    }
+
+    fn block_producers(signers: &[ValidatorSigner]) -> Vec<ValidatorStake> {
+        signers
+            .iter()
+            .map(|signer| {
+                ValidatorStake::new(signer.validator_id().clone(), signer.public_key(), 100)
+            })
+            .collect()
+    }
+
+    fn inner_lite(
+        height: u64,
+        epoch_id: CryptoHash,
+        next_epoch_id: CryptoHash,
+    ) -> BlockHeaderInnerLiteView {
+        BlockHeaderInnerLiteView {
+            height,
+            epoch_id,
+            next_epoch_id,
+            prev_state_root: CryptoHash::default(),
+            outcome_root: CryptoHash::default(),
+            timestamp: 0,
+            timestamp_nanosec: 0,
+            next_bp_hash: CryptoHash::default(),
+            block_merkle_root: CryptoHash::default(),
+        }
+    }
+
+    fn state(block_producers: Vec<ValidatorStake>) -> LightClientState {
+        let head = LightClientBlockLiteView {
+            prev_block_hash: CryptoHash::default(),
+            inner_rest_hash: CryptoHash::default(),
+            inner_lite: inner_lite(10, EPOCH, NEXT_EPOCH),
+        };
+        LightClientState::new(head, block_producers.clone(), block_producers)
+    }
+
+    /// Creates a block approved by the given signers, in their order.
+    fn block(
+        inner_lite: BlockHeaderInnerLiteView,
+        next_bps: Option<Vec<ValidatorStakeView>>,
+        approvers: &[Option<&ValidatorSigner>],
+    ) -> LightClientBlockView {
+        let mut block = LightClientBlockView {
+            prev_block_hash: hash(b"prev"),
+            next_block_inner_hash: hash(b"next"),
+            inner_lite,
+            inner_rest_hash: hash(b"rest"),
+            next_bps,
+            approvals_after_next: vec![],
+        };
+        let block_lite = LightClientBlockLiteView {
+            prev_block_hash: block.prev_block_hash,
+            inner_rest_hash: block.inner_rest_hash,
+            inner_lite: block.inner_lite.clone(),
+        };
+        let next_block_hash = combine_hash(&block.next_block_inner_hash, &block_lite.hash());
+        let message = Approval::get_data_for_sig(
+            &ApprovalInner::Endorsement(next_block_hash),
+            block.inner_lite.height + 2,
+        );
+        block.approvals_after_next = approvers
+            .iter()
+            .map(|signer| signer.map(|signer| Box::new(signer.sign_bytes(&message))))
+            .collect();
+        block
+    }
+
+    #[test]
+    fn test_valid_block_updates_head() {
+        let signers = create_signers(&["test0", "test1", "test2"]);
+        let mut state = state(block_producers(&signers));
+        let block = block(
+            inner_lite(11, EPOCH, NEXT_EPOCH),
+            None,
+            &[Some(&signers[0]), Some(&signers[1]), Some(&signers[2])],
+        );
+        state.validate_and_update_head(&block).unwrap();
+        assert_eq!(state.head().inner_lite.height, 11);
+        assert_eq!(state.head().prev_block_hash, block.prev_block_hash);
+    }
+
+    #[test]
+    fn test_not_enough_approvals() {
+        let signers = create_signers(&["test0", "test1", "test2"]);
+        let mut state = state(block_producers(&signers));
+        // Exactly 2/3 of the stake is not enough.
+        let block = block(
+            inner_lite(11, EPOCH, NEXT_EPOCH),
+            None,
+            &[Some(&signers[0]), Some(&signers[1]), None],
+        );
+        assert!(matches!(
+            state.validate_and_update_head(&block),
+            Err(LightClientError::InvalidBlock(_))
+        ));
+        assert_eq!(state.head().inner_lite.height, 10);
+    }
+
+    #[test]
+    fn test_invalid_approval() {
+        let signers = create_signers(&["test0", "test1", "test2"]);
+        let mut state = state(block_producers(&signers));
+        let other = create_test_signer("other");
+        let block = block(
+            inner_lite(11, EPOCH, NEXT_EPOCH),
+            None,
+            &[Some(&signers[0]), Some(&signers[1]), Some(&other)],
+        );
+        assert!(state.validate_and_update_head(&block).is_err());
+    }
+
+    #[test]
+    fn test_unknown_epoch() {
+        let signers = create_signers(&["test0", "test1", "test2"]);
+        let mut state = state(block_producers(&signers));
+        let approvers: Vec<_> = signers.iter().map(Some).collect();
+        let block = block(inner_lite(11, NEXT_NEXT_EPOCH, CryptoHash([4; 32])), None, &approvers);
+        assert!(state.validate_and_update_head(&block).is_err());
+    }
+
+    #[test]
+    fn test_next_epoch_requires_next_bps() {
+        let signers = create_signers(&["test0", "test1", "test2"]);
+        let mut state = state(block_producers(&signers));
+        let approvers: Vec<_> = signers.iter().map(Some).collect();
+        let block = block(inner_lite(11, NEXT_EPOCH, NEXT_NEXT_EPOCH), None, &approvers);
+        assert!(state.validate_and_update_head(&block).is_err());
+    }
+
+    #[test]
+    fn test_next_bps_update_block_producers() {
+        let signers = create_signers(&["test0", "test1", "test2"]);
+        let next_signers = create_signers(&["test3", "test4"]);
+        let next_block_producers = block_producers(&next_signers);
+        let mut state = state(block_producers(&signers));
+        let approvers: Vec<_> = signers.iter().map(Some).collect();
+
+        // The next_bps must match next_bp_hash.
+        let mut lite = inner_lite(11, NEXT_EPOCH, NEXT_NEXT_EPOCH);
+        let next_bps: Vec<ValidatorStakeView> =
+            next_block_producers.iter().cloned().map(Into::into).collect();
+        let wrong_block = block(lite.clone(), Some(next_bps.clone()), &approvers);
+        assert!(state.validate_and_update_head(&wrong_block).is_err());
+
+        lite.next_bp_hash = compute_bp_hash_from_validator_stakes(&next_block_producers, true);
+        let block_in_next_epoch = block(lite, Some(next_bps), &approvers);
+        state.validate_and_update_head(&block_in_next_epoch).unwrap();
+        assert_eq!(state.head().inner_lite.epoch_id, NEXT_EPOCH);
+
+        // Blocks of the epoch after are now approved by its block producers.
+        let next_approvers: Vec<_> = next_signers.iter().map(Some).collect();
+        let mut lite = inner_lite(12, NEXT_NEXT_EPOCH, CryptoHash([4; 32]));
+        let next_next_bps = block_producers(&signers);
+        lite.next_bp_hash = compute_bp_hash_from_validator_stakes(&next_next_bps, false);
+        let next_next_bps = next_next_bps.into_iter().map(Into::into).collect();
+        let block_in_next_next_epoch = block(lite, Some(next_next_bps), &next_approvers);
+        state.validate_and_update_head(&block_in_next_next_epoch).unwrap();
+        assert_eq!(state.head().inner_lite.height, 12);
+    }
+}
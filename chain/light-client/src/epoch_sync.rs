@@ -0,0 +1,245 @@
+use crate::LightClientError;
+use near_crypto::Signature;
+use near_primitives::block::{Approval, ApprovalInner, compute_bp_hash_from_validator_stakes};
+use near_primitives::block_header::BlockHeader;
+use near_primitives::epoch_info::EpochInfo;
+use near_primitives::epoch_sync::{
+    CompressedEpochSyncProof, EpochSyncProofCurrentEpochData, EpochSyncProofEpochData,
+    EpochSyncProofLastEpochData, EpochSyncProofV1,
+};
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{Balance, BlockHeight};
+use near_primitives::utils::compression::CompressedData;
+use std::collections::HashSet;
+
+/// Gets the ordered list of block producers and their stake from the EpochInfo.
+pub fn epoch_info_block_producers(epoch_info: &EpochInfo) -> Vec<ValidatorStake> {
+    // The block producers settlement can actually have duplicates.
+    // The list of block producers used to compute bp_hash is the deduplicated version
+    // of this list, keeping the order of first appearance.
+    let mut block_producers = Vec::new();
+    let mut seen_validators = HashSet::new();
+    for bp_index in epoch_info.block_producers_settlement() {
+        if seen_validators.insert(*bp_index) {
+            block_producers.push(epoch_info.get_validator(*bp_index));
+        }
+    }
+    block_producers
+}
+
+/// Decompresses an epoch sync proof as served by nodes, over the network or by
+/// the `EXPERIMENTAL_epoch_sync_proof` JSON-RPC method.
+pub fn decode_epoch_sync_proof(compressed: &[u8]) -> Result<EpochSyncProofV1, LightClientError> {
+    let compressed = CompressedEpochSyncProof::from(Box::<[u8]>::from(compressed));
+    let (proof, _) = compressed.decode()?;
+    Ok(proof.into_v1())
+}
+
+/// Verifies an epoch sync proof. `genesis_block_producers` are the block
+/// producers of the epoch right after genesis, whose ID is the genesis hash;
+/// they are the same as the block producers of genesis.
+pub fn verify_epoch_sync_proof(
+    proof: &EpochSyncProofV1,
+    genesis_block_producers: &[ValidatorStake],
+) -> Result<(), LightClientError> {
+    let EpochSyncProofV1 { all_epochs, last_epoch, current_epoch } = proof;
+    if all_epochs.len() < 2 {
+        return Err(LightClientError::InvalidEpochSyncProof(
+            "need at least two epochs in all_epochs".to_string(),
+        ));
+    }
+
+    // Verify block producer handoff to the second epoch after genesis.
+    if all_epochs[0].block_producers != genesis_block_producers {
+        return Err(LightClientError::InvalidEpochSyncProof(
+            "invalid block producers for second epoch after genesis".to_string(),
+        ));
+    }
+    verify_final_block_endorsement(&all_epochs[0])?;
+
+    // Verify the data of each epoch, in chronological order. When verifying each epoch,
+    // we assume that the previous epoch has been verified (thereby giving correctness of all
+    // epochs by induction.) For each epoch, we need to verify the following:
+    //
+    // - Its block producers. To verify this, we compare the previous epoch's last final block's
+    //   next_bp_hash against the hash of the current epoch's block producers, taking into
+    //   account the use_versioned_bp_hash_format flag.
+    // - Its last final block. To verify this, we use the endorsements provided for the final
+    //   block. What we verify is that more than 2/3 of the block producers of the current epoch
+    //   have endorsed the final block.
+    //
+    // See the comments in `EpochSyncProofEpochData` for more detailed information.
+    for epoch_index in 1..all_epochs.len() {
+        let epoch = &all_epochs[epoch_index];
+        let prev_epoch = &all_epochs[epoch_index - 1];
+        if !verify_block_producer_handoff(
+            &epoch.block_producers,
+            epoch.use_versioned_bp_hash_format,
+            prev_epoch.last_final_block_header.next_bp_hash(),
+        ) {
+            return Err(LightClientError::InvalidEpochSyncProof(format!(
+                "invalid block producer handoff to epoch index {}",
+                epoch_index
+            )));
+        }
+        verify_final_block_endorsement(epoch)?;
+    }
+
+    verify_epoch_sync_data_hash(&last_epoch, &current_epoch.first_block_header_in_epoch)?;
+
+    verify_current_epoch_data(current_epoch, &all_epochs.last().unwrap().last_final_block_header)?;
+    Ok(())
+}
+
+fn verify_current_epoch_data(
+    current_epoch: &EpochSyncProofCurrentEpochData,
+    current_epoch_final_block_header: &BlockHeader,
+) -> Result<(), LightClientError> {
+    // Verify first_block_header_in_epoch
+    let first_block_header = &current_epoch.first_block_header_in_epoch;
+    if !near_primitives::merkle::verify_hash(
+        *current_epoch_final_block_header.block_merkle_root(),
+        &current_epoch.merkle_proof_for_first_block,
+        *first_block_header.hash(),
+    ) {
+        return Err(LightClientError::InvalidEpochSyncProof(
+            "invalid merkle_proof_for_first_block".to_string(),
+        ));
+    }
+
+    // Verify partial_merkle_tree_for_first_block. The size needs to match to ensure that
+    // the partial merkle tree is for the right block ordinal, and the partial tree itself
+    // needs to be valid and have the correct root.
+    //
+    // Note that the block_ordinal in the header is 1-based, so we need to add 1 to the size.
+    if current_epoch.partial_merkle_tree_for_first_block.size() + 1
+        != first_block_header.block_ordinal()
+    {
+        return Err(LightClientError::InvalidEpochSyncProof(
+            "invalid size in partial_merkle_tree_for_first_block".to_string(),
+        ));
+    }
+
+    if !current_epoch.partial_merkle_tree_for_first_block.is_well_formed()
+        || current_epoch.partial_merkle_tree_for_first_block.root()
+            != *first_block_header.block_merkle_root()
+    {
+        return Err(LightClientError::InvalidEpochSyncProof(
+            "invalid path in partial_merkle_tree_for_first_block".to_string(),
+        ));
+    }
+
+    // Verify the two headers before the first block.
+    if current_epoch.last_block_header_in_prev_epoch.hash()
+        != current_epoch.first_block_header_in_epoch.prev_hash()
+    {
+        return Err(LightClientError::InvalidEpochSyncProof(
+            "invalid last_block_header_in_prev_epoch".to_string(),
+        ));
+    }
+    if current_epoch.second_last_block_header_in_prev_epoch.hash()
+        != current_epoch.last_block_header_in_prev_epoch.prev_hash()
+    {
+        return Err(LightClientError::InvalidEpochSyncProof(
+            "invalid second_last_block_header_in_prev_epoch".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Verify epoch_sync_data_hash matches current_epoch_first_block_header's epoch_sync_data_hash.
+fn verify_epoch_sync_data_hash(
+    last_epoch: &EpochSyncProofLastEpochData,
+    current_epoch_first_block_header: &BlockHeader,
+) -> Result<(), LightClientError> {
+    let epoch_sync_data_hash = CryptoHash::hash_borsh(&(
+        &last_epoch.first_block_in_epoch,
+        &last_epoch.second_last_block_in_epoch,
+        &last_epoch.last_block_in_epoch,
+        &last_epoch.epoch_info,
+        &last_epoch.next_epoch_info,
+        &last_epoch.next_next_epoch_info,
+    ));
+    let expected_epoch_sync_data_hash =
+        current_epoch_first_block_header.epoch_sync_data_hash().ok_or_else(|| {
+            LightClientError::InvalidEpochSyncProof("missing epoch_sync_data_hash".to_string())
+        })?;
+    if epoch_sync_data_hash != expected_epoch_sync_data_hash {
+        return Err(LightClientError::InvalidEpochSyncProof(
+            "invalid epoch_sync_data_hash".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Verifies that EpochSyncProofPastEpochData's block_producers is valid,
+/// returning true if it is.
+fn verify_block_producer_handoff(
+    block_producers: &Vec<ValidatorStake>,
+    use_versioned_bp_hash_format: bool,
+    prev_epoch_next_bp_hash: &CryptoHash,
+) -> bool {
+    let bp_hash =
+        compute_bp_hash_from_validator_stakes(block_producers, use_versioned_bp_hash_format);
+    bp_hash == *prev_epoch_next_bp_hash
+}
+
+/// Verifies that the epoch's last_final_block_header is sufficiently endorsed by the current
+/// epoch's block producers.
+fn verify_final_block_endorsement(epoch: &EpochSyncProofEpochData) -> Result<(), LightClientError> {
+    verify_block_endorsements(
+        *epoch.last_final_block_header.hash(),
+        epoch.last_final_block_header.height(),
+        &epoch.block_producers,
+        &epoch.this_epoch_endorsements_for_last_final_block,
+    )
+}
+
+/// Verifies that the given block is endorsed properly, and with enough stake.
+fn verify_block_endorsements(
+    prev_block_hash: CryptoHash,
+    block_height: BlockHeight,
+    block_producers: &[ValidatorStake],
+    endorsements: &[Option<Box<Signature>>],
+) -> Result<(), LightClientError> {
+    if endorsements.len() != block_producers.len() {
+        return Err(LightClientError::InvalidEpochSyncProof(format!(
+            "Block {} should be provided with {} endorsements but has {}",
+            block_height,
+            block_producers.len(),
+            endorsements.len()
+        )));
+    }
+
+    let message_to_sign =
+        Approval::get_data_for_sig(&ApprovalInner::Endorsement(prev_block_hash), block_height + 1);
+
+    let mut total_stake: Balance = 0;
+    let mut endorsed_stake: Balance = 0;
+
+    for (validator, may_be_signature) in block_producers.iter().zip(endorsements.iter()) {
+        if let Some(signature) = may_be_signature {
+            if !signature.verify(&message_to_sign, validator.public_key()) {
+                return Err(LightClientError::InvalidEpochSyncProof(format!(
+                    "Invalid signature for block {} from validator {:?}",
+                    block_height,
+                    validator.account_id()
+                )));
+            }
+            endorsed_stake += validator.stake();
+        }
+        total_stake += validator.stake();
+    }
+
+    if endorsed_stake <= total_stake * 2 / 3 {
+        return Err(LightClientError::InvalidEpochSyncProof(format!(
+            "Block {} does not have enough endorsements",
+            block_height
+        )));
+    }
+
+    Ok(())
+}
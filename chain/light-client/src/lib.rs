@@ -0,0 +1,24 @@
+//! Verification of epoch sync proofs and light client blocks, for clients which
+//! want to trust a recent block without running a node.
+//!
+//! An `EpochSyncProof` proves the block producers of every epoch since genesis
+//! and takes a light client to the first block of a recent epoch. From there,
+//! `LightClientState` follows the chain one `LightClientBlockView` at a time.
+
+mod block;
+mod epoch_sync;
+
+pub use block::LightClientState;
+pub use epoch_sync::{
+    decode_epoch_sync_proof, epoch_info_block_producers, verify_epoch_sync_proof,
+};
+
+#[derive(thiserror::Error, Debug)]
+pub enum LightClientError {
+    #[error("Invalid epoch sync proof: {0}")]
+    InvalidEpochSyncProof(String),
+    #[error("Invalid light client block: {0}")]
+    InvalidBlock(String),
+    #[error("Failed to decode epoch sync proof: {0}")]
+    Decode(#[from] std::io::Error),
+}
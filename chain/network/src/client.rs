@@ -92,6 +92,9 @@ pub struct SetNetworkInfo(pub NetworkInfo);
 pub struct ProcessTxRequest {
     pub transaction: SignedTransaction,
     pub is_forwarded: bool,
+    /// Whether the transaction was received through the gossip among the chunk producers,
+    /// see `crate::tx_gossip`. Such transactions are neither forwarded nor announced again.
+    pub is_gossiped: bool,
     pub check_only: bool,
 }
 
@@ -1,12 +1,17 @@
 use crate::blacklist;
 use crate::concurrency::rate;
+use crate::discovery;
+use crate::hole_punching;
 use crate::network_protocol::PeerAddr;
 use crate::network_protocol::PeerInfo;
+use crate::peer::noise;
 use crate::peer_manager::peer_store;
-use crate::rate_limits::messages_limits;
+use crate::rate_limits::{adaptive, messages_limits};
+use crate::recorder;
 use crate::snapshot_hosts;
 use crate::stun;
 use crate::tcp;
+use crate::tx_gossip;
 use crate::types::ROUTED_MESSAGE_TTL;
 use anyhow::Context;
 use near_async::time;
@@ -101,6 +106,10 @@ pub struct Tier1 {
     /// - a node will try to start outbound TIER1 connections iff `enable_outbound` is true.
     pub enable_inbound: bool,
     pub enable_outbound: bool,
+    /// Whether to use QUIC for TIER1 connections, see `crate::quic`.
+    /// The node accepts QUIC connections on the UDP port with the same number as its TCP port,
+    /// and tries QUIC first for outbound TIER1 connections, falling back to TCP.
+    pub enable_quic: bool,
 }
 
 #[derive(Clone)]
@@ -195,6 +204,31 @@ pub struct NetworkConfig {
 
     /// Configuration of rate limits for incoming messages.
     pub received_messages_rate_limits: messages_limits::Config,
+    /// Scaling of `received_messages_rate_limits` depending on the load of the node.
+    pub adaptive_rate_limits: adaptive::Config,
+
+    /// Whether to encrypt connections with the peers which support it.
+    /// See `peer::noise` for details.
+    pub encrypted_transport: bool,
+    /// Whether to refuse connections which can't be encrypted, rather than
+    /// falling back to plaintext. QUIC connections are always encrypted.
+    pub require_encrypted_transport: bool,
+
+    /// If set, all the messages sent and received by the node are recorded.
+    /// See `recorder` for details.
+    pub traffic_recorder: Option<recorder::Config>,
+
+    /// If set, transactions are gossiped among the upcoming chunk producers.
+    /// See `tx_gossip` for details.
+    pub tx_gossip: Option<tx_gossip::Config>,
+
+    /// If set, peers are discovered from DNS seeds and signed peer lists.
+    /// See `discovery` for details.
+    pub peer_discovery: Option<discovery::Config>,
+
+    /// If set, the node connects to the peers which it can't reach directly
+    /// with TCP hole punching. See `hole_punching` for details.
+    pub hole_punching: Option<hole_punching::Config>,
 
     #[cfg(test)]
     pub(crate) event_sink:
@@ -324,6 +358,10 @@ impl NetworkConfig {
                 connect_only_to_boot_nodes: cfg.experimental.connect_only_to_boot_nodes,
                 ban_window: cfg.ban_window.try_into()?,
                 peer_expiration_duration: cfg.peer_expiration_duration.try_into()?,
+                reputation: peer_store::reputation::Config {
+                    half_life: cfg.peer_reputation_half_life.try_into()?,
+                    ..Default::default()
+                },
             },
             snapshot_hosts: snapshot_hosts::Config {
                 snapshot_hosts_cache_size: cfg.snapshot_hosts_cache_size,
@@ -376,6 +414,7 @@ impl NetworkConfig {
                 advertise_proxies_interval: time::Duration::minutes(15),
                 enable_inbound: cfg.experimental.tier1_enable_inbound,
                 enable_outbound: cfg.experimental.tier1_enable_outbound,
+                enable_quic: cfg.experimental.tier1_enable_quic,
             }),
             inbound_disabled: cfg.experimental.inbound_disabled,
             skip_tombstones: if cfg.experimental.skip_sending_tombstones_seconds > 0 {
@@ -385,6 +424,16 @@ impl NetworkConfig {
             },
             // Use a preset to configure rate limits and override entries with user defined values later.
             received_messages_rate_limits: messages_limits::Config::standard_preset(),
+            adaptive_rate_limits: cfg.experimental.adaptive_rate_limits,
+            encrypted_transport: cfg.experimental.encrypted_transport
+                || cfg.experimental.require_encrypted_transport,
+            require_encrypted_transport: cfg.experimental.require_encrypted_transport,
+            traffic_recorder: cfg.experimental.traffic_recording_path.map(|path| {
+                recorder::Config { path, max_bytes: cfg.experimental.traffic_recording_max_bytes }
+            }),
+            tx_gossip: cfg.experimental.tx_gossip.then(tx_gossip::Config::default),
+            peer_discovery: cfg.experimental.peer_discovery,
+            hole_punching: cfg.experimental.hole_punching,
             #[cfg(test)]
             event_sink: near_async::messaging::IntoSender::into_sender(
                 near_async::messaging::noop(),
@@ -422,6 +471,7 @@ impl NetworkConfig {
                 ban_window: time::Duration::seconds(1),
                 peer_expiration_duration: time::Duration::seconds(60 * 60),
                 connect_only_to_boot_nodes: false,
+                reputation: Default::default(),
             },
             snapshot_hosts: snapshot_hosts::Config {
                 snapshot_hosts_cache_size: 1000,
@@ -460,9 +510,17 @@ impl NetworkConfig {
                 advertise_proxies_interval: time::Duration::hours(1000),
                 enable_inbound: true,
                 enable_outbound: true,
+                enable_quic: false,
             }),
             skip_tombstones: None,
             received_messages_rate_limits: messages_limits::Config::default(),
+            adaptive_rate_limits: adaptive::Config::default(),
+            encrypted_transport: false,
+            require_encrypted_transport: false,
+            traffic_recorder: None,
+            tx_gossip: None,
+            peer_discovery: None,
+            hole_punching: None,
             #[cfg(test)]
             event_sink: near_async::messaging::IntoSender::into_sender(
                 near_async::messaging::noop(),
@@ -521,6 +579,24 @@ impl NetworkConfig {
         if let Err(err) = self.received_messages_rate_limits.validate() {
             anyhow::bail!("One or more invalid rate limits: {err:?}");
         }
+        self.adaptive_rate_limits.validate().context("adaptive_rate_limits")?;
+        if let Some(peer_discovery) = &self.peer_discovery {
+            peer_discovery.validate().context("peer_discovery")?;
+        }
+        if self.require_encrypted_transport {
+            if !self.encrypted_transport {
+                anyhow::bail!("require_encrypted_transport requires encrypted_transport");
+            }
+            if !noise::is_supported(&self.node_key) {
+                anyhow::bail!("require_encrypted_transport requires an ED25519 node key");
+            }
+        }
+        if let Some(hole_punching) = &self.hole_punching {
+            if self.node_addr.is_none() {
+                anyhow::bail!("hole_punching requires the node to listen for connections");
+            }
+            hole_punching.validate().context("hole_punching")?;
+        }
 
         Ok(VerifiedConfig { node_id: self.node_id(), inner: self })
     }
@@ -1,5 +1,7 @@
+use crate::discovery;
+use crate::hole_punching;
 use crate::network_protocol::PeerAddr;
-use crate::rate_limits::messages_limits;
+use crate::rate_limits::{adaptive, messages_limits};
 use crate::stun;
 use near_async::time::Duration;
 
@@ -68,6 +70,11 @@ fn default_peer_expiration_duration() -> Duration {
     Duration::seconds(7 * 24 * 60 * 60)
 }
 
+/// Time after which the reputation score of a peer drops to half of its value.
+fn default_peer_reputation_half_life() -> Duration {
+    Duration::seconds(60 * 60)
+}
+
 /// This is a list of public STUN servers provided by Google,
 /// which are known to have good availability. To avoid trusting
 /// a centralized entity (and DNS used for domain resolution),
@@ -163,6 +170,12 @@ pub struct Config {
     #[serde(default = "default_peer_expiration_duration")]
     #[serde(with = "near_async::time::serde_duration_as_std")]
     pub peer_expiration_duration: Duration,
+    /// Time after which the reputation score of a peer drops to half of its value.
+    /// Peers are scored based on their behaviour: invalid messages, rate limit violations
+    /// and slow responses lower the score, while timely responses raise it.
+    #[serde(default = "default_peer_reputation_half_life")]
+    #[serde(with = "near_async::time::serde_duration_as_std")]
+    pub peer_reputation_half_life: Duration,
 
     /// List of the public addresses (in the format "<node public key>@<IP>:<port>") of trusted nodes,
     /// which are willing to route messages to this node. Useful only if this node is a validator.
@@ -231,6 +244,10 @@ fn default_tier1_new_connections_per_attempt() -> u64 {
     50
 }
 
+fn default_traffic_recording_max_bytes() -> u64 {
+    10 * bytesize::GIB
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
 pub struct ExperimentalConfig {
     // If true - don't allow any inbound connections.
@@ -265,10 +282,48 @@ pub struct ExperimentalConfig {
     #[serde(default = "default_tier1_new_connections_per_attempt")]
     pub tier1_new_connections_per_attempt: u64,
 
+    /// See `near_network::config::Tier1::enable_quic`.
+    #[serde(default)]
+    pub tier1_enable_quic: bool,
+
     /// See `NetworkConfig`.
     /// Fields set here will override the NetworkConfig fields.
     #[serde(default)]
     pub network_config_overrides: NetworkConfigOverrides,
+
+    /// See `near_network::config::NetworkConfig::encrypted_transport`.
+    #[serde(default)]
+    pub encrypted_transport: bool,
+
+    /// See `near_network::config::NetworkConfig::require_encrypted_transport`.
+    /// Implies `encrypted_transport`.
+    #[serde(default)]
+    pub require_encrypted_transport: bool,
+
+    /// If set, all the messages sent and received by the node are recorded to this file.
+    /// See `near_network::recorder`.
+    #[serde(default)]
+    pub traffic_recording_path: Option<std::path::PathBuf>,
+
+    /// Recording stops once the file reaches this size.
+    #[serde(default = "default_traffic_recording_max_bytes")]
+    pub traffic_recording_max_bytes: u64,
+
+    /// See `near_network::config::NetworkConfig::adaptive_rate_limits`.
+    #[serde(default)]
+    pub adaptive_rate_limits: adaptive::Config,
+
+    /// See `near_network::config::NetworkConfig::tx_gossip`.
+    #[serde(default)]
+    pub tx_gossip: bool,
+
+    /// See `near_network::config::NetworkConfig::peer_discovery`.
+    #[serde(default)]
+    pub peer_discovery: Option<discovery::Config>,
+
+    /// See `near_network::config::NetworkConfig::hole_punching`.
+    #[serde(default)]
+    pub hole_punching: Option<hole_punching::Config>,
 }
 
 /// Overrides values from NetworkConfig.
@@ -289,6 +344,29 @@ pub struct NetworkConfigOverrides {
     pub received_messages_rate_limits: Option<messages_limits::OverrideConfig>,
 }
 
+/// Part of the network config which can be updated without restarting the node.
+/// See `core/dyn-configs`.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UpdatableNetworkConfig {
+    /// See `NetworkConfigOverrides::received_messages_rate_limits`.
+    pub received_messages_rate_limits: Option<messages_limits::OverrideConfig>,
+    /// See `ExperimentalConfig::adaptive_rate_limits`.
+    pub adaptive_rate_limits: adaptive::Config,
+}
+
+impl From<&Config> for UpdatableNetworkConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            received_messages_rate_limits: config
+                .experimental
+                .network_config_overrides
+                .received_messages_rate_limits
+                .clone(),
+            adaptive_rate_limits: config.experimental.adaptive_rate_limits.clone(),
+        }
+    }
+}
+
 impl Default for ExperimentalConfig {
     fn default() -> Self {
         ExperimentalConfig {
@@ -299,7 +377,16 @@ impl Default for ExperimentalConfig {
             tier1_enable_outbound: default_tier1_enable_outbound(),
             tier1_connect_interval: default_tier1_connect_interval(),
             tier1_new_connections_per_attempt: default_tier1_new_connections_per_attempt(),
+            tier1_enable_quic: false,
             network_config_overrides: Default::default(),
+            encrypted_transport: false,
+            require_encrypted_transport: false,
+            traffic_recording_path: None,
+            traffic_recording_max_bytes: default_traffic_recording_max_bytes(),
+            adaptive_rate_limits: Default::default(),
+            tx_gossip: false,
+            peer_discovery: None,
+            hole_punching: None,
         }
     }
 }
@@ -329,6 +416,7 @@ impl Default for Config {
             peer_stats_period: default_peer_stats_period(),
             monitor_peers_max_period: default_monitor_peers_max_period(),
             peer_expiration_duration: default_peer_expiration_duration(),
+            peer_reputation_half_life: default_peer_reputation_half_life(),
             public_addrs: vec![],
             allow_private_ip_in_public_addrs: false,
             trusted_stun_servers: default_trusted_stun_servers(),
@@ -0,0 +1,252 @@
+//! Discovery of peers from DNS seeds and signed peer lists.
+//!
+//! Bootstrapping a node depends on `boot_nodes` from the config, which have to be edited
+//! whenever the boot nodes change. Instead, the node can periodically fetch the peers from:
+//! * TXT records of the configured DNS names (DNS seeds),
+//! * local files with peer lists, e.g. served by a sidecar.
+//!
+//! Every entry (TXT record or a line of the file) has the form
+//! `<peer_info> <expires_at> <signature>`, where `peer_info` is `<peer_id>@<ip:port>`,
+//! `expires_at` is a unix timestamp in seconds and `signature` is made with the key of the
+//! publisher over the `EntryPayload` (see `sign_entry`). The payload binds the entry to the
+//! chain, so that entries published for one chain can't be used on another, and to the
+//! expiry time, so that stale entries can't be replayed forever. Entries which are not
+//! signed by the configured `publisher_key` or which have expired are ignored. The
+//! discovered peers are added to the peer store as indirect peers.
+use crate::network_protocol::PeerInfo;
+use crate::stats::metrics;
+use borsh::BorshSerialize;
+use futures::future::BoxFuture;
+use near_async::time;
+use near_crypto::{PublicKey, SecretKey, Signature};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests;
+
+fn default_refresh_interval() -> time::Duration {
+    time::Duration::minutes(10)
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Config {
+    /// DNS names whose TXT records contain the signed peer entries.
+    #[serde(default)]
+    pub dns_seeds: Vec<String>,
+    /// Files with the signed peer entries, one per line.
+    #[serde(default)]
+    pub peer_list_files: Vec<PathBuf>,
+    /// Key with which the entries have to be signed.
+    pub publisher_key: PublicKey,
+    /// How often the peers are fetched.
+    #[serde(default = "default_refresh_interval")]
+    #[serde(with = "near_async::time::serde_duration_as_std")]
+    pub refresh_interval: time::Duration,
+}
+
+impl Config {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.dns_seeds.is_empty() && self.peer_list_files.is_empty() {
+            anyhow::bail!("expected at least one of dns_seeds or peer_list_files");
+        }
+        if self.refresh_interval <= time::Duration::ZERO {
+            anyhow::bail!("refresh_interval has to be positive");
+        }
+        Ok(())
+    }
+}
+
+/// Domain separation tag of the signed entries, so that a signature of an entry can't be
+/// passed off as a signature of anything else made with the publisher key, and vice versa.
+const ENTRY_DOMAIN_TAG: &str = "near-peer-discovery-entry-v1";
+
+/// Data signed by the publisher for every entry.
+#[derive(BorshSerialize)]
+struct EntryPayload<'a> {
+    tag: &'a str,
+    chain_id: &'a str,
+    peer_info: &'a str,
+    /// Unix timestamp in seconds after which the entry is no longer valid.
+    expires_at: i64,
+}
+
+impl EntryPayload<'_> {
+    fn to_bytes(&self) -> Vec<u8> {
+        borsh::to_vec(self).unwrap()
+    }
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub(crate) enum EntryError {
+    #[error("expected <peer_info> <expires_at> <signature>")]
+    Malformed,
+    #[error("invalid peer info: {0}")]
+    PeerInfo(String),
+    #[error("peer info has no address")]
+    NoAddr,
+    #[error("invalid signature")]
+    Signature,
+    #[error("entry expired at {0}")]
+    Expired(i64),
+}
+
+/// Signs the peer info, producing an entry to publish in a DNS seed or a peer list.
+/// The entry is valid only for the chain `chain_id` and until `expires_at`.
+pub fn sign_entry(
+    peer_info: &PeerInfo,
+    chain_id: &str,
+    expires_at: time::Utc,
+    key: &SecretKey,
+) -> String {
+    let peer_info = peer_info.to_string();
+    let expires_at = expires_at.unix_timestamp();
+    let payload =
+        EntryPayload { tag: ENTRY_DOMAIN_TAG, chain_id, peer_info: &peer_info, expires_at };
+    let signature = key.sign(&payload.to_bytes());
+    format!("{peer_info} {expires_at} {signature}")
+}
+
+/// Parses the entry and verifies that it is signed by `publisher_key` for the chain
+/// `chain_id` and has not expired at `now`.
+pub(crate) fn parse_entry(
+    entry: &str,
+    chain_id: &str,
+    publisher_key: &PublicKey,
+    now: time::Utc,
+) -> Result<PeerInfo, EntryError> {
+    let mut parts = entry.split_whitespace();
+    let (Some(peer_info_str), Some(expires_at), Some(signature), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(EntryError::Malformed);
+    };
+    let expires_at: i64 = expires_at.parse().map_err(|_| EntryError::Malformed)?;
+    let signature: Signature = signature.parse().map_err(|_| EntryError::Malformed)?;
+    let payload =
+        EntryPayload { tag: ENTRY_DOMAIN_TAG, chain_id, peer_info: peer_info_str, expires_at };
+    if !signature.verify(&payload.to_bytes(), publisher_key) {
+        return Err(EntryError::Signature);
+    }
+    if now.unix_timestamp() >= expires_at {
+        return Err(EntryError::Expired(expires_at));
+    }
+    let peer_info: PeerInfo =
+        peer_info_str.parse().map_err(|err| EntryError::PeerInfo(format!("{err:?}")))?;
+    if peer_info.addr.is_none() {
+        return Err(EntryError::NoAddr);
+    }
+    Ok(peer_info)
+}
+
+/// Source of the DNS TXT records. Can be replaced in tests.
+pub trait TxtResolver: Send + Sync + 'static {
+    fn lookup_txt(&self, name: &str) -> BoxFuture<'static, anyhow::Result<Vec<String>>>;
+}
+
+/// Resolver using the DNS servers configured in the system.
+struct SystemResolver(hickory_resolver::TokioAsyncResolver);
+
+impl SystemResolver {
+    fn new() -> Self {
+        let resolver = hickory_resolver::TokioAsyncResolver::tokio_from_system_conf()
+            .unwrap_or_else(|err| {
+                tracing::warn!(target: "network", ?err, "failed to read the system DNS config, using the default one");
+                hickory_resolver::TokioAsyncResolver::tokio(
+                    Default::default(),
+                    Default::default(),
+                )
+            });
+        Self(resolver)
+    }
+}
+
+impl TxtResolver for SystemResolver {
+    fn lookup_txt(&self, name: &str) -> BoxFuture<'static, anyhow::Result<Vec<String>>> {
+        let resolver = self.0.clone();
+        let name = name.to_string();
+        Box::pin(async move {
+            let lookup = resolver.txt_lookup(name).await?;
+            // A single TXT record may be split into multiple strings.
+            Ok(lookup
+                .iter()
+                .map(|txt| {
+                    txt.txt_data().iter().map(|data| String::from_utf8_lossy(data)).collect()
+                })
+                .collect())
+        })
+    }
+}
+
+pub(crate) struct Discovery {
+    config: Config,
+    /// Chain for which the entries have to be signed.
+    chain_id: String,
+    resolver: Arc<dyn TxtResolver>,
+}
+
+impl Discovery {
+    pub fn new(config: Config, chain_id: String) -> Self {
+        Self::with_resolver(config, chain_id, Arc::new(SystemResolver::new()))
+    }
+
+    pub fn with_resolver(config: Config, chain_id: String, resolver: Arc<dyn TxtResolver>) -> Self {
+        Self { config, chain_id, resolver }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Fetches the entries from all the sources and returns the validly signed peers
+    /// whose entries have not expired. A failing source doesn't affect the other ones.
+    pub async fn discover(&self, clock: &time::Clock) -> Vec<PeerInfo> {
+        let mut entries = vec![];
+        for name in &self.config.dns_seeds {
+            match self.resolver.lookup_txt(name).await {
+                Ok(records) => entries.extend(records),
+                Err(err) => {
+                    metrics::PEER_DISCOVERY_SOURCE_ERRORS.with_label_values(&["dns"]).inc();
+                    tracing::warn!(target: "network", name, ?err, "failed to resolve the DNS seed");
+                }
+            }
+        }
+        for path in &self.config.peer_list_files {
+            match tokio::fs::read_to_string(path).await {
+                Ok(content) => entries.extend(content.lines().map(str::to_string)),
+                Err(err) => {
+                    metrics::PEER_DISCOVERY_SOURCE_ERRORS.with_label_values(&["file"]).inc();
+                    tracing::warn!(target: "network", ?path, ?err, "failed to read the peer list");
+                }
+            }
+        }
+
+        let now = clock.now_utc();
+        let mut seen = HashSet::new();
+        let mut peers = vec![];
+        for entry in entries {
+            let entry = entry.trim();
+            if entry.is_empty() || entry.starts_with('#') {
+                continue;
+            }
+            match parse_entry(entry, &self.chain_id, &self.config.publisher_key, now) {
+                Ok(peer_info) => {
+                    metrics::PEER_DISCOVERY_ENTRIES.with_label_values(&["valid"]).inc();
+                    if seen.insert(peer_info.id.clone()) {
+                        peers.push(peer_info);
+                    }
+                }
+                Err(err) => {
+                    let outcome = match err {
+                        EntryError::Expired(_) => "expired",
+                        _ => "invalid",
+                    };
+                    metrics::PEER_DISCOVERY_ENTRIES.with_label_values(&[outcome]).inc();
+                    tracing::debug!(target: "network", entry, %err, "ignoring a discovered peer");
+                }
+            }
+        }
+        peers
+    }
+}
@@ -0,0 +1,178 @@
+use crate::discovery::{Config, Discovery, EntryError, TxtResolver, parse_entry, sign_entry};
+use crate::network_protocol::PeerInfo;
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+use futures::future::BoxFuture;
+use near_async::time;
+use near_crypto::{KeyType, SecretKey};
+use near_o11y::testonly::init_test_logger;
+use std::collections::HashMap;
+use std::io::Write as _;
+use std::sync::Arc;
+
+/// Stand-in for the DNS, serving the TXT records from memory.
+struct FakeResolver(HashMap<String, Vec<String>>);
+
+impl TxtResolver for FakeResolver {
+    fn lookup_txt(&self, name: &str) -> BoxFuture<'static, anyhow::Result<Vec<String>>> {
+        let records = self.0.get(name).cloned().ok_or_else(|| anyhow::anyhow!("NXDOMAIN"));
+        Box::pin(async move { records })
+    }
+}
+
+fn make_peer_info(rng: &mut impl rand::Rng) -> PeerInfo {
+    let mut peer_info = data::make_peer_info(rng);
+    peer_info.account_id = None;
+    peer_info
+}
+
+const CHAIN_ID: &str = "testnet";
+
+/// Signs an entry for `CHAIN_ID`, valid for an hour after `clock` time.
+fn sign(peer_info: &PeerInfo, clock: &time::Clock, key: &SecretKey) -> String {
+    sign_entry(peer_info, CHAIN_ID, clock.now_utc() + time::Duration::hours(1), key)
+}
+
+fn make_config(publisher: &SecretKey) -> Config {
+    Config {
+        dns_seeds: vec![],
+        peer_list_files: vec![],
+        publisher_key: publisher.public_key(),
+        refresh_interval: time::Duration::minutes(10),
+    }
+}
+
+#[test]
+fn entries() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let now = clock.now_utc();
+    let publisher = SecretKey::from_random(KeyType::ED25519).unwrap();
+    let publisher_key = publisher.public_key();
+    let peer_info = make_peer_info(&mut rng);
+    let entry = sign(&peer_info, &clock.clock(), &publisher);
+    assert_eq!(parse_entry(&entry, CHAIN_ID, &publisher_key, now), Ok(peer_info.clone()));
+
+    let other = SecretKey::from_random(KeyType::ED25519).unwrap();
+    assert_eq!(parse_entry(&entry, CHAIN_ID, &other.public_key(), now), Err(EntryError::Signature));
+    assert_eq!(
+        parse_entry(&sign(&peer_info, &clock.clock(), &other), CHAIN_ID, &publisher_key, now),
+        Err(EntryError::Signature)
+    );
+    // Tampering with the peer info invalidates the signature.
+    let tampered = entry.replacen('@', "@1", 1);
+    assert_eq!(parse_entry(&tampered, CHAIN_ID, &publisher_key, now), Err(EntryError::Signature));
+    // So does extending the expiry time.
+    let [peer_info_str, expires_at, signature] = entry.split_whitespace().collect::<Vec<_>>()[..]
+    else {
+        panic!("unexpected entry {entry}");
+    };
+    let extended = format!("{peer_info_str} {expires_at}0 {signature}");
+    assert_eq!(parse_entry(&extended, CHAIN_ID, &publisher_key, now), Err(EntryError::Signature));
+    // Entries are valid only for the chain they were signed for.
+    assert_eq!(parse_entry(&entry, "mainnet", &publisher_key, now), Err(EntryError::Signature));
+    // A bare signature of the peer info is not accepted.
+    let bare_signature = publisher.sign(peer_info_str.as_bytes());
+    assert_eq!(
+        parse_entry(
+            &format!("{peer_info_str} {expires_at} {bare_signature}"),
+            CHAIN_ID,
+            &publisher_key,
+            now
+        ),
+        Err(EntryError::Signature)
+    );
+
+    clock.advance(time::Duration::hours(1));
+    assert_eq!(
+        parse_entry(&entry, CHAIN_ID, &publisher_key, clock.now_utc()),
+        Err(EntryError::Expired(expires_at.parse().unwrap()))
+    );
+
+    assert_eq!(
+        parse_entry(&peer_info.to_string(), CHAIN_ID, &publisher_key, now),
+        Err(EntryError::Malformed)
+    );
+    assert_eq!(
+        parse_entry(&format!("{entry} x"), CHAIN_ID, &publisher_key, now),
+        Err(EntryError::Malformed)
+    );
+    let no_addr = PeerInfo { addr: None, ..peer_info };
+    assert_eq!(
+        parse_entry(&sign(&no_addr, &clock.clock(), &publisher), CHAIN_ID, &publisher_key, now),
+        Err(EntryError::NoAddr)
+    );
+}
+
+#[tokio::test]
+async fn dns_seeds() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let clock = clock.clock();
+    let publisher = SecretKey::from_random(KeyType::ED25519).unwrap();
+    let other = SecretKey::from_random(KeyType::ED25519).unwrap();
+    let peers: Vec<_> = (0..3).map(|_| make_peer_info(&mut rng)).collect();
+    let resolver = FakeResolver(HashMap::from([
+        (
+            "seed1.example.com".to_string(),
+            vec![sign(&peers[0], &clock, &publisher), sign(&peers[1], &clock, &other)],
+        ),
+        (
+            "seed2.example.com".to_string(),
+            vec![
+                sign(&peers[2], &clock, &publisher),
+                sign(&peers[0], &clock, &publisher),
+                sign_entry(&peers[1], CHAIN_ID, clock.now_utc(), &publisher),
+            ],
+        ),
+    ]));
+    let config = Config {
+        dns_seeds: vec![
+            "seed1.example.com".to_string(),
+            "missing.example.com".to_string(),
+            "seed2.example.com".to_string(),
+        ],
+        ..make_config(&publisher)
+    };
+    let discovery = Discovery::with_resolver(config, CHAIN_ID.to_string(), Arc::new(resolver));
+    // Entries signed by other keys and expired entries are ignored, duplicates are removed,
+    // and the missing seed doesn't affect the other ones.
+    assert_eq!(discovery.discover(&clock).await, vec![peers[0].clone(), peers[2].clone()]);
+}
+
+#[tokio::test]
+async fn peer_list_files() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let clock = clock.clock();
+    let publisher = SecretKey::from_random(KeyType::ED25519).unwrap();
+    let peers: Vec<_> = (0..2).map(|_| make_peer_info(&mut rng)).collect();
+    let mut file = tempfile::NamedTempFile::new().unwrap();
+    writeln!(file, "# peers signed by the publisher").unwrap();
+    writeln!(file, "{}", sign(&peers[0], &clock, &publisher)).unwrap();
+    writeln!(file).unwrap();
+    writeln!(file, "garbage").unwrap();
+    writeln!(file, "  {}  ", sign(&peers[1], &clock, &publisher)).unwrap();
+    let config = Config {
+        peer_list_files: vec![file.path().to_path_buf(), "/nonexistent/peers.txt".into()],
+        ..make_config(&publisher)
+    };
+    let discovery = Discovery::with_resolver(
+        config,
+        CHAIN_ID.to_string(),
+        Arc::new(FakeResolver(HashMap::new())),
+    );
+    assert_eq!(discovery.discover(&clock).await, peers);
+}
+
+#[test]
+fn validate() {
+    let publisher = SecretKey::from_random(KeyType::ED25519).unwrap();
+    assert!(make_config(&publisher).validate().is_err());
+    let config =
+        Config { dns_seeds: vec!["seed.example.com".to_string()], ..make_config(&publisher) };
+    assert!(config.validate().is_ok());
+    assert!(Config { refresh_interval: time::Duration::ZERO, ..config }.validate().is_err());
+}
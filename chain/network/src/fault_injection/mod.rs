@@ -0,0 +1,152 @@
+//! Injection of network faults, for testing the node under adverse network conditions.
+//!
+//! A set of rules selects messages by the peer, the message type and a time window, and
+//! drops, delays, duplicates or reorders them. On a real node the rules are applied to
+//! the messages received from the peers (see `PeerActor`) and can be set via the
+//! `adv_network_faults` JSON-RPC method, which is available only with the `test_features`.
+//! To model a partition, set the rules on the nodes on both sides of it.
+//!
+//! The test-loop network applies the same rules to the requests sent by a node, where the
+//! delays are simulated deterministically.
+use crate::stats::metrics;
+use near_async::time;
+use near_primitives::network::PeerId;
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng as _, SeedableRng as _};
+
+#[cfg(test)]
+mod tests;
+
+/// What to do with the message matched by a rule.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize, strum::IntoStaticStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    Drop,
+    /// Delivers the message after the given delay.
+    Delay(#[serde(with = "near_async::time::serde_duration_as_std")] time::Duration),
+    /// Delivers the message and the given number of its copies.
+    Duplicate(usize),
+    /// Delivers the message after a random delay of at most the given duration, so that
+    /// it may arrive after the messages which were received later.
+    Reorder(#[serde(with = "near_async::time::serde_duration_as_std")] time::Duration),
+}
+
+fn default_probability() -> f64 {
+    1.
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Rule {
+    /// Peers whose messages are affected. All the peers if empty.
+    #[serde(default)]
+    pub peers: Vec<PeerId>,
+    /// Types of the affected messages, e.g. "Block" or "PartialEncodedChunk".
+    /// For routed messages, the type of the body is matched. All the types if empty.
+    #[serde(default)]
+    pub message_types: Vec<String>,
+    /// The rule applies from this time after the rules have been set.
+    #[serde(default, with = "near_async::time::serde_duration_as_std")]
+    pub start: time::Duration,
+    /// The rule stops applying after this time since `start`. Never if not set.
+    #[serde(default, with = "near_async::time::serde_opt_duration_as_std")]
+    pub duration: Option<time::Duration>,
+    /// Probability with which the rule applies to a matching message.
+    #[serde(default = "default_probability")]
+    pub probability: f64,
+    pub action: Action,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Config {
+    /// Seed of the randomness of `probability` and `Action::Reorder`, for reproducibility.
+    #[serde(default)]
+    pub seed: u64,
+    /// For every message only the first applying rule is executed.
+    pub rules: Vec<Rule>,
+}
+
+impl Config {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for (i, rule) in self.rules.iter().enumerate() {
+            if !(0. ..=1.).contains(&rule.probability) {
+                anyhow::bail!("rules[{i}]: probability has to be in [0, 1]");
+            }
+            if rule.start < time::Duration::ZERO
+                || rule.duration.is_some_and(|d| d < time::Duration::ZERO)
+            {
+                anyhow::bail!("rules[{i}]: start and duration have to be non-negative");
+            }
+            match rule.action {
+                Action::Delay(d) | Action::Reorder(d) if d < time::Duration::ZERO => {
+                    anyhow::bail!("rules[{i}]: delay has to be non-negative")
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sets the network faults to inject, replacing the previous ones. `None` disables the injection.
+#[derive(actix::Message, Clone, Debug)]
+#[rtype(result = "()")]
+pub struct SetFaultInjection(pub Option<Config>);
+
+/// Decides the fate of the messages according to the configured rules.
+pub struct FaultInjector {
+    config: Config,
+    /// Time at which the rules have been set, time windows are relative to it.
+    started: time::Instant,
+    rng: Mutex<StdRng>,
+}
+
+impl FaultInjector {
+    pub fn new(config: Config, now: time::Instant) -> Self {
+        let rng = Mutex::new(StdRng::seed_from_u64(config.seed));
+        Self { config, started: now, rng }
+    }
+
+    /// Returns the delays after which the message should be delivered, one per copy:
+    /// an empty list if the message should be dropped, `[ZERO]` if it should be delivered
+    /// as usual. `peer_id` is the other side of the connection, if known.
+    pub fn deliveries(
+        &self,
+        now: time::Instant,
+        peer_id: Option<&PeerId>,
+        message_type: &str,
+    ) -> Vec<time::Duration> {
+        let elapsed = now - self.started;
+        let mut rng = self.rng.lock();
+        for rule in &self.config.rules {
+            if elapsed < rule.start || rule.duration.is_some_and(|d| elapsed >= rule.start + d) {
+                continue;
+            }
+            if !rule.peers.is_empty() && !peer_id.is_some_and(|p| rule.peers.contains(p)) {
+                continue;
+            }
+            if !rule.message_types.is_empty()
+                && !rule.message_types.iter().any(|t| t == message_type)
+            {
+                continue;
+            }
+            if rule.probability < 1. && !rng.gen_bool(rule.probability) {
+                continue;
+            }
+            metrics::NETWORK_FAULTS_INJECTED
+                .with_label_values(&[(&rule.action).into(), message_type])
+                .inc();
+            return match rule.action {
+                Action::Drop => vec![],
+                Action::Delay(delay) => vec![delay],
+                Action::Duplicate(copies) => vec![time::Duration::ZERO; copies + 1],
+                Action::Reorder(max_delay) => {
+                    let nanos = rng.gen_range(0..=max_delay.whole_nanoseconds() as u64);
+                    vec![time::Duration::nanoseconds(nanos as i64)]
+                }
+            };
+        }
+        vec![time::Duration::ZERO]
+    }
+}
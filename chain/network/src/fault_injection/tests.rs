@@ -0,0 +1,147 @@
+use crate::fault_injection::{Action, Config, FaultInjector, Rule};
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+use near_async::time;
+
+fn rule(action: Action) -> Rule {
+    Rule {
+        peers: vec![],
+        message_types: vec![],
+        start: time::Duration::ZERO,
+        duration: None,
+        probability: 1.,
+        action,
+    }
+}
+
+#[test]
+fn actions() {
+    let clock = time::FakeClock::default();
+    let delay = time::Duration::milliseconds(300);
+    for (action, want) in [
+        (Action::Drop, vec![]),
+        (Action::Delay(delay), vec![delay]),
+        (Action::Duplicate(2), vec![time::Duration::ZERO; 3]),
+    ] {
+        let faults = FaultInjector::new(Config { seed: 0, rules: vec![rule(action)] }, clock.now());
+        assert_eq!(faults.deliveries(clock.now(), None, "Block"), want);
+    }
+
+    let max_delay = time::Duration::seconds(1);
+    let faults = FaultInjector::new(
+        Config { seed: 0, rules: vec![rule(Action::Reorder(max_delay))] },
+        clock.now(),
+    );
+    for _ in 0..100 {
+        let got = faults.deliveries(clock.now(), None, "Block");
+        assert_eq!(got.len(), 1);
+        assert!(time::Duration::ZERO <= got[0] && got[0] <= max_delay);
+    }
+}
+
+#[test]
+fn selectors() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let peer1 = data::make_peer_id(&mut rng);
+    let peer2 = data::make_peer_id(&mut rng);
+    let delay = time::Duration::milliseconds(300);
+    let faults = FaultInjector::new(
+        Config {
+            seed: 0,
+            rules: vec![
+                Rule {
+                    peers: vec![peer1.clone()],
+                    message_types: vec!["Block".to_string()],
+                    ..rule(Action::Drop)
+                },
+                Rule { message_types: vec!["Block".to_string()], ..rule(Action::Delay(delay)) },
+            ],
+        },
+        clock.now(),
+    );
+    assert_eq!(faults.deliveries(clock.now(), Some(&peer1), "Block"), vec![]);
+    // Only the first applying rule is executed.
+    assert_eq!(faults.deliveries(clock.now(), Some(&peer2), "Block"), vec![delay]);
+    assert_eq!(faults.deliveries(clock.now(), None, "Block"), vec![delay]);
+    assert_eq!(
+        faults.deliveries(clock.now(), Some(&peer1), "BlockRequest"),
+        vec![time::Duration::ZERO]
+    );
+}
+
+#[test]
+fn time_window() {
+    let clock = time::FakeClock::default();
+    let faults = FaultInjector::new(
+        Config {
+            seed: 0,
+            rules: vec![Rule {
+                start: time::Duration::seconds(10),
+                duration: Some(time::Duration::seconds(5)),
+                ..rule(Action::Drop)
+            }],
+        },
+        clock.now(),
+    );
+    assert_eq!(faults.deliveries(clock.now(), None, "Block"), vec![time::Duration::ZERO]);
+    clock.advance(time::Duration::seconds(10));
+    assert_eq!(faults.deliveries(clock.now(), None, "Block"), vec![]);
+    clock.advance(time::Duration::seconds(5));
+    assert_eq!(faults.deliveries(clock.now(), None, "Block"), vec![time::Duration::ZERO]);
+}
+
+#[test]
+fn deterministic() {
+    let clock = time::FakeClock::default();
+    let config = Config {
+        seed: 42,
+        rules: vec![
+            Rule { probability: 0.5, ..rule(Action::Drop) },
+            rule(Action::Reorder(time::Duration::seconds(1))),
+        ],
+    };
+    let run = || {
+        let faults = FaultInjector::new(config.clone(), clock.now());
+        (0..100).map(|_| faults.deliveries(clock.now(), None, "Block")).collect::<Vec<_>>()
+    };
+    let deliveries = run();
+    assert_eq!(deliveries, run());
+    let dropped = deliveries.iter().filter(|d| d.is_empty()).count();
+    assert!(0 < dropped && dropped < 100);
+}
+
+#[test]
+fn validate() {
+    assert!(Config::default().validate().is_ok());
+    let invalid = [
+        Rule { probability: 1.5, ..rule(Action::Drop) },
+        Rule { start: time::Duration::seconds(-1), ..rule(Action::Drop) },
+        rule(Action::Delay(time::Duration::seconds(-1))),
+    ];
+    for rule in invalid {
+        assert!(Config { seed: 0, rules: vec![rule] }.validate().is_err());
+    }
+}
+
+#[test]
+fn parse_config() {
+    let config: Config = serde_json::from_str(
+        r#"{
+            "rules": [{
+                "message_types": ["Block"],
+                "duration": {"secs": 30, "nanos": 0},
+                "action": {"delay": {"secs": 1, "nanos": 0}}
+            }]
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.rules,
+        vec![Rule {
+            message_types: vec!["Block".to_string()],
+            duration: Some(time::Duration::seconds(30)),
+            ..rule(Action::Delay(time::Duration::seconds(1)))
+        }]
+    );
+}
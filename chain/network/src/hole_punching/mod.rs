@@ -0,0 +1,273 @@
+//! NAT traversal with TCP hole punching.
+//!
+//! Nodes behind NAT can't accept inbound connections, so two such nodes can't connect to
+//! each other, even though both are connected to the rest of the network. To connect them,
+//! both nodes open a TCP connection to the public address of the other one from the port
+//! of their listener at about the same time: the SYN sent by a node creates a mapping in its
+//! NAT, which lets in the SYN sent by the other node (TCP simultaneous open).
+//!
+//! The attempt is coordinated through the peers which both nodes are connected to:
+//! * the initiator sends a `HolePunchRequest` routed message with its public address,
+//! * the target replies with a `HolePunchResponse` with its public address and starts
+//!   connecting right away, as the outbound side of the connection,
+//! * once the response arrives, the initiator starts connecting as the inbound side.
+//!
+//! The SYN sent first by the target is likely to be dropped by the NAT of the initiator,
+//! but it is retransmitted until the initiator's SYN opens the way. If the initiator is not
+//! behind NAT after all, the SYN is accepted by its listener, which is consistent with
+//! the initiator being the inbound side.
+//!
+//! The public address of the node is its IP as reported by the STUN servers and the port of
+//! its listener, which assumes that the NAT preserves the port of the outbound connections.
+//!
+//! The address in a request or response is chosen by the other side, so the node only dials
+//! it if it is a global unicast address with the IP the other side is known under. Otherwise
+//! any peer could make the node open connections to arbitrary hosts. On top of that, requests
+//! from the same peer are rate limited.
+use crate::config_json::default_trusted_stun_servers;
+use crate::network_protocol::HolePunch;
+use crate::stun;
+use lru::LruCache;
+use near_async::time;
+use near_primitives::network::PeerId;
+use parking_lot::{Mutex, RwLock};
+use rand::Rng as _;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroUsize;
+
+#[cfg(test)]
+mod tests;
+
+/// How often the public address of the node is refreshed.
+pub(crate) const PUBLIC_ADDR_REFRESH_INTERVAL: time::Duration = time::Duration::minutes(10);
+/// Limit on the number of attempts in progress at the same time, initiated by either side.
+const MAX_CONCURRENT_ATTEMPTS: usize = 16;
+/// Minimal time between two requests from the same peer which the node responds to.
+const MIN_REQUEST_INTERVAL: time::Duration = time::Duration::minutes(1);
+/// Number of peers for which the time of the last request is remembered.
+const REQUEST_TIMES_CACHE_SIZE: usize = 1024;
+
+fn default_attempts() -> usize {
+    3
+}
+
+fn default_attempt_timeout() -> time::Duration {
+    time::Duration::seconds(5)
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Config {
+    /// STUN servers used to discover the public IP of the node.
+    #[serde(default = "default_trusted_stun_servers")]
+    pub stun_servers: Vec<stun::ServerAddr>,
+    /// Number of times each side tries to open the connection.
+    #[serde(default = "default_attempts")]
+    pub attempts: usize,
+    /// How long a single attempt may take.
+    #[serde(default = "default_attempt_timeout")]
+    #[serde(with = "near_async::time::serde_duration_as_std")]
+    pub attempt_timeout: time::Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            stun_servers: default_trusted_stun_servers(),
+            attempts: default_attempts(),
+            attempt_timeout: default_attempt_timeout(),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.attempts == 0 {
+            anyhow::bail!("attempts has to be positive");
+        }
+        if self.attempt_timeout <= time::Duration::ZERO {
+            anyhow::bail!("attempt_timeout has to be positive");
+        }
+        Ok(())
+    }
+}
+
+struct Attempt {
+    /// Nonce of the request, until the response to it has been received.
+    awaiting_response: Option<u64>,
+    /// Time after which the attempt is considered finished.
+    deadline: time::Instant,
+}
+
+/// Whether the IP is a unicast address routable on the internet.
+// TODO: use `ip.is_global()` instead, once it is stable.
+fn is_global_unicast(ip: IpAddr) -> bool {
+    match ip.to_canonical() {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Shared address space (RFC 6598).
+                || (a == 100 && (b & 0b1100_0000) == 64)
+                // Reserved for future use (RFC 1112) and "this network" (RFC 791).
+                || a >= 240
+                || a == 0)
+        }
+        IpAddr::V6(ip) => {
+            let segments = ip.segments();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // Unique local addresses (RFC 4193).
+                || (segments[0] & 0xfe00) == 0xfc00
+                // Link-local unicast addresses (RFC 4291).
+                || (segments[0] & 0xffc0) == 0xfe80
+                // Documentation addresses (RFC 3849).
+                || (segments[0] == 0x2001 && segments[1] == 0xdb8))
+        }
+    }
+}
+
+/// Whether the node may connect to the address sent by a peer, given the IPs the peer
+/// is known under.
+pub(crate) fn is_valid_peer_addr(addr: SocketAddr, known_ips: &[IpAddr]) -> bool {
+    let ip = addr.ip().to_canonical();
+    addr.port() != 0
+        && is_global_unicast(ip)
+        && known_ips.iter().any(|known_ip| known_ip.to_canonical() == ip)
+}
+
+/// State of the hole punching attempts of the node.
+pub(crate) struct HolePunching {
+    config: Config,
+    /// Public address of the node discovered via STUN.
+    public_addr: RwLock<Option<std::net::SocketAddr>>,
+    /// Attempts in progress, by the other side of the connection.
+    attempts: Mutex<HashMap<PeerId, Attempt>>,
+    /// Time of the last request received from the peer.
+    last_request: Mutex<LruCache<PeerId, time::Instant>>,
+}
+
+impl HolePunching {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            public_addr: RwLock::new(None),
+            attempts: Mutex::new(HashMap::new()),
+            last_request: Mutex::new(LruCache::new(
+                NonZeroUsize::new(REQUEST_TIMES_CACHE_SIZE).unwrap(),
+            )),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn public_addr(&self) -> Option<std::net::SocketAddr> {
+        *self.public_addr.read()
+    }
+
+    /// Discovers the public address of the node, given the address of its listener.
+    pub async fn update_public_addr(&self, clock: &time::Clock, node_addr: std::net::SocketAddr) {
+        let ip = stun::query_all(clock, &self.config.stun_servers, node_addr.is_ipv4()).await;
+        let addr = ip.map(|ip| std::net::SocketAddr::new(ip, node_addr.port()));
+        tracing::debug!(target: "network", ?addr, "discovered public address for hole punching");
+        if addr.is_some() {
+            *self.public_addr.write() = addr;
+        }
+    }
+
+    /// Time after which an attempt started now is finished, for both sides.
+    fn attempt_deadline(&self, now: time::Instant) -> time::Instant {
+        now + self.config.attempt_timeout * (self.config.attempts as u32 + 1)
+    }
+
+    /// Registers an attempt with the peer, unless there is one in progress already
+    /// or there are too many of them. Returns whether the attempt can proceed.
+    fn try_start(
+        &self,
+        now: time::Instant,
+        peer_id: &PeerId,
+        awaiting_response: Option<u64>,
+    ) -> bool {
+        let mut attempts = self.attempts.lock();
+        attempts.retain(|_, attempt| attempt.deadline > now);
+        if attempts.contains_key(peer_id) || attempts.len() >= MAX_CONCURRENT_ATTEMPTS {
+            return false;
+        }
+        let deadline = self.attempt_deadline(now);
+        attempts.insert(peer_id.clone(), Attempt { awaiting_response, deadline });
+        true
+    }
+
+    /// Starts an attempt to connect to the peer. Returns the request to send to it.
+    pub fn request(
+        &self,
+        now: time::Instant,
+        my_addr: std::net::SocketAddr,
+        peer_id: &PeerId,
+    ) -> Option<HolePunch> {
+        let nonce = rand::thread_rng().r#gen();
+        self.try_start(now, peer_id, Some(nonce)).then(|| HolePunch { addr: my_addr, nonce })
+    }
+
+    /// Handles the request of the peer, known under `known_ips`. Returns the response to send
+    /// back, if the node should take part in the attempt.
+    pub fn handle_request(
+        &self,
+        now: time::Instant,
+        my_addr: std::net::SocketAddr,
+        peer_id: &PeerId,
+        known_ips: &[IpAddr],
+        request: &HolePunch,
+    ) -> Option<HolePunch> {
+        // Every request counts towards the limit, so that a peer can't keep trying addresses.
+        if let Some(last) = self.last_request.lock().put(peer_id.clone(), now) {
+            if now < last + MIN_REQUEST_INTERVAL {
+                tracing::debug!(target: "network", %peer_id, "hole punch request rate limited");
+                return None;
+            }
+        }
+        if !is_valid_peer_addr(request.addr, known_ips) {
+            tracing::debug!(target: "network", %peer_id, addr = %request.addr, "hole punch request with invalid address");
+            return None;
+        }
+        self.try_start(now, peer_id, None)
+            .then(|| HolePunch { addr: my_addr, nonce: request.nonce })
+    }
+
+    /// Handles the response of the peer, known under `known_ips`. Returns whether it matches
+    /// an attempt in progress, in which case the node should start connecting to the peer.
+    pub fn handle_response(
+        &self,
+        now: time::Instant,
+        peer_id: &PeerId,
+        known_ips: &[IpAddr],
+        response: &HolePunch,
+    ) -> bool {
+        if !is_valid_peer_addr(response.addr, known_ips) {
+            tracing::debug!(target: "network", %peer_id, addr = %response.addr, "hole punch response with invalid address");
+            return false;
+        }
+        let mut attempts = self.attempts.lock();
+        let Some(attempt) = attempts.get_mut(peer_id) else {
+            return false;
+        };
+        if attempt.deadline <= now || attempt.awaiting_response != Some(response.nonce) {
+            return false;
+        }
+        attempt.awaiting_response = None;
+        true
+    }
+
+    /// Marks the attempt with the peer as finished.
+    pub fn finish(&self, peer_id: &PeerId) {
+        self.attempts.lock().remove(peer_id);
+    }
+}
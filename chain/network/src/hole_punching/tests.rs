@@ -0,0 +1,198 @@
+use crate::hole_punching::{Config, HolePunching, is_valid_peer_addr};
+use crate::network_protocol::HolePunch;
+use crate::network_protocol::testonly as data;
+use crate::stun;
+use crate::testonly::make_rng;
+use near_async::time;
+use near_o11y::testonly::init_test_logger;
+
+fn make_config(stun_servers: Vec<stun::ServerAddr>) -> Config {
+    Config { stun_servers, attempts: 2, attempt_timeout: time::Duration::seconds(5) }
+}
+
+#[test]
+fn rendezvous() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let initiator_id = data::make_peer_id(&mut rng);
+    let target_id = data::make_peer_id(&mut rng);
+    let initiator_addr: std::net::SocketAddr = "1.2.3.4:24567".parse().unwrap();
+    let target_addr: std::net::SocketAddr = "5.6.7.8:24567".parse().unwrap();
+    let initiator_ips = [initiator_addr.ip()];
+    let target_ips = [target_addr.ip()];
+    let initiator = HolePunching::new(make_config(vec![]));
+    let target = HolePunching::new(make_config(vec![]));
+
+    let request = initiator.request(clock.now(), initiator_addr, &target_id).unwrap();
+    assert_eq!(request.addr, initiator_addr);
+    // Only one attempt with the peer at a time.
+    assert!(initiator.request(clock.now(), initiator_addr, &target_id).is_none());
+
+    let response = target
+        .handle_request(clock.now(), target_addr, &initiator_id, &initiator_ips, &request)
+        .unwrap();
+    assert_eq!(response.addr, target_addr);
+    assert_eq!(response.nonce, request.nonce);
+    assert!(
+        target
+            .handle_request(clock.now(), target_addr, &initiator_id, &initiator_ips, &request)
+            .is_none()
+    );
+
+    // Responses are accepted only once, only with the nonce of the request and only with
+    // the address of the target.
+    let mut wrong_nonce = response.clone();
+    wrong_nonce.nonce += 1;
+    assert!(!initiator.handle_response(clock.now(), &target_id, &target_ips, &wrong_nonce));
+    assert!(!initiator.handle_response(clock.now(), &initiator_id, &target_ips, &response));
+    assert!(!initiator.handle_response(clock.now(), &target_id, &initiator_ips, &response));
+    assert!(initiator.handle_response(clock.now(), &target_id, &target_ips, &response));
+    assert!(!initiator.handle_response(clock.now(), &target_id, &target_ips, &response));
+
+    // Once finished, a new attempt can be started.
+    initiator.finish(&target_id);
+    assert!(initiator.request(clock.now(), initiator_addr, &target_id).is_some());
+}
+
+#[test]
+fn expired_attempt() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let target_id = data::make_peer_id(&mut rng);
+    let my_addr: std::net::SocketAddr = "1.2.3.4:24567".parse().unwrap();
+    let hole_punching = HolePunching::new(make_config(vec![]));
+
+    let request = hole_punching.request(clock.now(), my_addr, &target_id).unwrap();
+    clock.advance(time::Duration::minutes(1));
+    // The response arrived too late.
+    assert!(!hole_punching.handle_response(clock.now(), &target_id, &[my_addr.ip()], &request));
+    assert!(hole_punching.request(clock.now(), my_addr, &target_id).is_some());
+}
+
+#[test]
+fn rate_limited_requests() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let initiator_id = data::make_peer_id(&mut rng);
+    let initiator_addr: std::net::SocketAddr = "1.2.3.4:24567".parse().unwrap();
+    let target_addr = "5.6.7.8:24567".parse().unwrap();
+    let initiator_ips = [initiator_addr.ip()];
+    let target = HolePunching::new(make_config(vec![]));
+    let request = HolePunch { addr: initiator_addr, nonce: 1 };
+
+    assert!(
+        target
+            .handle_request(clock.now(), target_addr, &initiator_id, &initiator_ips, &request)
+            .is_some()
+    );
+    // Even once the attempt is finished, the peer has to wait before the next request.
+    target.finish(&initiator_id);
+    assert!(
+        target
+            .handle_request(clock.now(), target_addr, &initiator_id, &initiator_ips, &request)
+            .is_none()
+    );
+    // Rejected requests count towards the limit too.
+    clock.advance(time::Duration::seconds(50));
+    assert!(
+        target
+            .handle_request(clock.now(), target_addr, &initiator_id, &initiator_ips, &request)
+            .is_none()
+    );
+    clock.advance(time::Duration::seconds(50));
+    assert!(
+        target
+            .handle_request(clock.now(), target_addr, &initiator_id, &initiator_ips, &request)
+            .is_none()
+    );
+    clock.advance(time::Duration::minutes(1));
+    assert!(
+        target
+            .handle_request(clock.now(), target_addr, &initiator_id, &initiator_ips, &request)
+            .is_some()
+    );
+}
+
+#[test]
+fn request_with_invalid_addr() {
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let target_addr = "5.6.7.8:24567".parse().unwrap();
+    let target = HolePunching::new(make_config(vec![]));
+    let known_ips = ["1.2.3.4".parse().unwrap()];
+    for addr in ["127.0.0.1:24567", "10.0.0.1:24567", "9.9.9.9:24567"] {
+        let initiator_id = data::make_peer_id(&mut rng);
+        let request = HolePunch { addr: addr.parse().unwrap(), nonce: 1 };
+        assert!(
+            target
+                .handle_request(clock.now(), target_addr, &initiator_id, &known_ips, &request)
+                .is_none(),
+            "{addr}"
+        );
+    }
+}
+
+#[test]
+fn valid_peer_addr() {
+    let ips = |ips: &[&str]| -> Vec<std::net::IpAddr> {
+        ips.iter().map(|ip| ip.parse().unwrap()).collect()
+    };
+    let valid =
+        |addr: &str, known_ips: &[&str]| is_valid_peer_addr(addr.parse().unwrap(), &ips(known_ips));
+
+    assert!(valid("1.2.3.4:24567", &["1.2.3.4"]));
+    assert!(valid("[2a01:4f8::1]:24567", &["2a01:4f8::1"]));
+    assert!(valid("[::ffff:1.2.3.4]:24567", &["1.2.3.4"]));
+    assert!(valid("1.2.3.4:24567", &["::ffff:1.2.3.4"]));
+    // The IP has to be the one the peer is known under.
+    assert!(!valid("1.2.3.4:24567", &[]));
+    assert!(!valid("1.2.3.4:24567", &["1.2.3.5"]));
+    assert!(!valid("1.2.3.4:0", &["1.2.3.4"]));
+    // Only global unicast addresses are allowed, even if the peer is known under them.
+    for ip in [
+        "0.0.0.0",
+        "0.1.2.3",
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.16.1.2",
+        "192.168.1.2",
+        "169.254.1.2",
+        "100.64.1.2",
+        "192.0.2.1",
+        "224.0.0.1",
+        "240.0.0.1",
+        "255.255.255.255",
+        "::",
+        "::1",
+        "::ffff:127.0.0.1",
+        "fc00::1",
+        "fe80::1",
+        "ff02::1",
+        "2001:db8::1",
+    ] {
+        let addr = std::net::SocketAddr::new(ip.parse().unwrap(), 24567);
+        assert!(!is_valid_peer_addr(addr, &ips(&[ip])), "{ip}");
+    }
+}
+
+#[tokio::test]
+async fn public_addr() {
+    init_test_logger();
+    let clock = time::FakeClock::default();
+    let stun_server = stun::testonly::Server::new().await;
+    let hole_punching = HolePunching::new(make_config(vec![stun_server.addr().to_string()]));
+    assert_eq!(hole_punching.public_addr(), None);
+
+    // The IP comes from STUN, the port from the listener.
+    hole_punching.update_public_addr(&clock.clock(), "[::]:24567".parse().unwrap()).await;
+    assert_eq!(hole_punching.public_addr(), Some("[::1]:24567".parse().unwrap()));
+    stun_server.close().await;
+}
+
+#[test]
+fn validate() {
+    assert!(make_config(vec![]).validate().is_ok());
+    assert!(Config { attempts: 0, ..make_config(vec![]) }.validate().is_err());
+    let config = Config { attempt_timeout: time::Duration::ZERO, ..make_config(vec![]) };
+    assert!(config.validate().is_err());
+}
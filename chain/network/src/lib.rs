@@ -1,6 +1,7 @@
 #![cfg_attr(enable_const_type_id, feature(const_type_id))]
 
 pub use crate::peer_manager::peer_manager_actor::{Event, PeerManagerActor};
+pub use crate::rate_limits::adaptive::Config as AdaptiveRateLimitsConfig;
 pub use crate::rate_limits::messages_limits::OverrideConfig as MessagesLimitsOverrideConfig;
 
 mod accounts_data;
@@ -9,11 +10,13 @@ mod network_protocol;
 mod peer;
 mod peer_manager;
 mod private_actix;
+mod quic;
 mod rate_limits;
 mod snapshot_hosts;
 mod stats;
 mod store;
 mod stun;
+mod tx_gossip;
 
 pub mod actix;
 pub mod blacklist;
@@ -22,7 +25,11 @@ pub mod concurrency;
 pub mod config;
 pub mod config_json;
 pub mod debug;
+pub mod discovery;
+pub mod fault_injection;
+pub mod hole_punching;
 pub mod raw;
+pub mod recorder;
 pub mod routing;
 pub mod shards_manager;
 pub mod state_sync;
@@ -367,6 +367,8 @@ pub struct Handshake {
     pub(crate) partial_edge_info: PartialEdgeInfo,
     /// Account owned by the sender.
     pub(crate) owned_account: Option<SignedOwnedAccount>,
+    /// Whether the sender wants to encrypt the connection, see `peer::noise`.
+    pub(crate) encrypted_transport: bool,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, strum::IntoStaticStr)]
@@ -459,7 +461,17 @@ impl fmt::Display for PeerMessage {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, strum::IntoStaticStr)]
+#[derive(
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    Hash,
+    strum::IntoStaticStr,
+    borsh::BorshSerialize,
+    borsh::BorshDeserialize,
+)]
 pub enum Encoding {
     Borsh,
     Proto,
@@ -568,6 +580,14 @@ pub enum RoutedMessageBody {
     ContractCodeResponse(ContractCodeResponse),
     PartialEncodedContractDeploys(PartialEncodedContractDeploys),
     StateHeaderRequest(StateHeaderRequest),
+    /// Hashes of the transactions accepted into the pool of the sender, see `tx_gossip`.
+    TxGossipInventory(Vec<CryptoHash>),
+    /// Request for the transactions announced in `TxGossipInventory`.
+    TxGossipRequest(Vec<CryptoHash>),
+    TxGossipResponse(Vec<SignedTransaction>),
+    /// Rendezvous messages of TCP hole punching, see `hole_punching`.
+    HolePunchRequest(HolePunch),
+    HolePunchResponse(HolePunch),
 }
 
 impl RoutedMessageBody {
@@ -680,6 +700,21 @@ impl fmt::Debug for RoutedMessageBody {
                 "StateHeaderRequest(sync_hash={:?}, shard_id={:?})",
                 request.sync_hash, request.shard_id,
             ),
+            RoutedMessageBody::TxGossipInventory(hashes) => {
+                write!(f, "TxGossipInventory(len={})", hashes.len())
+            }
+            RoutedMessageBody::TxGossipRequest(hashes) => {
+                write!(f, "TxGossipRequest(len={})", hashes.len())
+            }
+            RoutedMessageBody::TxGossipResponse(txs) => {
+                write!(f, "TxGossipResponse(len={})", txs.len())
+            }
+            RoutedMessageBody::HolePunchRequest(msg) => {
+                write!(f, "HolePunchRequest(addr={}, nonce={})", msg.addr, msg.nonce)
+            }
+            RoutedMessageBody::HolePunchResponse(msg) => {
+                write!(f, "HolePunchResponse(addr={}, nonce={})", msg.addr, msg.nonce)
+            }
         }
     }
 }
@@ -779,6 +814,18 @@ impl RoutedMessage {
     }
 }
 
+/// Asks the receiver to open a TCP connection to the sender at the same time as the sender
+/// opens one to the receiver, see `hole_punching`.
+#[derive(
+    Clone, Debug, Eq, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize, ProtocolSchema,
+)]
+pub struct HolePunch {
+    /// Public address of the sender.
+    pub addr: std::net::SocketAddr,
+    /// Identifies the attempt, the response carries the nonce of the request.
+    pub nonce: u64,
+}
+
 #[derive(
     borsh::BorshSerialize,
     borsh::BorshDeserialize,
@@ -925,6 +972,13 @@ impl StateResponseInfo {
         }
     }
 
+    pub fn part_id(&self) -> Option<u64> {
+        match self {
+            Self::V1(info) => info.state_response.part_id(),
+            Self::V2(info) => info.state_response.part_id(),
+        }
+    }
+
     pub fn take_state_response(self) -> ShardStateSyncResponse {
         match self {
             Self::V1(info) => ShardStateSyncResponse::V1(info.state_response),
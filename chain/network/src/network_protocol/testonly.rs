@@ -263,6 +263,7 @@ impl Chain {
             tracked_shards: Default::default(),
             block: self.blocks.last().unwrap().clone(),
             tier1_accounts: Arc::new(self.get_tier1_accounts()),
+            chunk_validation_backlog: 0,
         }
     }
 
@@ -322,6 +323,7 @@ pub fn make_handshake<R: Rng>(rng: &mut R, chain: &Chain) -> Handshake {
         sender_chain_info: chain.get_peer_chain_info(),
         partial_edge_info: make_partial_edge(rng),
         owned_account: None,
+        encrypted_transport: false,
     }
 }
 
@@ -0,0 +1,214 @@
+//! Encryption of peer connections with the Noise protocol framework.
+//!
+//! Encryption is negotiated in the `Handshake`: a peer sets `encrypted_transport` if it
+//! has the encrypted transport enabled and the agreed protocol version supports it.
+//! Once the inbound peer accepts the connection, the outbound peer starts a Noise XX
+//! handshake. The Noise handshake messages are sent as raw frames, and every frame
+//! sent after the handshake is encrypted. The static Noise keys are the X25519
+//! counterparts of the ED25519 node keys, so a completed handshake also proves that
+//! the other side owns the `PeerId` it claimed in the `Handshake`.
+//!
+//! Both `Handshake` messages are bound to the Noise handshake through its prologue,
+//! so if anything on the path modified them, the Noise handshake fails. This can't
+//! detect `encrypted_transport` being cleared in the handshake of the outbound peer,
+//! because then no Noise handshake takes place at all: nodes which must not fall back
+//! to plaintext should set `require_encrypted_transport`.
+//!
+//! The length prefix of the frames is not encrypted, so the sizes of the messages
+//! are still visible on the wire. Encryption adds `TAG_LEN` bytes per `MAX_CHUNK_LEN`
+//! bytes of the frame, see `encrypted_len`.
+use crate::network_protocol;
+use near_crypto::{PublicKey, SecretKey};
+use near_primitives::network::PeerId;
+use sha2::Digest as _;
+
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+/// Maximum length of a single Noise message.
+const MAX_NOISE_MESSAGE_LEN: usize = 65535;
+/// Length of the authentication tag added to every Noise message.
+const TAG_LEN: usize = 16;
+/// Frames longer than that are encrypted as a sequence of Noise messages.
+const MAX_CHUNK_LEN: usize = MAX_NOISE_MESSAGE_LEN - TAG_LEN;
+/// Domain separation tag of the prologue.
+const PROLOGUE_TAG: &[u8] = b"near-network-noise-v1";
+
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+    #[error("noise: {0}")]
+    Noise(#[from] snow::Error),
+    #[error("encrypted transport requires ED25519 node keys")]
+    UnsupportedKey,
+    #[error("static key of the peer doesn't match its PeerId")]
+    PeerIdMismatch,
+}
+
+/// Whether the node key can be used for the encrypted transport.
+pub(crate) fn is_supported(node_key: &SecretKey) -> bool {
+    static_secret(node_key).is_ok()
+}
+
+/// Length of the frame of the given length once encrypted.
+pub(crate) fn encrypted_len(len: usize) -> usize {
+    len + len.div_ceil(MAX_CHUNK_LEN).max(1) * TAG_LEN
+}
+
+/// Maximum length of a frame which still fits in `max_len` bytes once encrypted.
+pub(crate) fn max_plaintext_len(max_len: usize) -> usize {
+    max_len.saturating_sub(max_len.div_ceil(MAX_NOISE_MESSAGE_LEN).max(1) * TAG_LEN)
+}
+
+/// Prologue of the Noise handshake, covering the `Handshake` messages sent
+/// by the outbound and the inbound peer.
+pub(crate) fn prologue(
+    outbound: &network_protocol::Handshake,
+    inbound: &network_protocol::Handshake,
+) -> Vec<u8> {
+    let mut prologue = PROLOGUE_TAG.to_vec();
+    for h in [outbound, inbound] {
+        borsh::to_writer(
+            &mut prologue,
+            &(
+                h.protocol_version,
+                h.oldest_supported_version,
+                &h.sender_peer_id,
+                &h.target_peer_id,
+                h.sender_listen_port,
+                &h.partial_edge_info,
+                h.encrypted_transport,
+            ),
+        )
+        .unwrap();
+    }
+    prologue
+}
+
+/// Derives the X25519 secret key from the ED25519 node key, the same way as
+/// the ED25519 signing scalar is derived from the seed.
+fn static_secret(node_key: &SecretKey) -> Result<[u8; 32], Error> {
+    let SecretKey::ED25519(key) = node_key else {
+        return Err(Error::UnsupportedKey);
+    };
+    let hash = sha2::Sha512::digest(&key.0[..32]);
+    let mut secret = [0u8; 32];
+    secret.copy_from_slice(&hash[..32]);
+    secret[0] &= 248;
+    secret[31] &= 127;
+    secret[31] |= 64;
+    Ok(secret)
+}
+
+/// Converts the ED25519 public key of the peer to its X25519 form.
+fn static_public(peer_id: &PeerId) -> Result<[u8; 32], Error> {
+    let PublicKey::ED25519(key) = peer_id.public_key() else {
+        return Err(Error::UnsupportedKey);
+    };
+    let point = curve25519_dalek::edwards::CompressedEdwardsY(key.0)
+        .decompress()
+        .ok_or(Error::PeerIdMismatch)?;
+    Ok(point.to_montgomery().to_bytes())
+}
+
+/// Noise handshake in progress.
+pub(crate) struct Handshake {
+    state: snow::HandshakeState,
+    /// Expected X25519 static key of the peer.
+    peer_static: [u8; 32],
+}
+
+/// Result of processing a Noise handshake message.
+pub(crate) enum Step {
+    /// More messages from the peer are needed.
+    Continue(Handshake),
+    /// The handshake is finished and frames are encrypted from now on.
+    Done(Transport),
+}
+
+impl Handshake {
+    /// Starts the handshake as the initiator (outbound peer).
+    /// Returns the first handshake message to send.
+    pub fn initiate(
+        node_key: &SecretKey,
+        peer_id: &PeerId,
+        prologue: &[u8],
+    ) -> Result<(Self, Vec<u8>), Error> {
+        let secret = static_secret(node_key)?;
+        let mut state = snow::Builder::new(NOISE_PARAMS.parse().unwrap())
+            .local_private_key(&secret)
+            .prologue(prologue)
+            .build_initiator()?;
+        let mut buf = vec![0; MAX_NOISE_MESSAGE_LEN];
+        let n = state.write_message(&[], &mut buf)?;
+        buf.truncate(n);
+        Ok((Self { state, peer_static: static_public(peer_id)? }, buf))
+    }
+
+    /// Waits for the handshake as the responder (inbound peer).
+    pub fn respond(node_key: &SecretKey, peer_id: &PeerId, prologue: &[u8]) -> Result<Self, Error> {
+        let secret = static_secret(node_key)?;
+        let state = snow::Builder::new(NOISE_PARAMS.parse().unwrap())
+            .local_private_key(&secret)
+            .prologue(prologue)
+            .build_responder()?;
+        Ok(Self { state, peer_static: static_public(peer_id)? })
+    }
+
+    /// Processes a handshake message from the peer.
+    /// Returns the message to send back (if any) and the next step.
+    pub fn read_message(mut self, msg: &[u8]) -> Result<(Option<Vec<u8>>, Step), Error> {
+        let mut buf = vec![0; MAX_NOISE_MESSAGE_LEN];
+        self.state.read_message(msg, &mut buf)?;
+        // Check the identity of the peer as soon as its static key is known,
+        // so that we don't send our static key to an impostor.
+        if let Some(remote_static) = self.state.get_remote_static() {
+            if remote_static != self.peer_static {
+                return Err(Error::PeerIdMismatch);
+            }
+        }
+        let reply = if self.state.is_my_turn() && !self.state.is_handshake_finished() {
+            let n = self.state.write_message(&[], &mut buf)?;
+            Some(buf[..n].to_vec())
+        } else {
+            None
+        };
+        if !self.state.is_handshake_finished() {
+            return Ok((reply, Step::Continue(self)));
+        }
+        Ok((reply, Step::Done(Transport { state: self.state.into_transport_mode()? })))
+    }
+}
+
+/// Encrypts and decrypts the frames of an established connection.
+pub(crate) struct Transport {
+    state: snow::TransportState,
+}
+
+impl Transport {
+    /// Encrypts a frame, splitting it into as many Noise messages as needed.
+    pub fn encrypt(&mut self, frame: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut out = vec![0; encrypted_len(frame.len())];
+        let mut n = 0;
+        for chunk in frame.chunks(MAX_CHUNK_LEN).chain(frame.is_empty().then_some(&[][..])) {
+            n += self.state.write_message(chunk, &mut out[n..])?;
+        }
+        debug_assert_eq!(n, out.len());
+        Ok(out)
+    }
+
+    /// Decrypts a frame produced by `encrypt` on the other side of the connection.
+    pub fn decrypt(&mut self, frame: &[u8]) -> Result<Vec<u8>, Error> {
+        // Every Noise message carries at least the tag, so neither an empty frame
+        // nor a frame ending with a shorter message could have been produced by `encrypt`.
+        let last_chunk_len = frame.len() % MAX_NOISE_MESSAGE_LEN;
+        if frame.is_empty() || (last_chunk_len != 0 && last_chunk_len < TAG_LEN) {
+            return Err(snow::Error::Decrypt.into());
+        }
+        let chunks = frame.len().div_ceil(MAX_NOISE_MESSAGE_LEN);
+        let mut out = vec![0; frame.len() - chunks * TAG_LEN];
+        let mut n = 0;
+        for chunk in frame.chunks(MAX_NOISE_MESSAGE_LEN) {
+            n += self.state.read_message(chunk, &mut out[n..])?;
+        }
+        debug_assert_eq!(n, out.len());
+        Ok(out)
+    }
+}
@@ -15,6 +15,7 @@ use crate::network_protocol::{
     RoutedMessageBody, RoutingTableUpdate, SnapshotHostInfoVerificationError, SyncAccountsData,
     SyncSnapshotHosts,
 };
+use crate::peer::noise;
 use crate::peer::stream;
 use crate::peer::tracker::Tracker;
 use crate::peer_manager::connection;
@@ -22,8 +23,11 @@ use crate::peer_manager::network_state::{NetworkState, PRUNE_EDGES_AFTER};
 #[cfg(test)]
 use crate::peer_manager::peer_manager_actor::Event;
 use crate::peer_manager::peer_manager_actor::MAX_TIER2_PEERS;
+use crate::peer_manager::peer_store::reputation;
 use crate::private_actix::{RegisterPeerError, SendMessage};
+use crate::quic::StreamClass;
 use crate::rate_limits::messages_limits;
+use crate::recorder;
 #[cfg(feature = "distance_vector_routing")]
 use crate::routing::NetworkTopologyChange;
 use crate::routing::edge::verify_nonce;
@@ -45,7 +49,9 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::EpochId;
 use near_primitives::utils::DisplayOption;
-use near_primitives::version::{MIN_SUPPORTED_PROTOCOL_VERSION, PROTOCOL_VERSION, ProtocolVersion};
+use near_primitives::version::{
+    MIN_SUPPORTED_PROTOCOL_VERSION, PROTOCOL_VERSION, ProtocolFeature, ProtocolVersion,
+};
 use parking_lot::Mutex;
 use rand::seq::IteratorRandom;
 use rand::thread_rng;
@@ -76,6 +82,13 @@ pub(crate) const DROP_DUPLICATED_MESSAGES_PERIOD: time::Duration = time::Duratio
 const SYNC_LATEST_BLOCK_INTERVAL: time::Duration = time::Duration::seconds(60);
 /// How often to perform a full sync of AccountsData with the peer.
 const ACCOUNTS_DATA_FULL_SYNC_INTERVAL: time::Duration = time::Duration::minutes(10);
+/// How often the rate limits of the peer are rescaled according to the load of the node.
+const RATE_LIMITS_REFRESH_INTERVAL: time::Duration = time::Duration::seconds(1);
+/// Limits of the frames buffered while the Noise handshake is in progress.
+/// The handshake takes a single round trip, so only the messages sent right
+/// after the connection is established are expected to be buffered.
+const MAX_PENDING_FRAMES: usize = 1000;
+const MAX_PENDING_BYTES: usize = 128 * bytesize::MIB as usize;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ConnectionClosedEvent {
@@ -122,6 +135,10 @@ pub(crate) enum ClosingReason {
     TooLargeClockSkew,
     #[error("owned_account.peer_id doesn't match handshake.sender_peer_id")]
     OwnedAccountMismatch,
+    #[error("encrypted transport failed")]
+    EncryptionFailed,
+    #[error("encrypted transport is required, but the peer doesn't support it")]
+    EncryptionRequired,
     #[error("PeerActor stopped NOT via PeerActor::stop()")]
     Unknown,
 }
@@ -143,6 +160,8 @@ impl ClosingReason {
             ClosingReason::DisconnectMessage => false, // graceful disconnect
             ClosingReason::TooLargeClockSkew => true, // reconnect will fail for the same reason
             ClosingReason::OwnedAccountMismatch => true, // misbehaving peer
+            ClosingReason::EncryptionFailed => true, // misbehaving peer
+            ClosingReason::EncryptionRequired => true, // reconnect will fail for the same reason
             ClosingReason::Unknown => false,        // only happens in tests
         }
     }
@@ -163,8 +182,10 @@ pub(crate) struct PeerActor {
     /// Peer type.
     peer_type: PeerType,
 
-    /// Framed wrapper to send messages through the TCP connection.
+    /// Framed wrapper to send messages through the TCP or QUIC connection.
     framed: stream::FramedStream<PeerActor>,
+    /// Whether the connection is over QUIC.
+    quic: bool,
 
     /// Tracker for requests and responses.
     tracker: Arc<Mutex<Tracker>>,
@@ -189,6 +210,30 @@ pub(crate) struct PeerActor {
 
     /// Per-message rate limits for incoming messages.
     received_messages_rate_limits: messages_limits::RateLimits,
+    /// Config from which `received_messages_rate_limits` have been built.
+    received_messages_rate_limits_config: Arc<messages_limits::Config>,
+    /// Last time the limits have been rescaled, see `rate_limits::adaptive`.
+    rate_limits_refreshed: time::Instant,
+
+    /// Encryption of the frames, see `noise`.
+    encryption: Mutex<Encryption>,
+    /// The last handshake sent to the peer, which is a part of the Noise prologue.
+    sent_handshake: Option<Handshake>,
+}
+
+enum Encryption {
+    /// Frames are sent in plaintext.
+    Disabled,
+    /// Both peers agreed to encrypt the connection and the Noise handshake is in progress.
+    /// Frames to send are buffered until the handshake is finished.
+    Handshake {
+        state: noise::Handshake,
+        pending: Vec<Vec<u8>>,
+        pending_bytes: usize,
+    },
+    Enabled(noise::Transport),
+    /// Too many frames were buffered during the Noise handshake and the connection is closing.
+    Failed,
 }
 
 impl Debug for PeerActor {
@@ -204,6 +249,8 @@ struct HandshakeSpec {
     tier: tcp::Tier,
     protocol_version: ProtocolVersion,
     partial_edge_info: PartialEdgeInfo,
+    /// Whether to request (outbound) or accept (inbound) the encrypted transport.
+    encrypted_transport: bool,
 }
 
 type HandshakeSignalSender = tokio::sync::oneshot::Sender<std::convert::Infallible>;
@@ -215,7 +262,7 @@ impl PeerActor {
     /// actix makes everything complicated.
     pub(crate) async fn spawn_and_handshake(
         clock: time::Clock,
-        stream: tcp::Stream,
+        stream: impl Into<stream::Transport>,
         force_encoding: Option<Encoding>,
         network_state: Arc<NetworkState>,
     ) -> anyhow::Result<actix::Addr<Self>> {
@@ -232,10 +279,11 @@ impl PeerActor {
     /// You can asynchronously await the returned HandshakeSignal.
     pub(crate) fn spawn(
         clock: time::Clock,
-        stream: tcp::Stream,
+        stream: impl Into<stream::Transport>,
         force_encoding: Option<Encoding>,
         network_state: Arc<NetworkState>,
     ) -> anyhow::Result<(actix::Addr<Self>, HandshakeSignal)> {
+        let stream = stream.into();
         #[cfg(test)]
         let stream_id = stream.id();
         #[cfg(test)]
@@ -254,11 +302,11 @@ impl PeerActor {
 
     fn spawn_inner(
         clock: time::Clock,
-        stream: tcp::Stream,
+        stream: stream::Transport,
         force_encoding: Option<Encoding>,
         network_state: Arc<NetworkState>,
     ) -> Result<(actix::Addr<Self>, HandshakeSignal), ClosingReason> {
-        let connecting_status = match &stream.type_ {
+        let connecting_status = match stream.type_() {
             tcp::StreamType::Inbound => ConnectingStatus::Inbound(
                 network_state
                     .inbound_handshake_permits
@@ -304,14 +352,19 @@ impl PeerActor {
                     protocol_version: PROTOCOL_VERSION,
                     tier: *tier,
                     peer_id: peer_id.clone(),
+                    encrypted_transport: network_state.config.encrypted_transport,
                 },
             },
         };
         // Override force_encoding for outbound Tier1 and Tier3 connections;
         // Tier1Handshake and Tier3Handshake are supported only with proto encoding.
-        let force_encoding = match &stream.type_ {
+        // The same goes for requesting the encrypted transport, which moreover requires
+        // that the handshake is not sent twice in different encodings.
+        let force_encoding = match stream.type_() {
             tcp::StreamType::Outbound { tier, .. }
-                if tier == &tcp::Tier::T1 || tier == &tcp::Tier::T3 =>
+                if tier == &tcp::Tier::T1
+                    || tier == &tcp::Tier::T3
+                    || network_state.config.encrypted_transport =>
             {
                 Some(Encoding::Proto)
             }
@@ -324,10 +377,13 @@ impl PeerActor {
             // That likely requires bigger changes and account_id here is later used for debug / logging purposes only.
             account_id: network_state.config.validator.account_id(),
         };
+        let received_messages_rate_limits_config =
+            network_state.received_messages_rate_limits.load_full();
         let received_messages_rate_limits = messages_limits::RateLimits::from_config(
-            &network_state.config.received_messages_rate_limits,
+            &received_messages_rate_limits_config,
             clock.now(),
         );
+        let rate_limits_refreshed = clock.now();
         // recv is the HandshakeSignal returned by this spawn_inner() call.
         let (send, recv): (HandshakeSignalSender, HandshakeSignal) =
             tokio::sync::oneshot::channel();
@@ -335,8 +391,9 @@ impl PeerActor {
         Ok((
             Self::start_in_arbiter(&actix::Arbiter::new().handle(), move |ctx| {
                 let stream_id = stream.id();
-                let peer_addr = stream.peer_addr;
-                let stream_type = stream.type_.clone();
+                let peer_addr = stream.peer_addr();
+                let stream_type = stream.type_().clone();
+                let quic = stream.is_quic();
                 let stats = Arc::new(connection::Stats::default());
                 let framed = stream::FramedStream::spawn(ctx, stream, stats.clone());
                 Self {
@@ -351,6 +408,7 @@ impl PeerActor {
                     },
                     peer_status: PeerStatus::Connecting(send, connecting_status),
                     framed,
+                    quic,
                     tracker: Default::default(),
                     stats,
                     routed_message_cache: LruCache::new(
@@ -369,6 +427,10 @@ impl PeerActor {
                     .into(),
                     network_state,
                     received_messages_rate_limits,
+                    received_messages_rate_limits_config,
+                    rate_limits_refreshed,
+                    encryption: Mutex::new(Encryption::Disabled),
+                    sent_handshake: None,
                 }
             }),
             recv,
@@ -451,10 +513,19 @@ impl PeerActor {
         };
 
         let bytes = msg.serialize(enc);
+        if let Some(recorder) = &self.network_state.recorder {
+            recorder.record(
+                &self.clock,
+                recorder::Direction::Outbound,
+                self.other_peer_id(),
+                enc,
+                &bytes,
+            );
+        }
         self.tracker.lock().increment_sent(&self.clock, bytes.len() as u64);
         let bytes_len = bytes.len();
         tracing::trace!(target: "network", msg_len = bytes_len);
-        self.framed.send(stream::Frame(bytes));
+        self.send_frame(StreamClass::of(msg), bytes);
         metrics::PEER_DATA_SENT_BYTES.inc_by(bytes_len as u64);
         let msg_type = msg.msg_variant();
         metrics::PEER_MESSAGE_SENT_BY_TYPE_TOTAL.with_label_values(&[msg_type]).inc();
@@ -463,7 +534,151 @@ impl PeerActor {
             .inc_by(bytes_len as u64);
     }
 
-    fn send_handshake(&self, spec: HandshakeSpec) {
+    /// Sends the frame, encrypting it if the encrypted transport is in use.
+    /// Over QUIC the frame is sent on the stream of the given class.
+    fn send_frame(&self, class: StreamClass, frame: Vec<u8>) {
+        let mut encryption = self.encryption.lock();
+        if let Encryption::Handshake { .. } | Encryption::Enabled(_) = &*encryption {
+            // The encrypted frame has to fit in the frame size limit.
+            if frame.len() > noise::max_plaintext_len(stream::NETWORK_MESSAGE_MAX_SIZE_BYTES) {
+                metrics::MessageDropped::InputTooLong.inc_unknown_msg();
+                return;
+            }
+        }
+        let overflow = match &mut *encryption {
+            Encryption::Disabled => {
+                self.framed.send_on(class, stream::Frame(frame));
+                false
+            }
+            Encryption::Handshake { pending, pending_bytes, .. } => {
+                *pending_bytes += frame.len();
+                pending.push(frame);
+                pending.len() > MAX_PENDING_FRAMES || *pending_bytes > MAX_PENDING_BYTES
+            }
+            Encryption::Enabled(transport) => {
+                if let Some(frame) = encrypt_frame(transport, &frame) {
+                    self.framed.send(stream::Frame(frame));
+                }
+                false
+            }
+            Encryption::Failed => false,
+        };
+        if overflow {
+            tracing::info!(target: "network", "Too many frames buffered during the Noise handshake with {}", self.peer_info);
+            *encryption = Encryption::Failed;
+            // Encryption is started only once the connection is ready.
+            if let PeerStatus::Ready(conn) = &self.peer_status {
+                conn.addr.do_send(EncryptionPendingOverflow);
+            }
+        }
+    }
+
+    /// Whether the encrypted transport can be used with the given protocol version.
+    /// QUIC connections are already encrypted, with TLS bound to the key of the peer,
+    /// see `crate::quic`.
+    fn encrypted_transport_supported(&self, protocol_version: ProtocolVersion) -> bool {
+        let config = &self.network_state.config;
+        !self.quic
+            && config.encrypted_transport
+            && ProtocolFeature::EncryptedPeerTransport.enabled(protocol_version)
+            && noise::is_supported(&config.node_key)
+    }
+
+    /// Starts the Noise handshake once both peers agreed to encrypt the connection.
+    /// The outbound peer initiates the handshake. `received` is the handshake of the peer.
+    fn start_encryption(&mut self, ctx: &mut actix::Context<Self>, received: &Handshake) {
+        // Both peers have sent their handshake at this point.
+        let Some(sent) = &self.sent_handshake else {
+            log_assert!(false, "encryption started before sending the handshake");
+            self.stop(ctx, ClosingReason::EncryptionFailed);
+            return;
+        };
+        let node_key = &self.network_state.config.node_key;
+        let peer_id = &received.sender_peer_id;
+        let state = match self.peer_type {
+            PeerType::Outbound => {
+                let prologue = noise::prologue(sent, received);
+                noise::Handshake::initiate(node_key, peer_id, &prologue).map(|(state, msg)| {
+                    self.framed.send(stream::Frame(msg));
+                    state
+                })
+            }
+            PeerType::Inbound => {
+                let prologue = noise::prologue(received, sent);
+                noise::Handshake::respond(node_key, peer_id, &prologue)
+            }
+        };
+        match state {
+            Ok(state) => {
+                *self.encryption.get_mut() =
+                    Encryption::Handshake { state, pending: vec![], pending_bytes: 0 }
+            }
+            Err(err) => self.stop_encryption(ctx, err),
+        }
+    }
+
+    fn stop_encryption(&mut self, ctx: &mut actix::Context<Self>, err: noise::Error) {
+        tracing::info!(target: "network", ?err, "Encrypted transport with {} failed", self.peer_info);
+        metrics::PEER_ENCRYPTION_HANDSHAKES_TOTAL.with_label_values(&["failed"]).inc();
+        self.stop(ctx, ClosingReason::EncryptionFailed);
+    }
+
+    /// Decrypts a received frame. Frames of the Noise handshake are processed here,
+    /// in which case None is returned.
+    fn receive_frame(&mut self, ctx: &mut actix::Context<Self>, frame: Vec<u8>) -> Option<Vec<u8>> {
+        let encryption = self.encryption.get_mut();
+        match encryption {
+            Encryption::Disabled => return Some(frame),
+            Encryption::Failed => return None,
+            Encryption::Enabled(transport) => {
+                let _timer =
+                    metrics::PEER_ENCRYPTION_LATENCY.with_label_values(&["decrypt"]).start_timer();
+                match transport.decrypt(&frame) {
+                    Ok(msg) => {
+                        metrics::PEER_ENCRYPTION_OVERHEAD_BYTES
+                            .with_label_values(&["received"])
+                            .inc_by((frame.len() - msg.len()) as u64);
+                        return Some(msg);
+                    }
+                    Err(err) => {
+                        self.stop_encryption(ctx, err);
+                        return None;
+                    }
+                }
+            }
+            Encryption::Handshake { .. } => {}
+        }
+        let Encryption::Handshake { state, pending, pending_bytes } =
+            std::mem::replace(encryption, Encryption::Disabled)
+        else {
+            unreachable!()
+        };
+        match state.read_message(&frame) {
+            Ok((reply, step)) => {
+                if let Some(reply) = reply {
+                    self.framed.send(stream::Frame(reply));
+                }
+                match step {
+                    noise::Step::Continue(state) => {
+                        *self.encryption.get_mut() =
+                            Encryption::Handshake { state, pending, pending_bytes };
+                    }
+                    noise::Step::Done(transport) => {
+                        tracing::debug!(target: "network", "Encrypted transport with {} established", self.peer_info);
+                        metrics::PEER_ENCRYPTION_HANDSHAKES_TOTAL.with_label_values(&["ok"]).inc();
+                        *self.encryption.get_mut() = Encryption::Enabled(transport);
+                        for frame in pending {
+                            self.send_frame(StreamClass::Control, frame);
+                        }
+                    }
+                }
+            }
+            Err(err) => self.stop_encryption(ctx, err),
+        }
+        None
+    }
+
+    fn send_handshake(&mut self, spec: HandshakeSpec) {
         let (height, tracked_shards) =
             if let Some(chain_info) = self.network_state.chain_info.load().as_ref() {
                 (chain_info.block.header().height(), chain_info.tracked_shards.clone())
@@ -484,6 +699,8 @@ impl PeerActor {
                 archival: self.network_state.config.archive,
             },
             partial_edge_info: spec.partial_edge_info,
+            encrypted_transport: spec.encrypted_transport
+                && self.encrypted_transport_supported(spec.protocol_version),
             owned_account: self.network_state.config.validator.signer.get().map(|signer| {
                 OwnedAccount {
                     account_key: signer.public_key(),
@@ -493,6 +710,7 @@ impl PeerActor {
                 .sign(&signer)
             }),
         };
+        self.sent_handshake = Some(handshake.clone());
         let msg = match spec.tier {
             tcp::Tier::T1 => PeerMessage::Tier1Handshake(handshake),
             tcp::Tier::T2 => PeerMessage::Tier2Handshake(handshake),
@@ -562,6 +780,12 @@ impl PeerActor {
                 }
             }
             ConnectingStatus::Inbound { .. } => {
+                // QUIC is supported only for TIER1 connections.
+                if self.quic && tier != tcp::Tier::T1 {
+                    tracing::debug!(target: "network", ?tier, "Received a non-TIER1 handshake over QUIC. Disconnecting peer {}", handshake.sender_peer_id);
+                    self.stop(ctx, ClosingReason::HandshakeFailed);
+                    return;
+                }
                 if MIN_SUPPORTED_PROTOCOL_VERSION > handshake.protocol_version
                     || handshake.protocol_version > PROTOCOL_VERSION
                 {
@@ -663,9 +887,27 @@ impl PeerActor {
             account_id: None,
         };
 
+        // Both peers encrypt the connection iff the handshake of the other peer has
+        // `encrypted_transport` set and we support it. Inbound peer responds with the
+        // same decision, and outbound peer only gets a positive response if it asked for it.
+        let encrypted_transport = handshake.encrypted_transport
+            && self.encrypted_transport_supported(handshake.protocol_version);
+        // QUIC connections are encrypted anyway.
+        if self.network_state.config.require_encrypted_transport
+            && !self.quic
+            && !encrypted_transport
+        {
+            tracing::info!(target: "network", "Peer {} doesn't support the encrypted transport, which is required", peer_info);
+            self.stop(ctx, ClosingReason::EncryptionRequired);
+            return;
+        }
+
         let now = self.clock.now();
         let conn = Arc::new(connection::Connection {
             tier,
+            quic: self.quic,
+            encrypted: encrypted_transport,
+            protocol_version: handshake.protocol_version,
             addr: ctx.address(),
             peer_info: peer_info.clone(),
             owned_account: handshake.owned_account.clone(),
@@ -698,6 +940,7 @@ impl PeerActor {
             time::Interval::new(clock.now(), self.network_state.config.peer_stats_period);
         ctx.spawn({
             let conn = conn.clone();
+            let network_state = self.network_state.clone();
             wrap_future(async move {
                 loop {
                     interval.tick(&clock).await;
@@ -707,6 +950,14 @@ impl PeerActor {
                         .received_bytes_per_sec
                         .store(received.bytes_per_min / 60, Ordering::Relaxed);
                     conn.stats.sent_bytes_per_sec.store(sent.bytes_per_min / 60, Ordering::Relaxed);
+                    let rate_limited = conn.stats.rate_limited_messages.swap(0, Ordering::Relaxed);
+                    if rate_limited > 0 {
+                        network_state.peer_store.record_event(
+                            &clock,
+                            &conn.peer_info.id,
+                            reputation::Event::RateLimited(rate_limited),
+                        );
+                    }
                 }
             })
         });
@@ -733,6 +984,7 @@ impl PeerActor {
                     Ok(()) => {
                         act.peer_info = Some(peer_info).into();
                         act.peer_status = PeerStatus::Ready(conn.clone());
+                        act.framed.start_data_streams();
                         // Respond to handshake if it's inbound and connection was consolidated.
                         if act.peer_type == PeerType::Inbound {
                             act.send_handshake(HandshakeSpec{
@@ -740,8 +992,13 @@ impl PeerActor {
                                 tier,
                                 protocol_version: handshake.protocol_version,
                                 partial_edge_info: partial_edge_info,
+                                encrypted_transport,
                             });
                         }
+                        // Everything sent from now on is encrypted, if the peers agreed to.
+                        if encrypted_transport {
+                            act.start_encryption(ctx, &handshake);
+                        }
                         // TIER1 is strictly reserved for BFT consensus messages,
                         // so all kinds of periodical syncs happen only on TIER2 connections.
                         if tier==tcp::Tier::T2 {
@@ -984,6 +1241,34 @@ impl PeerActor {
         Ok(network_state.receive_routed_message(clock, msg_author, prev_hop, msg_hash, body).await)
     }
 
+    /// Rebuilds the rate limits if their config has been updated, and rescales them
+    /// according to the current load of the node and the reputation of the peer.
+    /// Done at most once per `RATE_LIMITS_REFRESH_INTERVAL`.
+    fn refresh_rate_limits(&mut self, now: time::Instant) {
+        if now < self.rate_limits_refreshed + RATE_LIMITS_REFRESH_INTERVAL {
+            return;
+        }
+        self.rate_limits_refreshed = now;
+        let config = self.network_state.received_messages_rate_limits.load_full();
+        if !Arc::ptr_eq(&config, &self.received_messages_rate_limits_config) {
+            self.received_messages_rate_limits =
+                messages_limits::RateLimits::from_config(&config, now);
+            self.received_messages_rate_limits_config = config;
+        }
+        let reputation_score = match &self.peer_status {
+            PeerStatus::Ready(conn) => {
+                self.network_state.peer_store.reputation_score(&conn.peer_info.id)
+            }
+            PeerStatus::Connecting(..) => 0.,
+        };
+        let factor = self
+            .network_state
+            .adaptive_rate_limits
+            .load()
+            .factor(&self.network_state.load, reputation_score);
+        self.received_messages_rate_limits.set_factor(factor, now);
+    }
+
     fn receive_message(
         &self,
         ctx: &mut actix::Context<Self>,
@@ -1012,6 +1297,11 @@ impl PeerActor {
                         last_block.clone()
                     }
                 });
+                self.network_state.peer_store.response_received(
+                    &self.clock,
+                    &conn.peer_info.id,
+                    reputation::Request::Block(hash),
+                );
                 let mut tracker = self.tracker.lock();
                 tracker.push_received(hash);
                 tracker.has_request(&hash)
@@ -1021,7 +1311,10 @@ impl PeerActor {
         let clock = self.clock.clone();
         let network_state = self.network_state.clone();
         let peer_id = conn.peer_info.id.clone();
+        // Counts the message as pending until it is processed, see `rate_limits::adaptive`.
+        let queue_guard = self.network_state.load.enqueue();
         let handling_future = async move {
+            let _queue_guard = queue_guard;
             Ok(match msg {
                 PeerMessage::Routed(msg) => {
                     let msg_hash = msg.hash();
@@ -1069,6 +1362,7 @@ impl PeerActor {
                         .send_async(ProcessTxRequest {
                             transaction,
                             is_forwarded: false,
+                            is_gossiped: false,
                             check_only: false,
                         })
                         .await
@@ -1101,6 +1395,17 @@ impl PeerActor {
                     .flatten()
                     .map(|response| PeerMessage::VersionedStateResponse(*response.0)),
                 PeerMessage::VersionedStateResponse(info) => {
+                    if let Some(part_id) = info.part_id() {
+                        network_state.peer_store.response_received(
+                            &clock,
+                            &peer_id,
+                            reputation::Request::StatePart {
+                                shard_id: info.shard_id(),
+                                sync_hash: info.sync_hash(),
+                                part_id,
+                            },
+                        );
+                    }
                     //TODO: Route to state sync actor.
                     network_state
                         .client
@@ -1545,6 +1850,24 @@ impl PeerActor {
     }
 }
 
+/// Encrypts the frame, recording the encryption overhead.
+fn encrypt_frame(transport: &mut noise::Transport, frame: &[u8]) -> Option<Vec<u8>> {
+    let _timer = metrics::PEER_ENCRYPTION_LATENCY.with_label_values(&["encrypt"]).start_timer();
+    match transport.encrypt(frame) {
+        Ok(encrypted) => {
+            metrics::PEER_ENCRYPTION_OVERHEAD_BYTES
+                .with_label_values(&["sent"])
+                .inc_by((encrypted.len() - frame.len()) as u64);
+            Some(encrypted)
+        }
+        // Can only happen once the nonce is exhausted, after 2^64 frames.
+        Err(err) => {
+            tracing::error!(target: "network", ?err, "Failed to encrypt a frame, dropping it");
+            None
+        }
+    }
+}
+
 impl actix::Actor for PeerActor {
     type Context = actix::Context<PeerActor>;
 
@@ -1569,7 +1892,8 @@ impl actix::Actor for PeerActor {
         if let PeerStatus::Connecting(_, ConnectingStatus::Outbound { handshake_spec, .. }) =
             &self.peer_status
         {
-            self.send_handshake(handshake_spec.clone());
+            let spec = handshake_spec.clone();
+            self.send_handshake(spec);
         }
         #[cfg(test)]
         self.network_state
@@ -1692,6 +2016,10 @@ impl actix::Handler<stream::Frame> for PeerActor {
             return;
         }
 
+        let Some(msg) = self.receive_frame(ctx, msg) else {
+            return;
+        };
+
         // Message type agnostic stats.
         {
             metrics::PEER_DATA_RECEIVED_BYTES.inc_by(msg.len() as u64);
@@ -1708,6 +2036,16 @@ impl actix::Handler<stream::Frame> for PeerActor {
         };
 
         tracing::trace!(target: "network", "Received message: {}", peer_msg);
+        if let Some(recorder) = &self.network_state.recorder {
+            // parse_message() has already detected the encoding.
+            recorder.record(
+                &self.clock,
+                recorder::Direction::Inbound,
+                self.other_peer_id(),
+                self.encoding().unwrap_or(Encoding::Borsh),
+                &msg,
+            );
+        }
 
         let now = self.clock.now();
         {
@@ -1716,8 +2054,11 @@ impl actix::Handler<stream::Frame> for PeerActor {
             metrics::PEER_MESSAGE_RECEIVED_BY_TYPE_BYTES
                 .with_label_values(&labels)
                 .inc_by(msg.len() as u64);
+            self.refresh_rate_limits(now);
             if !self.received_messages_rate_limits.is_allowed(&peer_msg, now) {
                 metrics::PEER_MESSAGE_RATE_LIMITED_BY_TYPE_TOTAL.with_label_values(&labels).inc();
+                // Reported to the peer store periodically, to avoid locking it on every frame.
+                self.stats.rate_limited_messages.fetch_add(1, Ordering::Relaxed);
                 tracing::debug!(target: "network", "Peer {} is being rate limited for message {}", self.peer_info, peer_msg.msg_variant());
                 return;
             }
@@ -1754,6 +2095,27 @@ impl actix::Handler<stream::Frame> for PeerActor {
                         }
                     }
                 }
+                // Apply the injected network faults, if any.
+                if let Some(faults) = self.network_state.fault_injector.load_full() {
+                    let msg_type = peer_msg.msg_variant();
+                    for delay in faults.deliveries(now, Some(&conn.peer_info.id), msg_type) {
+                        let (conn, peer_msg) = (conn.clone(), peer_msg.clone());
+                        if delay <= time::Duration::ZERO {
+                            self.handle_msg_ready(ctx, conn, peer_msg);
+                            continue;
+                        }
+                        near_performance_metrics::actix::run_later(
+                            ctx,
+                            delay.try_into().unwrap(),
+                            move |act, ctx| {
+                                if act.closing_reason.is_none() {
+                                    act.handle_msg_ready(ctx, conn, peer_msg);
+                                }
+                            },
+                        );
+                    }
+                    return;
+                }
                 // Handle the message.
                 self.handle_msg_ready(ctx, conn.clone(), peer_msg);
             }
@@ -1771,6 +2133,21 @@ impl actix::Handler<WithSpanContext<SendMessage>> for PeerActor {
     }
 }
 
+/// Sent by the PeerActor to itself once too many frames were buffered during the Noise handshake.
+#[derive(actix::Message, Debug)]
+#[rtype(result = "()")]
+struct EncryptionPendingOverflow;
+
+impl actix::Handler<EncryptionPendingOverflow> for PeerActor {
+    type Result = ();
+
+    #[perf]
+    fn handle(&mut self, _: EncryptionPendingOverflow, ctx: &mut Self::Context) {
+        metrics::PEER_ENCRYPTION_HANDSHAKES_TOTAL.with_label_values(&["failed"]).inc();
+        self.stop(ctx, ClosingReason::EncryptionFailed);
+    }
+}
+
 /// Messages from PeerManager to Peer
 #[derive(actix::Message, Debug)]
 #[rtype(result = "()")]
@@ -1,4 +1,5 @@
 use crate::peer_manager::connection;
+use crate::quic::{self, StreamClass};
 use crate::stats::metrics;
 use crate::tcp;
 use actix::AsyncContext as _;
@@ -13,12 +14,55 @@ use tokio::io::AsyncWriteExt as _;
 
 /// Maximum size of network message in encoded format.
 /// We encode length as `u32`, and therefore maximum size can't be larger than `u32::MAX`.
-const NETWORK_MESSAGE_MAX_SIZE_BYTES: usize = 512 * MIB as usize;
+pub(crate) const NETWORK_MESSAGE_MAX_SIZE_BYTES: usize = 512 * MIB as usize;
 /// Maximum capacity of write buffer in bytes.
 const MAX_WRITE_BUFFER_CAPACITY_BYTES: usize = GIB as usize;
 
-type ReadHalf = tokio::io::ReadHalf<tokio::net::TcpStream>;
-type WriteHalf = tokio::io::WriteHalf<tokio::net::TcpStream>;
+/// Connection over which the frames are exchanged.
+pub(crate) enum Transport {
+    Tcp(tcp::Stream),
+    /// Every `StreamClass` is sent over a separate QUIC stream.
+    Quic(quic::Stream),
+}
+
+impl From<tcp::Stream> for Transport {
+    fn from(stream: tcp::Stream) -> Self {
+        Self::Tcp(stream)
+    }
+}
+
+impl From<quic::Stream> for Transport {
+    fn from(stream: quic::Stream) -> Self {
+        Self::Quic(stream)
+    }
+}
+
+impl Transport {
+    pub fn type_(&self) -> &tcp::StreamType {
+        match self {
+            Self::Tcp(s) => &s.type_,
+            Self::Quic(s) => &s.type_,
+        }
+    }
+
+    pub fn peer_addr(&self) -> SocketAddr {
+        match self {
+            Self::Tcp(s) => s.peer_addr,
+            Self::Quic(s) => s.peer_addr,
+        }
+    }
+
+    pub fn id(&self) -> tcp::StreamId {
+        match self {
+            Self::Tcp(s) => s.id(),
+            Self::Quic(s) => s.id(),
+        }
+    }
+
+    pub fn is_quic(&self) -> bool {
+        matches!(self, Self::Quic(_))
+    }
+}
 
 #[derive(thiserror::Error, Debug)]
 pub(crate) enum SendError {
@@ -57,7 +101,12 @@ pub(crate) enum Error {
 }
 
 pub(crate) struct FramedStream<Actor: actix::Actor> {
-    queue_send: tokio::sync::mpsc::UnboundedSender<Frame>,
+    /// Send queues, one per `StreamClass` for QUIC, and a single one for TCP.
+    queues_send: Vec<tokio::sync::mpsc::UnboundedSender<Frame>>,
+    /// Over QUIC, frames from the streams other than `StreamClass::Control` are delivered
+    /// only once the actor calls `start_data_streams`. QUIC doesn't order frames sent over
+    /// different streams, so otherwise they could overtake the handshake.
+    data_streams_started: tokio::sync::watch::Sender<bool>,
     stats: Arc<connection::Stats>,
     send_buf_size_metric: Arc<metrics::IntGaugeGuard>,
     addr: actix::Addr<Actor>,
@@ -71,37 +120,145 @@ where
 {
     pub fn spawn(
         ctx: &mut actix::Context<Actor>,
-        stream: tcp::Stream,
+        stream: impl Into<Transport>,
         stats: Arc<connection::Stats>,
     ) -> Self {
-        let (tcp_recv, tcp_send) = tokio::io::split(stream.stream);
-        let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+        let stream = stream.into();
+        let peer_addr = stream.peer_addr();
         let send_buf_size_metric = Arc::new(metrics::MetricGuard::new(
             &*metrics::PEER_DATA_WRITE_BUFFER_SIZE,
-            vec![stream.peer_addr.to_string()],
+            vec![peer_addr.to_string()],
         ));
+        let (data_streams_started, _) = tokio::sync::watch::channel(false);
+        let mut queues_send = vec![];
+        match stream {
+            Transport::Tcp(stream) => {
+                let (tcp_recv, tcp_send) = tokio::io::split(stream.stream);
+                let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+                queues_send.push(queue_send);
+                Self::spawn_send_loop(
+                    ctx,
+                    async { Ok(tcp_send) },
+                    queue_recv,
+                    &stats,
+                    &send_buf_size_metric,
+                );
+                Self::spawn_recv_loop(ctx, peer_addr, tcp_recv, &stats);
+            }
+            Transport::Quic(stream) => {
+                for class in StreamClass::ALL {
+                    let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
+                    queues_send.push(queue_send);
+                    let conn = stream.conn.clone();
+                    let send = async move {
+                        let mut send = conn.open_uni().await.map_err(io::Error::other)?;
+                        // The first byte of the stream identifies its class.
+                        send.write_u8(class as u8).await?;
+                        Ok(send)
+                    };
+                    Self::spawn_send_loop(ctx, send, queue_recv, &stats, &send_buf_size_metric);
+                }
+                ctx.spawn(wrap_future({
+                    let addr = ctx.address();
+                    let stats = stats.clone();
+                    let started = data_streams_started.subscribe();
+                    async move {
+                        if let Err(err) = Self::run_accept_loop(
+                            stream.conn,
+                            peer_addr,
+                            addr.clone(),
+                            stats,
+                            started,
+                        )
+                        .await
+                        {
+                            addr.do_send(Error::Recv(err));
+                        }
+                    }
+                }));
+            }
+        }
+        Self { queues_send, data_streams_started, stats, send_buf_size_metric, addr: ctx.address() }
+    }
+
+    fn spawn_send_loop<W: tokio::io::AsyncWrite + Unpin + 'static>(
+        ctx: &mut actix::Context<Actor>,
+        send: impl std::future::Future<Output = io::Result<W>> + 'static,
+        queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
+        stats: &Arc<connection::Stats>,
+        send_buf_size_metric: &Arc<metrics::IntGaugeGuard>,
+    ) {
         ctx.spawn(wrap_future({
             let addr = ctx.address();
             let stats = stats.clone();
             let m = send_buf_size_metric.clone();
             async move {
-                if let Err(err) = Self::run_send_loop(tcp_send, queue_recv, stats, m).await {
+                let res = async { Self::run_send_loop(send.await?, queue_recv, stats, m).await };
+                if let Err(err) = res.await {
                     addr.do_send(Error::Send(SendError::IO(err)));
                 }
             }
         }));
+    }
+
+    fn spawn_recv_loop<R: tokio::io::AsyncRead + Unpin + 'static>(
+        ctx: &mut actix::Context<Actor>,
+        peer_addr: SocketAddr,
+        recv: R,
+        stats: &Arc<connection::Stats>,
+    ) {
         ctx.spawn(wrap_future({
             let addr = ctx.address();
             let stats = stats.clone();
             async move {
-                if let Err(err) =
-                    Self::run_recv_loop(stream.peer_addr, tcp_recv, addr.clone(), stats).await
-                {
+                if let Err(err) = Self::run_recv_loop(peer_addr, recv, addr.clone(), stats).await {
                     addr.do_send(Error::Recv(err));
                 }
             }
         }));
-        Self { queue_send, stats, send_buf_size_metric, addr: ctx.address() }
+    }
+
+    /// Accepts the QUIC streams opened by the peer and runs a recv loop for each of them.
+    async fn run_accept_loop(
+        conn: quinn::Connection,
+        peer_addr: SocketAddr,
+        addr: actix::Addr<Actor>,
+        stats: Arc<connection::Stats>,
+        started: tokio::sync::watch::Receiver<bool>,
+    ) -> Result<(), RecvError> {
+        let mut recv_loops = tokio::task::JoinSet::new();
+        for _ in StreamClass::ALL {
+            let mut recv =
+                conn.accept_uni().await.map_err(|err| RecvError::IO(io::Error::other(err)))?;
+            let class = recv.read_u8().await.map_err(RecvError::IO)?;
+            let class = StreamClass::from_u8(class).ok_or_else(|| {
+                RecvError::IO(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown stream class {class}"),
+                ))
+            })?;
+            let addr = addr.clone();
+            let stats = stats.clone();
+            let mut started = started.clone();
+            recv_loops.spawn_local(async move {
+                if class != StreamClass::Control {
+                    // The sender of `started` lives as long as the actor.
+                    let _ = started.wait_for(|started| *started).await;
+                }
+                Self::run_recv_loop(peer_addr, recv, addr, stats).await
+            });
+        }
+        // Report the first error of any of the recv loops.
+        while let Some(res) = recv_loops.join_next().await {
+            res.map_err(|err| RecvError::IO(io::Error::other(err)))??;
+        }
+        Ok(())
+    }
+
+    /// Starts delivering the frames received over the QUIC data streams.
+    /// Should be called once the handshake is completed. No-op for TCP.
+    pub fn start_data_streams(&self) {
+        self.data_streams_started.send_replace(true);
     }
 
     /// Pushes `msg` to the send queue.
@@ -109,6 +266,11 @@ where
     /// If the message is too large, it will be silently dropped inside run_send_loop.
     /// Emits a critical error to Actor if send queue is full.
     pub fn send(&self, frame: Frame) {
+        self.send_on(StreamClass::Control, frame)
+    }
+
+    /// Like `send`, but over QUIC sends the frame over the stream of the given class.
+    pub fn send_on(&self, class: StreamClass, frame: Frame) {
         let msg = &frame.0;
         let mut buf_size =
             self.stats.bytes_to_send.fetch_add(msg.len() as u64, Ordering::Acquire) as usize;
@@ -125,7 +287,8 @@ where
                 want_max_bytes: MAX_WRITE_BUFFER_CAPACITY_BYTES,
             }));
         }
-        let _ = self.queue_send.send(frame);
+        let queue_send = self.queues_send.get(class as usize).unwrap_or(&self.queues_send[0]);
+        let _ = queue_send.send(frame);
     }
 
     /// Event loop receiving and processing messages.
@@ -138,7 +301,7 @@ where
     // directly from the stream.
     async fn run_recv_loop(
         peer_addr: SocketAddr,
-        read: ReadHalf,
+        read: impl tokio::io::AsyncRead + Unpin,
         addr: actix::Addr<Actor>,
         stats: Arc<connection::Stats>,
     ) -> Result<(), RecvError> {
@@ -176,7 +339,7 @@ where
         }
     }
     async fn run_send_loop(
-        tcp_send: WriteHalf,
+        tcp_send: impl tokio::io::AsyncWrite + Unpin,
         mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
         stats: Arc<connection::Stats>,
         buf_size_metric: Arc<metrics::IntGaugeGuard>,
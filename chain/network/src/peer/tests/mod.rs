@@ -1,3 +1,4 @@
 mod communication;
+mod noise;
 mod rate_limits;
 mod stream;
@@ -0,0 +1,125 @@
+use crate::network_protocol::Handshake;
+use crate::network_protocol::testonly as data;
+use crate::peer::noise;
+use crate::testonly::make_rng;
+use near_async::time;
+use near_crypto::{KeyType, SecretKey};
+use near_primitives::network::PeerId;
+use rand::Rng as _;
+
+/// Runs the 3 messages of the Noise XX handshake, where `responder` expects the
+/// initiator to be `initiator_id`. Returns the transports of the initiator and the responder.
+fn handshake(
+    initiator: &SecretKey,
+    initiator_id: &PeerId,
+    responder: &SecretKey,
+) -> Result<(noise::Transport, noise::Transport), noise::Error> {
+    handshake_with_prologues(initiator, initiator_id, responder, b"prologue", b"prologue")
+}
+
+fn handshake_with_prologues(
+    initiator: &SecretKey,
+    initiator_id: &PeerId,
+    responder: &SecretKey,
+    initiator_prologue: &[u8],
+    responder_prologue: &[u8],
+) -> Result<(noise::Transport, noise::Transport), noise::Error> {
+    let responder_id = PeerId::new(responder.public_key());
+    let (i, msg1) = noise::Handshake::initiate(initiator, &responder_id, initiator_prologue)?;
+    let r = noise::Handshake::respond(responder, initiator_id, responder_prologue)?;
+    let (msg2, noise::Step::Continue(r)) = r.read_message(&msg1)? else {
+        panic!("responder finished too early");
+    };
+    let (msg3, noise::Step::Done(i)) = i.read_message(&msg2.unwrap())? else {
+        panic!("initiator didn't finish");
+    };
+    let (None, noise::Step::Done(r)) = r.read_message(&msg3.unwrap())? else {
+        panic!("responder didn't finish");
+    };
+    Ok((i, r))
+}
+
+#[test]
+fn roundtrip() {
+    let mut rng = make_rng(89028037453);
+    let a = data::make_secret_key(&mut rng);
+    let b = data::make_secret_key(&mut rng);
+    let (mut a, mut b) = handshake(&a, &PeerId::new(a.public_key()), &b).unwrap();
+    for len in [0, 1, 1000, 65519, 65520, 200000] {
+        let frame: Vec<u8> = (0..len).map(|_| rng.r#gen()).collect();
+        let encrypted = a.encrypt(&frame).unwrap();
+        assert_ne!(encrypted, frame);
+        assert_eq!(frame, b.decrypt(&encrypted).unwrap());
+        let encrypted = b.encrypt(&frame).unwrap();
+        assert_eq!(frame, a.decrypt(&encrypted).unwrap());
+    }
+}
+
+#[test]
+fn peer_id_mismatch() {
+    let mut rng = make_rng(89028037453);
+    let a = data::make_secret_key(&mut rng);
+    let b = data::make_secret_key(&mut rng);
+    let impostor = data::make_peer_id(&mut rng);
+    assert!(matches!(handshake(&a, &impostor, &b), Err(noise::Error::PeerIdMismatch)));
+}
+
+#[test]
+fn unsupported_key() {
+    let mut rng = make_rng(89028037453);
+    let a = SecretKey::from_seed(KeyType::SECP256K1, "a");
+    let b = data::make_secret_key(&mut rng);
+    assert!(!noise::is_supported(&a));
+    assert!(matches!(
+        handshake(&a, &PeerId::new(a.public_key()), &b),
+        Err(noise::Error::UnsupportedKey)
+    ));
+}
+
+#[test]
+fn tampered_frame() {
+    let mut rng = make_rng(89028037453);
+    let a = data::make_secret_key(&mut rng);
+    let b = data::make_secret_key(&mut rng);
+    let (mut a, mut b) = handshake(&a, &PeerId::new(a.public_key()), &b).unwrap();
+    let mut encrypted = a.encrypt(b"hello").unwrap();
+    encrypted[0] ^= 1;
+    assert!(b.decrypt(&encrypted).is_err());
+    assert!(b.decrypt(&[]).is_err());
+}
+
+#[test]
+fn tampered_handshake() {
+    let mut rng = make_rng(89028037453);
+    let clock = time::FakeClock::default();
+    let chain = data::Chain::make(&clock, &mut rng, 10);
+    let a = data::make_secret_key(&mut rng);
+    let b = data::make_secret_key(&mut rng);
+    let outbound = data::make_handshake(&mut rng, &chain);
+    let inbound = data::make_handshake(&mut rng, &chain);
+    let prologue = noise::prologue(&outbound, &inbound);
+    let a_id = PeerId::new(a.public_key());
+    handshake_with_prologues(&a, &a_id, &b, &prologue, &prologue).unwrap();
+    // The flag of the outbound peer has been modified on the way to the inbound peer.
+    let stripped = Handshake { encrypted_transport: !outbound.encrypted_transport, ..outbound };
+    let tampered = noise::prologue(&stripped, &inbound);
+    assert!(matches!(
+        handshake_with_prologues(&a, &a_id, &b, &prologue, &tampered),
+        Err(noise::Error::Noise(_))
+    ));
+}
+
+#[test]
+fn encrypted_len() {
+    let mut rng = make_rng(89028037453);
+    let a = data::make_secret_key(&mut rng);
+    let b = data::make_secret_key(&mut rng);
+    let (mut a, _) = handshake(&a, &PeerId::new(a.public_key()), &b).unwrap();
+    for len in [0, 1, 65519, 65520, 200000] {
+        assert_eq!(noise::encrypted_len(len), a.encrypt(&vec![0; len]).unwrap().len());
+    }
+    for max_len in [1000, 65535, 65536, 200000, 512 << 20] {
+        let len = noise::max_plaintext_len(max_len);
+        assert!(noise::encrypted_len(len) <= max_len);
+    }
+}
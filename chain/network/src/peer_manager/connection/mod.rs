@@ -18,6 +18,7 @@ use near_o11y::WithSpanContextExt;
 use near_primitives::genesis::GenesisId;
 use near_primitives::network::PeerId;
 use near_primitives::types::ShardId;
+use near_primitives::version::ProtocolVersion;
 use std::collections::{HashMap, hash_map::Entry};
 use std::fmt;
 use std::future::Future;
@@ -75,7 +76,10 @@ impl tcp::Tier {
             | RoutedMessageBody::VersionedPartialEncodedChunk(..)
             | RoutedMessageBody::ChunkContractAccesses(_)
             | RoutedMessageBody::ContractCodeRequest(_)
-            | RoutedMessageBody::ContractCodeResponse(_) => true,
+            | RoutedMessageBody::ContractCodeResponse(_)
+            | RoutedMessageBody::TxGossipInventory(_)
+            | RoutedMessageBody::TxGossipRequest(_)
+            | RoutedMessageBody::TxGossipResponse(_) => true,
             // Rest
             RoutedMessageBody::ForwardTx(..)
             | RoutedMessageBody::TxStatusRequest(..)
@@ -88,7 +92,9 @@ impl tcp::Tier {
             | RoutedMessageBody::ChunkStateWitnessAck(..)
             | RoutedMessageBody::StatePartRequest(..)
             | RoutedMessageBody::StateHeaderRequest(..)
-            | RoutedMessageBody::PartialEncodedContractDeploys(..) => self == tcp::Tier::T2,
+            | RoutedMessageBody::PartialEncodedContractDeploys(..)
+            | RoutedMessageBody::HolePunchRequest(..)
+            | RoutedMessageBody::HolePunchResponse(..) => self == tcp::Tier::T2,
             // Deprecated
             RoutedMessageBody::_UnusedQueryRequest
             | RoutedMessageBody::_UnusedQueryResponse
@@ -117,6 +123,9 @@ pub(crate) struct Stats {
     pub received_bytes_per_sec: AtomicU64,
     /// Avg sent bytes/s, based on the last few minutes of traffic.
     pub sent_bytes_per_sec: AtomicU64,
+    /// Number of messages dropped by the rate limits since they were last
+    /// reported to the peer store.
+    pub rate_limited_messages: AtomicU64,
 
     /// Number of messages in the buffer to send.
     pub messages_to_send: AtomicU64,
@@ -128,6 +137,12 @@ pub(crate) struct Stats {
 pub(crate) struct Connection {
     // TODO(gprusak): add rate limiting on TIER1 connections for defense in-depth.
     pub tier: tcp::Tier,
+    /// Whether the connection uses QUIC rather than TCP (TIER1 only).
+    pub quic: bool,
+    /// Whether the frames are encrypted with the Noise protocol, see `peer::noise`.
+    pub encrypted: bool,
+    /// Protocol version negotiated in the handshake.
+    pub protocol_version: ProtocolVersion,
     // TODO(gprusak): addr should be internal, so that Connection will become an API of the
     // PeerActor.
     pub addr: actix::Addr<PeerActor>,
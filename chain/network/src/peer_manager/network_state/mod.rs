@@ -7,6 +7,9 @@ use crate::client::{
 use crate::concurrency::demux;
 use crate::concurrency::runtime::Runtime;
 use crate::config;
+use crate::discovery;
+use crate::fault_injection;
+use crate::hole_punching;
 use crate::network_protocol::{
     Edge, EdgeState, PartialEdgeInfo, PeerIdOrHash, PeerInfo, PeerMessage, RawRoutedMessage,
     RoutedMessageBody, RoutedMessageV2, SignedAccountData, SnapshotHostInfo,
@@ -17,6 +20,9 @@ use crate::peer_manager::connection;
 use crate::peer_manager::connection_store;
 use crate::peer_manager::peer_store;
 use crate::private_actix::RegisterPeerError;
+use crate::quic;
+use crate::rate_limits::{adaptive, messages_limits};
+use crate::recorder;
 #[cfg(feature = "distance_vector_routing")]
 use crate::routing::NetworkTopologyChange;
 use crate::routing::route_back_cache::RouteBackCache;
@@ -31,20 +37,22 @@ use crate::state_witness::{
 use crate::stats::metrics;
 use crate::store;
 use crate::tcp;
+use crate::tx_gossip;
 use crate::types::{
     ChainInfo, PeerManagerSenderForNetwork, PeerType, ReasonForBan, StateHeaderRequestBody,
     StatePartRequestBody, Tier3Request, Tier3RequestBody,
 };
 use anyhow::Context;
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use near_async::messaging::{CanSend, SendAsync, Sender};
 use near_async::time;
 use near_primitives::genesis::GenesisId;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
 use near_primitives::types::AccountId;
+use near_primitives::version::ProtocolFeature;
 use parking_lot::{Mutex, RwLock};
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::num::NonZeroUsize;
 use std::sync::Arc;
 use std::sync::atomic::AtomicUsize;
@@ -70,6 +78,12 @@ pub const PRUNE_EDGES_AFTER: time::Duration = time::Duration::minutes(30);
 /// How long to wait between reconnection attempts to the same peer
 pub(crate) const RECONNECT_ATTEMPT_INTERVAL: time::Duration = time::Duration::seconds(10);
 
+/// How long TIER1 connections to a peer go straight to TCP after a QUIC connection to it failed.
+pub(crate) const TIER1_QUIC_RETRY_INTERVAL: time::Duration = time::Duration::minutes(10);
+
+/// Maximal number of peers for which a failed QUIC connection is remembered.
+const TIER1_QUIC_FAILURES_CACHE_SIZE: usize = 1000;
+
 impl WhitelistNode {
     pub fn from_peer_info(pi: &PeerInfo) -> anyhow::Result<Self> {
         Ok(Self {
@@ -128,6 +142,30 @@ pub(crate) struct NetworkState {
     pub my_public_addr: Arc<RwLock<Option<std::net::SocketAddr>>>,
     /// Peer store that provides read/write access to peers.
     pub peer_store: peer_store::PeerStore,
+    /// QUIC endpoint for TIER1 connections.
+    /// Set once the endpoint is bound, only if `tier1.enable_quic` is set.
+    pub quic_endpoint: std::sync::OnceLock<quic::Endpoint>,
+    /// Peers to which a TIER1 QUIC connection failed recently, with the time of the failure.
+    /// Connections to them skip QUIC until `TIER1_QUIC_RETRY_INTERVAL` passes, so that they
+    /// don't wait for the QUIC connect timeout on every reconnect.
+    pub tier1_quic_failures: Mutex<lru::LruCache<PeerId, time::Instant>>,
+    /// Records the network traffic, if enabled in the config.
+    pub recorder: Option<recorder::Recorder>,
+    /// Rate limits for incoming messages. Initialized from the config,
+    /// can be updated at runtime (see `update_network_config`).
+    pub received_messages_rate_limits: ArcSwap<messages_limits::Config>,
+    /// Config of the adaptive scaling of `received_messages_rate_limits`.
+    pub adaptive_rate_limits: ArcSwap<adaptive::Config>,
+    /// Load signals of the node, which drive the adaptive rate limits.
+    pub load: Arc<adaptive::Load>,
+    /// Gossip of transactions among the chunk producers, if enabled in the config.
+    pub tx_gossip: Option<tx_gossip::TxGossip>,
+    /// Discovery of peers from DNS seeds and signed peer lists, if enabled in the config.
+    pub discovery: Option<discovery::Discovery>,
+    /// NAT traversal with TCP hole punching, if enabled in the config.
+    pub hole_punching: Option<hole_punching::HolePunching>,
+    /// Network faults injected into the received messages, see `fault_injection`.
+    pub fault_injector: ArcSwapOption<fault_injection::FaultInjector>,
     /// Information about state snapshots hosted by network peers.
     pub snapshot_hosts: Arc<SnapshotHostsCache>,
     /// Connection store that provides read/write access to stored connections.
@@ -187,7 +225,12 @@ impl NetworkState {
         shards_manager_adapter: Sender<ShardsManagerRequestFromNetwork>,
         partial_witness_adapter: PartialWitnessSenderForNetwork,
         whitelist_nodes: Vec<WhitelistNode>,
+        recorder: Option<recorder::Recorder>,
     ) -> Self {
+        let discovery = config
+            .peer_discovery
+            .clone()
+            .map(|config| discovery::Discovery::new(config, genesis_id.chain_id.clone()));
         Self {
             runtime: Runtime::new(),
             graph: Arc::new(crate::routing::Graph::new(crate::routing::GraphConfig {
@@ -212,6 +255,20 @@ impl NetworkState {
             inbound_handshake_permits: Arc::new(tokio::sync::Semaphore::new(LIMIT_PENDING_PEERS)),
             my_public_addr: Arc::new(RwLock::new(None)),
             peer_store,
+            quic_endpoint: Default::default(),
+            tier1_quic_failures: Mutex::new(lru::LruCache::new(
+                NonZeroUsize::new(TIER1_QUIC_FAILURES_CACHE_SIZE).unwrap(),
+            )),
+            recorder,
+            received_messages_rate_limits: ArcSwap::from_pointee(
+                config.received_messages_rate_limits.clone(),
+            ),
+            adaptive_rate_limits: ArcSwap::from_pointee(config.adaptive_rate_limits.clone()),
+            load: Default::default(),
+            tx_gossip: config.tx_gossip.clone().map(tx_gossip::TxGossip::new),
+            discovery,
+            hole_punching: config.hole_punching.clone().map(hole_punching::HolePunching::new),
+            fault_injector: ArcSwapOption::empty(),
             snapshot_hosts: Arc::new(SnapshotHostsCache::new(config.snapshot_hosts.clone())),
             connection_store: connection_store::ConnectionStore::new(store.clone()).unwrap(),
             pending_reconnect: Mutex::new(Vec::<PeerInfo>::new()),
@@ -322,6 +379,11 @@ impl NetworkState {
                 return Err(RegisterPeerError::Banned);
             }
 
+            if this.peer_store.in_reconnect_cooldown(&clock, &peer_info.id) {
+                tracing::debug!(target: "network", id = ?peer_info.id, "Dropping connection from recently misbehaving peer");
+                return Err(RegisterPeerError::ReconnectCooldown);
+            }
+
             match conn.tier {
                 tcp::Tier::T1 => {
                     if conn.peer_type == PeerType::Inbound {
@@ -705,6 +767,174 @@ impl NetworkState {
         success
     }
 
+    /// Tier of the direct connection to the peer over which the transaction gossip messages
+    /// can be sent, preferably TIER1. The connection has to be negotiated at a protocol version
+    /// which supports the transaction gossip, since older peers can't parse the messages.
+    pub fn tx_gossip_tier(&self, peer_id: &PeerId) -> Option<tcp::Tier> {
+        let supported = |conn: Option<&Arc<connection::Connection>>| {
+            conn.is_some_and(|conn| ProtocolFeature::TxGossip.enabled(conn.protocol_version))
+        };
+        if supported(self.tier1.load().ready.get(peer_id)) {
+            Some(tcp::Tier::T1)
+        } else if supported(self.tier2.load().ready.get(peer_id)) {
+            Some(tcp::Tier::T2)
+        } else {
+            None
+        }
+    }
+
+    /// Sends a transaction gossip message directly to the peer, see `tx_gossip_tier`.
+    fn send_tx_gossip(&self, clock: &time::Clock, target: PeerId, body: RoutedMessageBody) {
+        let Some(tier) = self.tx_gossip_tier(&target) else {
+            tracing::debug!(target: "network", peer_id = ?target, "Drop transaction gossip message: peer doesn't support it");
+            return;
+        };
+        let msg = self
+            .sign_message(clock, RawRoutedMessage { target: PeerIdOrHash::PeerId(target), body });
+        self.send_message_to_peer(clock, tier, msg);
+    }
+
+    /// Adds the peers from the DNS seeds and signed peer lists to the peer store, see `discovery`.
+    pub async fn discover_peers(&self, clock: &time::Clock) {
+        let Some(discovery) = &self.discovery else {
+            return;
+        };
+        let node_id = self.config.node_id();
+        let peers: Vec<_> =
+            discovery.discover(clock).await.into_iter().filter(|peer| peer.id != node_id).collect();
+        tracing::debug!(target: "network", num_peers = peers.len(), "discovered peers");
+        self.peer_store.add_indirect_peers(clock, peers.into_iter());
+    }
+
+    /// Public address of the node to exchange with the peers for hole punching: the one
+    /// discovered via STUN, or else the one observed by the peers.
+    fn hole_punch_addr(&self) -> Option<SocketAddr> {
+        self.hole_punching.as_ref()?.public_addr().or(*self.my_public_addr.read())
+    }
+
+    /// IPs the peer is known under: the one of the address it advertises and the ones
+    /// of its TIER1 proxies running on the peer itself. Hole punching addresses sent by
+    /// the peer have to match one of them.
+    fn known_peer_ips(&self, peer_id: &PeerId) -> Vec<IpAddr> {
+        let mut ips: Vec<_> = self
+            .peer_store
+            .get_peer_state(peer_id)
+            .and_then(|state| state.peer_info.addr)
+            .map(|addr| addr.ip())
+            .into_iter()
+            .collect();
+        for data in self.accounts_data.load().data.values() {
+            ips.extend(data.proxies.iter().filter(|p| &p.peer_id == peer_id).map(|p| p.addr.ip()));
+        }
+        ips
+    }
+
+    /// Asks the peer, which the node failed to connect to, to take part in TCP hole punching,
+    /// see `hole_punching`. The request is routed through the peers of the node.
+    pub fn request_hole_punch(&self, clock: &time::Clock, peer_id: &PeerId) {
+        let Some(hole_punching) = &self.hole_punching else {
+            return;
+        };
+        let Some(my_addr) = self.hole_punch_addr() else {
+            return;
+        };
+        if self.tier2.load().ready.contains_key(peer_id) {
+            return;
+        }
+        let Some(request) = hole_punching.request(clock.now(), my_addr, peer_id) else {
+            return;
+        };
+        let msg = self.sign_message(
+            clock,
+            RawRoutedMessage {
+                target: PeerIdOrHash::PeerId(peer_id.clone()),
+                body: RoutedMessageBody::HolePunchRequest(request),
+            },
+        );
+        if !self.send_message_to_peer(clock, tcp::Tier::T2, msg) {
+            hole_punching.finish(peer_id);
+        }
+    }
+
+    /// Opens a connection to the peer from the port of the listener, as a part of
+    /// TCP hole punching. `outbound` decides which side performs the handshake.
+    async fn hole_punch_connect(
+        self: Arc<Self>,
+        clock: time::Clock,
+        peer_id: PeerId,
+        peer_addr: SocketAddr,
+        outbound: bool,
+    ) {
+        let (Some(hole_punching), Some(node_addr)) = (&self.hole_punching, &self.config.node_addr)
+        else {
+            return;
+        };
+        let config = hole_punching.config();
+        for attempt in 0..config.attempts {
+            if self.tier2.load().ready.contains_key(&peer_id) {
+                break;
+            }
+            let deadline = clock.now() + config.attempt_timeout;
+            let type_ = if outbound {
+                tcp::StreamType::Outbound { peer_id: peer_id.clone(), tier: tcp::Tier::T2 }
+            } else {
+                tcp::StreamType::Inbound
+            };
+            let result = async {
+                let stream =
+                    tcp::Stream::punch(node_addr, peer_addr, type_, config.attempt_timeout)
+                        .await
+                        .context("tcp::Stream::punch()")?;
+                PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone())
+                    .await
+                    .context("PeerActor::spawn()")?;
+                anyhow::Ok(())
+            }
+            .await;
+            if let Err(err) = result {
+                tracing::debug!(target: "network", %peer_id, attempt, err = format!("{:#}", err), "hole punching attempt failed");
+                // The connection may be refused right away, in which case the next attempt
+                // waits for the peer to catch up.
+                clock.sleep_until(deadline).await;
+            }
+        }
+        let connected = self.tier2.load().ready.contains_key(&peer_id);
+        metrics::HOLE_PUNCHING_ATTEMPTS
+            .with_label_values(&[
+                if outbound { "outbound" } else { "inbound" },
+                if connected { "success" } else { "failure" },
+            ])
+            .inc();
+        hole_punching.finish(&peer_id);
+    }
+
+    /// Sends the pending transaction gossip announcements, see `tx_gossip`. Announcements
+    /// to accounts without a direct connection supporting the gossip are dropped.
+    pub fn tx_gossip_send_inventory(self: &Arc<Self>, clock: &time::Clock) {
+        let Some(tx_gossip) = &self.tx_gossip else {
+            return;
+        };
+        let accounts_data = self.accounts_data.load();
+        for (account_id, hashes) in tx_gossip.take_inventory() {
+            let mut peer_ids = accounts_data
+                .keys_by_id
+                .get(&account_id)
+                .iter()
+                .flat_map(|keys| keys.iter())
+                .filter_map(|key| accounts_data.data.get(key))
+                .map(|data| data.peer_id.clone())
+                .chain(self.account_announcements.get_account_owner(&account_id));
+            let Some(peer_id) = peer_ids.find(|peer_id| self.tx_gossip_tier(peer_id).is_some())
+            else {
+                metrics::TX_GOSSIP_TRANSACTIONS
+                    .with_label_values(&["unsent"])
+                    .inc_by(hashes.len() as u64);
+                continue;
+            };
+            self.send_tx_gossip(clock, peer_id, RoutedMessageBody::TxGossipInventory(hashes));
+        }
+    }
+
     pub async fn receive_routed_message(
         self: &Arc<Self>,
         clock: &time::Clock,
@@ -730,10 +960,14 @@ impl NetworkState {
                 None
             }
             RoutedMessageBody::ForwardTx(transaction) => {
+                if let Some(tx_gossip) = &self.tx_gossip {
+                    tx_gossip.insert_known(transaction.get_hash());
+                }
                 self.client
                     .send_async(ProcessTxRequest {
                         transaction,
                         is_forwarded: true,
+                        is_gossiped: false,
                         check_only: false,
                     })
                     .await
@@ -830,6 +1064,81 @@ impl NetworkState {
                 self.partial_witness_adapter.send(PartialEncodedContractDeploysMessage(deploys));
                 None
             }
+            RoutedMessageBody::TxGossipInventory(hashes) => {
+                let tx_gossip = self.tx_gossip.as_ref()?;
+                let hashes = tx_gossip.handle_inventory(clock.now(), hashes);
+                if !hashes.is_empty() {
+                    self.send_tx_gossip(
+                        clock,
+                        msg_author,
+                        RoutedMessageBody::TxGossipRequest(hashes),
+                    );
+                }
+                None
+            }
+            RoutedMessageBody::TxGossipRequest(hashes) => {
+                let tx_gossip = self.tx_gossip.as_ref()?;
+                let txs = tx_gossip.handle_request(hashes);
+                if !txs.is_empty() {
+                    self.send_tx_gossip(
+                        clock,
+                        msg_author,
+                        RoutedMessageBody::TxGossipResponse(txs),
+                    );
+                }
+                None
+            }
+            RoutedMessageBody::TxGossipResponse(txs) => {
+                let tx_gossip = self.tx_gossip.as_ref()?;
+                for transaction in tx_gossip.handle_response(clock.now(), txs) {
+                    self.client
+                        .send_async(ProcessTxRequest {
+                            transaction,
+                            is_forwarded: false,
+                            is_gossiped: true,
+                            check_only: false,
+                        })
+                        .await
+                        .ok();
+                }
+                None
+            }
+            RoutedMessageBody::HolePunchRequest(request) => {
+                let hole_punching = self.hole_punching.as_ref()?;
+                if self.tier2.load().ready.contains_key(&msg_author) {
+                    return None;
+                }
+                let my_addr = self.hole_punch_addr()?;
+                let known_ips = self.known_peer_ips(&msg_author);
+                let response = hole_punching.handle_request(
+                    clock.now(),
+                    my_addr,
+                    &msg_author,
+                    &known_ips,
+                    &request,
+                )?;
+                // The target of the request connects first, as the outbound side.
+                self.spawn(self.clone().hole_punch_connect(
+                    clock.clone(),
+                    msg_author,
+                    request.addr,
+                    true,
+                ));
+                Some(RoutedMessageBody::HolePunchResponse(response))
+            }
+            RoutedMessageBody::HolePunchResponse(response) => {
+                let hole_punching = self.hole_punching.as_ref()?;
+                let known_ips = self.known_peer_ips(&msg_author);
+                if hole_punching.handle_response(clock.now(), &msg_author, &known_ips, &response) {
+                    self.spawn(self.clone().hole_punch_connect(
+                        clock.clone(),
+                        msg_author,
+                        response.addr,
+                        false,
+                    ));
+                }
+                None
+            }
             body => {
                 tracing::error!(target: "network", "Peer received unexpected message type: {:?}", body);
                 None
@@ -1008,6 +1317,51 @@ impl NetworkState {
         return self.tier2.load().ready.values().map(|c| c.peer_info.clone()).collect();
     }
 
+    /// Applies the part of the config which can be updated at runtime.
+    /// Rate limits of the existing connections are updated within a second.
+    pub fn update_network_config(
+        &self,
+        update: crate::config_json::UpdatableNetworkConfig,
+    ) -> anyhow::Result<()> {
+        // Same as in `NetworkConfig::new()`: the overrides are applied on top of the preset.
+        let mut rate_limits = messages_limits::Config::standard_preset();
+        if let Some(overrides) = update.received_messages_rate_limits {
+            rate_limits.apply_overrides(overrides);
+        }
+        if let Err(err) = rate_limits.validate() {
+            anyhow::bail!("One or more invalid rate limits: {err:?}");
+        }
+        update.adaptive_rate_limits.validate().context("adaptive_rate_limits")?;
+        if **self.received_messages_rate_limits.load() != rate_limits {
+            tracing::info!(target: "network", ?rate_limits, "updated received_messages_rate_limits");
+            self.received_messages_rate_limits.store(Arc::new(rate_limits));
+        }
+        if **self.adaptive_rate_limits.load() != update.adaptive_rate_limits {
+            tracing::info!(target: "network", adaptive_rate_limits = ?update.adaptive_rate_limits, "updated adaptive_rate_limits");
+            self.adaptive_rate_limits.store(Arc::new(update.adaptive_rate_limits));
+        }
+        Ok(())
+    }
+
+    /// Replaces the network faults injected into the received messages.
+    /// Time windows of the rules start now.
+    pub fn set_fault_injection(
+        &self,
+        clock: &time::Clock,
+        config: Option<fault_injection::Config>,
+    ) -> anyhow::Result<()> {
+        let Some(config) = config else {
+            tracing::info!(target: "network", "disabled fault injection");
+            self.fault_injector.store(None);
+            return Ok(());
+        };
+        config.validate()?;
+        tracing::info!(target: "network", ?config, "enabled fault injection");
+        self.fault_injector
+            .store(Some(Arc::new(fault_injection::FaultInjector::new(config, clock.now()))));
+        Ok(())
+    }
+
     /// Sets the chain info, and updates the set of TIER1 keys.
     /// Returns true iff the set of TIER1 keys has changed.
     pub fn set_chain_info(self: &Arc<Self>, info: ChainInfo) -> bool {
@@ -1018,6 +1372,7 @@ impl NetworkState {
         // there will be no race condition between subsequent SetChainInfo
         // calls.
         self.chain_info.store(Arc::new(Some(info.clone())));
+        self.load.set_chunk_validation_backlog(info.chunk_validation_backlog);
 
         // If tier1 is not enabled, we skip set_keys() call.
         // This way self.state.accounts_data is always empty, hence no data
@@ -5,6 +5,7 @@ use crate::network_protocol::{
 };
 use crate::peer::peer_actor::PeerActor;
 use crate::peer_manager::connection;
+use crate::stats::metrics;
 use crate::stun;
 use crate::tcp;
 use crate::types::PeerType;
@@ -38,6 +39,61 @@ impl super::NetworkState {
         Some(FrozenValidatorConfig { signer, proxies: &self.config.validator.proxies })
     }
 
+    /// Establishes a TIER1 connection to the peer.
+    /// QUIC is tried first (if enabled), falling back to TCP if the QUIC connection
+    /// could not be established, for example because the peer doesn't support it.
+    /// After a failure QUIC is not tried again for `TIER1_QUIC_RETRY_INTERVAL`.
+    async fn tier1_connect_to(
+        self: &Arc<Self>,
+        clock: &time::Clock,
+        peer_info: &PeerInfo,
+    ) -> anyhow::Result<()> {
+        if let Some(endpoint) = self.quic_endpoint.get() {
+            if self.tier1_quic_recently_failed(clock, &peer_info.id) {
+                tracing::debug!(target: "network", ?peer_info, "TIER1 QUIC connection failed recently, using TCP");
+            } else {
+                let res = async {
+                    let stream = endpoint.connect(peer_info).await?;
+                    PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone()).await
+                }
+                .await;
+                match res {
+                    Ok(_) if self.tier1.load().ready.contains_key(&peer_info.id) => {
+                        self.tier1_quic_failures.lock().pop(&peer_info.id);
+                        metrics::PEER_TIER1_CONNECTIONS_BY_TRANSPORT_TOTAL
+                            .with_label_values(&["quic"])
+                            .inc();
+                        return Ok(());
+                    }
+                    Ok(_) => {
+                        tracing::debug!(target: "network", ?peer_info, "TIER1 handshake over QUIC failed, falling back to TCP");
+                    }
+                    Err(err) => {
+                        tracing::debug!(target: "network", ?err, ?peer_info, "TIER1 QUIC connection failed, falling back to TCP");
+                    }
+                }
+                self.tier1_quic_failures.lock().put(peer_info.id.clone(), clock.now());
+            }
+        }
+        let stream =
+            tcp::Stream::connect(peer_info, tcp::Tier::T1, &self.config.socket_options).await?;
+        PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone()).await?;
+        metrics::PEER_TIER1_CONNECTIONS_BY_TRANSPORT_TOTAL.with_label_values(&["tcp"]).inc();
+        Ok(())
+    }
+
+    fn tier1_quic_recently_failed(&self, clock: &time::Clock, peer_id: &PeerId) -> bool {
+        let mut failures = self.tier1_quic_failures.lock();
+        match failures.get(peer_id) {
+            Some(failed_at) if clock.now() < *failed_at + super::TIER1_QUIC_RETRY_INTERVAL => true,
+            Some(_) => {
+                failures.pop(peer_id);
+                false
+            }
+            None => false,
+        }
+    }
+
     async fn tier1_connect_to_my_proxies(
         self: &Arc<Self>,
         clock: &time::Clock,
@@ -52,20 +108,9 @@ impl super::NetworkState {
                 continue;
             }
             handles.push(async move {
-                let res = async {
-                    let stream = tcp::Stream::connect(
-                        &PeerInfo {
-                            id: proxy.peer_id.clone(),
-                            addr: Some(proxy.addr),
-                            account_id: None,
-                        },
-                        tcp::Tier::T1,
-                        &self.config.socket_options,
-                    )
-                    .await?;
-                    anyhow::Ok(PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone()).await?)
-                }.await;
-                if let Err(err) = res {
+                let peer_info =
+                    PeerInfo { id: proxy.peer_id.clone(), addr: Some(proxy.addr), account_id: None };
+                if let Err(err) = self.tier1_connect_to(clock, &peer_info).await {
                     tracing::warn!(target:"network", ?err, "failed to establish connection to TIER1 proxy {:?}",proxy);
                 }
             });
@@ -111,37 +156,12 @@ impl super::NetworkState {
             // We do not require all stun servers to be available, but
             // we require the received responses to be consistent.
             (Some(node_addr), config::ValidatorProxies::Dynamic(stun_servers)) => {
-                // Query all the STUN servers in parallel.
-                let queries = stun_servers.iter().map(|addr| {
-                    let clock = clock.clone();
-                    let want_ipv4 = node_addr.is_ipv4();
-                    let addr = addr.clone();
-                    self.spawn(async move {
-                        let addr = stun::lookup_host(&addr, want_ipv4).await?;
-                        match stun::query(&clock, &addr).await {
-                            Ok(ip) => Some(ip),
-                            Err(err) => {
-                                tracing::warn!(target:"network", "STUN lookup failed for {addr}: {err}");
-                                None
-                            }
-                        }
-                    })
-                });
-                let mut node_ips = vec![];
-                for q in queries {
-                    node_ips.extend(q.await.unwrap());
-                }
-                // Check that we have received non-zero responses and that they are consistent.
-                if node_ips.is_empty() {
-                    vec![]
-                } else if !node_ips.iter().all(|ip| ip == &node_ips[0]) {
-                    tracing::warn!(target:"network", "received inconsistent responses from the STUN servers");
-                    vec![]
-                } else {
-                    vec![PeerAddr {
+                match stun::query_all(clock, stun_servers, node_addr.is_ipv4()).await {
+                    Some(ip) => vec![PeerAddr {
                         peer_id: self.config.node_id(),
-                        addr: std::net::SocketAddr::new(node_ips[0], node_addr.port()),
-                    }]
+                        addr: std::net::SocketAddr::new(ip, node_addr.port()),
+                    }],
+                    None => vec![],
                 }
             }
         };
@@ -331,18 +351,12 @@ impl super::NetworkState {
                 if let Some(proxy) = proxy {
                     let proxy = (*proxy).clone();
                     handles.push(async move {
-                        let stream = tcp::Stream::connect(
-                            &PeerInfo {
-                                id: proxy.peer_id,
-                                addr: Some(proxy.addr),
-                                account_id: None,
-                            },
-                            tcp::Tier::T1,
-                            &self.config.socket_options,
-                        )
-                        .await?;
-                        PeerActor::spawn_and_handshake(clock.clone(), stream, None, self.clone())
-                            .await
+                        let peer_info = PeerInfo {
+                            id: proxy.peer_id,
+                            addr: Some(proxy.addr),
+                            account_id: None,
+                        };
+                        self.tier1_connect_to(clock, &peer_info).await
                     });
                 }
             }
@@ -1,6 +1,8 @@
 use crate::client::{ClientSenderForNetwork, SetNetworkInfo, StateRequestHeader, StateRequestPart};
 use crate::config;
 use crate::debug::{DebugStatus, GetDebugStatus};
+use crate::fault_injection::SetFaultInjection;
+use crate::hole_punching;
 use crate::network_protocol;
 use crate::network_protocol::SyncSnapshotHosts;
 use crate::network_protocol::{
@@ -11,6 +13,8 @@ use crate::peer::peer_actor::PeerActor;
 use crate::peer_manager::connection;
 use crate::peer_manager::network_state::{NetworkState, WhitelistNode};
 use crate::peer_manager::peer_store;
+use crate::quic;
+use crate::recorder;
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
 use crate::state_witness::PartialWitnessSenderForNetwork;
 use crate::stats::metrics;
@@ -236,6 +240,12 @@ impl PeerManagerActor {
             }
             v
         };
+        let recorder = config
+            .traffic_recorder
+            .as_ref()
+            .map(recorder::Recorder::new)
+            .transpose()
+            .context("Recorder::new")?;
         let my_peer_id = config.node_id();
         let arbiter = actix::Arbiter::new().handle();
         let clock = clock;
@@ -250,6 +260,7 @@ impl PeerManagerActor {
             shards_manager_adapter,
             partial_witness_adapter,
             whitelist_nodes,
+            recorder,
         ));
         arbiter.spawn({
             let arbiter = arbiter.clone();
@@ -259,12 +270,51 @@ impl PeerManagerActor {
                 // Start server if address provided.
                 if let Some(server_addr) = &state.config.node_addr {
                     tracing::debug!(target: "network", at = ?server_addr, "starting public server");
-                    let listener = match server_addr.listener() {
+                    // Hole punching connects from the port of the listener, which has to be shared.
+                    let listener = match state.hole_punching {
+                        Some(_) => server_addr.shared_listener(),
+                        None => server_addr.listener(),
+                    };
+                    let listener = match listener {
                         Ok(it) => it,
                         Err(e) => {
                             panic!("failed to start listening on server_addr={server_addr:?} e={e:?}")
                         }
                     };
+                    if state.config.tier1.as_ref().is_some_and(|cfg| cfg.enable_quic) {
+                        match quic::Endpoint::bind(server_addr, &state.config.node_key) {
+                            Ok(endpoint) => {
+                                state.quic_endpoint.set(endpoint.clone()).ok();
+                                arbiter.spawn({
+                                    let clock = clock.clone();
+                                    let state = state.clone();
+                                    async move {
+                                        while let Some(incoming) = endpoint.accept().await {
+                                            tracing::debug!(target: "network", from = ?incoming.remote_address(), "got new QUIC connection");
+                                            let clock = clock.clone();
+                                            let state = state.clone();
+                                            // Complete the QUIC handshake in the background,
+                                            // so that it doesn't block accepting other connections.
+                                            actix::spawn(async move {
+                                                let res = async {
+                                                    let stream = incoming.establish().await?;
+                                                    PeerActor::spawn(clock, stream, None, state)?;
+                                                    anyhow::Ok(())
+                                                }
+                                                .await;
+                                                if let Err(err) = res {
+                                                    tracing::info!(target:"network", ?err, "failed to accept a QUIC connection");
+                                                }
+                                            });
+                                        }
+                                    }
+                                });
+                            }
+                            Err(err) => {
+                                tracing::warn!(target: "network", ?err, "failed to start the QUIC endpoint, TIER1 will use TCP only");
+                            }
+                        }
+                    }
                     #[cfg(test)]
                     state.config.event_sink.send(Event::ServerStarted);
                     arbiter.spawn({
@@ -317,6 +367,51 @@ impl PeerManagerActor {
                         }
                     });
                 }
+                if let Some(tx_gossip) = &state.tx_gossip {
+                    // Send the transaction announcements in batches.
+                    arbiter.spawn({
+                        let clock = clock.clone();
+                        let state = state.clone();
+                        let mut interval = time::Interval::new(clock.now(), tx_gossip.config().inventory_interval);
+                        async move {
+                            loop {
+                                interval.tick(&clock).await;
+                                state.tx_gossip_send_inventory(&clock);
+                            }
+                        }
+                    });
+                }
+                if let Some(discovery) = &state.discovery {
+                    // Fetch the peers from the DNS seeds and signed peer lists.
+                    arbiter.spawn({
+                        let clock = clock.clone();
+                        let state = state.clone();
+                        let mut interval = time::Interval::new(clock.now(), discovery.config().refresh_interval);
+                        async move {
+                            loop {
+                                interval.tick(&clock).await;
+                                state.discover_peers(&clock).await;
+                            }
+                        }
+                    });
+                }
+                if let (Some(_), Some(node_addr)) = (&state.hole_punching, &state.config.node_addr) {
+                    // Discover the public address of the node to share with the peers.
+                    arbiter.spawn({
+                        let clock = clock.clone();
+                        let state = state.clone();
+                        let node_addr = **node_addr;
+                        let mut interval = time::Interval::new(clock.now(), hole_punching::PUBLIC_ADDR_REFRESH_INTERVAL);
+                        async move {
+                            loop {
+                                interval.tick(&clock).await;
+                                if let Some(hole_punching) = &state.hole_punching {
+                                    hole_punching.update_public_addr(&clock, node_addr).await;
+                                }
+                            }
+                        }
+                    });
+                }
                 // Periodically poll the connection store for connections we'd like to re-establish
                 arbiter.spawn({
                     let clock = clock.clone();
@@ -433,13 +528,19 @@ impl PeerManagerActor {
             None => return vec![],
         };
         // Find all peers whose height is within `highest_peer_horizon` from max height peer(s).
-        infos
+        let infos: Vec<_> = infos
             .into_iter()
             .filter(|i| {
                 i.highest_block_height.saturating_add(self.state.config.highest_peer_horizon)
                     >= max_height
             })
-            .collect()
+            .collect();
+        // Avoid syncing from peers with bad reputation, unless there are no other peers.
+        let threshold = self.state.peer_store.sync_peer_threshold();
+        let (reputable, disreputable): (Vec<_>, Vec<_>) = infos
+            .into_iter()
+            .partition(|i| self.state.peer_store.reputation_score(&i.peer_info.id) >= threshold);
+        if reputable.is_empty() { disreputable } else { reputable }
     }
 
     // Get peers that are potentially unreliable and we should avoid routing messages through them.
@@ -549,8 +650,16 @@ impl PeerManagerActor {
         }
 
         // Build valid candidate list to choose the peer to be removed. All peers outside the safe set.
-        let candidates = tier2.ready.values().filter(|p| !safe_set.contains(&p.peer_info.id));
-        if let Some(p) = candidates.choose(&mut rand::thread_rng()) {
+        let mut candidates: Vec<_> =
+            tier2.ready.values().filter(|p| !safe_set.contains(&p.peer_info.id)).collect();
+        // Remove the peer with the lowest reputation, choosing randomly among the ties.
+        candidates.shuffle(&mut rand::thread_rng());
+        let reputation = |p: &&Arc<connection::Connection>| {
+            self.state.peer_store.reputation_score(&p.peer_info.id)
+        };
+        if let Some(p) =
+            candidates.into_iter().min_by(|a, b| reputation(a).total_cmp(&reputation(b)))
+        {
             tracing::debug!(target: "network", id = ?p.peer_info.id,
                 tier2_len = tier2.ready.len(),
                 ideal_connections_hi = self.state.config.ideal_connections_hi,
@@ -612,6 +721,7 @@ impl PeerManagerActor {
             let prefer_previously_connected_peer =
                 thread_rng().gen_bool(PREFER_PREVIOUSLY_CONNECTED_PEER);
             if let Some(peer_info) = self.state.peer_store.unconnected_peer(
+                &self.clock,
                 |peer_state| {
                     // Ignore connecting to ourself
                     self.my_peer_id == peer_state.peer_info.id
@@ -638,6 +748,8 @@ impl PeerManagerActor {
 
                         if let Err(ref err) = result {
                             tracing::info!(target: "network", err = format!("{:#}", err), "tier2 failed to connect to {peer_info}");
+                            // The peer may be behind NAT, try to reach it with hole punching.
+                            state.request_hole_punch(&clock, &peer_info.id);
                         }
                         if state.peer_store.peer_connection_attempt(&clock, &peer_info.id, result).is_err() {
                             tracing::error!(target: "network", ?peer_info, "Failed to store connection attempt.");
@@ -804,8 +916,16 @@ impl PeerManagerActor {
                 NetworkResponses::NoResponse
             }
             NetworkRequests::BlockRequest { hash, peer_id } => {
-                if self.state.tier2.send_message(peer_id, Arc::new(PeerMessage::BlockRequest(hash)))
+                if self
+                    .state
+                    .tier2
+                    .send_message(peer_id.clone(), Arc::new(PeerMessage::BlockRequest(hash)))
                 {
+                    self.state.peer_store.request_sent(
+                        &self.clock,
+                        &peer_id,
+                        peer_store::reputation::Request::Block(hash),
+                    );
                     NetworkResponses::NoResponse
                 } else {
                     NetworkResponses::RouteNotFound
@@ -910,6 +1030,11 @@ impl PeerManagerActor {
                 if !self.state.send_message_to_peer(&self.clock, tcp::Tier::T2, routed_message) {
                     return NetworkResponses::RouteNotFound;
                 }
+                self.state.peer_store.request_sent(
+                    &self.clock,
+                    &peer_id,
+                    peer_store::reputation::Request::StatePart { shard_id, sync_hash, part_id },
+                );
 
                 tracing::debug!(target: "network", ?shard_id, ?sync_hash, ?part_id, "requesting state part from host {peer_id}");
                 NetworkResponses::SelectedDestination(peer_id)
@@ -1091,6 +1216,12 @@ impl PeerManagerActor {
                     NetworkResponses::RouteNotFound
                 }
             }
+            NetworkRequests::AnnounceTx(account_ids, tx) => {
+                if let Some(tx_gossip) = &self.state.tx_gossip {
+                    tx_gossip.announce(account_ids, tx);
+                }
+                NetworkResponses::NoResponse
+            }
             NetworkRequests::TxStatus(account_id, signer_account_id, tx_hash) => {
                 if self.state.send_message_to_account(
                     &self.clock,
@@ -1235,6 +1366,12 @@ impl PeerManagerActor {
                 }
                 PeerManagerMessageResponse::OutboundTcpConnect
             }
+            PeerManagerMessageRequest::UpdateNetworkConfig(config) => {
+                if let Err(err) = self.state.update_network_config(config) {
+                    tracing::warn!(target: "network", ?err, "ignoring invalid network config update");
+                }
+                PeerManagerMessageResponse::UpdateNetworkConfig
+            }
             // TEST-ONLY
             PeerManagerMessageRequest::FetchRoutingTable => {
                 PeerManagerMessageResponse::FetchRoutingTable(self.state.graph.routing_table.info())
@@ -1307,6 +1444,9 @@ impl actix::Handler<WithSpanContext<StateSyncEvent>> for PeerManagerActor {
             StateSyncEvent::StatePartReceived(shard_id, part_id) => {
                 self.state.snapshot_hosts.part_received(shard_id, part_id);
             }
+            StateSyncEvent::DemotedSnapshotHosts(hosts) => {
+                self.state.snapshot_hosts.set_demoted_hosts(hosts);
+            }
         }
     }
 }
@@ -1384,6 +1524,15 @@ impl actix::Handler<WithSpanContext<Tier3Request>> for PeerManagerActor {
     }
 }
 
+impl actix::Handler<SetFaultInjection> for PeerManagerActor {
+    type Result = ();
+    fn handle(&mut self, msg: SetFaultInjection, _ctx: &mut Self::Context) -> Self::Result {
+        if let Err(err) = self.state.set_fault_injection(&self.clock, msg.0) {
+            tracing::warn!(target: "network", ?err, "invalid fault injection config");
+        }
+    }
+}
+
 impl actix::Handler<GetDebugStatus> for PeerManagerActor {
     type Result = DebugStatus;
     #[perf]
@@ -1410,6 +1559,7 @@ impl actix::Handler<GetDebugStatus> for PeerManagerActor {
                                 (attempt_time.unix_timestamp(), foo)
                             },
                         ),
+                        reputation: known_peer_state.reputation.score().round() as i64,
                     })
                     .collect::<Vec<_>>();
 
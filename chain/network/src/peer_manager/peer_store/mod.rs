@@ -6,9 +6,12 @@
 //!
 //! Contents of the PeerStore are not persisted to the database. Upon starting a node,
 //! the PeerStore is initialized from the boot nodes in its config.
+//!
+//! The PeerStore also keeps track of the reputation of the known peers (see `reputation`).
 
 use crate::blacklist;
 use crate::network_protocol::PeerInfo;
+use crate::stats::metrics;
 use crate::types::{KnownPeerState, KnownPeerStatus, ReasonForBan};
 use anyhow::bail;
 use im::hashmap::Entry;
@@ -17,12 +20,14 @@ use lru::LruCache;
 use near_async::time;
 use near_primitives::network::PeerId;
 use parking_lot::Mutex;
-use rand::seq::IteratorRandom;
+use rand::seq::{IteratorRandom, SliceRandom};
 use rand::thread_rng;
+use reputation::{Event, PendingRequests, Request};
 use std::net::SocketAddr;
 use std::num::NonZeroUsize;
 use std::ops::Not;
 
+pub(crate) mod reputation;
 #[cfg(test)]
 mod testonly;
 #[cfg(test)]
@@ -72,8 +77,14 @@ pub struct Config {
     pub peer_expiration_duration: time::Duration,
     /// Duration of the ban for misbehaving peers.
     pub ban_window: time::Duration,
+    /// Configuration of the peer reputation scoring.
+    pub reputation: reputation::Config,
 }
 
+/// Number of random candidates considered when choosing a peer to connect to.
+/// The one with the highest reputation is chosen.
+const CONNECT_CANDIDATES: usize = 3;
+
 /// Known peers store, maintaining cache of known peers
 struct Inner {
     config: Config,
@@ -85,6 +96,8 @@ struct Inner {
     // It can happens that some peers don't have known address, so
     // they will not be present in this list, otherwise they will be present.
     addr_peers: HashMap<SocketAddr, VerifiedPeer>,
+    // Requests sent to the peers, for which we measure the response time.
+    pending_requests: PendingRequests,
 }
 
 impl Inner {
@@ -169,6 +182,31 @@ impl Inner {
         }
     }
 
+    /// Updates the reputation of the peer, if it is known.
+    fn record_event(&mut self, now: time::Utc, peer_id: &PeerId, event: Event) {
+        metrics::PEER_REPUTATION_EVENTS.with_label_values(&[event.into()]).inc();
+        if let Some(peer_state) = self.peer_states.peek_mut(peer_id) {
+            peer_state.reputation.record(&self.config.reputation, now, event);
+        }
+    }
+
+    /// Find a random peer based on filter, preferring the peers with higher reputation.
+    fn find_best_peer<F>(&self, filter: F) -> Option<PeerInfo>
+    where
+        F: FnMut(&&KnownPeerState) -> bool,
+    {
+        let mut candidates = (self.peer_states.iter().map(|(_, v)| v))
+            .filter(filter)
+            .choose_multiple(&mut thread_rng(), CONNECT_CANDIDATES);
+        // `choose_multiple` doesn't randomize the order of the candidates, so shuffle them
+        // to break the ties randomly.
+        candidates.shuffle(&mut thread_rng());
+        candidates
+            .into_iter()
+            .max_by(|a, b| a.reputation.score().total_cmp(&b.reputation.score()))
+            .map(|kps| kps.peer_info.clone())
+    }
+
     /// Find a random subset of peers based on filter.
     fn find_peers<F>(&self, filter: F, count: usize) -> Vec<PeerInfo>
     where
@@ -239,6 +277,19 @@ impl Inner {
         self.delete_peers(&to_remove);
     }
 
+    /// Decays the reputation of all the peers and penalizes the peers
+    /// which didn't respond to our requests in time.
+    fn update_reputation(&mut self, clock: &time::Clock) {
+        let now = clock.now_utc();
+        let cfg = self.config.reputation.clone();
+        for (_, peer_state) in &mut self.peer_states {
+            peer_state.reputation.decay(&cfg, now);
+        }
+        for peer_id in self.pending_requests.remove_expired(clock.now() - cfg.response_timeout) {
+            self.record_event(now, &peer_id, Event::SlowResponse);
+        }
+    }
+
     fn unban(&mut self, now: time::Utc) {
         let mut to_unban = vec![];
         for (peer_id, peer_state) in &self.peer_states {
@@ -277,9 +328,11 @@ impl Inner {
     /// * it unbans a peer if config.ban_window has passed
     /// * it updates KnownPeerStatus.last_seen of the connected peers
     /// * it removes peers which were not seen for config.peer_expiration_duration
+    /// * it decays the reputation of the peers
     /// This function should be called periodically.
     pub fn update(&mut self, clock: &time::Clock) {
         let now = clock.now_utc();
+        self.update_reputation(clock);
         self.unban(now);
         self.update_last_seen(now);
         self.remove_expired(now);
@@ -337,8 +390,13 @@ impl PeerStore {
             }
         }
 
-        let inner =
-            Inner { config, boot_nodes, peer_states: peer_id_2_state, addr_peers: addr_2_peer };
+        let inner = Inner {
+            config,
+            boot_nodes,
+            peer_states: peer_id_2_state,
+            addr_peers: addr_2_peer,
+            pending_requests: PendingRequests::new(),
+        };
         Ok(PeerStore(Mutex::new(inner)))
     }
 
@@ -362,7 +420,54 @@ impl PeerStore {
         self.0.lock().update(clock)
     }
 
-    #[allow(dead_code)]
+    /// Returns the reputation score of the peer, or 0 if the peer is unknown.
+    pub fn reputation_score(&self, peer_id: &PeerId) -> f64 {
+        self.0.lock().peer_states.peek(peer_id).map_or(0., |s| s.reputation.score())
+    }
+
+    /// Minimal reputation score of the peers which we use for syncing.
+    pub fn sync_peer_threshold(&self) -> f64 {
+        self.0.lock().config.reputation.sync_peer_threshold
+    }
+
+    /// Whether the peer has recently misbehaved, so that we shouldn't connect to it,
+    /// even though it is not banned.
+    pub fn in_reconnect_cooldown(&self, clock: &time::Clock, peer_id: &PeerId) -> bool {
+        let inner = self.0.lock();
+        inner.peer_states.peek(peer_id).is_some_and(|s| {
+            s.reputation.in_reconnect_cooldown(&inner.config.reputation, clock.now_utc())
+        })
+    }
+
+    /// Updates the reputation of the peer based on its behaviour.
+    pub fn record_event(&self, clock: &time::Clock, peer_id: &PeerId, event: Event) {
+        self.0.lock().record_event(clock.now_utc(), peer_id, event)
+    }
+
+    /// Records a request sent to the peer, so that its response time can be measured.
+    pub fn request_sent(&self, clock: &time::Clock, peer_id: &PeerId, request: Request) {
+        let mut inner = self.0.lock();
+        if let Some(evicted) = inner.pending_requests.insert(peer_id.clone(), request, clock.now())
+        {
+            inner.record_event(clock.now_utc(), &evicted, Event::SlowResponse);
+        }
+    }
+
+    /// Updates the reputation of the peer based on the time it took to respond to our request.
+    /// Responses to the requests we haven't sent (or which have already timed out) are ignored.
+    pub fn response_received(&self, clock: &time::Clock, peer_id: &PeerId, request: Request) {
+        let mut inner = self.0.lock();
+        let Some(sent) = inner.pending_requests.remove(peer_id.clone(), request) else {
+            return;
+        };
+        let event = if clock.now() - sent > inner.config.reputation.slow_response {
+            Event::SlowResponse
+        } else {
+            Event::UsefulData
+        };
+        inner.record_event(clock.now_utc(), peer_id, event);
+    }
+
     /// Returns the state of the current peer in memory.
     pub fn get_peer_state(&self, peer_id: &PeerId) -> Option<KnownPeerState> {
         self.0.lock().peer_states.get(peer_id).cloned()
@@ -411,18 +516,30 @@ impl PeerStore {
         Ok(())
     }
 
+    /// Lowers the reputation of a misbehaving peer, and bans it if the offence is severe
+    /// or its reputation drops to `reputation.ban_threshold` or below.
     pub fn peer_ban(
         &self,
         clock: &time::Clock,
         peer_id: &PeerId,
         ban_reason: ReasonForBan,
     ) -> anyhow::Result<()> {
-        tracing::warn!(target: "network", "Banning peer {} for {:?}", peer_id, ban_reason);
         let mut inner = self.0.lock();
+        let now = clock.now_utc();
+        inner.record_event(now, peer_id, Event::Misbehaviour(ban_reason));
+        let ban_threshold = inner.config.reputation.ban_threshold;
         if let Some(peer_state) = inner.peer_states.get_mut(peer_id) {
-            let now = clock.now_utc();
             peer_state.last_seen = now;
-            peer_state.status = KnownPeerStatus::Banned(ban_reason, now);
+            let score = peer_state.reputation.score();
+            if reputation::is_severe(ban_reason) || score <= ban_threshold {
+                tracing::warn!(target: "network", score, "Banning peer {} for {:?}", peer_id, ban_reason);
+                peer_state.status = KnownPeerStatus::Banned(ban_reason, now);
+            } else {
+                tracing::info!(target: "network", score, "Not banning peer {} for {:?}, its reputation is above the threshold", peer_id, ban_reason);
+                if peer_state.status == KnownPeerStatus::Connected {
+                    peer_state.status = KnownPeerStatus::NotConnected;
+                }
+            }
         } else {
             bail!("Peer {} is missing in the peer store", peer_id);
         }
@@ -431,43 +548,40 @@ impl PeerStore {
 
     /// Return unconnected or peers with unknown status that we can try to connect to.
     /// Peers with unknown addresses are filtered out.
+    /// Peers with higher reputation are more likely to be returned.
+    /// Peers which have recently misbehaved are filtered out.
     pub fn unconnected_peer(
         &self,
+        clock: &time::Clock,
         ignore_fn: impl Fn(&KnownPeerState) -> bool,
         prefer_previously_connected_peer: bool,
     ) -> Option<PeerInfo> {
         let inner = self.0.lock();
+        let now = clock.now_utc();
+        let ignore_fn = |p: &KnownPeerState| {
+            ignore_fn(p) || p.reputation.in_reconnect_cooldown(&inner.config.reputation, now)
+        };
         if prefer_previously_connected_peer {
-            let preferred_peer = inner.find_peers(
-                |p| {
-                    (p.status == KnownPeerStatus::NotConnected)
-                        && !ignore_fn(p)
-                        && p.peer_info.addr.is_some()
-                        // if we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
-                        && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
-                },
-                1,
-            )
-            .get(0)
-            .cloned();
+            let preferred_peer = inner.find_best_peer(|p| {
+                (p.status == KnownPeerStatus::NotConnected)
+                    && !ignore_fn(p)
+                    && p.peer_info.addr.is_some()
+                    // if we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
+                    && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
+            });
             // If we found a preferred peer - return it.
             if preferred_peer.is_some() {
                 return preferred_peer;
             };
             // otherwise, pick a peer from the wider pool below.
         }
-        inner.find_peers(
-            |p| {
-                (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
-                    && !ignore_fn(p)
-                    && p.peer_info.addr.is_some()
-                    // If we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
-                    && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
-            },
-            1,
-        )
-        .get(0)
-        .cloned()
+        inner.find_best_peer(|p| {
+            (p.status == KnownPeerStatus::NotConnected || p.status == KnownPeerStatus::Unknown)
+                && !ignore_fn(p)
+                && p.peer_info.addr.is_some()
+                // If we're connecting only to the boot nodes - filter out the nodes that are not boot nodes.
+                && (!inner.config.connect_only_to_boot_nodes || inner.boot_nodes.contains(&p.peer_info.id))
+        })
     }
 
     /// Return healthy known peers up to given amount.
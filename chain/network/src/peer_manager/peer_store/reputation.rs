@@ -0,0 +1,183 @@
+//! Reputation of the known peers.
+//!
+//! Every peer has a score, which starts at 0, goes up when the peer delivers useful data
+//! and goes down when it misbehaves (sends invalid messages, exceeds rate limits) or responds
+//! slowly to our requests. The score decays exponentially towards 0, so that old behaviour
+//! matters less than the recent one.
+//!
+//! The score is used to:
+//! * ban peers: a peer committing a minor offence (see [`is_severe`]) is banned only once its
+//!   score drops below `ban_threshold`, so that a single minor offence doesn't cut off an
+//!   otherwise well behaving peer. Severe offences get the peer banned regardless of its score.
+//!   A misbehaving peer which is not banned is still disconnected, and we don't connect
+//!   to it again for `reconnect_cooldown`.
+//! * choose peers to connect to: out of a few random candidates the one with the highest score is picked.
+//! * choose peers to evict: the connection to the peer with the lowest score is dropped first.
+//! * choose sync peers: peers with score below `sync_peer_threshold` are not offered for syncing,
+//!   unless there are no other peers.
+use crate::types::ReasonForBan;
+use lru::LruCache;
+use near_async::time;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use near_primitives::types::ShardId;
+use std::num::NonZeroUsize;
+
+/// Scores are kept within [-MAX_SCORE, MAX_SCORE].
+pub(crate) const MAX_SCORE: f64 = 100.;
+/// Maximum number of our requests, for which we are waiting for the response.
+pub(crate) const MAX_PENDING_REQUESTS: usize = 1000;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Time after which the score drops to half of its value.
+    pub half_life: time::Duration,
+    /// Misbehaving peers with a score below this value get banned.
+    pub ban_threshold: f64,
+    /// Peers with a score below this value are not used for syncing.
+    pub sync_peer_threshold: f64,
+    /// Responses which took longer than that are considered slow.
+    pub slow_response: time::Duration,
+    /// Requests which are not responded within this time are considered failed.
+    pub response_timeout: time::Duration,
+    /// Peers which misbehaved without getting banned are not connected to within this time.
+    pub reconnect_cooldown: time::Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            half_life: time::Duration::hours(1),
+            ban_threshold: -50.,
+            sync_peer_threshold: -10.,
+            slow_response: time::Duration::seconds(2),
+            response_timeout: time::Duration::seconds(30),
+            reconnect_cooldown: time::Duration::minutes(5),
+        }
+    }
+}
+
+/// Whether the offence gets the peer banned regardless of its reputation.
+/// Only the offences which honest peers may commit by accident are left to the score.
+pub(crate) fn is_severe(reason: ReasonForBan) -> bool {
+    !matches!(reason, ReasonForBan::None | ReasonForBan::ProvidedNotEnoughHeaders)
+}
+
+/// Behaviour of a peer that affects its reputation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum::IntoStaticStr)]
+pub(crate) enum Event {
+    /// The peer has sent an invalid message.
+    Misbehaviour(ReasonForBan),
+    /// The peer has sent the given number of messages exceeding the rate limits.
+    RateLimited(u64),
+    /// The peer has responded to our request too late or not at all.
+    SlowResponse,
+    /// The peer has responded to our request in time.
+    UsefulData,
+}
+
+impl Event {
+    fn delta(&self) -> f64 {
+        match self {
+            // Honest peers may occasionally fail to provide all the headers.
+            Event::Misbehaviour(ReasonForBan::ProvidedNotEnoughHeaders) => -20.,
+            Event::Misbehaviour(ReasonForBan::None) => 0.,
+            Event::Misbehaviour(ReasonForBan::Abusive) => -50.,
+            Event::Misbehaviour(_) => -MAX_SCORE,
+            Event::RateLimited(messages) => -0.5 * *messages as f64,
+            Event::SlowResponse => -2.,
+            Event::UsefulData => 1.,
+        }
+    }
+}
+
+/// Decaying reputation score of a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reputation {
+    score: f64,
+    /// Time at which `score` was last decayed.
+    updated: time::Utc,
+    /// Time of the last misbehaviour of the peer.
+    misbehaved: Option<time::Utc>,
+}
+
+impl Reputation {
+    pub fn new(now: time::Utc) -> Self {
+        Self { score: 0., updated: now, misbehaved: None }
+    }
+
+    /// Score as of the last update.
+    pub fn score(&self) -> f64 {
+        self.score
+    }
+
+    /// Decays the score up to `now`.
+    pub(crate) fn decay(&mut self, cfg: &Config, now: time::Utc) {
+        if now <= self.updated {
+            return;
+        }
+        let half_lives = (now - self.updated) / cfg.half_life;
+        self.score *= 0.5f64.powf(half_lives);
+        self.updated = now;
+    }
+
+    pub(crate) fn record(&mut self, cfg: &Config, now: time::Utc, event: Event) {
+        self.decay(cfg, now);
+        self.score = (self.score + event.delta()).clamp(-MAX_SCORE, MAX_SCORE);
+        if matches!(event, Event::Misbehaviour(reason) if reason != ReasonForBan::None) {
+            self.misbehaved = Some(now);
+        }
+    }
+
+    /// Whether the peer misbehaved within the last `cfg.reconnect_cooldown`.
+    pub(crate) fn in_reconnect_cooldown(&self, cfg: &Config, now: time::Utc) -> bool {
+        self.misbehaved.is_some_and(|misbehaved| now < misbehaved + cfg.reconnect_cooldown)
+    }
+}
+
+/// Request sent to a peer, for which we measure the response time.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum Request {
+    Block(CryptoHash),
+    StatePart { shard_id: ShardId, sync_hash: CryptoHash, part_id: u64 },
+}
+
+/// Requests for which we are waiting for the response.
+pub(crate) struct PendingRequests(LruCache<(PeerId, Request), time::Instant>);
+
+impl PendingRequests {
+    pub fn new() -> Self {
+        Self(LruCache::new(NonZeroUsize::new(MAX_PENDING_REQUESTS).unwrap()))
+    }
+
+    /// Returns the peer whose request has been evicted to make room for this one.
+    /// Evicted requests are never responded to in time, so that a peer can't avoid
+    /// the timeout by having its requests pushed out of the cache.
+    pub fn insert(
+        &mut self,
+        peer_id: PeerId,
+        request: Request,
+        now: time::Instant,
+    ) -> Option<PeerId> {
+        let key = (peer_id, request);
+        match self.0.push(key.clone(), now) {
+            Some((evicted, _)) if evicted != key => Some(evicted.0),
+            _ => None,
+        }
+    }
+
+    /// Returns the time at which the request has been sent, if it is still pending.
+    pub fn remove(&mut self, peer_id: PeerId, request: Request) -> Option<time::Instant> {
+        self.0.pop(&(peer_id, request))
+    }
+
+    /// Removes the requests sent before `deadline`, returning the peers which didn't respond.
+    pub fn remove_expired(&mut self, deadline: time::Instant) -> Vec<PeerId> {
+        let expired: Vec<_> =
+            self.0.iter().filter(|(_, sent)| **sent < deadline).map(|(k, _)| k.clone()).collect();
+        for key in &expired {
+            self.0.pop(key);
+        }
+        expired.into_iter().map(|(peer_id, _)| peer_id).collect()
+    }
+}
@@ -2,6 +2,7 @@ use super::*;
 use crate::blacklist::Blacklist;
 use near_async::time;
 use near_crypto::{KeyType, SecretKey};
+use near_primitives::hash::CryptoHash;
 use std::collections::HashSet;
 use std::net::{Ipv4Addr, SocketAddrV4};
 
@@ -19,7 +20,7 @@ fn get_peer_info(peer_id: PeerId, addr: Option<SocketAddr>) -> PeerInfo {
 
 fn gen_peer_info(port: u16) -> PeerInfo {
     PeerInfo {
-        id: PeerId::new(SecretKey::from_random(KeyType::ED25519).public_key()),
+        id: PeerId::new(SecretKey::from_random(KeyType::ED25519).unwrap().public_key()),
         addr: Some(get_addr(port)),
         account_id: None,
     }
@@ -37,6 +38,7 @@ fn make_config(
         connect_only_to_boot_nodes,
         ban_window: time::Duration::seconds(1),
         peer_expiration_duration: time::Duration::days(1000),
+        reputation: Default::default(),
     }
 }
 
@@ -66,8 +68,8 @@ fn test_unconnected_peer() {
         PeerStore::new(&clock.clock(), make_config(&boot_nodes, Blacklist::default(), false))
             .unwrap();
 
-    assert!(peer_store.unconnected_peer(|_| false, false).is_some());
-    assert!(peer_store.unconnected_peer(|_| true, false).is_none());
+    assert!(peer_store.unconnected_peer(&clock.clock(), |_| false, false).is_some());
+    assert!(peer_store.unconnected_peer(&clock.clock(), |_| true, false).is_none());
 }
 
 #[test]
@@ -120,7 +122,7 @@ fn test_unknown_vs_not_connected() {
     // if we prefer 'previously connected' peers - we should keep picking 'b'.
     assert_eq!(
         (0..10)
-            .map(|_| peer_store.unconnected_peer(|_| false, true).unwrap().id)
+            .map(|_| peer_store.unconnected_peer(&clock.clock(), |_| false, true).unwrap().id)
             .collect::<HashSet<PeerId>>(),
         [peer_info_b.id.clone()].into_iter().collect::<HashSet<_>>()
     );
@@ -128,7 +130,7 @@ fn test_unknown_vs_not_connected() {
     // if we don't care, we should pick either 'b' or 'boot'.
     assert_eq!(
         (0..100)
-            .map(|_| peer_store.unconnected_peer(|_| false, false).unwrap().id)
+            .map(|_| peer_store.unconnected_peer(&clock.clock(), |_| false, false).unwrap().id)
             .collect::<HashSet<PeerId>>(),
         [peer_info_b.id.clone(), peer_info_boot_node.id.clone()]
             .into_iter()
@@ -164,7 +166,10 @@ fn test_unconnected_peer_only_boot_nodes() {
                 .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_in_store.clone());
         peer_store.peer_connected(&clock.clock(), &peer_info_a);
-        assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_in_store.clone()));
+        assert_eq!(
+            peer_store.unconnected_peer(&clock.clock(), |_| false, false),
+            Some(peer_in_store.clone())
+        );
     }
 
     // 1 boot node (peer_info_a) that we're already connected to.
@@ -176,7 +181,7 @@ fn test_unconnected_peer_only_boot_nodes() {
                 .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_in_store);
         peer_store.peer_connected(&clock.clock(), &peer_info_a);
-        assert_eq!(peer_store.unconnected_peer(|_| false, false), None);
+        assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false, false), None);
     }
 
     // 1 boot node (peer_info_a) is in the store.
@@ -188,7 +193,10 @@ fn test_unconnected_peer_only_boot_nodes() {
         )
         .unwrap();
         peer_store.add_direct_peer(&clock.clock(), peer_info_a.clone());
-        assert_eq!(peer_store.unconnected_peer(|_| false, false), Some(peer_info_a.clone()));
+        assert_eq!(
+            peer_store.unconnected_peer(&clock.clock(), |_| false, false),
+            Some(peer_info_a.clone())
+        );
     }
 }
 
@@ -482,3 +490,177 @@ fn test_lru_ignore_duplicate_peers() {
     peer_store.add_indirect_peers(&clock.clock(), peer_infos[10..].iter().cloned());
     assert_peers_in_cache(&peer_store, &peer_ids[5..], &peer_addresses[5..]);
 }
+
+#[test]
+fn minor_offences_accumulate() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[peer_info.clone()], Blacklist::default(), false),
+    )
+    .unwrap();
+    for _ in 0..2 {
+        peer_store
+            .peer_ban(&clock.clock(), &peer_info.id, ReasonForBan::ProvidedNotEnoughHeaders)
+            .unwrap();
+        assert!(!peer_store.is_banned(&peer_info.id));
+    }
+    peer_store
+        .peer_ban(&clock.clock(), &peer_info.id, ReasonForBan::ProvidedNotEnoughHeaders)
+        .unwrap();
+    assert!(peer_store.is_banned(&peer_info.id));
+}
+
+#[test]
+fn severe_offences_ban_reputable_peers() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[peer_info.clone()], Blacklist::default(), false),
+    )
+    .unwrap();
+    for _ in 0..reputation::MAX_SCORE as usize {
+        peer_store.record_event(&clock.clock(), &peer_info.id, reputation::Event::UsefulData);
+    }
+    assert_eq!(peer_store.reputation_score(&peer_info.id), reputation::MAX_SCORE);
+    peer_store.peer_ban(&clock.clock(), &peer_info.id, ReasonForBan::BadBlock).unwrap();
+    assert!(peer_store.is_banned(&peer_info.id));
+}
+
+#[test]
+fn misbehaving_peers_cool_down() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let config = make_config(&[peer_info.clone()], Blacklist::default(), false);
+    let reconnect_cooldown = config.reputation.reconnect_cooldown;
+    let peer_store = PeerStore::new(&clock.clock(), config).unwrap();
+    peer_store.peer_connected(&clock.clock(), &peer_info);
+    peer_store
+        .peer_ban(&clock.clock(), &peer_info.id, ReasonForBan::ProvidedNotEnoughHeaders)
+        .unwrap();
+    assert!(!peer_store.is_banned(&peer_info.id));
+    assert!(peer_store.in_reconnect_cooldown(&clock.clock(), &peer_info.id));
+    assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false, false), None);
+
+    clock.advance(reconnect_cooldown);
+    assert!(!peer_store.in_reconnect_cooldown(&clock.clock(), &peer_info.id));
+    assert_eq!(peer_store.unconnected_peer(&clock.clock(), |_| false, false), Some(peer_info));
+}
+
+#[test]
+fn rate_limited_messages_are_aggregated() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[peer_info.clone()], Blacklist::default(), false),
+    )
+    .unwrap();
+    peer_store.record_event(&clock.clock(), &peer_info.id, reputation::Event::RateLimited(4));
+    assert_eq!(peer_store.reputation_score(&peer_info.id), -2.);
+    // Exceeding the rate limits doesn't prevent reconnecting.
+    assert!(!peer_store.in_reconnect_cooldown(&clock.clock(), &peer_info.id));
+}
+
+#[test]
+fn reputation_decays() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let config = make_config(&[peer_info.clone()], Blacklist::default(), false);
+    let half_life = config.reputation.half_life;
+    let peer_store = PeerStore::new(&clock.clock(), config).unwrap();
+    peer_store
+        .peer_ban(&clock.clock(), &peer_info.id, ReasonForBan::ProvidedNotEnoughHeaders)
+        .unwrap();
+    assert_eq!(peer_store.reputation_score(&peer_info.id), -20.);
+    clock.advance(half_life);
+    peer_store.update(&clock.clock());
+    assert_eq!(peer_store.reputation_score(&peer_info.id), -10.);
+}
+
+#[test]
+fn response_times() {
+    let clock = time::FakeClock::default();
+    let peer_info = gen_peer_info(0);
+    let config = make_config(&[peer_info.clone()], Blacklist::default(), false);
+    let reputation_config = config.reputation.clone();
+    let peer_store = PeerStore::new(&clock.clock(), config).unwrap();
+    let request = |i: u8| reputation::Request::Block(CryptoHash::hash_bytes(&[i]));
+    let score = || peer_store.reputation_score(&peer_info.id);
+
+    // Timely response.
+    peer_store.request_sent(&clock.clock(), &peer_info.id, request(0));
+    peer_store.response_received(&clock.clock(), &peer_info.id, request(0));
+    assert_eq!(score(), 1.);
+    // Response to a request we didn't send is ignored.
+    peer_store.response_received(&clock.clock(), &peer_info.id, request(0));
+    assert_eq!(score(), 1.);
+
+    // Slow response.
+    peer_store.request_sent(&clock.clock(), &peer_info.id, request(1));
+    clock.advance(reputation_config.slow_response + time::Duration::seconds(1));
+    peer_store.response_received(&clock.clock(), &peer_info.id, request(1));
+    assert!(score() < -0.9);
+
+    // No response at all.
+    let before = score();
+    peer_store.request_sent(&clock.clock(), &peer_info.id, request(2));
+    clock.advance(reputation_config.response_timeout + time::Duration::seconds(1));
+    peer_store.update(&clock.clock());
+    assert!(score() < before - 1.9);
+}
+
+#[test]
+fn evicted_requests_count_as_slow_responses() {
+    let clock = time::FakeClock::default();
+    let slow = gen_peer_info(0);
+    let busy = gen_peer_info(1);
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[slow.clone(), busy.clone()], Blacklist::default(), false),
+    )
+    .unwrap();
+    let request = |i: usize| reputation::Request::Block(CryptoHash::hash_bytes(&i.to_le_bytes()));
+
+    peer_store.request_sent(&clock.clock(), &slow.id, request(0));
+    // Sending the same request again doesn't evict anything.
+    peer_store.request_sent(&clock.clock(), &slow.id, request(0));
+    assert_eq!(peer_store.reputation_score(&slow.id), 0.);
+    for i in 1..reputation::MAX_PENDING_REQUESTS {
+        peer_store.request_sent(&clock.clock(), &busy.id, request(i));
+    }
+    assert_eq!(peer_store.reputation_score(&slow.id), 0.);
+
+    // The request to the slow peer is the least recently sent one.
+    peer_store.request_sent(&clock.clock(), &busy.id, request(reputation::MAX_PENDING_REQUESTS));
+    assert_eq!(peer_store.reputation_score(&slow.id), -2.);
+    assert_eq!(peer_store.reputation_score(&busy.id), 0.);
+}
+
+#[test]
+fn unconnected_peer_prefers_reputable_peers() {
+    let clock = time::FakeClock::default();
+    let good = gen_peer_info(0);
+    let bad = gen_peer_info(1);
+    let peer_store = PeerStore::new(
+        &clock.clock(),
+        make_config(&[good.clone(), bad.clone()], Blacklist::default(), false),
+    )
+    .unwrap();
+    peer_store.peer_ban(&clock.clock(), &bad.id, ReasonForBan::ProvidedNotEnoughHeaders).unwrap();
+    assert!(!peer_store.is_banned(&bad.id));
+    clock.advance(reputation::Config::default().reconnect_cooldown);
+    for _ in 0..10 {
+        assert_eq!(
+            peer_store.unconnected_peer(&clock.clock(), |_| false, false),
+            Some(good.clone())
+        );
+    }
+    // The bad peer is still returned if it is the only candidate.
+    assert_eq!(
+        peer_store.unconnected_peer(&clock.clock(), |p| p.peer_info.id == good.id, false),
+        Some(bad)
+    );
+}
@@ -107,6 +107,7 @@ async fn loop_connection() {
                 &pm.cfg.node_key,
             ),
             owned_account: None,
+            encrypted_transport: false,
         }))
         .await;
     let reason = events
@@ -177,6 +178,7 @@ async fn owned_account_mismatch() {
                 }
                 .sign(&signer),
             ),
+            encrypted_transport: false,
         }))
         .await;
     let reason = events
@@ -299,6 +301,7 @@ async fn invalid_edge() {
                     }
                     .sign(&signer),
                 ),
+                encrypted_transport: false,
             };
             let handshake = match tier {
                 tcp::Tier::T1 => PeerMessage::Tier1Handshake(handshake),
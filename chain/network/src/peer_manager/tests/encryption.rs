@@ -0,0 +1,199 @@
+use crate::config::{NetworkConfig, SocketOptions};
+use crate::network_protocol::testonly as data;
+use crate::network_protocol::{Encoding, Handshake, PartialEdgeInfo, PeerMessage, Ping, Pong};
+use crate::peer::peer_actor::ClosingReason;
+use crate::peer_manager::peer_manager_actor::Event as PME;
+use crate::peer_manager::testonly::start as start_pm;
+use crate::peer_manager::testonly::{ActorHandler, Event};
+use crate::peer_manager::tests::routing::{wait_for_ping, wait_for_pong};
+use crate::tcp;
+use crate::testonly::{make_rng, stream};
+use crate::types::Edge;
+use near_async::time;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::network::PeerId;
+use near_primitives::version::{self, ProtocolFeature, ProtocolVersion};
+use near_store::db::TestDB;
+use std::sync::Arc;
+
+fn make_config(
+    chain: &data::Chain,
+    rng: &mut impl rand::Rng,
+    encrypted_transport: bool,
+) -> NetworkConfig {
+    let mut cfg = chain.make_config(rng);
+    cfg.encrypted_transport = encrypted_transport;
+    cfg
+}
+
+async fn is_encrypted(pm: &ActorHandler, peer_id: &PeerId) -> bool {
+    let peer_id = peer_id.clone();
+    pm.with_state(move |s| async move { s.tier2.load().ready.get(&peer_id).unwrap().encrypted })
+        .await
+}
+
+/// Checks that messages go through the connection between pm0 and pm1 both ways.
+async fn ping(clock: &time::Clock, pm0: &ActorHandler, pm1: &ActorHandler) {
+    let id0 = pm0.cfg.node_id();
+    let id1 = pm1.cfg.node_id();
+    pm0.wait_for_routing_table(&[(id1.clone(), vec![id1.clone()])]).await;
+    let mut pm0_ev = pm0.events.from_now();
+    let mut pm1_ev = pm1.events.from_now();
+    pm0.send_ping(clock, 0, id1.clone()).await;
+    wait_for_ping(&mut pm1_ev, Ping { nonce: 0, source: id0.clone() }).await;
+    wait_for_pong(&mut pm0_ev, Pong { nonce: 0, source: id1 }).await;
+}
+
+/// Sends a handshake over a raw connection to `pm`.
+/// Returns the handshake sent back or the reason for which `pm` closed the connection.
+async fn raw_handshake(
+    rng: &mut impl rand::Rng,
+    clock: &time::Clock,
+    chain: &data::Chain,
+    pm: &ActorHandler,
+    protocol_version: ProtocolVersion,
+    encrypted_transport: bool,
+) -> Result<Handshake, ClosingReason> {
+    let mut events = pm.events.from_now();
+    let stream = tcp::Stream::connect(&pm.peer_info(), tcp::Tier::T2, &SocketOptions::default())
+        .await
+        .unwrap();
+    let stream_id = stream.id();
+    let mut stream = stream::Stream::new(Some(Encoding::Proto), stream);
+    let peer_key = data::make_secret_key(rng);
+    let peer_id = PeerId::new(peer_key.public_key());
+    let nonce = Edge::create_fresh_nonce(clock);
+    stream
+        .write(&PeerMessage::Tier2Handshake(Handshake {
+            protocol_version,
+            oldest_supported_version: version::MIN_SUPPORTED_PROTOCOL_VERSION,
+            sender_peer_id: peer_id.clone(),
+            target_peer_id: pm.cfg.node_id(),
+            sender_listen_port: Some(24567),
+            sender_chain_info: chain.get_peer_chain_info(),
+            partial_edge_info: PartialEdgeInfo::new(&peer_id, &pm.cfg.node_id(), nonce, &peer_key),
+            owned_account: None,
+            encrypted_transport,
+        }))
+        .await;
+    let handshake = match stream.read().await {
+        Ok(PeerMessage::Tier2Handshake(handshake)) => handshake,
+        Ok(got) => panic!("got = {got:?}, want Handshake"),
+        Err(_) => {
+            return Err(events
+                .recv_until(|ev| match ev {
+                    Event::PeerManager(PME::ConnectionClosed(ev)) if ev.stream_id == stream_id => {
+                        Some(ev.reason)
+                    }
+                    _ => None,
+                })
+                .await);
+        }
+    };
+    Ok(handshake)
+}
+
+// Both peers enable the encrypted transport.
+#[tokio::test]
+#[cfg_attr(not(feature = "nightly"), ignore)]
+async fn encrypted_transport() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let pm0 =
+        start_pm(clock.clock(), TestDB::new(), make_config(&chain, rng, true), chain.clone()).await;
+    let pm1 =
+        start_pm(clock.clock(), TestDB::new(), make_config(&chain, rng, true), chain.clone()).await;
+    pm0.connect_to(&pm1.peer_info(), tcp::Tier::T2).await;
+    assert!(is_encrypted(&pm0, &pm1.cfg.node_id()).await);
+    assert!(is_encrypted(&pm1, &pm0.cfg.node_id()).await);
+    ping(&clock.clock(), &pm0, &pm1).await;
+    ping(&clock.clock(), &pm1, &pm0).await;
+}
+
+// Only one of the peers enables the encrypted transport, so the connection is not encrypted.
+#[tokio::test]
+async fn peer_without_encrypted_transport() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let pm0 =
+        start_pm(clock.clock(), TestDB::new(), make_config(&chain, rng, true), chain.clone()).await;
+    let pm1 =
+        start_pm(clock.clock(), TestDB::new(), make_config(&chain, rng, false), chain.clone())
+            .await;
+    let pm2 =
+        start_pm(clock.clock(), TestDB::new(), make_config(&chain, rng, false), chain.clone())
+            .await;
+    let pm3 =
+        start_pm(clock.clock(), TestDB::new(), make_config(&chain, rng, true), chain.clone()).await;
+
+    tracing::info!(target:"test", "outbound peer requests encryption");
+    pm0.connect_to(&pm1.peer_info(), tcp::Tier::T2).await;
+    assert!(!is_encrypted(&pm0, &pm1.cfg.node_id()).await);
+    assert!(!is_encrypted(&pm1, &pm0.cfg.node_id()).await);
+    ping(&clock.clock(), &pm0, &pm1).await;
+
+    tracing::info!(target:"test", "inbound peer accepts encryption");
+    pm2.connect_to(&pm3.peer_info(), tcp::Tier::T2).await;
+    assert!(!is_encrypted(&pm2, &pm3.cfg.node_id()).await);
+    assert!(!is_encrypted(&pm3, &pm2.cfg.node_id()).await);
+    ping(&clock.clock(), &pm3, &pm2).await;
+}
+
+// A peer with a protocol version predating the encrypted transport is served in plaintext,
+// even if it sets the flag.
+#[tokio::test]
+async fn older_protocol_peer() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let pm =
+        start_pm(clock.clock(), TestDB::new(), make_config(&chain, rng, true), chain.clone()).await;
+    let old_version = ProtocolFeature::EncryptedPeerTransport.protocol_version() - 1;
+    let handshake =
+        raw_handshake(rng, &clock.clock(), &chain, &pm, old_version, true).await.unwrap();
+    assert!(!handshake.encrypted_transport);
+}
+
+// The flag of the outbound peer was cleared on the way. The inbound peer falls back to
+// plaintext, unless it requires the encrypted transport.
+#[tokio::test]
+#[cfg_attr(not(feature = "nightly"), ignore)]
+async fn stripped_encrypted_transport_flag() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let pm =
+        start_pm(clock.clock(), TestDB::new(), make_config(&chain, rng, true), chain.clone()).await;
+    let handshake =
+        raw_handshake(rng, &clock.clock(), &chain, &pm, version::PROTOCOL_VERSION, true)
+            .await
+            .unwrap();
+    assert!(handshake.encrypted_transport);
+    let handshake =
+        raw_handshake(rng, &clock.clock(), &chain, &pm, version::PROTOCOL_VERSION, false)
+            .await
+            .unwrap();
+    assert!(!handshake.encrypted_transport);
+
+    let mut cfg = make_config(&chain, rng, true);
+    cfg.require_encrypted_transport = true;
+    let pm = start_pm(clock.clock(), TestDB::new(), cfg, chain.clone()).await;
+    let reason = raw_handshake(rng, &clock.clock(), &chain, &pm, version::PROTOCOL_VERSION, false)
+        .await
+        .unwrap_err();
+    assert_eq!(reason, ClosingReason::EncryptionRequired);
+}
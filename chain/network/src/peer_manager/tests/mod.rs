@@ -1,8 +1,10 @@
 mod accounts_data;
 mod connection_pool;
+mod encryption;
 mod fuzzers;
 mod nonce;
 mod routing;
 mod snapshot_hosts;
 mod tier1;
 mod tier2;
+mod tx_gossip;
@@ -188,7 +188,7 @@ async fn invalid_signature_not_broadcast() {
     assert_eq!(empty_sync_msg.hosts, vec![]);
 
     tracing::info!(target:"test", "Send an invalid SyncSnapshotHosts message from peer1. One of the host infos has an invalid signature.");
-    let random_secret_key = SecretKey::from_random(near_crypto::KeyType::ED25519);
+    let random_secret_key = SecretKey::from_random(near_crypto::KeyType::ED25519).unwrap();
     let invalid_info = make_snapshot_host_info(&peer1_config.node_id(), &random_secret_key, rng);
 
     let ok_info_a = make_snapshot_host_info(&peer1_config.node_id(), &peer1_config.node_key, rng);
@@ -1,6 +1,6 @@
 use crate::config;
 use crate::network_protocol::testonly as data;
-use crate::network_protocol::{PeerAddr, PeerMessage, RoutedMessageBody};
+use crate::network_protocol::{PeerAddr, PeerInfo, PeerMessage, RoutedMessageBody};
 use crate::peer_manager;
 use crate::peer_manager::peer_manager_actor::Event as PME;
 use crate::peer_manager::testonly::Event;
@@ -11,6 +11,10 @@ use crate::testonly::{Rng, make_rng};
 use near_async::time;
 use near_o11y::testonly::init_test_logger;
 use near_primitives::block_header::{Approval, ApprovalInner};
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ShardChunkHeader;
+use near_primitives::stateless_validation::partial_witness::PartialEncodedStateWitness;
+use near_primitives::types::{EpochId, ShardId};
 use near_primitives::validator_signer::ValidatorSigner;
 use near_store::db::TestDB;
 use rand::Rng as _;
@@ -177,6 +181,171 @@ async fn direct_connections() {
     test_clique(rng, &clock.clock(), &pms[..]).await;
 }
 
+/// Same as `direct_connections`, but some of the nodes have QUIC enabled.
+/// Connections between QUIC enabled nodes should use QUIC, the rest should fall back to TCP.
+#[tokio::test]
+async fn direct_connections_quic() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let mut pms = vec![];
+    for i in 0..5 {
+        let mut cfg = chain.make_config(rng);
+        cfg.tier1.as_mut().unwrap().enable_quic = i % 2 == 0;
+        pms.push(start_pm(clock.clock(), near_store::db::TestDB::new(), cfg, chain.clone()).await);
+    }
+    let pms: Vec<_> = pms.iter().collect();
+
+    tracing::info!(target:"test", "Connect peers serially.");
+    for i in 1..pms.len() {
+        pms[i - 1].connect_to(&pms[i].peer_info(), tcp::Tier::T2).await;
+    }
+
+    tracing::info!(target:"test", "Set chain info.");
+    let chain_info = peer_manager::testonly::make_chain_info(
+        &chain,
+        &pms.iter().map(|pm| &pm.cfg).collect::<Vec<_>>()[..],
+    );
+    for pm in &pms {
+        pm.set_chain_info(chain_info.clone()).await;
+    }
+    tracing::info!(target:"test", "Establish connections.");
+    establish_connections(&clock.clock(), &pms[..]).await;
+    tracing::info!(target:"test", "Test clique.");
+    test_clique(rng, &clock.clock(), &pms[..]).await;
+
+    tracing::info!(target:"test", "Check transports.");
+    let quic_enabled: HashSet<_> = pms
+        .iter()
+        .filter(|pm| pm.cfg.tier1.as_ref().unwrap().enable_quic)
+        .map(|pm| pm.cfg.node_id())
+        .collect();
+    for pm in &pms {
+        let enabled = quic_enabled.contains(&pm.cfg.node_id());
+        let conns = pm
+            .with_state(|s| async move {
+                s.tier1
+                    .load()
+                    .ready
+                    .values()
+                    .map(|c| (c.peer_info.id.clone(), c.quic))
+                    .collect::<Vec<_>>()
+            })
+            .await;
+        assert_eq!(pms.len() - 1, conns.len());
+        for (peer_id, quic) in conns {
+            assert_eq!(enabled && quic_enabled.contains(&peer_id), quic, "connection to {peer_id}");
+        }
+
+        // QUIC failures are remembered only for the peers without QUIC.
+        let failures = pm
+            .with_state(|s| async move {
+                s.tier1_quic_failures.lock().iter().map(|(id, _)| id.clone()).collect::<Vec<_>>()
+            })
+            .await;
+        for peer_id in failures {
+            assert!(enabled && !quic_enabled.contains(&peer_id), "QUIC failure of {peer_id}");
+        }
+    }
+}
+
+/// Sends a large `PartialEncodedStateWitness` followed by a small `BlockApproval` between two
+/// TIER1 peers. Returns whether the approval was delivered first.
+async fn approval_overtakes_witness(enable_quic: bool) -> bool {
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let mut pms = vec![];
+    for _ in 0..2 {
+        let mut cfg = chain.make_config(rng);
+        cfg.tier1.as_mut().unwrap().enable_quic = enable_quic;
+        pms.push(start_pm(clock.clock(), TestDB::new(), cfg, chain.clone()).await);
+    }
+    let pms: Vec<_> = pms.iter().collect();
+    pms[0].connect_to(&pms[1].peer_info(), tcp::Tier::T2).await;
+    let chain_info = peer_manager::testonly::make_chain_info(
+        &chain,
+        &pms.iter().map(|pm| &pm.cfg).collect::<Vec<_>>()[..],
+    );
+    for pm in &pms {
+        pm.set_chain_info(chain_info.clone()).await;
+    }
+    establish_connections(&clock.clock(), &pms[..]).await;
+
+    let signer = pms[0].cfg.validator.signer.get().unwrap();
+    let target = pms[1].cfg.validator.signer.get().unwrap().validator_id().clone();
+    let witness = PartialEncodedStateWitness::new(
+        EpochId::default(),
+        ShardChunkHeader::new_dummy(1, ShardId::new(0), CryptoHash::default()),
+        0,
+        vec![0; 16 * bytesize::MIB as usize],
+        16 * bytesize::MIB as usize,
+        signer.as_ref(),
+    );
+    let approval = make_block_approval(rng, signer.as_ref());
+    let mut events = pms[1].events.from_now();
+    let clock = clock.clock();
+    pms[0]
+        .with_state(move |s| async move {
+            let witness = RoutedMessageBody::PartialEncodedStateWitness(witness);
+            assert!(s.send_message_to_account(&clock, &target, witness));
+            let approval = RoutedMessageBody::BlockApproval(approval);
+            assert!(s.send_message_to_account(&clock, &target, approval));
+        })
+        .await;
+    events
+        .recv_until(|ev| match ev {
+            Event::PeerManager(PME::MessageProcessed(tcp::Tier::T1, PeerMessage::Routed(msg))) => {
+                match msg.body {
+                    RoutedMessageBody::BlockApproval(_) => Some(true),
+                    RoutedMessageBody::PartialEncodedStateWitness(_) => Some(false),
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .await
+}
+
+/// Over TCP a small `BlockApproval` waits until a large `PartialEncodedStateWitness` sent before
+/// it is delivered. Over QUIC they are sent on separate streams, so the approval isn't delayed.
+#[tokio::test]
+async fn quic_no_head_of_line_blocking() {
+    init_test_logger();
+    assert!(!approval_overtakes_witness(false).await);
+    assert!(approval_overtakes_witness(true).await);
+}
+
+/// QUIC connections are established only if the TLS certificate of the peer carries
+/// the key of the expected peer.
+#[tokio::test]
+async fn quic_verifies_peer_key() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let mut pms = vec![];
+    for _ in 0..2 {
+        let mut cfg = chain.make_config(rng);
+        cfg.tier1.as_mut().unwrap().enable_quic = true;
+        pms.push(start_pm(clock.clock(), TestDB::new(), cfg, chain.clone()).await);
+    }
+    let endpoint =
+        pms[0].with_state(|s| async move { s.quic_endpoint.get().cloned().unwrap() }).await;
+
+    let peer_info = pms[1].peer_info();
+    endpoint.connect(&peer_info).await.unwrap();
+    let impostor = PeerInfo { id: data::make_peer_id(rng), ..peer_info };
+    assert!(endpoint.connect(&impostor).await.is_err());
+}
+
 /// Test which spawns N validators, each with 1 proxy.
 /// All the nodes are connected in TIER2 star topology.
 /// Then all validators connect to the proxy of each other validator.
@@ -0,0 +1,85 @@
+use crate::config::SocketOptions;
+use crate::network_protocol::testonly as data;
+use crate::network_protocol::{Encoding, Handshake, PartialEdgeInfo, PeerMessage};
+use crate::peer_manager::peer_manager_actor::Event as PME;
+use crate::peer_manager::testonly::start as start_pm;
+use crate::peer_manager::testonly::{ActorHandler, Event};
+use crate::tcp;
+use crate::testonly::{make_rng, stream};
+use crate::tx_gossip;
+use crate::types::Edge;
+use near_async::time;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::network::PeerId;
+use near_primitives::version::{self, ProtocolFeature, ProtocolVersion};
+use near_store::db::TestDB;
+use std::sync::Arc;
+
+/// Connects a raw peer to `pm` with the given protocol version.
+/// Returns the stream, which has to be kept open, and the id of the peer.
+async fn connect(
+    rng: &mut impl rand::Rng,
+    clock: &time::Clock,
+    chain: &data::Chain,
+    pm: &ActorHandler,
+    protocol_version: ProtocolVersion,
+) -> (stream::Stream, PeerId) {
+    let mut events = pm.events.from_now();
+    let stream = tcp::Stream::connect(&pm.peer_info(), tcp::Tier::T2, &SocketOptions::default())
+        .await
+        .unwrap();
+    let stream_id = stream.id();
+    let mut stream = stream::Stream::new(Some(Encoding::Proto), stream);
+    let peer_key = data::make_secret_key(rng);
+    let peer_id = PeerId::new(peer_key.public_key());
+    let nonce = Edge::create_fresh_nonce(clock);
+    stream
+        .write(&PeerMessage::Tier2Handshake(Handshake {
+            protocol_version,
+            oldest_supported_version: version::MIN_SUPPORTED_PROTOCOL_VERSION,
+            sender_peer_id: peer_id.clone(),
+            target_peer_id: pm.cfg.node_id(),
+            sender_listen_port: Some(24567),
+            sender_chain_info: chain.get_peer_chain_info(),
+            partial_edge_info: PartialEdgeInfo::new(&peer_id, &pm.cfg.node_id(), nonce, &peer_key),
+            owned_account: None,
+            encrypted_transport: false,
+        }))
+        .await;
+    events
+        .recv_until(|ev| match ev {
+            Event::PeerManager(PME::HandshakeCompleted(ev)) if ev.stream_id == stream_id => {
+                Some(())
+            }
+            _ => None,
+        })
+        .await;
+    (stream, peer_id)
+}
+
+// The transaction gossip messages are sent only to the peers which negotiated a protocol
+// version supporting them.
+#[tokio::test]
+#[cfg_attr(not(feature = "nightly"), ignore)]
+async fn peer_protocol_version() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let mut cfg = chain.make_config(rng);
+    cfg.tx_gossip = Some(tx_gossip::Config::default());
+    let pm = start_pm(clock.clock(), TestDB::new(), cfg, chain.clone()).await;
+
+    let old_version = ProtocolFeature::TxGossip.protocol_version() - 1;
+    let (_old_stream, old_peer) = connect(rng, &clock.clock(), &chain, &pm, old_version).await;
+    let (_new_stream, new_peer) =
+        connect(rng, &clock.clock(), &chain, &pm, version::PROTOCOL_VERSION).await;
+    let tiers = pm
+        .with_state(
+            move |s| async move { (s.tx_gossip_tier(&old_peer), s.tx_gossip_tier(&new_peer)) },
+        )
+        .await;
+    assert_eq!(tiers, (None, Some(tcp::Tier::T2)));
+}
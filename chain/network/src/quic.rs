@@ -0,0 +1,343 @@
+//! QUIC transport for TIER1 connections.
+//!
+//! Over TCP a large message (a `PartialEncodedStateWitness` or a chunk part) delays all the
+//! messages queued after it, including small and latency critical ones like `BlockApproval`.
+//! Over QUIC every `StreamClass` is sent on a separate unidirectional stream, so the classes
+//! don't block each other. Messages of the same class are still delivered in order.
+//!
+//! The QUIC endpoint listens on the UDP port with the same number as the TCP port of the node.
+//! TIER1 connections are established over QUIC if both peers enable it, and fall back to TCP
+//! otherwise. Repeated connections to the same peer use 0-RTT.
+//!
+//! The TLS certificate of the node is self-signed with the node key, so the connecting side
+//! accepts only the certificate with the key of the peer it wants to connect to. This binds
+//! the encrypted connection to the `PeerId` which is then verified by the `Handshake`, so that
+//! nobody in between can terminate TLS on both sides and relay the messages. The inbound side
+//! doesn't verify the certificate of the other side, the `Handshake` still authenticates it.
+//! Only ED25519 node keys are supported, nodes with other keys use TCP.
+use crate::network_protocol::{PeerInfo, PeerMessage, RoutedMessageBody};
+use crate::tcp;
+use anyhow::{Context as _, anyhow};
+use near_crypto::{ED25519PublicKey, PublicKey, SecretKey};
+use std::sync::Arc;
+
+/// ALPN protocol name of the TIER1 QUIC connections.
+const ALPN: &[u8] = b"near-tier1";
+/// Subject alternative name of the certificates. The server name used when connecting is
+/// derived from the `PeerId` instead, see `server_name`.
+const SERVER_NAME: &str = "near";
+/// DER encoding of the ED25519 algorithm identifier followed by the header of the public key
+/// bit string, i.e. the contents of the SubjectPublicKeyInfo without the key itself (RFC 8410).
+const ED25519_SPKI_PREFIX: [u8; 10] = [0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+/// Header of the PKCS#8 v2 document with an ED25519 key, followed by the seed (RFC 8410).
+const ED25519_PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x53, 0x02, 0x01, 0x01, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+/// Part of the PKCS#8 v2 document between the seed and the public key.
+const ED25519_PKCS8_PUBLIC_KEY_PREFIX: [u8; 5] = [0xa1, 0x23, 0x03, 0x21, 0x00];
+/// Timeout for establishing a QUIC connection. Same as for TCP (see `tcp::Stream::connect`).
+const CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Class of a message, which determines the QUIC stream it is sent on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum::IntoStaticStr)]
+#[repr(u8)]
+pub(crate) enum StreamClass {
+    /// Handshakes and everything which doesn't fit the other classes.
+    Control = 0,
+    /// Small, latency critical consensus messages.
+    Consensus = 1,
+    /// Large messages: state witness parts and chunk parts.
+    Bulk = 2,
+}
+
+impl StreamClass {
+    pub const ALL: [Self; 3] = [Self::Control, Self::Consensus, Self::Bulk];
+
+    pub fn of(msg: &PeerMessage) -> Self {
+        match msg {
+            PeerMessage::OptimisticBlock(..) => Self::Consensus,
+            PeerMessage::Routed(msg) => match &msg.body {
+                RoutedMessageBody::BlockApproval(..)
+                | RoutedMessageBody::VersionedChunkEndorsement(..)
+                | RoutedMessageBody::ChunkContractAccesses(_)
+                | RoutedMessageBody::ContractCodeRequest(_) => Self::Consensus,
+                RoutedMessageBody::PartialEncodedStateWitness(..)
+                | RoutedMessageBody::PartialEncodedStateWitnessForward(..)
+                | RoutedMessageBody::VersionedPartialEncodedChunk(..)
+                | RoutedMessageBody::ContractCodeResponse(_) => Self::Bulk,
+                _ => Self::Control,
+            },
+            _ => Self::Control,
+        }
+    }
+
+    pub fn from_u8(class: u8) -> Option<Self> {
+        match class {
+            0 => Some(Self::Control),
+            1 => Some(Self::Consensus),
+            2 => Some(Self::Bulk),
+            _ => None,
+        }
+    }
+}
+
+/// QUIC connection, counterpart of `tcp::Stream`.
+pub(crate) struct Stream {
+    pub(crate) conn: quinn::Connection,
+    pub(crate) type_: tcp::StreamType,
+    pub(crate) local_addr: std::net::SocketAddr,
+    pub(crate) peer_addr: std::net::SocketAddr,
+}
+
+impl Stream {
+    // TEST-ONLY used in reporting test events.
+    pub(crate) fn id(&self) -> tcp::StreamId {
+        tcp::StreamId::new(&self.type_, self.local_addr, self.peer_addr)
+    }
+}
+
+/// QUIC endpoint, accepting and establishing TIER1 connections.
+#[derive(Clone)]
+pub(crate) struct Endpoint {
+    endpoint: quinn::Endpoint,
+    /// Session tickets, shared by the client configs of all the connections to allow 0-RTT.
+    resumption: rustls::client::Resumption,
+}
+
+impl Endpoint {
+    /// Binds the endpoint to the UDP port with the same number as the TCP listener.
+    pub fn bind(addr: &tcp::ListenerAddr, node_key: &SecretKey) -> anyhow::Result<Self> {
+        let (cert, key) = node_certificate(node_key)?;
+        let mut server_crypto = rustls::ServerConfig::builder_with_provider(crypto_provider())
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .with_no_client_auth()
+            .with_single_cert(vec![cert], key.into())?;
+        server_crypto.alpn_protocols = vec![ALPN.to_vec()];
+        // Required by QUIC to accept 0-RTT data.
+        server_crypto.max_early_data_size = u32::MAX;
+        let server_config = quinn::ServerConfig::with_crypto(Arc::new(
+            quinn::crypto::rustls::QuicServerConfig::try_from(server_crypto)?,
+        ));
+        let endpoint =
+            quinn::Endpoint::server(server_config, **addr).context("quinn::Endpoint::server()")?;
+        Ok(Self { endpoint, resumption: rustls::client::Resumption::default() })
+    }
+
+    /// Client config accepting only the certificate with the key of the peer.
+    fn client_config(&self, public_key: &ED25519PublicKey) -> anyhow::Result<quinn::ClientConfig> {
+        let mut client_crypto = rustls::ClientConfig::builder_with_provider(crypto_provider())
+            .with_protocol_versions(&[&rustls::version::TLS13])?
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(PeerVerifier(public_key.clone())))
+            .with_no_client_auth();
+        client_crypto.alpn_protocols = vec![ALPN.to_vec()];
+        client_crypto.enable_early_data = true;
+        client_crypto.resumption = self.resumption.clone();
+        Ok(quinn::ClientConfig::new(Arc::new(quinn::crypto::rustls::QuicClientConfig::try_from(
+            client_crypto,
+        )?)))
+    }
+
+    /// Establishes a TIER1 connection to the peer, using 0-RTT if possible.
+    pub async fn connect(&self, peer_info: &PeerInfo) -> anyhow::Result<Stream> {
+        let addr = peer_info
+            .addr
+            .ok_or_else(|| anyhow!("Trying to connect to peer with no public address"))?;
+        let PublicKey::ED25519(public_key) = peer_info.id.public_key() else {
+            anyhow::bail!("QUIC is supported only for peers with an ED25519 key");
+        };
+        let connecting = self
+            .endpoint
+            .connect_with(self.client_config(public_key)?, addr, &server_name(public_key))
+            .context("quinn::Endpoint::connect_with()")?;
+        let conn = match connecting.into_0rtt() {
+            // The handshake is completed in the background. If the peer rejects 0-RTT,
+            // quinn retransmits the data once the handshake is completed.
+            // 0-RTT is only possible with a session ticket from a previous connection,
+            // whose certificate has been verified.
+            Ok((conn, _accepted)) => conn,
+            Err(connecting) => tokio::time::timeout(CONNECT_TIMEOUT, connecting)
+                .await?
+                .context("quinn::Connecting")?,
+        };
+        Ok(Stream {
+            local_addr: self.endpoint.local_addr()?,
+            peer_addr: conn.remote_address(),
+            conn,
+            type_: tcp::StreamType::Outbound { peer_id: peer_info.id.clone(), tier: tcp::Tier::T1 },
+        })
+    }
+
+    /// Waits for the next inbound connection attempt.
+    /// Returns None if the endpoint has been closed.
+    pub async fn accept(&self) -> Option<Incoming> {
+        Some(Incoming {
+            incoming: self.endpoint.accept().await?,
+            local_addr: self.endpoint.local_addr().ok()?,
+        })
+    }
+}
+
+/// Inbound connection attempt.
+pub(crate) struct Incoming {
+    incoming: quinn::Incoming,
+    local_addr: std::net::SocketAddr,
+}
+
+impl Incoming {
+    pub fn remote_address(&self) -> std::net::SocketAddr {
+        self.incoming.remote_address()
+    }
+
+    /// Completes the QUIC handshake.
+    pub async fn establish(self) -> anyhow::Result<Stream> {
+        // 0-RTT data can be replayed, so we wait for the handshake to complete before
+        // processing any data. An attacker replaying the data won't complete the handshake.
+        let conn = tokio::time::timeout(CONNECT_TIMEOUT, self.incoming)
+            .await?
+            .context("quinn::Incoming")?;
+        Ok(Stream {
+            local_addr: self.local_addr,
+            peer_addr: conn.remote_address(),
+            conn,
+            type_: tcp::StreamType::Inbound,
+        })
+    }
+}
+
+fn crypto_provider() -> Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+/// Server name to connect to the peer with. Session tickets are cached by the server name,
+/// so it has to be unique per peer: a ticket is only used with the peer whose certificate
+/// has been verified when the ticket was issued.
+fn server_name(public_key: &ED25519PublicKey) -> String {
+    let key: String = public_key.0.iter().map(|b| format!("{b:02x}")).collect();
+    // DNS labels are limited to 63 characters.
+    format!("{}.{}", &key[..32], &key[32..])
+}
+
+/// Self-signed certificate with the node key and the PKCS#8 encoding of the key.
+fn node_certificate(
+    node_key: &SecretKey,
+) -> anyhow::Result<(
+    rustls::pki_types::CertificateDer<'static>,
+    rustls::pki_types::PrivatePkcs8KeyDer<'static>,
+)> {
+    let SecretKey::ED25519(node_key) = node_key else {
+        anyhow::bail!("QUIC is supported only with an ED25519 node key");
+    };
+    let (seed, public_key) = node_key.0.split_at(32);
+    let key = rustls::pki_types::PrivatePkcs8KeyDer::from(
+        [&ED25519_PKCS8_PREFIX[..], seed, &ED25519_PKCS8_PUBLIC_KEY_PREFIX[..], public_key]
+            .concat(),
+    );
+    let key_pair = rcgen::KeyPair::from_pkcs8_der_and_sign_algo(&key, &rcgen::PKCS_ED25519)
+        .context("KeyPair::from_pkcs8_der_and_sign_algo()")?;
+    let cert = rcgen::CertificateParams::new(vec![SERVER_NAME.to_string()])?
+        .self_signed(&key_pair)
+        .context("self_signed()")?;
+    Ok((cert.der().clone(), key))
+}
+
+/// Reads a DER encoded value. Returns its tag, its contents and the remaining input.
+fn der_read(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, der) = der.split_first()?;
+    let (&len, der) = der.split_first()?;
+    let (len, der) = if len < 0x80 {
+        (len as usize, der)
+    } else {
+        let len_len = (len & 0x7f) as usize;
+        if len_len == 0 || len_len > 4 || der.len() < len_len {
+            return None;
+        }
+        let (len, der) = der.split_at(len_len);
+        (len.iter().fold(0, |len, &b| (len << 8) | b as usize), der)
+    };
+    if der.len() < len {
+        return None;
+    }
+    let (contents, rest) = der.split_at(len);
+    Some((tag, contents, rest))
+}
+
+/// Returns the contents of the SubjectPublicKeyInfo of the DER encoded X.509 certificate.
+fn subject_public_key_info(cert: &[u8]) -> Option<&[u8]> {
+    const SEQUENCE: u8 = 0x30;
+    const EXPLICIT_VERSION: u8 = 0xa0;
+    let (SEQUENCE, cert, _) = der_read(cert)? else {
+        return None;
+    };
+    let (SEQUENCE, mut tbs_cert, _) = der_read(cert)? else {
+        return None;
+    };
+    if let (EXPLICIT_VERSION, _, rest) = der_read(tbs_cert)? {
+        tbs_cert = rest;
+    }
+    // Skip the serial number, the signature algorithm, the issuer, the validity and the subject.
+    for _ in 0..5 {
+        tbs_cert = der_read(tbs_cert)?.2;
+    }
+    let (SEQUENCE, spki, _) = der_read(tbs_cert)? else {
+        return None;
+    };
+    Some(spki)
+}
+
+/// Accepts only the certificate with the key of the expected peer, see `node_certificate`.
+/// The handshake signature proves that the server holds the key.
+#[derive(Debug)]
+struct PeerVerifier(ED25519PublicKey);
+
+impl rustls::client::danger::ServerCertVerifier for PeerVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        let spki = subject_public_key_info(end_entity)
+            .ok_or(rustls::Error::InvalidCertificate(rustls::CertificateError::BadEncoding))?;
+        if spki != [&ED25519_SPKI_PREFIX[..], &self.0.0[..]].concat() {
+            return Err(rustls::Error::InvalidCertificate(
+                rustls::CertificateError::ApplicationVerificationFailure,
+            ));
+        }
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &crypto_provider().signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &rustls::pki_types::CertificateDer<'_>,
+        dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &crypto_provider().signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        vec![rustls::SignatureScheme::ED25519]
+    }
+}
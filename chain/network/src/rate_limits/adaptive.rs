@@ -0,0 +1,191 @@
+//! Adaptive scaling of the per-peer rate limits.
+//!
+//! The limits configured in `messages_limits::Config` are scaled by a factor, which depends on:
+//! * load of the node: when the client falls behind processing the messages (or the chunk
+//!   validation backlog grows), the limits are tightened down to `min_factor`. When the node
+//!   is idle, they are relaxed up to `max_factor`.
+//! * reputation of the peer: peers with a good reputation get higher limits than the
+//!   peers with a bad one.
+
+use crate::peer_manager::peer_store::reputation;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Range of a load signal within which the limits are scaled.
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Thresholds {
+    /// At or below this value the signal doesn't tighten the limits.
+    pub low: usize,
+    /// At or above this value the signal tightens the limits to `min_factor`.
+    pub high: usize,
+}
+
+impl Thresholds {
+    /// Returns the pressure in [0, 1] that the given value of the signal exerts.
+    fn pressure(&self, value: usize) -> f64 {
+        if value <= self.low {
+            return 0.;
+        }
+        if value >= self.high {
+            return 1.;
+        }
+        (value - self.low) as f64 / (self.high - self.low) as f64
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub enabled: bool,
+    /// Number of received messages waiting to be processed by the client.
+    pub client_queue: Thresholds,
+    /// Number of chunk state witnesses waiting for validation.
+    pub chunk_validation_backlog: Thresholds,
+    /// Factor applied to the limits under the maximal load.
+    pub min_factor: f64,
+    /// Factor applied to the limits when the node is idle.
+    pub max_factor: f64,
+    /// How much the reputation of the peer affects its limits. A peer with the maximal
+    /// (minimal) reputation has its limits multiplied by `1 + reputation_weight`
+    /// (`1 - reputation_weight`).
+    pub reputation_weight: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            client_queue: Thresholds { low: 1000, high: 10000 },
+            chunk_validation_backlog: Thresholds { low: 10, high: 100 },
+            min_factor: 0.25,
+            max_factor: 2.,
+            reputation_weight: 0.5,
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if !(0. < self.min_factor && self.min_factor <= self.max_factor) {
+            anyhow::bail!("expected 0 < min_factor <= max_factor");
+        }
+        if !(0. ..=1.).contains(&self.reputation_weight) {
+            anyhow::bail!("reputation_weight has to be in [0, 1]");
+        }
+        for (name, thresholds) in [
+            ("client_queue", &self.client_queue),
+            ("chunk_validation_backlog", &self.chunk_validation_backlog),
+        ] {
+            if thresholds.low >= thresholds.high {
+                anyhow::bail!("{name}: expected low < high");
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the factor by which the limits of a peer with the given reputation should be scaled.
+    pub(crate) fn factor(&self, load: &Load, reputation_score: f64) -> f64 {
+        if !self.enabled {
+            return 1.;
+        }
+        let pressure = f64::max(
+            self.client_queue.pressure(load.client_queue()),
+            self.chunk_validation_backlog.pressure(load.chunk_validation_backlog()),
+        );
+        let load_factor = self.max_factor - pressure * (self.max_factor - self.min_factor);
+        let reputation_factor =
+            1. + self.reputation_weight * reputation_score / reputation::MAX_SCORE;
+        (load_factor * reputation_factor).clamp(self.min_factor, self.max_factor)
+    }
+}
+
+/// Load signals of the node.
+#[derive(Default)]
+pub(crate) struct Load {
+    client_queue: AtomicUsize,
+    chunk_validation_backlog: AtomicUsize,
+}
+
+impl Load {
+    pub fn client_queue(&self) -> usize {
+        self.client_queue.load(Ordering::Relaxed)
+    }
+
+    pub fn chunk_validation_backlog(&self) -> usize {
+        self.chunk_validation_backlog.load(Ordering::Relaxed)
+    }
+
+    pub fn set_chunk_validation_backlog(&self, backlog: usize) {
+        self.chunk_validation_backlog.store(backlog, Ordering::Relaxed);
+    }
+
+    /// Counts a message as waiting in the client queue until the returned guard is dropped.
+    pub fn enqueue(self: &Arc<Self>) -> QueueGuard {
+        self.client_queue.fetch_add(1, Ordering::Relaxed);
+        QueueGuard(self.clone())
+    }
+}
+
+pub(crate) struct QueueGuard(Arc<Load>);
+
+impl Drop for QueueGuard {
+    fn drop(&mut self) {
+        self.0.client_queue.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate() {
+        assert!(Config::default().validate().is_ok());
+        assert!(Config { min_factor: 0., ..Config::default() }.validate().is_err());
+        assert!(Config { min_factor: 3., ..Config::default() }.validate().is_err());
+        assert!(Config { reputation_weight: 1.5, ..Config::default() }.validate().is_err());
+        let client_queue = Thresholds { low: 10, high: 10 };
+        assert!(Config { client_queue, ..Config::default() }.validate().is_err());
+    }
+
+    #[test]
+    fn disabled() {
+        let cfg = Config::default();
+        let load = Arc::new(Load::default());
+        load.set_chunk_validation_backlog(1000);
+        assert_eq!(cfg.factor(&load, -100.), 1.);
+    }
+
+    #[test]
+    fn load() {
+        let cfg = Config { enabled: true, reputation_weight: 0., ..Config::default() };
+        let load = Arc::new(Load::default());
+        assert_eq!(cfg.factor(&load, 0.), cfg.max_factor);
+
+        // Halfway between the thresholds.
+        load.set_chunk_validation_backlog(55);
+        assert_eq!(cfg.factor(&load, 0.), (cfg.max_factor + cfg.min_factor) / 2.);
+
+        // The highest pressure wins.
+        let guards: Vec<_> = (0..cfg.client_queue.high).map(|_| load.enqueue()).collect();
+        assert_eq!(cfg.factor(&load, 0.), cfg.min_factor);
+        drop(guards);
+        assert_eq!(load.client_queue(), 0);
+        assert_eq!(cfg.factor(&load, 0.), (cfg.max_factor + cfg.min_factor) / 2.);
+    }
+
+    #[test]
+    fn reputation() {
+        let cfg = Config { enabled: true, ..Config::default() };
+        let load = Arc::new(Load::default());
+        load.set_chunk_validation_backlog(55);
+        let neutral = cfg.factor(&load, 0.);
+        assert!(cfg.factor(&load, 50.) > neutral);
+        assert!(cfg.factor(&load, -50.) < neutral);
+        // The factor stays within the bounds regardless of the reputation.
+        load.set_chunk_validation_backlog(0);
+        assert_eq!(cfg.factor(&load, 100.), cfg.max_factor);
+        load.set_chunk_validation_backlog(100);
+        assert_eq!(cfg.factor(&load, -100.), cfg.min_factor);
+    }
+}
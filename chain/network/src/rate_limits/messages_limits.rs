@@ -9,9 +9,18 @@ use std::collections::HashMap;
 
 /// Object responsible to manage the rate limits of all network messages
 /// for a single connection/peer.
-#[derive(Default)]
 pub struct RateLimits {
     buckets: EnumMap<RateLimitedPeerMessageKey, Option<TokenBucket>>,
+    /// Limits from the config, before scaling by `factor`.
+    configs: EnumMap<RateLimitedPeerMessageKey, Option<SingleMessageConfig>>,
+    /// Factor by which the configured limits are currently scaled, see `adaptive`.
+    factor: f64,
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self { buckets: Default::default(), configs: Default::default(), factor: 1. }
+    }
 }
 
 impl RateLimits {
@@ -19,6 +28,7 @@ impl RateLimits {
     /// See also [TokenBucket::new].
     pub fn from_config(config: &Config, start_time: Instant) -> Self {
         let mut buckets = enum_map! { _ => None };
+        let mut configs = enum_map! { _ => None };
         // Configuration is assumed to be correct. Any failure to build a bucket is ignored.
         for (key, message_config) in &config.rate_limits {
             let initial_size = message_config.initial_size.unwrap_or(message_config.maximum_size);
@@ -28,13 +38,39 @@ impl RateLimits {
                 message_config.refill_rate,
                 start_time,
             ) {
-                Ok(bucket) => buckets[*key] = Some(bucket),
+                Ok(bucket) => {
+                    buckets[*key] = Some(bucket);
+                    configs[*key] = Some(message_config.clone());
+                }
                 Err(err) => {
                     tracing::warn!(target: "network", "ignoring rate limit for {key} due to an error ({err})")
                 }
             }
         }
-        Self { buckets }
+        Self { buckets, configs, factor: 1. }
+    }
+
+    /// Scales the configured limits by `factor`.
+    /// Tokens already in the buckets are kept, up to the new maximum size.
+    pub fn set_factor(&mut self, factor: f64, now: Instant) {
+        if factor == self.factor {
+            return;
+        }
+        self.factor = factor;
+        for (key, bucket) in &mut self.buckets {
+            let (Some(bucket), Some(config)) = (bucket, &self.configs[key]) else {
+                continue;
+            };
+            // Don't scale a non-zero limit down to zero, it would block the message entirely.
+            let maximum_size = match config.maximum_size {
+                0 => 0,
+                size => ((size as f64 * factor).round() as u32).max(1),
+            };
+            let refill_rate = (config.refill_rate as f64 * factor) as f32;
+            if let Err(err) = bucket.set_limits(maximum_size, refill_rate, now) {
+                tracing::warn!(target: "network", "failed to scale rate limit for {key} ({err})");
+            }
+        }
     }
 
     /// Checks if the given message is under the rate limits.
@@ -57,8 +93,7 @@ impl RateLimits {
 }
 
 /// Rate limit configuration for a single network message.
-#[derive(Clone, serde::Serialize, serde::Deserialize, Debug)]
-#[cfg_attr(test, derive(PartialEq))]
+#[derive(Clone, serde::Serialize, serde::Deserialize, Debug, PartialEq)]
 pub struct SingleMessageConfig {
     pub maximum_size: u32,
     pub refill_rate: f32,
@@ -73,14 +108,14 @@ impl SingleMessageConfig {
 }
 
 /// Network messages rate limits configuration.
-#[derive(Default, Clone)]
+#[derive(Default, Clone, Debug, PartialEq)]
 pub struct Config {
     pub rate_limits: HashMap<RateLimitedPeerMessageKey, SingleMessageConfig>,
 }
 
 /// Struct to manage user defined overrides for [Config]. The key difference with the base struct
 /// is that in this values can be set to `None` to disable preset rate limits.
-#[derive(serde::Serialize, serde::Deserialize, Default, Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone, Debug, PartialEq)]
 pub struct OverrideConfig {
     pub rate_limits: HashMap<RateLimitedPeerMessageKey, Option<SingleMessageConfig>>,
 }
@@ -179,6 +214,9 @@ pub enum RateLimitedPeerMessageKey {
     PartialEncodedContractDeploys,
     EpochSyncRequest,
     OptimisticBlock,
+    TxGossipInventory,
+    TxGossipRequest,
+    TxGossipResponse,
 }
 
 /// Given a `PeerMessage` returns a tuple containing the `RateLimitedPeerMessageKey`
@@ -237,6 +275,11 @@ fn get_key_and_token_cost(message: &PeerMessage) -> Option<(RateLimitedPeerMessa
             RoutedMessageBody::_UnusedEpochSyncResponse(_) => None,
             RoutedMessageBody::StatePartRequest(_) => None, // TODO
             RoutedMessageBody::StateHeaderRequest(_) => None, // TODO
+            RoutedMessageBody::TxGossipInventory(_) => Some((TxGossipInventory, 1)),
+            RoutedMessageBody::TxGossipRequest(_) => Some((TxGossipRequest, 1)),
+            RoutedMessageBody::TxGossipResponse(_) => Some((TxGossipResponse, 1)),
+            RoutedMessageBody::HolePunchRequest(_) => None, // TODO
+            RoutedMessageBody::HolePunchResponse(_) => None, // TODO
             RoutedMessageBody::Ping(_)
             | RoutedMessageBody::Pong(_)
             | RoutedMessageBody::_UnusedChunkEndorsement
@@ -480,4 +523,33 @@ mod tests {
         clock.advance(Duration::seconds(30));
         assert!(rate_limits.is_allowed(&PeerMessage::EpochSyncRequest, clock.now()));
     }
+
+    #[test]
+    fn set_factor() {
+        use RateLimitedPeerMessageKey::*;
+        let mut config = Config::default();
+        config.rate_limits.insert(Block, SingleMessageConfig::new(4, 1.0, Some(0)));
+        config.rate_limits.insert(BlockApproval, SingleMessageConfig::new(1, 1.0, Some(0)));
+
+        let clock = FakeClock::default();
+        let mut limits = RateLimits::from_config(&config, clock.now());
+        limits.set_factor(0.5, clock.now());
+
+        // Refill rate is halved: 1 token after 2 seconds.
+        clock.advance(Duration::seconds(1));
+        assert!(!limits.buckets[Block].as_mut().unwrap().acquire(1, clock.now()));
+        clock.advance(Duration::seconds(1));
+        assert!(limits.buckets[Block].as_mut().unwrap().acquire(1, clock.now()));
+
+        // Maximum size is halved, but not below 1.
+        clock.advance(Duration::seconds(100));
+        assert!(!limits.buckets[Block].as_mut().unwrap().acquire(3, clock.now()));
+        assert!(limits.buckets[Block].as_mut().unwrap().acquire(2, clock.now()));
+        assert!(limits.buckets[BlockApproval].as_mut().unwrap().acquire(1, clock.now()));
+
+        // Scaling up is relative to the configured limits.
+        limits.set_factor(2., clock.now());
+        clock.advance(Duration::seconds(100));
+        assert!(limits.buckets[Block].as_mut().unwrap().acquire(8, clock.now()));
+    }
 }
@@ -1,2 +1,3 @@
+pub mod adaptive;
 pub mod messages_limits;
 pub mod token_bucket;
@@ -80,6 +80,27 @@ impl TokenBucket {
         }
     }
 
+    /// Changes the maximum size and the refill rate of the bucket.
+    /// Tokens accumulated until `now` are refilled with the old rate, and
+    /// the tokens exceeding the new maximum size are discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the refill rate is invalid, in which case the bucket is not changed.
+    pub fn set_limits(
+        &mut self,
+        maximum_size: u32,
+        refill_rate: f32,
+        now: Instant,
+    ) -> Result<(), TokenBucketError> {
+        TokenBucket::validate_refill_rate(refill_rate)?;
+        self.refill(now);
+        self.maximum_size = maximum_size;
+        self.refill_rate = refill_rate;
+        self.size = self.size.min(to_tokens_with_parts(maximum_size));
+        Ok(())
+    }
+
     /// Refills the bucket with the right number of tokens according to
     /// the `refill_rate` and the new current time `now`.
     ///
@@ -285,4 +306,23 @@ mod tests {
         }
         assert_eq!(tokens_added, 1);
     }
+
+    #[test]
+    fn set_limits() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(10, 10, 1.0, now).expect("bucket should be well formed");
+
+        // Tokens above the new maximum are discarded.
+        bucket.set_limits(4, 2.0, now).unwrap();
+        assert_eq!(bucket.size, to_tokens_with_parts(4));
+        assert!(bucket.acquire(4, now));
+
+        // The new refill rate applies from now on.
+        assert!(bucket.acquire(2, now + Duration::seconds(1)));
+        assert!(!bucket.acquire(1, now + Duration::seconds(1)));
+
+        // Invalid limits leave the bucket unchanged.
+        assert!(bucket.set_limits(4, -1.0, now + Duration::seconds(1)).is_err());
+        assert_eq!(bucket.refill_rate, 2.0);
+    }
 }
@@ -3,6 +3,7 @@ use crate::network_protocol::{
     Encoding, Handshake, HandshakeFailureReason, PartialEdgeInfo, PeerChainInfoV2, PeerIdOrHash,
     PeerMessage, Ping, Pong, RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate,
 };
+use crate::recorder;
 use crate::tcp;
 use crate::types::{
     Edge, PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg, PeerInfo,
@@ -221,6 +222,7 @@ fn new_handshake(
         },
         partial_edge_info: PartialEdgeInfo::new(my_peer_id, target_peer_id, nonce, secret_key),
         owned_account: None,
+        encrypted_transport: false,
     })
 }
 
@@ -238,7 +240,7 @@ impl Connection {
         tracked_shards: Vec<ShardId>,
         recv_timeout: Option<Duration>,
     ) -> Result<Self, ConnectError> {
-        let secret_key = SecretKey::from_random(KeyType::ED25519);
+        let secret_key = SecretKey::from_random(KeyType::ED25519).unwrap();
         let my_peer_id = PeerId::new(secret_key.public_key());
 
         let start = Instant::now();
@@ -435,6 +437,27 @@ impl Connection {
         self.stream.write_message(&PeerMessage::Routed(Box::new(msg))).await
     }
 
+    /// Sends a message from a traffic recording. Messages which only make sense on the
+    /// connection they were recorded on (handshakes, edge nonce updates, disconnects)
+    /// are skipped. Returns whether the message has been sent.
+    pub async fn send_recorded_message(&mut self, record: &recorder::Record) -> io::Result<bool> {
+        let msg = record.message().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, "failed to parse the recorded message")
+        })?;
+        match msg {
+            PeerMessage::Tier1Handshake(_)
+            | PeerMessage::Tier2Handshake(_)
+            | PeerMessage::Tier3Handshake(_)
+            | PeerMessage::HandshakeFailure(..)
+            | PeerMessage::LastEdge(_)
+            | PeerMessage::RequestUpdateNonce(_)
+            | PeerMessage::Disconnect(_) => return Ok(false),
+            _ => {}
+        }
+        self.stream.write_message(&msg).await?;
+        Ok(true)
+    }
+
     fn target_is_for_me(&mut self, target: &PeerIdOrHash) -> bool {
         match target {
             PeerIdOrHash::PeerId(peer_id) => peer_id == &self.my_peer_id,
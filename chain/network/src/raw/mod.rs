@@ -1,6 +1,8 @@
 mod connection;
+mod replay;
 
 pub use connection::{ConnectError, Connection, DirectMessage, Listener, Message, RoutedMessage};
+pub use replay::{ReplayOptions, ReplayStats, replay};
 
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,64 @@
+//! Replays the traffic recorded by `recorder` against a node.
+use super::Connection;
+use crate::recorder::{Direction, Record};
+use near_async::time;
+use near_primitives::network::PeerId;
+use std::io;
+
+/// Which of the recorded messages to replay and how.
+#[derive(Clone, Debug, Default)]
+pub struct ReplayOptions {
+    /// Replay only the messages received from this peer.
+    /// By default messages received from all the peers are replayed.
+    pub from_peer: Option<PeerId>,
+    /// Preserve the intervals between the messages, shortened by this factor.
+    /// If None, messages are sent as fast as possible.
+    pub speedup: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReplayStats {
+    /// Number of messages sent.
+    pub sent: usize,
+    /// Number of inbound messages which were filtered out or couldn't be replayed.
+    pub skipped: usize,
+}
+
+/// Sends the messages that the recording node has received to the peer on the other side
+/// of `conn`, in the recorded order. The intervals between the messages are awaited on
+/// `clock`, so with a fake clock the replay is fully deterministic.
+pub async fn replay(
+    clock: &time::Clock,
+    conn: &mut Connection,
+    records: impl IntoIterator<Item = io::Result<Record>>,
+    opts: &ReplayOptions,
+) -> io::Result<ReplayStats> {
+    let mut stats = ReplayStats::default();
+    let mut prev: Option<time::Utc> = None;
+    for record in records {
+        let record = record?;
+        if record.direction != Direction::Inbound {
+            continue;
+        }
+        if opts.from_peer.is_some() && record.peer_id != opts.from_peer {
+            stats.skipped += 1;
+            continue;
+        }
+        if let Some(speedup) = opts.speedup {
+            let now = record.timestamp();
+            if let Some(prev) = prev {
+                let delay = (now - prev) / speedup;
+                if delay > time::Duration::ZERO {
+                    clock.sleep(delay).await;
+                }
+            }
+            prev = Some(now);
+        }
+        if conn.send_recorded_message(&record).await? {
+            stats.sent += 1;
+        } else {
+            stats.skipped += 1;
+        }
+    }
+    Ok(stats)
+}
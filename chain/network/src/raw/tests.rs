@@ -1,5 +1,9 @@
+use crate::network_protocol::PeerMessage;
 use crate::network_protocol::testonly as data;
+use crate::peer_manager::peer_manager_actor::Event as PME;
+use crate::peer_manager::testonly::Event;
 use crate::raw;
+use crate::recorder;
 use crate::tcp;
 use crate::testonly;
 use crate::types::PeerInfo;
@@ -161,7 +165,7 @@ async fn test_listener() {
     let genesis_id = chain.genesis_id.clone();
 
     let addr = tcp::ListenerAddr::reserve_for_test();
-    let secret_key = SecretKey::from_random(KeyType::ED25519);
+    let secret_key = SecretKey::from_random(KeyType::ED25519).unwrap();
     let peer_id = PeerId::new(secret_key.public_key());
     cfg.peer_store.boot_nodes.push(PeerInfo::new(peer_id, *addr));
     cfg.outbound_disabled = false;
@@ -191,3 +195,104 @@ async fn test_listener() {
     // at least the handshake logic has gotten exercised somewhat
     let _ = conn.recv().await.unwrap();
 }
+
+#[tokio::test]
+async fn test_raw_conn_replay() {
+    init_test_logger();
+    let mut rng = testonly::make_rng(33955575545);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+    let genesis_id = chain.genesis_id.clone();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("traffic");
+
+    let connect = |cfg: &crate::config::NetworkConfig| {
+        let clock = clock.clock();
+        let addr = **cfg.node_addr.as_ref().unwrap();
+        let peer_id = cfg.node_id();
+        let genesis_id = genesis_id.clone();
+        async move {
+            raw::Connection::connect(
+                &clock,
+                addr,
+                peer_id,
+                None,
+                &genesis_id.chain_id,
+                genesis_id.hash,
+                0,
+                vec![ShardId::new(0)],
+                Some(time::Duration::SECOND),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    tracing::info!(target:"test", "Record the traffic.");
+    let mut cfg = chain.make_config(rng);
+    cfg.traffic_recorder = Some(recorder::Config { path: path.clone(), max_bytes: u64::MAX });
+    let _pm = crate::peer_manager::testonly::start(
+        clock.clock(),
+        near_store::db::TestDB::new(),
+        cfg.clone(),
+        chain.clone(),
+    )
+    .await;
+    let mut conn = connect(&cfg).await;
+    let hashes: Vec<CryptoHash> = (0..5).map(|_| data::make_hash(rng)).collect();
+    for hash in &hashes {
+        conn.send_message(raw::DirectMessage::BlockRequest(*hash)).await.unwrap();
+    }
+
+    tracing::info!(target:"test", "Wait for the messages to be recorded.");
+    let records = loop {
+        // The last record may be only partially written yet.
+        let records: Vec<_> =
+            recorder::Reader::open(&path).unwrap().map_while(Result::ok).collect();
+        let recorded = records
+            .iter()
+            .filter(|r| {
+                r.direction == recorder::Direction::Inbound
+                    && r.message_type() == Some("BlockRequest")
+            })
+            .count();
+        if recorded == hashes.len() {
+            break records;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    };
+
+    tracing::info!(target:"test", "Replay the traffic against another node.");
+    let cfg = chain.make_config(rng);
+    let pm = crate::peer_manager::testonly::start(
+        clock.clock(),
+        near_store::db::TestDB::new(),
+        cfg.clone(),
+        chain.clone(),
+    )
+    .await;
+    let mut conn = connect(&cfg).await;
+    let mut events = pm.events.from_now();
+    let stats = raw::replay(
+        &clock.clock(),
+        &mut conn,
+        records.into_iter().map(Ok),
+        &raw::ReplayOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(hashes.len(), stats.sent);
+    for want in &hashes {
+        let got = events
+            .recv_until(|ev| match ev {
+                Event::PeerManager(PME::MessageProcessed(
+                    tcp::Tier::T2,
+                    PeerMessage::BlockRequest(hash),
+                )) => Some(hash),
+                _ => None,
+            })
+            .await;
+        assert_eq!(want, &got);
+    }
+}
@@ -0,0 +1,157 @@
+//! Recording of the network traffic, for debugging network issues offline.
+//!
+//! When enabled, every `PeerMessage` sent or received by the node is appended to a file,
+//! together with the time, direction and the id of the peer. Messages are stored in the
+//! encoding in which they were sent over the wire, so recording doesn't add any serialization
+//! cost to the `PeerActor`. The file is written by a dedicated thread; if it cannot keep up,
+//! messages are dropped (see `near_network_traffic_recorder_dropped_total` metric) rather than
+//! slowing down the node.
+//!
+//! The recording can be replayed against a node with `raw::replay`.
+use crate::network_protocol::{Encoding, PeerMessage};
+use crate::stats::metrics;
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_async::time;
+use near_primitives::network::PeerId;
+use std::io::{self, Write as _};
+use std::path::PathBuf;
+
+/// Identifies the file format, followed by the format version.
+const MAGIC: &[u8; 8] = b"NEARNREC";
+const VERSION: u32 = 1;
+/// Maximal number of records waiting to be written to the file.
+const QUEUE_SIZE: usize = 10_000;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// File to write the recording to. It is truncated when the node starts.
+    pub path: PathBuf,
+    /// Recording stops once the file reaches this size.
+    pub max_bytes: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Inbound,
+    Outbound,
+}
+
+/// A single recorded message.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Time at which the message was sent or received, in nanoseconds since the unix epoch.
+    pub timestamp_nanos: i128,
+    pub direction: Direction,
+    /// Peer on the other side of the connection.
+    /// None for the messages received before the handshake on inbound connections.
+    pub peer_id: Option<PeerId>,
+    pub encoding: Encoding,
+    /// Serialized `PeerMessage`.
+    pub message: Vec<u8>,
+}
+
+impl Record {
+    pub fn timestamp(&self) -> time::Utc {
+        time::Utc::from_unix_timestamp_nanos(self.timestamp_nanos).unwrap()
+    }
+
+    pub(crate) fn message(&self) -> Option<PeerMessage> {
+        PeerMessage::deserialize(self.encoding, &self.message).ok()
+    }
+
+    /// Type of the recorded message, or None if the message cannot be parsed.
+    pub fn message_type(&self) -> Option<&'static str> {
+        Some(self.message()?.msg_variant())
+    }
+}
+
+/// Appends the messages to the recording file.
+pub(crate) struct Recorder {
+    sender: crossbeam_channel::Sender<Record>,
+}
+
+impl Recorder {
+    pub fn new(cfg: &Config) -> io::Result<Self> {
+        let mut file = io::BufWriter::new(std::fs::File::create(&cfg.path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&VERSION.to_le_bytes())?;
+        let (sender, receiver) = crossbeam_channel::bounded::<Record>(QUEUE_SIZE);
+        let cfg = cfg.clone();
+        std::thread::spawn(move || {
+            let mut written = (MAGIC.len() + 4) as u64;
+            let mut write = |record: Record| -> io::Result<()> {
+                let bytes = borsh::to_vec(&record)?;
+                if written + bytes.len() as u64 > cfg.max_bytes {
+                    return Err(io::Error::other("recording reached max_bytes"));
+                }
+                written += bytes.len() as u64;
+                file.write_all(&bytes)?;
+                // Flush whenever the queue is drained, so that the recording is usable
+                // even if the node is not shut down gracefully.
+                if receiver.is_empty() {
+                    file.flush()?;
+                }
+                Ok(())
+            };
+            for record in receiver.iter() {
+                if let Err(err) = write(record) {
+                    tracing::warn!(target: "network", ?err, path = ?cfg.path, "traffic recording stopped");
+                    return;
+                }
+            }
+        });
+        Ok(Self { sender })
+    }
+
+    pub fn record(
+        &self,
+        clock: &time::Clock,
+        direction: Direction,
+        peer_id: Option<&PeerId>,
+        encoding: Encoding,
+        message: &[u8],
+    ) {
+        let record = Record {
+            timestamp_nanos: clock.now_utc().unix_timestamp_nanos(),
+            direction,
+            peer_id: peer_id.cloned(),
+            encoding,
+            message: message.to_vec(),
+        };
+        if self.sender.try_send(record).is_err() {
+            metrics::TRAFFIC_RECORDER_DROPPED.inc();
+        }
+    }
+}
+
+/// Reads the records from a recording file.
+pub struct Reader {
+    file: io::BufReader<std::fs::File>,
+}
+
+impl Reader {
+    pub fn open(path: &std::path::Path) -> io::Result<Self> {
+        let mut file = io::BufReader::new(std::fs::File::open(path)?);
+        let magic = <[u8; 8]>::deserialize_reader(&mut file)?;
+        let version = u32::deserialize_reader(&mut file)?;
+        if &magic != MAGIC || version != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("not a traffic recording or unsupported version {version}"),
+            ));
+        }
+        Ok(Self { file })
+    }
+}
+
+impl Iterator for Reader {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match io::BufRead::fill_buf(&mut self.file) {
+            Ok([]) => None,
+            Ok(_) => Some(Record::deserialize_reader(&mut self.file)),
+            Err(err) => Some(Err(err)),
+        }
+    }
+}
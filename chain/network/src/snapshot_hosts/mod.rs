@@ -65,16 +65,20 @@ struct StatePartHost {
     /// TODO: consider storing this on disk, so we can remember who hasn't
     /// been able to provide us with the parts across restarts
     num_requests: usize,
+    /// Whether the host has been reported by state sync as slow or unreliable
+    demoted: bool,
 }
 
 impl Ord for StatePartHost {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         // std::collections:BinaryHeap used in PeerPartSelector is a max-heap.
-        // We prefer hosts with the least num_requests, after which we break
-        // ties according to the priority score and the peer_id.
+        // We prefer hosts with the least num_requests, then the hosts which
+        // are not demoted, after which we break ties according to the priority
+        // score and the peer_id.
         self.num_requests
             .cmp(&other.num_requests)
             .reverse()
+            .then_with(|| self.demoted.cmp(&other.demoted).reverse())
             .then_with(|| self.score.cmp(&other.score))
             .then_with(|| self.peer_id.cmp(&other.peer_id))
     }
@@ -126,6 +130,20 @@ impl PartPeerSelector {
     fn peer_set(&self) -> HashSet<PeerId> {
         self.peers.iter().map(|p| p.peer_id.clone()).collect()
     }
+
+    /// Updates the demoted flags of the hosts, reordering them if needed.
+    fn update_demoted(&mut self, demoted: &HashSet<PeerId>) {
+        if self.peers.iter().all(|p| p.demoted == demoted.contains(&p.peer_id)) {
+            return;
+        }
+        self.peers = std::mem::take(&mut self.peers)
+            .into_iter()
+            .map(|mut p| {
+                p.demoted = demoted.contains(&p.peer_id);
+                p
+            })
+            .collect();
+    }
 }
 
 struct Inner {
@@ -139,6 +157,9 @@ struct Inner {
     peer_selector: HashMap<(ShardId, u64), PartPeerSelector>,
     /// Batch size for populating the peer_selector from the hosts
     part_selection_cache_batch_size: usize,
+    /// Hosts which state sync found slow or unreliable. They are queried only
+    /// after the other hosts have been tried.
+    demoted: HashSet<PeerId>,
 }
 
 impl Inner {
@@ -199,7 +220,13 @@ impl Inner {
     ) -> Option<PeerId> {
         self.maybe_update_sync_hash(sync_hash);
 
-        self.hosts_for_shard.get(&shard_id)?.iter().choose(&mut thread_rng()).cloned()
+        let hosts = self.hosts_for_shard.get(&shard_id)?;
+        hosts
+            .iter()
+            .filter(|peer_id| !self.demoted.contains(peer_id))
+            .choose(&mut thread_rng())
+            .or_else(|| hosts.iter().choose(&mut thread_rng()))
+            .cloned()
     }
 
     /// Given a state part request produced by the local node,
@@ -233,6 +260,7 @@ impl Inner {
                     peer_id: peer_id.clone(),
                     score,
                     num_requests: 0,
+                    demoted: self.demoted.contains(peer_id),
                 }));
 
                 if new_peers.len() > self.part_selection_cache_batch_size {
@@ -243,6 +271,7 @@ impl Inner {
             selector.insert_peers(new_peers.drain().map(|e| e.0));
         }
 
+        selector.update_demoted(&self.demoted);
         let res = selector.next();
         res
     }
@@ -260,6 +289,7 @@ impl SnapshotHostsCache {
             hosts_for_shard: HashMap::new(),
             peer_selector: HashMap::new(),
             part_selection_cache_batch_size: config.part_selection_cache_batch_size as usize,
+            demoted: HashSet::new(),
         }))
     }
 
@@ -364,6 +394,11 @@ impl SnapshotHostsCache {
         inner.peer_selector.remove(&(shard_id, part_id));
     }
 
+    /// Triggered by state sync actor when its view of the slow or unreliable hosts changes.
+    pub fn set_demoted_hosts(&self, demoted: HashSet<PeerId>) {
+        self.0.lock().demoted = demoted;
+    }
+
     #[cfg(test)]
     pub(crate) fn has_selector(&self, shard_id: ShardId, part_id: u64) -> bool {
         let inner = self.0.lock();
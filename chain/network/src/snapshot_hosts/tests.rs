@@ -222,6 +222,7 @@ enum SelectPeerAction {
     CallSelect(Option<usize>),
     InsertHosts(&'static [usize]),
     PartReceived,
+    DemoteHosts(&'static [usize]),
 }
 
 struct SelectPeerTest {
@@ -303,6 +304,30 @@ static SELECT_PEER_CASES: &[SelectPeerTest] = &[
             SelectPeerAction::CallSelect(Some(0)),
         ],
     },
+    SelectPeerTest {
+        num_peers: 3,
+        part_selection_cache_batch_size: 2,
+        actions: &[
+            SelectPeerAction::InsertHosts(&[0, 1, 2]),
+            SelectPeerAction::DemoteHosts(&[0]),
+            // The demoted host is left out of the batch and queried only after
+            // the other hosts have been tried.
+            SelectPeerAction::CallSelect(Some(1)),
+            SelectPeerAction::CallSelect(Some(2)),
+            SelectPeerAction::CallSelect(Some(0)),
+            SelectPeerAction::PartReceived,
+            SelectPeerAction::DemoteHosts(&[1]),
+            SelectPeerAction::CallSelect(Some(0)),
+            SelectPeerAction::CallSelect(Some(2)),
+            SelectPeerAction::CallSelect(Some(1)),
+            // Demoting a host already in the batch moves it behind the hosts
+            // which have been queried the same number of times.
+            SelectPeerAction::DemoteHosts(&[0]),
+            SelectPeerAction::CallSelect(Some(1)),
+            SelectPeerAction::CallSelect(Some(2)),
+            SelectPeerAction::CallSelect(Some(0)),
+        ],
+    },
 ];
 
 async fn run_select_peer_test(
@@ -343,6 +368,9 @@ async fn run_select_peer_test(
                 cache.part_received(shard_id, part_id);
                 assert!(!cache.has_selector(shard_id, part_id));
             }
+            SelectPeerAction::DemoteHosts(hosts) => {
+                cache.set_demoted_hosts(hosts.iter().map(|h| peers[*h].peer_id.clone()).collect());
+            }
         }
     }
 }
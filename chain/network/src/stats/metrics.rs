@@ -145,6 +145,44 @@ pub(crate) static PEER_DATA_SENT_BYTES: LazyLock<IntCounter> = LazyLock::new(||
     try_create_int_counter("near_peer_data_sent_bytes", "Total data sent to peers").unwrap()
 });
 
+pub(crate) static PEER_ENCRYPTION_HANDSHAKES_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_encryption_handshakes_total",
+        "Number of Noise handshakes on peer connections, by result",
+        &["result"],
+    )
+    .unwrap()
+});
+
+pub(crate) static PEER_ENCRYPTION_OVERHEAD_BYTES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_encryption_overhead_bytes",
+        "Bytes added to the frames by the encryption of peer connections",
+        &["direction"],
+    )
+    .unwrap()
+});
+
+pub(crate) static PEER_TIER1_CONNECTIONS_BY_TRANSPORT_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_peer_tier1_connections_by_transport_total",
+            "Number of outbound TIER1 connections established, by transport",
+            &["transport"],
+        )
+        .unwrap()
+    });
+
+pub(crate) static PEER_ENCRYPTION_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    try_create_histogram_vec(
+        "near_peer_encryption_latency",
+        "Time that PeerActor spends on encrypting or decrypting a frame",
+        &["direction"],
+        Some(exponential_buckets(0.00001, 2., 20).unwrap()),
+    )
+    .unwrap()
+});
+
 pub(crate) static PEER_DATA_READ_BUFFER_SIZE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
     try_create_int_gauge_vec(
         "near_peer_read_buffer_size",
@@ -204,6 +242,14 @@ pub(crate) static PEER_MESSAGE_RATE_LIMITED_BY_TYPE_TOTAL: LazyLock<IntCounterVe
         )
         .unwrap()
     });
+pub(crate) static PEER_REPUTATION_EVENTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_reputation_events_total",
+        "Number of events affecting the reputation of peers, by event type",
+        &["event"],
+    )
+    .unwrap()
+});
 pub(crate) static SYNC_ACCOUNTS_DATA: LazyLock<IntCounterVec> = LazyLock::new(|| {
     try_create_int_counter_vec(
         "near_sync_accounts_data",
@@ -230,7 +276,69 @@ pub(crate) static REQUEST_COUNT_BY_TYPE_TOTAL: LazyLock<IntCounterVec> = LazyLoc
     .unwrap()
 });
 
+pub(crate) static TX_GOSSIP_TRANSACTIONS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_tx_gossip_transactions_total",
+        "Number of transactions processed by the transaction gossip, by outcome",
+        &["outcome"],
+    )
+    .unwrap()
+});
+
+pub(crate) static TX_GOSSIP_PROPAGATION_LATENCY: LazyLock<Histogram> = LazyLock::new(|| {
+    try_create_histogram_with_buckets(
+        "near_tx_gossip_propagation_latency_seconds",
+        "Time from receiving an announcement of an unknown transaction until receiving the transaction",
+        exponential_buckets(0.001, 1.5, 25).unwrap(),
+    )
+    .unwrap()
+});
+
+pub(crate) static NETWORK_FAULTS_INJECTED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_network_faults_injected_total",
+        "Number of messages affected by the injected network faults, by action and message type",
+        &["action", "type"],
+    )
+    .unwrap()
+});
+
+pub(crate) static PEER_DISCOVERY_ENTRIES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_discovery_entries_total",
+        "Number of entries fetched from the DNS seeds and peer lists, by outcome: valid, invalid or expired",
+        &["outcome"],
+    )
+    .unwrap()
+});
+
+pub(crate) static PEER_DISCOVERY_SOURCE_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_peer_discovery_source_errors_total",
+        "Number of failures to fetch the peers from a DNS seed or a peer list, by source type",
+        &["source"],
+    )
+    .unwrap()
+});
+
+pub(crate) static HOLE_PUNCHING_ATTEMPTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_hole_punching_attempts_total",
+        "Number of TCP hole punching attempts, by the side of the connection and the result",
+        &["side", "result"],
+    )
+    .unwrap()
+});
+
 // Routing table metrics
+pub(crate) static TRAFFIC_RECORDER_DROPPED: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_network_traffic_recorder_dropped_total",
+        "Number of messages which were not recorded, because the recorder couldn't keep up",
+    )
+    .unwrap()
+});
+
 pub(crate) static ROUTING_TABLE_RECALCULATIONS: LazyLock<IntCounter> = LazyLock::new(|| {
     try_create_int_counter(
         "near_routing_table_recalculations_total",
@@ -67,3 +67,33 @@ pub(crate) async fn query(
     client.close().await?;
     Ok(ip)
 }
+
+/// Queries the STUN servers in parallel for the IP of this machine. Servers which are
+/// unavailable are ignored, but the received responses are required to be consistent.
+/// Looks for IPv4 or IPv6 according to `want_ipv4`.
+pub(crate) async fn query_all(
+    clock: &time::Clock,
+    servers: &[ServerAddr],
+    want_ipv4: bool,
+) -> Option<std::net::IpAddr> {
+    let queries = servers.iter().map(|addr| async move {
+        let addr = lookup_host(addr, want_ipv4).await?;
+        match query(clock, &addr).await {
+            Ok(ip) => Some(ip),
+            Err(err) => {
+                tracing::warn!(target:"network", "STUN lookup failed for {addr}: {err}");
+                None
+            }
+        }
+    });
+    let ips: Vec<_> = futures::future::join_all(queries).await.into_iter().flatten().collect();
+    // Check that we have received non-zero responses and that they are consistent.
+    if ips.is_empty() {
+        return None;
+    }
+    if !ips.iter().all(|ip| ip == &ips[0]) {
+        tracing::warn!(target:"network", "received inconsistent responses from the STUN servers");
+        return None;
+    }
+    Some(ips[0])
+}
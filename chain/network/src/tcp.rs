@@ -1,6 +1,7 @@
 use crate::config::SocketOptions;
 use crate::network_protocol::PeerInfo;
 use anyhow::{Context as _, anyhow};
+use near_async::time;
 use near_primitives::network::PeerId;
 use parking_lot::Mutex;
 use std::collections::HashMap;
@@ -83,6 +84,19 @@ impl Socket {
     }
 }
 
+impl StreamId {
+    pub(crate) fn new(
+        type_: &StreamType,
+        local_addr: std::net::SocketAddr,
+        peer_addr: std::net::SocketAddr,
+    ) -> Self {
+        match type_ {
+            StreamType::Inbound => Self { inbound: local_addr, outbound: peer_addr },
+            StreamType::Outbound { .. } => Self { inbound: peer_addr, outbound: local_addr },
+        }
+    }
+}
+
 impl Stream {
     fn new(stream: tokio::net::TcpStream, type_: StreamType) -> std::io::Result<Self> {
         if let Err(err) = stream.set_nodelay(true) {
@@ -135,6 +149,31 @@ impl Stream {
         Ok(Stream::new(stream, StreamType::Outbound { peer_id: peer_info.id.clone(), tier })?)
     }
 
+    /// Opens a connection to the peer from the port of the TCP listener, as a part of
+    /// TCP hole punching (see `hole_punching`). Both nodes open the connection at about
+    /// the same time, and `type_` decides which of them performs the handshake.
+    /// The listener has to be created with `ListenerAddr::shared_listener`.
+    pub(crate) async fn punch(
+        local_addr: &ListenerAddr,
+        peer_addr: std::net::SocketAddr,
+        type_: StreamType,
+        timeout: time::Duration,
+    ) -> anyhow::Result<Stream> {
+        let socket = match local_addr.0 {
+            std::net::SocketAddr::V4(_) => tokio::net::TcpSocket::new_v4()?,
+            std::net::SocketAddr::V6(_) => tokio::net::TcpSocket::new_v6()?,
+        };
+        socket.set_reuseaddr(true)?;
+        socket.set_reuseport(true)?;
+        socket.bind(local_addr.0)?;
+        // Unlike in `connect`, the timeout is long enough for the SYN to be retransmitted,
+        // because the first SYN is expected to be dropped by the NAT of the peer.
+        let stream = tokio::time::timeout(timeout.unsigned_abs(), socket.connect(peer_addr))
+            .await?
+            .context("TcpStream::connect()")?;
+        Ok(Stream::new(stream, type_)?)
+    }
+
     /// Establishes a loopback TCP connection to localhost with random ports.
     /// Returns a pair of streams: (outbound,inbound).
     #[cfg(test)]
@@ -150,12 +189,7 @@ impl Stream {
 
     // TEST-ONLY used in reporting test events.
     pub(crate) fn id(&self) -> StreamId {
-        match self.type_ {
-            StreamType::Inbound => StreamId { inbound: self.local_addr, outbound: self.peer_addr },
-            StreamType::Outbound { .. } => {
-                StreamId { inbound: self.peer_addr, outbound: self.local_addr }
-            }
-        }
+        StreamId::new(&self.type_, self.local_addr, self.peer_addr)
     }
 }
 
@@ -231,11 +265,21 @@ impl ListenerAddr {
 
     /// Constructs a Listener out of ListenerAddr.
     pub(crate) fn listener(&self) -> std::io::Result<Listener> {
+        self.make_listener(RESERVED_LISTENER_ADDRS.lock().contains_key(&self.0))
+    }
+
+    /// Constructs a Listener which shares its port with the outbound connections
+    /// opened by `Stream::punch`.
+    pub(crate) fn shared_listener(&self) -> std::io::Result<Listener> {
+        self.make_listener(true)
+    }
+
+    fn make_listener(&self, reuseport: bool) -> std::io::Result<Listener> {
         let socket = match &self.0 {
             std::net::SocketAddr::V4(_) => tokio::net::TcpSocket::new_v4()?,
             std::net::SocketAddr::V6(_) => tokio::net::TcpSocket::new_v6()?,
         };
-        if RESERVED_LISTENER_ADDRS.lock().contains_key(&self.0) {
+        if reuseport {
             socket.set_reuseport(true)?;
         }
         socket.set_reuseaddr(true)?;
@@ -144,7 +144,7 @@ where
 
 // Gets random PeerId
 pub fn random_peer_id() -> PeerId {
-    let sk = SecretKey::from_random(KeyType::ED25519);
+    let sk = SecretKey::from_random(KeyType::ED25519).unwrap();
     PeerId::new(sk.public_key())
 }
 
@@ -0,0 +1,179 @@
+//! Gossip of transactions among the upcoming chunk producers of a shard.
+//!
+//! Transactions reach the chunk producers through `ForwardTx` messages, sent by the node which
+//! received the transaction to a few upcoming chunk producers. If a route fails, the producer
+//! never learns about the transaction. To make the delivery more robust, every chunk producer
+//! which accepts a new transaction into its pool announces its hash to the other upcoming chunk
+//! producers of the shard (`TxGossipInventory`). A producer which doesn't know the transaction
+//! yet requests it from the announcer (`TxGossipRequest`) and gets it in `TxGossipResponse`.
+//!
+//! Announcements are batched per target account and sent every `Config::inventory_interval`.
+//! The messages are sent only to the peers connected directly, over a connection negotiated at
+//! a protocol version supporting `ProtocolFeature::TxGossip`; other peers get no announcements.
+//! All the state is bounded: transactions are kept only for serving the requests, in an LRU
+//! cache, and a transaction is requested from at most one announcer at a time.
+use crate::stats::metrics;
+use lru::LruCache;
+use near_async::time;
+use near_primitives::hash::CryptoHash;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::AccountId;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximal number of transactions announced, requested or sent in a single message.
+pub(crate) const MAX_TXS_PER_MESSAGE: usize = 1000;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// How often the pending announcements are sent.
+    pub inventory_interval: time::Duration,
+    /// Number of transactions kept for serving the requests, and number of
+    /// transaction hashes remembered as known.
+    pub cache_size: usize,
+    /// After this time a transaction which we requested, but haven't received,
+    /// can be requested from another announcer.
+    pub request_timeout: time::Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            inventory_interval: time::Duration::milliseconds(50),
+            cache_size: 100_000,
+            request_timeout: time::Duration::seconds(1),
+        }
+    }
+}
+
+struct Inner {
+    /// Transactions announced by this node, kept for serving the requests.
+    txs: LruCache<CryptoHash, SignedTransaction>,
+    /// Hashes of the transactions which this node has already received.
+    known: LruCache<CryptoHash, ()>,
+    /// Transactions requested from the announcers, with the time of the request.
+    requested: LruCache<CryptoHash, time::Instant>,
+    /// Announcements waiting to be sent, by target account.
+    pending: HashMap<AccountId, Vec<CryptoHash>>,
+}
+
+pub(crate) struct TxGossip {
+    config: Config,
+    inner: Mutex<Inner>,
+}
+
+impl TxGossip {
+    pub fn new(config: Config) -> Self {
+        let cache_size = NonZeroUsize::new(config.cache_size).unwrap();
+        Self {
+            config,
+            inner: Mutex::new(Inner {
+                txs: LruCache::new(cache_size),
+                known: LruCache::new(cache_size),
+                requested: LruCache::new(cache_size),
+                pending: HashMap::new(),
+            }),
+        }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Schedules an announcement of a transaction accepted into the local pool to `targets`.
+    /// Every transaction is announced at most once.
+    pub fn announce(&self, targets: Vec<AccountId>, tx: SignedTransaction) {
+        let hash = tx.get_hash();
+        let mut inner = self.inner.lock();
+        if inner.txs.put(hash, tx).is_some() {
+            return;
+        }
+        inner.known.put(hash, ());
+        for target in targets {
+            let pending = inner.pending.entry(target).or_default();
+            if pending.len() >= MAX_TXS_PER_MESSAGE {
+                metrics::TX_GOSSIP_TRANSACTIONS.with_label_values(&["dropped"]).inc();
+                continue;
+            }
+            pending.push(hash);
+        }
+        metrics::TX_GOSSIP_TRANSACTIONS.with_label_values(&["announced"]).inc();
+    }
+
+    /// Marks a transaction received by other means (e.g. `ForwardTx`) as known,
+    /// so that it is not requested when announced.
+    pub fn insert_known(&self, hash: CryptoHash) {
+        self.inner.lock().known.put(hash, ());
+    }
+
+    /// Takes the announcements to send.
+    pub fn take_inventory(&self) -> HashMap<AccountId, Vec<CryptoHash>> {
+        std::mem::take(&mut self.inner.lock().pending)
+    }
+
+    /// Processes the announced hashes, returning the ones that should be requested from
+    /// the announcer.
+    pub fn handle_inventory(
+        &self,
+        now: time::Instant,
+        mut hashes: Vec<CryptoHash>,
+    ) -> Vec<CryptoHash> {
+        hashes.truncate(MAX_TXS_PER_MESSAGE);
+        let mut inner = self.inner.lock();
+        hashes.retain(|hash| {
+            if inner.known.contains(hash) {
+                return false;
+            }
+            if let Some(requested) = inner.requested.peek(hash) {
+                if now < *requested + self.config.request_timeout {
+                    return false;
+                }
+            }
+            inner.requested.put(*hash, now);
+            true
+        });
+        metrics::TX_GOSSIP_TRANSACTIONS
+            .with_label_values(&["requested"])
+            .inc_by(hashes.len() as u64);
+        hashes
+    }
+
+    /// Returns the requested transactions which this node has announced.
+    pub fn handle_request(&self, mut hashes: Vec<CryptoHash>) -> Vec<SignedTransaction> {
+        hashes.truncate(MAX_TXS_PER_MESSAGE);
+        let mut inner = self.inner.lock();
+        let txs: Vec<_> = hashes.iter().filter_map(|hash| inner.txs.get(hash).cloned()).collect();
+        metrics::TX_GOSSIP_TRANSACTIONS.with_label_values(&["served"]).inc_by(txs.len() as u64);
+        txs
+    }
+
+    /// Processes the received transactions, returning the ones which this node didn't know yet.
+    /// Only the transactions which have been requested are accepted.
+    pub fn handle_response(
+        &self,
+        now: time::Instant,
+        txs: Vec<SignedTransaction>,
+    ) -> Vec<SignedTransaction> {
+        let mut inner = self.inner.lock();
+        let mut new_txs = vec![];
+        for tx in txs.into_iter().take(MAX_TXS_PER_MESSAGE) {
+            let hash = tx.get_hash();
+            let Some(requested) = inner.requested.pop(&hash) else {
+                metrics::TX_GOSSIP_TRANSACTIONS.with_label_values(&["unrequested"]).inc();
+                continue;
+            };
+            if inner.known.put(hash, ()).is_some() {
+                metrics::TX_GOSSIP_TRANSACTIONS.with_label_values(&["duplicate"]).inc();
+                continue;
+            }
+            metrics::TX_GOSSIP_PROPAGATION_LATENCY.observe((now - requested).as_seconds_f64());
+            metrics::TX_GOSSIP_TRANSACTIONS.with_label_values(&["received"]).inc();
+            new_txs.push(tx);
+        }
+        new_txs
+    }
+}
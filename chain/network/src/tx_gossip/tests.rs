@@ -0,0 +1,72 @@
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+use crate::tx_gossip::{Config, MAX_TXS_PER_MESSAGE, TxGossip};
+use near_async::time;
+use near_o11y::testonly::init_test_logger;
+
+#[test]
+fn announce_request_response() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let announcer = TxGossip::new(Config::default());
+    let receiver = TxGossip::new(Config::default());
+    let targets = vec![data::make_account_id(&mut rng), data::make_account_id(&mut rng)];
+    let txs: Vec<_> = (0..3).map(|_| data::make_signed_transaction(&mut rng)).collect();
+
+    // Every transaction is announced once to every target.
+    for tx in &txs {
+        announcer.announce(targets.clone(), tx.clone());
+        announcer.announce(targets.clone(), tx.clone());
+    }
+    let hashes: Vec<_> = txs.iter().map(|tx| tx.get_hash()).collect();
+    let inventory = announcer.take_inventory();
+    assert_eq!(inventory.len(), 2);
+    for target in &targets {
+        assert_eq!(inventory[target], hashes);
+    }
+    assert!(announcer.take_inventory().is_empty());
+
+    // The receiver requests only the transactions it doesn't know yet.
+    receiver.insert_known(hashes[0]);
+    let requested = receiver.handle_inventory(clock.now(), hashes.clone());
+    assert_eq!(requested, hashes[1..]);
+    // Transactions already requested are not requested again from another announcer.
+    assert!(receiver.handle_inventory(clock.now(), hashes.clone()).is_empty());
+
+    let response = announcer.handle_request(requested);
+    assert_eq!(response, txs[1..]);
+    assert_eq!(receiver.handle_response(clock.now(), response.clone()), txs[1..]);
+    // Duplicated and unrequested transactions are ignored.
+    assert!(receiver.handle_response(clock.now(), response).is_empty());
+    assert!(receiver.handle_inventory(clock.now(), hashes).is_empty());
+}
+
+#[test]
+fn request_timeout() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let clock = time::FakeClock::default();
+    let cfg = Config::default();
+    let receiver = TxGossip::new(cfg.clone());
+    let hash = data::make_signed_transaction(&mut rng).get_hash();
+
+    assert_eq!(receiver.handle_inventory(clock.now(), vec![hash]), vec![hash]);
+    clock.advance(cfg.request_timeout / 2);
+    assert!(receiver.handle_inventory(clock.now(), vec![hash]).is_empty());
+    // The announcer didn't respond in time, so the transaction is requested again.
+    clock.advance(cfg.request_timeout);
+    assert_eq!(receiver.handle_inventory(clock.now(), vec![hash]), vec![hash]);
+}
+
+#[test]
+fn bounded_inventory() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let announcer = TxGossip::new(Config::default());
+    let target = data::make_account_id(&mut rng);
+    for _ in 0..MAX_TXS_PER_MESSAGE + 10 {
+        announcer.announce(vec![target.clone()], data::make_signed_transaction(&mut rng));
+    }
+    assert_eq!(announcer.take_inventory()[&target].len(), MAX_TXS_PER_MESSAGE);
+}
@@ -9,6 +9,7 @@ pub use crate::network_protocol::{
     PartialEncodedChunkResponseMsg, PeerChainInfoV2, PeerInfo, SnapshotHostInfo, StateResponseInfo,
     StateResponseInfoV1, StateResponseInfoV2,
 };
+use crate::peer_manager::peer_store::reputation::Reputation;
 use crate::routing::routing_table_view::RoutingTableInfo;
 pub use crate::state_sync::StateSyncResponse;
 use near_async::messaging::{AsyncSender, Sender};
@@ -121,6 +122,9 @@ pub struct KnownPeerState {
     // Last time we tried to connect to this peer.
     // This data is not persisted in storage.
     pub last_outbound_attempt: Option<(time::Utc, Result<(), String>)>,
+    /// Reputation score of the peer, based on its past behaviour.
+    /// This data is not persisted in storage.
+    pub reputation: Reputation,
 }
 
 impl KnownPeerState {
@@ -131,6 +135,7 @@ impl KnownPeerState {
             first_seen: now,
             last_seen: now,
             last_outbound_attempt: None,
+            reputation: Reputation::new(now),
         }
     }
 }
@@ -166,6 +171,9 @@ pub struct ChainInfo {
     // Peers acting on behalf of these accounts have a higher
     // priority on the NEAR network than other peers.
     pub tier1_accounts: Arc<AccountKeys>,
+    // Number of chunk state witnesses waiting for validation.
+    // Used to scale down the rate limits when the node is overloaded.
+    pub chunk_validation_backlog: usize,
 }
 
 #[derive(Debug, actix::Message)]
@@ -186,6 +194,8 @@ pub enum PeerManagerMessageRequest {
     /// Used in tests and internally by PeerManager.
     /// TODO: replace it with AsyncContext::spawn/run_later for internal use.
     OutboundTcpConnect(crate::tcp::Stream),
+    /// Request PeerManager to apply the updated config, see `core/dyn-configs`.
+    UpdateNetworkConfig(crate::config_json::UpdatableNetworkConfig),
     /// The following types of requests are used to trigger actions in the Peer Manager for testing.
     /// TEST-ONLY: Fetch current routing table.
     FetchRoutingTable,
@@ -216,6 +226,7 @@ pub enum PeerManagerMessageResponse {
     AdvertiseTier1Proxies,
     /// TEST-ONLY
     OutboundTcpConnect,
+    UpdateNetworkConfig,
     FetchRoutingTable(RoutingTableInfo),
 }
 
@@ -282,6 +293,9 @@ pub enum NetworkRequests {
     PartialEncodedChunkForward { account_id: AccountId, forward: PartialEncodedChunkForwardMsg },
     /// Valid transaction but since we are not validators we send this transaction to current validators.
     ForwardTx(AccountId, SignedTransaction),
+    /// Announce a transaction accepted into the pool to the other upcoming chunk producers
+    /// of the shard, see `tx_gossip`.
+    AnnounceTx(Vec<AccountId>, SignedTransaction),
     /// Query transaction status
     TxStatus(AccountId, AccountId, CryptoHash),
     /// Acknowledgement to a chunk's state witness, sent back to the originating chunk producer.
@@ -314,6 +328,9 @@ pub enum NetworkRequests {
 #[rtype(result = "()")]
 pub enum StateSyncEvent {
     StatePartReceived(ShardId, u64),
+    /// The snapshot hosts which state sync found slow or unreliable, replacing the
+    /// previously reported ones. They are queried only after the other hosts.
+    DemotedSnapshotHosts(HashSet<PeerId>),
 }
 
 /// Combines peer address info, chain.
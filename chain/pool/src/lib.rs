@@ -169,6 +169,19 @@ impl TransactionPool {
         self.transaction_pool_size_metric.set(self.total_transaction_size as i64);
     }
 
+    /// Returns one transaction from each of up to `limit` transaction groups, in the order in
+    /// which the pool iterator is going to visit the groups. All transactions in a group share
+    /// the signer and the access key. The pool is not modified.
+    pub fn peek_groups(&self, limit: usize) -> impl Iterator<Item = &ValidatedTransaction> {
+        let after_last_used =
+            self.transactions.range((Bound::Excluded(self.last_used_key), Bound::Unbounded));
+        let up_to_last_used = self.transactions.range(..=self.last_used_key);
+        after_last_used
+            .chain(up_to_last_used)
+            .filter_map(|(_, validated_txs)| validated_txs.first())
+            .take(limit)
+    }
+
     /// Returns the number of unique transactions in the pool.
     pub fn len(&self) -> usize {
         self.unique_transactions.len()
@@ -632,4 +645,33 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_peek_groups() {
+        let mut pool = TransactionPool::new(TEST_SEED, None, "");
+        let mut transactions = generate_transactions("alice.near", "alice.near", 1, 3);
+        transactions.extend(generate_transactions("bob.near", "bob.near", 1, 2));
+        transactions.extend(generate_transactions("carol.near", "carol.near", 1, 1));
+        for tx in transactions {
+            assert_eq!(pool.insert_transaction(tx), InsertTransactionResult::Success);
+        }
+        let signers = |pool: &TransactionPool, limit| {
+            pool.peek_groups(limit).map(|tx| tx.signer_id().to_string()).collect::<Vec<_>>()
+        };
+        let mut all_signers = signers(&pool, 10);
+        all_signers.sort();
+        assert_eq!(all_signers, vec!["alice.near", "bob.near", "carol.near"]);
+        assert_eq!(signers(&pool, 2).len(), 2);
+        assert_eq!(pool.len(), 6);
+
+        // Peeking follows the order in which the pool iterator visits the groups.
+        let visited_signer = {
+            let mut pool_iter = pool.pool_iterator();
+            let group = pool_iter.next().unwrap();
+            group.peek_next().unwrap().signer_id().to_string()
+        };
+        let peeked_signers = signers(&pool, 3);
+        assert_eq!(peeked_signers.len(), 3);
+        assert_eq!(peeked_signers.last().unwrap(), &visited_signer);
+    }
 }
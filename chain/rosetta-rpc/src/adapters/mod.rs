@@ -216,9 +216,12 @@ pub struct NearActions {
     pub actions: Vec<near_primitives::transaction::Action>,
 }
 
-impl From<NearActions> for Vec<crate::models::Operation> {
-    /// Convert NEAR Actions to Rosetta Operations. It never fails.
-    fn from(near_actions: NearActions) -> Self {
+impl TryFrom<NearActions> for Vec<crate::models::Operation> {
+    type Error = crate::errors::ErrorKind;
+
+    /// Convert NEAR Actions to Rosetta Operations. It only fails on keys which
+    /// cannot be represented in Rosetta (e.g. MULTISIG access keys).
+    fn try_from(near_actions: NearActions) -> Result<Self, Self::Error> {
         let NearActions { sender_account_id, receiver_account_id, actions } = near_actions;
         let sender_account_identifier: crate::models::AccountIdentifier = sender_account_id.into();
         let receiver_account_identifier: crate::models::AccountIdentifier =
@@ -294,7 +297,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                     operations.push(
                         validated_operations::AddKeyOperation {
                             account: receiver_account_identifier.clone(),
-                            public_key: (&action.public_key).into(),
+                            public_key: (&action.public_key).try_into()?,
                         }
                         .into_related_operation(
                             add_key_operation_id,
@@ -316,7 +319,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                     operations.push(
                         validated_operations::DeleteKeyOperation {
                             account: receiver_account_identifier.clone(),
-                            public_key: (&action.public_key).into(),
+                            public_key: (&action.public_key).try_into()?,
                         }
                         .into_related_operation(
                             crate::models::OperationIdentifier::new(&operations),
@@ -357,7 +360,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                         validated_operations::StakeOperation {
                             account: receiver_account_identifier.clone(),
                             amount: action.stake,
-                            public_key: (&action.public_key).into(),
+                            public_key: (&action.public_key).try_into()?,
                         }
                         .into_operation(crate::models::OperationIdentifier::new(&operations)),
                     );
@@ -460,7 +463,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                     let delegate_action_operation_id =
                         crate::models::OperationIdentifier::new(&operations);
                     let delegate_action_operation: validated_operations::DelegateActionOperation =
-                        action.delegate_action.clone().into();
+                        action.delegate_action.clone().try_into()?;
 
                     operations.push(delegate_action_operation.into_related_operation(
                         delegate_action_operation_id,
@@ -479,7 +482,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                             .map(|a| a.into())
                             .collect::<Vec<near_primitives::transaction::Action>>(),
                     }
-                    .into();
+                    .try_into()?;
 
                     operations.extend(delegated_operations);
                 } // TODO(#8469): Implement delegate action support, for now they are ignored.
@@ -489,7 +492,7 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                 }
             }
         }
-        operations
+        Ok(operations)
     }
 }
 
@@ -853,6 +856,7 @@ mod tests {
             near_primitives::transaction::AddKeyAction {
                 access_key: near_primitives::account::AccessKey::full_access(),
                 public_key: near_crypto::SecretKey::from_random(near_crypto::KeyType::ED25519)
+                    .unwrap()
                     .public_key(),
             }
             .into(),
@@ -860,6 +864,7 @@ mod tests {
         let delete_key_actions = vec![
             near_primitives::transaction::DeleteKeyAction {
                 public_key: near_crypto::SecretKey::from_random(near_crypto::KeyType::ED25519)
+                    .unwrap()
                     .public_key(),
             }
             .into(),
@@ -874,6 +879,7 @@ mod tests {
             near_primitives::transaction::StakeAction {
                 stake: 456,
                 public_key: near_crypto::SecretKey::from_random(near_crypto::KeyType::ED25519)
+                    .unwrap()
                     .public_key(),
             }
             .into(),
@@ -941,7 +947,8 @@ mod tests {
                 actions,
             };
             println!("NEAR Actions: {:#?}", near_actions);
-            let operations: Vec<crate::models::Operation> = near_actions.clone().into();
+            let operations: Vec<crate::models::Operation> =
+                near_actions.clone().try_into().unwrap();
             println!("Operations: {:#?}", operations);
 
             let near_actions_recreated = NearActions::try_from(operations).unwrap();
@@ -962,7 +969,8 @@ mod tests {
                 actions,
             };
             println!("NEAR Actions: {:#?}", near_actions);
-            let operations: Vec<crate::models::Operation> = near_actions.clone().into();
+            let operations: Vec<crate::models::Operation> =
+                near_actions.clone().try_into().unwrap();
             println!("Operations: {:#?}", operations);
 
             let near_actions_recreated = NearActions::try_from(operations).unwrap();
@@ -976,6 +984,26 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_multisig_key_actions_are_rejected() {
+        let multisig_key = near_crypto::PublicKey::MULTISIG(
+            near_crypto::MultisigPublicKey::new(
+                1,
+                vec![SecretKey::from_seed(KeyType::ED25519, "test").public_key()],
+            )
+            .unwrap(),
+        );
+        let near_actions = NearActions {
+            sender_account_id: "sender.near".parse().unwrap(),
+            receiver_account_id: "receiver.near".parse().unwrap(),
+            actions: vec![
+                near_primitives::transaction::DeleteKeyAction { public_key: multisig_key }.into(),
+            ],
+        };
+        let operations: Result<Vec<crate::models::Operation>, _> = near_actions.try_into();
+        assert!(matches!(operations, Err(crate::errors::ErrorKind::InvalidInput(_))));
+    }
+
     #[test]
     fn test_delegate_actions_bijection() {
         // dummy key
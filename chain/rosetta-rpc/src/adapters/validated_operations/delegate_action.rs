@@ -54,13 +54,17 @@ impl TryFrom<crate::models::Operation> for DelegateActionOperation {
     }
 }
 
-impl From<near_primitives::action::delegate::DelegateAction> for DelegateActionOperation {
-    fn from(delegate_action: near_primitives::action::delegate::DelegateAction) -> Self {
-        DelegateActionOperation {
+impl TryFrom<near_primitives::action::delegate::DelegateAction> for DelegateActionOperation {
+    type Error = crate::errors::ErrorKind;
+
+    fn try_from(
+        delegate_action: near_primitives::action::delegate::DelegateAction,
+    ) -> Result<Self, Self::Error> {
+        Ok(DelegateActionOperation {
             receiver_id: delegate_action.receiver_id.into(),
             max_block_height: delegate_action.max_block_height,
-            public_key: (&delegate_action.public_key).into(),
+            public_key: (&delegate_action.public_key).try_into()?,
             nonce: delegate_action.nonce,
-        }
+        })
     }
 }
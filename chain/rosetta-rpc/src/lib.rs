@@ -661,7 +661,7 @@ async fn construction_payloads(
         unsigned_transaction: unsigned_transaction.into(),
         payloads: vec![models::SigningPayload {
             account_identifier: signer_account_id.into(),
-            signature_type: Some(signer_public_access_key.key_type().into()),
+            signature_type: Some(signer_public_access_key.key_type().try_into()?),
             hex_bytes: transaction_hash.as_ref().to_owned().into(),
         }],
     }))
@@ -748,7 +748,7 @@ async fn construction_parse(
 
     Ok(Json(models::ConstructionParseResponse {
         account_identifier_signers,
-        operations: near_actions.into(),
+        operations: near_actions.try_into()?,
     }))
 }
 
@@ -796,6 +796,7 @@ async fn construction_submit(
             near_client::ProcessTxRequest {
                 transaction: signed_transaction.into_inner(),
                 is_forwarded: false,
+                is_gossiped: false,
                 check_only: false,
             }
             .with_span_context(),
@@ -1217,10 +1217,13 @@ pub(crate) struct PublicKey {
     pub curve_type: CurveType,
 }
 
-impl From<&near_crypto::PublicKey> for PublicKey {
-    fn from(public_key: &near_crypto::PublicKey) -> Self {
+impl TryFrom<&near_crypto::PublicKey> for PublicKey {
+    type Error = crate::errors::ErrorKind;
+
+    fn try_from(public_key: &near_crypto::PublicKey) -> Result<Self, Self::Error> {
+        let curve_type = public_key.key_type().try_into()?;
         let hex_bytes = public_key.key_data().to_owned().into();
-        Self { hex_bytes, curve_type: public_key.key_type().into() }
+        Ok(Self { hex_bytes, curve_type })
     }
 }
 
@@ -1235,6 +1238,9 @@ impl TryFrom<&PublicKey> for near_crypto::PublicKey {
             CurveType::Secp256k1 => {
                 near_crypto::PublicKey::SECP256K1((hex_bytes.as_ref() as &[u8]).try_into()?)
             }
+            CurveType::Secp256r1 => {
+                near_crypto::PublicKey::SECP256R1((hex_bytes.as_ref() as &[u8]).try_into()?)
+            }
         })
     }
 }
@@ -1247,13 +1253,23 @@ pub(crate) enum CurveType {
     Edwards25519,
     /// SEC compressed - 33 bytes (<https://secg.org/sec1-v2.pdf#subsubsection.2.3.3>)
     Secp256k1,
+    /// SEC compressed - 33 bytes (<https://secg.org/sec1-v2.pdf#subsubsection.2.3.3>)
+    Secp256r1,
 }
 
-impl From<near_crypto::KeyType> for CurveType {
-    fn from(key_type: near_crypto::KeyType) -> Self {
+impl TryFrom<near_crypto::KeyType> for CurveType {
+    type Error = crate::errors::ErrorKind;
+
+    /// MULTISIG keys are a set of keys on possibly different curves, so there
+    /// is no single Rosetta curve type which could describe them.
+    fn try_from(key_type: near_crypto::KeyType) -> Result<Self, Self::Error> {
         match key_type {
-            near_crypto::KeyType::ED25519 => Self::Edwards25519,
-            near_crypto::KeyType::SECP256K1 => Self::Secp256k1,
+            near_crypto::KeyType::ED25519 => Ok(Self::Edwards25519),
+            near_crypto::KeyType::SECP256K1 => Ok(Self::Secp256k1),
+            near_crypto::KeyType::SECP256R1 => Ok(Self::Secp256r1),
+            near_crypto::KeyType::MULTISIG => Err(crate::errors::ErrorKind::InvalidInput(
+                "MULTISIG keys are not supported in Rosetta".to_string(),
+            )),
         }
     }
 }
@@ -1313,13 +1329,17 @@ pub(crate) enum SignatureType {
      * Schnorr1, */
 }
 
-impl From<near_crypto::KeyType> for SignatureType {
-    fn from(key_type: near_crypto::KeyType) -> Self {
+impl TryFrom<near_crypto::KeyType> for SignatureType {
+    type Error = crate::errors::ErrorKind;
+
+    fn try_from(key_type: near_crypto::KeyType) -> Result<Self, Self::Error> {
         match key_type {
-            near_crypto::KeyType::ED25519 => Self::Ed25519,
-            near_crypto::KeyType::SECP256K1 => {
-                unimplemented!("SECP256K1 keys are not implemented in Rosetta yet")
-            }
+            near_crypto::KeyType::ED25519 => Ok(Self::Ed25519),
+            near_crypto::KeyType::SECP256K1
+            | near_crypto::KeyType::SECP256R1
+            | near_crypto::KeyType::MULTISIG => Err(crate::errors::ErrorKind::InvalidInput(
+                format!("{} keys are not supported in Rosetta yet", key_type),
+            )),
         }
     }
 }
@@ -120,6 +120,23 @@ pub struct GCConfig {
     /// How often gc should be run
     #[serde(with = "near_time::serde_duration_as_std")]
     pub gc_step_period: Duration,
+
+    /// Data of the chosen accounts which is kept for longer than `gc_num_epochs_to_keep`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retention: Option<GCRetentionConfig>,
+}
+
+/// Retention policy for the data touching the chosen accounts, so that `tx` and
+/// `EXPERIMENTAL_receipt` queries about them keep working after the rest is garbage collected.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct GCRetentionConfig {
+    /// Accounts (or contracts) whose transactions, receipts, execution outcomes and state
+    /// changes are retained. This includes the whole execution of the transactions signed by
+    /// or sent to these accounts, and of the receipts they send or receive.
+    pub accounts: Vec<AccountId>,
+    /// Number of epochs for which the retained data is kept. It is kept forever if not set.
+    #[serde(default)]
+    pub num_epochs_to_keep: Option<u64>,
 }
 
 impl Default for GCConfig {
@@ -129,6 +146,7 @@ impl Default for GCConfig {
             gc_fork_clean_step: 100,
             gc_num_epochs_to_keep: DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
             gc_step_period: Duration::seconds(1),
+            retention: None,
         }
     }
 }
@@ -644,6 +662,13 @@ pub struct ClientConfig {
     /// which can cause extra load on the database. This option is not recommended for production use,
     /// as a large number of incoming witnesses could cause denial of service.
     pub save_latest_witnesses: bool,
+    /// If set, divergences found by shadow chunk validation are saved to this
+    /// directory, see `near_chain::stateless_validation::divergence`. Only has
+    /// an effect when the node is built with `shadow_chunk_validation`.
+    pub shadow_validation_divergence_dir: Option<PathBuf>,
+    /// If true, recent orphan blocks and orphan chunk state witnesses are
+    /// saved to the database and requeued for processing after a restart.
+    pub persist_orphans: bool,
     pub transaction_request_handler_threads: usize,
 }
 
@@ -732,6 +757,8 @@ impl ClientConfig {
             orphan_state_witness_pool_size: default_orphan_state_witness_pool_size(),
             orphan_state_witness_max_size: default_orphan_state_witness_max_size(),
             save_latest_witnesses: false,
+            shadow_validation_divergence_dir: None,
+            persist_orphans: false,
             transaction_request_handler_threads: default_rpc_handler_thread_count(),
         }
     }
@@ -13,7 +13,7 @@ pub use client_config::{
     ChunkDistributionNetworkConfig, ChunkDistributionUris, ClientConfig,
     DEFAULT_GC_NUM_EPOCHS_TO_KEEP, DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_EXTERNAL,
     DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_ON_CATCHUP_EXTERNAL, DumpConfig, EpochSyncConfig,
-    ExternalStorageConfig, ExternalStorageLocation, GCConfig, LogSummaryStyle,
+    ExternalStorageConfig, ExternalStorageLocation, GCConfig, GCRetentionConfig, LogSummaryStyle,
     MIN_GC_NUM_EPOCHS_TO_KEEP, ReshardingConfig, ReshardingHandle, StateSyncConfig, SyncConfig,
     TEST_STATE_SYNC_TIMEOUT, TrackedShardsConfig, default_chunk_wait_mult,
     default_enable_multiline_logging, default_epoch_sync,
@@ -1,3 +1,4 @@
+use crate::KeyType;
 use near_account_id::AccountId;
 
 #[derive(Debug, Clone, thiserror::Error)]
@@ -50,6 +51,12 @@ impl From<ParseKeyTypeError> for ParseSignatureError {
     }
 }
 
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("there is no secret key of type '{key_type}'")]
+pub struct SecretKeyTypeError {
+    pub key_type: KeyType,
+}
+
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum ImplicitPublicKeyError {
     #[error("'{account_id}' is not a NEAR-implicit account")]
@@ -7,7 +7,7 @@ pub fn is_valid_staking_key(public_key: &PublicKey) -> bool {
     // The valid staking key is ED25519, and can be converted to ristretto.
     match public_key {
         PublicKey::ED25519(key) => convert_public_key(key).is_some(),
-        PublicKey::SECP256K1(_) => false,
+        PublicKey::SECP256K1(_) | PublicKey::SECP256R1(_) | PublicKey::MULTISIG(_) => false,
     }
 }
 
@@ -36,7 +36,7 @@ mod tests {
         use super::*;
 
         for _ in 0..10 {
-            let kk = signature::SecretKey::from_random(signature::KeyType::ED25519);
+            let kk = signature::SecretKey::from_random(signature::KeyType::ED25519).unwrap();
             let pk = match kk.public_key() {
                 signature::PublicKey::ED25519(k) => k,
                 _ => unreachable!(),
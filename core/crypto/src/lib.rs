@@ -1,11 +1,12 @@
 #![cfg_attr(enable_const_type_id, feature(const_type_id))]
 #![deny(clippy::arithmetic_side_effects)]
 
-pub use errors::{ParseKeyError, ParseKeyTypeError, ParseSignatureError};
+pub use errors::{ParseKeyError, ParseKeyTypeError, ParseSignatureError, SecretKeyTypeError};
 pub use key_file::KeyFile;
 pub use signature::{
-    ED25519PublicKey, ED25519SecretKey, KeyType, PublicKey, Secp256K1PublicKey, Secp256K1Signature,
-    SecretKey, Signature,
+    ED25519PublicKey, ED25519SecretKey, KeyType, MAX_MULTISIG_KEYS, MultisigPublicKey,
+    MultisigSignature, PublicKey, Secp256K1PublicKey, Secp256K1Signature, Secp256R1PublicKey,
+    Secp256R1Signature, SecretKey, Signature,
 };
 pub use signer::{EmptySigner, InMemorySigner, Signer};
 
@@ -19,6 +19,8 @@ pub static SECP256K1: LazyLock<secp256k1::Secp256k1<secp256k1::All>> =
 pub enum KeyType {
     ED25519 = 0,
     SECP256K1 = 1,
+    SECP256R1 = 2,
+    MULTISIG = 3,
 }
 
 impl Display for KeyType {
@@ -26,6 +28,8 @@ impl Display for KeyType {
         f.write_str(match self {
             KeyType::ED25519 => "ed25519",
             KeyType::SECP256K1 => "secp256k1",
+            KeyType::SECP256R1 => "secp256r1",
+            KeyType::MULTISIG => "multisig",
         })
     }
 }
@@ -38,6 +42,8 @@ impl FromStr for KeyType {
         match lowercase_key_type.as_str() {
             "ed25519" => Ok(KeyType::ED25519),
             "secp256k1" => Ok(KeyType::SECP256K1),
+            "secp256r1" => Ok(KeyType::SECP256R1),
+            "multisig" => Ok(KeyType::MULTISIG),
             _ => Err(Self::Err::UnknownKeyType { unknown_key_type: lowercase_key_type }),
         }
     }
@@ -50,6 +56,8 @@ impl TryFrom<u8> for KeyType {
         match value {
             0 => Ok(KeyType::ED25519),
             1 => Ok(KeyType::SECP256K1),
+            2 => Ok(KeyType::SECP256R1),
+            3 => Ok(KeyType::MULTISIG),
             unknown_key_type => {
                 Err(Self::Error::UnknownKeyType { unknown_key_type: unknown_key_type.to_string() })
             }
@@ -114,6 +122,135 @@ impl std::fmt::Debug for ED25519PublicKey {
     }
 }
 
+/// Length of a SEC1 compressed point on the P-256 curve.
+const SECP256R1_PUBLIC_KEY_LENGTH: usize = 33;
+
+/// NIST P-256 public key in the SEC1 compressed form, as used by passkeys and
+/// hardware secure enclaves.
+#[derive(
+    Clone, Eq, Ord, PartialEq, PartialOrd, derive_more::AsRef, derive_more::From, ProtocolSchema,
+)]
+#[cfg_attr(test, derive(bolero::TypeGenerator))]
+#[as_ref(forward)]
+pub struct Secp256R1PublicKey([u8; SECP256R1_PUBLIC_KEY_LENGTH]);
+
+impl TryFrom<&[u8]> for Secp256R1PublicKey {
+    type Error = crate::errors::ParseKeyError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        data.try_into().map(Self).map_err(|_| Self::Error::InvalidLength {
+            expected_length: SECP256R1_PUBLIC_KEY_LENGTH,
+            received_length: data.len(),
+        })
+    }
+}
+
+impl std::fmt::Debug for Secp256R1PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        Display::fmt(&Bs58(&self.0), f)
+    }
+}
+
+/// Maximum number of keys in a multisig key, which also bounds the number of
+/// signatures verified for a single multisig signature.
+pub const MAX_MULTISIG_KEYS: usize = 16;
+
+/// Threshold key which requires signatures of `threshold` out of its `keys`.
+///
+/// Component keys can't be multisig keys themselves and must be unique. These
+/// invariants are checked on construction and deserialization.
+#[derive(Clone, Eq, Ord, PartialEq, PartialOrd, ProtocolSchema)]
+pub struct MultisigPublicKey {
+    threshold: u8,
+    keys: Vec<PublicKey>,
+    /// Borsh encoding of `threshold` and `keys`, kept so that the key can be
+    /// exposed as raw bytes just like the single keys.
+    data: Vec<u8>,
+}
+
+impl MultisigPublicKey {
+    pub fn new(threshold: u8, keys: Vec<PublicKey>) -> Result<Self, crate::errors::ParseKeyError> {
+        let invalid = |error_message: &str| crate::errors::ParseKeyError::InvalidData {
+            error_message: error_message.to_string(),
+        };
+        if keys.is_empty() || keys.len() > MAX_MULTISIG_KEYS {
+            return Err(invalid("multisig key must have between 1 and 16 keys"));
+        }
+        if threshold == 0 || usize::from(threshold) > keys.len() {
+            return Err(invalid("multisig threshold must be between 1 and the number of keys"));
+        }
+        if keys.iter().any(|key| matches!(key, PublicKey::MULTISIG(_))) {
+            return Err(invalid("multisig keys can't be nested"));
+        }
+        if keys.iter().enumerate().any(|(i, key)| keys[..i].contains(key)) {
+            return Err(invalid("multisig key contains duplicate keys"));
+        }
+        let mut data = vec![threshold];
+        BorshSerialize::serialize(&keys, &mut data).expect("writing to a vector can't fail");
+        Ok(Self { threshold, keys, data })
+    }
+
+    /// Number of signatures required by this key.
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn keys(&self) -> &[PublicKey] {
+        &self.keys
+    }
+
+    /// Parses the key from its raw data, i.e. the borsh encoding without the
+    /// key type prefix.
+    fn from_data(mut data: &[u8]) -> Result<Self, crate::errors::ParseKeyError> {
+        let key = Self::deserialize_data(&mut data).map_err(|err| {
+            crate::errors::ParseKeyError::InvalidData { error_message: err.to_string() }
+        })?;
+        if !data.is_empty() {
+            return Err(crate::errors::ParseKeyError::InvalidData {
+                error_message: "unexpected trailing bytes in multisig key".to_string(),
+            });
+        }
+        Ok(key)
+    }
+
+    /// Reads the component keys without recursing into `PublicKey`
+    /// deserialization, so that nested multisig keys are rejected before being
+    /// read.
+    fn deserialize_data<R: Read>(rd: &mut R) -> std::io::Result<Self> {
+        let threshold = u8::deserialize_reader(rd)?;
+        let len = u32::deserialize_reader(rd)? as usize;
+        if len > MAX_MULTISIG_KEYS {
+            return Err(Error::new(ErrorKind::InvalidData, "too many keys in multisig key"));
+        }
+        let keys = (0..len)
+            .map(|_| match read_key_type(rd)? {
+                KeyType::MULTISIG => {
+                    Err(Error::new(ErrorKind::InvalidData, "multisig keys can't be nested"))
+                }
+                key_type => PublicKey::deserialize_single(key_type, rd),
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        Self::new(threshold, keys).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+}
+
+impl std::fmt::Debug for MultisigPublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(&bs58::encode(&self.data).into_string())
+    }
+}
+
+// Component keys are generated as ED25519 keys, so that the generator does not
+// recurse into generating further multisig keys.
+#[cfg(test)]
+impl bolero::TypeGenerator for MultisigPublicKey {
+    fn generate<D: bolero::Driver>(driver: &mut D) -> Option<Self> {
+        let threshold = u8::generate(driver)?;
+        let keys = <Vec<ED25519PublicKey>>::generate(driver)?;
+        Self::new(threshold, keys.into_iter().map(PublicKey::ED25519).collect()).ok()
+    }
+}
+
 /// Public key container supporting different curves.
 #[derive(Clone, PartialEq, PartialOrd, Ord, Eq, ProtocolSchema)]
 #[cfg_attr(test, derive(bolero::TypeGenerator))]
@@ -122,6 +259,10 @@ pub enum PublicKey {
     ED25519(ED25519PublicKey),
     /// 512 bit elliptic curve based public-key used in Bitcoin's public-key cryptography.
     SECP256K1(Secp256K1PublicKey),
+    /// NIST P-256 elliptic curve based public-key, used by passkeys.
+    SECP256R1(Secp256R1PublicKey),
+    /// Threshold combination of other public keys.
+    MULTISIG(MultisigPublicKey),
 }
 
 impl PublicKey {
@@ -132,6 +273,8 @@ impl PublicKey {
         match self {
             Self::ED25519(_) => ED25519_LEN,
             Self::SECP256K1(_) => 65,
+            Self::SECP256R1(_) => SECP256R1_PUBLIC_KEY_LENGTH + 1,
+            Self::MULTISIG(key) => key.data.len().saturating_add(1),
         }
     }
 
@@ -141,6 +284,13 @@ impl PublicKey {
                 PublicKey::ED25519(ED25519PublicKey([0u8; ed25519_dalek::PUBLIC_KEY_LENGTH]))
             }
             KeyType::SECP256K1 => PublicKey::SECP256K1(Secp256K1PublicKey([0u8; 64])),
+            KeyType::SECP256R1 => {
+                PublicKey::SECP256R1(Secp256R1PublicKey([0u8; SECP256R1_PUBLIC_KEY_LENGTH]))
+            }
+            KeyType::MULTISIG => PublicKey::MULTISIG(
+                MultisigPublicKey::new(1, vec![PublicKey::empty(KeyType::ED25519)])
+                    .expect("1 out of 1 key is a valid multisig key"),
+            ),
         }
     }
 
@@ -148,6 +298,8 @@ impl PublicKey {
         match self {
             Self::ED25519(_) => KeyType::ED25519,
             Self::SECP256K1(_) => KeyType::SECP256K1,
+            Self::SECP256R1(_) => KeyType::SECP256R1,
+            Self::MULTISIG(_) => KeyType::MULTISIG,
         }
     }
 
@@ -155,48 +307,64 @@ impl PublicKey {
         match self {
             Self::ED25519(key) => key.as_ref(),
             Self::SECP256K1(key) => key.as_ref(),
+            Self::SECP256R1(key) => key.as_ref(),
+            Self::MULTISIG(key) => &key.data,
         }
     }
 
     pub fn unwrap_as_ed25519(&self) -> &ED25519PublicKey {
         match self {
             Self::ED25519(key) => key,
-            Self::SECP256K1(_) => panic!(),
+            _ => panic!(),
         }
     }
 
     pub fn unwrap_as_secp256k1(&self) -> &Secp256K1PublicKey {
         match self {
             Self::SECP256K1(key) => key,
-            Self::ED25519(_) => panic!(),
+            _ => panic!(),
+        }
+    }
+
+    /// Reads the data of a key of the given type, which must not be multisig.
+    fn deserialize_single<R: Read>(key_type: KeyType, rd: &mut R) -> std::io::Result<Self> {
+        match key_type {
+            KeyType::ED25519 => {
+                Ok(PublicKey::ED25519(ED25519PublicKey(BorshDeserialize::deserialize_reader(rd)?)))
+            }
+            KeyType::SECP256K1 => Ok(PublicKey::SECP256K1(Secp256K1PublicKey(
+                BorshDeserialize::deserialize_reader(rd)?,
+            ))),
+            KeyType::SECP256R1 => Ok(PublicKey::SECP256R1(Secp256R1PublicKey(
+                BorshDeserialize::deserialize_reader(rd)?,
+            ))),
+            KeyType::MULTISIG => unreachable!("multisig keys are read separately"),
         }
     }
 }
 
+fn read_key_type<R: Read>(rd: &mut R) -> std::io::Result<KeyType> {
+    KeyType::try_from(u8::deserialize_reader(rd)?)
+        .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))
+}
+
 // This `Hash` implementation is safe since it retains the property
 // `k1 == k2 ⇒ hash(k1) == hash(k2)`.
 impl Hash for PublicKey {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        match self {
-            PublicKey::ED25519(public_key) => {
-                state.write_u8(0u8);
-                state.write(&public_key.0);
-            }
-            PublicKey::SECP256K1(public_key) => {
-                state.write_u8(1u8);
-                state.write(&public_key.0);
-            }
-        }
+        state.write_u8(self.key_type() as u8);
+        state.write(self.key_data());
     }
 }
 
 impl Display for PublicKey {
     fn fmt(&self, fmt: &mut Formatter) -> std::fmt::Result {
-        let (key_type, key_data) = match self {
-            PublicKey::ED25519(public_key) => (KeyType::ED25519, &public_key.0[..]),
-            PublicKey::SECP256K1(public_key) => (KeyType::SECP256K1, &public_key.0[..]),
-        };
-        write!(fmt, "{}:{}", key_type, Bs58(key_data))
+        match self {
+            PublicKey::MULTISIG(public_key) => {
+                write!(fmt, "{}:{:?}", KeyType::MULTISIG, public_key)
+            }
+            _ => write!(fmt, "{}:{}", self.key_type(), Bs58(self.key_data())),
+        }
     }
 }
 
@@ -208,31 +376,16 @@ impl Debug for PublicKey {
 
 impl BorshSerialize for PublicKey {
     fn serialize<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
-        match self {
-            PublicKey::ED25519(public_key) => {
-                BorshSerialize::serialize(&0u8, writer)?;
-                writer.write_all(&public_key.0)?;
-            }
-            PublicKey::SECP256K1(public_key) => {
-                BorshSerialize::serialize(&1u8, writer)?;
-                writer.write_all(&public_key.0)?;
-            }
-        }
-        Ok(())
+        BorshSerialize::serialize(&(self.key_type() as u8), writer)?;
+        writer.write_all(self.key_data())
     }
 }
 
 impl BorshDeserialize for PublicKey {
     fn deserialize_reader<R: Read>(rd: &mut R) -> std::io::Result<Self> {
-        let key_type = KeyType::try_from(u8::deserialize_reader(rd)?)
-            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
-        match key_type {
-            KeyType::ED25519 => {
-                Ok(PublicKey::ED25519(ED25519PublicKey(BorshDeserialize::deserialize_reader(rd)?)))
-            }
-            KeyType::SECP256K1 => Ok(PublicKey::SECP256K1(Secp256K1PublicKey(
-                BorshDeserialize::deserialize_reader(rd)?,
-            ))),
+        match read_key_type(rd)? {
+            KeyType::MULTISIG => Ok(PublicKey::MULTISIG(MultisigPublicKey::deserialize_data(rd)?)),
+            key_type => PublicKey::deserialize_single(key_type, rd),
         }
     }
 }
@@ -268,6 +421,13 @@ impl FromStr for PublicKey {
         Ok(match key_type {
             KeyType::ED25519 => Self::ED25519(ED25519PublicKey(decode_bs58(key_data)?)),
             KeyType::SECP256K1 => Self::SECP256K1(Secp256K1PublicKey(decode_bs58(key_data)?)),
+            KeyType::SECP256R1 => Self::SECP256R1(Secp256R1PublicKey(decode_bs58(key_data)?)),
+            KeyType::MULTISIG => {
+                let data = bs58::decode(key_data)
+                    .into_vec()
+                    .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
+                Self::MULTISIG(MultisigPublicKey::from_data(&data)?)
+            }
         })
     }
 }
@@ -284,6 +444,18 @@ impl From<Secp256K1PublicKey> for PublicKey {
     }
 }
 
+impl From<Secp256R1PublicKey> for PublicKey {
+    fn from(secp256r1: Secp256R1PublicKey) -> Self {
+        Self::SECP256R1(secp256r1)
+    }
+}
+
+impl From<MultisigPublicKey> for PublicKey {
+    fn from(multisig: MultisigPublicKey) -> Self {
+        Self::MULTISIG(multisig)
+    }
+}
+
 #[derive(Clone, Eq)]
 // This is actually a keypair, because ed25519_dalek api only has keypair.sign
 // From ed25519_dalek doc: The first SECRET_KEY_LENGTH of bytes is the SecretKey
@@ -304,9 +476,13 @@ impl std::fmt::Debug for ED25519SecretKey {
 
 /// Secret key container supporting different curves.
 #[derive(Clone, Eq, PartialEq, Debug)]
+///
+/// There is no secret key for multisig keys. Multisig signatures are combined
+/// from the signatures of the component keys with `MultisigSignature::new`.
 pub enum SecretKey {
     ED25519(ED25519SecretKey),
     SECP256K1(secp256k1::SecretKey),
+    SECP256R1(p256::ecdsa::SigningKey),
 }
 
 impl SecretKey {
@@ -314,20 +490,28 @@ impl SecretKey {
         match self {
             SecretKey::ED25519(_) => KeyType::ED25519,
             SecretKey::SECP256K1(_) => KeyType::SECP256K1,
+            SecretKey::SECP256R1(_) => KeyType::SECP256R1,
         }
     }
 
+    /// Generates a new random secret key.
+    ///
+    /// Fails for `KeyType::MULTISIG`: there is no multisig secret key, a
+    /// multisig public key is combined from the public keys of other key types
+    /// with `MultisigPublicKey::new`.
     #[cfg(feature = "rand")]
-    pub fn from_random(key_type: KeyType) -> SecretKey {
+    pub fn from_random(key_type: KeyType) -> Result<SecretKey, crate::errors::SecretKeyTypeError> {
         use secp256k1::rand::rngs::OsRng;
 
-        match key_type {
+        Ok(match key_type {
             KeyType::ED25519 => {
                 let keypair = ed25519_dalek::SigningKey::generate(&mut OsRng);
                 SecretKey::ED25519(ED25519SecretKey(keypair.to_keypair_bytes()))
             }
             KeyType::SECP256K1 => SecretKey::SECP256K1(secp256k1::SecretKey::new(&mut OsRng)),
-        }
+            KeyType::SECP256R1 => SecretKey::SECP256R1(p256::ecdsa::SigningKey::random(&mut OsRng)),
+            KeyType::MULTISIG => return Err(crate::errors::SecretKeyTypeError { key_type }),
+        })
     }
 
     pub fn sign(&self, data: &[u8]) -> Signature {
@@ -348,6 +532,16 @@ impl SecretKey {
                 buf[64] = rec_id.to_i32() as u8;
                 Signature::SECP256K1(Secp256K1Signature(buf))
             }
+
+            SecretKey::SECP256R1(secret_key) => {
+                let signature: p256::ecdsa::Signature =
+                    p256::ecdsa::signature::Signer::sign(secret_key, data);
+                // Verification only accepts the low-S form, see `Signature::verify`.
+                let signature = signature.normalize_s().unwrap_or(signature);
+                let mut buf = [0; SECP256R1_SIGNATURE_LENGTH];
+                buf.copy_from_slice(&signature.to_bytes());
+                Signature::SECP256R1(Secp256R1Signature(buf))
+            }
         }
     }
 
@@ -363,22 +557,34 @@ impl SecretKey {
                 public_key.0.copy_from_slice(&serialized[1..65]);
                 PublicKey::SECP256K1(public_key)
             }
+            SecretKey::SECP256R1(secret_key) => {
+                let point = secret_key.verifying_key().to_encoded_point(true);
+                PublicKey::SECP256R1(
+                    Secp256R1PublicKey::try_from(point.as_bytes())
+                        .expect("compressed P-256 point has 33 bytes"),
+                )
+            }
         }
     }
 
     pub fn unwrap_as_ed25519(&self) -> &ED25519SecretKey {
         match self {
             SecretKey::ED25519(key) => key,
-            SecretKey::SECP256K1(_) => panic!(),
+            _ => panic!(),
         }
     }
 }
 
 impl std::fmt::Display for SecretKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        let buf;
         let (key_type, key_data) = match self {
             SecretKey::ED25519(secret_key) => (KeyType::ED25519, &secret_key.0[..]),
             SecretKey::SECP256K1(secret_key) => (KeyType::SECP256K1, &secret_key[..]),
+            SecretKey::SECP256R1(secret_key) => {
+                buf = secret_key.to_bytes();
+                (KeyType::SECP256R1, &buf[..])
+            }
         };
         write!(f, "{}:{}", key_type, Bs58(key_data))
     }
@@ -397,6 +603,17 @@ impl FromStr for SecretKey {
                     .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
                 Self::SECP256K1(sk)
             }
+            KeyType::SECP256R1 => {
+                let data = decode_bs58::<32>(key_data)?;
+                let sk = p256::ecdsa::SigningKey::from_slice(&data)
+                    .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
+                Self::SECP256R1(sk)
+            }
+            KeyType::MULTISIG => {
+                return Err(Self::Err::InvalidData {
+                    error_message: "multisig keys have no secret key".to_string(),
+                });
+            }
         })
     }
 }
@@ -499,11 +716,129 @@ impl Debug for Secp256K1Signature {
     }
 }
 
+const SECP256R1_SIGNATURE_LENGTH: usize = 64;
+
+/// P-256 ECDSA signature in the `r || s` form, over the SHA-256 digest of the
+/// signed data.
+#[derive(Clone, Eq, PartialEq, Hash, derive_more::From, derive_more::Into, ProtocolSchema)]
+pub struct Secp256R1Signature([u8; SECP256R1_SIGNATURE_LENGTH]);
+
+impl TryFrom<&[u8]> for Secp256R1Signature {
+    type Error = crate::errors::ParseSignatureError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        Ok(Self(data.try_into().map_err(|_| Self::Error::InvalidLength {
+            expected_length: SECP256R1_SIGNATURE_LENGTH,
+            received_length: data.len(),
+        })?))
+    }
+}
+
+impl Debug for Secp256R1Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        Display::fmt(&Bs58(&self.0), f)
+    }
+}
+
+/// Signature of a multisig key, made of the signatures of some of its component
+/// keys, each given together with the index of the key in the multisig key.
+///
+/// Indices must be strictly increasing, so every key signs at most once, and
+/// component signatures can't be multisig signatures themselves.
+#[derive(Clone, Eq, PartialEq, Hash, ProtocolSchema)]
+pub struct MultisigSignature {
+    signatures: Vec<(u8, Signature)>,
+}
+
+impl MultisigSignature {
+    pub fn new(
+        signatures: Vec<(u8, Signature)>,
+    ) -> Result<Self, crate::errors::ParseSignatureError> {
+        let invalid = |error_message: &str| crate::errors::ParseSignatureError::InvalidData {
+            error_message: error_message.to_string(),
+        };
+        if signatures.is_empty() || signatures.len() > MAX_MULTISIG_KEYS {
+            return Err(invalid("multisig signature must have between 1 and 16 signatures"));
+        }
+        if signatures.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(invalid("multisig signature indices must be strictly increasing"));
+        }
+        if signatures.iter().any(|(_, signature)| matches!(signature, Signature::MULTISIG(_))) {
+            return Err(invalid("multisig signatures can't be nested"));
+        }
+        Ok(Self { signatures })
+    }
+
+    pub fn signatures(&self) -> &[(u8, Signature)] {
+        &self.signatures
+    }
+
+    /// Checks that exactly `threshold` distinct component keys signed the data.
+    fn verify(&self, data: &[u8], public_key: &MultisigPublicKey) -> bool {
+        self.signatures.len() == usize::from(public_key.threshold)
+            && self.signatures.iter().all(|(index, signature)| {
+                match public_key.keys.get(usize::from(*index)) {
+                    Some(key) => signature.verify(data, key),
+                    None => false,
+                }
+            })
+    }
+
+    fn from_data(mut data: &[u8]) -> Result<Self, crate::errors::ParseSignatureError> {
+        let signature = Self::deserialize_data(&mut data).map_err(|err| {
+            crate::errors::ParseSignatureError::InvalidData { error_message: err.to_string() }
+        })?;
+        if !data.is_empty() {
+            return Err(crate::errors::ParseSignatureError::InvalidData {
+                error_message: "unexpected trailing bytes in multisig signature".to_string(),
+            });
+        }
+        Ok(signature)
+    }
+
+    fn to_data(&self) -> Vec<u8> {
+        borsh::to_vec(&self.signatures).expect("writing to a vector can't fail")
+    }
+
+    /// Reads the component signatures without recursing into `Signature`
+    /// deserialization, see `MultisigPublicKey::deserialize_data`.
+    fn deserialize_data<R: Read>(rd: &mut R) -> std::io::Result<Self> {
+        let len = u32::deserialize_reader(rd)? as usize;
+        if len > MAX_MULTISIG_KEYS {
+            return Err(Error::new(ErrorKind::InvalidData, "too many multisig signatures"));
+        }
+        let signatures = (0..len)
+            .map(|_| {
+                let index = u8::deserialize_reader(rd)?;
+                let signature = match read_key_type(rd)? {
+                    KeyType::MULTISIG => {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            "multisig signatures can't be nested",
+                        ));
+                    }
+                    key_type => Signature::deserialize_single(key_type, rd)?,
+                };
+                Ok((index, signature))
+            })
+            .collect::<std::io::Result<Vec<_>>>()?;
+        Self::new(signatures).map_err(|err| Error::new(ErrorKind::InvalidData, err))
+    }
+}
+
+impl Debug for MultisigSignature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        f.write_str(&bs58::encode(self.to_data()).into_string())
+    }
+}
+
 /// Signature container supporting different curves.
 #[derive(Clone, PartialEq, Eq, ProtocolSchema)]
 pub enum Signature {
     ED25519(ed25519_dalek::Signature),
     SECP256K1(Secp256K1Signature),
+    SECP256R1(Secp256R1Signature),
+    MULTISIG(MultisigSignature),
 }
 
 // This `Hash` implementation is safe since it retains the property
@@ -513,6 +848,8 @@ impl Hash for Signature {
         match self {
             Signature::ED25519(sig) => sig.to_bytes().hash(state),
             Signature::SECP256K1(sig) => sig.hash(state),
+            Signature::SECP256R1(sig) => sig.hash(state),
+            Signature::MULTISIG(sig) => sig.hash(state),
         };
     }
 }
@@ -538,6 +875,16 @@ impl Signature {
                     },
                 )?))
             }
+            KeyType::SECP256R1 => {
+                Ok(Signature::SECP256R1(Secp256R1Signature::try_from(signature_data).map_err(
+                    |_| crate::errors::ParseSignatureError::InvalidData {
+                        error_message: "invalid Secp256r1 signature length".to_string(),
+                    },
+                )?))
+            }
+            KeyType::MULTISIG => {
+                Ok(Signature::MULTISIG(MultisigSignature::from_data(signature_data)?))
+            }
         }
     }
 
@@ -582,6 +929,24 @@ impl Signature {
                 };
                 SECP256K1.verify_ecdsa(&message, &sig, &pub_key).is_ok()
             }
+            (Signature::SECP256R1(signature), PublicKey::SECP256R1(public_key)) => {
+                let Ok(public_key) = p256::ecdsa::VerifyingKey::from_sec1_bytes(&public_key.0)
+                else {
+                    return false;
+                };
+                let Ok(signature) = p256::ecdsa::Signature::from_slice(&signature.0) else {
+                    return false;
+                };
+                // Reject the high-S form, otherwise anyone could produce a second
+                // valid signature for the same data (ECDSA malleability).
+                if signature.normalize_s().is_some() {
+                    return false;
+                }
+                p256::ecdsa::signature::Verifier::verify(&public_key, data, &signature).is_ok()
+            }
+            (Signature::MULTISIG(signature), PublicKey::MULTISIG(public_key)) => {
+                signature.verify(data, public_key)
+            }
             _ => false,
         }
     }
@@ -590,6 +955,37 @@ impl Signature {
         match self {
             Signature::ED25519(_) => KeyType::ED25519,
             Signature::SECP256K1(_) => KeyType::SECP256K1,
+            Signature::SECP256R1(_) => KeyType::SECP256R1,
+            Signature::MULTISIG(_) => KeyType::MULTISIG,
+        }
+    }
+
+    /// Reads the data of a signature of the given type, which must not be
+    /// multisig.
+    fn deserialize_single<R: Read>(key_type: KeyType, rd: &mut R) -> std::io::Result<Self> {
+        match key_type {
+            KeyType::ED25519 => {
+                let array: [u8; ed25519_dalek::SIGNATURE_LENGTH] =
+                    BorshDeserialize::deserialize_reader(rd)?;
+                // Sanity-check that was performed by ed25519-dalek in from_bytes before version 2,
+                // but was removed with version 2. It is not actually any good a check, but we have
+                // it here in case we need to keep backward compatibility. Maybe this check is not
+                // actually required, but please think carefully before removing it.
+                if array[ed25519_dalek::SIGNATURE_LENGTH - 1] & 0b1110_0000 != 0 {
+                    return Err(Error::new(ErrorKind::InvalidData, "signature error"));
+                }
+                Ok(Signature::ED25519(ed25519_dalek::Signature::from_bytes(&array)))
+            }
+            KeyType::SECP256K1 => {
+                let array: [u8; 65] = BorshDeserialize::deserialize_reader(rd)?;
+                Ok(Signature::SECP256K1(Secp256K1Signature(array)))
+            }
+            KeyType::SECP256R1 => {
+                let array: [u8; SECP256R1_SIGNATURE_LENGTH] =
+                    BorshDeserialize::deserialize_reader(rd)?;
+                Ok(Signature::SECP256R1(Secp256R1Signature(array)))
+            }
+            KeyType::MULTISIG => unreachable!("multisig signatures are read separately"),
         }
     }
 }
@@ -611,6 +1007,14 @@ impl BorshSerialize for Signature {
                 BorshSerialize::serialize(&1u8, writer)?;
                 writer.write_all(&signature.0)?;
             }
+            Signature::SECP256R1(signature) => {
+                BorshSerialize::serialize(&2u8, writer)?;
+                writer.write_all(&signature.0)?;
+            }
+            Signature::MULTISIG(signature) => {
+                BorshSerialize::serialize(&3u8, writer)?;
+                BorshSerialize::serialize(&signature.signatures, writer)?;
+            }
         }
         Ok(())
     }
@@ -618,25 +1022,9 @@ impl BorshSerialize for Signature {
 
 impl BorshDeserialize for Signature {
     fn deserialize_reader<R: Read>(rd: &mut R) -> std::io::Result<Self> {
-        let key_type = KeyType::try_from(u8::deserialize_reader(rd)?)
-            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
-        match key_type {
-            KeyType::ED25519 => {
-                let array: [u8; ed25519_dalek::SIGNATURE_LENGTH] =
-                    BorshDeserialize::deserialize_reader(rd)?;
-                // Sanity-check that was performed by ed25519-dalek in from_bytes before version 2,
-                // but was removed with version 2. It is not actually any good a check, but we have
-                // it here in case we need to keep backward compatibility. Maybe this check is not
-                // actually required, but please think carefully before removing it.
-                if array[ed25519_dalek::SIGNATURE_LENGTH - 1] & 0b1110_0000 != 0 {
-                    return Err(Error::new(ErrorKind::InvalidData, "signature error"));
-                }
-                Ok(Signature::ED25519(ed25519_dalek::Signature::from_bytes(&array)))
-            }
-            KeyType::SECP256K1 => {
-                let array: [u8; 65] = BorshDeserialize::deserialize_reader(rd)?;
-                Ok(Signature::SECP256K1(Secp256K1Signature(array)))
-            }
+        match read_key_type(rd)? {
+            KeyType::MULTISIG => Ok(Signature::MULTISIG(MultisigSignature::deserialize_data(rd)?)),
+            key_type => Signature::deserialize_single(key_type, rd),
         }
     }
 }
@@ -650,6 +1038,10 @@ impl Display for Signature {
                 (KeyType::ED25519, &buf[..])
             }
             Signature::SECP256K1(signature) => (KeyType::SECP256K1, &signature.0[..]),
+            Signature::SECP256R1(signature) => (KeyType::SECP256R1, &signature.0[..]),
+            Signature::MULTISIG(signature) => {
+                return write!(f, "{}:{:?}", KeyType::MULTISIG, signature);
+            }
         };
         write!(f, "{}:{}", key_type, Bs58(&key_data))
     }
@@ -685,6 +1077,13 @@ impl FromStr for Signature {
                 Signature::ED25519(sig)
             }
             KeyType::SECP256K1 => Signature::SECP256K1(Secp256K1Signature(decode_bs58(sig_data)?)),
+            KeyType::SECP256R1 => Signature::SECP256R1(Secp256R1Signature(decode_bs58(sig_data)?)),
+            KeyType::MULTISIG => {
+                let data = bs58::decode(sig_data)
+                    .into_vec()
+                    .map_err(|err| Self::Err::InvalidData { error_message: err.to_string() })?;
+                Signature::MULTISIG(MultisigSignature::from_data(&data)?)
+            }
         })
     }
 }
@@ -776,19 +1175,20 @@ impl std::convert::From<DecodeBs58Error> for crate::errors::ParseSignatureError
 
 #[cfg(test)]
 mod tests {
-    use super::{KeyType, PublicKey, SecretKey, Signature};
+    use super::{KeyType, MultisigPublicKey, MultisigSignature, PublicKey, SecretKey, Signature};
 
     #[cfg(feature = "rand")]
     #[test]
     fn test_sign_verify() {
-        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
-            let secret_key = SecretKey::from_random(key_type);
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1, KeyType::SECP256R1] {
+            let secret_key = SecretKey::from_random(key_type).unwrap();
             let public_key = secret_key.public_key();
             use sha2::Digest;
             let data = sha2::Sha256::digest(b"123").to_vec();
             let signature = secret_key.sign(&data);
             assert!(signature.verify(&data, &public_key));
         }
+        assert!(SecretKey::from_random(KeyType::MULTISIG).is_err());
     }
 
     #[test]
@@ -802,6 +1202,13 @@ mod tests {
                     KeyType::SECP256K1 => {
                         Signature::from_parts(KeyType::SECP256K1, &sign[..65]).unwrap()
                     }
+                    KeyType::SECP256R1 => {
+                        Signature::from_parts(KeyType::SECP256R1, &sign[..64]).unwrap()
+                    }
+                    KeyType::MULTISIG => match Signature::from_parts(KeyType::MULTISIG, sign) {
+                        Ok(signature) => signature,
+                        Err(_) => return,
+                    },
                 };
                 let _ = signature.verify(&data, &public_key);
             },
@@ -877,7 +1284,7 @@ mod tests {
         use sha2::Digest;
 
         let data = sha2::Sha256::digest(b"123").to_vec();
-        for key_type in [KeyType::ED25519, KeyType::SECP256K1] {
+        for key_type in [KeyType::ED25519, KeyType::SECP256K1, KeyType::SECP256R1] {
             let sk = SecretKey::from_seed(key_type, "test");
             let pk = sk.public_key();
             let bytes = borsh::to_vec(&pk).unwrap();
@@ -892,6 +1299,88 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_secp256r1_rejects_high_s() {
+        let sk = SecretKey::from_seed(KeyType::SECP256R1, "test");
+        let pk = sk.public_key();
+        let Signature::SECP256R1(signature) = sk.sign(b"123") else { unreachable!() };
+        let signature = p256::ecdsa::Signature::from_slice(&signature.0).unwrap();
+        assert!(signature.normalize_s().is_none());
+
+        // Negating `s` gives the other signature valid for the same data.
+        let (r, s) = signature.split_scalars();
+        let high_s = p256::ecdsa::Signature::from_scalars(r, -*s).unwrap();
+        let high_s = Signature::from_parts(KeyType::SECP256R1, &high_s.to_bytes()).unwrap();
+        assert!(!high_s.verify(b"123", &pk));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_multisig_verify() {
+        use borsh::BorshDeserialize;
+        use sha2::Digest;
+
+        let data = sha2::Sha256::digest(b"123").to_vec();
+        let sks: Vec<_> = [KeyType::ED25519, KeyType::SECP256K1, KeyType::SECP256R1]
+            .into_iter()
+            .map(|key_type| SecretKey::from_seed(key_type, "test"))
+            .collect();
+        let pk = PublicKey::MULTISIG(
+            MultisigPublicKey::new(2, sks.iter().map(SecretKey::public_key).collect()).unwrap(),
+        );
+        let multisig = |indices: &[u8]| {
+            let signatures =
+                indices.iter().map(|&i| (i, sks[i as usize].sign(&data))).collect::<Vec<_>>();
+            Signature::MULTISIG(MultisigSignature::new(signatures).unwrap())
+        };
+
+        assert!(multisig(&[0, 2]).verify(&data, &pk));
+        assert!(multisig(&[1, 2]).verify(&data, &pk));
+        assert!(!multisig(&[1]).verify(&data, &pk));
+        assert!(!multisig(&[0, 1, 2]).verify(&data, &pk));
+        assert!(!multisig(&[0, 2]).verify(b"other data", &pk));
+
+        // Signature by a key which is not at the given index.
+        let wrong_index =
+            MultisigSignature::new(vec![(0, sks[0].sign(&data)), (1, sks[2].sign(&data))]);
+        assert!(!Signature::MULTISIG(wrong_index.unwrap()).verify(&data, &pk));
+        // The same key can't sign twice.
+        assert!(
+            MultisigSignature::new(vec![(0, sks[0].sign(&data)), (0, sks[0].sign(&data))]).is_err()
+        );
+
+        let signature = multisig(&[0, 2]);
+        let bytes = borsh::to_vec(&signature).unwrap();
+        assert_eq!(Signature::try_from_slice(&bytes).unwrap(), signature);
+        assert_eq!(signature.to_string().parse::<Signature>().unwrap(), signature);
+        let bytes = borsh::to_vec(&pk).unwrap();
+        assert_eq!(bytes.len(), pk.len());
+        assert_eq!(PublicKey::try_from_slice(&bytes).unwrap(), pk);
+        assert_eq!(
+            serde_json::from_str::<PublicKey>(&serde_json::to_string(&pk).unwrap()).unwrap(),
+            pk
+        );
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_multisig_invalid_keys() {
+        use borsh::BorshDeserialize;
+
+        let key = PublicKey::from_seed(KeyType::ED25519, "test");
+        assert!(MultisigPublicKey::new(0, vec![key.clone()]).is_err());
+        assert!(MultisigPublicKey::new(2, vec![key.clone()]).is_err());
+        assert!(MultisigPublicKey::new(1, vec![key.clone(), key.clone()]).is_err());
+        assert!(MultisigPublicKey::new(1, vec![]).is_err());
+
+        // Nested multisig keys are rejected when deserializing, too.
+        let inner = PublicKey::MULTISIG(MultisigPublicKey::new(1, vec![key]).unwrap());
+        let mut bytes = vec![3u8, 1];
+        borsh::to_writer(&mut bytes, &vec![inner]).unwrap();
+        assert!(PublicKey::try_from_slice(&bytes).is_err());
+    }
+
     #[test]
     fn test_invalid_data() {
         // cspell:disable-next-line
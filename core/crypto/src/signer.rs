@@ -110,6 +110,7 @@ pub struct InMemorySigner {
 }
 
 impl InMemorySigner {
+    /// Panics for `KeyType::MULTISIG`: there is no multisig secret key.
     #[cfg(feature = "rand")]
     pub fn from_seed(account_id: AccountId, key_type: KeyType, seed: &str) -> Signer {
         let secret_key = SecretKey::from_seed(key_type, seed);
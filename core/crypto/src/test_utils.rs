@@ -22,7 +22,20 @@ fn secp256k1_secret_key_from_seed(seed: &str) -> secp256k1::SecretKey {
     secp256k1::SecretKey::new(&mut rng)
 }
 
+#[cfg(feature = "rand")]
+fn secp256r1_secret_key_from_seed(seed: &str) -> p256::ecdsa::SigningKey {
+    use secp256k1::rand::SeedableRng;
+
+    let seed_bytes = seed.as_bytes();
+    let len = std::cmp::min(32, seed_bytes.len());
+    let mut seed: [u8; 32] = [b' '; 32];
+    seed[..len].copy_from_slice(&seed_bytes[..len]);
+    let mut rng = secp256k1::rand::rngs::StdRng::from_seed(seed);
+    p256::ecdsa::SigningKey::random(&mut rng)
+}
+
 impl PublicKey {
+    /// Panics for `KeyType::MULTISIG`: there is no multisig secret key.
     #[cfg(feature = "rand")]
     pub fn from_seed(key_type: KeyType, seed: &str) -> Self {
         match key_type {
@@ -36,11 +49,14 @@ impl PublicKey {
                 let secret_key = SecretKey::SECP256K1(secp256k1_secret_key_from_seed(seed));
                 PublicKey::SECP256K1(secret_key.public_key().unwrap_as_secp256k1().clone())
             }
+            KeyType::SECP256R1 => SecretKey::from_seed(key_type, seed).public_key(),
+            KeyType::MULTISIG => panic!("multisig keys are combined from other keys"),
         }
     }
 }
 
 impl SecretKey {
+    /// Panics for `KeyType::MULTISIG`: there is no multisig secret key.
     #[cfg(feature = "rand")]
     pub fn from_seed(key_type: KeyType, seed: &str) -> Self {
         match key_type {
@@ -49,6 +65,8 @@ impl SecretKey {
                 SecretKey::ED25519(crate::signature::ED25519SecretKey(keypair.to_keypair_bytes()))
             }
             KeyType::SECP256K1 => SecretKey::SECP256K1(secp256k1_secret_key_from_seed(seed)),
+            KeyType::SECP256R1 => SecretKey::SECP256R1(secp256r1_secret_key_from_seed(seed)),
+            KeyType::MULTISIG => panic!("multisig keys are combined from other keys"),
         }
     }
 }
@@ -66,9 +84,13 @@ impl Signature {
 }
 
 impl InMemorySigner {
+    /// Fails for `KeyType::MULTISIG`, see `SecretKey::from_random`.
     #[cfg(feature = "rand")]
-    pub fn from_random(account_id: near_account_id::AccountId, key_type: KeyType) -> Self {
-        let secret_key = SecretKey::from_random(key_type);
-        Self { account_id, public_key: secret_key.public_key(), secret_key }
+    pub fn from_random(
+        account_id: near_account_id::AccountId,
+        key_type: KeyType,
+    ) -> Result<Self, crate::SecretKeyTypeError> {
+        let secret_key = SecretKey::from_random(key_type)?;
+        Ok(Self { account_id, public_key: secret_key.public_key(), secret_key })
     }
 }
@@ -28,6 +28,10 @@ pub struct UpdatableConfigs {
     pub client_config: Option<UpdatableClientConfig>,
     /// Validator key hot loaded from file.
     pub validator_signer: UpdatableValidatorSigner,
+    /// Contents of the `network` section of `config.json` corresponding to the mutable fields
+    /// of `NetworkConfig`. Kept as JSON, so that this crate doesn't depend on `near-network`,
+    /// which parses it as `near_network::config_json::UpdatableNetworkConfig`.
+    pub network_config: Option<serde_json::Value>,
 }
 
 /// Pushes the updates to listeners.
@@ -308,6 +308,8 @@ pub enum ActionCosts {
     deploy_global_contract_byte = 17,
     use_global_contract_base = 18,
     use_global_contract_byte = 19,
+    secp256r1_signature_verification = 20,
+    multisig_signature_verification = 21,
 }
 
 impl ExtCosts {
@@ -587,6 +589,16 @@ impl RuntimeFeesConfig {
                     send_not_sir: 47_683_715,
                     execution: 64_572_944,
                 },
+                ActionCosts::secp256r1_signature_verification => Fee {
+                    send_sir: 300_000_000_000,
+                    send_not_sir: 300_000_000_000,
+                    execution: 300_000_000_000,
+                },
+                ActionCosts::multisig_signature_verification => Fee {
+                    send_sir: 300_000_000_000,
+                    send_not_sir: 300_000_000_000,
+                    execution: 300_000_000_000,
+                },
             },
         }
     }
@@ -235,6 +235,11 @@ pub enum Parameter {
     ActionUseGlobalContract,
     ActionUseGlobalContractPerIdentifierByte,
     SaturatingFloatToInt,
+
+    // Extended access key types
+    ExtendedAccessKeyTypes,
+    ActionSecp256r1SignatureVerification,
+    ActionMultisigSignatureVerification,
 }
 
 #[derive(
@@ -271,6 +276,8 @@ pub enum FeeParameter {
     ActionDeployGlobalContractPerByte,
     ActionUseGlobalContract,
     ActionUseGlobalContractPerIdentifierByte,
+    ActionSecp256r1SignatureVerification,
+    ActionMultisigSignatureVerification,
 }
 
 impl Parameter {
@@ -336,6 +343,12 @@ impl From<ActionCosts> for FeeParameter {
             ActionCosts::deploy_global_contract_byte => Self::ActionDeployGlobalContractPerByte,
             ActionCosts::use_global_contract_base => Self::ActionUseGlobalContract,
             ActionCosts::use_global_contract_byte => Self::ActionUseGlobalContractPerIdentifierByte,
+            ActionCosts::secp256r1_signature_verification => {
+                Self::ActionSecp256r1SignatureVerification
+            }
+            ActionCosts::multisig_signature_verification => {
+                Self::ActionMultisigSignatureVerification
+            }
         }
     }
 }
@@ -329,6 +329,7 @@ impl TryFrom<&ParameterTable> for RuntimeConfig {
                 },
                 implicit_account_creation: params.get(Parameter::ImplicitAccountCreation)?,
                 eth_implicit_accounts: params.get(Parameter::EthImplicitAccounts)?,
+                extended_access_key_types: params.get(Parameter::ExtendedAccessKeyTypes)?,
             }),
             account_creation_config: AccountCreationConfig {
                 min_allowed_top_level_account_length: params
@@ -225,6 +225,8 @@ pub struct VMConfigView {
     pub implicit_account_creation: bool,
     /// See [VMConfig::eth_implicit_accounts](crate::vm::Config::eth_implicit_accounts).
     pub eth_implicit_accounts: bool,
+    /// See [VMConfig::extended_access_key_types](crate::vm::Config::extended_access_key_types).
+    pub extended_access_key_types: bool,
 
     /// Describes limits for VM and Runtime.
     ///
@@ -246,6 +248,7 @@ impl From<crate::vm::Config> for VMConfigView {
             implicit_account_creation: config.implicit_account_creation,
             vm_kind: config.vm_kind,
             eth_implicit_accounts: config.eth_implicit_accounts,
+            extended_access_key_types: config.extended_access_key_types,
             saturating_float_to_int: config.saturating_float_to_int,
         }
     }
@@ -264,6 +267,7 @@ impl From<VMConfigView> for crate::vm::Config {
             implicit_account_creation: view.implicit_account_creation,
             vm_kind: view.vm_kind,
             eth_implicit_accounts: view.eth_implicit_accounts,
+            extended_access_key_types: view.extended_access_key_types,
             saturating_float_to_int: view.saturating_float_to_int,
         }
     }
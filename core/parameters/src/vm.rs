@@ -165,6 +165,10 @@ pub struct Config {
     /// Enable the `EthImplicitAccounts` protocol feature.
     pub eth_implicit_accounts: bool,
 
+    /// Enable the `ExtendedAccessKeyTypes` protocol feature, which lets host
+    /// functions accept SECP256R1 and multisig public keys.
+    pub extended_access_key_types: bool,
+
     /// Whether to discard custom sections.
     pub discard_custom_sections: bool,
 
@@ -198,6 +202,7 @@ impl Config {
     pub fn enable_all_features(&mut self) {
         self.eth_implicit_accounts = true;
         self.implicit_account_creation = true;
+        self.extended_access_key_types = true;
     }
 }
 
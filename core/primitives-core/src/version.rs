@@ -305,6 +305,16 @@ pub enum ProtocolFeature {
     /// receipts.
     ReducedGasRefunds,
     SaturatingFloatToInt,
+    /// Allow SECP256R1 and multisig access keys, so that transactions can be
+    /// signed by passkeys and by a threshold of several keys.
+    ExtendedAccessKeyTypes,
+    /// Encrypt peer connections with the Noise protocol, if both peers enable it.
+    /// Only gates the negotiation in the network handshake.
+    EncryptedPeerTransport,
+    /// Gossip of transactions among the upcoming chunk producers. Only gates the
+    /// `TxGossip*` network messages, which are sent only over connections negotiated
+    /// at a protocol version supporting them.
+    TxGossip,
 }
 
 impl ProtocolFeature {
@@ -406,6 +416,9 @@ impl ProtocolFeature {
             // that always enables this for mocknet (see config_mocknet function).
             ProtocolFeature::ShuffleShardAssignments => 143,
             ProtocolFeature::ExcludeExistingCodeFromWitnessForCodeLen => 148,
+            ProtocolFeature::ExtendedAccessKeyTypes => 149,
+            ProtocolFeature::EncryptedPeerTransport => 150,
+            ProtocolFeature::TxGossip => 151,
             // Place features that are not yet in Nightly below this line.
         }
     }
@@ -425,7 +438,7 @@ pub const MIN_SUPPORTED_PROTOCOL_VERSION: ProtocolVersion = 75;
 const STABLE_PROTOCOL_VERSION: ProtocolVersion = 78;
 
 // On nightly, pick big enough version to support all features.
-const NIGHTLY_PROTOCOL_VERSION: ProtocolVersion = 149;
+const NIGHTLY_PROTOCOL_VERSION: ProtocolVersion = 151;
 
 /// Largest protocol version supported by the current binary.
 pub const PROTOCOL_VERSION: ProtocolVersion =
@@ -54,7 +54,8 @@ fn create_block() -> Block {
         1_000,
         &vec![],
     );
-    let signer = InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer =
+        InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap();
     Block::produce(
         PROTOCOL_VERSION,
         genesis.header(),
@@ -37,7 +37,11 @@ impl PeerId {
 impl PeerId {
     #[cfg(feature = "rand")]
     pub fn random() -> Self {
-        PeerId::new(near_crypto::SecretKey::from_random(near_crypto::KeyType::ED25519).public_key())
+        PeerId::new(
+            near_crypto::SecretKey::from_random(near_crypto::KeyType::ED25519)
+                .unwrap()
+                .public_key(),
+        )
     }
 }
 
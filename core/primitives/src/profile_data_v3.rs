@@ -352,6 +352,8 @@ mod test {
             deploy_global_contract_byte -> 1017
             use_global_contract_base -> 1018
             use_global_contract_byte -> 1019
+            secp256r1_signature_verification -> 1020
+            multisig_signature_verification -> 1021
             ------------------------------
         "#]]
         .assert_eq(&pretty_debug_str)
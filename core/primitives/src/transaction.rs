@@ -616,7 +616,7 @@ mod tests {
     #[test]
     fn test_verify_transaction() {
         let signer: Signer =
-            InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+            InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
         let transaction = Transaction::V0(TransactionV0 {
             signer_id: "test".parse().unwrap(),
             public_key: signer.public_key(),
@@ -100,9 +100,12 @@ pub struct InMemoryValidatorSigner {
 
 impl InMemoryValidatorSigner {
     #[cfg(feature = "rand")]
-    pub fn from_random(account_id: AccountId, key_type: KeyType) -> ValidatorSigner {
-        let signer = Arc::new(InMemorySigner::from_random(account_id.clone(), key_type).into());
-        ValidatorSigner::InMemory(Self { account_id, signer })
+    pub fn from_random(
+        account_id: AccountId,
+        key_type: KeyType,
+    ) -> Result<ValidatorSigner, near_crypto::SecretKeyTypeError> {
+        let signer = Arc::new(InMemorySigner::from_random(account_id.clone(), key_type)?.into());
+        Ok(ValidatorSigner::InMemory(Self { account_id, signer }))
     }
 
     #[cfg(feature = "rand")]
@@ -53,7 +53,7 @@ use near_schema_checker_lib::ProtocolSchema;
 use near_time::Utc;
 use serde_with::base64::Base64;
 use serde_with::serde_as;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::ops::Range;
 use std::sync::Arc;
@@ -263,6 +263,8 @@ pub struct KnownPeerStateView {
     pub first_seen: i64,
     pub last_seen: i64,
     pub last_attempt: Option<(i64, String)>,
+    /// Reputation score of the peer, rounded to an integer.
+    pub reputation: i64,
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
@@ -456,6 +458,20 @@ pub struct StateSyncStatusView {
     pub shard_sync_status: HashMap<ShardId, String>,
     pub download_tasks: Vec<String>,
     pub computation_tasks: Vec<String>,
+    pub host_stats: Vec<StateSyncHostStatsView>,
+}
+
+/// State part downloads from a snapshot host, as observed by the syncing node.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StateSyncHostStatsView {
+    pub peer_id: PeerId,
+    pub parts_downloaded: u64,
+    pub failures: u64,
+    pub bytes_downloaded: u64,
+    /// Moving average of the download throughput, if any part has been downloaded.
+    pub throughput_bytes_per_sec: Option<u64>,
+    /// Whether the host is queried only after the other hosts.
+    pub demoted: bool,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
@@ -593,6 +609,88 @@ pub struct ChainProcessingInfo {
     pub floating_chunks_info: Vec<ChunkProcessingInfo>,
 }
 
+/// A duty assigned to a validator at some height, with its outcome as observed by this node.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ValidatorDutyView {
+    /// Height of the block for block production, and the height the chunk was created for
+    /// for chunk production and endorsement.
+    pub height: BlockHeight,
+    pub duty: ValidatorDutyKind,
+    pub outcome: ValidatorDutyOutcome,
+    /// Likely reason for a missed duty, or a remark about a performed one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<ValidatorDutyReason>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ValidatorDutyKind {
+    BlockProduction,
+    ChunkProduction { shard_id: ShardId },
+    ChunkEndorsement { shard_id: ShardId },
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidatorDutyOutcome {
+    Done,
+    Missed,
+    /// The duty could not be performed because there was no chunk to endorse.
+    Skipped,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ValidatorDutyReason {
+    /// The node was syncing at the time of the duty.
+    NodeSyncing,
+    /// The node has no record of the duty, e.g. it was offline or restarted since.
+    NoLocalRecord,
+    /// Approvals for the height did not reach the doomslug threshold.
+    NotEnoughApprovals,
+    /// The block was produced but did not end up on the canonical chain.
+    BlockNotIncluded,
+    /// The previous block was waiting for missing chunk parts, so the chunk
+    /// could not be produced in time.
+    MissingChunkParts,
+    /// Producing the chunk locally failed with the given error.
+    ChunkProductionFailed { error: String },
+    /// The chunk was produced but the block producer did not include it.
+    ChunkNotIncluded,
+    /// The chunk was produced without transactions because the tx pool was empty.
+    EmptyTxPool,
+    /// The state witness for the chunk was never received.
+    WitnessNotReceived,
+    /// The state witness was received only after the block including the chunk.
+    LateWitness { delay_ms: u64 },
+    /// The witness was received in time, but the endorsement was not included
+    /// in the block, e.g. because validation failed or the endorsement was late.
+    EndorsementNotIncluded,
+}
+
+/// Block and chunk lifecycle events in the Chrome trace event format, which can be opened in
+/// Perfetto or `chrome://tracing`.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ChromeTraceView {
+    pub trace_events: Vec<ChromeTraceEventView>,
+    pub display_time_unit: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ChromeTraceEventView {
+    pub name: String,
+    pub cat: String,
+    /// Event phase: "X" for complete events, "i" for instant events and "M" for metadata.
+    pub ph: String,
+    /// Start of the event in microseconds since the unix epoch.
+    pub ts: u64,
+    /// Duration of a complete event in microseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dur: Option<u64>,
+    pub pid: u64,
+    pub tid: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<String, String>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 pub struct BlockProcessingInfo {
     pub height: BlockHeight,
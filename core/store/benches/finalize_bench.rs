@@ -85,7 +85,7 @@ fn benchmark_write_partial_encoded_chunk(bench: &mut Bencher) {
 }
 
 fn validator_signer() -> ValidatorSigner {
-    InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519)
+    InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap()
 }
 
 /// All receipts together are ~24MB
@@ -93,7 +93,7 @@ fn validator_signer() -> ValidatorSigner {
 /// 24 MB isn't the norm but certainly possible.
 fn create_benchmark_receipts() -> Vec<Receipt> {
     let account_id: AccountId = "test".parse().unwrap();
-    let signer = InMemorySigner::from_random(account_id.clone(), KeyType::ED25519);
+    let signer = InMemorySigner::from_random(account_id.clone(), KeyType::ED25519).unwrap();
     let action = Action::FunctionCall(Box::new(FunctionCallAction {
         args: vec![42u8; 2_000_000],
         method_name: "foo".to_owned(),
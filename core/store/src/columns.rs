@@ -322,6 +322,22 @@ pub enum DBCol {
     /// - *Rows*: BlockShardId (BlockHash || ShardId) - 40 bytes
     /// - *Column type*: `ChunkApplyStats`
     ChunkApplyStats,
+    /// Keys of the data which garbage collection keeps for the accounts chosen in the
+    /// retention policy, by the height of the block or chunk the data belongs to.
+    /// Used to delete the retained data once it expires.
+    /// - *Rows*: BlockHeight (big-endian, so that the rows are iterated by height)
+    /// - *Column type*: `Vec<RetainedKey>`
+    GCRetainedData,
+    /// Recent orphan blocks, persisted so that they survive a restart of the node.
+    /// Only written when `persist_orphans` is enabled in the client config.
+    /// - *Rows*: BlockHeight (big-endian) || BlockHash
+    /// - *Column type*: `Block`
+    OrphanBlocks,
+    /// Recent orphan chunk state witnesses, persisted so that they survive a restart of the node.
+    /// Only written when `persist_orphans` is enabled in the client config.
+    /// - *Rows*: BlockHeight (big-endian) || ShardId || EpochId
+    /// - *Column type*: `ChunkStateWitness`
+    OrphanChunkStateWitnesses,
 }
 
 /// Defines different logical parts of a db key.
@@ -489,11 +505,16 @@ impl DBCol {
             DBCol::ProcessedBlockHeights => false,
             // HeaderHashesByHeight is only needed for GC.
             DBCol::HeaderHashesByHeight => false,
+            // GCRetainedData is only needed for GC.
+            DBCol::GCRetainedData => false,
             // StateTransitionData is only needed to produce ChunkStateWitness
             DBCol::StateTransitionData => false,
             // LatestChunkStateWitnesses stores the last N observed witnesses, used only for debugging.
             DBCol::LatestChunkStateWitnesses => false,
             DBCol::LatestWitnessesByIndex => false,
+            // Orphans are only kept until they can be processed.
+            DBCol::OrphanBlocks => false,
+            DBCol::OrphanChunkStateWitnesses => false,
             // Deprecated.
             DBCol::_ReceiptIdToShardId => false,
             // This can be re-constructed from the Chunks column, so no need to store in Cold DB.
@@ -605,6 +626,11 @@ impl DBCol {
             DBCol::StateSyncHashes => &[DBKeyType::EpochId],
             DBCol::StateSyncNewChunks => &[DBKeyType::BlockHash],
             DBCol::ChunkApplyStats => &[DBKeyType::BlockHash, DBKeyType::ShardId],
+            DBCol::GCRetainedData => &[DBKeyType::BlockHeight],
+            DBCol::OrphanBlocks => &[DBKeyType::BlockHeight, DBKeyType::BlockHash],
+            DBCol::OrphanChunkStateWitnesses => {
+                &[DBKeyType::BlockHeight, DBKeyType::ShardId, DBKeyType::EpochId]
+            }
         }
     }
 }
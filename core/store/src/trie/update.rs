@@ -1,3 +1,4 @@
+pub use self::fork::ForkChanges;
 pub use self::iterator::TrieUpdateIterator;
 use super::{AccessOptions, OptimizedValueRef, Trie, TrieWithReadLock};
 use crate::StorageError;
@@ -17,6 +18,7 @@ use near_primitives::types::{
 use near_vm_runner::ContractCode;
 use std::collections::BTreeMap;
 
+mod fork;
 mod iterator;
 
 /// Key-value update. Contains a TrieKey and a value.
@@ -35,6 +37,8 @@ pub struct TrieUpdate {
     contract_storage: ContractStorage,
     committed: RawStateChanges,
     prospective: TrieUpdates,
+    /// Set if this update is a fork of another one, see `TrieUpdate::fork`.
+    fork: Option<Box<fork::ForkState>>,
 }
 
 static_assertions::assert_impl_all!(TrieUpdate: Send, Sync);
@@ -86,6 +90,7 @@ impl TrieUpdate {
             contract_storage: ContractStorage::new(trie_storage),
             committed: Default::default(),
             prospective: Default::default(),
+            fork: None,
         }
     }
 
@@ -108,10 +113,17 @@ impl TrieUpdate {
         if let Some(value_ref) = self.get_ref_from_updates(&key) {
             return Ok(value_ref);
         }
+        if let Some(fork) = &self.fork {
+            if let Some(value) = fork.get_base(&key) {
+                return Ok(value.map(TrieUpdateValuePtr::MemoryRef));
+            }
+        }
 
-        let result = self
-            .trie
-            .get_optimized_ref(&key, mode, opts)?
+        let result = self.trie.get_optimized_ref(&key, mode, opts)?;
+        if let Some(fork) = &self.fork {
+            fork.record_read(&key, result.as_ref().map(|value_ref| value_ref.value_hash()));
+        }
+        let result = result
             .map(|optimized_value_ref| TrieUpdateValuePtr::Ref(&self.trie, optimized_value_ref));
         Ok(result)
     }
@@ -136,6 +148,17 @@ impl TrieUpdate {
                 return Ok(data.is_some());
             }
         }
+        if let Some(fork) = &self.fork {
+            if let Some(value) = fork.get_base(&key) {
+                return Ok(value.is_some());
+            }
+            let value_ref = self.trie.get_optimized_ref(
+                &key,
+                KeyLookupMode::MemOrFlatOrTrie,
+                AccessOptions::NO_SIDE_EFFECTS,
+            )?;
+            fork.record_read(&key, value_ref.map(|value_ref| value_ref.value_hash()));
+        }
         self.trie.contains_key(&key, opts)
     }
 
@@ -242,12 +265,20 @@ impl TrieUpdate {
                 .changes
                 .push(RawStateChange { cause: event.clone(), data: value });
         }
-        self.contract_storage.commit_deploys();
+        // Forks share the contract storage with their parent and never record
+        // deploys, so they must not touch the deploys of the parent.
+        match &mut self.fork {
+            Some(fork) => fork.record_commit(&event),
+            None => self.contract_storage.commit_deploys(),
+        }
     }
 
     pub fn rollback(&mut self) {
         self.prospective.clear();
-        self.contract_storage.rollback_deploys();
+        match &self.fork {
+            Some(fork) => fork.record_rollback(),
+            None => self.contract_storage.rollback_deploys(),
+        }
     }
 
     /// Prepare the accumulated state changes to be applied to the underlying storage.
@@ -285,6 +316,9 @@ impl TrieUpdate {
 
     /// Returns Error if the underlying storage fails
     pub fn iter(&self, key_prefix: &[u8]) -> Result<TrieUpdateIterator<'_>, StorageError> {
+        if let Some(fork) = &self.fork {
+            fork.mark_unsupported();
+        }
         TrieUpdateIterator::new(self, key_prefix, None)
     }
 
@@ -293,6 +327,9 @@ impl TrieUpdate {
         key_prefix: &[u8],
         lock: &'a TrieWithReadLock<'_>,
     ) -> Result<TrieUpdateIterator<'a>, StorageError> {
+        if let Some(fork) = &self.fork {
+            fork.mark_unsupported();
+        }
         TrieUpdateIterator::new(self, key_prefix, Some(lock))
     }
 
@@ -313,11 +350,23 @@ impl TrieUpdate {
                 return Ok(data.as_ref().map(<Vec<u8>>::clone));
             }
         }
+        if let Some(fork) = &self.fork {
+            if let Some(value) = fork.get_base(&key) {
+                return Ok(value.map(<[u8]>::to_vec));
+            }
+            let value = fallback(&key)?;
+            fork.record_read(&key, value.as_deref().map(hash));
+            return Ok(value);
+        }
         fallback(&key)
     }
 
     /// Records deployment of a contract due to a deploy-contract action.
     pub fn record_contract_deploy(&self, code: ContractCode) {
+        if let Some(fork) = &self.fork {
+            fork.mark_unsupported();
+            return;
+        }
         self.contract_storage.record_deploy(code);
     }
 
@@ -368,7 +417,10 @@ impl TrieUpdate {
         let contract_exists =
             contract_ref.is_some_and(|value_ref| value_ref.value_hash() == code_hash);
         if contract_exists {
-            self.contract_storage.record_call(code_hash);
+            match &self.fork {
+                Some(fork) => fork.record_contract_call(code_hash),
+                None => self.contract_storage.record_call(code_hash),
+            }
         }
         Ok(())
     }
@@ -588,4 +640,43 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn trie_update_fork() {
+        let tries = TestTriesBuilder::new().build();
+        let cause = StateChangeCause::TransactionProcessing { tx_hash: CryptoHash::default() };
+        let mut trie_update = tries.new_trie_update(ShardUId::single_shard(), Trie::EMPTY_ROOT);
+        trie_update.set(test_key(b"dog".to_vec()), b"puppy".to_vec());
+        trie_update.commit(cause.clone());
+
+        let mut fork = trie_update.fork().unwrap();
+        assert_eq!(
+            fork.get(&test_key(b"dog".to_vec()), AccessOptions::DEFAULT),
+            Ok(Some(b"puppy".to_vec()))
+        );
+        fork.set(test_key(b"cat".to_vec()), b"kitten".to_vec());
+        fork.commit(cause.clone());
+        let changes = fork.take_fork_changes().unwrap();
+        assert_eq!(trie_update.get(&test_key(b"cat".to_vec()), AccessOptions::DEFAULT), Ok(None));
+        assert_eq!(trie_update.fork_reads_match(&changes), Ok(true));
+        trie_update.apply_fork_changes(changes);
+        assert_eq!(
+            trie_update.get(&test_key(b"cat".to_vec()), AccessOptions::DEFAULT),
+            Ok(Some(b"kitten".to_vec()))
+        );
+
+        // Changing a value read in the fork is a conflict.
+        assert!(fork.contains_key(&test_key(b"dog".to_vec()), AccessOptions::DEFAULT).unwrap());
+        fork.set(test_key(b"cat".to_vec()), b"lion".to_vec());
+        fork.commit(cause.clone());
+        let changes = fork.take_fork_changes().unwrap();
+        trie_update.set(test_key(b"dog".to_vec()), b"wolf".to_vec());
+        trie_update.commit(cause);
+        assert_eq!(trie_update.fork_reads_match(&changes), Ok(false));
+
+        // Iterating is not supported in forks.
+        let mut fork = trie_update.fork().unwrap();
+        drop(fork.iter(&test_key(vec![]).to_vec()).unwrap());
+        assert!(fork.take_fork_changes().is_none());
+    }
 }
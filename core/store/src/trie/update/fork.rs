@@ -0,0 +1,208 @@
+//! Forks of `TrieUpdate` for speculative execution.
+//!
+//! A fork starts from all changes of its parent, committed or not, and logs
+//! every value it reads from them or from the trie. Changes committed in the
+//! fork can later be applied to the parent, but only if the logged values are
+//! still the same there, which makes the result identical to making the
+//! changes in the parent directly.
+
+use super::TrieUpdate;
+use crate::StorageError;
+use crate::trie::{AccessOptions, KeyLookupMode, Trie};
+use near_primitives::hash::{CryptoHash, hash};
+use near_primitives::types::{RawStateChanges, RawStateChangesWithTrieKey, StateChangeCause};
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub(super) struct ForkState {
+    /// Latest values of the parent's changes at the time of the fork, updated
+    /// with the changes taken out of the fork.
+    base: BTreeMap<Vec<u8>, Option<Vec<u8>>>,
+    /// First read of each key which was not changed in the fork yet.
+    reads: Mutex<BTreeMap<Vec<u8>, ForkRead>>,
+    /// Contract calls, recorded in the parent once the changes are applied.
+    contract_calls: Mutex<Vec<CryptoHash>>,
+    /// Cause of the first commit, used to commit pending changes of the parent
+    /// before the changes of the fork are applied.
+    first_commit_cause: Option<StateChangeCause>,
+    /// Set when the fork was used in a way which can't be validated, namely
+    /// iterated, used to deploy contracts or rolled back before a commit.
+    unsupported: AtomicBool,
+}
+
+/// A value read in the fork. Whether it was served from the changes or from
+/// the trie matters too, as only trie reads are charged for trie nodes.
+#[derive(PartialEq, Eq)]
+struct ForkRead {
+    from_changes: bool,
+    /// None if the value was missing.
+    value_hash: Option<CryptoHash>,
+}
+
+impl ForkState {
+    /// Returns the value of the parent if the key was changed there.
+    pub(super) fn get_base(&self, key: &[u8]) -> Option<Option<&[u8]>> {
+        let value = self.base.get(key)?.as_deref();
+        let read = ForkRead { from_changes: true, value_hash: value.map(hash) };
+        self.reads.lock().entry(key.to_vec()).or_insert(read);
+        Some(value)
+    }
+
+    /// Records a read of the key from the trie.
+    pub(super) fn record_read(&self, key: &[u8], value_hash: Option<CryptoHash>) {
+        let read = ForkRead { from_changes: false, value_hash };
+        self.reads.lock().entry(key.to_vec()).or_insert(read);
+    }
+
+    pub(super) fn record_contract_call(&self, code_hash: CryptoHash) {
+        self.contract_calls.lock().push(code_hash);
+    }
+
+    pub(super) fn record_commit(&mut self, cause: &StateChangeCause) {
+        self.first_commit_cause.get_or_insert_with(|| cause.clone());
+    }
+
+    /// In the parent, a rollback before the first commit would also drop its
+    /// pending changes, which can't be reproduced.
+    pub(super) fn record_rollback(&self) {
+        if self.first_commit_cause.is_none() {
+            self.mark_unsupported();
+        }
+    }
+
+    pub(super) fn mark_unsupported(&self) {
+        self.unsupported.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Changes committed in a fork of `TrieUpdate`, together with the values they
+/// depend on. See `TrieUpdate::fork`.
+pub struct ForkChanges {
+    committed: RawStateChanges,
+    first_commit_cause: Option<StateChangeCause>,
+    reads: BTreeMap<Vec<u8>, ForkRead>,
+    contract_calls: Vec<CryptoHash>,
+}
+
+impl TrieUpdate {
+    /// Creates a fork of this update which sees all its changes, committed or
+    /// not. Changes made in the fork are not visible here until they are
+    /// taken out with `take_fork_changes` and applied with
+    /// `apply_fork_changes`.
+    ///
+    /// Returns None if the trie records reads for a storage proof, as reads in
+    /// the fork would not be recorded in the order of execution.
+    pub fn fork(&self) -> Option<TrieUpdate> {
+        if self.trie.recorder.is_some() {
+            return None;
+        }
+        let mut base: BTreeMap<_, _> = self
+            .committed
+            .iter()
+            .filter_map(|(key, changes)| {
+                let change = changes.changes.last()?;
+                Some((key.clone(), change.data.clone()))
+            })
+            .collect();
+        for (key, update) in &self.prospective {
+            base.insert(key.clone(), update.value.clone());
+        }
+        let mut trie = Trie::new_with_memtries(
+            self.trie.storage.clone(),
+            self.trie.memtries.clone(),
+            self.trie.children_memtries.clone(),
+            self.trie.root,
+            self.trie.flat_storage_chunk_view.clone(),
+        );
+        trie.use_access_tracker = self.trie.use_access_tracker;
+        Some(TrieUpdate {
+            trie,
+            contract_storage: self.contract_storage.clone(),
+            committed: Default::default(),
+            prospective: Default::default(),
+            fork: Some(Box::new(ForkState {
+                base,
+                reads: Default::default(),
+                contract_calls: Default::default(),
+                first_commit_cause: None,
+                unsupported: AtomicBool::new(false),
+            })),
+        })
+    }
+
+    /// Takes the changes committed in the fork since it was created or since
+    /// the previous call.
+    ///
+    /// Returns None if the changes can't be applied to the parent, because
+    /// some changes were left uncommitted or the fork was used in an
+    /// unsupported way. The fork must not be used after that.
+    pub fn take_fork_changes(&mut self) -> Option<ForkChanges> {
+        let fork = self.fork.as_mut().expect("take_fork_changes called on a non-forked update");
+        if !self.prospective.is_empty() || fork.unsupported.load(Ordering::Relaxed) {
+            return None;
+        }
+        let committed = std::mem::take(&mut self.committed);
+        for (key, changes) in &committed {
+            if let Some(change) = changes.changes.last() {
+                fork.base.insert(key.clone(), change.data.clone());
+            }
+        }
+        Some(ForkChanges {
+            committed,
+            first_commit_cause: fork.first_commit_cause.take(),
+            reads: std::mem::take(&mut *fork.reads.lock()),
+            contract_calls: std::mem::take(&mut *fork.contract_calls.lock()),
+        })
+    }
+
+    /// Whether all values read in the fork are the same in this update, so
+    /// that making the changes here would have had the same result.
+    pub fn fork_reads_match(&self, changes: &ForkChanges) -> Result<bool, StorageError> {
+        for (key, fork_read) in &changes.reads {
+            // Uncommitted changes are not checked, `contains_key` treats them
+            // differently from the committed ones.
+            if self.prospective.contains_key(key) {
+                return Ok(false);
+            }
+            let read = if let Some(change) = self.committed.get(key).and_then(|c| c.changes.last())
+            {
+                ForkRead { from_changes: true, value_hash: change.data.as_deref().map(hash) }
+            } else {
+                let value_ref = self.trie.get_optimized_ref(
+                    key,
+                    KeyLookupMode::MemOrFlatOrTrie,
+                    AccessOptions::NO_SIDE_EFFECTS,
+                )?;
+                ForkRead {
+                    from_changes: false,
+                    value_hash: value_ref.map(|value_ref| value_ref.value_hash()),
+                }
+            };
+            if read != *fork_read {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Applies changes taken from a fork as if they were made here. Pending
+    /// changes of this update are committed first, as they would have been by
+    /// the first commit in the fork.
+    pub fn apply_fork_changes(&mut self, changes: ForkChanges) {
+        let ForkChanges { committed, first_commit_cause, reads: _, contract_calls } = changes;
+        if let Some(cause) = first_commit_cause {
+            self.commit(cause);
+        }
+        for (key, RawStateChangesWithTrieKey { trie_key, changes }) in committed {
+            self.committed
+                .entry(key)
+                .or_insert_with(|| RawStateChangesWithTrieKey { trie_key, changes: Vec::new() })
+                .changes
+                .extend(changes);
+        }
+        for code_hash in contract_calls {
+            self.contract_storage.record_call(code_hash);
+        }
+    }
+}
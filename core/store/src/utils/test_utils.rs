@@ -80,6 +80,7 @@ pub struct TestTriesBuilder {
     shard_layout: ShardLayout,
     enable_flat_storage: bool,
     enable_in_memory_tries: bool,
+    enable_receipt_prefetching: bool,
 }
 
 impl TestTriesBuilder {
@@ -89,6 +90,7 @@ impl TestTriesBuilder {
             shard_layout: ShardLayout::single_shard(),
             enable_flat_storage: false,
             enable_in_memory_tries: false,
+            enable_receipt_prefetching: false,
         }
     }
 
@@ -112,6 +114,11 @@ impl TestTriesBuilder {
         self
     }
 
+    pub fn with_receipt_prefetching(mut self, enable: bool) -> Self {
+        self.enable_receipt_prefetching = enable;
+        self
+    }
+
     pub fn build2(self) -> (ShardTries, ShardLayout) {
         let shard_layout = self.shard_layout.clone();
         let shard_tries = self.build();
@@ -129,6 +136,7 @@ impl TestTriesBuilder {
             store.trie_store(),
             TrieConfig {
                 load_memtries_for_tracked_shards: self.enable_in_memory_tries,
+                enable_receipt_prefetching: self.enable_receipt_prefetching,
                 ..Default::default()
             },
             &shard_uids,
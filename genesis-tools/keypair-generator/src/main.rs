@@ -61,7 +61,7 @@ fn main() {
                 .map(|x| x.parse().expect("Failed to parse number keys."))
                 .unwrap_or(3usize);
             let keys: Vec<SecretKey> =
-                (0..num_keys).map(|_| SecretKey::from_random(KeyType::ED25519)).collect();
+                (0..num_keys).map(|_| SecretKey::from_random(KeyType::ED25519).unwrap()).collect();
             let mut pks = vec![];
             for (i, key) in keys.into_iter().enumerate() {
                 println!("Key#{}", i);
@@ -86,7 +86,7 @@ fn main() {
             println!("{}", pks.join(","));
         }
         Some(("validator-key", _)) => {
-            let key = SecretKey::from_random(KeyType::ED25519);
+            let key = SecretKey::from_random(KeyType::ED25519).unwrap();
             println!("PK: {}", key.public_key());
             if generate_config {
                 let account_id =
@@ -100,7 +100,7 @@ fn main() {
             }
         }
         Some(("node-key", _args)) => {
-            let key = SecretKey::from_random(KeyType::ED25519);
+            let key = SecretKey::from_random(KeyType::ED25519).unwrap();
             println!("PK: {}", key.public_key());
             if generate_config {
                 let mut path = home_dir.to_path_buf();
@@ -215,6 +215,7 @@ fn setup(
         tx_routing_height_horizon: config.tx_routing_height_horizon,
         epoch_length: config.epoch_length,
         transaction_validity_period,
+        tx_gossip: false,
     };
 
     let rpc_handler_addr = spawn_rpc_handler_actor(
@@ -488,6 +489,7 @@ pub fn setup_synchronous_shards_manager(
                 ReshardingConfig::default(),
                 "resharding_config",
             ),
+            persist_orphans: false,
         }, // irrelevant
         None,
         Arc::new(RayonAsyncComputationSpawner),
@@ -528,6 +530,7 @@ pub fn setup_tx_request_handler(
         tx_routing_height_horizon: client_config.tx_routing_height_horizon,
         epoch_length: chain_genesis.epoch_length,
         transaction_validity_period: chain_genesis.transaction_validity_period,
+        tx_gossip: false,
     };
 
     RpcHandler::new(
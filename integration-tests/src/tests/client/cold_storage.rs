@@ -496,7 +496,8 @@ fn test_cold_loop_on_gc_boundary() {
         cold_store.get_ser::<Tip>(DBCol::BlockMisc, COLD_HEAD_KEY).unwrap().unwrap().height;
 
     let signer =
-        InMemorySigner::from_random(AccountId::from_str("test").unwrap(), KeyType::ED25519);
+        InMemorySigner::from_random(AccountId::from_str("test").unwrap(), KeyType::ED25519)
+            .unwrap();
 
     let mut near_config = NearConfig::new(
         nearcore::config::Config::default(),
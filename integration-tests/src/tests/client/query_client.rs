@@ -171,8 +171,13 @@ fn test_execution_outcome_for_chunk() {
             let res = actor_handles
                 .rpc_handler_actor
                 .send(
-                    ProcessTxRequest { transaction, is_forwarded: false, check_only: false }
-                        .with_span_context(),
+                    ProcessTxRequest {
+                        transaction,
+                        is_forwarded: false,
+                        is_gossiped: false,
+                        check_only: false,
+                    }
+                    .with_span_context(),
                 )
                 .await
                 .unwrap();
@@ -133,6 +133,7 @@ fn ultra_slow_test_stake_nodes() {
                         ProcessTxRequest {
                             transaction: tx,
                             is_forwarded: false,
+                            is_gossiped: false,
                             check_only: false,
                         }
                         .with_span_context(),
@@ -218,6 +219,7 @@ fn ultra_slow_test_validator_kickout() {
                             ProcessTxRequest {
                                 transaction: stake_transaction,
                                 is_forwarded: false,
+                                is_gossiped: false,
                                 check_only: false,
                             }
                             .with_span_context(),
@@ -369,6 +371,7 @@ fn ultra_slow_test_validator_join() {
                         ProcessTxRequest {
                             transaction: unstake_transaction,
                             is_forwarded: false,
+                            is_gossiped: false,
                             check_only: false,
                         }
                         .with_span_context(),
@@ -382,6 +385,7 @@ fn ultra_slow_test_validator_join() {
                         ProcessTxRequest {
                             transaction: stake_transaction,
                             is_forwarded: false,
+                            is_gossiped: false,
                             check_only: false,
                         }
                         .with_span_context(),
@@ -65,6 +65,7 @@ fn ultra_slow_test_sync_state_stake_change() {
                         ProcessTxRequest {
                             transaction: unstake_transaction,
                             is_forwarded: false,
+                            is_gossiped: false,
                             check_only: false,
                         }
                         .with_span_context(),
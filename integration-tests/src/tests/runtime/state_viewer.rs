@@ -191,6 +191,72 @@ fn test_view_call_with_args() {
     assert_eq!(view_call_result.unwrap(), 3u64.to_le_bytes().to_vec());
 }
 
+fn test_view_state() -> ViewApplyState {
+    ViewApplyState {
+        block_height: 1,
+        prev_block_hash: CryptoHash::default(),
+        block_hash: CryptoHash::default(),
+        shard_id: ShardUId::single_shard().shard_id(),
+        epoch_id: EpochId::default(),
+        epoch_height: 0,
+        block_timestamp: 1,
+        current_protocol_version: PROTOCOL_VERSION,
+        cache: None,
+    }
+}
+
+#[test]
+fn test_upgrade_contract_with_migration() {
+    let (viewer, mut state_update) = get_test_trie_viewer();
+    let contract_id: AccountId = "test.contract".parse().unwrap();
+    let args: Vec<_> = [10u64, 20u64].iter().flat_map(|x| (*x).to_le_bytes().to_vec()).collect();
+    let mut logs = vec![];
+    let outcome = viewer
+        .upgrade_contract(
+            &mut state_update,
+            test_view_state(),
+            &contract_id,
+            near_test_contracts::rs_contract().to_vec(),
+            Some(("write_key_value", &args)),
+            &mut logs,
+            &MockEpochInfoProvider::default(),
+        )
+        .unwrap();
+    assert!(outcome.migration_gas_burnt > 0);
+    assert!(outcome.storage_usage_after > outcome.storage_usage_before);
+    // The test contract account holds no balance to stake for its storage.
+    assert!(outcome.lacking_storage_stake.is_some());
+
+    // The view call observes the value written by the migration.
+    let result = viewer.call_function_on_update(
+        &mut state_update,
+        test_view_state(),
+        &contract_id,
+        "read_value",
+        &10u64.to_le_bytes(),
+        &mut logs,
+        &MockEpochInfoProvider::default(),
+    );
+    assert_eq!(result.unwrap(), 20u64.to_le_bytes().to_vec());
+}
+
+#[test]
+fn test_upgrade_contract_failing_migration() {
+    let (viewer, mut state_update) = get_test_trie_viewer();
+    let mut logs = vec![];
+    let result = viewer.upgrade_contract(
+        &mut state_update,
+        test_view_state(),
+        &"test.contract".parse().unwrap(),
+        near_test_contracts::rs_contract().to_vec(),
+        Some(("no_such_method", &[])),
+        &mut logs,
+        &MockEpochInfoProvider::default(),
+    );
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("MethodNotFound"), "unexpected error: {}", err);
+}
+
 fn assert_view_state(
     trie_viewer: &TrieViewer,
     state_update: &near_store::TrieUpdate,
@@ -347,6 +347,9 @@ pub fn transfer_tokens_to_implicit_account(node: impl Node, public_key: PublicKe
     let receiver_id = match public_key.key_type() {
         KeyType::ED25519 => derive_near_implicit_account_id(public_key.unwrap_as_ed25519()),
         KeyType::SECP256K1 => derive_eth_implicit_account_id(public_key.unwrap_as_secp256k1()),
+        KeyType::SECP256R1 | KeyType::MULTISIG => {
+            std::panic!("no implicit account for this key type")
+        }
     };
 
     let transfer_cost = match receiver_id.get_account_type() {
@@ -423,6 +426,9 @@ pub fn trying_to_create_implicit_account(node: impl Node, public_key: PublicKey)
     let receiver_id = match public_key.key_type() {
         KeyType::ED25519 => derive_near_implicit_account_id(public_key.unwrap_as_ed25519()),
         KeyType::SECP256K1 => derive_eth_implicit_account_id(public_key.unwrap_as_secp256k1()),
+        KeyType::SECP256R1 | KeyType::MULTISIG => {
+            std::panic!("no implicit account for this key type")
+        }
     };
 
     let transaction_result = node_user
@@ -725,7 +731,7 @@ pub fn test_create_account_failure_already_exists(node: impl Node) {
 
 pub fn test_swap_key(node: impl Node) {
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap();
     let node_user = node.user();
     let root = node_user.get_state_root();
     let money_used = TESTING_INIT_BALANCE / 2;
@@ -761,7 +767,8 @@ pub fn test_swap_key(node: impl Node) {
 
 pub fn test_add_key(node: impl Node) {
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+    let signer2 =
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
     let node_user = node.user();
 
     add_access_key(&node, node_user.as_ref(), &AccessKey::full_access(), &signer2);
@@ -799,7 +806,8 @@ pub fn test_add_existing_key(node: impl Node) {
 
 pub fn test_delete_key(node: impl Node) {
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+    let signer2 =
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
     let node_user = node.user();
     add_access_key(&node, node_user.as_ref(), &AccessKey::full_access(), &signer2);
 
@@ -820,7 +828,7 @@ pub fn test_delete_key(node: impl Node) {
 
 pub fn test_delete_key_not_owned(node: impl Node) {
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519);
+    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap();
     let node_user = node.user();
 
     assert!(node_user.get_access_key(account_id, &node.signer().public_key()).is_ok());
@@ -914,7 +922,8 @@ pub fn test_add_access_key_function_call(node: impl Node) {
             method_names: vec![],
         }),
     };
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+    let signer2 =
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
     let result = add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
 
     assert!(node_user.get_access_key(account_id, &node.signer().public_key()).is_ok());
@@ -934,7 +943,8 @@ pub fn test_delete_access_key(node: impl Node) {
             method_names: vec![],
         }),
     };
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+    let signer2 =
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
 
     assert!(node_user.get_access_key(account_id, &node.signer().public_key()).is_ok());
@@ -963,7 +973,8 @@ pub fn test_add_access_key_with_allowance(node: impl Node) {
         }),
     };
     let node_user = node.user();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+    let signer2 =
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
     let account = node_user.view_account(account_id).unwrap();
     let initial_balance = account.amount;
     let fee_helper = fee_helper(&node);
@@ -989,7 +1000,8 @@ pub fn test_delete_access_key_with_allowance(node: impl Node) {
         }),
     };
     let node_user = node.user();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+    let signer2 =
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
     let account = node_user.view_account(account_id).unwrap();
     let initial_balance = account.amount;
     let fee_helper = fee_helper(&node);
@@ -1026,8 +1038,9 @@ pub fn test_access_key_smart_contract(node: impl Node) {
     };
     let mut node_user = node.user();
     let account_id = &node.account_id().unwrap();
-    let signer2 =
-        Arc::new(InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into());
+    let signer2 = Arc::new(
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into(),
+    );
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
     node_user.set_signer(signer2.clone());
 
@@ -1081,7 +1094,8 @@ pub fn test_access_key_smart_contract_reject_method_name(node: impl Node) {
     };
     let mut node_user = node.user();
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+    let signer2 =
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
     node_user.set_signer(Arc::new(signer2));
 
@@ -1102,7 +1116,8 @@ pub fn test_access_key_smart_contract_reject_contract_id(node: impl Node) {
     };
     let mut node_user = node.user();
     let account_id = &node.account_id().unwrap();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+    let signer2 =
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
     node_user.set_signer(Arc::new(signer2));
 
@@ -1130,7 +1145,8 @@ pub fn test_access_key_reject_non_function_call(node: impl Node) {
         }),
     };
     let mut node_user = node.user();
-    let signer2 = InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).into();
+    let signer2 =
+        InMemorySigner::from_random("test".parse().unwrap(), KeyType::ED25519).unwrap().into();
     add_access_key(&node, node_user.as_ref(), &access_key, &signer2);
     node_user.set_signer(Arc::new(signer2));
 
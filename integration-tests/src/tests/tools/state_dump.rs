@@ -56,13 +56,13 @@ fn setup(
         KeyFile {
             account_id: "test".parse().unwrap(),
             public_key: PublicKey::empty(KeyType::ED25519),
-            secret_key: SecretKey::from_random(KeyType::ED25519),
+            secret_key: SecretKey::from_random(KeyType::ED25519).unwrap(),
         },
         MutableConfigValue::new(
-            Some(Arc::new(InMemoryValidatorSigner::from_random(
-                "test".parse().unwrap(),
-                KeyType::ED25519,
-            ))),
+            Some(Arc::new(
+                InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519)
+                    .unwrap(),
+            )),
             "validator_signer",
         ),
     )
@@ -381,13 +381,13 @@ fn test_dump_state_not_track_shard() {
         KeyFile {
             account_id: "test".parse().unwrap(),
             public_key: PublicKey::empty(KeyType::ED25519),
-            secret_key: SecretKey::from_random(KeyType::ED25519),
+            secret_key: SecretKey::from_random(KeyType::ED25519).unwrap(),
         },
         MutableConfigValue::new(
-            Some(Arc::new(InMemoryValidatorSigner::from_random(
-                "test".parse().unwrap(),
-                KeyType::ED25519,
-            ))),
+            Some(Arc::new(
+                InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519)
+                    .unwrap(),
+            )),
             "validator_signer",
         ),
     )
@@ -456,13 +456,13 @@ fn test_dump_state_with_delayed_receipt() {
         KeyFile {
             account_id: "test".parse().unwrap(),
             public_key: PublicKey::empty(KeyType::ED25519),
-            secret_key: SecretKey::from_random(KeyType::ED25519),
+            secret_key: SecretKey::from_random(KeyType::ED25519).unwrap(),
         },
         MutableConfigValue::new(
-            Some(Arc::new(InMemoryValidatorSigner::from_random(
-                "test".parse().unwrap(),
-                KeyType::ED25519,
-            ))),
+            Some(Arc::new(
+                InMemoryValidatorSigner::from_random("test".parse().unwrap(), KeyType::ED25519)
+                    .unwrap(),
+            )),
             "validator_signer",
         ),
     )
@@ -38,6 +38,7 @@ use near_jsonrpc::RpcConfig;
 use near_network::config::NetworkConfig;
 use near_network::tcp;
 use near_o11y::log_config::LogConfig;
+use near_parameters::vm::VMKind;
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::ShardLayout;
 use near_primitives::test_utils::create_test_signer;
@@ -359,12 +360,28 @@ pub struct Config {
     ///
     /// Each loaded contract will increase the baseline memory use of the node appreciably.
     pub max_loaded_contracts: usize,
+    /// If set, every function call is additionally executed on this VM and any divergence in
+    /// the outcome or gas from the VM prescribed by the protocol is logged and counted in the
+    /// `near_vm_shadow_divergences_total` metric.
+    ///
+    /// This roughly doubles the cost of executing contracts and is meant for cross-checking VM
+    /// implementations, not for production validators.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shadow_vm_kind: Option<VMKind>,
     /// Save observed instances of ChunkStateWitness to the database in DBCol::LatestChunkStateWitnesses.
     /// Saving the latest witnesses is useful for analysis and debugging.
     /// When this option is enabled, the node will save ALL witnesses it observes, even invalid ones,
     /// which can cause extra load on the database. This option is not recommended for production use,
     /// as a large number of incoming witnesses could cause denial of service.
     pub save_latest_witnesses: bool,
+    /// If set, divergences found by shadow chunk validation are saved to this
+    /// directory, relative to the home directory. Only has an effect when the
+    /// node is built with the `shadow_chunk_validation` feature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shadow_validation_divergence_dir: Option<PathBuf>,
+    /// If true, recent orphan blocks and orphan chunk state witnesses are
+    /// saved to the database and requeued for processing after a restart.
+    pub persist_orphans: bool,
     pub transaction_request_handler_threads: usize,
 }
 
@@ -419,7 +436,10 @@ impl Default for Config {
             orphan_state_witness_pool_size: default_orphan_state_witness_pool_size(),
             orphan_state_witness_max_size: default_orphan_state_witness_max_size(),
             max_loaded_contracts: 256,
+            shadow_vm_kind: None,
             save_latest_witnesses: false,
+            shadow_validation_divergence_dir: None,
+            persist_orphans: false,
             transaction_request_handler_threads: 4,
         }
     }
@@ -615,6 +635,8 @@ impl NearConfig {
                 orphan_state_witness_pool_size: config.orphan_state_witness_pool_size,
                 orphan_state_witness_max_size: config.orphan_state_witness_max_size,
                 save_latest_witnesses: config.save_latest_witnesses,
+                shadow_validation_divergence_dir: config.shadow_validation_divergence_dir.clone(),
+                persist_orphans: config.persist_orphans,
                 transaction_request_handler_threads: config.transaction_request_handler_threads,
             },
             #[cfg(feature = "tx_generator")]
@@ -677,6 +699,20 @@ impl NightshadeRuntime {
         store: Store,
         config: &NearConfig,
         epoch_manager: Arc<EpochManagerHandle>,
+    ) -> std::io::Result<Arc<NightshadeRuntime>> {
+        Self::from_config_with_parallel_receipts(home_dir, store, config, epoch_manager, false)
+    }
+
+    /// Like `from_config`, but optionally executes independent receipts of a chunk in
+    /// parallel, see `node_runtime::Runtime::with_parallel_receipt_execution`.
+    /// Only meant for offline tools replaying chunks, like `view-state apply-range`
+    /// and `replay-archive`, never for a running node.
+    pub fn from_config_with_parallel_receipts(
+        home_dir: &Path,
+        store: Store,
+        config: &NearConfig,
+        epoch_manager: Arc<EpochManagerHandle>,
+        parallel_receipt_execution: bool,
     ) -> std::io::Result<Arc<NightshadeRuntime>> {
         #[allow(clippy::or_fun_call)] // Closure cannot return reference to a temporary value
         let state_snapshot_config =
@@ -707,6 +743,8 @@ impl NightshadeRuntime {
             config.config.gc.gc_num_epochs_to_keep(),
             TrieConfig::from_store_config(&config.config.store),
             state_snapshot_config,
+            config.config.shadow_vm_kind,
+            parallel_receipt_execution,
         ))
     }
 }
@@ -747,7 +785,7 @@ fn generate_or_load_key(
         let signer = if let Some(seed) = test_seed {
             InMemorySigner::from_seed(account_id, KeyType::ED25519, seed)
         } else {
-            InMemorySigner::from_random(account_id, KeyType::ED25519).into()
+            InMemorySigner::from_random(account_id, KeyType::ED25519)?.into()
         };
         info!(target: "near", "Using key {} for {}", signer.public_key(), signer.get_account_id());
         signer
@@ -1473,12 +1511,15 @@ pub fn load_config(
     if genesis.is_none() || network_signer.is_none() {
         panic!("Genesis and network_signer should not be None by now.")
     }
-    let near_config = NearConfig::new(
+    let mut near_config = NearConfig::new(
         config,
         genesis.unwrap(),
         network_signer.unwrap().into(),
         MutableConfigValue::new(validator_signer, "validator_signer"),
     )?;
+    if let Some(path) = &mut near_config.client_config.shadow_validation_divergence_dir {
+        *path = dir.join(&*path);
+    }
     Ok(near_config)
 }
 
@@ -1491,7 +1532,8 @@ pub fn load_test_config(seed: &str, addr: tcp::ListenerAddr, genesis: Genesis) -
     config.consensus.max_block_production_delay =
         Duration::milliseconds(FAST_MAX_BLOCK_PRODUCTION_DELAY);
     let (signer, validator_signer) = if seed.is_empty() {
-        let signer = InMemorySigner::from_random("node".parse().unwrap(), KeyType::ED25519).into();
+        let signer =
+            InMemorySigner::from_random("node".parse().unwrap(), KeyType::ED25519).unwrap().into();
         (signer, None)
     } else {
         let signer = InMemorySigner::from_seed(seed.parse().unwrap(), KeyType::ED25519, seed);
@@ -1692,6 +1734,7 @@ mod tests {
                     gc_fork_clean_step: 420,
                     gc_num_epochs_to_keep: 24,
                     gc_step_period: Duration::seconds(1),
+                    retention: None,
                 }
             } else {
                 GCConfig {
@@ -1699,6 +1742,7 @@ mod tests {
                     gc_fork_clean_step: 100,
                     gc_num_epochs_to_keep: 5,
                     gc_step_period: Duration::seconds(1),
+                    retention: None,
                 }
             };
             assert_eq!(want_gc, config.gc);
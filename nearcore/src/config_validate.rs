@@ -1,5 +1,7 @@
 use near_chain_configs::{ExternalStorageLocation, SyncConfig};
 use near_config_utils::{ValidationError, ValidationErrors};
+use near_parameters::vm::VMKind;
+use near_vm_runner::internal::VMKindExt;
 use std::collections::HashSet;
 use std::path::Path;
 
@@ -87,6 +89,22 @@ impl<'a> ConfigValidator<'a> {
             self.validation_errors.push_config_semantics_error(error_message);
         }
 
+        if let Some(retention) = &self.config.gc.retention {
+            if retention.accounts.is_empty() {
+                let error_message = "gc.retention.accounts should not be empty".to_string();
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+            if let Some(num_epochs_to_keep) = retention.num_epochs_to_keep {
+                if num_epochs_to_keep < self.config.gc.gc_num_epochs_to_keep {
+                    let error_message = format!(
+                        "gc.retention.num_epochs_to_keep is {}, but it should not be less than gc_num_epochs_to_keep, which is {}.",
+                        num_epochs_to_keep, self.config.gc.gc_num_epochs_to_keep
+                    );
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+            }
+        }
+
         if let Some(state_sync) = &self.config.state_sync {
             if let Some(dump_config) = &state_sync.dump {
                 if let Some(restart_dump_for_shards) = &dump_config.restart_dump_for_shards {
@@ -187,6 +205,16 @@ impl<'a> ConfigValidator<'a> {
             );
             self.validation_errors.push_config_semantics_error(error_message);
         }
+        if let Some(shadow_vm_kind) = self.config.shadow_vm_kind {
+            if !matches!(shadow_vm_kind, VMKind::Wasmtime | VMKind::NearVm)
+                || !shadow_vm_kind.is_available()
+            {
+                let error_message = format!(
+                    "'config.shadow_vm_kind' is {shadow_vm_kind:?}, which is not available in this build. Only Wasmtime and NearVm can be used for shadow execution, and only if they have been enabled at compile time."
+                );
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+        }
         self.validate_tracked_shards_config();
     }
 
@@ -224,7 +252,7 @@ impl<'a> ConfigValidator<'a> {
 
 #[cfg(test)]
 mod tests {
-    use near_chain_configs::TrackedShardsConfig;
+    use near_chain_configs::{GCRetentionConfig, TrackedShardsConfig};
 
     use super::*;
 
@@ -245,6 +273,25 @@ mod tests {
         validate_config(&config).unwrap();
     }
 
+    #[test]
+    #[should_panic(expected = "it should not be less than gc_num_epochs_to_keep")]
+    fn test_gc_retention_shorter_than_gc() {
+        let mut config = Config::default();
+        config.gc.retention = Some(GCRetentionConfig {
+            accounts: vec!["alice.near".parse().unwrap()],
+            num_epochs_to_keep: Some(config.gc.gc_num_epochs_to_keep - 1),
+        });
+        validate_config(&config).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "'config.shadow_vm_kind' is Wasmer2")]
+    fn test_shadow_vm_kind_unavailable() {
+        let mut config = Config::default();
+        config.shadow_vm_kind = Some(VMKind::Wasmer2);
+        validate_config(&config).unwrap();
+    }
+
     #[test]
     #[should_panic(
         expected = "Configuration with archive = false and save_trie_changes = false is not supported"
@@ -1,6 +1,7 @@
 use crate::config::Config;
 use near_chain_configs::UpdatableClientConfig;
 use near_dyn_configs::{UpdatableConfigLoaderError, UpdatableConfigs, UpdatableValidatorSigner};
+use near_network::config_json::UpdatableNetworkConfig;
 use near_o11y::log_config::LogConfig;
 use near_primitives::validator_signer::ValidatorSigner;
 use serde::Deserialize;
@@ -32,6 +33,11 @@ pub fn read_updatable_configs(
         }
     };
     let updatable_client_config = config.as_ref().map(get_updatable_client_config);
+    let updatable_network_config = config.as_ref().and_then(|config| {
+        serde_json::to_value(UpdatableNetworkConfig::from(&config.network))
+            .inspect_err(|err| tracing::warn!(target: "neard", ?err, "Failed to serialize the updatable network config"))
+            .ok()
+    });
 
     let validator_signer = if let Some(config) = config {
         match read_validator_key(home_dir, &config) {
@@ -51,6 +57,7 @@ pub fn read_updatable_configs(
             log_config,
             client_config: updatable_client_config,
             validator_signer,
+            network_config: updatable_network_config,
         })
     } else {
         tracing::warn!(target: "neard", "Dynamically updatable configs are not valid. Please fix this ASAP otherwise the node will be unable to restart: {:?}", &errs);
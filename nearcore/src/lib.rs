@@ -452,6 +452,7 @@ pub fn start_with_config_and_synchronization(
         tx_routing_height_horizon: config.client_config.tx_routing_height_horizon,
         epoch_length: config.client_config.epoch_length,
         transaction_validity_period: config.genesis.config.transaction_validity_period,
+        tx_gossip: config.network_config.tx_gossip.is_some(),
     };
     let rpc_handler = spawn_rpc_handler_actor(
         rpc_handler_config,
@@ -18,7 +18,7 @@ use near_o11y::{
     BuildEnvFilterError, EnvFilterBuilder, default_subscriber,
     default_subscriber_with_opentelemetry,
 };
-use near_ping::PingCommand;
+use near_ping::{PingCommand, ReplayTrafficCommand};
 use near_primitives::epoch_manager::EpochConfigStore;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::compute_root_from_path;
@@ -112,6 +112,9 @@ impl NeardCmd {
             NeardSubCommand::Ping(cmd) => {
                 cmd.run()?;
             }
+            NeardSubCommand::ReplayNetworkTraffic(cmd) => {
+                cmd.run()?;
+            }
             NeardSubCommand::Mirror(cmd) => {
                 cmd.run()?;
             }
@@ -229,6 +232,9 @@ pub(super) enum NeardSubCommand {
     /// us after the handshake is completed, printing stats to stdout.
     Ping(PingCommand),
 
+    /// Connects to a NEAR node and replays the network traffic recorded by another node.
+    ReplayNetworkTraffic(ReplayTrafficCommand),
+
     /// Mirror transactions from a source chain to a test chain with state forked
     /// from it, reproducing traffic and state as closely as possible.
     Mirror(MirrorCommand),
@@ -37,10 +37,10 @@ enum ContractCacheKey {
     },
 }
 
-fn vm_hash(vm_kind: VMKind) -> u64 {
-    match vm_kind {
+fn vm_hash(config: &Config) -> u64 {
+    match config.vm_kind {
         #[cfg(feature = "wasmtime_vm")]
-        VMKind::Wasmtime => crate::wasmtime_runner::wasmtime_vm_hash(),
+        VMKind::Wasmtime => crate::wasmtime_runner::wasmtime_vm_hash(config),
         #[cfg(not(feature = "wasmtime_vm"))]
         VMKind::Wasmtime => panic!("Wasmtime is not enabled"),
         #[cfg(all(feature = "near_vm", target_arch = "x86_64"))]
@@ -58,7 +58,7 @@ pub fn get_contract_cache_key(code_hash: CryptoHash, config: &Config) -> CryptoH
         code_hash,
         vm_config_non_crypto_hash: config.non_crypto_hash(),
         vm_kind: config.vm_kind,
-        vm_hash: vm_hash(config.vm_kind),
+        vm_hash: vm_hash(config),
     };
     CryptoHash::hash_borsh(key)
 }
@@ -23,17 +23,27 @@ mod opts {
     pub(super) const SIGN_EXTENSION: bool = true;
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 #[allow(unused)]
 pub struct WasmFeatures {
     saturating_float_to_int: bool,
 }
 
 impl WasmFeatures {
+    /// Number of distinct sets of features, see `index`.
+    #[allow(unused)]
+    pub const COUNT: usize = 2;
+
     #[allow(unused)]
     pub fn new(config: &vm::Config) -> Self {
         Self { saturating_float_to_int: config.saturating_float_to_int }
     }
+
+    /// Index of this set of features in `0..COUNT`, for caching the data derived from it.
+    #[allow(unused)]
+    pub fn index(&self) -> usize {
+        usize::from(self.saturating_float_to_int)
+    }
 }
 
 #[cfg(feature = "finite-wasm")]
@@ -14,6 +14,7 @@ mod near_vm_runner;
 pub mod prepare;
 mod profile;
 mod runner;
+mod shadow;
 #[cfg(test)]
 mod tests;
 mod utils;
@@ -27,11 +28,13 @@ pub use cache::{
     CompiledContract, CompiledContractInfo, ContractRuntimeCache, MockContractRuntimeCache,
     NoContractRuntimeCache, get_contract_cache_key, precompile_contract,
 };
+pub use errors::ContractPrecompilatonResult;
 #[cfg(feature = "metrics")]
 pub use metrics::{report_metrics, reset_metrics};
 pub use near_primitives_core::code::ContractCode;
 pub use profile::ProfileDataV3;
 pub use runner::{Contract, PreparedContract, VM, prepare, run};
+pub use shadow::{ShadowDivergence, run_with_shadow};
 
 /// This is public for internal experimentation use only, and should otherwise be considered an
 /// implementation detail of `near-vm-runner`.
@@ -110,7 +110,7 @@ pub trait ValuePtr {
     fn deref(&self, storage_tracker: &mut dyn StorageAccessTracker) -> Result<Vec<u8>>;
 }
 
-pub(super) mod sealed {
+pub(crate) mod sealed {
     pub trait StorageAccessTrackerSeal {}
 }
 
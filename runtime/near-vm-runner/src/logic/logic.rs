@@ -215,11 +215,23 @@ macro_rules! get_memory_or_register {
 /// Why not just keep the old ways without this noise? By doing deserialization
 /// immediately we’re copying the data onto the stack without having to allocate
 /// a temporary vector.
+///
+/// Key types other than ED25519 and SECP256K1 did not deserialize before the
+/// `ExtendedAccessKeyTypes` protocol feature, so they are rejected as invalid
+/// until `extended_access_key_types` is enabled.
 struct PublicKeyBuffer(Result<near_crypto::PublicKey, ()>);
 
 impl PublicKeyBuffer {
-    fn new(data: &[u8]) -> Self {
-        Self(borsh::BorshDeserialize::try_from_slice(data).map_err(|_| ()))
+    fn new(data: &[u8], extended_access_key_types: bool) -> Self {
+        let key: Result<near_crypto::PublicKey, ()> =
+            borsh::BorshDeserialize::try_from_slice(data).map_err(|_| ());
+        Self(key.and_then(|key| {
+            let legacy = matches!(
+                key.key_type(),
+                near_crypto::KeyType::ED25519 | near_crypto::KeyType::SECP256K1
+            );
+            if legacy || extended_access_key_types { Ok(key) } else { Err(()) }
+        }))
     }
 
     fn decode(self) -> Result<near_crypto::PublicKey> {
@@ -535,7 +547,11 @@ impl<'a> VMLogic<'a> {
     }
 
     fn get_public_key(&mut self, ptr: u64, len: u64) -> Result<PublicKeyBuffer> {
-        Ok(PublicKeyBuffer::new(&get_memory_or_register!(self, ptr, len)?))
+        let extended_access_key_types = self.config.extended_access_key_types;
+        Ok(PublicKeyBuffer::new(
+            &get_memory_or_register!(self, ptr, len)?,
+            extended_access_key_types,
+        ))
     }
 
     // ###############
@@ -1,7 +1,7 @@
 mod alt_bn128;
 mod bls12381;
 mod context;
-mod dependencies;
+pub(crate) mod dependencies;
 pub mod errors;
 pub mod gas_counter;
 mod logic;
@@ -2,8 +2,9 @@ use crate::logic::mocks::mock_external::MockedExternal;
 use crate::logic::tests::helpers::*;
 use crate::logic::tests::vm_logic_builder::VMLogicBuilder;
 use crate::logic::types::PromiseResult;
+use crate::logic::{HostError, VMLogicError};
 
-use near_crypto::PublicKey;
+use near_crypto::{KeyType, PublicKey};
 use serde_json;
 
 fn vm_receipts<'a>(ext: &'a MockedExternal) -> Vec<impl serde::Serialize + 'a> {
@@ -549,6 +550,32 @@ fn test_promise_batch_action_add_key_with_function_call() {
     .assert_eq(&serde_json::to_string_pretty(&vm_receipts(&logic_builder.ext)).unwrap());
 }
 
+#[test]
+fn test_promise_batch_action_add_key_extended_key_types() {
+    for key_type in [KeyType::SECP256R1, KeyType::MULTISIG] {
+        let key = borsh::to_vec(&PublicKey::empty(key_type)).unwrap();
+
+        // Before `ExtendedAccessKeyTypes` these keys did not deserialize.
+        let mut logic_builder = VMLogicBuilder::default();
+        logic_builder.config.extended_access_key_types = false;
+        let mut logic = logic_builder.build();
+        let index =
+            promise_create(&mut logic, b"rick.test", 0, 0).expect("should create a promise");
+        assert_eq!(
+            promise_batch_action_add_key_with_full_access(&mut logic, index, &key, 1),
+            Err(VMLogicError::HostError(HostError::InvalidPublicKey))
+        );
+
+        let mut logic_builder = VMLogicBuilder::default();
+        logic_builder.config.extended_access_key_types = true;
+        let mut logic = logic_builder.build();
+        let index =
+            promise_create(&mut logic, b"rick.test", 0, 0).expect("should create a promise");
+        promise_batch_action_add_key_with_full_access(&mut logic, index, &key, 1)
+            .expect("should add the key");
+    }
+}
+
 #[test]
 fn test_promise_batch_then() {
     let mut logic_builder = VMLogicBuilder::default();
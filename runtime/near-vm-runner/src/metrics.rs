@@ -41,6 +41,24 @@ static COMPILED_CONTRACT_CACHE_HITS_TOTAL: LazyLock<IntCounterVec> = LazyLock::n
     .unwrap()
 });
 
+static SHADOW_EXECUTIONS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_vm_shadow_executions_total",
+        "The number of function calls that were additionally executed on the shadow VM",
+        &["vm_kind"],
+    )
+    .unwrap()
+});
+
+static SHADOW_DIVERGENCES_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_vm_shadow_divergences_total",
+        "The number of shadow executions whose outcome or gas differed from the primary VM, by what differed",
+        &["vm_kind", "kind"],
+    )
+    .unwrap()
+});
+
 #[derive(Default, Copy, Clone)]
 struct Metrics {
     near_vm_compilation_time: Duration,
@@ -55,16 +73,16 @@ struct Metrics {
 pub(crate) fn compilation_duration(kind: near_parameters::vm::VMKind, duration: Duration) {
     use near_parameters::vm::VMKind;
     METRICS.with_borrow_mut(|m| match kind {
-        VMKind::Wasmer0 => unreachable!(),
         VMKind::Wasmtime => m.wasmtime_compilation_time += duration,
-        VMKind::Wasmer2 => unreachable!(),
         VMKind::NearVm => m.near_vm_compilation_time += duration,
+        // These VMs are no longer supported and never compile anything.
+        VMKind::Wasmer0 | VMKind::Wasmer2 => {}
     });
 }
 
 /// Updates metrics to record a compiled-contract cache lookup,
 /// where is_hit=true indicates that we found an entry in the cache.
-#[cfg(all(feature = "near_vm", target_arch = "x86_64"))]
+#[cfg(any(all(feature = "near_vm", target_arch = "x86_64"), feature = "wasmtime_vm"))]
 pub(crate) fn record_compiled_contract_cache_lookup(is_hit: bool) {
     METRICS.with_borrow_mut(|m| {
         m.compiled_contract_cache_lookups += 1;
@@ -74,6 +92,23 @@ pub(crate) fn record_compiled_contract_cache_lookup(is_hit: bool) {
     });
 }
 
+/// Records a shadow execution on the `shadow_vm_kind` VM and what diverged, if anything.
+pub(crate) fn record_shadow_execution(
+    shadow_vm_kind: near_parameters::vm::VMKind,
+    divergence: Option<&'static str>,
+) {
+    let vm_kind: &str = match shadow_vm_kind {
+        near_parameters::vm::VMKind::NearVm => "near_vm",
+        near_parameters::vm::VMKind::Wasmtime => "wasmtime",
+        near_parameters::vm::VMKind::Wasmer0 => "wasmer0",
+        near_parameters::vm::VMKind::Wasmer2 => "wasmer2",
+    };
+    SHADOW_EXECUTIONS_TOTAL.with_label_values(&[vm_kind]).inc();
+    if let Some(kind) = divergence {
+        SHADOW_DIVERGENCES_TOTAL.with_label_values(&[vm_kind, kind]).inc();
+    }
+}
+
 pub fn reset_metrics() {
     METRICS.with_borrow_mut(|m| *m = Metrics::default());
 }
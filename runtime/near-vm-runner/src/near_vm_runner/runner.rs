@@ -524,79 +524,15 @@ impl near_vm_vm::Tunables for &NearVM {
 
     /// Instrumentation configuration: stack limiter config
     fn stack_limiter_cfg(&self) -> Box<dyn finite_wasm::max_stack::SizeConfig> {
-        Box::new(MaxStackCfg)
+        Box::new(prepare::MaxStackCfg)
     }
 
     /// Instrumentation configuration: gas accounting config
     fn gas_cfg(&self) -> Box<dyn finite_wasm::wasmparser::VisitOperator<Output = u64>> {
-        Box::new(GasCostCfg(u64::from(self.config.regular_op_cost)))
+        Box::new(prepare::GasCostCfg(u64::from(self.config.regular_op_cost)))
     }
 }
 
-struct MaxStackCfg;
-
-impl finite_wasm::max_stack::SizeConfig for MaxStackCfg {
-    fn size_of_value(&self, ty: finite_wasm::wasmparser::ValType) -> u8 {
-        use finite_wasm::wasmparser::ValType;
-        match ty {
-            ValType::I32 => 4,
-            ValType::I64 => 8,
-            ValType::F32 => 4,
-            ValType::F64 => 8,
-            ValType::V128 => 16,
-            ValType::Ref(_) => 8,
-        }
-    }
-    fn size_of_function_activation(
-        &self,
-        locals: &prefix_sum_vec::PrefixSumVec<finite_wasm::wasmparser::ValType, u32>,
-    ) -> u64 {
-        let mut res = 64_u64; // Rough accounting for rip, rbp and some registers spilled. Not exact.
-        let mut last_idx_plus_one = 0_u64;
-        for (idx, local) in locals {
-            let idx = u64::from(*idx);
-            res = res.saturating_add(
-                idx.checked_sub(last_idx_plus_one)
-                    .expect("prefix-sum-vec indices went backwards")
-                    .saturating_add(1)
-                    .saturating_mul(u64::from(self.size_of_value(*local))),
-            );
-            last_idx_plus_one = idx.saturating_add(1);
-        }
-        res
-    }
-}
-
-struct GasCostCfg(u64);
-
-macro_rules! gas_cost {
-    ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
-        $(
-            fn $visit(&mut self $($(, $arg: $argty)*)?) -> u64 {
-                gas_cost!(@@$proposal $op self $({ $($arg: $argty),* })? => $visit)
-            }
-        )*
-    };
-
-    (@@mvp $_op:ident $_self:ident $({ $($_arg:ident: $_argty:ty),* })? => visit_block) => {
-        0
-    };
-    (@@mvp $_op:ident $_self:ident $({ $($_arg:ident: $_argty:ty),* })? => visit_end) => {
-        0
-    };
-    (@@mvp $_op:ident $_self:ident $({ $($_arg:ident: $_argty:ty),* })? => visit_else) => {
-        0
-    };
-    (@@$_proposal:ident $_op:ident $self:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident) => {
-        $self.0
-    };
-}
-
-impl<'a> finite_wasm::wasmparser::VisitOperator<'a> for GasCostCfg {
-    type Output = u64;
-    finite_wasm::wasmparser::for_each_operator!(gas_cost);
-}
-
 impl crate::runner::VM for NearVM {
     fn prepare(
         self: Box<Self>,
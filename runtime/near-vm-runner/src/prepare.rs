@@ -6,6 +6,9 @@ use near_parameters::vm::{Config, VMKind};
 
 mod prepare_v2;
 
+#[cfg(all(feature = "near_vm", target_arch = "x86_64"))]
+pub(crate) use prepare_v2::{GasCostCfg, MaxStackCfg};
+
 /// Loads the given module given in `original_code`, performs some checks on it and
 /// does some preprocessing.
 ///
@@ -281,8 +281,8 @@ pub(crate) fn prepare_contract(
     }
 
     let res = finite_wasm::Analysis::new()
-        .with_stack(Box::new(SimpleMaxStackCfg))
-        .with_gas(Box::new(SimpleGasCostCfg(u64::from(config.regular_op_cost))))
+        .with_stack(Box::new(MaxStackCfg))
+        .with_gas(Box::new(GasCostCfg(u64::from(config.regular_op_cost))))
         .analyze(&lightly_steamed)
         .map_err(|err| {
             tracing::error!(?err, ?kind, "Analysis failed");
@@ -297,10 +297,13 @@ pub(crate) fn prepare_contract(
     Ok(res)
 }
 
-// TODO: refactor to avoid copy-paste with the ones currently defined in near_vm_runner
-struct SimpleMaxStackCfg;
+/// Stack size accounting used by every VM.
+///
+/// NearVM instruments the code itself but uses the same configuration, so that stack limits are
+/// hit at exactly the same call depth regardless of the VM.
+pub(crate) struct MaxStackCfg;
 
-impl finite_wasm::max_stack::SizeConfig for SimpleMaxStackCfg {
+impl finite_wasm::max_stack::SizeConfig for MaxStackCfg {
     fn size_of_value(&self, ty: wp::ValType) -> u8 {
         use wp::ValType;
         match ty {
@@ -332,7 +335,8 @@ impl finite_wasm::max_stack::SizeConfig for SimpleMaxStackCfg {
     }
 }
 
-struct SimpleGasCostCfg(u64);
+/// Per-instruction gas costs used by every VM, see [`MaxStackCfg`].
+pub(crate) struct GasCostCfg(pub(crate) u64);
 
 macro_rules! gas_cost {
     ($( @$proposal:ident $op:ident $({ $($arg:ident: $argty:ty),* })? => $visit:ident)*) => {
@@ -357,7 +361,7 @@ macro_rules! gas_cost {
     };
 }
 
-impl<'a> wp::VisitOperator<'a> for SimpleGasCostCfg {
+impl<'a> wp::VisitOperator<'a> for GasCostCfg {
     type Output = u64;
     wp::for_each_operator!(gas_cost);
 }
@@ -0,0 +1,921 @@
+//! Shadow execution of contracts on a secondary VM.
+//!
+//! The primary VM executes the function call against the real [`External`] as usual, while every
+//! host call it makes is recorded together with its result and the storage access fees it
+//! incurred. The same call is then executed by the shadow VM against a replay of that recording,
+//! so the shadow execution never observes or modifies the real state. Any difference between the
+//! two outcomes, or in the sequence of host calls the two VMs make, is reported as a divergence.
+//!
+//! Shadow execution is a debugging aid. It never affects the outcome of the primary execution.
+use crate::logic::dependencies::Result;
+use crate::logic::dependencies::sealed::StorageAccessTrackerSeal;
+use crate::logic::errors::{AnyError, VMLogicError, VMRunnerError};
+use crate::logic::types::ReceiptIndex;
+use crate::logic::{External, StorageAccessTracker, VMContext, VMOutcome, ValuePtr};
+use crate::runner::VMResult;
+use crate::{PreparedContract, run};
+use borsh::BorshSerialize;
+use near_crypto::PublicKey;
+use near_parameters::RuntimeFeesConfig;
+use near_parameters::vm::VMKind;
+use near_primitives_core::hash::CryptoHash;
+use near_primitives_core::types::{AccountId, Balance, Gas, GasWeight, Nonce};
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+
+/// Run the `primary` contract and replay the same call on the `shadow` contract.
+///
+/// Both contracts must have been prepared for the same code, method and gas. The result of the
+/// primary execution is returned unchanged, any divergence of the shadow execution is logged and
+/// counted in the metrics.
+pub fn run_with_shadow(
+    primary: Box<dyn PreparedContract>,
+    shadow: Box<dyn PreparedContract>,
+    shadow_vm_kind: VMKind,
+    ext: &mut (dyn External + Send),
+    context: &VMContext,
+    fees_config: Arc<RuntimeFeesConfig>,
+) -> VMResult {
+    let (result, divergence) = run_and_compare(primary, shadow, ext, context, fees_config);
+    if let Some(divergence) = &divergence {
+        tracing::error!(
+            target: "vm",
+            ?shadow_vm_kind,
+            account_id = %context.current_account_id,
+            kind = divergence.kind,
+            primary = %divergence.primary,
+            shadow = %divergence.shadow,
+            "shadow execution diverged from the primary execution"
+        );
+    }
+    #[cfg(feature = "metrics")]
+    if result.is_ok() {
+        crate::metrics::record_shadow_execution(
+            shadow_vm_kind,
+            divergence.as_ref().map(|d| d.kind),
+        );
+    }
+    result
+}
+
+/// Same as [`run_with_shadow`], but returns the divergence instead of reporting it.
+pub(crate) fn run_and_compare(
+    primary: Box<dyn PreparedContract>,
+    shadow: Box<dyn PreparedContract>,
+    ext: &mut (dyn External + Send),
+    context: &VMContext,
+    fees_config: Arc<RuntimeFeesConfig>,
+) -> (VMResult, Option<ShadowDivergence>) {
+    let mut recording = RecordingExternal::new(ext);
+    let result = run(primary, &mut recording, context, Arc::clone(&fees_config));
+    // Errors from the primary execution are not a property of the contract, but of the node
+    // (e.g. storage errors) so there is nothing meaningful to compare against.
+    let divergence = match &result {
+        Ok(primary_outcome) => {
+            replay_shadow(shadow, recording.into_calls(), primary_outcome, context, fees_config)
+        }
+        Err(_) => None,
+    };
+    (result, divergence)
+}
+
+/// Describes how the shadow execution differed from the primary one.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ShadowDivergence {
+    /// What differed, e.g. `burnt_gas` or `host_calls`.
+    pub kind: &'static str,
+    pub primary: String,
+    pub shadow: String,
+}
+
+fn replay_shadow(
+    shadow: Box<dyn PreparedContract>,
+    calls: Vec<HostCall>,
+    primary: &VMOutcome,
+    context: &VMContext,
+    fees_config: Arc<RuntimeFeesConfig>,
+) -> Option<ShadowDivergence> {
+    let mut replay = ReplayExternal::new(calls, context.current_account_id.clone());
+    let shadow_result = shadow.run(&mut replay, context, fees_config);
+    if let Some(mismatch) = replay.take_mismatch() {
+        return Some(mismatch.into_divergence());
+    }
+    let shadow_outcome = match shadow_result {
+        Ok(outcome) => outcome,
+        Err(VMRunnerError::ExternalError(err)) => {
+            let divergence = match err.downcast::<HostCallMismatch>() {
+                Ok(mismatch) => mismatch.into_divergence(),
+                Err(_) => ShadowDivergence {
+                    kind: "runner_error",
+                    primary: "Ok".to_string(),
+                    shadow: "external error".to_string(),
+                },
+            };
+            return Some(divergence);
+        }
+        Err(err) => {
+            return Some(ShadowDivergence {
+                kind: "runner_error",
+                primary: "Ok".to_string(),
+                shadow: err.to_string(),
+            });
+        }
+    };
+    if replay.remaining() > 0 {
+        return Some(ShadowDivergence {
+            kind: "host_calls",
+            primary: format!("{} host calls", replay.calls.len()),
+            shadow: format!("{} host calls", replay.calls.len() - replay.remaining()),
+        });
+    }
+    compare_outcomes(primary, &shadow_outcome)
+}
+
+/// Compare the consensus-relevant parts of two outcomes of the same function call.
+pub(crate) fn compare_outcomes(
+    primary: &VMOutcome,
+    shadow: &VMOutcome,
+) -> Option<ShadowDivergence> {
+    macro_rules! compare {
+        ($($field:ident),*) => {
+            $(
+                if primary.$field != shadow.$field {
+                    return Some(ShadowDivergence {
+                        kind: stringify!($field),
+                        primary: format!("{:?}", primary.$field),
+                        shadow: format!("{:?}", shadow.$field),
+                    });
+                }
+            )*
+        };
+    }
+    compare!(
+        aborted,
+        burnt_gas,
+        used_gas,
+        compute_usage,
+        balance,
+        storage_usage,
+        return_data,
+        logs
+    );
+    None
+}
+
+/// A storage access fee charged through the [`StorageAccessTracker`].
+#[derive(Clone, Copy, Debug)]
+enum Charge {
+    TrieNodeTouched(u64),
+    CachedTrieNodeAccess(u64),
+    DerefWriteEvictedValueBytes(u64),
+    DerefRemovedValueBytes(u64),
+}
+
+impl Charge {
+    fn apply(self, tracker: &mut dyn StorageAccessTracker) -> Result<()> {
+        match self {
+            Charge::TrieNodeTouched(count) => tracker.trie_node_touched(count),
+            Charge::CachedTrieNodeAccess(count) => tracker.cached_trie_node_access(count),
+            Charge::DerefWriteEvictedValueBytes(bytes) => {
+                tracker.deref_write_evicted_value_bytes(bytes)
+            }
+            Charge::DerefRemovedValueBytes(bytes) => tracker.deref_removed_value_bytes(bytes),
+        }
+    }
+}
+
+/// Forwards the charges to the actual tracker, remembering them along the way.
+struct RecordingTracker<'a> {
+    inner: &'a mut dyn StorageAccessTracker,
+    charges: Vec<Charge>,
+}
+
+impl RecordingTracker<'_> {
+    fn charge(&mut self, charge: Charge) -> Result<()> {
+        self.charges.push(charge);
+        charge.apply(self.inner)
+    }
+}
+
+impl StorageAccessTrackerSeal for RecordingTracker<'_> {}
+impl StorageAccessTracker for RecordingTracker<'_> {
+    fn trie_node_touched(&mut self, count: u64) -> Result<()> {
+        self.charge(Charge::TrieNodeTouched(count))
+    }
+    fn cached_trie_node_access(&mut self, count: u64) -> Result<()> {
+        self.charge(Charge::CachedTrieNodeAccess(count))
+    }
+    fn deref_write_evicted_value_bytes(&mut self, bytes: u64) -> Result<()> {
+        self.charge(Charge::DerefWriteEvictedValueBytes(bytes))
+    }
+    fn deref_removed_value_bytes(&mut self, bytes: u64) -> Result<()> {
+        self.charge(Charge::DerefRemovedValueBytes(bytes))
+    }
+}
+
+#[derive(Debug)]
+enum Output {
+    Unit,
+    Bool(bool),
+    Bytes(Option<Vec<u8>>),
+    ValuePtr(Option<u32>),
+    Value(Vec<u8>),
+    Hash(CryptoHash),
+    Size(usize),
+    Balance(Balance),
+    Stake(Option<Balance>),
+    ReceiptIndex(ReceiptIndex),
+    Yield(ReceiptIndex, CryptoHash),
+    AccountId(AccountId),
+}
+
+/// A single call to the [`External`] made by the primary execution.
+struct HostCall {
+    /// Name of the `External` method.
+    method: &'static str,
+    /// Digest of the arguments the method was called with.
+    args: CryptoHash,
+    /// Storage access fees incurred by the call, in order.
+    charges: Vec<Charge>,
+    /// The returned value, or `None` if the call has failed.
+    output: Option<Output>,
+}
+
+fn digest(args: impl BorshSerialize) -> CryptoHash {
+    CryptoHash::hash_borsh(args)
+}
+
+struct RecordingExternal<'a> {
+    inner: &'a mut (dyn External + Send),
+    calls: RefCell<Vec<HostCall>>,
+}
+
+impl<'a> RecordingExternal<'a> {
+    fn new(inner: &'a mut (dyn External + Send)) -> Self {
+        Self { inner, calls: RefCell::new(Vec::new()) }
+    }
+
+    fn into_calls(self) -> Vec<HostCall> {
+        self.calls.into_inner()
+    }
+
+    fn push(&self, method: &'static str, args: CryptoHash, output: Option<Output>) {
+        self.calls.borrow_mut().push(HostCall { method, args, charges: Vec::new(), output });
+    }
+
+    fn record_storage<T>(
+        calls: &RefCell<Vec<HostCall>>,
+        method: &'static str,
+        args: CryptoHash,
+        tracker: &mut dyn StorageAccessTracker,
+        call: impl FnOnce(&mut dyn StorageAccessTracker) -> Result<T>,
+        output: impl FnOnce(&T) -> Output,
+    ) -> Result<T> {
+        let mut tracker = RecordingTracker { inner: tracker, charges: Vec::new() };
+        let result = call(&mut tracker);
+        let output = result.as_ref().ok().map(output);
+        calls.borrow_mut().push(HostCall { method, args, charges: tracker.charges, output });
+        result
+    }
+}
+
+struct RecordingValuePtr<'a> {
+    inner: Box<dyn ValuePtr + 'a>,
+    args: CryptoHash,
+    calls: &'a RefCell<Vec<HostCall>>,
+}
+
+impl ValuePtr for RecordingValuePtr<'_> {
+    fn len(&self) -> u32 {
+        self.inner.len()
+    }
+
+    fn deref(&self, storage_tracker: &mut dyn StorageAccessTracker) -> Result<Vec<u8>> {
+        RecordingExternal::record_storage(
+            self.calls,
+            "value_ptr_deref",
+            self.args,
+            storage_tracker,
+            |tracker| self.inner.deref(tracker),
+            |value| Output::Value(value.clone()),
+        )
+    }
+}
+
+impl External for RecordingExternal<'_> {
+    fn storage_set<'a>(
+        &'a mut self,
+        access_tracker: &mut dyn StorageAccessTracker,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        let inner = &mut *self.inner;
+        Self::record_storage(
+            &self.calls,
+            "storage_set",
+            digest((key, value)),
+            access_tracker,
+            |tracker| inner.storage_set(tracker, key, value),
+            |evicted| Output::Bytes(evicted.clone()),
+        )
+    }
+
+    fn storage_get<'a>(
+        &'a self,
+        access_tracker: &mut dyn StorageAccessTracker,
+        key: &[u8],
+    ) -> Result<Option<Box<dyn ValuePtr + 'a>>> {
+        let args = digest(key);
+        let ptr = Self::record_storage(
+            &self.calls,
+            "storage_get",
+            args,
+            access_tracker,
+            |tracker| self.inner.storage_get(tracker, key),
+            |ptr| Output::ValuePtr(ptr.as_ref().map(|ptr| ptr.len())),
+        )?;
+        Ok(ptr.map(|inner| {
+            Box::new(RecordingValuePtr { inner, args, calls: &self.calls })
+                as Box<dyn ValuePtr + 'a>
+        }))
+    }
+
+    fn storage_remove(
+        &mut self,
+        access_tracker: &mut dyn StorageAccessTracker,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        let inner = &mut *self.inner;
+        Self::record_storage(
+            &self.calls,
+            "storage_remove",
+            digest(key),
+            access_tracker,
+            |tracker| inner.storage_remove(tracker, key),
+            |removed| Output::Bytes(removed.clone()),
+        )
+    }
+
+    fn storage_has_key(
+        &mut self,
+        access_tracker: &mut dyn StorageAccessTracker,
+        key: &[u8],
+    ) -> Result<bool> {
+        let inner = &mut *self.inner;
+        Self::record_storage(
+            &self.calls,
+            "storage_has_key",
+            digest(key),
+            access_tracker,
+            |tracker| inner.storage_has_key(tracker, key),
+            |has| Output::Bool(*has),
+        )
+    }
+
+    fn generate_data_id(&mut self) -> CryptoHash {
+        let data_id = self.inner.generate_data_id();
+        self.push("generate_data_id", digest(()), Some(Output::Hash(data_id)));
+        data_id
+    }
+
+    fn get_recorded_storage_size(&self) -> usize {
+        let size = self.inner.get_recorded_storage_size();
+        self.push("get_recorded_storage_size", digest(()), Some(Output::Size(size)));
+        size
+    }
+
+    fn validator_stake(&self, account_id: &AccountId) -> Result<Option<Balance>> {
+        let stake = self.inner.validator_stake(account_id);
+        let output = stake.as_ref().ok().map(|stake| Output::Stake(*stake));
+        self.push("validator_stake", digest(account_id), output);
+        stake
+    }
+
+    fn validator_total_stake(&self) -> Result<Balance> {
+        let stake = self.inner.validator_total_stake();
+        let output = stake.as_ref().ok().map(|stake| Output::Balance(*stake));
+        self.push("validator_total_stake", digest(()), output);
+        stake
+    }
+
+    fn create_action_receipt(
+        &mut self,
+        receipt_indices: Vec<ReceiptIndex>,
+        receiver_id: AccountId,
+    ) -> Result<ReceiptIndex, VMLogicError> {
+        let args = digest((&receipt_indices, &receiver_id));
+        let index = self.inner.create_action_receipt(receipt_indices, receiver_id);
+        let output = index.as_ref().ok().map(|index| Output::ReceiptIndex(*index));
+        self.push("create_action_receipt", args, output);
+        index
+    }
+
+    fn create_promise_yield_receipt(
+        &mut self,
+        receiver_id: AccountId,
+    ) -> Result<(ReceiptIndex, CryptoHash), VMLogicError> {
+        let args = digest(&receiver_id);
+        let result = self.inner.create_promise_yield_receipt(receiver_id);
+        let output = result.as_ref().ok().map(|(index, data_id)| Output::Yield(*index, *data_id));
+        self.push("create_promise_yield_receipt", args, output);
+        result
+    }
+
+    fn submit_promise_resume_data(
+        &mut self,
+        data_id: CryptoHash,
+        data: Vec<u8>,
+    ) -> Result<bool, VMLogicError> {
+        let args = digest((&data_id, &data));
+        let result = self.inner.submit_promise_resume_data(data_id, data);
+        let output = result.as_ref().ok().map(|resumed| Output::Bool(*resumed));
+        self.push("submit_promise_resume_data", args, output);
+        result
+    }
+
+    fn append_action_create_account(
+        &mut self,
+        receipt_index: ReceiptIndex,
+    ) -> Result<(), VMLogicError> {
+        let result = self.inner.append_action_create_account(receipt_index);
+        let output = result.as_ref().ok().map(|()| Output::Unit);
+        self.push("append_action_create_account", digest(receipt_index), output);
+        result
+    }
+
+    fn append_action_deploy_contract(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        code: Vec<u8>,
+    ) -> Result<(), VMLogicError> {
+        let args = digest((receipt_index, &code));
+        let result = self.inner.append_action_deploy_contract(receipt_index, code);
+        let output = result.as_ref().ok().map(|()| Output::Unit);
+        self.push("append_action_deploy_contract", args, output);
+        result
+    }
+
+    fn append_action_function_call_weight(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        method_name: Vec<u8>,
+        args: Vec<u8>,
+        attached_deposit: Balance,
+        prepaid_gas: Gas,
+        gas_weight: GasWeight,
+    ) -> Result<(), VMLogicError> {
+        let call_digest = digest((
+            receipt_index,
+            &method_name,
+            &args,
+            attached_deposit,
+            prepaid_gas,
+            gas_weight.0,
+        ));
+        let result = self.inner.append_action_function_call_weight(
+            receipt_index,
+            method_name,
+            args,
+            attached_deposit,
+            prepaid_gas,
+            gas_weight,
+        );
+        let output = result.as_ref().ok().map(|()| Output::Unit);
+        self.push("append_action_function_call_weight", call_digest, output);
+        result
+    }
+
+    fn append_action_transfer(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        deposit: Balance,
+    ) -> Result<(), VMLogicError> {
+        let result = self.inner.append_action_transfer(receipt_index, deposit);
+        let output = result.as_ref().ok().map(|()| Output::Unit);
+        self.push("append_action_transfer", digest((receipt_index, deposit)), output);
+        result
+    }
+
+    fn append_action_stake(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        stake: Balance,
+        public_key: PublicKey,
+    ) {
+        let args = digest((receipt_index, stake, &public_key));
+        self.inner.append_action_stake(receipt_index, stake, public_key);
+        self.push("append_action_stake", args, Some(Output::Unit));
+    }
+
+    fn append_action_add_key_with_full_access(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        public_key: PublicKey,
+        nonce: Nonce,
+    ) {
+        let args = digest((receipt_index, &public_key, nonce));
+        self.inner.append_action_add_key_with_full_access(receipt_index, public_key, nonce);
+        self.push("append_action_add_key_with_full_access", args, Some(Output::Unit));
+    }
+
+    fn append_action_add_key_with_function_call(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        public_key: PublicKey,
+        nonce: Nonce,
+        allowance: Option<Balance>,
+        receiver_id: AccountId,
+        method_names: Vec<Vec<u8>>,
+    ) -> Result<(), VMLogicError> {
+        let args =
+            digest((receipt_index, &public_key, nonce, allowance, &receiver_id, &method_names));
+        let result = self.inner.append_action_add_key_with_function_call(
+            receipt_index,
+            public_key,
+            nonce,
+            allowance,
+            receiver_id,
+            method_names,
+        );
+        let output = result.as_ref().ok().map(|()| Output::Unit);
+        self.push("append_action_add_key_with_function_call", args, output);
+        result
+    }
+
+    fn append_action_delete_key(&mut self, receipt_index: ReceiptIndex, public_key: PublicKey) {
+        let args = digest((receipt_index, &public_key));
+        self.inner.append_action_delete_key(receipt_index, public_key);
+        self.push("append_action_delete_key", args, Some(Output::Unit));
+    }
+
+    fn append_action_delete_account(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        beneficiary_id: AccountId,
+    ) -> Result<(), VMLogicError> {
+        let args = digest((receipt_index, &beneficiary_id));
+        let result = self.inner.append_action_delete_account(receipt_index, beneficiary_id);
+        let output = result.as_ref().ok().map(|()| Output::Unit);
+        self.push("append_action_delete_account", args, output);
+        result
+    }
+
+    fn get_receipt_receiver(&self, receipt_index: ReceiptIndex) -> &AccountId {
+        let receiver_id = self.inner.get_receipt_receiver(receipt_index);
+        let output = Some(Output::AccountId(receiver_id.clone()));
+        self.push("get_receipt_receiver", digest(receipt_index), output);
+        receiver_id
+    }
+}
+
+/// The shadow execution made a host call that the primary execution did not make.
+#[derive(Debug, PartialEq, Eq)]
+struct HostCallMismatch {
+    /// Index of the host call in the recording.
+    position: usize,
+    /// The host call made by the primary execution, if any.
+    expected: Option<&'static str>,
+    /// The host call made by the shadow execution.
+    actual: &'static str,
+}
+
+impl HostCallMismatch {
+    fn into_divergence(self) -> ShadowDivergence {
+        ShadowDivergence {
+            kind: "host_calls",
+            primary: format!("{:?} at #{}", self.expected, self.position),
+            shadow: format!("{:?} at #{}", self.actual, self.position),
+        }
+    }
+}
+
+struct ReplayExternal {
+    calls: Vec<HostCall>,
+    next: Cell<usize>,
+    /// Host calls that cannot fail record their mismatch here instead of returning an error.
+    mismatch: Cell<Option<HostCallMismatch>>,
+    /// Returned from `get_receipt_receiver` after a mismatch.
+    fallback_account_id: AccountId,
+}
+
+impl ReplayExternal {
+    fn new(calls: Vec<HostCall>, fallback_account_id: AccountId) -> Self {
+        Self { calls, next: Cell::new(0), mismatch: Cell::new(None), fallback_account_id }
+    }
+
+    fn remaining(&self) -> usize {
+        self.calls.len() - self.next.get()
+    }
+
+    fn take_mismatch(&self) -> Option<HostCallMismatch> {
+        self.mismatch.take()
+    }
+
+    /// Find the next recorded call, which must match the call being made by the shadow.
+    fn next_call(
+        &self,
+        method: &'static str,
+        args: CryptoHash,
+    ) -> Result<&HostCall, HostCallMismatch> {
+        let position = self.next.get();
+        match self.calls.get(position) {
+            Some(call) if call.method == method && call.args == args => {
+                self.next.set(position + 1);
+                Ok(call)
+            }
+            call => {
+                Err(HostCallMismatch { position, expected: call.map(|c| c.method), actual: method })
+            }
+        }
+    }
+
+    /// Replay the next recorded call, charging the recorded storage fees to the shadow's tracker.
+    fn replay(
+        &self,
+        method: &'static str,
+        args: CryptoHash,
+        tracker: &mut dyn StorageAccessTracker,
+    ) -> Result<&Output> {
+        let call = self.next_call(method, args).map_err(mismatch_error)?;
+        for charge in &call.charges {
+            charge.apply(tracker)?;
+        }
+        // The primary execution failed this call, but the shadow got past all the charges.
+        call.output.as_ref().ok_or_else(|| {
+            mismatch_error(HostCallMismatch {
+                position: self.next.get() - 1,
+                expected: None,
+                actual: method,
+            })
+        })
+    }
+
+    /// Replay a call that has no storage fees associated with it.
+    fn replay_plain(&self, method: &'static str, args: CryptoHash) -> Result<&Output> {
+        let call = self.next_call(method, args).map_err(mismatch_error)?;
+        call.output.as_ref().ok_or_else(|| {
+            mismatch_error(HostCallMismatch {
+                position: self.next.get() - 1,
+                expected: None,
+                actual: method,
+            })
+        })
+    }
+
+    /// Replay a call whose signature does not allow reporting failures.
+    fn replay_infallible(&self, method: &'static str, args: CryptoHash) -> Option<&Output> {
+        match self.next_call(method, args) {
+            Ok(call) => call.output.as_ref(),
+            Err(mismatch) => {
+                self.record_mismatch(mismatch);
+                None
+            }
+        }
+    }
+
+    fn record_mismatch(&self, mismatch: HostCallMismatch) {
+        // Only the first mismatch is interesting, the rest are likely its consequences.
+        let previous = self.mismatch.take();
+        self.mismatch.set(Some(previous.unwrap_or(mismatch)));
+    }
+
+    fn unexpected_output(&self, method: &'static str) -> VMLogicError {
+        mismatch_error(HostCallMismatch {
+            position: self.next.get().saturating_sub(1),
+            expected: None,
+            actual: method,
+        })
+    }
+}
+
+fn mismatch_error(mismatch: HostCallMismatch) -> VMLogicError {
+    VMLogicError::ExternalError(AnyError::new(mismatch))
+}
+
+struct ReplayValuePtr<'a> {
+    replay: &'a ReplayExternal,
+    len: u32,
+    args: CryptoHash,
+}
+
+impl ValuePtr for ReplayValuePtr<'_> {
+    fn len(&self) -> u32 {
+        self.len
+    }
+
+    fn deref(&self, storage_tracker: &mut dyn StorageAccessTracker) -> Result<Vec<u8>> {
+        match self.replay.replay("value_ptr_deref", self.args, storage_tracker)? {
+            Output::Value(value) => Ok(value.clone()),
+            _ => Err(self.replay.unexpected_output("value_ptr_deref")),
+        }
+    }
+}
+
+impl External for ReplayExternal {
+    fn storage_set<'a>(
+        &'a mut self,
+        access_tracker: &mut dyn StorageAccessTracker,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        match self.replay("storage_set", digest((key, value)), access_tracker)? {
+            Output::Bytes(evicted) => Ok(evicted.clone()),
+            _ => Err(self.unexpected_output("storage_set")),
+        }
+    }
+
+    fn storage_get<'a>(
+        &'a self,
+        access_tracker: &mut dyn StorageAccessTracker,
+        key: &[u8],
+    ) -> Result<Option<Box<dyn ValuePtr + 'a>>> {
+        let args = digest(key);
+        match self.replay("storage_get", args, access_tracker)? {
+            Output::ValuePtr(len) => Ok(len.map(|len| {
+                Box::new(ReplayValuePtr { replay: self, len, args }) as Box<dyn ValuePtr + 'a>
+            })),
+            _ => Err(self.unexpected_output("storage_get")),
+        }
+    }
+
+    fn storage_remove(
+        &mut self,
+        access_tracker: &mut dyn StorageAccessTracker,
+        key: &[u8],
+    ) -> Result<Option<Vec<u8>>> {
+        match self.replay("storage_remove", digest(key), access_tracker)? {
+            Output::Bytes(removed) => Ok(removed.clone()),
+            _ => Err(self.unexpected_output("storage_remove")),
+        }
+    }
+
+    fn storage_has_key(
+        &mut self,
+        access_tracker: &mut dyn StorageAccessTracker,
+        key: &[u8],
+    ) -> Result<bool> {
+        match self.replay("storage_has_key", digest(key), access_tracker)? {
+            Output::Bool(has) => Ok(*has),
+            _ => Err(self.unexpected_output("storage_has_key")),
+        }
+    }
+
+    fn generate_data_id(&mut self) -> CryptoHash {
+        match self.replay_infallible("generate_data_id", digest(())) {
+            Some(Output::Hash(data_id)) => *data_id,
+            _ => CryptoHash::default(),
+        }
+    }
+
+    fn get_recorded_storage_size(&self) -> usize {
+        match self.replay_infallible("get_recorded_storage_size", digest(())) {
+            Some(Output::Size(size)) => *size,
+            _ => 0,
+        }
+    }
+
+    fn validator_stake(&self, account_id: &AccountId) -> Result<Option<Balance>> {
+        match self.replay_plain("validator_stake", digest(account_id))? {
+            Output::Stake(stake) => Ok(*stake),
+            _ => Err(self.unexpected_output("validator_stake")),
+        }
+    }
+
+    fn validator_total_stake(&self) -> Result<Balance> {
+        match self.replay_plain("validator_total_stake", digest(()))? {
+            Output::Balance(stake) => Ok(*stake),
+            _ => Err(self.unexpected_output("validator_total_stake")),
+        }
+    }
+
+    fn create_action_receipt(
+        &mut self,
+        receipt_indices: Vec<ReceiptIndex>,
+        receiver_id: AccountId,
+    ) -> Result<ReceiptIndex, VMLogicError> {
+        let args = digest((&receipt_indices, &receiver_id));
+        match self.replay_plain("create_action_receipt", args)? {
+            Output::ReceiptIndex(index) => Ok(*index),
+            _ => Err(self.unexpected_output("create_action_receipt")),
+        }
+    }
+
+    fn create_promise_yield_receipt(
+        &mut self,
+        receiver_id: AccountId,
+    ) -> Result<(ReceiptIndex, CryptoHash), VMLogicError> {
+        match self.replay_plain("create_promise_yield_receipt", digest(&receiver_id))? {
+            Output::Yield(index, data_id) => Ok((*index, *data_id)),
+            _ => Err(self.unexpected_output("create_promise_yield_receipt")),
+        }
+    }
+
+    fn submit_promise_resume_data(
+        &mut self,
+        data_id: CryptoHash,
+        data: Vec<u8>,
+    ) -> Result<bool, VMLogicError> {
+        match self.replay_plain("submit_promise_resume_data", digest((&data_id, &data)))? {
+            Output::Bool(resumed) => Ok(*resumed),
+            _ => Err(self.unexpected_output("submit_promise_resume_data")),
+        }
+    }
+
+    fn append_action_create_account(
+        &mut self,
+        receipt_index: ReceiptIndex,
+    ) -> Result<(), VMLogicError> {
+        self.replay_plain("append_action_create_account", digest(receipt_index)).map(drop)
+    }
+
+    fn append_action_deploy_contract(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        code: Vec<u8>,
+    ) -> Result<(), VMLogicError> {
+        self.replay_plain("append_action_deploy_contract", digest((receipt_index, &code))).map(drop)
+    }
+
+    fn append_action_function_call_weight(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        method_name: Vec<u8>,
+        args: Vec<u8>,
+        attached_deposit: Balance,
+        prepaid_gas: Gas,
+        gas_weight: GasWeight,
+    ) -> Result<(), VMLogicError> {
+        let call_digest = digest((
+            receipt_index,
+            &method_name,
+            &args,
+            attached_deposit,
+            prepaid_gas,
+            gas_weight.0,
+        ));
+        self.replay_plain("append_action_function_call_weight", call_digest).map(drop)
+    }
+
+    fn append_action_transfer(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        deposit: Balance,
+    ) -> Result<(), VMLogicError> {
+        self.replay_plain("append_action_transfer", digest((receipt_index, deposit))).map(drop)
+    }
+
+    fn append_action_stake(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        stake: Balance,
+        public_key: PublicKey,
+    ) {
+        let args = digest((receipt_index, stake, &public_key));
+        self.replay_infallible("append_action_stake", args);
+    }
+
+    fn append_action_add_key_with_full_access(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        public_key: PublicKey,
+        nonce: Nonce,
+    ) {
+        let args = digest((receipt_index, &public_key, nonce));
+        self.replay_infallible("append_action_add_key_with_full_access", args);
+    }
+
+    fn append_action_add_key_with_function_call(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        public_key: PublicKey,
+        nonce: Nonce,
+        allowance: Option<Balance>,
+        receiver_id: AccountId,
+        method_names: Vec<Vec<u8>>,
+    ) -> Result<(), VMLogicError> {
+        let args =
+            digest((receipt_index, &public_key, nonce, allowance, &receiver_id, &method_names));
+        self.replay_plain("append_action_add_key_with_function_call", args).map(drop)
+    }
+
+    fn append_action_delete_key(&mut self, receipt_index: ReceiptIndex, public_key: PublicKey) {
+        let args = digest((receipt_index, &public_key));
+        self.replay_infallible("append_action_delete_key", args);
+    }
+
+    fn append_action_delete_account(
+        &mut self,
+        receipt_index: ReceiptIndex,
+        beneficiary_id: AccountId,
+    ) -> Result<(), VMLogicError> {
+        let args = digest((receipt_index, &beneficiary_id));
+        self.replay_plain("append_action_delete_account", args).map(drop)
+    }
+
+    fn get_receipt_receiver(&self, receipt_index: ReceiptIndex) -> &AccountId {
+        match self.replay_infallible("get_receipt_receiver", digest(receipt_index)) {
+            Some(Output::AccountId(receiver_id)) => receiver_id,
+            _ => &self.fallback_account_id,
+        }
+    }
+}
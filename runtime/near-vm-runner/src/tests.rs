@@ -5,6 +5,7 @@ mod fuzzers;
 mod regression_tests;
 mod rs_contract;
 mod runtime_errors;
+mod shadow;
 pub(crate) mod test_builder;
 mod ts_contract;
 mod wasm_validation;
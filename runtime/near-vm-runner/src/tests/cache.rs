@@ -1,5 +1,4 @@
-//! Tests that `ContractRuntimeCache` is working correctly. Currently testing only wasmer code, so disabled outside of x86_64
-#![cfg(target_arch = "x86_64")]
+//! Tests that `ContractRuntimeCache` is working correctly.
 
 use super::{create_context, test_vm_config, with_vm_variants};
 use crate::cache::{CompiledContractInfo, ContractRuntimeCache};
@@ -21,10 +20,9 @@ use std::sync::atomic::{AtomicBool, Ordering};
 fn test_caches_compilation_error() {
     let config = Arc::new(test_vm_config());
     with_vm_variants(&config, |vm_kind: VMKind| {
-        // The cache is currently properly implemented only for NearVM
         match vm_kind {
-            VMKind::NearVm => {}
-            VMKind::Wasmer0 | VMKind::Wasmer2 | VMKind::Wasmtime => return,
+            VMKind::NearVm | VMKind::Wasmtime => {}
+            VMKind::Wasmer0 | VMKind::Wasmer2 => return,
         }
         let cache = MockContractRuntimeCache::default();
         let code = [42; 1000];
@@ -63,8 +61,8 @@ fn test_does_not_cache_io_error() {
     let config = Arc::new(test_vm_config());
     with_vm_variants(&config, |vm_kind: VMKind| {
         match vm_kind {
-            VMKind::NearVm => {}
-            VMKind::Wasmer0 | VMKind::Wasmer2 | VMKind::Wasmtime => return,
+            VMKind::NearVm | VMKind::Wasmtime => {}
+            VMKind::Wasmer0 | VMKind::Wasmer2 => return,
         }
 
         let code = near_test_contracts::trivial_contract();
@@ -135,7 +133,36 @@ fn make_cached_contract_call_vm(
 }
 
 #[test]
-#[cfg(feature = "near_vm")]
+#[cfg(feature = "wasmtime_vm")]
+fn test_wasmtime_reports_corrupted_artifact() {
+    use crate::cache::{CompiledContract, get_contract_cache_key};
+    let config = Arc::new(Config { vm_kind: VMKind::Wasmtime, ..test_vm_config() });
+    let code = ContractCode::new(near_test_contracts::trivial_contract().to_vec(), None);
+    let code_hash = *code.hash();
+    let cache = MockContractRuntimeCache::default();
+    let key = get_contract_cache_key(code_hash, &config);
+    let record = CompiledContractInfo {
+        wasm_bytes: code.code().len() as u64,
+        compiled: CompiledContract::Code(vec![42; 64]),
+    };
+    cache.put(&key, record).unwrap();
+    let result = make_cached_contract_call_vm(
+        Arc::clone(&config),
+        &cache,
+        code_hash,
+        Some(&code),
+        "main",
+        10u64.pow(12),
+        VMKind::Wasmtime,
+    );
+    assert_matches!(
+        result.err(),
+        Some(VMRunnerError::CacheError(crate::logic::errors::CacheError::DeserializationError))
+    );
+}
+
+#[test]
+#[cfg(all(feature = "near_vm", target_arch = "x86_64"))]
 fn test_near_vm_artifact_output_stability() {
     use crate::near_vm_runner::NearVM;
     use crate::prepare;
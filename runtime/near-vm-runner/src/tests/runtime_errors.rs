@@ -393,6 +393,38 @@ fn test_stack_instrumentation_protocol_upgrade() {
         ]);
 }
 
+/// Every VM accounts the stack and gas with the same `MaxStackCfg` and `GasCostCfg`, so the
+/// stack overflows at the same call depth after burning the same amount of gas. Unlike the tests
+/// above, these run on Wasmtime too and fail if its outcome differs from the NearVM one.
+#[test]
+fn test_stack_limit_vm_parity() {
+    test_builder().wat(r#"(module (func $f (export "main") (call $f)))"#).opaque_error().expect(
+        &expect![[r#"
+            VMOutcome: balance 4 storage_usage 12 return data None burnt gas 30418898602 used gas 30418898602
+            Err: ...
+        "#]],
+    );
+
+    let wat = r#"
+        (module
+          (func $f1 (export "f1")
+            (local i32)
+            (call $f1))
+          (func $f2 (export "f2")
+            (local i32 i32 i32 i32)
+            (call $f2))
+        )
+    "#;
+    test_builder().wat(wat).method("f1").opaque_error().expect(&expect![[r#"
+        VMOutcome: balance 4 storage_usage 12 return data None burnt gas 31825672528 used gas 31825672528
+        Err: ...
+    "#]]);
+    test_builder().wat(wat).method("f2").opaque_error().expect(&expect![[r#"
+        VMOutcome: balance 4 storage_usage 12 return data None burnt gas 29757263944 used gas 29757263944
+        Err: ...
+    "#]]);
+}
+
 #[test]
 fn test_memory_grow() {
     test_builder()
@@ -0,0 +1,140 @@
+use super::{create_context, test_vm_config};
+use crate::logic::Config;
+use crate::logic::mocks::mock_external::MockedExternal;
+use crate::runner::VMKindExt;
+use crate::shadow::run_and_compare;
+use crate::{ContractCode, PreparedContract};
+use near_parameters::RuntimeFeesConfig;
+use near_parameters::vm::VMKind;
+use std::sync::Arc;
+
+/// Encode array of `u64` to be passed as a smart contract argument.
+fn encode(xs: &[u64]) -> Vec<u8> {
+    xs.iter().flat_map(|it| it.to_le_bytes()).collect()
+}
+
+fn prepare(
+    config: Config,
+    ext: &MockedExternal,
+    input: &[u8],
+    method: &str,
+) -> Box<dyn PreparedContract> {
+    let context = create_context(input.to_vec());
+    let gas_counter = context.make_gas_counter(&config);
+    let vm_kind = config.vm_kind;
+    let runtime = vm_kind.runtime(Arc::new(config)).expect("runtime has not been compiled");
+    runtime.prepare(ext, None, gas_counter, method)
+}
+
+fn rs_contract_external() -> MockedExternal {
+    MockedExternal::with_code(ContractCode::new(near_test_contracts::rs_contract().to_vec(), None))
+}
+
+#[test]
+fn test_shadow_same_vm_agrees() {
+    let config = test_vm_config();
+    if !config.vm_kind.is_available() {
+        return;
+    }
+    let mut ext = rs_contract_external();
+    let input = encode(&[10u64, 20u64]);
+    let primary = prepare(config.clone(), &ext, &input, "write_key_value");
+    let shadow = prepare(config, &ext, &input, "write_key_value");
+    let context = create_context(input);
+    let fees = Arc::new(RuntimeFeesConfig::test());
+    let (result, divergence) = run_and_compare(primary, shadow, &mut ext, &context, fees);
+    result.expect("primary execution failed");
+    assert_eq!(divergence, None);
+    // The shadow execution must not have written anything on its own.
+    assert_eq!(ext.fake_trie.len(), 1);
+}
+
+#[test]
+fn test_shadow_detects_gas_divergence() {
+    let config = test_vm_config();
+    if !config.vm_kind.is_available() {
+        return;
+    }
+    let mut ext = rs_contract_external();
+    let input = encode(&[10u64, 20u64]);
+    let primary = prepare(config.clone(), &ext, &input, "write_key_value");
+    let shadow_config = Config { regular_op_cost: config.regular_op_cost * 2, ..config };
+    let shadow = prepare(shadow_config, &ext, &input, "write_key_value");
+    let context = create_context(input);
+    let fees = Arc::new(RuntimeFeesConfig::test());
+    let (result, divergence) = run_and_compare(primary, shadow, &mut ext, &context, fees);
+    result.expect("primary execution failed");
+    assert_eq!(divergence.expect("gas should diverge").kind, "burnt_gas");
+}
+
+#[test]
+fn test_shadow_detects_host_call_divergence() {
+    let config = test_vm_config();
+    if !config.vm_kind.is_available() {
+        return;
+    }
+    let mut ext = rs_contract_external();
+    let input = encode(&[10u64, 20u64]);
+    let primary = prepare(config.clone(), &ext, &input, "write_key_value");
+    let shadow = prepare(config, &ext, &input, "read_value");
+    let context = create_context(input);
+    let fees = Arc::new(RuntimeFeesConfig::test());
+    let (result, divergence) = run_and_compare(primary, shadow, &mut ext, &context, fees);
+    result.expect("primary execution failed");
+    assert_eq!(divergence.expect("host calls should diverge").kind, "host_calls");
+}
+
+#[test]
+#[cfg_attr(not(all(feature = "wasmtime_vm", feature = "near_vm", target_arch = "x86_64")), ignore)]
+fn test_shadow_near_vm_and_wasmtime_agree() {
+    let near_vm_config = Config { vm_kind: VMKind::NearVm, ..test_vm_config() };
+    let wasmtime_config = Config { vm_kind: VMKind::Wasmtime, ..test_vm_config() };
+    let mut ext = rs_contract_external();
+    let fees = Arc::new(RuntimeFeesConfig::test());
+    for (method, input) in [
+        ("write_key_value", encode(&[10u64, 20u64])),
+        ("read_value", encode(&[10u64])),
+        ("log_something", vec![]),
+        ("run_test", vec![]),
+    ] {
+        let primary = prepare(near_vm_config.clone(), &ext, &input, method);
+        let shadow = prepare(wasmtime_config.clone(), &ext, &input, method);
+        let context = create_context(input);
+        let (result, divergence) =
+            run_and_compare(primary, shadow, &mut ext, &context, Arc::clone(&fees));
+        result.expect("primary execution failed");
+        assert_eq!(divergence, None, "{method} diverged");
+    }
+}
+
+#[test]
+#[cfg_attr(not(all(feature = "wasmtime_vm", feature = "near_vm", target_arch = "x86_64")), ignore)]
+fn test_shadow_near_vm_and_wasmtime_agree_on_limits() {
+    let fees = Arc::new(RuntimeFeesConfig::test());
+    for (wat, prepaid_gas) in [
+        // Unbounded recursion runs into the stack limit.
+        (r#"(module (func $f (export "main") (call $f)))"#, 10u64.pow(14)),
+        // Deeper frames hit the limit at a different call depth.
+        (r#"(module (func $f (export "main") (local i64 i64 i64 i64) (call $f)))"#, 10u64.pow(14)),
+        // An infinite loop runs out of gas.
+        (r#"(module (func (export "main") (loop br 0)))"#, 10u64.pow(10)),
+    ] {
+        let mut ext =
+            MockedExternal::with_code(ContractCode::new(wat::parse_str(wat).unwrap(), None));
+        let mut context = create_context(vec![]);
+        context.prepaid_gas = prepaid_gas;
+        let prepare = |vm_kind| {
+            let config = Config { vm_kind, ..test_vm_config() };
+            let gas_counter = context.make_gas_counter(&config);
+            let runtime = vm_kind.runtime(Arc::new(config)).expect("runtime has not been compiled");
+            runtime.prepare(&ext, None, gas_counter, "main")
+        };
+        let primary = prepare(VMKind::NearVm);
+        let shadow = prepare(VMKind::Wasmtime);
+        let (result, divergence) =
+            run_and_compare(primary, shadow, &mut ext, &context, Arc::clone(&fees));
+        let outcome = result.expect("primary execution failed");
+        assert!(outcome.aborted.is_some(), "{wat} did not abort");
+        assert_eq!(divergence, None, "{wat} diverged");
+    }
+}
@@ -1,6 +1,7 @@
 use crate::errors::ContractPrecompilatonResult;
+use crate::features::WasmFeatures;
 use crate::logic::errors::{
-    CacheError, CompilationError, FunctionCallError, MethodResolveError, PrepareError,
+    CacheError, CompilationError, FunctionCallError, HostError, MethodResolveError, PrepareError,
     VMLogicError, VMRunnerError, WasmTrap,
 };
 use crate::logic::{Config, ExecutionResultState, GasCounter};
@@ -15,7 +16,7 @@ use near_parameters::vm::VMKind;
 use std::borrow::Cow;
 use std::cell::{RefCell, UnsafeCell};
 use std::ffi::c_void;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use wasmtime::ExternType::Func;
 use wasmtime::{Engine, Linker, Memory, MemoryType, Module, Store};
 
@@ -128,15 +129,32 @@ pub fn get_engine(config: &wasmtime::Config) -> Engine {
 }
 
 pub(crate) fn default_wasmtime_config(c: &Config) -> wasmtime::Config {
-    let features = crate::features::WasmFeatures::new(c);
+    let features = WasmFeatures::new(c);
     let mut config = wasmtime::Config::from(features);
     config.max_wasm_stack(1024 * 1024 * 1024); // wasm stack metering is implemented by instrumentation, we don't want wasmtime to trap before that
     config
 }
 
-pub(crate) fn wasmtime_vm_hash() -> u64 {
-    // TODO: take into account compiler and engine used to compile the contract.
-    64
+// Bump this version whenever the way contracts are compiled or loaded for wasmtime changes in a
+// way that is not reflected in the wasmtime engine compatibility hash below.
+const WASMTIME_VM_VERSION: u64 = 65;
+
+pub(crate) fn wasmtime_vm_hash(config: &Config) -> u64 {
+    // Artifacts produced by `Engine::precompile_module` can only be loaded by an engine of the
+    // same wasmtime version configured with compatible compilation settings. Fold the
+    // compatibility hash of the engine we actually compile with into the value so that upgrading
+    // wasmtime or changing its settings evicts the stale artifacts from the compiled contract
+    // cache instead of failing to load them.
+    //
+    // Creating an engine is expensive, so the hash is computed once for every set of the enabled
+    // wasm features, which are the only engine settings depending on the config.
+    static HASHES: [OnceLock<u64>; WasmFeatures::COUNT] =
+        [const { OnceLock::new() }; WasmFeatures::COUNT];
+    let features = WasmFeatures::new(config);
+    *HASHES[features.index()].get_or_init(|| {
+        let engine = get_engine(&default_wasmtime_config(config));
+        crate::utils::stable_hash((WASMTIME_VM_VERSION, engine.precompile_compatibility_hash()))
+    })
 }
 
 pub(crate) struct WasmtimeVM {
@@ -190,15 +208,22 @@ impl WasmtimeVM {
     ) -> VMResult<PreparedContract> {
         type MemoryCacheType = (u64, Result<Module, CompilationError>);
         let to_any = |v: MemoryCacheType| -> Box<dyn std::any::Any + Send> { Box::new(v) };
+        // Same as for NearVM, assume a cache hit until we find out that we need to compile.
+        let mut is_cache_hit = true;
         let key = get_contract_cache_key(contract.hash(), &self.config);
         let (wasm_bytes, module_result) = cache.memory_cache().try_lookup(
             key,
             || {
+                let _span =
+                    tracing::debug_span!(target: "vm", "WasmtimeVM::fetch_from_cache").entered();
                 let cache_record = cache.get(&key).map_err(CacheError::ReadError)?;
                 let Some(compiled_contract_info) = cache_record else {
                     let Some(code) = contract.get_code() else {
                         return Err(VMRunnerError::ContractCodeNotPresent);
                     };
+                    let _span = tracing::debug_span!(target: "vm", "WasmtimeVM::build_from_source")
+                        .entered();
+                    is_cache_hit = false;
                     return Ok(to_any((
                         code.code().len() as u64,
                         match self.compile_and_cache(&code, cache)? {
@@ -216,19 +241,23 @@ impl WasmtimeVM {
                         Err(err.clone()),
                     ))),
                     CompiledContract::Code(serialized_module) => {
+                        let _span =
+                            tracing::debug_span!(target: "vm", "WasmtimeVM::load_from_fs_cache")
+                                .entered();
                         unsafe {
                             // (UN-)SAFETY: the `serialized_module` must have been produced by
                             // a prior call to `serialize`.
                             //
                             // In practice this is not necessarily true. One could have
                             // forgotten to change the cache key when upgrading the version of
-                            // the near_vm library or the database could have had its data
+                            // the wasmtime library or the database could have had its data
                             // corrupted while at rest.
                             //
-                            // There should definitely be some validation in near_vm to ensure
-                            // we load what we think we load.
+                            // Wasmtime does validate the header of the serialized artifact
+                            // against the engine, so a mismatch is reported as a cache
+                            // deserialization failure, same as with NearVM.
                             let module = Module::deserialize(&self.engine, &serialized_module)
-                                .map_err(|err| VMRunnerError::LoadingError(err.to_string()))?;
+                                .map_err(|_| CacheError::DeserializationError)?;
                             Ok(to_any((compiled_contract_info.wasm_bytes, Ok(module))))
                         }
                     }
@@ -243,6 +272,7 @@ impl WasmtimeVM {
             },
         )?;
 
+        crate::metrics::record_compiled_contract_cache_lookup(is_cache_hit);
         let config = Arc::clone(&self.config);
         let result = gas_counter.before_loading_executable(&config, &method, wasm_bytes);
         if let Err(e) = result {
@@ -429,6 +459,19 @@ impl std::fmt::Display for ErrorContainer {
     }
 }
 
+/// `finite_wasm_stack` reports an exhausted stack as a host error, while NearVM raises a stack
+/// overflow trap in the same situation. Turn it into the trap so both VMs produce the same outcome.
+fn host_error_into_anyhow(host_function: &str, err: VMLogicError) -> anyhow::Error {
+    match err {
+        VMLogicError::HostError(HostError::MemoryAccessViolation)
+            if host_function == "finite_wasm_stack" =>
+        {
+            wasmtime::Trap::StackOverflow.into()
+        }
+        err => ErrorContainer(parking_lot::Mutex::new(Some(err))).into(),
+    }
+}
+
 thread_local! {
     static CALLER_CONTEXT: UnsafeCell<*mut c_void> = const { UnsafeCell::new(core::ptr::null_mut()) };
 }
@@ -474,9 +517,7 @@ fn link<'a, 'b>(
                 let logic: &mut VMLogic<'_> = unsafe { &mut *(data as *mut VMLogic<'_>) };
                 match logic.$func( $( $arg_name as $arg_type, )* ) {
                     Ok(result) => Ok(result as ($( $returns ),* ) ),
-                    Err(err) => {
-                        Err(ErrorContainer(parking_lot::Mutex::new(Some(err))).into())
-                    }
+                    Err(err) => Err(host_error_into_anyhow(stringify!($name), err)),
                 }
             }
 
@@ -225,6 +225,11 @@ pub enum Cost {
     ActionUseGlobalContractPerIdentifierByteSendNotSir,
     ActionUseGlobalContractPerIdentifierByteSendSir,
     ActionUseGlobalContractPerIdentifierByteExec,
+    // Verifying the signature of a transaction signed by a SECP256R1 access key,
+    // charged on top of the action costs.
+    ActionSecp256r1SignatureVerification,
+    // Verifying one component signature of a multisig access key.
+    ActionMultisigSignatureVerification,
     /// Estimates `wasm_config.ext_costs.base` which is intended to be charged
     /// once on every host function call. However, this is currently
     /// inconsistent. First, we do not charge on Math API methods (`sha256`,
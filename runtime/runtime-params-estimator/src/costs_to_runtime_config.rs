@@ -77,6 +77,8 @@ fn runtime_fees_config(cost_table: &CostTable) -> anyhow::Result<RuntimeFeesConf
             ActionCosts::deploy_global_contract_byte => fee(Cost::ActionDeployGlobalContractPerByte)?,
             ActionCosts::use_global_contract_base => fee(Cost::ActionUseGlobalContractBase)?,
             ActionCosts::use_global_contract_byte => fee(Cost::ActionUseGlobalContractPerIdentifierByte)?,
+            ActionCosts::secp256r1_signature_verification => fee(Cost::ActionSecp256r1SignatureVerification)?,
+            ActionCosts::multisig_signature_verification => fee(Cost::ActionMultisigSignatureVerification)?,
         },
         ..RuntimeFeesConfig::clone(&actual_fees_config)
     };
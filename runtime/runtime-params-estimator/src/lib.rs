@@ -98,7 +98,10 @@ use crate::vm_estimator::create_context;
 use estimator_params::sha256_cost;
 use gas_cost::{LeastSquaresTolerance, NonNegativeTolerance};
 use gas_metering::gas_metering_cost;
-use near_crypto::{KeyType, SecretKey};
+use near_crypto::{
+    KeyType, MAX_MULTISIG_KEYS, MultisigPublicKey, MultisigSignature, PublicKey, SecretKey,
+    Signature,
+};
 use near_parameters::{ExtCosts, RuntimeConfigStore, RuntimeFeesConfig};
 use near_primitives::account::{AccessKey, AccessKeyPermission, FunctionCallPermission};
 use near_primitives::transaction::{
@@ -211,6 +214,8 @@ static ALL_COSTS: &[(Cost, fn(&mut EstimatorContext) -> GasCost)] = &[
     (Cost::ActionDelegateSendNotSir, action_costs::delegate_send_not_sir),
     (Cost::ActionDelegateSendSir, action_costs::delegate_send_sir),
     (Cost::ActionDelegateExec, action_costs::delegate_exec),
+    (Cost::ActionSecp256r1SignatureVerification, action_secp256r1_signature_verification),
+    (Cost::ActionMultisigSignatureVerification, action_multisig_signature_verification),
     (Cost::HostFunctionCall, host_function_call),
     (Cost::WasmInstruction, wasm_instruction),
     (Cost::DataReceiptCreationBase, data_receipt_creation_base),
@@ -1035,6 +1040,49 @@ fn ripemd160_block(ctx: &mut EstimatorContext) -> GasCost {
     fn_cost(ctx, "ripemd160_10kib_10k", ExtCosts::ripemd160_block, (10 * 1024 / 64 + 1) * 10_000)
 }
 
+/// Verifying a transaction or delegate action signed with a SECP256R1 key.
+fn action_secp256r1_signature_verification(ctx: &mut EstimatorContext) -> GasCost {
+    let secret_key = SecretKey::from_seed(KeyType::SECP256R1, "seed");
+    signature_verification_cost(ctx, &secret_key.public_key(), |data| secret_key.sign(data))
+}
+
+/// Verifying one component signature of a multisig key. All components are
+/// SECP256R1 keys, the most expensive key type to verify.
+fn action_multisig_signature_verification(ctx: &mut EstimatorContext) -> GasCost {
+    let secret_keys: Vec<_> = (0..MAX_MULTISIG_KEYS)
+        .map(|i| SecretKey::from_seed(KeyType::SECP256R1, &format!("seed{i}")))
+        .collect();
+    let public_key = PublicKey::MULTISIG(
+        MultisigPublicKey::new(
+            MAX_MULTISIG_KEYS as u8,
+            secret_keys.iter().map(SecretKey::public_key).collect(),
+        )
+        .unwrap(),
+    );
+    let cost = signature_verification_cost(ctx, &public_key, |data| {
+        let signatures =
+            secret_keys.iter().enumerate().map(|(i, key)| (i as u8, key.sign(data))).collect();
+        Signature::MULTISIG(MultisigSignature::new(signatures).unwrap())
+    });
+    cost / MAX_MULTISIG_KEYS as u64
+}
+
+fn signature_verification_cost(
+    ctx: &mut EstimatorContext,
+    public_key: &PublicKey,
+    sign: impl Fn(&[u8]) -> Signature,
+) -> GasCost {
+    let n_iters = 1000;
+    let data = near_primitives::hash::hash(b"signature verification");
+    let signature = sign(data.as_ref());
+
+    let start = GasCost::measure(ctx.config.metric);
+    for _ in 0..n_iters {
+        assert!(signature.verify(data.as_ref(), public_key));
+    }
+    start.elapsed() / n_iters
+}
+
 fn ecrecover_base(ctx: &mut EstimatorContext) -> GasCost {
     fn_cost(ctx, "ecrecover_10k", ExtCosts::ecrecover_base, 10_000)
 }
@@ -6,6 +6,7 @@ use crate::ext::{ExternalError, RuntimeExt};
 use crate::receipt_manager::ReceiptManager;
 use crate::{ActionResult, ApplyState, metrics};
 use near_crypto::PublicKey;
+use near_parameters::vm::VMKind;
 use near_parameters::{AccountCreationConfig, ActionCosts, RuntimeConfig, RuntimeFeesConfig};
 use near_primitives::account::{AccessKey, AccessKeyPermission, Account, AccountContract};
 use near_primitives::action::delegate::{DelegateAction, SignedDelegateAction};
@@ -42,8 +43,12 @@ use near_wallet_contract::{wallet_contract, wallet_contract_magic_bytes};
 use std::sync::Arc;
 
 /// Runs given function call with given context / apply state.
+///
+/// If a `shadow_contract` is given, the call is additionally replayed on it and any divergence
+/// from the primary execution is reported. The shadow execution never affects the outcome.
 pub(crate) fn execute_function_call(
     contract: Box<dyn near_vm_runner::PreparedContract>,
+    shadow_contract: Option<(VMKind, Box<dyn near_vm_runner::PreparedContract>)>,
     apply_state: &ApplyState,
     runtime_ext: &mut RuntimeExt,
     predecessor_id: &AccountId,
@@ -87,7 +92,17 @@ pub(crate) fn execute_function_call(
     };
 
     near_vm_runner::reset_metrics();
-    let result = near_vm_runner::run(contract, runtime_ext, &context, Arc::clone(&config.fees));
+    let result = match shadow_contract {
+        Some((shadow_vm_kind, shadow_contract)) => near_vm_runner::run_with_shadow(
+            contract,
+            shadow_contract,
+            shadow_vm_kind,
+            runtime_ext,
+            &context,
+            Arc::clone(&config.fees),
+        ),
+        None => near_vm_runner::run(contract, runtime_ext, &context, Arc::clone(&config.fees)),
+    };
     near_vm_runner::report_metrics(
         &apply_state.shard_id.to_string(),
         &apply_state.apply_reason.to_string(),
@@ -158,6 +173,7 @@ pub(crate) fn action_function_call(
     is_last_action: bool,
     epoch_info_provider: &dyn EpochInfoProvider,
     contract: Box<dyn PreparedContract>,
+    shadow_contract: Option<(VMKind, Box<dyn PreparedContract>)>,
 ) -> Result<(), RuntimeError> {
     if account.amount().checked_add(function_call.deposit).is_none() {
         return Err(StorageError::StorageInconsistentState(
@@ -194,6 +210,7 @@ pub(crate) fn action_function_call(
     );
     let outcome = execute_function_call(
         contract,
+        shadow_contract,
         apply_state,
         &mut runtime_ext,
         receipt.predecessor_id(),
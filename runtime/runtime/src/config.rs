@@ -1,5 +1,6 @@
 //! Settings of the parameters of the runtime.
 
+use near_crypto::PublicKey;
 use near_primitives::account::AccessKeyPermission;
 use near_primitives::action::DeployGlobalContractAction;
 use near_primitives::errors::IntegerOverflowError;
@@ -9,7 +10,9 @@ use num_bigint::BigUint;
 use num_traits::cast::ToPrimitive;
 use num_traits::pow::Pow;
 // Just re-exporting RuntimeConfig for backwards compatibility.
-use near_parameters::{ActionCosts, RuntimeConfig, transfer_exec_fee, transfer_send_fee};
+use near_parameters::{
+    ActionCosts, RuntimeConfig, RuntimeFeesConfig, transfer_exec_fee, transfer_send_fee,
+};
 pub use near_primitives::num_rational::Rational32;
 use near_primitives::transaction::{Action, DeployContractAction, Transaction};
 use near_primitives::types::{AccountId, Balance, Compute, Gas};
@@ -123,6 +126,11 @@ pub fn total_send_fees(
                 let delegate_action = &signed_delegate_action.delegate_action;
 
                 delegate_cost
+                    + signature_verification_send_fee(
+                        fees,
+                        sender_is_receiver,
+                        &delegate_action.public_key,
+                    )
                     + total_send_fees(
                         config,
                         sender_is_receiver,
@@ -224,7 +232,12 @@ pub fn exec_fee(config: &RuntimeConfig, action: &Action, receiver_id: &AccountId
         },
         DeleteKey(_) => fees.fee(ActionCosts::delete_key).exec_fee(),
         DeleteAccount(_) => fees.fee(ActionCosts::delete_account).exec_fee(),
-        Delegate(_) => fees.fee(ActionCosts::delegate).exec_fee(),
+        Delegate(signed_delegate_action) => {
+            let public_key = &signed_delegate_action.delegate_action.public_key;
+            fees.fee(ActionCosts::delegate).exec_fee()
+                + signature_verification_cost(public_key)
+                    .map_or(0, |(cost, count)| fees.fee(cost).exec_fee() * count)
+        }
         DeployGlobalContract(DeployGlobalContractAction { code, .. }) => {
             let num_bytes = code.len() as u64;
             fees.fee(ActionCosts::deploy_global_contract_base).exec_fee()
@@ -238,6 +251,29 @@ pub fn exec_fee(config: &RuntimeConfig, action: &Action, receiver_id: &AccountId
     }
 }
 
+/// Signature verification charged on top of the action fees when signing with
+/// `public_key`, as the fee parameter and the number of times it is charged.
+///
+/// ED25519 and SECP256K1 signatures are covered by the receipt creation fees.
+fn signature_verification_cost(public_key: &PublicKey) -> Option<(ActionCosts, u64)> {
+    match public_key {
+        PublicKey::ED25519(_) | PublicKey::SECP256K1(_) => None,
+        PublicKey::SECP256R1(_) => Some((ActionCosts::secp256r1_signature_verification, 1)),
+        PublicKey::MULTISIG(key) => {
+            Some((ActionCosts::multisig_signature_verification, key.threshold() as u64))
+        }
+    }
+}
+
+fn signature_verification_send_fee(
+    fees: &RuntimeFeesConfig,
+    sender_is_receiver: bool,
+    public_key: &PublicKey,
+) -> Gas {
+    signature_verification_cost(public_key)
+        .map_or(0, |(cost, count)| fees.fee(cost).send_fee(sender_is_receiver) * count)
+}
+
 /// Returns transaction costs for a given transaction.
 pub fn tx_cost(
     config: &RuntimeConfig,
@@ -248,6 +284,10 @@ pub fn tx_cost(
     let sender_is_receiver = tx.receiver_id() == tx.signer_id();
     let fees = &config.fees;
     let mut gas_burnt: Gas = fees.fee(ActionCosts::new_action_receipt).send_fee(sender_is_receiver);
+    gas_burnt = safe_add_gas(
+        gas_burnt,
+        signature_verification_send_fee(fees, sender_is_receiver, tx.public_key()),
+    )?;
     gas_burnt = safe_add_gas(
         gas_burnt,
         total_send_fees(config, sender_is_receiver, tx.actions(), tx.receiver_id())?,
@@ -29,6 +29,9 @@ use std::collections::{BTreeSet, HashMap};
 
 pub enum ReceiptSink {
     V2(ReceiptSinkV2),
+    /// Only collects the receipts produced while a receipt is executed
+    /// speculatively, so that they can be forwarded in order later.
+    Collect(Vec<Receipt>),
 }
 
 /// A helper struct to buffer or forward receipts.
@@ -136,6 +139,7 @@ impl ReceiptSink {
             ReceiptSink::V2(inner) => {
                 inner.forward_from_buffer(state_update, apply_state, epoch_info_provider)
             }
+            ReceiptSink::Collect(_) => Ok(()),
         }
     }
 
@@ -156,6 +160,10 @@ impl ReceiptSink {
                 state_update,
                 epoch_info_provider,
             ),
+            ReceiptSink::Collect(receipts) => {
+                receipts.push(receipt);
+                Ok(())
+            }
         }
     }
 
@@ -171,18 +179,26 @@ impl ReceiptSink {
                 *stats = inner.stats;
                 inner.outgoing_receipts
             }
+            ReceiptSink::Collect(receipts) => receipts,
         }
     }
 
+    // The methods below are only called when committing the results of the whole chunk,
+    // which always uses `ReceiptSink::V2`. A collecting sink only lives while a single
+    // receipt is executed speculatively and is dropped once its receipts are taken,
+    // see `parallel::execute_in_fork`.
+
     pub(crate) fn own_congestion_info(&self) -> CongestionInfo {
         match self {
             ReceiptSink::V2(inner) => inner.own_congestion_info,
+            ReceiptSink::Collect(_) => unreachable!("collecting sink has no congestion info"),
         }
     }
 
     pub(crate) fn bandwidth_scheduler_output(&self) -> &BandwidthSchedulerOutput {
         match self {
             ReceiptSink::V2(inner) => &inner.bandwidth_scheduler_output,
+            ReceiptSink::Collect(_) => unreachable!("collecting sink has no bandwidth output"),
         }
     }
 
@@ -198,6 +214,7 @@ impl ReceiptSink {
             ReceiptSink::V2(inner) => {
                 inner.generate_bandwidth_requests(trie, shard_layout, side_effects, stats)
             }
+            ReceiptSink::Collect(_) => unreachable!("collecting sink has no outgoing buffers"),
         }
     }
 }
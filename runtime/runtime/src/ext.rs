@@ -557,9 +557,78 @@ pub struct AccountingState {
     mem_reads: AtomicU64,
     db_reads: AtomicU64,
     cache: Mutex<BTreeMap<CryptoHash, Arc<[u8]>>>,
+    /// Set if this is a fork of another state, see `AccountingState::fork`.
+    fork: Option<AccountingFork>,
+}
+
+#[derive(Debug)]
+struct AccountingFork {
+    /// State this one was forked from. Its cache is shared with the fork, which
+    /// only keeps the values it inserted itself in its own cache. The base state
+    /// must not be modified while the fork is in use.
+    base: Arc<AccountingState>,
+    lookups: Mutex<AccountingLookups>,
+}
+
+/// Lookups made through a fork of `AccountingState` since the last call to
+/// `AccountingState::take_lookups`.
+#[derive(Default, Debug)]
+pub(crate) struct AccountingLookups {
+    /// Whether the first lookup of each key was served from the cache.
+    first_lookups: BTreeMap<CryptoHash, bool>,
+    /// Values inserted into the cache, in order.
+    inserted: Vec<(CryptoHash, Arc<[u8]>)>,
+    mem_reads: u64,
+    db_reads: u64,
 }
 
 impl AccountingState {
+    /// Creates a fork of the state for speculative execution, which sees the
+    /// cache of this state without copying it. Lookups made through the fork
+    /// are logged, so that they can be checked against this state with
+    /// `lookups_match` and then applied to it with `apply_lookups`.
+    pub(crate) fn fork(self: &Arc<Self>) -> Self {
+        Self {
+            mem_reads: AtomicU64::new(0),
+            db_reads: AtomicU64::new(0),
+            cache: Mutex::new(BTreeMap::new()),
+            fork: Some(AccountingFork {
+                base: Arc::clone(self),
+                lookups: Mutex::new(AccountingLookups::default()),
+            }),
+        }
+    }
+
+    pub(crate) fn take_lookups(&self) -> AccountingLookups {
+        let fork = self.fork.as_ref().expect("only forks of the state log lookups");
+        std::mem::take(&mut *fork.lookups.lock())
+    }
+
+    /// Looks the key up in the cache of this state and then of the states it was forked from.
+    fn cached(&self, key: &CryptoHash) -> Option<Arc<[u8]>> {
+        if let Some(value) = self.cache.lock().get(key) {
+            return Some(Arc::clone(value));
+        }
+        self.fork.as_ref()?.base.cached(key)
+    }
+
+    /// Whether each key looked up in the fork is cached in this state if and
+    /// only if it was cached in the fork, so that the lookups would have been
+    /// charged the same here.
+    pub(crate) fn lookups_match(&self, lookups: &AccountingLookups) -> bool {
+        let cache = self.cache.lock();
+        lookups.first_lookups.iter().all(|(key, cached)| cache.contains_key(key) == *cached)
+    }
+
+    pub(crate) fn apply_lookups(&self, lookups: AccountingLookups) {
+        let mut cache = self.cache.lock();
+        for (key, value) in lookups.inserted {
+            cache.insert(key, value);
+        }
+        self.mem_reads.fetch_add(lookups.mem_reads, Ordering::Relaxed);
+        self.db_reads.fetch_add(lookups.db_reads, Ordering::Relaxed);
+    }
+
     fn get_counts(&self) -> TrieNodesCount {
         TrieNodesCount {
             db_reads: self.db_reads.load(Ordering::Relaxed),
@@ -608,13 +677,29 @@ impl Debug for AccountingAccessTracker {
 
 impl AccessTracker for AccountingAccessTracker {
     fn track_mem_lookup(&self, key: &CryptoHash) -> Option<Arc<[u8]>> {
-        let value = Arc::clone(self.state.cache.lock().get(key)?);
+        let value = self.state.cached(key);
+        if let Some(fork) = &self.state.fork {
+            let mut lookups = fork.lookups.lock();
+            lookups.first_lookups.entry(*key).or_insert(value.is_some());
+            if value.is_some() {
+                lookups.mem_reads += 1;
+            }
+        }
+        let value = value?;
         self.state.mem_reads.fetch_add(1, Ordering::Relaxed);
         Some(value)
     }
 
     fn track_disk_lookup(&self, key: CryptoHash, value: Arc<[u8]>) {
         self.state.db_reads.fetch_add(1, Ordering::Relaxed);
+        if let Some(fork) = &self.state.fork {
+            let mut lookups = fork.lookups.lock();
+            lookups.first_lookups.entry(key).or_insert(false);
+            lookups.db_reads += 1;
+            if self.allow_insert {
+                lookups.inserted.push((key, Arc::clone(&value)));
+            }
+        }
         if self.allow_insert {
             self.state.cache.lock().insert(key, value);
         }
@@ -7,6 +7,7 @@ use crate::config::{
 };
 use crate::congestion_control::DelayedReceiptQueueWrapper;
 use crate::prefetch::TriePrefetcher;
+pub use crate::prefetch::prefetch_next_chunk_data;
 pub use crate::types::SignedValidPeriodTransactions;
 use crate::verifier::{StorageStakingError, check_storage_stake, validate_receipt};
 pub use crate::verifier::{
@@ -24,6 +25,7 @@ use global_contracts::{
 use itertools::Itertools;
 use metrics::ApplyMetrics;
 pub use near_crypto;
+use near_parameters::vm::VMKind;
 use near_parameters::{ActionCosts, RuntimeConfig};
 pub use near_primitives;
 use near_primitives::account::Account;
@@ -91,6 +93,7 @@ mod conversions;
 pub mod ext;
 mod global_contracts;
 pub mod metrics;
+mod parallel;
 mod pipelining;
 mod prefetch;
 pub mod receipt_manager;
@@ -287,11 +290,28 @@ pub struct GasRefundResult {
     pub refund_penalty: Balance,
 }
 
-pub struct Runtime {}
+pub struct Runtime {
+    /// If set, every function call is additionally executed on this VM and any divergence from
+    /// the primary VM is reported. See [`near_vm_runner::run_with_shadow`].
+    shadow_vm_kind: Option<VMKind>,
+    /// If set, independent receipts of a chunk are executed speculatively in parallel. The result
+    /// is always the same as with sequential execution, see the `parallel` module.
+    parallel_receipt_execution: bool,
+}
 
 impl Runtime {
     pub fn new() -> Self {
-        Self {}
+        Self { shadow_vm_kind: None, parallel_receipt_execution: false }
+    }
+
+    pub fn with_shadow_vm_kind(mut self, shadow_vm_kind: Option<VMKind>) -> Self {
+        self.shadow_vm_kind = shadow_vm_kind;
+        self
+    }
+
+    pub fn with_parallel_receipt_execution(mut self, parallel_receipt_execution: bool) -> Self {
+        self.parallel_receipt_execution = parallel_receipt_execution;
+        self
     }
 
     fn print_log(log: &[LogEntry]) {
@@ -534,6 +554,18 @@ impl Runtime {
                     state_update.get_account_contract_hash(account_contract.as_ref())?;
                 let contract =
                     preparation_pipeline.get_contract(receipt, code_hash, action_index, None);
+                let shadow_contract = self
+                    .shadow_vm_kind
+                    .filter(|kind| *kind != apply_state.config.wasm_config.vm_kind)
+                    .map(|kind| {
+                        let contract = preparation_pipeline.get_shadow_contract(
+                            receipt,
+                            code_hash,
+                            action_index,
+                            kind,
+                        );
+                        (kind, contract)
+                    });
                 let is_last_action = action_index + 1 == actions.len();
                 action_function_call(
                     state_update,
@@ -551,6 +583,7 @@ impl Runtime {
                     is_last_action,
                     epoch_info_provider,
                     contract,
+                    shadow_contract,
                 )?;
             }
             Action::Transfer(TransferAction { deposit }) => {
@@ -1157,19 +1190,15 @@ impl Runtime {
 
     fn process_receipt(
         &self,
-        processing_state: &mut ApplyProcessingReceiptState,
+        state_update: &mut TrieUpdate,
+        apply_state: &ApplyState,
+        epoch_info_provider: &dyn EpochInfoProvider,
+        pipeline_manager: &ReceiptPreparationPipeline,
+        stats: &mut ChunkApplyStatsV0,
         receipt: &Receipt,
         receipt_sink: &mut ReceiptSink,
         validator_proposals: &mut Vec<ValidatorStake>,
     ) -> Result<Option<ExecutionOutcomeWithId>, RuntimeError> {
-        let ApplyProcessingReceiptState {
-            ref mut state_update,
-            apply_state,
-            epoch_info_provider,
-            ref pipeline_manager,
-            ref mut stats,
-            ..
-        } = *processing_state;
         let account_id = receipt.receiver_id();
         match receipt.receipt() {
             ReceiptEnum::Data(data_receipt) => {
@@ -1704,10 +1733,12 @@ impl Runtime {
     }
 
     /// This function wraps [Runtime::process_receipt]. It adds a tracing span around the latter
-    /// and populates various metrics.
+    /// and populates various metrics. If the receipt was executed speculatively and the result is
+    /// still valid, the result is applied instead.
     fn process_receipt_with_metrics(
         &self,
         receipt: &Receipt,
+        speculative_receipt: Option<parallel::SpeculativeReceipt>,
         processing_state: &mut ApplyProcessingReceiptState,
         mut receipt_sink: &mut ReceiptSink,
         mut validator_proposals: &mut Vec<ValidatorStake>,
@@ -1729,12 +1760,25 @@ impl Runtime {
         let storage_proof_size_upper_bound_before = trie.recorded_storage_size_upper_bound();
 
         // Main logic
-        let result = self.process_receipt(
-            processing_state,
-            receipt,
-            &mut receipt_sink,
-            &mut validator_proposals,
-        );
+        let result =
+            match parallel::validate_speculative_receipt(processing_state, speculative_receipt)? {
+                Some(speculative_receipt) => parallel::apply_speculative_receipt(
+                    processing_state,
+                    speculative_receipt,
+                    &mut receipt_sink,
+                    &mut validator_proposals,
+                ),
+                None => self.process_receipt(
+                    &mut processing_state.state_update,
+                    processing_state.apply_state,
+                    processing_state.epoch_info_provider,
+                    &processing_state.pipeline_manager,
+                    &mut processing_state.stats,
+                    receipt,
+                    &mut receipt_sink,
+                    &mut validator_proposals,
+                ),
+            };
 
         let shard_id_str = processing_state.apply_state.shard_id.to_string();
         let trie = processing_state.state_update.trie();
@@ -1795,6 +1839,8 @@ impl Runtime {
             _ = prefetcher.prefetch_receipts_data(front);
             _ = prefetcher.prefetch_receipts_data(back);
         }
+        let mut speculative_receipts =
+            self.execute_receipts_speculatively(processing_state, local_receipts.iter());
 
         let mut prep_lookahead_iter = local_receipts.iter();
         // Advance the preparation by one step (stagger it) so that we're preparing one interesting
@@ -1805,7 +1851,7 @@ impl Runtime {
             &mut prep_lookahead_iter,
         );
 
-        for receipt in &local_receipts {
+        for (index, receipt) in local_receipts.iter().enumerate() {
             if processing_state.total.compute >= compute_limit
                 || processing_state.state_update.trie.check_proof_size_limit_exceed()
             {
@@ -1832,6 +1878,7 @@ impl Runtime {
                 // the `verify_and_charge_transaction`.
                 self.process_receipt_with_metrics(
                     &receipt,
+                    speculative_receipts.get_mut(index).and_then(Option::take),
                     &mut processing_state,
                     receipt_sink,
                     validator_proposals,
@@ -1933,6 +1980,7 @@ impl Runtime {
 
             self.process_receipt_with_metrics(
                 &receipt,
+                None,
                 &mut processing_state,
                 receipt_sink,
                 validator_proposals,
@@ -1970,6 +2018,10 @@ impl Runtime {
             // Prefetcher is allowed to fail
             _ = prefetcher.prefetch_receipts_data(&processing_state.incoming_receipts);
         }
+        let mut speculative_receipts = self.execute_receipts_speculatively(
+            processing_state,
+            processing_state.incoming_receipts.iter(),
+        );
 
         let mut prep_lookahead_iter = processing_state.incoming_receipts.iter();
         // Advance the preparation by one step (stagger it) so that we're preparing one interesting
@@ -1980,7 +2032,7 @@ impl Runtime {
             &mut prep_lookahead_iter,
         );
 
-        for receipt in processing_state.incoming_receipts {
+        for (index, receipt) in processing_state.incoming_receipts.iter().enumerate() {
             // Validating new incoming no matter whether we have available gas or not. We don't
             // want to store invalid receipts in state as delayed.
             validate_receipt(
@@ -2015,6 +2067,7 @@ impl Runtime {
 
                 self.process_receipt_with_metrics(
                     &receipt,
+                    speculative_receipts.get_mut(index).and_then(Option::take),
                     &mut processing_state,
                     receipt_sink,
                     validator_proposals,
@@ -2105,6 +2158,9 @@ impl Runtime {
         state_patch: SandboxStatePatch,
     ) -> Result<ApplyResult, RuntimeError> {
         let _span = tracing::debug_span!(target: "runtime", "apply_commit").entered();
+        // Only the sink of the chunk is committed, collecting sinks are local to the
+        // speculative execution of a receipt, see `parallel`.
+        debug_assert!(matches!(receipt_sink, ReceiptSink::V2(_)));
         let apply_state = processing_state.apply_state;
         let epoch_info_provider = processing_state.epoch_info_provider;
         let mut stats = processing_state.stats;
@@ -2716,7 +2772,7 @@ pub mod estimator {
             apply_state.cache.as_ref().map(|c| c.handle()),
             state_update.contract_storage(),
         );
-        let apply_result = Runtime {}.apply_action_receipt(
+        let apply_result = Runtime::new().apply_action_receipt(
             state_update,
             apply_state,
             &empty_pipeline,
@@ -93,6 +93,16 @@ pub static LOCAL_RECEIPT_PROCESSING_SECONDS_TOTAL: LazyLock<CounterVec> = LazyLo
     .unwrap()
 });
 
+pub static SPECULATIVE_RECEIPTS_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_speculative_receipts_total",
+        "The number of receipts executed speculatively in parallel, by whether the result was \
+         applied or the receipt had to be executed again because of a conflict",
+        &["shard_id", "result"],
+    )
+    .unwrap()
+});
+
 pub static YIELD_TIMEOUTS_PROCESSED_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
     try_create_int_counter_vec(
         "near_yield_timeouts_processed_total",
@@ -756,6 +766,7 @@ pub fn report_congestion_metrics(
             report_congestion_indicators(&inner.own_congestion_info, &sender_shard_label, &config);
             report_outgoing_buffers(inner, sender_shard_label);
         }
+        ReceiptSink::Collect(_) => {}
     }
 }
 
@@ -0,0 +1,234 @@
+//! Optional parallel execution of independent receipts within a chunk.
+//!
+//! Receipts are grouped by receiver and the groups are spread over forks of the
+//! state, which execute them speculatively in parallel. The results are then
+//! applied one by one in the original order, but only if every value read by
+//! the receipt in its fork, and every trie node it was charged for, is the
+//! same in the state built so far. Otherwise the receipt is executed again
+//! sequentially. The outcome is therefore always identical to sequential
+//! execution, parallelism only pays off when groups don't touch the same state.
+//!
+//! This is not part of consensus, it is only enabled for tools which replay
+//! chunks, see `Runtime::with_parallel_receipt_execution`.
+
+use crate::config::safe_add_balance;
+use crate::congestion_control::ReceiptSink;
+use crate::ext::{AccountingLookups, AccountingState};
+use crate::pipelining::ReceiptPreparationPipeline;
+use crate::{ApplyProcessingReceiptState, ApplyState, Runtime, metrics};
+use near_primitives::chunk_apply_stats::{BalanceStats, ChunkApplyStatsV0};
+use near_primitives::errors::{RuntimeError, StorageError};
+use near_primitives::receipt::Receipt;
+use near_primitives::transaction::ExecutionOutcomeWithId;
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{AccountId, EpochInfoProvider};
+use near_store::TrieUpdate;
+use near_store::trie::update::ForkChanges;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Result of executing a receipt in a fork of the state.
+pub(crate) struct SpeculativeReceipt {
+    changes: ForkChanges,
+    lookups: AccountingLookups,
+    outgoing_receipts: Vec<Receipt>,
+    validator_proposals: Vec<ValidatorStake>,
+    balance: BalanceStats,
+    outcome: Option<ExecutionOutcomeWithId>,
+}
+
+impl Runtime {
+    /// Executes `receipts` speculatively if parallel execution is enabled.
+    /// Returns the result for each receipt which could be executed, by its
+    /// position in `receipts`.
+    pub(crate) fn execute_receipts_speculatively<'r>(
+        &self,
+        processing_state: &ApplyProcessingReceiptState,
+        receipts: impl Iterator<Item = &'r Receipt>,
+    ) -> Vec<Option<SpeculativeReceipt>> {
+        if !self.parallel_receipt_execution {
+            return vec![];
+        }
+        let num_forks = rayon::current_num_threads().max(1);
+        let mut num_receipts = 0;
+        let mut buckets: Vec<Vec<(usize, &Receipt)>> = vec![vec![]; num_forks];
+        let mut bucket_by_receiver: HashMap<&AccountId, usize> = HashMap::new();
+        for (index, receipt) in receipts.enumerate() {
+            let next_bucket = bucket_by_receiver.len() % num_forks;
+            let bucket = *bucket_by_receiver.entry(receipt.receiver_id()).or_insert(next_bucket);
+            buckets[bucket].push((index, receipt));
+            num_receipts += 1;
+        }
+        // A single group of receipts is executed sequentially anyway.
+        if bucket_by_receiver.len() < 2 {
+            return vec![];
+        }
+
+        let state_update = &processing_state.state_update;
+        let mut forks = vec![];
+        for bucket in buckets.into_iter().filter(|bucket| !bucket.is_empty()) {
+            let Some(fork) = state_update.fork() else {
+                return vec![];
+            };
+            forks.push((fork, bucket));
+        }
+        let apply_state = processing_state.apply_state;
+        let epoch_info_provider = processing_state.epoch_info_provider;
+        let results: Vec<_> = forks
+            .into_par_iter()
+            .map(|(fork, bucket)| {
+                self.execute_in_fork(fork, apply_state, epoch_info_provider, bucket)
+            })
+            .collect();
+
+        let mut speculative_receipts: Vec<_> = (0..num_receipts).map(|_| None).collect();
+        for (index, speculative_receipt) in results.into_iter().flatten() {
+            speculative_receipts[index] = Some(speculative_receipt);
+        }
+        speculative_receipts
+    }
+
+    /// Executes receipts of a bucket one by one in the given fork of the state.
+    /// Stops at the first receipt whose changes can't be applied from the fork.
+    fn execute_in_fork(
+        &self,
+        mut state_update: TrieUpdate,
+        apply_state: &ApplyState,
+        epoch_info_provider: &dyn EpochInfoProvider,
+        receipts: Vec<(usize, &Receipt)>,
+    ) -> Vec<(usize, SpeculativeReceipt)> {
+        let accounting_state = Arc::new(apply_state.trie_access_tracker_state.fork());
+        let apply_state = fork_apply_state(apply_state, Arc::clone(&accounting_state));
+        let pipeline_manager = ReceiptPreparationPipeline::new(
+            Arc::clone(&apply_state.config),
+            apply_state.cache.as_ref().map(|cache| cache.handle()),
+            state_update.contract_storage(),
+        );
+        let mut results = vec![];
+        for (index, receipt) in receipts {
+            let mut receipt_sink = ReceiptSink::Collect(vec![]);
+            let mut validator_proposals = vec![];
+            let mut stats = ChunkApplyStatsV0::new(apply_state.block_height, apply_state.shard_id);
+            // Errors are reproduced when the receipt is executed sequentially.
+            let Ok(outcome) = self.process_receipt(
+                &mut state_update,
+                &apply_state,
+                epoch_info_provider,
+                &pipeline_manager,
+                &mut stats,
+                receipt,
+                &mut receipt_sink,
+                &mut validator_proposals,
+            ) else {
+                break;
+            };
+            let Some(changes) = state_update.take_fork_changes() else {
+                break;
+            };
+            let ReceiptSink::Collect(outgoing_receipts) = receipt_sink else {
+                unreachable!("receipts executed in a fork are collected");
+            };
+            results.push((
+                index,
+                SpeculativeReceipt {
+                    changes,
+                    lookups: accounting_state.take_lookups(),
+                    outgoing_receipts,
+                    validator_proposals,
+                    balance: stats.balance,
+                    outcome,
+                },
+            ));
+        }
+        results
+    }
+}
+
+/// Returns the result of speculative execution of a receipt if it is the same
+/// as the result of sequential execution in the current state would be.
+pub(crate) fn validate_speculative_receipt(
+    processing_state: &ApplyProcessingReceiptState,
+    speculative_receipt: Option<SpeculativeReceipt>,
+) -> Result<Option<SpeculativeReceipt>, StorageError> {
+    let Some(speculative_receipt) = speculative_receipt else {
+        return Ok(None);
+    };
+    let accounting_state = &processing_state.apply_state.trie_access_tracker_state;
+    let is_valid = processing_state.state_update.fork_reads_match(&speculative_receipt.changes)?
+        && accounting_state.lookups_match(&speculative_receipt.lookups);
+    let shard_id = processing_state.apply_state.shard_id.to_string();
+    let result = if is_valid { "applied" } else { "conflict" };
+    metrics::SPECULATIVE_RECEIPTS_TOTAL.with_label_values(&[&shard_id, result]).inc();
+    Ok(is_valid.then_some(speculative_receipt))
+}
+
+/// Applies the result of speculative execution of a receipt, which must
+/// have been checked with `validate_speculative_receipt`. Returns the same
+/// as `process_receipt`.
+pub(crate) fn apply_speculative_receipt(
+    processing_state: &mut ApplyProcessingReceiptState,
+    speculative_receipt: SpeculativeReceipt,
+    receipt_sink: &mut ReceiptSink,
+    validator_proposals: &mut Vec<ValidatorStake>,
+) -> Result<Option<ExecutionOutcomeWithId>, RuntimeError> {
+    let SpeculativeReceipt {
+        changes,
+        lookups,
+        outgoing_receipts,
+        validator_proposals: new_validator_proposals,
+        balance,
+        outcome,
+    } = speculative_receipt;
+    let state_update = &mut processing_state.state_update;
+    state_update.apply_fork_changes(changes);
+    processing_state.apply_state.trie_access_tracker_state.apply_lookups(lookups);
+    for receipt in outgoing_receipts {
+        receipt_sink.forward_or_buffer_receipt(
+            receipt,
+            processing_state.apply_state,
+            state_update,
+            processing_state.epoch_info_provider,
+        )?;
+    }
+    validator_proposals.extend(new_validator_proposals);
+    let total = &mut processing_state.stats.balance;
+    total.tx_burnt_amount = safe_add_balance(total.tx_burnt_amount, balance.tx_burnt_amount)?;
+    total.slashed_burnt_amount =
+        safe_add_balance(total.slashed_burnt_amount, balance.slashed_burnt_amount)?;
+    total.other_burnt_amount =
+        safe_add_balance(total.other_burnt_amount, balance.other_burnt_amount)?;
+    total.gas_deficit_amount =
+        safe_add_balance(total.gas_deficit_amount, balance.gas_deficit_amount)?;
+    // Burnt amounts of global contract actions saturate, see `global_contracts`.
+    total.global_actions_burnt_amount =
+        total.global_actions_burnt_amount.saturating_add(balance.global_actions_burnt_amount);
+    Ok(outcome)
+}
+
+/// Copy of `apply_state` for a fork, with its own accounting of trie accesses.
+fn fork_apply_state(
+    apply_state: &ApplyState,
+    accounting_state: Arc<AccountingState>,
+) -> ApplyState {
+    ApplyState {
+        apply_reason: apply_state.apply_reason.clone(),
+        block_height: apply_state.block_height,
+        prev_block_hash: apply_state.prev_block_hash,
+        block_hash: apply_state.block_hash,
+        shard_id: apply_state.shard_id,
+        epoch_id: apply_state.epoch_id,
+        epoch_height: apply_state.epoch_height,
+        gas_price: apply_state.gas_price,
+        block_timestamp: apply_state.block_timestamp,
+        gas_limit: apply_state.gas_limit,
+        random_seed: apply_state.random_seed,
+        current_protocol_version: apply_state.current_protocol_version,
+        config: Arc::clone(&apply_state.config),
+        cache: apply_state.cache.as_ref().map(|cache| cache.handle()),
+        trie_access_tracker_state: accounting_state,
+        is_new_chunk: apply_state.is_new_chunk,
+        congestion_info: apply_state.congestion_info.clone(),
+        bandwidth_requests: apply_state.bandwidth_requests.clone(),
+    }
+}
@@ -6,8 +6,9 @@ use crate::metrics::{
     PIPELINING_ACTIONS_TASK_WORKING_TIME, PIPELINING_ACTIONS_WAITING_TIME,
 };
 use near_parameters::RuntimeConfig;
+use near_parameters::vm::VMKind;
 use near_primitives::account::{Account, AccountContract};
-use near_primitives::action::{Action, GlobalContractIdentifier};
+use near_primitives::action::{Action, FunctionCallAction, GlobalContractIdentifier};
 use near_primitives::config::ViewConfig;
 use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{Receipt, ReceiptEnum};
@@ -249,6 +250,33 @@ impl ReceiptPreparationPipeline {
         return any_function_calls;
     }
 
+    /// Prepare the contract for the provided receipt to be run on the shadow `vm_kind` VM.
+    ///
+    /// Shadow contracts are never pipelined, they are always prepared in the calling thread.
+    pub(crate) fn get_shadow_contract(
+        &self,
+        receipt: &Receipt,
+        code_hash: CryptoHash,
+        action_index: usize,
+        vm_kind: VMKind,
+    ) -> Box<dyn PreparedContract> {
+        let function_call = function_call_action(receipt, action_index);
+        let wasm_config = near_parameters::vm::Config {
+            vm_kind,
+            ..near_parameters::vm::Config::clone(&self.config.wasm_config)
+        };
+        let gas_counter = self.gas_counter(None, function_call.gas);
+        prepare_function_call(
+            &self.storage,
+            self.contract_cache.as_deref(),
+            Arc::new(wasm_config),
+            gas_counter,
+            code_hash,
+            receipt.receiver_id(),
+            &function_call.method_name,
+        )
+    }
+
     /// Obtain the prepared contract for the provided receipt.
     ///
     /// If the contract is currently being prepared this function will block waiting for the
@@ -265,20 +293,7 @@ impl ReceiptPreparationPipeline {
         view_config: Option<ViewConfig>,
     ) -> Box<dyn PreparedContract> {
         let account_id = receipt.receiver_id();
-        let action = match receipt.receipt() {
-            ReceiptEnum::Action(r) | ReceiptEnum::PromiseYield(r) => r
-                .actions
-                .get(action_index)
-                .expect("indexing receipt actions by an action_index failed!"),
-            ReceiptEnum::GlobalContractDistribution(_)
-            | ReceiptEnum::Data(_)
-            | ReceiptEnum::PromiseResume(_) => {
-                panic!("attempting to get_contract with a non-action receipt!?")
-            }
-        };
-        let Action::FunctionCall(function_call) = action else {
-            panic!("referenced receipt action is not a function call!");
-        };
+        let function_call = function_call_action(receipt, action_index);
         let key = PrepareTaskKey { receipt_id: receipt.get_hash(), action_index };
         let Some(task) = self.map.get(&key) else {
             let start = Instant::now();
@@ -369,6 +384,24 @@ impl ReceiptPreparationPipeline {
     }
 }
 
+fn function_call_action(receipt: &Receipt, action_index: usize) -> &FunctionCallAction {
+    let action = match receipt.receipt() {
+        ReceiptEnum::Action(r) | ReceiptEnum::PromiseYield(r) => r
+            .actions
+            .get(action_index)
+            .expect("indexing receipt actions by an action_index failed!"),
+        ReceiptEnum::GlobalContractDistribution(_)
+        | ReceiptEnum::Data(_)
+        | ReceiptEnum::PromiseResume(_) => {
+            panic!("attempting to get_contract with a non-action receipt!?")
+        }
+    };
+    let Action::FunctionCall(function_call) = action else {
+        panic!("referenced receipt action is not a function call!");
+    };
+    function_call
+}
+
 fn prepare_function_call(
     contract_storage: &ContractStorage,
     cache: Option<&dyn ContractRuntimeCache>,
@@ -40,18 +40,29 @@
 //! the number of IO threads, and memory checks before staring new DB requests
 //! in the prefetcher. Implementation details for most limits are in
 //! `core/store/src/trie/prefetching_trie_storage.rs`
-
-use borsh::BorshSerialize as _;
+//!
+//! Chunk producers can additionally start prefetching for the next chunk of a
+//! shard before it is produced, see `prefetch_next_chunk_data`. At that point
+//! the transactions are still in the transaction pool, and the incoming
+//! receipts are the outgoing receipts of the previous block. The prefetched
+//! data stays in the staging area until the next chunk application, which
+//! clears it when done.
+//!
+//! Contract storage (`TrieKey::ContractData`) is not prefetched, as there is no
+//! general way to predict which keys a function call reads. This used to be done
+//! for a few hard-coded contracts (SWEAT and Kaiching), configured with
+//! `store.sweat_prefetch_*`, `store.claim_sweat_prefetch_config` and
+//! `store.kaiching_prefetch_config`. These prefetchers were removed, so on nodes
+//! without memtries calls to those contracts may wait longer on disk reads.
+
+use near_crypto::PublicKey;
 use near_o11y::metrics::prometheus;
 use near_o11y::metrics::prometheus::core::GenericCounter;
 use near_primitives::receipt::{Receipt, ReceiptEnum};
 use near_primitives::transaction::Action;
 use near_primitives::trie_key::TrieKey;
-use near_primitives::types::AccountId;
-use near_primitives::types::StateRoot;
+use near_primitives::types::{AccountId, StateRoot};
 use near_store::{PrefetchApi, PrefetchError, Trie};
-use sha2::Digest;
-use std::str::FromStr;
 use tracing::{debug, warn};
 
 use crate::{SignedValidPeriodTransactions, metrics};
@@ -141,47 +152,13 @@ impl TriePrefetcher {
                 }
             }
 
-            let mut code_prefetch_requested = false;
-            for action in &action_receipt.actions {
-                let Action::FunctionCall(fn_call) = action else {
-                    continue;
-                };
-                if !code_prefetch_requested {
-                    let trie_key = TrieKey::ContractCode { account_id: account_id.clone() };
-                    self.prefetch_trie_key(trie_key)?;
-                    code_prefetch_requested = true;
-                }
-
-                if self.prefetch_api.sweat_prefetch_receivers.contains(&account_id)
-                    && self.prefetch_api.sweat_prefetch_senders.contains(receipt.predecessor_id())
-                {
-                    if fn_call.method_name == "record_batch" {
-                        self.prefetch_sweat_record_batch(account_id.clone(), &fn_call.args)?;
-                    }
-                }
-
-                let claim_sweat_cfg = &self.prefetch_api.claim_sweat_prefetch_config;
-                if fn_call.method_name == "record_batch_for_hold" {
-                    let config = claim_sweat_cfg.iter().find(|cfg| {
-                        cfg.receiver == account_id.as_str()
-                            && cfg.method_name == fn_call.method_name
-                            && cfg.sender == receipt.predecessor_id().as_str()
-                    });
-                    if config.is_some() {
-                        self.prefetch_claim_sweat_record_batch_for_hold(
-                            account_id.clone(),
-                            &fn_call.args,
-                        )?
-                    }
-                }
-
-                if self.prefetch_api.kaiching_prefetch_config.iter().any(|cfg| {
-                    cfg.sender == receipt.predecessor_id().as_str()
-                        && cfg.receiver == account_id.as_str()
-                        && cfg.method_name == fn_call.method_name
-                }) {
-                    self.prefetch_kaiching(account_id.clone(), &fn_call.args)?;
-                }
+            let has_function_call = action_receipt
+                .actions
+                .iter()
+                .any(|action| matches!(action, Action::FunctionCall(_)));
+            if has_function_call {
+                let trie_key = TrieKey::ContractCode { account_id };
+                self.prefetch_trie_key(trie_key)?;
             }
         }
         Ok(())
@@ -195,16 +172,27 @@ impl TriePrefetcher {
     pub(crate) fn prefetch_transactions_data(
         &self,
         signed_txs: &SignedValidPeriodTransactions,
+    ) -> Result<(), PrefetchError> {
+        self.prefetch_signers_data(
+            signed_txs
+                .iter_nonexpired_transactions()
+                .map(|t| (t.transaction.signer_id(), t.transaction.public_key())),
+        )
+    }
+
+    /// Starts prefetching the accounts and the access keys of transaction signers.
+    fn prefetch_signers_data<'a>(
+        &self,
+        signers: impl Iterator<Item = (&'a AccountId, &'a PublicKey)>,
     ) -> Result<(), PrefetchError> {
         if self.prefetch_api.enable_receipt_prefetching {
-            for t in signed_txs.iter_nonexpired_transactions() {
-                let account_id = t.transaction.signer_id().clone();
-                let trie_key = TrieKey::Account { account_id };
+            for (account_id, public_key) in signers {
+                let trie_key = TrieKey::Account { account_id: account_id.clone() };
                 self.prefetch_trie_key(trie_key)?;
 
                 let trie_key = TrieKey::AccessKey {
-                    account_id: t.transaction.signer_id().clone(),
-                    public_key: t.transaction.public_key().clone(),
+                    account_id: account_id.clone(),
+                    public_key: public_key.clone(),
                 };
                 self.prefetch_trie_key(trie_key)?;
             }
@@ -249,146 +237,49 @@ impl TriePrefetcher {
         };
         res
     }
+}
 
-    /// Prefetcher tuned for SWEAT contract calls of method steps_batch.
-    ///
-    /// Remove after #10965 reaches mainnet.
-    fn prefetch_sweat_record_batch(
-        &self,
-        account_id: AccountId,
-        arg: &[u8],
-    ) -> Result<(), PrefetchError> {
-        let Ok(json) = serde_json::de::from_slice::<serde_json::Value>(arg) else {
-            return Ok(());
-        };
-        let Some(list) = &json.get("steps_batch") else {
-            return Ok(());
-        };
-        let Some(list) = list.as_array() else {
-            return Ok(());
-        };
-
-        for tuple in list {
-            let Some(tuple) = tuple.as_array() else {
-                continue;
-            };
-            let Some(user_account) = tuple.first().and_then(|a| a.as_str()) else {
-                continue;
-            };
-            let hashed_account = sha2::Sha256::digest(user_account.as_bytes()).into_iter();
-            // This is a "t" string used as the unique prefix of underlying
-            // data structure terminated by a null value.
-            let mut key = vec![0x74, 0x00];
-            key.extend(hashed_account);
-            let trie_key =
-                TrieKey::ContractData { account_id: account_id.clone(), key: key.to_vec() };
-            near_o11y::io_trace!(count: "prefetch");
-            self.prefetch_trie_key(trie_key)?;
-        }
-        Ok(())
-    }
-
-    /// Prefetcher tuned for claim.sweat::record_batch_for_hold contract calls.
-    ///
-    /// Remove after #10965 reaches mainnet.
-    fn prefetch_claim_sweat_record_batch_for_hold(
-        &self,
-        account_id: AccountId,
-        arg: &[u8],
-    ) -> Result<(), PrefetchError> {
-        let Ok(json) = serde_json::de::from_slice::<serde_json::Value>(arg) else {
-            return Ok(());
-        };
-        let Some(list) = json.get("amounts") else {
-            return Ok(());
-        };
-        let Some(list) = list.as_array() else {
-            return Ok(());
-        };
-        for tuple in list {
-            let Some(tuple) = tuple.as_array() else { continue };
-            let Some(user_account) = tuple.first().and_then(|a| a.as_str()) else { continue };
-            let mut account_data_key = Vec::with_capacity(4 + 8 + user_account.len());
-            // (branch v2) StorageKey::Accounts = 4u8
-            let Ok(()) = 4u8.serialize(&mut account_data_key) else { continue };
-            let Ok(()) = user_account.serialize(&mut account_data_key) else { continue };
-            let trie_key =
-                TrieKey::ContractData { account_id: account_id.clone(), key: account_data_key };
-            near_o11y::io_trace!(count: "prefetch");
-            self.prefetch_trie_key(trie_key)?;
-        }
-        Ok(())
-    }
-
-    /// Prefetcher tuned for kaiching contract calls.
-    ///
-    /// Remove after #10965 reaches mainnet.
-    fn prefetch_kaiching(&self, account_id: AccountId, arg: &[u8]) -> Result<(), PrefetchError> {
-        let Ok(json) = serde_json::de::from_slice::<serde_json::Value>(&arg) else {
-            return Ok(());
-        };
-        let Some(msg) = json.get("msg") else {
-            return Ok(());
-        };
-        let Some(json) = msg
-            .as_str()
-            .and_then(|s| serde_json::de::from_slice::<serde_json::Value>(s.as_bytes()).ok())
-        else {
-            return Ok(());
-        };
-        let Some(list) = json.get("rewards") else {
-            return Ok(());
-        };
-        let Some(list) = list.as_array() else {
-            return Ok(());
-        };
-
-        for tuple in list {
-            let Some(tuple) = tuple.as_array() else {
-                continue;
-            };
-            // Unique prefix of underlying data structure.
-            let mut user_account_key = vec![1, 109];
-            let user_account_serialize_result = tuple
-                .get(0)
-                .and_then(|a| a.as_str())
-                .and_then(|a| AccountId::from_str(a).ok())
-                .and_then(|a| borsh::BorshSerialize::serialize(&a, &mut user_account_key).ok());
-            if user_account_serialize_result.is_none() {
-                continue;
-            }
-            let reward_id = tuple.get(2).and_then(|a| a.as_str());
-            let Some(reward_id) = reward_id else {
-                continue;
-            };
-            let user_account_key_hash = sha2::Sha256::digest(&user_account_key);
-            let trie_key = TrieKey::ContractData {
-                account_id: account_id.clone(),
-                key: user_account_key_hash.to_vec(),
-            };
-            near_o11y::io_trace!(count: "prefetch");
-            self.prefetch_trie_key(trie_key)?;
-
-            // Unique prefix of underlying data structure.
-            let mut reward_key = vec![0, 24, 0, 0, 0];
-            reward_key.extend(reward_id.as_bytes());
-            let trie_key =
-                TrieKey::ContractData { account_id: account_id.clone(), key: reward_key };
-            near_o11y::io_trace!(count: "prefetch");
-            self.prefetch_trie_key(trie_key)?;
-        }
-        Ok(())
+/// Starts prefetching data which the next chunk of the shard is expected to
+/// access: accounts and access keys of the transaction `signers`, and the data
+/// accessed by `receipts`. `trie` must be at the pre-state of the chunk.
+///
+/// Returns an error if prefetching stopped early, e.g. because the prefetch
+/// queue is full. Does nothing if prefetching is disabled or if the trie is
+/// served from memtries, which need no prefetching.
+pub fn prefetch_next_chunk_data(
+    trie: &Trie,
+    signers: &[(AccountId, PublicKey)],
+    receipts: &[Receipt],
+) -> Result<(), PrefetchError> {
+    if trie.has_memtries() {
+        return Ok(());
     }
+    let Some(prefetcher) = TriePrefetcher::new_if_enabled(trie) else {
+        return Ok(());
+    };
+    prefetcher.prefetch_signers_data(
+        signers.iter().map(|(account_id, public_key)| (account_id, public_key)),
+    )?;
+    prefetcher.prefetch_receipts_data(receipts)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::TriePrefetcher;
-    use near_primitives::{trie_key::TrieKey, types::AccountId};
+    use super::{TriePrefetcher, prefetch_next_chunk_data};
+    use near_crypto::{InMemorySigner, PublicKey};
+    use near_primitives::action::FunctionCallAction;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum, ReceiptV0};
+    use near_primitives::state::PartialState;
+    use near_primitives::transaction::Action;
+    use near_primitives::{
+        trie_key::TrieKey,
+        types::{AccountId, StateRoot},
+    };
     use near_store::adapter::StoreAdapter;
-    use near_store::test_utils::{create_test_store, test_populate_trie};
+    use near_store::test_utils::{TestTriesBuilder, create_test_store, test_populate_trie};
     use near_store::trie::AccessOptions;
-    use near_store::{ShardTries, ShardUId, StateSnapshotConfig, Trie, TrieConfig};
+    use near_store::{PrefetchApi, ShardTries, ShardUId, StateSnapshotConfig, Trie, TrieConfig};
     use std::str::FromStr;
     use std::time::{Duration, Instant};
 
@@ -504,6 +395,28 @@ mod tests {
         for trie_key in &prefetch_keys {
             _ = prefetcher.prefetch_trie_key(trie_key.clone());
         }
+        wait_for_prefetching(prefetch_api, tries);
+
+        assert_eq!(
+            prefetch_api.num_prefetched_and_staged(),
+            expected_prefetched,
+            "unexpected number of prefetched values"
+        );
+
+        // Read all prefetched values to ensure everything gets removed from the staging area.
+        for trie_key in &prefetch_keys {
+            let storage_key = trie_key.to_vec();
+            let _value = trie.get(&storage_key, AccessOptions::DEFAULT).unwrap();
+        }
+        assert_eq!(
+            prefetch_api.num_prefetched_and_staged(),
+            0,
+            "prefetching staging area not clear after reading all values from main thread"
+        );
+    }
+
+    /// Waits until the IO threads are done with all queued prefetch requests.
+    fn wait_for_prefetching(prefetch_api: &PrefetchApi, tries: ShardTries) {
         std::thread::yield_now();
 
         let wait_work_queue_empty_start = Instant::now();
@@ -546,23 +459,127 @@ mod tests {
                 );
             }
         });
+    }
 
-        assert_eq!(
-            prefetch_api.num_prefetched_and_staged(),
-            expected_prefetched,
-            "unexpected number of prefetched values"
-        );
+    #[test]
+    fn test_prefetch_next_chunk_data() {
+        let (signer_id, signer_key) = test_account_and_key("alice.near");
+        let (receiver_id, _) = test_account_and_key("bob.near");
+        let (other_id, other_key) = test_account_and_key("carol.near");
+        let expected_keys = [
+            TrieKey::Account { account_id: signer_id.clone() },
+            TrieKey::AccessKey { account_id: signer_id.clone(), public_key: signer_key.clone() },
+            TrieKey::Account { account_id: receiver_id.clone() },
+            TrieKey::ContractCode { account_id: receiver_id.clone() },
+        ];
+        let other_keys = [
+            TrieKey::Account { account_id: other_id.clone() },
+            TrieKey::AccessKey { account_id: other_id, public_key: other_key },
+        ];
 
-        // Read all prefetched values to ensure everything gets removed from the staging area.
-        for trie_key in &prefetch_keys {
-            let storage_key = trie_key.to_vec();
-            let _value = trie.get(&storage_key, AccessOptions::DEFAULT).unwrap();
+        let tries = TestTriesBuilder::new().with_receipt_prefetching(true).build();
+        let shard_uid = ShardUId::single_shard();
+        let root = populate_trie(&tries, expected_keys.iter().chain(&other_keys));
+
+        // The prefetched values are the nodes and values on the paths to the expected keys.
+        let recording_trie =
+            tries.get_trie_for_shard(shard_uid, root).recording_reads_new_recorder();
+        for trie_key in &expected_keys {
+            recording_trie.get(&trie_key.to_vec(), AccessOptions::DEFAULT).unwrap().unwrap();
         }
-        assert_eq!(
-            prefetch_api.num_prefetched_and_staged(),
-            0,
-            "prefetching staging area not clear after reading all values from main thread"
-        );
+        let PartialState::TrieValues(expected_prefetched) =
+            recording_trie.recorded_storage().unwrap().nodes;
+
+        let trie = tries.get_trie_for_shard(shard_uid, root);
+        let caching_storage = trie.internal_get_storage_as_caching_storage().unwrap();
+        caching_storage.clear_cache();
+        let prefetch_api = caching_storage.prefetch_api().clone().unwrap();
+        let receipt = function_call_receipt(&signer_id, &signer_key, &receiver_id);
+        prefetch_next_chunk_data(&trie, &[(signer_id, signer_key)], &[receipt]).unwrap();
+        wait_for_prefetching(&prefetch_api, tries);
+
+        assert_eq!(prefetch_api.num_prefetched_and_staged(), expected_prefetched.len());
+        for trie_key in &expected_keys {
+            trie.get(&trie_key.to_vec(), AccessOptions::DEFAULT).unwrap();
+        }
+        assert_eq!(prefetch_api.num_prefetched_and_staged(), 0);
+    }
+
+    #[test]
+    fn test_prefetch_next_chunk_data_disabled() {
+        let (account_id, public_key) = test_account_and_key("alice.near");
+        let trie_key = TrieKey::Account { account_id: account_id.clone() };
+        let tries = TestTriesBuilder::new().build();
+        let root = populate_trie(&tries, [&trie_key].into_iter());
+        let trie = tries.get_trie_for_shard(ShardUId::single_shard(), root);
+        assert!(trie.internal_get_storage_as_caching_storage().unwrap().prefetch_api().is_none());
+
+        let receipt = function_call_receipt(&account_id, &public_key, &account_id);
+        prefetch_next_chunk_data(&trie, &[(account_id, public_key)], &[receipt]).unwrap();
+    }
+
+    #[test]
+    fn test_prefetch_next_chunk_data_with_memtries() {
+        let (account_id, public_key) = test_account_and_key("alice.near");
+        let trie_key = TrieKey::Account { account_id: account_id.clone() };
+        let tries = TestTriesBuilder::new()
+            .with_flat_storage(true)
+            .with_in_memory_tries(true)
+            .with_receipt_prefetching(true)
+            .build();
+        let root = populate_trie(&tries, [&trie_key].into_iter());
+        let trie = tries.get_trie_for_shard(ShardUId::single_shard(), root);
+        assert!(trie.has_memtries());
+        let prefetch_api =
+            trie.internal_get_storage_as_caching_storage().unwrap().prefetch_api().clone().unwrap();
+
+        let receipt = function_call_receipt(&account_id, &public_key, &account_id);
+        prefetch_next_chunk_data(&trie, &[(account_id, public_key)], &[receipt]).unwrap();
+        wait_for_prefetching(&prefetch_api, tries);
+        assert_eq!(prefetch_api.num_prefetched_and_staged(), 0);
+    }
+
+    fn test_account_and_key(account_id: &str) -> (AccountId, PublicKey) {
+        let account_id = AccountId::from_str(account_id).unwrap();
+        let public_key = InMemorySigner::test_signer(&account_id).public_key();
+        (account_id, public_key)
+    }
+
+    /// Inserts a distinct value for each of `trie_keys` into an empty trie.
+    fn populate_trie<'a>(
+        tries: &ShardTries,
+        trie_keys: impl Iterator<Item = &'a TrieKey>,
+    ) -> StateRoot {
+        let changes = trie_keys
+            .enumerate()
+            .map(|(i, trie_key)| (trie_key.to_vec(), Some(i.to_string().into_bytes())))
+            .collect();
+        test_populate_trie(tries, &Trie::EMPTY_ROOT, ShardUId::single_shard(), changes)
+    }
+
+    fn function_call_receipt(
+        signer_id: &AccountId,
+        signer_public_key: &PublicKey,
+        receiver_id: &AccountId,
+    ) -> Receipt {
+        Receipt::V0(ReceiptV0 {
+            predecessor_id: signer_id.clone(),
+            receiver_id: receiver_id.clone(),
+            receipt_id: CryptoHash::default(),
+            receipt: ReceiptEnum::Action(ActionReceipt {
+                signer_id: signer_id.clone(),
+                signer_public_key: signer_public_key.clone(),
+                gas_price: 0,
+                output_data_receivers: vec![],
+                input_data_ids: vec![],
+                actions: vec![Action::FunctionCall(Box::new(FunctionCallAction {
+                    method_name: "main".to_string(),
+                    args: vec![],
+                    gas: 0,
+                    deposit: 0,
+                }))],
+            }),
+        })
     }
 
     #[track_caller]
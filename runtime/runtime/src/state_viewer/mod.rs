@@ -1,8 +1,9 @@
 use crate::ApplyState;
-use crate::actions::execute_function_call;
+use crate::actions::{action_deploy_contract, execute_function_call};
 use crate::ext::RuntimeExt;
 use crate::pipelining::ReceiptPreparationPipeline;
 use crate::receipt_manager::ReceiptManager;
+use crate::verifier::{StorageStakingError, check_storage_stake};
 use near_crypto::{KeyType, PublicKey};
 use near_parameters::RuntimeConfigStore;
 use near_primitives::account::{AccessKey, Account};
@@ -12,16 +13,17 @@ use near_primitives::bandwidth_scheduler::BlockBandwidthRequests;
 use near_primitives::borsh::BorshDeserialize;
 use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum, ReceiptV1};
-use near_primitives::transaction::FunctionCallAction;
+use near_primitives::transaction::{DeployContractAction, FunctionCallAction};
 use near_primitives::trie_key::trie_key_parsers;
 use near_primitives::types::{
-    AccountId, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, ShardId,
+    AccountId, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, ShardId,
+    StorageUsage,
 };
 use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::{StateItem, ViewStateResult};
 use near_primitives_core::config::ViewConfig;
-use near_store::{TrieUpdate, get_access_key, get_account};
-use near_vm_runner::logic::{ProtocolVersion, ReturnData};
+use near_store::{TrieUpdate, get_access_key, get_account, set_account};
+use near_vm_runner::logic::{ProtocolVersion, ReturnData, VMOutcome};
 use near_vm_runner::{ContractCode, ContractRuntimeCache};
 use std::{str, sync::Arc, time::Instant};
 use tracing::debug;
@@ -51,6 +53,22 @@ pub struct ViewApplyState {
     pub cache: Option<Box<dyn ContractRuntimeCache>>,
 }
 
+/// Result of `TrieViewer::upgrade_contract`.
+#[derive(Debug)]
+pub struct ContractUpgradeOutcome {
+    /// Storage usage of the account before the upgrade.
+    pub storage_usage_before: StorageUsage,
+    /// Storage usage after deploying the new code and running the migration.
+    pub storage_usage_after: StorageUsage,
+    /// Gas burnt by the migration, zero if there was none.
+    pub migration_gas_burnt: Gas,
+    /// Number of receipts created by the migration. They are not executed.
+    pub migration_receipts: usize,
+    /// Balance missing to cover the storage stake after the upgrade, if any.
+    /// A real upgrade would fail in this case.
+    pub lacking_storage_stake: Option<Balance>,
+}
+
 pub struct TrieViewer {
     /// Upper bound of the byte size of contract state that is still viewable. None is no limit
     state_size_limit: Option<u64>,
@@ -203,14 +221,169 @@ impl TrieViewer {
         args: &[u8],
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
+    ) -> Result<Vec<u8>, errors::CallFunctionError> {
+        self.call_function_on_update(
+            &mut state_update,
+            view_state,
+            contract_id,
+            method_name,
+            args,
+            logs,
+            epoch_info_provider,
+        )
+    }
+
+    /// Same as `call_function`, but borrows the `TrieUpdate`, so that the call
+    /// can observe uncommitted changes made to it, e.g. by `upgrade_contract`.
+    pub fn call_function_on_update(
+        &self,
+        state_update: &mut TrieUpdate,
+        view_state: ViewApplyState,
+        contract_id: &AccountId,
+        method_name: &str,
+        args: &[u8],
+        logs: &mut Vec<String>,
+        epoch_info_provider: &dyn EpochInfoProvider,
     ) -> Result<Vec<u8>, errors::CallFunctionError> {
         let now = Instant::now();
-        let root = *state_update.get_root();
-        let account = get_account(&state_update, contract_id)?.ok_or_else(|| {
+        let account = get_account(state_update, contract_id)?.ok_or_else(|| {
+            errors::CallFunctionError::AccountDoesNotExist {
+                requested_account_id: contract_id.clone(),
+            }
+        })?;
+        let function_call = FunctionCallAction {
+            method_name: method_name.to_string(),
+            args: args.to_vec(),
+            gas: self.max_gas_burnt_view,
+            deposit: 0,
+        };
+        let view_config = Some(ViewConfig { max_gas_burnt: self.max_gas_burnt_view });
+        let (outcome, _) = self.execute_function_call(
+            state_update,
+            view_state,
+            contract_id,
+            account,
+            function_call,
+            view_config,
+            epoch_info_provider,
+        )?;
+        let elapsed = now.elapsed();
+        let time_ms =
+            (elapsed.as_secs() as f64 / 1_000.0) + f64::from(elapsed.subsec_nanos()) / 1_000_000.0;
+        let time_str = format!("{:.*}ms", 2, time_ms);
+
+        if let Some(err) = outcome.aborted {
+            logs.extend(outcome.logs);
+            let message = format!("wasm execution failed with error: {:?}", err);
+            debug!(target: "runtime", "(exec time {}) {}", time_str, message);
+            Err(errors::CallFunctionError::VMError { error_message: message })
+        } else {
+            debug!(target: "runtime", "(exec time {}) result of execution: {:?}", time_str, outcome);
+            logs.extend(outcome.logs);
+            let result = match outcome.return_data {
+                ReturnData::Value(buf) => buf,
+                ReturnData::ReceiptIndex(_) | ReturnData::None => vec![],
+            };
+            Ok(result)
+        }
+    }
+
+    /// Deploys `code` to `contract_id` and calls the `migration` method with
+    /// the given arguments on it, like a `DeployContract` and `FunctionCall`
+    /// batch sent by the account to itself would.
+    ///
+    /// Nothing is committed, the changes are only kept in `state_update`, so
+    /// that the upgraded contract can be inspected with
+    /// `call_function_on_update`. Receipts created by the migration are not
+    /// executed.
+    pub fn upgrade_contract(
+        &self,
+        state_update: &mut TrieUpdate,
+        view_state: ViewApplyState,
+        contract_id: &AccountId,
+        code: Vec<u8>,
+        migration: Option<(&str, &[u8])>,
+        logs: &mut Vec<String>,
+        epoch_info_provider: &dyn EpochInfoProvider,
+    ) -> Result<ContractUpgradeOutcome, errors::CallFunctionError> {
+        let mut account = get_account(state_update, contract_id)?.ok_or_else(|| {
             errors::CallFunctionError::AccountDoesNotExist {
                 requested_account_id: contract_id.clone(),
             }
         })?;
+        let storage_usage_before = account.storage_usage();
+        let config_store = RuntimeConfigStore::new(None);
+        let config = config_store.get_config(PROTOCOL_VERSION);
+        action_deploy_contract(
+            state_update,
+            &mut account,
+            contract_id,
+            &DeployContractAction { code },
+            Arc::clone(&config.wasm_config),
+            view_state.cache.as_deref(),
+            view_state.current_protocol_version,
+        )?;
+
+        let mut outcome = ContractUpgradeOutcome {
+            storage_usage_before,
+            storage_usage_after: account.storage_usage(),
+            migration_gas_burnt: 0,
+            migration_receipts: 0,
+            lacking_storage_stake: None,
+        };
+        if let Some((method_name, args)) = migration {
+            let function_call = FunctionCallAction {
+                method_name: method_name.to_string(),
+                args: args.to_vec(),
+                gas: config.wasm_config.limit_config.max_total_prepaid_gas,
+                deposit: 0,
+            };
+            let (vm_outcome, num_receipts) = self.execute_function_call(
+                state_update,
+                view_state,
+                contract_id,
+                account.clone(),
+                function_call,
+                None,
+                epoch_info_provider,
+            )?;
+            logs.extend(vm_outcome.logs);
+            if let Some(err) = vm_outcome.aborted {
+                let message = format!("migration failed with error: {:?}", err);
+                return Err(errors::CallFunctionError::VMError { error_message: message });
+            }
+            account.set_amount(vm_outcome.balance);
+            account.set_storage_usage(vm_outcome.storage_usage);
+            outcome.storage_usage_after = vm_outcome.storage_usage;
+            outcome.migration_gas_burnt = vm_outcome.burnt_gas;
+            outcome.migration_receipts = num_receipts;
+        }
+        match check_storage_stake(&account, config) {
+            Ok(()) => {}
+            Err(StorageStakingError::LackBalanceForStorageStaking(amount)) => {
+                outcome.lacking_storage_stake = Some(amount);
+            }
+            Err(StorageStakingError::StorageError(error_message)) => {
+                return Err(errors::CallFunctionError::InternalError { error_message });
+            }
+        }
+        set_account(state_update, contract_id.clone(), &account);
+        Ok(outcome)
+    }
+
+    /// Executes a function call on behalf of the contract itself. Returns the
+    /// outcome together with the number of receipts the call created.
+    fn execute_function_call(
+        &self,
+        state_update: &mut TrieUpdate,
+        view_state: ViewApplyState,
+        contract_id: &AccountId,
+        account: Account,
+        function_call: FunctionCallAction,
+        view_config: Option<ViewConfig>,
+        epoch_info_provider: &dyn EpochInfoProvider,
+    ) -> Result<(VMOutcome, usize), errors::CallFunctionError> {
+        let root = *state_update.get_root();
         // TODO(#1015): Add ability to pass public key and originator_id
         let originator_id = contract_id;
         let public_key = PublicKey::empty(KeyType::ED25519);
@@ -239,12 +412,6 @@ impl TrieViewer {
             bandwidth_requests: BlockBandwidthRequests::empty(),
             trie_access_tracker_state: Default::default(),
         };
-        let function_call = FunctionCallAction {
-            method_name: method_name.to_string(),
-            args: args.to_vec(),
-            gas: self.max_gas_burnt_view,
-            deposit: 0,
-        };
         let action_receipt = ActionReceipt {
             signer_id: originator_id.clone(),
             signer_public_key: public_key,
@@ -265,12 +432,11 @@ impl TrieViewer {
             apply_state.cache.as_ref().map(|v| v.handle()),
             state_update.contract_storage(),
         );
-        let view_config = Some(ViewConfig { max_gas_burnt: self.max_gas_burnt_view });
         let code_hash = state_update.get_account_contract_hash(account.contract().as_ref())?;
         let contract = pipeline.get_contract(&receipt, code_hash, 0, view_config.clone());
 
         let mut runtime_ext = RuntimeExt::new(
-            &mut state_update,
+            state_update,
             &mut receipt_manager,
             contract_id.clone(),
             account,
@@ -285,6 +451,7 @@ impl TrieViewer {
         );
         let outcome = execute_function_call(
             contract,
+            None,
             &apply_state,
             &mut runtime_ext,
             originator_id,
@@ -297,24 +464,6 @@ impl TrieViewer {
             view_config,
         )
         .map_err(|e| errors::CallFunctionError::InternalError { error_message: e.to_string() })?;
-        let elapsed = now.elapsed();
-        let time_ms =
-            (elapsed.as_secs() as f64 / 1_000.0) + f64::from(elapsed.subsec_nanos()) / 1_000_000.0;
-        let time_str = format!("{:.*}ms", 2, time_ms);
-
-        if let Some(err) = outcome.aborted {
-            logs.extend(outcome.logs);
-            let message = format!("wasm execution failed with error: {:?}", err);
-            debug!(target: "runtime", "(exec time {}) {}", time_str, message);
-            Err(errors::CallFunctionError::VMError { error_message: message })
-        } else {
-            debug!(target: "runtime", "(exec time {}) result of execution: {:?}", time_str, outcome);
-            logs.extend(outcome.logs);
-            let result = match outcome.return_data {
-                ReturnData::Value(buf) => buf,
-                ReturnData::ReceiptIndex(_) | ReturnData::None => vec![],
-            };
-            Ok(result)
-        }
+        Ok((outcome, receipt_manager.action_receipts.len()))
     }
 }
@@ -46,7 +46,7 @@ use near_store::{
 use near_vm_runner::{ContractCode, FilesystemContractRuntimeCache};
 use std::collections::HashSet;
 use std::sync::Arc;
-use testlib::runtime_utils::{alice_account, bob_account};
+use testlib::runtime_utils::{alice_account, bob_account, carol_account};
 
 /***************/
 /* Apply tests */
@@ -3200,3 +3200,111 @@ fn test_expired_transaction() {
         "should have not produced any outcomes for the expired tx"
     );
 }
+
+/// Parallel execution of receipts must give exactly the same result as the
+/// sequential one, also when receipts of different receivers touch the same
+/// trie nodes and when receipts of the same receiver depend on each other.
+#[test]
+fn test_parallel_receipt_execution() {
+    let accounts = vec![alice_account(), bob_account(), carol_account()];
+    let (runtime, tries, root, mut apply_state, signers, epoch_info_provider) =
+        setup_runtime(accounts.clone(), to_yocto(1_000_000), to_yocto(500_000), 10u64.pow(15));
+    let shard_uid = ShardUId::single_shard();
+
+    let deploy_receipts: Vec<_> = accounts
+        .iter()
+        .zip(&signers)
+        .map(|(account_id, signer)| {
+            create_receipt_with_actions(
+                account_id.clone(),
+                signer.clone(),
+                vec![Action::DeployContract(DeployContractAction {
+                    code: near_test_contracts::rs_contract().to_vec(),
+                })],
+            )
+        })
+        .collect();
+    let apply_result = runtime
+        .apply(
+            tries.get_trie_for_shard(shard_uid, root),
+            &None,
+            &apply_state,
+            &deploy_receipts,
+            SignedValidPeriodTransactions::empty(),
+            &epoch_info_provider,
+            Default::default(),
+        )
+        .unwrap();
+    let root = commit_apply_result(&apply_result, &mut apply_state, &tries, shard_uid);
+
+    let write_key_value = |account_index: usize, key: u64, value: u64| {
+        let args = [key.to_le_bytes(), value.to_le_bytes()].concat();
+        create_receipt_with_actions(
+            accounts[account_index].clone(),
+            signers[account_index].clone(),
+            vec![Action::FunctionCall(Box::new(FunctionCallAction {
+                method_name: "write_key_value".to_string(),
+                args,
+                gas: MAX_ATTACHED_GAS,
+                deposit: 0,
+            }))],
+        )
+    };
+    let mut receipts = vec![];
+    for i in 0..5 {
+        receipts.push(write_key_value(0, i, i));
+        receipts.push(write_key_value(1, i % 2, i));
+        receipts.push(write_key_value(2, 0, i));
+    }
+    // Transfers change the balance of the receiver, which is also read and
+    // changed by the function calls.
+    receipts.push(Receipt::V0(ReceiptV0 {
+        predecessor_id: alice_account(),
+        receiver_id: bob_account(),
+        receipt_id: hash(b"transfer"),
+        receipt: ReceiptEnum::Action(ActionReceipt {
+            signer_id: alice_account(),
+            signer_public_key: signers[0].public_key(),
+            gas_price: GAS_PRICE,
+            output_data_receivers: vec![],
+            input_data_ids: vec![],
+            actions: vec![Action::Transfer(TransferAction { deposit: to_yocto(1) })],
+        }),
+    }));
+    receipts.push(write_key_value(1, 10, 10));
+    let transactions = vec![
+        SignedTransaction::send_money(1, alice_account(), alice_account(), &*signers[0], 1, root),
+        SignedTransaction::send_money(1, carol_account(), bob_account(), &*signers[2], 1, root),
+    ];
+
+    let apply = |runtime: &Runtime, apply_state: &mut ApplyState| {
+        apply_state.trie_access_tracker_state = Default::default();
+        runtime
+            .apply(
+                tries.get_trie_for_shard(shard_uid, root),
+                &None,
+                apply_state,
+                &receipts,
+                SignedValidPeriodTransactions::new(transactions.clone(), vec![true, true]),
+                &epoch_info_provider,
+                Default::default(),
+            )
+            .unwrap()
+    };
+    let sequential = apply(&runtime, &mut apply_state);
+    let parallel_runtime = Runtime::new().with_parallel_receipt_execution(true);
+    let parallel = apply(&parallel_runtime, &mut apply_state);
+
+    assert_eq!(sequential.delayed_receipts_count, 0);
+    assert_eq!(parallel.state_root, sequential.state_root);
+    assert_eq!(parallel.outcomes, sequential.outcomes);
+    assert_eq!(parallel.outgoing_receipts, sequential.outgoing_receipts);
+    assert_eq!(
+        borsh::to_vec(&parallel.state_changes).unwrap(),
+        borsh::to_vec(&sequential.state_changes).unwrap()
+    );
+    assert_eq!(
+        borsh::to_vec(&parallel.stats.balance).unwrap(),
+        borsh::to_vec(&sequential.stats.balance).unwrap()
+    );
+}
@@ -1,6 +1,7 @@
 use crate::VerificationResult;
 use crate::config::{TransactionCost, total_prepaid_gas};
 use crate::near_primitives::account::Account;
+use near_crypto::KeyType;
 use near_crypto::key_conversion::is_valid_staking_key;
 use near_parameters::RuntimeConfig;
 use near_primitives::account::{AccessKey, AccessKeyPermission};
@@ -413,7 +414,7 @@ pub fn validate_action(
         Action::FunctionCall(a) => validate_function_call_action(limit_config, a),
         Action::Transfer(_) => Ok(()),
         Action::Stake(a) => validate_stake_action(a),
-        Action::AddKey(a) => validate_add_key_action(limit_config, a),
+        Action::AddKey(a) => validate_add_key_action(limit_config, a, current_protocol_version),
         Action::DeleteKey(_) => Ok(()),
         Action::DeleteAccount(a) => validate_delete_action(a),
         Action::Delegate(a) => validate_delegate_action(limit_config, a, current_protocol_version),
@@ -511,10 +512,24 @@ fn validate_stake_action(action: &StakeAction) -> Result<(), ActionsValidationEr
 /// Validates `AddKeyAction`. If the access key permission is `FunctionCall`, checks that the
 /// total number of bytes of the method names doesn't exceed the limit and
 /// every method name length doesn't exceed the limit.
+/// SECP256R1 and multisig keys can only be added once `ExtendedAccessKeyTypes` is enabled.
 fn validate_add_key_action(
     limit_config: &LimitConfig,
     action: &AddKeyAction,
+    current_protocol_version: ProtocolVersion,
 ) -> Result<(), ActionsValidationError> {
+    match action.public_key.key_type() {
+        KeyType::ED25519 | KeyType::SECP256K1 => {}
+        KeyType::SECP256R1 | KeyType::MULTISIG => {
+            if !ProtocolFeature::ExtendedAccessKeyTypes.enabled(current_protocol_version) {
+                return Err(ActionsValidationError::UnsupportedProtocolFeature {
+                    protocol_feature: "ExtendedAccessKeyTypes".to_owned(),
+                    version: current_protocol_version,
+                });
+            }
+        }
+    }
+
     if let AccessKeyPermission::FunctionCall(fc) = &action.access_key.permission {
         // Check whether `receiver_id` is a valid account_id. Historically, we
         // allowed arbitrary strings there!
@@ -1912,6 +1927,27 @@ mod tests {
         .expect("valid action");
     }
 
+    #[test]
+    fn test_validate_action_add_key_extended_key_types() {
+        let feature = ProtocolFeature::ExtendedAccessKeyTypes;
+        for key_type in [KeyType::SECP256R1, KeyType::MULTISIG] {
+            let action = Action::AddKey(Box::new(AddKeyAction {
+                public_key: PublicKey::empty(key_type),
+                access_key: AccessKey::full_access(),
+            }));
+            validate_action(&test_limit_config(), &action, feature.protocol_version())
+                .expect("valid action");
+            assert_eq!(
+                validate_action(&test_limit_config(), &action, feature.protocol_version() - 1)
+                    .expect_err("Expected an error"),
+                ActionsValidationError::UnsupportedProtocolFeature {
+                    protocol_feature: "ExtendedAccessKeyTypes".to_owned(),
+                    version: feature.protocol_version() - 1,
+                },
+            );
+        }
+    }
+
     #[test]
     fn test_validate_action_valid_delete_key() {
         validate_action(
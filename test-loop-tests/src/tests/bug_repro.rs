@@ -119,6 +119,7 @@ fn slow_test_repro_1183() {
                                     *block.header().prev_hash(),
                                 ),
                                 is_forwarded: false,
+                                is_gossiped: false,
                                 check_only: false,
                             });
                             nonce_delta += 1
@@ -195,6 +195,7 @@ fn test_catchup_random_single_part_sync_common(
                 node.rpc_handler_sender.send(ProcessTxRequest {
                     transaction: tx.clone(),
                     is_forwarded: false,
+                    is_gossiped: false,
                     check_only: false,
                 });
             }
@@ -198,6 +198,7 @@ fn test_cross_shard_tx_common(Params { num_transfers, rotate_validators, drop_ch
             node_datas[0].rpc_handler_sender.send(ProcessTxRequest {
                 transaction: tx,
                 is_forwarded: false,
+                is_gossiped: false,
                 check_only: false,
             });
 
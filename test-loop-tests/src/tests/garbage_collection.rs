@@ -1,22 +1,33 @@
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
+use near_async::messaging::Handler;
 use near_async::time::Duration;
+use near_chain_configs::GCRetentionConfig;
 use near_chain_configs::test_genesis::{TestEpochConfigBuilder, ValidatorsSpec};
+use near_client::{GetReceipt, TxStatus};
 use near_o11y::testonly::init_test_logger;
 use near_primitives::epoch_manager::EpochConfigStore;
+use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::ShardLayout;
-use near_primitives::types::{AccountId, ShardId};
+use near_primitives::test_utils::create_user_test_signer;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{AccountId, BlockHeight, ShardId};
 use near_primitives::utils::{get_block_shard_id, get_block_shard_id_rev};
 use near_primitives::version::ProtocolFeature;
-use near_store::DBCol;
+use near_primitives::views::{FinalExecutionOutcomeView, TxExecutionStatus};
 use near_store::adapter::StoreAdapter as _;
 use near_store::adapter::chain_store::ChainStoreAdapter;
+use near_store::{DBCol, GC_RETAINED_RECEIPTS_KEY, Store};
 
 use crate::setup;
 use crate::setup::builder::TestLoopBuilder;
-use crate::utils::retrieve_client_actor;
+use crate::setup::env::TestLoopEnv;
 use crate::utils::setups::derive_new_epoch_config_from_boundary;
+use crate::utils::transactions::{
+    do_deploy_contract, execute_tx, get_next_nonce, get_shared_block_hash,
+};
+use crate::utils::{ONE_NEAR, TGAS, retrieve_client_actor};
 
 // We set small gc_step_period in tests to help make sure gc runs at least as often as blocks are
 // produced.
@@ -125,6 +136,218 @@ fn test_state_transition_data_gc_when_resharding() {
     env.shutdown_and_drain_remaining_events(Duration::seconds(10));
 }
 
+#[test]
+fn test_gc_retention_forever() {
+    check_gc_retention(None);
+}
+
+#[test]
+fn test_gc_retention_expires() {
+    // Must not be shorter than `gc_num_epochs_to_keep` in `check_gc_retention`.
+    check_gc_retention(Some(5));
+}
+
+/// Sends a transaction from an account chosen by the retention policy and one from another
+/// account. Both call a contract which calls another contract, so that the receipt executed by
+/// the second contract is only retained as a child receipt of the retained data. Checks that the
+/// retained transaction, receipt and outcomes survive garbage collection while the others are
+/// garbage collected, that the retained call can still be queried through the view client as
+/// the `tx` and `EXPERIMENTAL_receipt` RPC methods do, and that the retained data is deleted
+/// once the retention period expires.
+fn check_gc_retention(retention_num_epochs_to_keep: Option<u64>) {
+    init_test_logger();
+
+    let epoch_length = 5;
+    let gc_num_epochs_to_keep = 3;
+    let chunk_producer: AccountId = "cp0".parse().unwrap();
+    let retained_account: AccountId = "alice".parse().unwrap();
+    let other_account: AccountId = "bob".parse().unwrap();
+    let caller: AccountId = "carol".parse().unwrap();
+    let callee: AccountId = "dave".parse().unwrap();
+    let genesis = TestLoopBuilder::new_genesis_builder()
+        .epoch_length(epoch_length)
+        .validators_spec(ValidatorsSpec::desired_roles(&[chunk_producer.as_str()], &[]))
+        .add_user_accounts_simple(
+            &[retained_account.clone(), other_account.clone(), caller.clone(), callee.clone()],
+            1_000_000 * ONE_NEAR,
+        )
+        .build();
+    let epoch_config_store = TestEpochConfigBuilder::build_store_from_genesis(&genesis);
+    let retention = GCRetentionConfig {
+        accounts: vec![retained_account.clone()],
+        num_epochs_to_keep: retention_num_epochs_to_keep,
+    };
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .clients(vec![chunk_producer.clone()])
+        .epoch_config_store(epoch_config_store)
+        .gc_num_epochs_to_keep(gc_num_epochs_to_keep)
+        .config_modifier(move |config, _client_index| {
+            config.gc.gc_step_period = GC_STEP_PERIOD;
+            config.gc.retention = Some(retention.clone());
+        })
+        .build()
+        .warmup();
+
+    let code = near_test_contracts::rs_contract().to_vec();
+    do_deploy_contract(&mut env, &chunk_producer, &caller, code.clone());
+    do_deploy_contract(&mut env, &chunk_producer, &callee, code);
+
+    let args = serde_json::json!([
+        {"create": {
+            "account_id": callee.as_str(),
+            "method_name": "log_something",
+            "arguments": [],
+            "amount": "0",
+            "gas": 30 * TGAS,
+        }, "id": 0 }
+    ]);
+    let call = |env: &mut TestLoopEnv, signer_id: &AccountId| -> FinalExecutionOutcomeView {
+        let tx = SignedTransaction::call(
+            get_next_nonce(&env.test_loop.data, &env.node_datas, signer_id),
+            signer_id.clone(),
+            caller.clone(),
+            &create_user_test_signer(signer_id),
+            0,
+            "call_promise".to_string(),
+            serde_json::to_vec(&args).unwrap(),
+            300 * TGAS,
+            get_shared_block_hash(&env.node_datas, &env.test_loop.data),
+        );
+        execute_tx(&mut env.test_loop, &chunk_producer, tx, &env.node_datas, Duration::seconds(10))
+            .unwrap()
+    };
+    let client_handle = env.node_datas[0].client_sender.actor_handle();
+    let chain_store = env.test_loop.data.get(&client_handle).client.chain.chain_store.clone();
+    let retained = CallIds::new(&chain_store, call(&mut env, &retained_account), &caller, &callee);
+    let collected = CallIds::new(&chain_store, call(&mut env, &other_account), &caller, &callee);
+    let store = chain_store.store().store();
+    let last_height = retained.last_height.max(collected.last_height);
+
+    // Wait until the blocks and chunks with both calls are garbage collected.
+    env.test_loop.run_until(
+        |_| {
+            chain_store.tail().unwrap() > last_height
+                && chain_store.chunk_tail().unwrap() > last_height
+        },
+        Duration::seconds((3 * gc_num_epochs_to_keep * epoch_length) as i64),
+    );
+
+    retained.assert_stored(&store, true);
+    retained.assert_queryable(&mut env, &retained_account);
+    // The child receipt itself is not retained, only its outcome is.
+    assert!(!store.exists(DBCol::Receipts, retained.child_receipt_id.as_bytes()).unwrap());
+    collected.assert_stored(&store, false);
+    assert!(
+        env.test_loop
+            .data
+            .get(&client_handle)
+            .client
+            .chain
+            .get_final_transaction_result(&collected.tx_hash)
+            .is_err()
+    );
+    let retained_receipts: HashMap<CryptoHash, BlockHeight> =
+        store.get_ser(DBCol::BlockMisc, GC_RETAINED_RECEIPTS_KEY).unwrap().unwrap_or_default();
+    assert!(!retained_receipts.contains_key(&retained.child_receipt_id));
+
+    if retention_num_epochs_to_keep.is_some() {
+        env.test_loop.run_until(
+            |_| store.iter(DBCol::GCRetainedData).next().is_none(),
+            Duration::seconds((3 * gc_num_epochs_to_keep * epoch_length) as i64),
+        );
+        retained.assert_stored(&store, false);
+    } else {
+        env.test_loop.run_for(Duration::seconds((2 * epoch_length) as i64));
+        retained.assert_stored(&store, true);
+        retained.assert_queryable(&mut env, &retained_account);
+        assert!(store.iter(DBCol::GCRetainedData).next().is_none());
+    }
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(10));
+}
+
+/// Ids of the transaction and receipts of a contract call made in `check_gc_retention`.
+struct CallIds {
+    tx_hash: CryptoHash,
+    /// Receipt sent by the signer of the transaction to the caller contract.
+    receipt_id: CryptoHash,
+    /// Receipt sent by the caller contract to the callee contract.
+    child_receipt_id: CryptoHash,
+    /// Height of the last block with an outcome of the call.
+    last_height: BlockHeight,
+    /// Result of the call returned when it was executed.
+    outcome: FinalExecutionOutcomeView,
+}
+
+impl CallIds {
+    fn new(
+        chain_store: &ChainStoreAdapter,
+        outcome: FinalExecutionOutcomeView,
+        caller: &AccountId,
+        callee: &AccountId,
+    ) -> Self {
+        let receipt_id = |executor_id: &AccountId| {
+            outcome
+                .receipts_outcome
+                .iter()
+                .find(|receipt| &receipt.outcome.executor_id == executor_id)
+                .unwrap()
+                .id
+        };
+        let last_height = std::iter::once(&outcome.transaction_outcome)
+            .chain(&outcome.receipts_outcome)
+            .map(|outcome| chain_store.get_block_height(&outcome.block_hash).unwrap())
+            .max()
+            .unwrap();
+        Self {
+            tx_hash: outcome.transaction_outcome.id,
+            receipt_id: receipt_id(caller),
+            child_receipt_id: receipt_id(callee),
+            last_height,
+            outcome,
+        }
+    }
+
+    /// Checks that the call is returned in full by `get_final_transaction_result` and by the
+    /// view client requests behind the `tx` and `EXPERIMENTAL_receipt` RPC methods.
+    fn assert_queryable(&self, env: &mut TestLoopEnv, signer_id: &AccountId) {
+        let client_handle = env.node_datas[0].client_sender.actor_handle();
+        let chain = &env.test_loop.data.get(&client_handle).client.chain;
+        assert_eq!(chain.get_final_transaction_result(&self.tx_hash).unwrap(), self.outcome);
+
+        let view_client_handle = env.node_datas[0].view_client_sender.actor_handle();
+        let view_client = env.test_loop.data.get_mut(&view_client_handle);
+        let tx_status = view_client
+            .handle(TxStatus {
+                tx_hash: self.tx_hash,
+                signer_account_id: signer_id.clone(),
+                fetch_receipt: false,
+            })
+            .unwrap();
+        assert_eq!(tx_status.status, TxExecutionStatus::Final);
+        assert_eq!(tx_status.execution_outcome.unwrap().into_outcome(), self.outcome);
+
+        let receipt = view_client.handle(GetReceipt { receipt_id: self.receipt_id }).unwrap();
+        assert_eq!(receipt.unwrap().receipt_id, self.receipt_id);
+        // Receipts between accounts not chosen by the retention policy are not retained.
+        let child_receipt =
+            view_client.handle(GetReceipt { receipt_id: self.child_receipt_id }).unwrap();
+        assert!(child_receipt.is_none());
+    }
+
+    fn assert_stored(&self, store: &Store, stored: bool) {
+        let has_outcome = |id: &CryptoHash| {
+            store.iter_prefix(DBCol::TransactionResultForBlock, id.as_bytes()).next().is_some()
+        };
+        assert_eq!(store.exists(DBCol::Transactions, self.tx_hash.as_bytes()).unwrap(), stored);
+        assert_eq!(store.exists(DBCol::Receipts, self.receipt_id.as_bytes()).unwrap(), stored);
+        assert_eq!(has_outcome(&self.tx_hash), stored);
+        assert_eq!(has_outcome(&self.receipt_id), stored);
+        assert_eq!(has_outcome(&self.child_receipt_id), stored);
+    }
+}
+
 fn assert_state_transition_data_is_cleared(
     chain_store: &ChainStoreAdapter,
     expected_shard_ids: &HashSet<ShardId>,
@@ -77,8 +77,12 @@ fn test_producer_with_expired_transactions() {
                 ONE_NEAR,
                 anchor_hash,
             );
-            let process_tx_request =
-                ProcessTxRequest { transaction: tx, is_forwarded: false, check_only: false };
+            let process_tx_request = ProcessTxRequest {
+                transaction: tx,
+                is_forwarded: false,
+                is_gossiped: false,
+                check_only: false,
+            };
             chunk_producer.rpc_handler_sender.send(process_tx_request);
         });
     }
@@ -20,6 +20,7 @@ mod in_memory_tries;
 mod malicious_chunk_producer;
 mod max_receipt_size;
 mod multinode_stateless_validators;
+mod network_faults;
 mod optimistic_block;
 mod process_blocks;
 mod protocol_upgrade;
@@ -0,0 +1,79 @@
+use itertools::Itertools;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{TestEpochConfigBuilder, ValidatorsSpec};
+use near_network::fault_injection::{Action, Config, Rule};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+
+use crate::setup::builder::TestLoopBuilder;
+use crate::setup::env::TestLoopEnv;
+
+fn rule(message_type: &str, action: Action) -> Rule {
+    Rule {
+        peers: vec![],
+        message_types: vec![message_type.to_string()],
+        start: Duration::ZERO,
+        duration: Some(Duration::seconds(20)),
+        probability: 1.,
+        action,
+    }
+}
+
+/// Makes the links of one of the block producers slow and lossy for a while,
+/// and checks that the chain keeps going and all the nodes converge afterwards.
+#[test]
+fn test_chain_progresses_with_network_faults() {
+    init_test_logger();
+
+    let block_producers = ["test1", "test2", "test3", "test4"];
+    let genesis = TestLoopBuilder::new_genesis_builder()
+        .epoch_length(100)
+        .shard_layout(ShardLayout::single_shard())
+        .validators_spec(ValidatorsSpec::desired_roles(&block_producers, &[]))
+        .build();
+    let epoch_config_store = TestEpochConfigBuilder::build_store_from_genesis(&genesis);
+    let clients = block_producers.into_iter().map(|a| a.parse().unwrap()).collect_vec();
+    let env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .build()
+        .warmup();
+
+    let faults = Config {
+        seed: 42,
+        rules: vec![
+            rule("Block", Action::Delay(Duration::milliseconds(700))),
+            Rule { probability: 0.5, ..rule("Approval", Action::Drop) },
+            rule("ChunkEndorsement", Action::Duplicate(1)),
+            rule("PartialEncodedChunkMessage", Action::Reorder(Duration::milliseconds(300))),
+        ],
+    };
+    let TestLoopEnv { mut test_loop, node_datas, shared_state } = env;
+    let peer_manager = node_datas[0].peer_manager_sender.actor_handle();
+    test_loop.data.get_mut(&peer_manager).set_fault_injection(Some(faults));
+
+    let client_handle = node_datas[0].client_sender.actor_handle();
+    let start_height = test_loop.data.get(&client_handle).client.chain.head().unwrap().height;
+    test_loop.run_until(
+        |data| data.get(&client_handle).client.chain.head().unwrap().height > start_height + 10,
+        Duration::seconds(20),
+    );
+
+    // Once the faults are over, all the nodes end up on the same head.
+    test_loop.run_for(Duration::seconds(20));
+    test_loop.run_until(
+        |data| {
+            node_datas
+                .iter()
+                .map(|node| {
+                    data.get(&node.client_sender.actor_handle()).client.chain.head().unwrap()
+                })
+                .all_equal()
+        },
+        Duration::seconds(5),
+    );
+
+    TestLoopEnv { test_loop, node_datas, shared_state }
+        .shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -239,6 +239,7 @@ fn send_txs_between_shards(
             .send_async(ProcessTxRequest {
                 transaction: tx,
                 is_forwarded: false,
+                is_gossiped: false,
                 check_only: false,
             });
         drop(future);
@@ -3,9 +3,12 @@ use std::sync::Arc;
 
 use itertools::Itertools;
 use near_async::actix::ActixResult;
-use near_async::futures::{DelayedActionRunnerExt as _, FutureSpawner, FutureSpawnerExt};
+use near_async::futures::{
+    DelayedActionRunner, DelayedActionRunnerExt as _, FutureSpawner, FutureSpawnerExt,
+};
 use near_async::messaging::{
-    Actor, AsyncSender, CanSend, Handler, IntoMultiSender as _, IntoSender as _, SendAsync, Sender,
+    Actor, AsyncSender, CanSend, Handler, HandlerWithContext, IntoMultiSender as _,
+    IntoSender as _, SendAsync, Sender,
 };
 use near_async::test_loop::sender::TestLoopSender;
 use near_async::time::{Clock, Duration};
@@ -17,6 +20,7 @@ use near_network::client::{
     EpochSyncRequestMessage, EpochSyncResponseMessage, OptimisticBlockMessage, ProcessTxRequest,
     ProcessTxResponse,
 };
+use near_network::fault_injection::{self, FaultInjector, SetFaultInjection};
 use near_network::shards_manager::ShardsManagerRequestFromNetwork;
 use near_network::state_witness::{
     ChunkContractAccessesMessage, ChunkStateWitnessAckMessage, ContractCodeRequestMessage,
@@ -96,8 +100,15 @@ type NetworkRequestHandler = Box<dyn Fn(NetworkRequests) -> Option<NetworkReques
 /// - Override handler to skip sending messages to or from a specific client.
 /// - Override handler to simulate more network delays.
 /// - Override handler to modify data and simulate malicious behavior.
+///
+/// Before the handlers, the requests go through the network faults set with `SetFaultInjection`
+/// (see `near_network::fault_injection`), which can drop, delay, duplicate or reorder them.
 pub struct TestLoopPeerManagerActor {
     handlers: Vec<NetworkRequestHandler>,
+    fault_injector: Option<FaultInjector>,
+
+    clock: Clock,
+    shared_state: TestLoopNetworkSharedState,
 
     client_sender: ClientSenderForTestLoopNetwork,
     genesis_id: GenesisId,
@@ -145,10 +156,22 @@ impl TestLoopPeerManagerActor {
                 future_spawner,
             ),
             network_message_to_partial_witness_handler(&account_id, shared_state.clone()),
-            network_message_to_shards_manager_handler(clock, &account_id, shared_state.clone()),
+            network_message_to_shards_manager_handler(
+                clock.clone(),
+                &account_id,
+                shared_state.clone(),
+            ),
             network_message_to_state_snapshot_handler(),
         ];
-        Self { handlers, client_sender, genesis_id, last_block_headers: HashMap::new() }
+        Self {
+            handlers,
+            fault_injector: None,
+            clock,
+            shared_state: shared_state.clone(),
+            client_sender,
+            genesis_id,
+            last_block_headers: HashMap::new(),
+        }
     }
 
     /// Register a new handler to override the default handlers.
@@ -158,6 +181,57 @@ impl TestLoopPeerManagerActor {
         self.handlers.push(handler);
     }
 
+    /// Replaces the network faults injected into the requests sent by this node.
+    /// Time windows of the rules start now.
+    ///
+    /// The `peers` of a rule are matched against the target of the request. Requests without
+    /// a single target (e.g. block broadcasts) are matched only by the rules without `peers`;
+    /// to cut a link completely, use `TestLoopNetworkSharedState::disallow_requests`.
+    pub fn set_fault_injection(&mut self, config: Option<fault_injection::Config>) {
+        if let Some(config) = &config {
+            config.validate().unwrap();
+        }
+        self.fault_injector = config.map(|config| FaultInjector::new(config, self.clock.now()));
+    }
+
+    /// Returns the peer to which the request is addressed, if there is a single one.
+    fn request_target(&self, request: &NetworkRequests) -> Option<PeerId> {
+        let account_id = match request {
+            NetworkRequests::Approval { approval_message } => &approval_message.target,
+            NetworkRequests::ForwardTx(account_id, _)
+            | NetworkRequests::ChunkEndorsement(account_id, _)
+            | NetworkRequests::ChunkStateWitnessAck(account_id, _)
+            | NetworkRequests::ContractCodeRequest(account_id, _)
+            | NetworkRequests::ContractCodeResponse(account_id, _)
+            | NetworkRequests::PartialEncodedChunkMessage { account_id, .. }
+            | NetworkRequests::PartialEncodedChunkForward { account_id, .. } => account_id,
+            NetworkRequests::PartialEncodedChunkRequest { target, .. } => {
+                target.account_id.as_ref()?
+            }
+            NetworkRequests::EpochSyncRequest { peer_id }
+            | NetworkRequests::EpochSyncResponse { peer_id, .. }
+            | NetworkRequests::BlockHeadersRequest { peer_id, .. }
+            | NetworkRequests::BlockRequest { peer_id, .. } => return Some(peer_id.clone()),
+            _ => return None,
+        };
+        Some(self.shared_state.account_to_peer_id(account_id))
+    }
+
+    fn handle_network_request(&self, request: NetworkRequests) {
+        // Iterate over the handlers in reverse order to allow for overriding the default handlers.
+        let mut request = Some(request);
+        for handler in self.handlers.iter().rev() {
+            if let Some(new_request) = handler(request.take().unwrap()) {
+                request = Some(new_request);
+            } else {
+                // Some handler was successfully able to handle the request.
+                return;
+            }
+        }
+        // If no handler was able to handle the request, panic.
+        panic!("Unhandled request: {:?}", request);
+    }
+
     fn push_network_info(
         &self,
         ctx: &mut dyn near_async::futures::DelayedActionRunner<Self>,
@@ -367,24 +441,41 @@ impl Handler<Tier3Request> for TestLoopPeerManagerActor {
     fn handle(&mut self, _msg: Tier3Request) {}
 }
 
-impl Handler<PeerManagerMessageRequest> for TestLoopPeerManagerActor {
-    fn handle(&mut self, msg: PeerManagerMessageRequest) -> PeerManagerMessageResponse {
+impl Handler<SetFaultInjection> for TestLoopPeerManagerActor {
+    fn handle(&mut self, msg: SetFaultInjection) {
+        self.set_fault_injection(msg.0);
+    }
+}
+
+impl HandlerWithContext<PeerManagerMessageRequest> for TestLoopPeerManagerActor {
+    fn handle(
+        &mut self,
+        msg: PeerManagerMessageRequest,
+        ctx: &mut dyn DelayedActionRunner<Self>,
+    ) -> PeerManagerMessageResponse {
         let PeerManagerMessageRequest::NetworkRequests(request) = msg else {
             panic!("Unexpected message: {:?}", msg);
         };
+        let response = PeerManagerMessageResponse::NetworkResponses(NetworkResponses::NoResponse);
 
-        // Iterate over the handlers in reverse order to allow for overriding the default handlers.
-        let mut request = Some(request);
-        for handler in self.handlers.iter().rev() {
-            if let Some(new_request) = handler(request.take().unwrap()) {
-                request = Some(new_request);
-            } else {
-                // Some handler was successfully able to handle the request.
-                return PeerManagerMessageResponse::NetworkResponses(NetworkResponses::NoResponse);
+        let Some(fault_injector) = &self.fault_injector else {
+            self.handle_network_request(request);
+            return response;
+        };
+        let target = self.request_target(&request);
+        let deliveries =
+            fault_injector.deliveries(self.clock.now(), target.as_ref(), request.as_ref());
+        for delay in deliveries {
+            if delay <= Duration::ZERO {
+                self.handle_network_request(request.clone());
+                continue;
             }
+            let request = request.clone();
+            ctx.run_later("TestLoopPeerManagerActor::delayed_request", delay, move |act, _| {
+                act.handle_network_request(request);
+            });
         }
-        // If no handler was able to handle the request, panic.
-        panic!("Unhandled request: {:?}", request);
+        response
     }
 }
 
@@ -458,6 +549,7 @@ fn network_message_to_client_handler(
                 .send_async(ProcessTxRequest {
                     transaction,
                     is_forwarded: true,
+                    is_gossiped: false,
                     check_only: false,
                 });
             drop(future);
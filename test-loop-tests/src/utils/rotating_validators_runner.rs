@@ -146,6 +146,7 @@ impl RotatingValidatorsRunner {
                     let process_tx_request = ProcessTxRequest {
                         transaction: tx.clone(),
                         is_forwarded: false,
+                        is_gossiped: false,
                         check_only: false,
                     };
                     tx_processor_sender.send(process_tx_request);
@@ -141,8 +141,12 @@ pub(crate) fn execute_money_transfers(
                     amount,
                     anchor_hash,
                 );
-                let process_tx_request =
-                    ProcessTxRequest { transaction: tx, is_forwarded: false, check_only: false };
+                let process_tx_request = ProcessTxRequest {
+                    transaction: tx,
+                    is_forwarded: false,
+                    is_gossiped: false,
+                    check_only: false,
+                };
                 node_data[i % num_clients].rpc_handler_sender.send(process_tx_request);
             },
         );
@@ -409,8 +413,12 @@ pub fn call_contract(
 /// Submit a transaction to the rpc node with the given account id.
 /// Doesn't wait for the result, it must be requested separately.
 pub fn submit_tx(node_datas: &[NodeExecutionData], rpc_id: &AccountId, tx: SignedTransaction) {
-    let process_tx_request =
-        ProcessTxRequest { transaction: tx, is_forwarded: false, check_only: false };
+    let process_tx_request = ProcessTxRequest {
+        transaction: tx,
+        is_forwarded: false,
+        is_gossiped: false,
+        check_only: false,
+    };
 
     let rpc_node_data = get_node_data(node_datas, rpc_id);
     let rpc_node_data_sender = &rpc_node_data.rpc_handler_sender;
@@ -674,6 +682,7 @@ impl TransactionRunner {
         let process_tx_request = ProcessTxRequest {
             transaction: self.transaction.clone(),
             is_forwarded: false,
+            is_gossiped: false,
             check_only: false,
         };
         let process_tx_future = client_sender.send_async(process_tx_request);
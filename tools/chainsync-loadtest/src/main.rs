@@ -68,7 +68,7 @@ fn download_configs(chain_id: &str, dir: &std::path::Path) -> anyhow::Result<Nea
     // Generate node key.
     let account_id = "node".parse().unwrap();
     let node_signer =
-        near_crypto::InMemorySigner::from_random(account_id, near_crypto::KeyType::ED25519);
+        near_crypto::InMemorySigner::from_random(account_id, near_crypto::KeyType::ED25519)?;
     let mut genesis = Genesis::default();
     genesis.config.chain_id = chain_id.to_string();
     let validator_signer = MutableConfigValue::new(None, "validator_signer");
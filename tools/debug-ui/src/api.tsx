@@ -108,6 +108,15 @@ export interface AccountData {
     timestamp: string;
 }
 
+export interface StateSyncHostStatsView {
+    peer_id: string;
+    parts_downloaded: number;
+    failures: number;
+    bytes_downloaded: number;
+    throughput_bytes_per_sec: number | null;
+    demoted: boolean;
+}
+
 export type SyncStatusView =
     | 'AwaitingPeers'
     | 'NoSync'
@@ -127,6 +136,7 @@ export type SyncStatusView =
               sync_status: { [shard_id: number]: string };
               download_tasks: string[];
               computation_tasks: string[];
+              host_stats: StateSyncHostStatsView[];
           };
       }
     | 'StateSyncDone'
@@ -253,6 +263,7 @@ export interface KnownPeerStateView {
     first_seen: number;
     last_seen: number;
     last_attempt: [number, string] | null;
+    reputation: number;
 }
 
 export interface SyncStatusResponse {
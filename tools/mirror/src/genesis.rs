@@ -341,7 +341,7 @@ mod test {
             }),
         });
 
-        let secret_key = SecretKey::from_random(KeyType::ED25519);
+        let secret_key = SecretKey::from_random(KeyType::ED25519).unwrap();
         let delegate_action = DelegateAction {
             sender_id: "d4156e03cb09f47117ddfde4fdcd5f3b8b087dccb364e228b8b3ed91d69054f4"
                 .parse()
@@ -4,7 +4,7 @@ use near_crypto::{ED25519PublicKey, ED25519SecretKey, PublicKey, Secp256K1Public
 use near_primitives::types::AccountId;
 use near_primitives::utils::derive_near_implicit_account_id;
 use near_primitives_core::account::id::AccountType;
-use sha2::Sha256;
+use sha2::{Digest, Sha256};
 
 // there is nothing special about this key, it's just some randomly generated one.
 // We will ensure that every account in the target chain has at least one full access
@@ -47,7 +47,32 @@ fn map_ed25519(
     let mut buf = [0; ed25519_dalek::KEYPAIR_LENGTH];
 
     ed25519_map_secret(&mut buf[..ed25519_dalek::SECRET_KEY_LENGTH], public, secret);
+    ed25519_from_secret(buf)
+}
+
+// SECP256R1 and multisig keys are mapped to ED25519 keys derived from the key data,
+// since there is no secret key for a multisig key that we could sign with. The mapping
+// is still deterministic, so access keys and transaction signers are mapped consistently.
+fn map_key_data_to_ed25519(
+    public: &PublicKey,
+    secret: Option<&[u8; crate::secret::SECRET_LEN]>,
+) -> ED25519SecretKey {
+    let mut buf = [0; ed25519_dalek::KEYPAIR_LENGTH];
 
+    let secret_buf = &mut buf[..ed25519_dalek::SECRET_KEY_LENGTH];
+    match secret {
+        Some(secret) => {
+            let hk = Hkdf::<Sha256>::new(None, secret);
+            hk.expand(public.key_data(), secret_buf).unwrap();
+        }
+        None => {
+            secret_buf.copy_from_slice(&Sha256::digest(public.key_data()));
+        }
+    };
+    ed25519_from_secret(buf)
+}
+
+fn ed25519_from_secret(mut buf: [u8; ed25519_dalek::KEYPAIR_LENGTH]) -> ED25519SecretKey {
     let secret_key = ed25519_dalek::SigningKey::from_bytes(
         <&[u8; ed25519_dalek::SECRET_KEY_LENGTH]>::try_from(
             &buf[..ed25519_dalek::SECRET_KEY_LENGTH],
@@ -101,6 +126,9 @@ pub fn map_key(key: &PublicKey, secret: Option<&[u8; crate::secret::SECRET_LEN]>
     match key {
         PublicKey::ED25519(k) => SecretKey::ED25519(map_ed25519(k, secret)),
         PublicKey::SECP256K1(k) => SecretKey::SECP256K1(map_secp256k1(k, secret)),
+        PublicKey::SECP256R1(_) | PublicKey::MULTISIG(_) => {
+            SecretKey::ED25519(map_key_data_to_ed25519(key, secret))
+        }
     }
 }
 
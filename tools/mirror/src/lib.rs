@@ -882,6 +882,7 @@ impl<T: ChainAccess> TxMirror<T> {
                             ProcessTxRequest {
                                 transaction: tx.target_tx.clone(),
                                 is_forwarded: false,
+                                is_gossiped: false,
                                 check_only: false,
                             }
                             .with_span_context(),
@@ -0,0 +1,110 @@
+use crate::cli::CHAIN_INFO;
+use anyhow::Context;
+use near_network::raw::{Connection, ReplayOptions};
+use near_network::recorder::Reader;
+use near_network::types::PeerInfo;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::ShardId;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(clap::Parser)]
+pub struct ReplayTrafficCommand {
+    /// File with the recorded traffic, see `experimental.traffic_recording_path` in config.json.
+    #[clap(long)]
+    file: PathBuf,
+    /// Instead of replaying, print the number of recorded messages of each type.
+    #[clap(long)]
+    summary: bool,
+    /// node public key and socket address in the format {pub key}@{socket addr}. e.g.:
+    /// ed25519:7PGseFbWxvYVgZ89K1uTJKYoKetWs7BJtbyXDzfbAcqX@127.0.0.1:24567
+    #[clap(long, required_unless_present = "summary")]
+    peer: Option<String>,
+    #[clap(long, required_unless_present = "summary")]
+    chain_id: Option<String>,
+    /// genesis hash to use in the Handshake we send. This must be provided if --chain-id
+    /// is not "mainnet" or "testnet"
+    #[clap(long)]
+    genesis_hash: Option<String>,
+    /// head height to use in the Handshake we send.
+    #[clap(long, default_value = "0")]
+    head_height: u64,
+    /// Replay only the messages received from this peer (public key).
+    #[clap(long)]
+    from_peer: Option<String>,
+    /// Preserve the recorded intervals between the messages, shortened by this factor.
+    /// By default messages are sent as fast as possible.
+    #[clap(long)]
+    speedup: Option<f64>,
+}
+
+impl ReplayTrafficCommand {
+    pub fn run(&self) -> anyhow::Result<()> {
+        let reader = Reader::open(&self.file)
+            .with_context(|| format!("Couldn't open the recording at {:?}", self.file))?;
+        if self.summary {
+            return print_summary(reader);
+        }
+        let chain_id = self.chain_id.as_ref().unwrap();
+        let genesis_hash = match &self.genesis_hash {
+            Some(h) => CryptoHash::from_str(h)
+                .map_err(|e| anyhow::anyhow!("Could not parse --genesis-hash {}: {:?}", h, e))?,
+            None => match CHAIN_INFO.iter().find(|info| &info.chain_id == chain_id) {
+                Some(info) => info.genesis_hash,
+                None => anyhow::bail!(
+                    "--genesis-hash not given, and genesis hash for --chain-id {} not known",
+                    chain_id
+                ),
+            },
+        };
+        let peer = self.peer.as_ref().unwrap();
+        let peer = PeerInfo::from_str(peer)
+            .map_err(|e| anyhow::anyhow!("Could not parse --peer {}: {:?}", peer, e))?;
+        let Some(peer_addr) = peer.addr else {
+            anyhow::bail!("--peer should be in the form [public key]@[socket addr]");
+        };
+        let from_peer = match &self.from_peer {
+            Some(p) => Some(
+                PeerInfo::from_str(p)
+                    .map_err(|e| anyhow::anyhow!("Could not parse --from-peer {}: {:?}", p, e))?
+                    .id,
+            ),
+            None => None,
+        };
+        let opts = ReplayOptions { from_peer, speedup: self.speedup };
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async move {
+            let clock = near_time::Clock::real();
+            let mut conn = Connection::connect(
+                &clock,
+                peer_addr,
+                peer.id,
+                None,
+                chain_id,
+                genesis_hash,
+                self.head_height,
+                vec![ShardId::new(0)],
+                None,
+            )
+            .await?;
+            let stats = near_network::raw::replay(&clock, &mut conn, reader, &opts).await?;
+            println!("sent {} messages, skipped {}", stats.sent, stats.skipped);
+            Ok(())
+        })
+    }
+}
+
+fn print_summary(reader: Reader) -> anyhow::Result<()> {
+    let mut counts = BTreeMap::<_, usize>::new();
+    for record in reader {
+        let record = record?;
+        let msg_type = record.message_type().unwrap_or("<invalid>");
+        *counts.entry((msg_type, format!("{:?}", record.direction))).or_default() += 1;
+    }
+    for ((msg_type, direction), count) in counts {
+        println!("{:<40} {:<10} {}", msg_type, direction, count);
+    }
+    Ok(())
+}
@@ -45,6 +45,10 @@ pub struct ReplayArchiveCommand {
     start_height: Option<BlockHeight>,
     #[clap(long)]
     end_height: Option<BlockHeight>,
+    /// Executes independent receipts of a chunk in parallel. The results are the same as with
+    /// sequential execution.
+    #[clap(long)]
+    parallel_receipts: bool,
 }
 
 impl ReplayArchiveCommand {
@@ -59,8 +63,13 @@ impl ReplayArchiveCommand {
             bail!("Cold storage is not configured for the archival node.".to_string());
         }
 
-        let mut controller =
-            ReplayController::new(home_dir, near_config, self.start_height, self.end_height)?;
+        let mut controller = ReplayController::new(
+            home_dir,
+            near_config,
+            self.start_height,
+            self.end_height,
+            self.parallel_receipts,
+        )?;
 
         // Replay all the blocks until we reach the end block height.
         while controller.replay_next_block()? {}
@@ -109,6 +118,7 @@ impl ReplayController {
         near_config: NearConfig,
         start_height: Option<BlockHeight>,
         end_height: Option<BlockHeight>,
+        parallel_receipts: bool,
     ) -> Result<Self> {
         let storage = open_storage_for_replay(home_dir, &near_config)?;
         let store = Store::new(storage.clone());
@@ -130,9 +140,14 @@ impl ReplayController {
             Some(home_dir),
         );
 
-        let runtime =
-            NightshadeRuntime::from_config(home_dir, store, &near_config, epoch_manager.clone())
-                .context("Failed to create runtime")?;
+        let runtime = NightshadeRuntime::from_config_with_parallel_receipts(
+            home_dir,
+            store,
+            &near_config,
+            epoch_manager.clone(),
+            parallel_receipts,
+        )
+        .context("Failed to create runtime")?;
 
         let progress_reporter = ProgressReporter {
             cnt: AtomicU64::new(0),
@@ -261,6 +261,7 @@ fn load_snapshot(load_cmd: LoadCmd) {
                 ReshardingConfig::default(),
                 "resharding_config",
             ),
+            persist_orphans: false,
         },
         None,
         Arc::new(RayonAsyncComputationSpawner),
@@ -1,11 +1,15 @@
 use crate::commands::*;
 use crate::congestion_control::CongestionControlCmd;
 use crate::contract_accounts::ContractAccountFilter;
+use crate::contract_upgrade::ContractUpgradeCmd;
 use crate::replay_headers::replay_headers;
 use crate::rocksdb_stats::get_rocksdb_stats;
+use crate::storage_staking::{StorageStakingArgs, print_storage_staking_report};
 use crate::trie_iteration_benchmark::TrieIterationBenchmarkCmd;
+use crate::tx_selection::SimulateTxSelectionCmd;
 
 use crate::latest_witnesses::StateWitnessCmd;
+use crate::precompile_contracts::PrecompileContractsCmd;
 use near_chain::types::RuntimeStorageConfig;
 use near_chain_configs::{GenesisChangeConfig, GenesisValidationMode};
 use near_epoch_manager::EpochManager;
@@ -61,6 +65,10 @@ pub enum StateViewerSubCommand {
     /// List account names with contracts deployed.
     #[clap(alias = "contract_accounts")]
     ContractAccounts(ContractAccountsCmd),
+    /// Deploy new code to an account and run its migration on top of the
+    /// current state without committing anything, then compare view calls
+    /// before and after the upgrade.
+    ContractUpgrade(ContractUpgradeCmd),
     /// Run a readonly Debug UI API server so the Debug UI can be used to query this node.
     #[clap(alias = "debug_ui")]
     DebugUI(DebugUICmd),
@@ -88,6 +96,9 @@ pub enum StateViewerSubCommand {
     /// Looks up a certain partial chunk.
     #[clap(alias = "partial_chunks")]
     PartialChunks(PartialChunksCmd),
+    /// Compile all deployed contracts for a given protocol version and store
+    /// them in the compiled contract cache.
+    PrecompileContracts(PrecompileContractsCmd),
     /// Looks up a certain receipt.
     Receipts(ReceiptsCmd),
     /// Replay block headers from chain.
@@ -97,6 +108,10 @@ pub enum StateViewerSubCommand {
     RocksDBStats(RocksDBStatsCmd),
     /// Reads all rows of a DB column and deserializes keys and values and prints them.
     ScanDbColumn(ScanDbColumnCmd),
+    /// Simulates the selection of pool transactions into chunks of a shard
+    /// over a number of heights, on top of the state of a block, and reports
+    /// throughput, latency per signer and rejection reasons.
+    SimulateTxSelection(SimulateTxSelectionCmd),
     /// Iterates over a trie and prints the StateRecords.
     State,
     /// Dumps or applies StateChanges.
@@ -175,6 +190,7 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::ChunkApplyStats(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::ClearCache => clear_cache(store),
             StateViewerSubCommand::ContractAccounts(cmd) => cmd.run(home_dir, near_config, store),
+            StateViewerSubCommand::ContractUpgrade(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::DebugUI(cmd) => {
                 cmd.run(home_dir, near_config, storage.get_hot_store(), storage.get_cold_store())
             }
@@ -186,10 +202,16 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::EpochInfo(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::EpochAnalysis(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::PartialChunks(cmd) => cmd.run(near_config, store),
+            StateViewerSubCommand::PrecompileContracts(cmd) => {
+                cmd.run(home_dir, near_config, store)
+            }
             StateViewerSubCommand::Receipts(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::ReplayHeaders(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::RocksDBStats(cmd) => cmd.run(store_opener.path()),
             StateViewerSubCommand::ScanDbColumn(cmd) => cmd.run(store),
+            StateViewerSubCommand::SimulateTxSelection(cmd) => {
+                cmd.run(home_dir, near_config, store)
+            }
             StateViewerSubCommand::State => state(home_dir, near_config, store),
             StateViewerSubCommand::StateChanges(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::StateParts(cmd) => cmd.run(home_dir, near_config, store),
@@ -325,6 +347,10 @@ pub struct ApplyRangeCmd {
     /// Modifies the DB column 'State' and writes the missing trie nodes generated as a result of applying the blocks.
     #[clap(long)]
     save_state: Option<SaveTrieTemperature>,
+    /// Executes independent receipts of a chunk in parallel. The results are the same as with
+    /// sequential execution.
+    #[clap(long)]
+    parallel_receipts: bool,
 }
 
 impl ApplyRangeCmd {
@@ -351,6 +377,7 @@ impl ApplyRangeCmd {
             self.save_state.map(|temperature| initialize_write_store(temperature, node_storage)),
             self.only_contracts,
             self.storage,
+            self.parallel_receipts,
         );
     }
 }
@@ -838,11 +865,29 @@ pub struct StateStatsCmd {
     split_parts: usize,
     #[clap(long, help = "Print stats only for the given shard ID")]
     shard_uid: Option<ShardUId>,
+    /// Instead of the size stats, break down the storage staked by each account
+    /// by record type and key prefix, optionally finding contract data that has
+    /// not been written for a number of epochs. Prints one JSON object per account.
+    #[clap(long)]
+    storage_staking: bool,
+    #[clap(flatten)]
+    storage_staking_args: StorageStakingArgs,
 }
 
 impl StateStatsCmd {
     pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
-        print_state_stats(home_dir, store, near_config, self.split_parts, self.shard_uid);
+        if self.storage_staking {
+            print_storage_staking_report(
+                home_dir,
+                near_config,
+                store,
+                self.shard_uid,
+                self.storage_staking_args,
+            )
+            .unwrap();
+        } else {
+            print_state_stats(home_dir, store, near_config, self.split_parts, self.shard_uid);
+        }
     }
 }
 
@@ -59,6 +59,7 @@ use nearcore::{NearConfig, NightshadeRuntime};
 use node_runtime::SignedValidPeriodTransactions;
 use node_runtime::adapter::ViewRuntimeAdapter;
 use serde_json::json;
+use std::cell::Cell;
 use std::collections::HashMap;
 use std::collections::{BTreeMap, BinaryHeap};
 use std::fs::{self, File};
@@ -251,6 +252,7 @@ pub(crate) fn apply_range(
     write_store: Option<Store>,
     only_contracts: bool,
     storage: StorageSource,
+    parallel_receipts: bool,
 ) {
     let mut csv_file = csv_file.map(|filename| std::fs::File::create(filename).unwrap());
 
@@ -259,11 +261,12 @@ pub(crate) fn apply_range(
         &near_config.genesis.config,
         Some(home_dir),
     );
-    let runtime = NightshadeRuntime::from_config(
+    let runtime = NightshadeRuntime::from_config_with_parallel_receipts(
         home_dir,
         read_store.clone(),
         &near_config,
         epoch_manager.clone(),
+        parallel_receipts,
     )
     .expect("could not create the transaction runtime");
     apply_chain_range(
@@ -1347,16 +1350,17 @@ fn print_state_stats_for_shard_uid(
     let chunk_view = flat_storage_manager.chunk_view(shard_uid, block_hash).unwrap();
 
     let mut state_stats = StateStats::default();
+    let errors = Cell::new(0);
 
     // iterate for the first time to get the size statistics
-    let group_by = get_state_stats_group_by(&chunk_view, &trie_storage);
+    let group_by = get_state_stats_group_by(&chunk_view, &trie_storage, &errors);
     let iter = get_state_stats_account_iter(&group_by);
     for state_stats_account in iter {
         state_stats.push(state_stats_account);
     }
 
     // iterate for the second time to find the split accounts
-    let group_by = get_state_stats_group_by(&chunk_view, &trie_storage);
+    let group_by = get_state_stats_group_by(&chunk_view, &trie_storage, &errors);
     let total_size = state_stats.total_size.as_u64();
     let mut current_size = ByteSize::default();
     let mut current_threshold: usize = 1;
@@ -1374,14 +1378,19 @@ fn print_state_stats_for_shard_uid(
 
     tracing::info!(target: "state_viewer", "{shard_uid:?}");
     tracing::info!(target: "state_viewer", "{state_stats:#?}");
+    if errors.get() > 0 {
+        tracing::warn!(target: "state_viewer", errors = errors.get(), "failed to read some of the flat state, the stats are incomplete");
+    }
 }
 
 /// Gets the flat state iterator from the chunk view, rearranges it to be sorted
 /// by the account id, rather than type, account id and finally groups the
 /// records by account id while collecting aggregate statistics.
-fn get_state_stats_group_by<'a>(
+/// Flat state items which can't be read are skipped and counted in `errors`.
+pub(crate) fn get_state_stats_group_by<'a>(
     chunk_view: &'a FlatStorageChunkView,
     trie_storage: &'a TrieDBStorage,
+    errors: &'a Cell<u64>,
 ) -> GroupBy<
     AccountId,
     impl Iterator<Item = StateStatsStateRecord> + 'a,
@@ -1395,8 +1404,18 @@ fn get_state_stats_group_by<'a>(
         .map(|(type_byte, _)| chunk_view.iter_range(Some(&[*type_byte]), Some(&[*type_byte + 1])))
         .into_iter();
 
-    // Filter out any errors.
-    let type_iters = type_iters.map(|type_iter| type_iter.filter_map(|item| item.ok())).into_iter();
+    // Filter out and count any errors.
+    let type_iters = type_iters
+        .map(move |type_iter| {
+            type_iter.filter_map(move |item| {
+                item.inspect_err(|err| {
+                    tracing::debug!(target: "state_viewer", ?err, "failed to read flat state");
+                    errors.set(errors.get() + 1);
+                })
+                .ok()
+            })
+        })
+        .into_iter();
 
     // Read the values from and convert items to StateStatsStateRecord.
     let type_iters = type_iters
@@ -1527,9 +1546,9 @@ impl StateStats {
 /// StateStatsStateRecord stores the state record and associated information.
 /// It's used as a helper struct for merging state records from different record types.
 #[derive(Eq, PartialEq)]
-struct StateStatsStateRecord {
-    state_record: StateRecord,
-    account_id: AccountId,
+pub(crate) struct StateStatsStateRecord {
+    pub(crate) state_record: StateRecord,
+    pub(crate) account_id: AccountId,
     size: ByteSize,
 }
 
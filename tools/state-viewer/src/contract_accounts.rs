@@ -28,6 +28,8 @@ pub(crate) struct ContractAccountSummary {
 /// streaming, not all fields are available.
 pub(crate) struct ContractAccount {
     pub(crate) account_id: AccountId,
+    /// Hash of the deployed WASM code, as stored in the trie.
+    pub(crate) code_hash: CryptoHash,
     pub(crate) info: ContractInfo,
 }
 
@@ -186,6 +188,7 @@ impl ContractAccount {
         };
         Ok(Self {
             account_id,
+            code_hash: value_hash,
             info: ContractInfo { code_size: code.map(|bytes| bytes.len()), ..Default::default() },
         })
     }
@@ -193,29 +196,39 @@ impl ContractAccount {
 
 impl ContractAccountIterator {
     pub(crate) fn new(trie: Trie, filter: ContractAccountFilter) -> anyhow::Result<Self> {
-        let mut trie_iter = trie.disk_iter()?;
         // TODO(#8376): Consider changing the interface to TrieKey to make this easier.
         // `TrieKey::ContractCode` requires a valid `AccountId`, we use "xx"
         let key = TrieKey::ContractCode { account_id: "xx".parse()? }.to_vec();
         let (prefix, suffix) = key.split_at(key.len() - 2);
         assert_eq!(suffix, "xx".as_bytes());
 
-        // `visit_nodes_interval` wants nibbles stored in `Vec<u8>` as input
-        let nibbles_before: Vec<u8> = NibbleSlice::new(prefix).iter().collect();
-        let nibbles_after = {
-            let mut tmp = nibbles_before.clone();
-            *tmp.last_mut().unwrap() += 1;
-            tmp
-        };
-
         // finally, use trie iterator to find all contract nodes
-        let vec_of_nodes = trie_iter.visit_nodes_interval(&nibbles_before, &nibbles_after)?;
-        drop(trie_iter);
+        let vec_of_nodes = trie_nodes_with_prefix(&trie, prefix)?;
         let contract_nodes = VecDeque::from(vec_of_nodes);
         Ok(Self { contract_nodes, filter, trie })
     }
 }
 
+/// Visits all trie nodes whose keys start with `prefix`, without reading the
+/// values stored in them.
+///
+/// Value nodes are returned with their key and the hash of the value, which
+/// for code columns is the code hash.
+pub(crate) fn trie_nodes_with_prefix(
+    trie: &Trie,
+    prefix: &[u8],
+) -> anyhow::Result<Vec<TrieTraversalItem>> {
+    let mut trie_iter = trie.disk_iter()?;
+    // `visit_nodes_interval` wants nibbles stored in `Vec<u8>` as input
+    let nibbles_before: Vec<u8> = NibbleSlice::new(prefix).iter().collect();
+    let nibbles_after = {
+        let mut tmp = nibbles_before.clone();
+        *tmp.last_mut().unwrap() += 1;
+        tmp
+    };
+    Ok(trie_iter.visit_nodes_interval(&nibbles_before, &nibbles_after)?)
+}
+
 /// Helper trait for blanket implementation, making the iterator composable.
 pub(crate) trait Summary {
     fn summary(self, store: &Store, filter: &ContractAccountFilter) -> ContractAccountSummary;
@@ -540,6 +553,20 @@ mod tests {
         assert_eq!(contract3.info.code_size, Some(3));
     }
 
+    /// The code hash must be the hash of the deployed code, so that it can be
+    /// used to look up the compiled contract.
+    #[test]
+    fn test_code_hash() {
+        let trie = create_trie(vec![contract_tuple("alice.near", 3)]);
+        let contract_accounts: Vec<_> =
+            ContractAccount::in_trie(trie, ContractAccountFilter::default())
+                .expect("failed creating iterator")
+                .collect();
+        assert_eq!(1, contract_accounts.len(), "wrong number of contracts returned by iterator");
+        let contract = contract_accounts[0].as_ref().expect("returned error instead of contract");
+        assert_eq!(contract.code_hash, CryptoHash::hash_bytes(&[3, 3, 3]));
+    }
+
     /// Check basic summary output and make sure the output looks right.
     #[test]
     fn test_simple_summary() {
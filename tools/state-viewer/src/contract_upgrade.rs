@@ -0,0 +1,196 @@
+//! Dry run of a contract upgrade against the live state of a node.
+//!
+//! The new code is deployed and the migration method called on top of the
+//! latest state of the account's shard, without committing anything. View
+//! calls are then run both before and after the upgrade, so that a broken
+//! migration shows up before it is deployed for real.
+
+use crate::util::load_trie;
+use near_chain::types::RuntimeAdapter;
+use near_epoch_manager::EpochManagerAdapter;
+use near_primitives::types::AccountId;
+use near_store::{Store, TrieUpdate};
+use nearcore::NearConfig;
+use node_runtime::state_viewer::{TrieViewer, ViewApplyState};
+use std::path::{Path, PathBuf};
+
+/// A view call given on the command line as `METHOD[:ARGS]`.
+#[derive(Clone, Debug)]
+struct ViewCall {
+    method_name: String,
+    args: Vec<u8>,
+}
+
+impl std::str::FromStr for ViewCall {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (method_name, args) = s.split_once(':').unwrap_or((s, ""));
+        Ok(Self { method_name: method_name.to_string(), args: args.as_bytes().to_vec() })
+    }
+}
+
+#[derive(clap::Parser)]
+pub struct ContractUpgradeCmd {
+    /// Account whose contract is upgraded.
+    #[clap(long)]
+    account_id: AccountId,
+    /// Path to the new Wasm code.
+    #[clap(long, value_parser)]
+    code: PathBuf,
+    /// Method called right after deploying the new code.
+    #[clap(long)]
+    migration_method: Option<String>,
+    /// Arguments passed to the migration method, usually JSON.
+    #[clap(long, default_value = "")]
+    migration_args: String,
+    /// View call to check after the upgrade, as `METHOD[:ARGS]`. Can be given
+    /// multiple times. Each call also runs against the current contract, so
+    /// that changed results can be reported.
+    #[clap(long)]
+    view_call: Vec<ViewCall>,
+}
+
+impl ContractUpgradeCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        let success = dry_run_contract_upgrade(home_dir, near_config, store, self).unwrap();
+        if !success {
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Runs the view call and formats its outcome for the report.
+fn run_view_call(
+    trie_viewer: &TrieViewer,
+    state_update: &mut TrieUpdate,
+    view_state: ViewApplyState,
+    account_id: &AccountId,
+    view_call: &ViewCall,
+    epoch_manager: &near_epoch_manager::EpochManagerHandle,
+) -> Result<String, String> {
+    let mut logs = vec![];
+    trie_viewer
+        .call_function_on_update(
+            state_update,
+            view_state,
+            account_id,
+            &view_call.method_name,
+            &view_call.args,
+            &mut logs,
+            epoch_manager,
+        )
+        .map(|result| String::from_utf8_lossy(&result).into_owned())
+        .map_err(|err| err.to_string())
+}
+
+/// Returns whether the migration and all view calls succeeded on the upgraded
+/// contract.
+fn dry_run_contract_upgrade(
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+    cmd: ContractUpgradeCmd,
+) -> anyhow::Result<bool> {
+    let code = std::fs::read(&cmd.code)?;
+    let (epoch_manager, runtime, state_roots, header) = load_trie(store, home_dir, &near_config);
+    let epoch_id = *header.epoch_id();
+    let epoch_info = epoch_manager.get_epoch_info(&epoch_id)?;
+    let shard_layout = epoch_manager.get_shard_layout(&epoch_id)?;
+    let shard_uid = shard_layout.account_id_to_shard_uid(&cmd.account_id);
+    let shard_index = shard_layout.get_shard_index(shard_uid.shard_id())?;
+    let state_root = state_roots[shard_index];
+    let view_state = || ViewApplyState {
+        block_height: header.height(),
+        prev_block_hash: *header.prev_hash(),
+        block_hash: *header.hash(),
+        shard_id: shard_uid.shard_id(),
+        epoch_id,
+        epoch_height: epoch_info.epoch_height(),
+        block_timestamp: header.raw_timestamp(),
+        current_protocol_version: epoch_info.protocol_version(),
+        cache: Some(runtime.compiled_contract_cache().handle()),
+    };
+    let trie_viewer = TrieViewer::new(None, near_config.client_config.max_gas_burnt_view);
+    println!("Dry run of upgrading {} at height {}", cmd.account_id, header.height());
+
+    let mut current_state = runtime.get_tries().new_trie_update_view(shard_uid, state_root);
+    let current_results: Vec<_> = cmd
+        .view_call
+        .iter()
+        .map(|view_call| {
+            run_view_call(
+                &trie_viewer,
+                &mut current_state,
+                view_state(),
+                &cmd.account_id,
+                view_call,
+                &epoch_manager,
+            )
+        })
+        .collect();
+
+    let mut upgraded_state = runtime.get_tries().new_trie_update_view(shard_uid, state_root);
+    let mut logs = vec![];
+    let migration =
+        cmd.migration_method.as_deref().map(|method| (method, cmd.migration_args.as_bytes()));
+    let outcome = trie_viewer.upgrade_contract(
+        &mut upgraded_state,
+        view_state(),
+        &cmd.account_id,
+        code,
+        migration,
+        &mut logs,
+        epoch_manager.as_ref(),
+    );
+    for log in logs {
+        println!("  log: {log}");
+    }
+    let outcome = match outcome {
+        Ok(outcome) => outcome,
+        Err(err) => {
+            println!("Upgrade FAILED: {err}");
+            return Ok(false);
+        }
+    };
+    let growth = outcome.storage_usage_after as i64 - outcome.storage_usage_before as i64;
+    println!(
+        "Upgrade succeeded: storage usage {} -> {} bytes ({growth:+}), migration burnt {} gas \
+         and created {} receipts",
+        outcome.storage_usage_before,
+        outcome.storage_usage_after,
+        outcome.migration_gas_burnt,
+        outcome.migration_receipts,
+    );
+    let mut success = true;
+    if let Some(amount) = outcome.lacking_storage_stake {
+        println!("Account lacks {amount} yoctoNEAR to cover the storage stake after the upgrade");
+        success = false;
+    }
+
+    for (view_call, current_result) in cmd.view_call.iter().zip(current_results) {
+        let result = run_view_call(
+            &trie_viewer,
+            &mut upgraded_state,
+            view_state(),
+            &cmd.account_id,
+            view_call,
+            &epoch_manager,
+        );
+        let method_name = &view_call.method_name;
+        match (&current_result, &result) {
+            (_, Err(err)) => {
+                println!("View call {method_name} FAILED: {err}");
+                success = false;
+            }
+            (Ok(before), Ok(after)) if before == after => {
+                println!("View call {method_name} returned the same result: {after}");
+            }
+            (before, Ok(after)) => {
+                let before = before.as_ref().unwrap_or_else(|err| err);
+                println!("View call {method_name} changed: {before} -> {after}");
+            }
+        }
+    }
+    Ok(success)
+}
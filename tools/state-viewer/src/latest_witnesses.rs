@@ -2,10 +2,12 @@ use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use near_chain::runtime::NightshadeRuntime;
+use near_chain::stateless_validation::divergence::ShadowValidationDivergence;
 use near_chain::stateless_validation::processing_tracker::ProcessingDoneTracker;
 use near_chain::{Chain, ChainGenesis, ChainStore, DoomslugThresholdMode};
 use near_epoch_manager::EpochManager;
 use near_epoch_manager::shard_tracker::ShardTracker;
+use near_primitives::hash::hash;
 use near_primitives::stateless_validation::state_witness::ChunkStateWitness;
 use near_primitives::types::EpochId;
 use near_store::Store;
@@ -19,6 +21,9 @@ pub enum StateWitnessCmd {
     Dump(DumpWitnessesCmd),
     /// Validates given state witness.
     Validate(ValidateWitnessCmd),
+    /// Prints a divergence report saved by shadow validation and validates
+    /// its witness again.
+    ReplayDivergence(ReplayDivergenceCmd),
 }
 
 impl StateWitnessCmd {
@@ -26,6 +31,7 @@ impl StateWitnessCmd {
         match self {
             StateWitnessCmd::Dump(cmd) => cmd.run(near_config, store),
             StateWitnessCmd::Validate(cmd) => cmd.run(home_dir, near_config, store),
+            StateWitnessCmd::ReplayDivergence(cmd) => cmd.run(home_dir, near_config, store),
         }
     }
 }
@@ -114,41 +120,92 @@ impl ValidateWitnessCmd {
             std::fs::read(&self.input_file).expect("Failed to read file");
         let witness: ChunkStateWitness = borsh::BorshDeserialize::try_from_slice(&encoded_witness)
             .expect("Failed to deserialize witness");
-        let chain_genesis = ChainGenesis::new(&near_config.genesis.config);
-        let epoch_manager = EpochManager::new_arc_handle(
-            store.clone(),
-            &near_config.genesis.config,
-            Some(home_dir),
-        );
-        let runtime_adapter =
-            NightshadeRuntime::from_config(home_dir, store, &near_config, epoch_manager.clone())
-                .expect("could not create the transaction runtime");
-        let shard_tracker = ShardTracker::new(
-            near_config.client_config.tracked_shards_config,
-            epoch_manager.clone(),
+        validate_witness(home_dir, near_config, store, witness, None);
+    }
+}
+
+#[derive(clap::Parser)]
+pub struct ReplayDivergenceCmd {
+    /// File with a divergence report saved by shadow validation.
+    #[arg(long)]
+    input_file: PathBuf,
+    /// If the witness diverges again, save a new report to this directory.
+    #[arg(long)]
+    output_dir: Option<PathBuf>,
+}
+
+impl ReplayDivergenceCmd {
+    pub(crate) fn run(&self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        let divergence = ShadowValidationDivergence::load(&self.input_file)
+            .expect("Failed to load divergence report");
+        let witness = &divergence.witness;
+        println!(
+            "Divergence at height {} shard {} chunk {:?} (protocol version {})",
+            witness.chunk_header.height_created(),
+            witness.chunk_header.shard_id(),
+            witness.chunk_header.chunk_hash(),
+            divergence.protocol_version,
         );
-        // TODO(stateless_validation): consider using `ChainStore` instead of
-        // `Chain`.
-        let chain = Chain::new_for_view_client(
-            Clock::real(),
-            epoch_manager.clone(),
-            shard_tracker,
-            runtime_adapter,
-            &chain_genesis,
-            DoomslugThresholdMode::TwoThirds,
-            false,
+        println!("Error: {}", divergence.error);
+        println!("Local chunk extra: {:#?}", divergence.local_chunk_extra);
+        println!("Witness chunk extra: {:#?}", divergence.witness_chunk_extra);
+        println!("{} differing post-state values:", divergence.trie_diff.len());
+        for diff in &divergence.trie_diff {
+            println!(
+                "  {:?}: local {}, witness {}",
+                diff.trie_key,
+                describe_value(diff.local_value.as_deref()),
+                describe_value(diff.witness_value.as_deref()),
+            );
+        }
+        validate_witness(home_dir, near_config, store, divergence.witness, self.output_dir.clone());
+    }
+}
+
+fn describe_value(value: Option<&[u8]>) -> String {
+    match value {
+        Some(value) => format!("{} bytes, hash {}", value.len(), hash(value)),
+        None => "<deleted>".to_string(),
+    }
+}
+
+fn validate_witness(
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+    witness: ChunkStateWitness,
+    divergence_dir: Option<PathBuf>,
+) {
+    let chain_genesis = ChainGenesis::new(&near_config.genesis.config);
+    let epoch_manager =
+        EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config, Some(home_dir));
+    let runtime_adapter =
+        NightshadeRuntime::from_config(home_dir, store, &near_config, epoch_manager.clone())
+            .expect("could not create the transaction runtime");
+    let shard_tracker =
+        ShardTracker::new(near_config.client_config.tracked_shards_config, epoch_manager.clone());
+    // TODO(stateless_validation): consider using `ChainStore` instead of
+    // `Chain`.
+    let chain = Chain::new_for_view_client(
+        Clock::real(),
+        epoch_manager.clone(),
+        shard_tracker,
+        runtime_adapter,
+        &chain_genesis,
+        DoomslugThresholdMode::TwoThirds,
+        false,
+    )
+    .unwrap();
+    let processing_done_tracker = ProcessingDoneTracker::new();
+    let waiter = processing_done_tracker.make_waiter();
+    chain
+        .shadow_validate_state_witness(
+            witness,
+            epoch_manager.as_ref(),
+            Some(processing_done_tracker),
+            divergence_dir,
         )
         .unwrap();
-        let processing_done_tracker = ProcessingDoneTracker::new();
-        let waiter = processing_done_tracker.make_waiter();
-        chain
-            .shadow_validate_state_witness(
-                witness,
-                epoch_manager.as_ref(),
-                Some(processing_done_tracker),
-            )
-            .unwrap();
-        waiter.wait();
-        println!("Validation finished. Use `RUST_LOG=debug` to see validation result");
-    }
+    waiter.wait();
+    println!("Validation finished. Use `RUST_LOG=debug` to see validation result");
 }
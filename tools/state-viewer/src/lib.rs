@@ -6,8 +6,10 @@ pub mod cli;
 mod commands;
 mod congestion_control;
 mod contract_accounts;
+mod contract_upgrade;
 mod epoch_info;
 mod latest_witnesses;
+mod precompile_contracts;
 pub mod progress_reporter;
 mod replay_headers;
 mod rocksdb_stats;
@@ -15,8 +17,10 @@ mod scan_db;
 mod state_changes;
 mod state_dump;
 mod state_parts;
+mod storage_staking;
 mod trie_iteration_benchmark;
 mod tx_dump;
+mod tx_selection;
 pub mod util;
 
 pub use apply_chain_range::apply_chain_range;
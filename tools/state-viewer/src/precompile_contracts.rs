@@ -0,0 +1,191 @@
+//! Offline precompilation of all contracts deployed in the state.
+//!
+//! Changes to `VMConfig` invalidate the compiled contract cache, so after a
+//! protocol upgrade every contract is recompiled lazily on its first call.
+//! Running this command ahead of the upgrade height fills the cache for the
+//! target protocol version, so that chunk application does not pay for the
+//! compilation.
+
+use crate::contract_accounts::{ContractAccount, ContractAccountFilter, trie_nodes_with_prefix};
+use crate::progress_reporter::timestamp_ms;
+use crate::util::load_trie;
+use near_chain::types::RuntimeAdapter;
+use near_epoch_manager::EpochManagerAdapter;
+use near_primitives::hash::CryptoHash;
+use near_primitives::trie_key::col;
+use near_primitives::types::ProtocolVersion;
+use near_primitives::version::PROTOCOL_VERSION;
+use near_store::adapter::StoreAdapter;
+use near_store::trie::AccessOptions;
+use near_store::trie::ops::iter::TrieTraversalItem;
+use near_store::{Store, Trie, TrieDBStorage};
+use near_vm_runner::logic::errors::{CacheError, CompilationError};
+use near_vm_runner::{ContractCode, ContractPrecompilatonResult, precompile_contract};
+use nearcore::NearConfig;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+#[derive(clap::Parser)]
+pub struct PrecompileContractsCmd {
+    /// Protocol version whose `VMConfig` the contracts are compiled for.
+    ///
+    /// Defaults to the latest protocol version supported by this binary,
+    /// which is usually the version of an upcoming upgrade.
+    #[clap(long)]
+    protocol_version: Option<ProtocolVersion>,
+    /// Number of threads used for compilation. Defaults to the number of CPUs.
+    #[clap(long)]
+    threads: Option<usize>,
+}
+
+impl PrecompileContractsCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        precompile_contracts(
+            home_dir,
+            near_config,
+            store,
+            self.protocol_version.unwrap_or(PROTOCOL_VERSION),
+            self.threads,
+        )
+        .unwrap();
+    }
+}
+
+/// Outcome counters of a precompilation run.
+#[derive(Default)]
+struct PrecompileStats {
+    compiled: AtomicU64,
+    already_cached: AtomicU64,
+    /// Contracts that failed to compile. The failure is cached just like a
+    /// successful compilation, so these are not retried on the first call.
+    invalid: AtomicU64,
+    cache_errors: AtomicU64,
+}
+
+impl PrecompileStats {
+    fn record(
+        &self,
+        code_hash: &CryptoHash,
+        result: &Result<Result<ContractPrecompilatonResult, CompilationError>, CacheError>,
+    ) {
+        let counter = match result {
+            Ok(Ok(ContractPrecompilatonResult::ContractCompiled)) => &self.compiled,
+            Ok(Ok(ContractPrecompilatonResult::ContractAlreadyInCache)) => &self.already_cached,
+            Ok(Ok(ContractPrecompilatonResult::CacheNotAvailable)) => &self.cache_errors,
+            Ok(Err(_)) => &self.invalid,
+            Err(err) => {
+                eprintln!("failed to cache contract {code_hash}: {err}");
+                &self.cache_errors
+            }
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl std::fmt::Display for PrecompileStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "compiled: {}, already cached: {}, invalid: {}, cache errors: {}",
+            self.compiled.load(Ordering::Relaxed),
+            self.already_cached.load(Ordering::Relaxed),
+            self.invalid.load(Ordering::Relaxed),
+            self.cache_errors.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// Returns the code hashes of all contracts stored in the trie, both the ones
+/// deployed to accounts and the global contracts.
+fn contract_code_hashes(trie: Trie) -> anyhow::Result<Vec<CryptoHash>> {
+    let global_nodes = trie_nodes_with_prefix(&trie, &[col::GLOBAL_CONTRACT_CODE])?;
+    let mut code_hashes: Vec<_> = global_nodes
+        .into_iter()
+        .filter_map(|item| match item {
+            TrieTraversalItem { hash, key: Some(_) } => Some(hash),
+            TrieTraversalItem { key: None, .. } => None,
+        })
+        .collect();
+    for contract in ContractAccount::in_trie(trie, ContractAccountFilter::default())? {
+        match contract {
+            Ok(contract) => code_hashes.push(contract.code_hash),
+            Err(err) => eprintln!("skipping contract due to {err}"),
+        }
+    }
+    Ok(code_hashes)
+}
+
+pub(crate) fn precompile_contracts(
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+    protocol_version: ProtocolVersion,
+    threads: Option<usize>,
+) -> anyhow::Result<()> {
+    let (epoch_manager, runtime, state_roots, header) =
+        load_trie(store.clone(), home_dir, &near_config);
+    let shard_layout = epoch_manager.get_shard_layout(header.epoch_id())?;
+    let wasm_config = Arc::clone(&runtime.get_runtime_config(protocol_version).wasm_config);
+    let cache = runtime.compiled_contract_cache().handle();
+
+    // Collect the unique code hashes first, which only touches the trie nodes
+    // and gives us the total amount of work for the progress report.
+    let mut seen = HashSet::new();
+    let mut contracts = vec![];
+    for (shard_index, &state_root) in state_roots.iter().enumerate() {
+        let shard_uid = shard_layout.get_shard_uid(shard_index)?;
+        eprintln!("Collecting contracts in shard {shard_uid}");
+        let storage = Arc::new(TrieDBStorage::new(store.trie_store(), shard_uid));
+        let trie = Trie::new(storage.clone(), state_root, None);
+        for code_hash in contract_code_hashes(trie)? {
+            if seen.insert(code_hash) {
+                contracts.push((code_hash, Arc::clone(&storage), state_root));
+            }
+        }
+    }
+    let total = contracts.len();
+    eprintln!(
+        "Precompiling {total} unique contracts for protocol version {protocol_version} ({:?})",
+        wasm_config.vm_kind
+    );
+
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(threads.unwrap_or(0)).build()?;
+    let stats = PrecompileStats::default();
+    let done = AtomicU64::new(0);
+    let start_ms = timestamp_ms();
+    // Run the scope on this thread, which blocks while waiting for free slots.
+    pool.in_place_scope(|scope| -> anyhow::Result<()> {
+        // Bound the number of contracts held in memory to the number of threads.
+        let (slot_sender, slot_receiver) = std::sync::mpsc::channel();
+        for _ in 0..pool.current_num_threads() {
+            slot_sender.send(()).expect("both sender and receiver are owned here");
+        }
+        for (code_hash, storage, state_root) in contracts {
+            slot_receiver.recv().expect("could not receive a slot to compile contract");
+            let trie = Trie::new(storage, state_root, None);
+            let code = trie.retrieve_value(&code_hash, AccessOptions::DEFAULT)?;
+            let code = ContractCode::new(code, Some(code_hash));
+            let (wasm_config, cache, stats, done) = (&wasm_config, &cache, &stats, &done);
+            let slot_sender = slot_sender.clone();
+            scope.spawn(move |_| {
+                let result =
+                    precompile_contract(&code, Arc::clone(wasm_config), Some(cache.as_ref()));
+                stats.record(&code_hash, &result);
+                let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                if done % 100 == 0 || done as usize == total {
+                    let elapsed_s = (timestamp_ms() - start_ms) as f64 / 1000.0;
+                    eprintln!("{done}/{total} contracts processed in {elapsed_s:.1}s; {stats}");
+                }
+                // If this fails, it just means there won't be any more attempts to recv the
+                // slots
+                let _ = slot_sender.send(());
+            });
+        }
+        Ok(())
+    })?;
+
+    println!("Finished precompiling {total} contracts; {stats}");
+    Ok(())
+}
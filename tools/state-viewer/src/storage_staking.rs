@@ -0,0 +1,413 @@
+//! Per account breakdown of the storage staked balance, printed by
+//! `state-stats --storage-staking`.
+//!
+//! Contract owners regularly ask how much of their balance locked for storage
+//! staking is tied up in keys their contract no longer uses. This walks the
+//! flat state of each account, attributes its `storage_usage` to the
+//! individual records the same way the runtime charges for them, and, given
+//! enough `StateChanges` history, finds contract data that has not been
+//! written for a number of epochs.
+
+use crate::commands::get_state_stats_group_by;
+use crate::util::load_trie;
+use near_chain::types::RuntimeAdapter;
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_epoch_manager::EpochManagerAdapter;
+use near_parameters::StorageUsageConfig;
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::dec_format;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::state_record::StateRecord;
+use near_primitives::trie_key::TrieKey;
+use near_primitives::types::{AccountId, Balance, BlockHeight};
+use near_store::adapter::StoreAdapter;
+use near_store::flat::FlatStorageChunkView;
+use near_store::{KeyForStateChanges, Store, TrieDBStorage};
+use nearcore::NearConfig;
+use std::cell::Cell;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(clap::Args)]
+pub struct StorageStakingArgs {
+    /// Only report the given accounts.
+    #[clap(long, use_value_delimiter = true, requires = "storage_staking")]
+    select_accounts: Option<Vec<AccountId>>,
+    /// Report contract data not written in this many epochs as stale.
+    ///
+    /// This relies on the `StateChanges` column covering the whole range,
+    /// which in practice requires an archival node.
+    #[clap(long, requires = "storage_staking")]
+    stale_epochs: Option<u64>,
+    /// Number of leading bytes of the contract data keys to group usage by.
+    #[clap(long, default_value = "1", requires = "storage_staking")]
+    prefix_len: usize,
+    /// File to write the report to, as one JSON object per line and account.
+    /// Defaults to stdout.
+    #[clap(long, value_parser, requires = "storage_staking")]
+    output: Option<PathBuf>,
+}
+
+/// Number of records and the storage usage they are charged for.
+#[derive(serde::Serialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+struct RecordsUsage {
+    count: u64,
+    bytes: u64,
+}
+
+impl RecordsUsage {
+    fn add(&mut self, bytes: u64) {
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
+
+#[derive(serde::Serialize)]
+struct AccountStorageReport {
+    account_id: AccountId,
+    /// The `storage_usage` recorded in the account.
+    recorded_usage: u64,
+    /// The sum of the usage of all records found in the state. Any difference
+    /// to `recorded_usage` cannot be attributed to individual records.
+    computed_usage: u64,
+    account: u64,
+    contract_code: u64,
+    access_keys: RecordsUsage,
+    contract_data: RecordsUsage,
+    /// Contract data usage grouped by the hex encoded key prefix.
+    contract_data_by_prefix: BTreeMap<String, RecordsUsage>,
+    /// Contract data not written since `stale_since_height`. Only present if
+    /// stale data was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stale_contract_data: Option<RecordsUsage>,
+    /// Balance that would be unlocked by deleting the stale contract data.
+    #[serde(with = "dec_format", skip_serializing_if = "Option::is_none")]
+    stale_balance: Option<Balance>,
+}
+
+/// Summary printed on the first line of the report.
+#[derive(serde::Serialize)]
+struct ReportHeader {
+    block_height: BlockHeight,
+    block_hash: CryptoHash,
+    #[serde(with = "dec_format")]
+    storage_amount_per_byte: Balance,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stale_since_height: Option<BlockHeight>,
+}
+
+/// Storage usage the runtime charges an account for the given record. See
+/// `action_add_key`, `action_deploy_contract` and `storage_write` for where the
+/// charges are applied.
+fn record_storage_usage(record: &StateRecord, config: &StorageUsageConfig) -> u64 {
+    match record {
+        StateRecord::Account { .. } => config.num_bytes_account,
+        StateRecord::Contract { code, .. } => code.len() as u64,
+        StateRecord::AccessKey { public_key, access_key, .. } => {
+            borsh::object_length(public_key).unwrap() as u64
+                + borsh::object_length(access_key).unwrap() as u64
+                + config.num_extra_bytes_record
+        }
+        StateRecord::Data { data_key, value, .. } => {
+            data_key.len() as u64 + value.len() as u64 + config.num_extra_bytes_record
+        }
+        StateRecord::PostponedReceipt(_)
+        | StateRecord::ReceivedData { .. }
+        | StateRecord::DelayedReceipt(_) => 0,
+    }
+}
+
+/// Collects the trie keys of all contract data written between the given
+/// heights, as recorded in the `StateChanges` column.
+fn written_contract_data_keys(
+    store: &Store,
+    chain_store: &ChainStore,
+    from_height: BlockHeight,
+    to_height: BlockHeight,
+) -> anyhow::Result<HashSet<Vec<u8>>> {
+    let mut keys = HashSet::new();
+    for height in from_height..=to_height {
+        // Skipped heights have no block and no changes.
+        let Ok(block_hash) = chain_store.get_block_hash_by_height(height) else { continue };
+        for changes in KeyForStateChanges::for_block(&block_hash).find_iter(store) {
+            let changes = changes?;
+            if let TrieKey::ContractData { .. } = changes.trie_key {
+                keys.insert(changes.trie_key.to_vec());
+            }
+        }
+        if height % 10_000 == 0 {
+            eprintln!("Collected state changes up to height {height}");
+        }
+    }
+    Ok(keys)
+}
+
+struct ReportContext<'a> {
+    config: &'a StorageUsageConfig,
+    prefix_len: usize,
+    written_keys: Option<&'a HashSet<Vec<u8>>>,
+}
+
+/// Attributes the storage usage of the account to its records.
+fn account_report(
+    account_id: AccountId,
+    records: impl Iterator<Item = StateRecord>,
+    ctx: &ReportContext,
+) -> AccountStorageReport {
+    let mut report = AccountStorageReport {
+        account_id,
+        recorded_usage: 0,
+        computed_usage: 0,
+        account: 0,
+        contract_code: 0,
+        access_keys: RecordsUsage::default(),
+        contract_data: RecordsUsage::default(),
+        contract_data_by_prefix: BTreeMap::new(),
+        stale_contract_data: ctx.written_keys.map(|_| RecordsUsage::default()),
+        stale_balance: None,
+    };
+    for record in records {
+        let usage = record_storage_usage(&record, ctx.config);
+        report.computed_usage += usage;
+        match &record {
+            StateRecord::Account { account, .. } => {
+                report.recorded_usage = account.storage_usage();
+                report.account = usage;
+            }
+            StateRecord::Contract { .. } => report.contract_code = usage,
+            StateRecord::AccessKey { .. } => report.access_keys.add(usage),
+            StateRecord::Data { account_id, data_key, .. } => {
+                report.contract_data.add(usage);
+                let data_key: &[u8] = data_key;
+                let prefix = &data_key[..ctx.prefix_len.min(data_key.len())];
+                let prefix = prefix.iter().map(|byte| format!("{byte:02x}")).collect();
+                report.contract_data_by_prefix.entry(prefix).or_default().add(usage);
+                if let (Some(written_keys), Some(stale)) =
+                    (ctx.written_keys, report.stale_contract_data.as_mut())
+                {
+                    let trie_key = TrieKey::ContractData {
+                        account_id: account_id.clone(),
+                        key: data_key.to_vec(),
+                    };
+                    if !written_keys.contains(&trie_key.to_vec()) {
+                        stale.add(usage);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    report.stale_balance = report
+        .stale_contract_data
+        .map(|stale| stale.bytes as Balance * ctx.config.storage_amount_per_byte);
+    report
+}
+
+fn write_shard_report(
+    chunk_view: &FlatStorageChunkView,
+    trie_storage: &TrieDBStorage,
+    select_accounts: Option<&[AccountId]>,
+    ctx: &ReportContext,
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    let errors = Cell::new(0);
+    let group_by = get_state_stats_group_by(chunk_view, trie_storage, &errors);
+    for (account_id, group) in &group_by {
+        if select_accounts.is_some_and(|accounts| !accounts.contains(&account_id)) {
+            continue;
+        }
+        let report = account_report(account_id, group.map(|record| record.state_record), ctx);
+        serde_json::to_writer(&mut *out, &report)?;
+        writeln!(out)?;
+    }
+    // A report missing some records would understate the usage, so don't let it pass silently.
+    if errors.get() > 0 {
+        anyhow::bail!("failed to read {} flat state items, the report is incomplete", errors.get());
+    }
+    Ok(())
+}
+
+pub(crate) fn print_storage_staking_report(
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+    shard_uid: Option<ShardUId>,
+    cmd: StorageStakingArgs,
+) -> anyhow::Result<()> {
+    let (epoch_manager, runtime, _, block_header) =
+        load_trie(store.clone(), home_dir, &near_config);
+    let block_hash = *block_header.hash();
+    let head_height = block_header.height();
+    let protocol_version = epoch_manager.get_epoch_protocol_version(block_header.epoch_id())?;
+    let config = runtime.get_runtime_config(protocol_version).fees.storage_usage_config.clone();
+
+    let stale_since_height = cmd
+        .stale_epochs
+        .map(|epochs| head_height.saturating_sub(epochs * near_config.genesis.config.epoch_length));
+    let written_keys = match stale_since_height {
+        Some(from_height) => {
+            let chain_store = ChainStore::new(
+                store.clone(),
+                near_config.client_config.save_trie_changes,
+                near_config.genesis.config.transaction_validity_period,
+            );
+            let tail = chain_store.tail()?;
+            if from_height < tail {
+                anyhow::bail!(
+                    "state changes are only available since height {tail}, \
+                     but {from_height} is needed to find stale keys; use an archival node \
+                     or fewer --stale-epochs"
+                );
+            }
+            Some(written_contract_data_keys(&store, &chain_store, from_height, head_height)?)
+        }
+        None => None,
+    };
+
+    let mut out: Box<dyn Write> = match &cmd.output {
+        Some(path) => Box::new(std::io::BufWriter::new(File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    let header = ReportHeader {
+        block_height: head_height,
+        block_hash,
+        storage_amount_per_byte: config.storage_amount_per_byte,
+        stale_since_height,
+    };
+    serde_json::to_writer(&mut out, &header)?;
+    writeln!(out)?;
+
+    let ctx = ReportContext {
+        config: &config,
+        prefix_len: cmd.prefix_len,
+        written_keys: written_keys.as_ref(),
+    };
+    let shard_uids = match shard_uid {
+        Some(shard_uid) => vec![shard_uid],
+        None => epoch_manager.get_shard_layout_from_prev_block(&block_hash)?.shard_uids().collect(),
+    };
+    let flat_storage_manager = runtime.get_flat_storage_manager();
+    for shard_uid in shard_uids {
+        eprintln!("Processing shard {shard_uid}");
+        flat_storage_manager.create_flat_storage_for_shard(shard_uid)?;
+        let chunk_view = flat_storage_manager
+            .chunk_view(shard_uid, block_hash)
+            .ok_or_else(|| anyhow::anyhow!("no flat storage for shard {shard_uid}"))?;
+        let trie_storage = TrieDBStorage::new(store.trie_store(), shard_uid);
+        write_shard_report(
+            &chunk_view,
+            &trie_storage,
+            cmd.select_accounts.as_deref(),
+            &ctx,
+            &mut out,
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecordsUsage, ReportContext, account_report, record_storage_usage};
+    use near_crypto::{KeyType, PublicKey};
+    use near_parameters::RuntimeConfig;
+    use near_primitives::account::{AccessKey, Account, AccountContract};
+    use near_primitives::state_record::StateRecord;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::AccountId;
+    use std::collections::HashSet;
+
+    /// Borsh length of a full access `AccessKey`: the nonce and the permission tag.
+    const FULL_ACCESS_KEY_LEN: u64 = 8 + 1;
+
+    fn data_record(account_id: &AccountId, key: &[u8], value: &[u8]) -> StateRecord {
+        StateRecord::Data {
+            account_id: account_id.clone(),
+            data_key: key.to_vec().into(),
+            value: value.to_vec().into(),
+        }
+    }
+
+    /// The usage attributed to the records of an account must add up to the
+    /// storage usage the runtime records for it.
+    #[test]
+    fn test_record_storage_usage_matches_runtime() {
+        let config = RuntimeConfig::test().fees.storage_usage_config.clone();
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let public_key = PublicKey::empty(KeyType::ED25519);
+        let access_key = AccessKey::full_access();
+        let code = vec![0u8; 100];
+        let expected = config.num_bytes_account
+            + code.len() as u64
+            + (public_key.len() as u64 + FULL_ACCESS_KEY_LEN + config.num_extra_bytes_record)
+            + (3 + 5 + config.num_extra_bytes_record);
+
+        let records = [
+            StateRecord::Account {
+                account_id: account_id.clone(),
+                account: Account::new(0, 0, AccountContract::None, 0),
+            },
+            StateRecord::Contract { account_id: account_id.clone(), code },
+            StateRecord::AccessKey { account_id: account_id.clone(), public_key, access_key },
+            data_record(&account_id, b"key", b"value"),
+        ];
+        let total: u64 = records.iter().map(|record| record_storage_usage(record, &config)).sum();
+        assert_eq!(total, expected);
+    }
+
+    /// Contract data is stale unless its key was written in the inspected range,
+    /// and only the stale data counts towards the balance it would unlock.
+    #[test]
+    fn test_account_report_stale_contract_data() {
+        let config = RuntimeConfig::test().fees.storage_usage_config.clone();
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let other_account_id: AccountId = "bob.near".parse().unwrap();
+        let written_keys: HashSet<_> = [
+            TrieKey::ContractData { account_id: account_id.clone(), key: b"a1".to_vec() },
+            // The same key of another account doesn't keep the data fresh.
+            TrieKey::ContractData { account_id: other_account_id, key: b"b1".to_vec() },
+        ]
+        .iter()
+        .map(TrieKey::to_vec)
+        .collect();
+        let records = vec![
+            StateRecord::Account {
+                account_id: account_id.clone(),
+                account: Account::new(0, 0, AccountContract::None, 123),
+            },
+            data_record(&account_id, b"a1", b"fresh"),
+            data_record(&account_id, b"b1", b"stale"),
+            data_record(&account_id, b"b2", b"stale value"),
+        ];
+        let ctx =
+            ReportContext { config: &config, prefix_len: 1, written_keys: Some(&written_keys) };
+        let report = account_report(account_id.clone(), records.clone().into_iter(), &ctx);
+
+        let data_usage = |key: &[u8], value: &[u8]| {
+            (key.len() + value.len()) as u64 + config.num_extra_bytes_record
+        };
+        let stale_bytes = data_usage(b"b1", b"stale") + data_usage(b"b2", b"stale value");
+        assert_eq!(report.recorded_usage, 123);
+        assert_eq!(report.contract_data.count, 3);
+        assert_eq!(report.stale_contract_data, Some(RecordsUsage { count: 2, bytes: stale_bytes }));
+        assert_eq!(
+            report.stale_balance,
+            Some(stale_bytes as u128 * config.storage_amount_per_byte)
+        );
+        assert_eq!(
+            report.contract_data_by_prefix.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("61".to_string(), RecordsUsage { count: 1, bytes: data_usage(b"a1", b"fresh") }),
+                ("62".to_string(), RecordsUsage { count: 2, bytes: stale_bytes }),
+            ]
+        );
+
+        // Without the history of writes, nothing is reported as stale.
+        let ctx = ReportContext { config: &config, prefix_len: 1, written_keys: None };
+        let report = account_report(account_id, records.into_iter(), &ctx);
+        assert_eq!(report.stale_contract_data, None);
+        assert_eq!(report.stale_balance, None);
+    }
+}
@@ -0,0 +1,383 @@
+//! Simulation of the transaction selection done by a chunk producer.
+//!
+//! A transaction pool is filled, height by height, with transactions taken
+//! either from the chunks which were actually produced after the start block
+//! or from the transactions generator. At every height the pool goes through
+//! `prepare_transactions`, the same way as when a chunk is produced, and the
+//! selected transactions are removed from the pool. The report shows how many
+//! transactions got into chunks, how long they waited in the pool and why the
+//! others were rejected.
+//!
+//! The state is not advanced between heights: transactions are always checked
+//! against the state after the start block. That keeps the simulation cheap
+//! and makes it measure the selection policy rather than the execution.
+
+use near_chain::types::{
+    PrepareTransactionsBlockContext, PrepareTransactionsChunkContext, PrepareTransactionsRejection,
+    RuntimeAdapter, RuntimeStorageConfig,
+};
+use near_chain::{Block, ChainStore, ChainStoreAccess};
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_pool::{InsertTransactionResult, TransactionPool};
+use near_primitives::errors::InvalidTxError;
+use near_primitives::hash::CryptoHash;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{AccountId, BlockHeight, ShardId};
+use near_store::{ShardUId, Store};
+use near_transactions_generator::account::{Account, accounts_from_path};
+use nearcore::{NearConfig, NightshadeRuntime, NightshadeRuntimeExt};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+#[clap(rename_all = "kebab_case")]
+enum TransactionSource {
+    /// Transactions of the chunks of the shard produced after the start block.
+    /// Each one arrives in the pool at the height at which it was included.
+    Chunks,
+    /// Transfers between random accounts from `--accounts-path`, created by
+    /// the transactions generator.
+    Generator,
+}
+
+#[derive(clap::Parser)]
+pub struct SimulateTxSelectionCmd {
+    /// Shard whose chunk producer is simulated.
+    #[clap(long)]
+    shard_id: ShardId,
+    /// Height of the block whose state is used. Defaults to the head.
+    #[clap(long)]
+    start_height: Option<BlockHeight>,
+    /// Number of heights to simulate.
+    #[clap(long, default_value = "100")]
+    num_heights: u64,
+    /// Where the transactions in the pool come from.
+    #[clap(long, value_enum, default_value = "chunks")]
+    source: TransactionSource,
+    /// Accounts used by the `generator` source, in the format of the
+    /// transactions generator. Only accounts of the shard are used.
+    #[clap(long)]
+    accounts_path: Option<PathBuf>,
+    /// Number of transactions the `generator` source adds at every height.
+    #[clap(long, default_value = "1000")]
+    transactions_per_height: u64,
+    /// Limit on the total size of transactions in the pool, in bytes.
+    /// Defaults to `transaction_pool_size_limit` from the config.
+    #[clap(long)]
+    pool_size_limit: Option<u64>,
+    /// Number of signers with the highest latency to list in the report.
+    #[clap(long, default_value = "10")]
+    top_signers: usize,
+}
+
+impl SimulateTxSelectionCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        simulate_tx_selection(home_dir, near_config, store, self).unwrap();
+    }
+}
+
+/// Transactions which arrive in the pool at a height.
+trait TransactionArrivals {
+    fn arrivals(&mut self, height: BlockHeight) -> anyhow::Result<Vec<SignedTransaction>>;
+}
+
+struct ChunkArrivals<'a> {
+    chain_store: &'a ChainStore,
+    shard_id: ShardId,
+}
+
+impl TransactionArrivals for ChunkArrivals<'_> {
+    fn arrivals(&mut self, height: BlockHeight) -> anyhow::Result<Vec<SignedTransaction>> {
+        let Ok(block_hash) = self.chain_store.get_block_hash_by_height(height) else {
+            return Ok(vec![]);
+        };
+        let block = self.chain_store.get_block(&block_hash)?;
+        let Some(chunk_header) = block.chunks().iter_raw().find(|chunk_header| {
+            chunk_header.shard_id() == self.shard_id && chunk_header.is_new_chunk(height)
+        }) else {
+            return Ok(vec![]);
+        };
+        let chunk = self.chain_store.get_chunk(&chunk_header.chunk_hash())?;
+        Ok(chunk.to_transactions().to_vec())
+    }
+}
+
+struct GeneratorArrivals {
+    accounts: Vec<Account>,
+    block_hash: CryptoHash,
+    transactions_per_height: u64,
+    rng: StdRng,
+}
+
+impl TransactionArrivals for GeneratorArrivals {
+    fn arrivals(&mut self, _height: BlockHeight) -> anyhow::Result<Vec<SignedTransaction>> {
+        Ok((0..self.transactions_per_height)
+            .map(|_| {
+                near_transactions_generator::random_send_transaction(
+                    &mut self.rng,
+                    &self.accounts,
+                    &self.block_hash,
+                )
+            })
+            .collect())
+    }
+}
+
+/// Transaction waiting in the pool.
+struct PendingTransaction {
+    signer_id: AccountId,
+    arrival_height: BlockHeight,
+}
+
+#[derive(Default)]
+struct SimulationReport {
+    arrived: u64,
+    included_per_height: Vec<u64>,
+    limited_by: BTreeMap<String, u64>,
+    rejections: BTreeMap<String, u64>,
+    latencies: Vec<u64>,
+    latencies_per_signer: HashMap<AccountId, Vec<u64>>,
+}
+
+fn simulate_tx_selection(
+    home_dir: &Path,
+    near_config: NearConfig,
+    store: Store,
+    cmd: SimulateTxSelectionCmd,
+) -> anyhow::Result<()> {
+    let chain_store = ChainStore::new(
+        store.clone(),
+        false,
+        near_config.genesis.config.transaction_validity_period,
+    );
+    let epoch_manager =
+        EpochManager::new_arc_handle(store.clone(), &near_config.genesis.config, Some(home_dir));
+    let runtime =
+        NightshadeRuntime::from_config(home_dir, store, &near_config, epoch_manager.clone())?;
+
+    let start_height = match cmd.start_height {
+        Some(height) => height,
+        None => chain_store.head()?.height,
+    };
+    let start_block =
+        chain_store.get_block(&chain_store.get_block_hash_by_height(start_height)?)?;
+    let start_hash = *start_block.hash();
+    let epoch_id = *start_block.header().epoch_id();
+    let protocol_version = epoch_manager.get_epoch_protocol_version(&epoch_id)?;
+    let shard_layout = epoch_manager.get_shard_layout(&epoch_id)?;
+    let shard_uid = ShardUId::from_shard_id_and_layout(cmd.shard_id, &shard_layout);
+    let chunk_extra = chain_store.get_chunk_extra(&start_hash, &shard_uid)?;
+    let state_root = *chunk_extra.state_root();
+    let gas_price = start_block.header().next_gas_price();
+
+    let mut arrivals: Box<dyn TransactionArrivals + '_> = match cmd.source {
+        TransactionSource::Chunks => {
+            Box::new(ChunkArrivals { chain_store: &chain_store, shard_id: cmd.shard_id })
+        }
+        TransactionSource::Generator => {
+            let Some(accounts_path) = &cmd.accounts_path else {
+                anyhow::bail!("--accounts-path is required for the generator source");
+            };
+            let state_update = runtime.get_tries().new_trie_update_view(shard_uid, state_root);
+            let mut accounts = vec![];
+            for account in accounts_from_path(accounts_path)? {
+                if shard_layout.account_id_to_shard_uid(&account.id) != shard_uid {
+                    continue;
+                }
+                let access_key =
+                    near_store::get_access_key(&state_update, &account.id, &account.public_key)?;
+                if let Some(access_key) = access_key {
+                    account.nonce.store(access_key.nonce, std::sync::atomic::Ordering::Relaxed);
+                }
+                accounts.push(account);
+            }
+            if accounts.len() < 2 {
+                anyhow::bail!(
+                    "the generator needs at least two accounts on shard {}",
+                    cmd.shard_id
+                );
+            }
+            Box::new(GeneratorArrivals {
+                accounts,
+                block_hash: start_hash,
+                transactions_per_height: cmd.transactions_per_height,
+                rng: StdRng::seed_from_u64(0),
+            })
+        }
+    };
+
+    let pool_size_limit =
+        cmd.pool_size_limit.or(near_config.client_config.transaction_pool_size_limit);
+    let mut pool = TransactionPool::new([0; 32], pool_size_limit, "simulation");
+    let mut pending: HashMap<CryptoHash, PendingTransaction> = HashMap::new();
+    let mut report = SimulationReport::default();
+    let mut prev_header = start_block.header().clone();
+    for height in start_height + 1..=start_height + cmd.num_heights {
+        for transaction in arrivals.arrivals(height)? {
+            report.arrived += 1;
+            let signer_id = transaction.transaction.signer_id().clone();
+            let validated_tx =
+                match runtime.validate_tx(&shard_layout, transaction, protocol_version, None) {
+                    Ok(validated_tx) => validated_tx,
+                    Err((err, _)) => {
+                        report.reject(format!("pool/{}", invalid_tx_name(&err)));
+                        continue;
+                    }
+                };
+            if let Err(err) = runtime.can_verify_and_charge_tx(
+                &shard_layout,
+                gas_price,
+                state_root,
+                &validated_tx,
+                protocol_version,
+            ) {
+                report.reject(format!("pool/{}", invalid_tx_name(&err)));
+                continue;
+            }
+            let tx_hash = validated_tx.get_hash();
+            match pool.insert_transaction(validated_tx) {
+                InsertTransactionResult::Success => {
+                    pending
+                        .insert(tx_hash, PendingTransaction { signer_id, arrival_height: height });
+                }
+                InsertTransactionResult::Duplicate => report.reject("pool/Duplicate".to_string()),
+                InsertTransactionResult::NoSpaceLeft => {
+                    report.reject("pool/NoSpaceLeft".to_string())
+                }
+            }
+        }
+
+        // Transactions are checked against the latest block known at this
+        // height, so that historical ones don't expire early.
+        if let Ok(block_hash) = chain_store.get_block_hash_by_height(height - 1) {
+            prev_header = chain_store.get_block_header(&block_hash)?;
+        }
+        let chain_validate = |tx: &SignedTransaction| {
+            chain_store
+                .check_transaction_validity_period(&prev_header, tx.transaction.block_hash())
+                .is_ok()
+        };
+        let prepared = runtime.prepare_transactions(
+            RuntimeStorageConfig::new(state_root, false),
+            PrepareTransactionsChunkContext {
+                shard_id: cmd.shard_id,
+                gas_limit: chunk_extra.gas_limit(),
+            },
+            simulated_block_context(&start_block, height - 1),
+            &mut pool.pool_iterator(),
+            &chain_validate,
+            None,
+        )?;
+
+        if let Some(limit) = prepared.limited_by {
+            *report.limited_by.entry(limit.as_ref().to_string()).or_default() += 1;
+        }
+        for (tx_hash, rejection) in prepared.rejected {
+            pending.remove(&tx_hash);
+            let reason = match rejection {
+                PrepareTransactionsRejection::InvalidTx(err) => invalid_tx_name(&err),
+                rejection => rejection.as_ref().to_string(),
+            };
+            report.reject(format!("chunk/{reason}"));
+        }
+        report.included_per_height.push(prepared.transactions.len() as u64);
+        for validated_tx in &prepared.transactions {
+            let Some(transaction) = pending.remove(&validated_tx.get_hash()) else {
+                continue;
+            };
+            let latency = height - transaction.arrival_height;
+            report.latencies.push(latency);
+            report.latencies_per_signer.entry(transaction.signer_id).or_default().push(latency);
+        }
+    }
+
+    println!(
+        "Simulated {} heights of shard {} after block #{start_height} {start_hash}",
+        cmd.num_heights, cmd.shard_id
+    );
+    report.print(pending.len(), cmd.top_signers);
+    Ok(())
+}
+
+/// Context of the block on top of which the chunk at `height + 1` is
+/// produced. Only the height advances, the rest comes from the start block,
+/// whose epoch and state are used for the whole simulation.
+fn simulated_block_context(
+    start_block: &Block,
+    height: BlockHeight,
+) -> PrepareTransactionsBlockContext {
+    PrepareTransactionsBlockContext { height, ..PrepareTransactionsBlockContext::from(start_block) }
+}
+
+/// Name of the error variant, without its fields.
+fn invalid_tx_name(err: &InvalidTxError) -> String {
+    let debug = format!("{err:?}");
+    debug.split(|c: char| !c.is_alphanumeric()).next().unwrap_or_default().to_string()
+}
+
+impl SimulationReport {
+    fn reject(&mut self, reason: String) {
+        *self.rejections.entry(reason).or_default() += 1;
+    }
+
+    fn print(&self, still_pending: usize, top_signers: usize) {
+        let included = self.latencies.len();
+        let rejected: u64 = self.rejections.values().sum();
+        println!(
+            "Transactions: {} arrived, {included} included, {rejected} rejected, \
+             {still_pending} left in the pool",
+            self.arrived
+        );
+        let heights = self.included_per_height.len().max(1);
+        let max_included = self.included_per_height.iter().max().copied().unwrap_or_default();
+        println!(
+            "Throughput: {:.1} transactions per chunk on average, {max_included} at most",
+            included as f64 / heights as f64
+        );
+        println!("Chunks limited by:");
+        for (limit, count) in &self.limited_by {
+            println!("  {limit}: {count}");
+        }
+        println!("Rejection reasons:");
+        for (reason, count) in &self.rejections {
+            println!("  {reason}: {count}");
+        }
+
+        let mut latencies = self.latencies.clone();
+        latencies.sort_unstable();
+        if latencies.is_empty() {
+            return;
+        }
+        println!(
+            "Latency in heights: mean {:.2}, p50 {}, p90 {}, p99 {}, max {}",
+            mean(&latencies),
+            percentile(&latencies, 50),
+            percentile(&latencies, 90),
+            percentile(&latencies, 99),
+            latencies[latencies.len() - 1],
+        );
+
+        let mut signers: Vec<_> = self.latencies_per_signer.iter().collect();
+        signers.sort_by(|(a_id, a), (b_id, b)| mean(b).total_cmp(&mean(a)).then(a_id.cmp(b_id)));
+        println!("Signers with the highest mean latency:");
+        for (signer_id, latencies) in signers.into_iter().take(top_signers) {
+            println!(
+                "  {signer_id}: {} included, mean {:.2}, max {}",
+                latencies.len(),
+                mean(latencies),
+                latencies.iter().max().unwrap(),
+            );
+        }
+    }
+}
+
+fn mean(values: &[u64]) -> f64 {
+    values.iter().sum::<u64>() as f64 / values.len() as f64
+}
+
+/// Percentile of sorted, non-empty values.
+fn percentile(sorted_values: &[u64], percent: usize) -> u64 {
+    sorted_values[(sorted_values.len() - 1) * percent / 100]
+}